@@ -0,0 +1,14 @@
+// Generates the gRPC messages and service from the proto file. protoc comes
+// from `protoc-bin-vendored`, so the build doesn't need one installed.
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let proto = "proto/computex/app_server/v1/app_server.proto";
+    println!("cargo:rerun-if-changed={proto}");
+    let mut config = prost_build::Config::new();
+    config.protoc_executable(protoc_bin_vendored::protoc_bin_path()?);
+    // Without transport helpers, so the generated client's `connect`
+    // constructor doesn't clash with the `Connect` method.
+    tonic_build::configure()
+        .build_transport(false)
+        .compile_protos_with_config(config, &[proto], &["proto"])?;
+    Ok(())
+}
@@ -700,6 +700,19 @@ pub(crate) async fn apply_bespoke_event_handling(
             )
             .await;
         }
+        EventMsg::GuiActionQueueReview(_) => {
+            // App-server clients have no way to review GUI action queues yet;
+            // reject the queue so the turn does not wait forever.
+            if let Err(err) = conversation
+                .submit(Op::ResolveGuiActionQueue {
+                    id: event_turn_id,
+                    actions: None,
+                })
+                .await
+            {
+                error!("failed to submit ResolveGuiActionQueue: {err}");
+            }
+        }
         EventMsg::PlanUpdate(plan_update_event) => {
             handle_turn_plan_update(
                 conversation_id,
@@ -912,7 +925,9 @@ async fn handle_token_count_event(
     token_count_event: TokenCountEvent,
     outgoing: &OutgoingMessageSender,
 ) {
-    let TokenCountEvent { info, rate_limits } = token_count_event;
+    let TokenCountEvent {
+        info, rate_limits, ..
+    } = token_count_event;
     if let Some(token_usage) = info.map(ThreadTokenUsage::from) {
         let notification = ThreadTokenUsageUpdatedNotification {
             thread_id: conversation_id.to_string(),
@@ -1578,6 +1593,7 @@ mod tests {
             TokenCountEvent {
                 info: Some(info),
                 rate_limits: Some(rate_limits),
+                gui_usage: None,
             },
             &outgoing,
         )
@@ -1631,6 +1647,7 @@ mod tests {
             TokenCountEvent {
                 info: None,
                 rate_limits: None,
+                gui_usage: None,
             },
             &outgoing,
         )
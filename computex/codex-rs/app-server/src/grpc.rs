@@ -0,0 +1,922 @@
+//! gRPC transport for the app server, defined in
+//! `proto/computex/app_server/v1/app_server.proto` and generated by
+//! `build.rs`.
+//!
+//! Each `Connect` stream gets its own [`MessageProcessor`], exactly like one
+//! stdio session. Typed client messages become app-server requests, and the
+//! replies, turn and item events, and approval requests that have a typed
+//! counterpart are converted back. Everything else passes through as
+//! JSON-RPC.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use codex_app_server_protocol::ApprovalDecision;
+use codex_app_server_protocol::AskForApproval;
+use codex_app_server_protocol::ClientInfo;
+use codex_app_server_protocol::ClientRequest;
+use codex_app_server_protocol::CommandExecutionRequestApprovalResponse;
+use codex_app_server_protocol::InitializeParams;
+use codex_app_server_protocol::InitializeResponse;
+use codex_app_server_protocol::JSONRPCMessage;
+use codex_app_server_protocol::JSONRPCResponse;
+use codex_app_server_protocol::RequestId;
+use codex_app_server_protocol::SandboxMode;
+use codex_app_server_protocol::ServerNotification;
+use codex_app_server_protocol::ServerRequest;
+use codex_app_server_protocol::ThreadItem;
+use codex_app_server_protocol::ThreadStartParams;
+use codex_app_server_protocol::ThreadStartResponse;
+use codex_app_server_protocol::TurnInterruptParams;
+use codex_app_server_protocol::TurnStartParams;
+use codex_app_server_protocol::TurnStartResponse;
+use codex_app_server_protocol::TurnStatus;
+use codex_app_server_protocol::UserInput;
+use codex_core::config::Config;
+use codex_feedback::CodexFeedback;
+use serde::Deserialize;
+use tokio::sync::Mutex;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use toml::Value as TomlValue;
+use tracing::info;
+use tracing::warn;
+
+use crate::CHANNEL_CAPACITY;
+use crate::error_code::INVALID_REQUEST_ERROR_CODE;
+use crate::message_processor::MessageProcessor;
+use crate::outgoing_message::OutgoingMessage;
+use crate::outgoing_message::OutgoingMessageSender;
+
+#[allow(clippy::all)]
+mod proto {
+    tonic::include_proto!("computex.app_server.v1");
+}
+
+use proto::app_server_server::AppServer;
+use proto::app_server_server::AppServerServer;
+use proto::client_message::Message as ClientPayload;
+use proto::server_message::Message as ServerPayload;
+
+/// Version of the gRPC API; bumped together with the proto package.
+pub const PROTOCOL_VERSION: &str = "v1";
+
+/// Settings for `run_grpc_main`.
+pub struct GrpcServerOptions {
+    pub listen: SocketAddr,
+    /// Expected in `authorization: Bearer <token>` metadata on every call.
+    pub token: String,
+    /// Returned by `GetServerInfo` for clients to pass as `base_instructions`.
+    pub computer_use_instructions: String,
+}
+
+/// State shared by all connections.
+struct ServerState {
+    config: Arc<Config>,
+    cli_overrides: Vec<(String, TomlValue)>,
+    codex_linux_sandbox_exe: Option<PathBuf>,
+    feedback: CodexFeedback,
+    token: String,
+    computer_use_instructions: String,
+}
+
+#[derive(Clone)]
+pub struct AppServerService {
+    state: Arc<ServerState>,
+}
+
+impl AppServerService {
+    fn new(
+        config: Arc<Config>,
+        cli_overrides: Vec<(String, TomlValue)>,
+        codex_linux_sandbox_exe: Option<PathBuf>,
+        feedback: CodexFeedback,
+        options: &GrpcServerOptions,
+    ) -> Self {
+        Self {
+            state: Arc::new(ServerState {
+                config,
+                cli_overrides,
+                codex_linux_sandbox_exe,
+                feedback,
+                token: options.token.clone(),
+                computer_use_instructions: options.computer_use_instructions.clone(),
+            }),
+        }
+    }
+}
+
+pub(crate) async fn serve(
+    config: Arc<Config>,
+    cli_overrides: Vec<(String, TomlValue)>,
+    codex_linux_sandbox_exe: Option<PathBuf>,
+    feedback: CodexFeedback,
+    options: GrpcServerOptions,
+) -> std::io::Result<()> {
+    let service = AppServerService::new(
+        config,
+        cli_overrides,
+        codex_linux_sandbox_exe,
+        feedback,
+        &options,
+    );
+    info!("app-server gRPC listening on {}", options.listen);
+    tonic::transport::Server::builder()
+        .add_service(AppServerServer::new(service))
+        .serve_with_shutdown(options.listen, async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await
+        .map_err(std::io::Error::other)
+}
+
+/// Constant-time check of the `authorization` metadata.
+fn authorize<T>(state: &ServerState, request: &tonic::Request<T>) -> Result<(), tonic::Status> {
+    let presented = request
+        .metadata()
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    let matches = presented.len() == state.token.len()
+        && presented
+            .bytes()
+            .zip(state.token.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0;
+    if matches {
+        Ok(())
+    } else {
+        Err(tonic::Status::unauthenticated(
+            "missing or invalid bearer token",
+        ))
+    }
+}
+
+#[tonic::async_trait]
+impl AppServer for AppServerService {
+    type ConnectStream = ReceiverStream<Result<proto::ServerMessage, tonic::Status>>;
+
+    async fn get_server_info(
+        &self,
+        request: tonic::Request<proto::GetServerInfoRequest>,
+    ) -> Result<tonic::Response<proto::ServerInfo>, tonic::Status> {
+        authorize(&self.state, &request)?;
+        Ok(tonic::Response::new(proto::ServerInfo {
+            protocol_version: PROTOCOL_VERSION.to_string(),
+            server_version: env!("CARGO_PKG_VERSION").to_string(),
+            computer_use_instructions: self.state.computer_use_instructions.clone(),
+        }))
+    }
+
+    async fn connect(
+        &self,
+        request: tonic::Request<tonic::Streaming<proto::ClientMessage>>,
+    ) -> Result<tonic::Response<Self::ConnectStream>, tonic::Status> {
+        authorize(&self.state, &request)?;
+        Ok(tonic::Response::new(connect(
+            &self.state,
+            request.into_inner(),
+        )))
+    }
+}
+
+/// A typed request awaiting its reply, so the reply can be typed too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Call {
+    Initialize,
+    StartThread,
+    StartTurn,
+    InterruptTurn,
+}
+
+/// Runs one app-server session over a `Connect` stream.
+fn connect(
+    state: &Arc<ServerState>,
+    mut incoming: tonic::Streaming<proto::ClientMessage>,
+) -> ReceiverStream<Result<proto::ServerMessage, tonic::Status>> {
+    let (incoming_tx, mut incoming_rx) = mpsc::channel::<JSONRPCMessage>(CHANNEL_CAPACITY);
+    let (outgoing_tx, mut outgoing_rx) = mpsc::channel::<OutgoingMessage>(CHANNEL_CAPACITY);
+    let (messages_tx, messages_rx) = mpsc::channel(CHANNEL_CAPACITY);
+    let calls = Arc::new(Mutex::new(HashMap::<RequestId, Call>::new()));
+
+    let reader_calls = Arc::clone(&calls);
+    let failures_tx = messages_tx.clone();
+    tokio::spawn(async move {
+        loop {
+            let message = match incoming.message().await {
+                Ok(Some(message)) => message,
+                Ok(None) => break,
+                Err(status) => {
+                    info!("gRPC client stream ended: {status}");
+                    break;
+                }
+            };
+            let request_id = message.request_id.clone();
+            match client_message(message) {
+                Ok((message, call)) => {
+                    if let Some(call) = call {
+                        reader_calls
+                            .lock()
+                            .await
+                            .insert(RequestId::String(request_id), call);
+                    }
+                    if incoming_tx.send(message).await.is_err() {
+                        break;
+                    }
+                }
+                Err(message) => {
+                    let failed = proto::ServerMessage {
+                        request_id,
+                        message: Some(ServerPayload::RequestFailed(proto::RequestFailed {
+                            code: INVALID_REQUEST_ERROR_CODE,
+                            message,
+                        })),
+                    };
+                    if failures_tx.send(Ok(failed)).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    let mut processor = MessageProcessor::new(
+        OutgoingMessageSender::new(outgoing_tx),
+        state.codex_linux_sandbox_exe.clone(),
+        Arc::clone(&state.config),
+        state.cli_overrides.clone(),
+        state.feedback.clone(),
+    );
+    tokio::spawn(async move {
+        while let Some(message) = incoming_rx.recv().await {
+            match message {
+                JSONRPCMessage::Request(r) => processor.process_request(r).await,
+                JSONRPCMessage::Response(r) => processor.process_response(r).await,
+                JSONRPCMessage::Notification(n) => processor.process_notification(n).await,
+                JSONRPCMessage::Error(e) => processor.process_error(e),
+            }
+        }
+    });
+
+    tokio::spawn(async move {
+        while let Some(message) = outgoing_rx.recv().await {
+            let call = match &message {
+                OutgoingMessage::Response(response) => calls.lock().await.remove(&response.id),
+                OutgoingMessage::Error(error) => calls.lock().await.remove(&error.id),
+                _ => None,
+            };
+            let Some(message) = server_message(message, call) else {
+                warn!("Failed to convert OutgoingMessage to a gRPC message");
+                continue;
+            };
+            if messages_tx.send(Ok(message)).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    ReceiverStream::new(messages_rx)
+}
+
+/// The app-server message for a client message, and the typed call whose
+/// reply to expect. Errors describe why the message was rejected.
+fn client_message(message: proto::ClientMessage) -> Result<(JSONRPCMessage, Option<Call>), String> {
+    let request_id = RequestId::String(message.request_id);
+    let (request, call) = match message.message {
+        None => return Err("the message is empty".to_string()),
+        Some(ClientPayload::JsonRpc(json_rpc)) => {
+            let message = serde_json::from_str(&json_rpc.message_json)
+                .map_err(|err| format!("invalid JSON-RPC message: {err}"))?;
+            return Ok((message, None));
+        }
+        Some(ClientPayload::ApprovalDecision(approval)) => {
+            return Ok((JSONRPCMessage::Response(approval_response(approval)?), None));
+        }
+        Some(ClientPayload::Initialize(initialize)) => (
+            ClientRequest::Initialize {
+                request_id,
+                params: InitializeParams {
+                    client_info: ClientInfo {
+                        name: initialize.client_name,
+                        title: None,
+                        version: initialize.client_version,
+                    },
+                },
+            },
+            Call::Initialize,
+        ),
+        Some(ClientPayload::StartThread(start)) => {
+            let approval_policy = approval_policy(start.approval_policy());
+            let sandbox = sandbox_mode(start.sandbox());
+            (
+                ClientRequest::ThreadStart {
+                    request_id,
+                    params: ThreadStartParams {
+                        model: start.model,
+                        cwd: start.cwd,
+                        approval_policy,
+                        sandbox,
+                        base_instructions: start.base_instructions,
+                        ..Default::default()
+                    },
+                },
+                Call::StartThread,
+            )
+        }
+        Some(ClientPayload::StartTurn(start)) => {
+            let input = start
+                .input
+                .into_iter()
+                .map(user_input)
+                .collect::<Result<Vec<_>, _>>()?;
+            (
+                ClientRequest::TurnStart {
+                    request_id,
+                    params: TurnStartParams {
+                        thread_id: start.thread_id,
+                        input,
+                        ..Default::default()
+                    },
+                },
+                Call::StartTurn,
+            )
+        }
+        Some(ClientPayload::InterruptTurn(interrupt)) => (
+            ClientRequest::TurnInterrupt {
+                request_id,
+                params: TurnInterruptParams {
+                    thread_id: interrupt.thread_id,
+                    turn_id: interrupt.turn_id,
+                },
+            },
+            Call::InterruptTurn,
+        ),
+    };
+    let request = serde_json::to_value(request)
+        .and_then(serde_json::from_value)
+        .map_err(|err| format!("failed to encode the request: {err}"))?;
+    Ok((JSONRPCMessage::Request(request), Some(call)))
+}
+
+fn approval_policy(policy: proto::ApprovalPolicy) -> Option<AskForApproval> {
+    match policy {
+        proto::ApprovalPolicy::Unspecified => None,
+        proto::ApprovalPolicy::Untrusted => Some(AskForApproval::UnlessTrusted),
+        proto::ApprovalPolicy::OnFailure => Some(AskForApproval::OnFailure),
+        proto::ApprovalPolicy::OnRequest => Some(AskForApproval::OnRequest),
+        proto::ApprovalPolicy::Never => Some(AskForApproval::Never),
+    }
+}
+
+fn sandbox_mode(mode: proto::SandboxMode) -> Option<SandboxMode> {
+    match mode {
+        proto::SandboxMode::Unspecified => None,
+        proto::SandboxMode::ReadOnly => Some(SandboxMode::ReadOnly),
+        proto::SandboxMode::WorkspaceWrite => Some(SandboxMode::WorkspaceWrite),
+        proto::SandboxMode::DangerFullAccess => Some(SandboxMode::DangerFullAccess),
+    }
+}
+
+fn user_input(input: proto::UserInput) -> Result<UserInput, String> {
+    match input.input {
+        Some(proto::user_input::Input::Text(text)) => Ok(UserInput::Text { text }),
+        Some(proto::user_input::Input::ImageUrl(url)) => Ok(UserInput::Image { url }),
+        Some(proto::user_input::Input::LocalImagePath(path)) => Ok(UserInput::LocalImage {
+            path: PathBuf::from(path),
+        }),
+        None => Err("a turn input is empty".to_string()),
+    }
+}
+
+/// The response to the server's approval request. Command and file-change
+/// approvals take the same result.
+fn approval_response(approval: proto::ApprovalDecision) -> Result<JSONRPCResponse, String> {
+    let decision = match approval.decision() {
+        proto::Decision::Unspecified => {
+            return Err("the approval decision is missing".to_string());
+        }
+        proto::Decision::Accept => ApprovalDecision::Accept,
+        proto::Decision::AcceptForSession => ApprovalDecision::AcceptForSession,
+        proto::Decision::Decline => ApprovalDecision::Decline,
+        proto::Decision::Cancel => ApprovalDecision::Cancel,
+    };
+    let id = match approval.approval_id.parse() {
+        Ok(id) => RequestId::Integer(id),
+        Err(_) => RequestId::String(approval.approval_id),
+    };
+    let result = serde_json::to_value(CommandExecutionRequestApprovalResponse { decision })
+        .map_err(|err| format!("failed to encode the decision: {err}"))?;
+    Ok(JSONRPCResponse { id, result })
+}
+
+/// The gRPC message for an outgoing app-server message. `call` is the typed
+/// request a response or error answers, if any.
+fn server_message(message: OutgoingMessage, call: Option<Call>) -> Option<proto::ServerMessage> {
+    let typed = match (&message, call) {
+        (OutgoingMessage::Response(response), Some(call)) => {
+            typed_result(call, &response.result).map(|result| (id_string(&response.id), result))
+        }
+        (OutgoingMessage::Error(error), Some(_)) => Some((
+            id_string(&error.id),
+            ServerPayload::RequestFailed(proto::RequestFailed {
+                code: error.error.code,
+                message: error.error.message.clone(),
+            }),
+        )),
+        (OutgoingMessage::AppServerNotification(notification), _) => {
+            event(notification).map(|event| (String::new(), event))
+        }
+        (OutgoingMessage::Request(request), _) => {
+            approval_request(request).map(|request| (String::new(), request))
+        }
+        _ => None,
+    };
+    let (request_id, payload) = match typed {
+        Some(typed) => typed,
+        None => (
+            String::new(),
+            ServerPayload::JsonRpc(proto::JsonRpc {
+                message_json: serde_json::to_string(&message).ok()?,
+            }),
+        ),
+    };
+    Some(proto::ServerMessage {
+        request_id,
+        message: Some(payload),
+    })
+}
+
+fn id_string(id: &RequestId) -> String {
+    match id {
+        RequestId::String(id) => id.clone(),
+        RequestId::Integer(id) => id.to_string(),
+    }
+}
+
+fn typed_result(call: Call, result: &serde_json::Value) -> Option<ServerPayload> {
+    Some(match call {
+        Call::Initialize => {
+            let response = InitializeResponse::deserialize(result).ok()?;
+            ServerPayload::InitializeResult(proto::InitializeResult {
+                user_agent: response.user_agent,
+            })
+        }
+        Call::StartThread => {
+            let response = ThreadStartResponse::deserialize(result).ok()?;
+            ServerPayload::StartThreadResult(proto::StartThreadResult {
+                thread_id: response.thread.id,
+                model: response.model,
+                cwd: response.cwd.display().to_string(),
+            })
+        }
+        Call::StartTurn => {
+            let response = TurnStartResponse::deserialize(result).ok()?;
+            ServerPayload::StartTurnResult(proto::StartTurnResult {
+                turn_id: response.turn.id,
+            })
+        }
+        Call::InterruptTurn => ServerPayload::InterruptTurnResult(proto::InterruptTurnResult {}),
+    })
+}
+
+fn event(notification: &ServerNotification) -> Option<ServerPayload> {
+    Some(match notification {
+        ServerNotification::TurnStarted(started) => {
+            ServerPayload::TurnStarted(proto::TurnStarted {
+                thread_id: started.thread_id.clone(),
+                turn_id: started.turn.id.clone(),
+            })
+        }
+        ServerNotification::TurnCompleted(completed) => {
+            let status = match completed.turn.status {
+                TurnStatus::Completed => proto::TurnStatus::Completed,
+                TurnStatus::Interrupted => proto::TurnStatus::Interrupted,
+                TurnStatus::Failed => proto::TurnStatus::Failed,
+                TurnStatus::InProgress => proto::TurnStatus::InProgress,
+            };
+            ServerPayload::TurnCompleted(proto::TurnCompleted {
+                thread_id: completed.thread_id.clone(),
+                turn_id: completed.turn.id.clone(),
+                status: status as i32,
+                error_message: completed
+                    .turn
+                    .error
+                    .as_ref()
+                    .map(|error| error.message.clone())
+                    .unwrap_or_default(),
+            })
+        }
+        ServerNotification::ItemStarted(started) => ServerPayload::ItemStarted(proto::ItemEvent {
+            thread_id: started.thread_id.clone(),
+            turn_id: started.turn_id.clone(),
+            item: Some(item(&started.item)),
+        }),
+        ServerNotification::ItemCompleted(completed) => {
+            ServerPayload::ItemCompleted(proto::ItemEvent {
+                thread_id: completed.thread_id.clone(),
+                turn_id: completed.turn_id.clone(),
+                item: Some(item(&completed.item)),
+            })
+        }
+        ServerNotification::AgentMessageDelta(delta) => {
+            ServerPayload::AgentMessageDelta(proto::AgentMessageDelta {
+                thread_id: delta.thread_id.clone(),
+                turn_id: delta.turn_id.clone(),
+                item_id: delta.item_id.clone(),
+                delta: delta.delta.clone(),
+            })
+        }
+        ServerNotification::CommandExecutionOutputDelta(delta) => {
+            ServerPayload::CommandOutputDelta(proto::CommandOutputDelta {
+                thread_id: delta.thread_id.clone(),
+                turn_id: delta.turn_id.clone(),
+                item_id: delta.item_id.clone(),
+                delta: delta.delta.clone(),
+            })
+        }
+        ServerNotification::Error(error) => ServerPayload::TurnError(proto::TurnError {
+            thread_id: error.thread_id.clone(),
+            turn_id: error.turn_id.clone(),
+            message: error.error.message.clone(),
+            will_retry: error.will_retry,
+        }),
+        _ => return None,
+    })
+}
+
+fn item(item: &ThreadItem) -> proto::Item {
+    let mut converted = proto::Item {
+        item_json: serde_json::to_string(item).unwrap_or_default(),
+        ..Default::default()
+    };
+    let (id, kind) = match item {
+        ThreadItem::UserMessage { id, content } => {
+            converted.text = content
+                .iter()
+                .filter_map(|input| match input {
+                    UserInput::Text { text } => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            (id, proto::ItemKind::UserMessage)
+        }
+        ThreadItem::AgentMessage { id, text } => {
+            converted.text = text.clone();
+            (id, proto::ItemKind::AgentMessage)
+        }
+        ThreadItem::Reasoning { id, summary, .. } => {
+            converted.text = summary.join("\n");
+            (id, proto::ItemKind::Reasoning)
+        }
+        ThreadItem::CommandExecution {
+            id,
+            command,
+            aggregated_output,
+            exit_code,
+            ..
+        } => {
+            converted.command = command.clone();
+            converted.output = aggregated_output.clone().unwrap_or_default();
+            converted.exit_code = *exit_code;
+            (id, proto::ItemKind::CommandExecution)
+        }
+        ThreadItem::FileChange { id, .. } => (id, proto::ItemKind::FileChange),
+        ThreadItem::McpToolCall { id, tool, .. } => {
+            converted.tool = tool.clone();
+            (id, proto::ItemKind::McpToolCall)
+        }
+        ThreadItem::WebSearch { id, query } => {
+            converted.text = query.clone();
+            (id, proto::ItemKind::WebSearch)
+        }
+        ThreadItem::ImageView { id, path } => {
+            converted.text = path.clone();
+            (id, proto::ItemKind::ImageView)
+        }
+        ThreadItem::EnteredReviewMode { id, review } => {
+            converted.text = review.clone();
+            (id, proto::ItemKind::EnteredReviewMode)
+        }
+        ThreadItem::ExitedReviewMode { id, review } => {
+            converted.text = review.clone();
+            (id, proto::ItemKind::ExitedReviewMode)
+        }
+    };
+    converted.id = id.clone();
+    converted.set_kind(kind);
+    converted
+}
+
+fn approval_request(request: &ServerRequest) -> Option<ServerPayload> {
+    let (request_id, kind, thread_id, turn_id, item_id, reason) = match request {
+        ServerRequest::CommandExecutionRequestApproval { request_id, params } => (
+            request_id,
+            proto::ApprovalKind::CommandExecution,
+            &params.thread_id,
+            &params.turn_id,
+            &params.item_id,
+            &params.reason,
+        ),
+        ServerRequest::FileChangeRequestApproval { request_id, params } => (
+            request_id,
+            proto::ApprovalKind::FileChange,
+            &params.thread_id,
+            &params.turn_id,
+            &params.item_id,
+            &params.reason,
+        ),
+        _ => return None,
+    };
+    Some(ServerPayload::ApprovalRequest(proto::ApprovalRequest {
+        approval_id: id_string(request_id),
+        kind: kind as i32,
+        thread_id: thread_id.clone(),
+        turn_id: turn_id.clone(),
+        item_id: item_id.clone(),
+        reason: reason.clone().unwrap_or_default(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_app_server_protocol::CommandExecutionRequestApprovalParams;
+    use codex_app_server_protocol::Turn;
+    use codex_app_server_protocol::TurnCompletedNotification;
+    use codex_app_server_protocol::TurnError;
+    use codex_core::config::ConfigBuilder;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tonic::transport::Endpoint;
+
+    use crate::outgoing_message::OutgoingNotification;
+    use crate::outgoing_message::OutgoingResponse;
+    use proto::app_server_client::AppServerClient;
+
+    fn authorized<T>(message: T) -> tonic::Request<T> {
+        let mut request = tonic::Request::new(message);
+        request
+            .metadata_mut()
+            .insert("authorization", "Bearer secret".parse().unwrap());
+        request
+    }
+
+    fn request(request_id: &str, payload: ClientPayload) -> proto::ClientMessage {
+        proto::ClientMessage {
+            request_id: request_id.to_string(),
+            message: Some(payload),
+        }
+    }
+
+    #[test]
+    fn typed_requests_become_app_server_requests() {
+        let (message, call) = client_message(request(
+            "t1",
+            ClientPayload::StartThread(proto::StartThread {
+                base_instructions: Some("use the GUI tools".to_string()),
+                approval_policy: proto::ApprovalPolicy::Never as i32,
+                sandbox: proto::SandboxMode::ReadOnly as i32,
+                ..Default::default()
+            }),
+        ))
+        .unwrap();
+        let JSONRPCMessage::Request(thread_start) = message else {
+            panic!("expected a request, got {message:?}");
+        };
+        let params = thread_start.params.unwrap();
+        assert_eq!(
+            (
+                thread_start.id,
+                thread_start.method.as_str(),
+                &params["approvalPolicy"],
+                &params["sandbox"],
+                &params["baseInstructions"],
+                call,
+            ),
+            (
+                RequestId::String("t1".to_string()),
+                "thread/start",
+                &json!("never"),
+                &json!("read-only"),
+                &json!("use the GUI tools"),
+                Some(Call::StartThread),
+            )
+        );
+
+        let (message, call) = client_message(request(
+            "t2",
+            ClientPayload::StartTurn(proto::StartTurn {
+                thread_id: "thread-1".to_string(),
+                input: vec![proto::UserInput {
+                    input: Some(proto::user_input::Input::Text(
+                        "open the report".to_string(),
+                    )),
+                }],
+            }),
+        ))
+        .unwrap();
+        let JSONRPCMessage::Request(turn_start) = message else {
+            panic!("expected a request, got {message:?}");
+        };
+        let params = turn_start.params.unwrap();
+        assert_eq!(
+            (turn_start.method.as_str(), &params["input"], call),
+            (
+                "turn/start",
+                &json!([{"type": "text", "text": "open the report"}]),
+                Some(Call::StartTurn),
+            )
+        );
+
+        assert!(client_message(proto::ClientMessage::default()).is_err());
+    }
+
+    #[test]
+    fn approval_decisions_answer_the_server_request() {
+        let decision = |decision: proto::Decision| {
+            client_message(request(
+                "",
+                ClientPayload::ApprovalDecision(proto::ApprovalDecision {
+                    approval_id: "7".to_string(),
+                    decision: decision as i32,
+                }),
+            ))
+        };
+        assert_eq!(
+            decision(proto::Decision::AcceptForSession),
+            Ok((
+                JSONRPCMessage::Response(JSONRPCResponse {
+                    id: RequestId::Integer(7),
+                    result: json!({"decision": "acceptForSession"}),
+                }),
+                None,
+            ))
+        );
+        assert!(decision(proto::Decision::Unspecified).is_err());
+
+        let request = ServerRequest::CommandExecutionRequestApproval {
+            request_id: RequestId::Integer(7),
+            params: CommandExecutionRequestApprovalParams {
+                thread_id: "thread-1".to_string(),
+                turn_id: "turn-1".to_string(),
+                item_id: "call-1".to_string(),
+                reason: None,
+                proposed_execpolicy_amendment: None,
+            },
+        };
+        assert_eq!(
+            server_message(OutgoingMessage::Request(request), None),
+            Some(proto::ServerMessage {
+                request_id: String::new(),
+                message: Some(ServerPayload::ApprovalRequest(proto::ApprovalRequest {
+                    approval_id: "7".to_string(),
+                    kind: proto::ApprovalKind::CommandExecution as i32,
+                    thread_id: "thread-1".to_string(),
+                    turn_id: "turn-1".to_string(),
+                    item_id: "call-1".to_string(),
+                    reason: String::new(),
+                })),
+            })
+        );
+    }
+
+    #[test]
+    fn replies_and_events_are_typed() {
+        let reply = server_message(
+            OutgoingMessage::Response(OutgoingResponse {
+                id: RequestId::String("t2".to_string()),
+                result: json!({"turn": {"id": "turn-1", "items": [], "status": "inProgress", "error": null}}),
+            }),
+            Some(Call::StartTurn),
+        );
+        assert_eq!(
+            reply,
+            Some(proto::ServerMessage {
+                request_id: "t2".to_string(),
+                message: Some(ServerPayload::StartTurnResult(proto::StartTurnResult {
+                    turn_id: "turn-1".to_string(),
+                })),
+            })
+        );
+
+        let completed = server_message(
+            OutgoingMessage::AppServerNotification(ServerNotification::TurnCompleted(
+                TurnCompletedNotification {
+                    thread_id: "thread-1".to_string(),
+                    turn: Turn {
+                        id: "turn-1".to_string(),
+                        items: Vec::new(),
+                        status: TurnStatus::Failed,
+                        error: Some(TurnError {
+                            message: "stream disconnected".to_string(),
+                            codex_error_info: None,
+                            additional_details: None,
+                        }),
+                    },
+                },
+            )),
+            None,
+        );
+        assert_eq!(
+            completed.and_then(|message| message.message),
+            Some(ServerPayload::TurnCompleted(proto::TurnCompleted {
+                thread_id: "thread-1".to_string(),
+                turn_id: "turn-1".to_string(),
+                status: proto::TurnStatus::Failed as i32,
+                error_message: "stream disconnected".to_string(),
+            }))
+        );
+
+        let untyped = server_message(
+            OutgoingMessage::Notification(OutgoingNotification {
+                method: "turn/started".to_string(),
+                params: None,
+            }),
+            None,
+        );
+        assert_eq!(
+            untyped.and_then(|message| message.message),
+            Some(ServerPayload::JsonRpc(proto::JsonRpc {
+                message_json: r#"{"method":"turn/started"}"#.to_string(),
+            }))
+        );
+    }
+
+    #[tokio::test]
+    async fn connect_runs_an_app_server_session() -> anyhow::Result<()> {
+        let codex_home = tempfile::tempdir()?;
+        let config = ConfigBuilder::default()
+            .codex_home(codex_home.path().to_path_buf())
+            .build()
+            .await?;
+        let options = GrpcServerOptions {
+            listen: SocketAddr::from(([127, 0, 0, 1], 0)),
+            token: "secret".to_string(),
+            computer_use_instructions: "use the GUI tools".to_string(),
+        };
+        let service = AppServerService::new(
+            Arc::new(config),
+            Vec::new(),
+            None,
+            CodexFeedback::new(),
+            &options,
+        );
+        let listener = tokio::net::TcpListener::bind(options.listen).await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(AppServerServer::new(service))
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+
+        let channel = Endpoint::from_shared(format!("http://{addr}"))?
+            .connect()
+            .await?;
+        let mut client = AppServerClient::new(channel);
+
+        let info = client
+            .get_server_info(authorized(proto::GetServerInfoRequest {}))
+            .await?
+            .into_inner();
+        assert_eq!(
+            (
+                info.protocol_version.as_str(),
+                info.computer_use_instructions.as_str()
+            ),
+            ("v1", "use the GUI tools")
+        );
+
+        let unauthenticated = client.get_server_info(proto::GetServerInfoRequest {}).await;
+        assert_eq!(
+            unauthenticated.map(|_| ()).map_err(|status| status.code()),
+            Err(tonic::Code::Unauthenticated)
+        );
+
+        let initialize = request(
+            "init",
+            ClientPayload::Initialize(proto::Initialize {
+                client_name: "dashboard".to_string(),
+                client_version: "1.0.0".to_string(),
+            }),
+        );
+        let mut messages = client
+            .connect(authorized(tokio_stream::iter(vec![initialize])))
+            .await?
+            .into_inner();
+        let reply = messages
+            .message()
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("no reply"))?;
+        assert_eq!(reply.request_id, "init");
+        let Some(ServerPayload::InitializeResult(result)) = reply.message else {
+            panic!("expected an initialize result, got {:?}", reply.message);
+        };
+        assert!(!result.user_agent.is_empty());
+        Ok(())
+    }
+}
@@ -32,10 +32,13 @@ mod codex_message_processor;
 mod config_api;
 mod error_code;
 mod fuzzy_file_search;
+mod grpc;
 mod message_processor;
 mod models;
 mod outgoing_message;
 
+pub use grpc::GrpcServerOptions;
+
 /// Size of the bounded channels used to communicate between tasks. The value
 /// is a balance between throughput and memory usage – 128 messages should be
 /// plenty for an interactive CLI.
@@ -72,53 +75,9 @@ pub async fn run_main(
         }
     });
 
-    // Parse CLI overrides once and derive the base Config eagerly so later
-    // components do not need to work with raw TOML values.
-    let cli_kv_overrides = cli_config_overrides.parse_overrides().map_err(|e| {
-        std::io::Error::new(
-            ErrorKind::InvalidInput,
-            format!("error parsing -c overrides: {e}"),
-        )
-    })?;
-    let config = Config::load_with_cli_overrides(cli_kv_overrides.clone())
-        .await
-        .map_err(|e| {
-            std::io::Error::new(ErrorKind::InvalidData, format!("error loading config: {e}"))
-        })?;
-
+    let (config, cli_kv_overrides) = load_config(cli_config_overrides).await?;
     let feedback = CodexFeedback::new();
-
-    let otel =
-        codex_core::otel_init::build_provider(&config, env!("CARGO_PKG_VERSION")).map_err(|e| {
-            std::io::Error::new(
-                ErrorKind::InvalidData,
-                format!("error loading otel config: {e}"),
-            )
-        })?;
-
-    // Install a simple subscriber so `tracing` output is visible.  Users can
-    // control the log level with `RUST_LOG`.
-    let stderr_fmt = tracing_subscriber::fmt::layer()
-        .with_writer(std::io::stderr)
-        .with_span_events(tracing_subscriber::fmt::format::FmtSpan::FULL)
-        .with_filter(EnvFilter::from_default_env());
-
-    let feedback_layer = tracing_subscriber::fmt::layer()
-        .with_writer(feedback.make_writer())
-        .with_ansi(false)
-        .with_target(false)
-        .with_filter(Targets::new().with_default(Level::TRACE));
-
-    let otel_logger_layer = otel.as_ref().and_then(|o| o.logger_layer());
-
-    let otel_tracing_layer = otel.as_ref().and_then(|o| o.tracing_layer());
-
-    let _ = tracing_subscriber::registry()
-        .with(stderr_fmt)
-        .with(feedback_layer)
-        .with(otel_logger_layer)
-        .with(otel_tracing_layer)
-        .try_init();
+    let _otel = init_tracing(&config, &feedback)?;
 
     // Task: process incoming messages.
     let processor_handle = tokio::spawn({
@@ -175,3 +134,81 @@ pub async fn run_main(
 
     Ok(())
 }
+
+/// Serves the app-server protocol over gRPC instead of stdio; see
+/// `proto/computex/app_server/v1/app_server.proto`.
+pub async fn run_grpc_main(
+    codex_linux_sandbox_exe: Option<PathBuf>,
+    cli_config_overrides: CliConfigOverrides,
+    options: GrpcServerOptions,
+) -> IoResult<()> {
+    let (config, cli_kv_overrides) = load_config(cli_config_overrides).await?;
+    let feedback = CodexFeedback::new();
+    let _otel = init_tracing(&config, &feedback)?;
+    grpc::serve(
+        std::sync::Arc::new(config),
+        cli_kv_overrides,
+        codex_linux_sandbox_exe,
+        feedback,
+        options,
+    )
+    .await
+}
+
+async fn load_config(
+    cli_config_overrides: CliConfigOverrides,
+) -> IoResult<(Config, Vec<(String, TomlValue)>)> {
+    // Parse CLI overrides once and derive the base Config eagerly so later
+    // components do not need to work with raw TOML values.
+    let cli_kv_overrides = cli_config_overrides.parse_overrides().map_err(|e| {
+        std::io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("error parsing -c overrides: {e}"),
+        )
+    })?;
+    let config = Config::load_with_cli_overrides(cli_kv_overrides.clone())
+        .await
+        .map_err(|e| {
+            std::io::Error::new(ErrorKind::InvalidData, format!("error loading config: {e}"))
+        })?;
+
+    Ok((config, cli_kv_overrides))
+}
+
+/// Installs the tracing subscriber. The returned guard keeps the OpenTelemetry
+/// provider alive and must be held for the life of the server.
+fn init_tracing(config: &Config, feedback: &CodexFeedback) -> IoResult<impl Sized + use<>> {
+    let otel =
+        codex_core::otel_init::build_provider(config, env!("CARGO_PKG_VERSION")).map_err(|e| {
+            std::io::Error::new(
+                ErrorKind::InvalidData,
+                format!("error loading otel config: {e}"),
+            )
+        })?;
+
+    // Install a simple subscriber so `tracing` output is visible.  Users can
+    // control the log level with `RUST_LOG`.
+    let stderr_fmt = tracing_subscriber::fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_span_events(tracing_subscriber::fmt::format::FmtSpan::FULL)
+        .with_filter(EnvFilter::from_default_env());
+
+    let feedback_layer = tracing_subscriber::fmt::layer()
+        .with_writer(feedback.make_writer())
+        .with_ansi(false)
+        .with_target(false)
+        .with_filter(Targets::new().with_default(Level::TRACE));
+
+    let otel_logger_layer = otel.as_ref().and_then(|o| o.logger_layer());
+
+    let otel_tracing_layer = otel.as_ref().and_then(|o| o.tracing_layer());
+
+    let _ = tracing_subscriber::registry()
+        .with(stderr_fmt)
+        .with(feedback_layer)
+        .with(otel_logger_layer)
+        .with(otel_tracing_layer)
+        .try_init();
+
+    Ok(otel)
+}
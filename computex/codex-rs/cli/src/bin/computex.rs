@@ -1,20 +1,63 @@
 use clap::Parser;
 use codex_arg0::arg0_dispatch_or_else;
+use codex_cli::computer_use_preview::start_preview;
+use codex_cli::computer_use_schedule::JobDefaults;
+use codex_cli::computer_use_schedule::ScheduleCommand;
+use codex_cli::computer_use_schedule::run_schedule_command;
+use codex_cli::computer_use_serve::ServeCommand;
+use codex_cli::computer_use_serve::TOKEN_ENV_VAR;
+use codex_cli::computer_use_serve::run_serve;
+use codex_cli::computer_use_serve::serve_token;
+use codex_cli::computer_use_setup::install_browser_bridge;
+use codex_cli::computer_use_setup::install_input_helper;
+use codex_cli::computer_use_setup::run_first_run_setup;
 use codex_common::CliConfigOverrides;
-use codex_core::COMPUTER_USE_PROMPT;
+use codex_core::RolloutRecorder;
+use codex_core::computer_use_browser_bridge;
+use codex_core::computer_use_calibration;
+use codex_core::computer_use_encryption;
+use codex_core::computer_use_export;
+use codex_core::computer_use_export::ScriptFormat;
+use codex_core::computer_use_prompt;
+use codex_core::computer_use_remote::Tunnel;
+use codex_core::computer_use_rotation;
+use codex_core::computer_use_script;
+use codex_core::computer_use_script::ActionScript;
+use codex_core::computer_use_script::ScriptAudit;
+use codex_core::computer_use_script::ScriptSyntax;
+use codex_core::computer_use_snapshots;
+use codex_core::computer_use_stats;
+use codex_core::computer_use_timelapse;
+use codex_core::computer_use_timelapse::TimelapseFormat;
+use codex_core::computer_use_unlock;
+use codex_core::computer_use_virtual_output::VirtualOutput;
+use codex_core::config::edit::ConfigEditsBuilder;
 use codex_core::config::find_codex_home;
+use codex_core::config::load_computer_use_config;
 use codex_core::config::load_config_as_toml_with_cli_overrides;
+use codex_core::config::types::ComputerUseBackend;
+use codex_core::config::types::ComputerUseConfig;
+use codex_core::config::types::ConsoleTarget;
+use codex_core::config::types::OcrScrubber;
+use codex_core::config::types::RemoteTarget;
+use codex_core::config::types::Resolution;
 use codex_core::features::Feature;
 use codex_core::features::FeatureOverrides;
 use codex_core::features::Features;
 use codex_core::features::is_known_feature_key;
+use codex_core::find_conversation_path_by_id_str;
 use codex_core::protocol::FinalOutput;
+use codex_exec::Cli as ExecCli;
 use codex_tui::AppExitInfo;
 use codex_tui::Cli as TuiCli;
 use codex_tui::update_action::UpdateAction;
 use codex_tui2 as tui2;
 use codex_utils_absolute_path::AbsolutePathBuf;
 use owo_colors::OwoColorize;
+use std::io::BufRead;
+use std::io::IsTerminal;
+use std::net::SocketAddr;
+use std::path::Path;
 use std::path::PathBuf;
 use supports_color::Stream;
 
@@ -22,8 +65,10 @@ use supports_color::Stream;
 #[command(
     author,
     version,
+    // If a sub-command is given, ignore requirements of the default args.
+    subcommand_negates_reqs = true,
     bin_name = "computex",
-    override_usage = "computex [OPTIONS] [PROMPT]"
+    override_usage = "computex [OPTIONS] [PROMPT]\n       computex [OPTIONS] <COMMAND> [ARGS]"
 )]
 struct ComputexCli {
     #[clap(flatten)]
@@ -32,6 +77,9 @@ struct ComputexCli {
     #[clap(flatten)]
     feature_toggles: FeatureToggles,
 
+    #[clap(flatten)]
+    computer_use_flags: ComputerUseFlags,
+
     #[clap(flatten)]
     interactive: TuiCli,
 
@@ -42,6 +90,190 @@ struct ComputexCli {
     /// Disable GUI tools (shell-only).
     #[arg(long, conflicts_with = "gui")]
     headless: bool,
+
+    #[clap(subcommand)]
+    command: Option<ComputexCommand>,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum ComputexCommand {
+    /// Install the bundled input helper and rerun the first-run GUI setup.
+    Setup(SetupCommand),
+
+    /// Measure where clicks land against a test pattern and store a coordinate correction.
+    Calibrate(CalibrateCommand),
+
+    /// Restore the VM to a checkpoint the agent took with `computer_checkpoint`.
+    Rollback(RollbackCommand),
+
+    /// Convert the GUI actions of a recorded session into a PyAutoGUI or Playwright script.
+    ExportScript(ExportScriptCommand),
+
+    /// Run a declarative GUI action script (or a PyAutoGUI/AutoHotkey one) without a model.
+    RunScript(RunScriptCommand),
+
+    /// Show GUI tool success rates, retries, and actions per subtask from recorded sessions.
+    Stats(StatsCommand),
+
+    /// Stitch a recorded session's screenshots into an annotated GIF or WebM timelapse.
+    Timelapse(TimelapseCommand),
+
+    /// Decrypt a screenshot kept with `computer_use.encrypt_captures`.
+    Decrypt(DecryptCommand),
+
+    /// Store or clear the secret `computer_unlock` types at a lock screen.
+    #[clap(subcommand)]
+    UnlockSecret(UnlockSecretCommand),
+
+    /// Run a prompt non-interactively, with the same computer-use setup as the TUI.
+    Exec(Box<ExecCli>),
+
+    /// Run prompts on a cron schedule or when a file or D-Bus signal fires.
+    Schedule(ScheduleCommand),
+
+    /// Serve an authenticated HTTP API for starting, following, and cancelling runs.
+    Serve(ServeCommand),
+
+    /// Serve the app-server protocol over gRPC for embedding computex in other products.
+    Grpc(GrpcCommand),
+
+    /// Native messaging host the browser extension starts; not run by hand.
+    #[clap(hide = true)]
+    BrowserBridge(BrowserBridgeCommand),
+}
+
+#[derive(Debug, Parser)]
+struct BrowserBridgeCommand {
+    /// The extension origin or manifest path the browser passes; unused.
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true, hide = true)]
+    _browser_args: Vec<String>,
+}
+
+#[derive(Debug, Parser)]
+struct SetupCommand {
+    /// `computex-inject` binary to install. Defaults to one next to this executable.
+    #[arg(long, value_name = "FILE")]
+    helper: Option<PathBuf>,
+}
+
+#[derive(Debug, Parser)]
+struct CalibrateCommand {
+    /// Remove the stored correction instead of measuring a new one.
+    #[arg(long)]
+    reset: bool,
+}
+
+#[derive(Debug, Parser)]
+struct RollbackCommand {
+    /// Checkpoint to restore. Defaults to the most recent one.
+    #[arg(value_name = "CHECKPOINT")]
+    checkpoint: Option<String>,
+
+    /// List recorded checkpoints instead of restoring one.
+    #[arg(long, conflicts_with = "checkpoint")]
+    list: bool,
+}
+
+#[derive(Debug, Parser)]
+struct ExportScriptCommand {
+    /// Session id, or the path to a rollout file under ~/.codex/sessions.
+    #[arg(value_name = "SESSION")]
+    session: String,
+
+    /// Script flavor to generate.
+    #[arg(long, value_name = "FORMAT", value_parser = parse_script_format, default_value = "pyautogui")]
+    format: ScriptFormat,
+
+    /// Write the script to this file instead of stdout.
+    #[arg(long, short = 'o', value_name = "FILE")]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Parser)]
+struct StatsCommand {
+    /// Session ids or rollout file paths. Defaults to the most recent sessions.
+    #[arg(value_name = "SESSION")]
+    sessions: Vec<String>,
+
+    /// How many recent sessions to read when none are named.
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 50,
+        conflicts_with = "sessions"
+    )]
+    last: usize,
+}
+
+#[derive(Debug, Parser)]
+struct TimelapseCommand {
+    /// Session id, or the path to a rollout file under ~/.codex/sessions.
+    #[arg(value_name = "SESSION")]
+    session: String,
+
+    /// File to write; a `.gif` or `.webm` extension picks the format.
+    #[arg(
+        long,
+        short = 'o',
+        value_name = "FILE",
+        default_value = "timelapse.gif"
+    )]
+    output: PathBuf,
+
+    /// How long each screenshot stays on screen.
+    #[arg(long, value_name = "SECONDS", default_value_t = 1.0)]
+    seconds_per_frame: f64,
+}
+
+#[derive(Debug, Parser)]
+struct DecryptCommand {
+    /// Encrypted screenshot, usually a `.png.enc` file under `screenshot_dir`.
+    #[arg(value_name = "FILE")]
+    file: PathBuf,
+
+    /// File to write. Defaults to the input path without its `.enc` extension.
+    #[arg(long, short = 'o', value_name = "FILE")]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum UnlockSecretCommand {
+    /// Read the secret from stdin (without echo on a terminal) and store it in the OS keyring.
+    Set,
+
+    /// Remove the stored secret.
+    Clear,
+
+    /// Report whether a secret is stored.
+    Status,
+}
+
+#[derive(Debug, Parser)]
+struct RunScriptCommand {
+    /// Script to run. `.py` files are read as PyAutoGUI and `.ahk` files as AutoHotkey.
+    #[arg(value_name = "FILE")]
+    file: PathBuf,
+
+    /// Script syntax, overriding the guess from the file extension.
+    #[arg(long, value_name = "SYNTAX", value_parser = parse_script_syntax)]
+    syntax: Option<ScriptSyntax>,
+
+    /// Print the script converted to the native syntax instead of running it.
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Send destructive key combos such as Alt+F4 even when
+    /// `computer_use.confirm_destructive_keys` is set.
+    #[arg(long)]
+    allow_destructive_keys: bool,
+}
+
+#[derive(Debug, Parser)]
+struct GrpcCommand {
+    /// Address to listen on. Anything other than loopback exposes the agent to
+    /// the network; put TLS in front of it.
+    #[arg(long, value_name = "ADDR", default_value = "127.0.0.1:8788")]
+    listen: SocketAddr,
 }
 
 #[derive(Debug, Default, Parser, Clone)]
@@ -78,6 +310,158 @@ impl FeatureToggles {
     }
 }
 
+/// Shorthands for the most common `computer_use.*` config keys.
+#[derive(Debug, Default, Parser, Clone)]
+struct ComputerUseFlags {
+    /// X11 display to drive (e.g. `:1`). Equivalent to `-c computer_use.display=<DISPLAY>`.
+    #[arg(long = "display", value_name = "DISPLAY")]
+    display: Option<String>,
+
+    /// Drive another machine over SSH (e.g. `ssh://me@lab:2222/:1`): the GUI
+    /// tools use its X display and shell commands run there.
+    /// Equivalent to `-c computer_use.target=<URL>`.
+    #[arg(long = "target", value_name = "ssh://HOST")]
+    target: Option<RemoteTarget>,
+
+    /// Virtual screenshot/coordinate resolution (e.g. `1920x1080`).
+    /// Equivalent to `-c computer_use.resolution=<WxH>`.
+    #[arg(long = "resolution", value_name = "WxH")]
+    resolution: Option<Resolution>,
+
+    /// Capture/input backend (`x11`, `qemu`, or `console`). Equivalent to `-c computer_use.backend=<BACKEND>`.
+    #[arg(long = "backend", value_name = "BACKEND", value_parser = parse_backend)]
+    backend: Option<ComputerUseBackend>,
+
+    /// QMP socket of the VM the `qemu` backend drives.
+    /// Equivalent to `-c computer_use.qmp_socket=<PATH>`.
+    #[arg(long = "qmp-socket", value_name = "PATH")]
+    qmp_socket: Option<PathBuf>,
+
+    /// Text console the `console` backend drives: `tmux:<pane>` or
+    /// `serial:<device>[@<baud>]`. Equivalent to `-c computer_use.console=<TARGET>`.
+    #[arg(long = "console", value_name = "TARGET")]
+    console: Option<ConsoleTarget>,
+
+    /// Disable pointer tools and navigate with the keyboard only.
+    /// Equivalent to `-c computer_use.keyboard_only=true`.
+    #[arg(long = "keyboard-only", default_value_t = false)]
+    keyboard_only: bool,
+
+    /// Serve a live view of the display, with a pause control, on this address
+    /// (e.g. `0.0.0.0:8790`). Equivalent to `-c computer_use.preview_listen=<ADDR>`.
+    #[arg(long = "preview", value_name = "ADDR")]
+    preview: Option<SocketAddr>,
+
+    /// Add a 1280x720 virtual monitor for the session and keep GUI tools on it.
+    /// Equivalent to `-c computer_use.virtual_output=true`.
+    #[arg(long = "virtual-output", default_value_t = false)]
+    virtual_output: bool,
+
+    /// Personal data to scrub from OCR text for this session (`email`,
+    /// `card_number`, `phone`, comma-separated).
+    /// Equivalent to `-c computer_use.ocr_scrubbers=[...]`.
+    #[arg(
+        long = "ocr-scrub",
+        value_name = "KINDS",
+        value_delimiter = ',',
+        value_parser = parse_ocr_scrubber,
+        conflicts_with = "no_ocr_scrub"
+    )]
+    ocr_scrub: Option<Vec<OcrScrubber>>,
+
+    /// Return OCR text unscrubbed for this session.
+    /// Equivalent to `-c computer_use.ocr_scrubbers=[]`.
+    #[arg(long = "no-ocr-scrub", default_value_t = false)]
+    no_ocr_scrub: bool,
+}
+
+impl ComputerUseFlags {
+    /// Renders the flags as `-c` overrides. With `--profile`, the keys target
+    /// that profile's `computer_use` table so they win over its settings.
+    fn to_overrides(&self, config_profile: Option<&str>) -> Vec<String> {
+        let prefix = computer_use_prefix(config_profile);
+        let mut overrides = Vec::new();
+        if let Some(display) = &self.display {
+            let value = toml::Value::String(display.clone());
+            overrides.push(format!("{prefix}.display={value}"));
+        }
+        if let Some(target) = &self.target {
+            let value = toml::Value::String(target.to_string());
+            overrides.push(format!("{prefix}.target={value}"));
+        }
+        if let Some(resolution) = self.resolution {
+            let value = toml::Value::String(resolution.to_string());
+            overrides.push(format!("{prefix}.resolution={value}"));
+        }
+        if let Some(backend) = self.backend
+            && let Ok(value) = toml::Value::try_from(backend)
+        {
+            overrides.push(format!("{prefix}.backend={value}"));
+        }
+        if let Some(socket) = &self.qmp_socket {
+            let value = toml::Value::String(socket.to_string_lossy().into_owned());
+            overrides.push(format!("{prefix}.qmp_socket={value}"));
+        }
+        if let Some(console) = &self.console {
+            let value = toml::Value::String(console.to_string());
+            overrides.push(format!("{prefix}.console={value}"));
+        }
+        if self.keyboard_only {
+            overrides.push(format!("{prefix}.keyboard_only=true"));
+        }
+        if let Some(preview) = self.preview {
+            let value = toml::Value::String(preview.to_string());
+            overrides.push(format!("{prefix}.preview_listen={value}"));
+        }
+        if self.virtual_output {
+            overrides.push(format!("{prefix}.virtual_output=true"));
+        }
+        let scrubbers = if self.no_ocr_scrub {
+            Some(Vec::new())
+        } else {
+            self.ocr_scrub.clone()
+        };
+        if let Some(scrubbers) = scrubbers
+            && let Ok(value) = toml::Value::try_from(scrubbers)
+        {
+            overrides.push(format!("{prefix}.ocr_scrubbers={value}"));
+        }
+        overrides
+    }
+}
+
+/// Config key prefix for the `computer_use` table `config_profile` reads.
+fn computer_use_prefix(config_profile: Option<&str>) -> String {
+    match config_profile {
+        Some(profile) => format!("profiles.{profile}.computer_use"),
+        None => "computer_use".to_string(),
+    }
+}
+
+fn parse_backend(value: &str) -> Result<ComputerUseBackend, String> {
+    toml::Value::String(value.to_string())
+        .try_into()
+        .map_err(|_| {
+            format!("unknown computer-use backend `{value}` (supported: x11, qemu, console)")
+        })
+}
+
+fn parse_ocr_scrubber(value: &str) -> Result<OcrScrubber, String> {
+    toml::Value::String(value.to_string())
+        .try_into()
+        .map_err(|_| {
+            format!("unknown OCR scrubber `{value}` (supported: email, card_number, phone)")
+        })
+}
+
+fn parse_script_format(value: &str) -> Result<ScriptFormat, String> {
+    value.parse()
+}
+
+fn parse_script_syntax(value: &str) -> Result<ScriptSyntax, String> {
+    value.parse()
+}
+
 fn main() -> anyhow::Result<()> {
     arg0_dispatch_or_else(|codex_linux_sandbox_exe| async move {
         cli_main(codex_linux_sandbox_exe).await?;
@@ -89,18 +473,133 @@ async fn cli_main(codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()
     let ComputexCli {
         config_overrides,
         feature_toggles,
+        computer_use_flags,
         interactive,
         gui,
         headless,
+        command,
     } = ComputexCli::parse();
 
-    let interactive = prepare_interactive(
+    let mut interactive = prepare_interactive(
         config_overrides,
         feature_toggles,
+        computer_use_flags,
         interactive,
         gui,
         headless,
     )?;
+    let enable_gui = gui && !headless;
+    match command {
+        Some(ComputexCommand::Setup(setup)) => {
+            let computer_use = resolve_computer_use_config(&interactive).await?;
+            return run_setup(setup, &computer_use, &find_codex_home()?).await;
+        }
+        Some(ComputexCommand::Calibrate(calibrate)) => {
+            let computer_use = resolve_computer_use_config(&interactive).await?;
+            return run_calibrate(calibrate, computer_use, &find_codex_home()?).await;
+        }
+        Some(ComputexCommand::Rollback(rollback)) => {
+            let computer_use = resolve_computer_use_config(&interactive).await?;
+            return run_rollback(rollback, &computer_use, &find_codex_home()?);
+        }
+        Some(ComputexCommand::ExportScript(export)) => {
+            let computer_use = resolve_computer_use_config(&interactive).await?;
+            return run_export_script(export, &computer_use, &find_codex_home()?).await;
+        }
+        Some(ComputexCommand::Stats(stats)) => {
+            return run_stats(stats, &find_codex_home()?).await;
+        }
+        Some(ComputexCommand::Timelapse(timelapse)) => {
+            return run_timelapse(timelapse, &find_codex_home()?).await;
+        }
+        Some(ComputexCommand::Decrypt(decrypt)) => {
+            return run_decrypt(decrypt);
+        }
+        Some(ComputexCommand::BrowserBridge(_)) => {
+            let codex_home = find_codex_home()?;
+            return tokio::task::spawn_blocking(move || {
+                computer_use_browser_bridge::serve_native_host(&codex_home)
+            })
+            .await?;
+        }
+        Some(ComputexCommand::UnlockSecret(command)) => {
+            return run_unlock_secret(command);
+        }
+        Some(ComputexCommand::RunScript(run)) => {
+            let computer_use = resolve_computer_use_config(&interactive).await?;
+            return tokio::task::spawn_blocking(move || run_script(run, &computer_use)).await?;
+        }
+        Some(ComputexCommand::Exec(exec_cli)) => {
+            return run_exec(*exec_cli, interactive, enable_gui, codex_linux_sandbox_exe).await;
+        }
+        Some(ComputexCommand::Schedule(schedule)) => {
+            let defaults = JobDefaults {
+                gui: enable_gui,
+                profile: interactive.config_profile.clone(),
+                cwd: std::env::current_dir()?,
+            };
+            return run_schedule_command(schedule, &find_codex_home()?, defaults).await;
+        }
+        Some(ComputexCommand::Serve(serve)) => {
+            let computer_use = resolve_computer_use_config(&interactive).await?;
+            let defaults = JobDefaults {
+                gui: enable_gui,
+                profile: interactive.config_profile.clone(),
+                cwd: std::env::current_dir()?,
+            };
+            let codex_home = find_codex_home()?;
+            return tokio::task::spawn_blocking(move || {
+                run_serve(serve, &codex_home, defaults, computer_use)
+            })
+            .await?;
+        }
+        Some(ComputexCommand::Grpc(grpc)) => {
+            let computer_use = resolve_computer_use_config(&interactive).await?;
+            let (token, generated) = serve_token();
+            eprintln!("computex gRPC listening on {}", grpc.listen);
+            if generated {
+                eprintln!("bearer token: {token} (set {TOKEN_ENV_VAR} to choose one)");
+            }
+            let options = codex_app_server::GrpcServerOptions {
+                listen: grpc.listen,
+                token,
+                computer_use_instructions: computer_use_prompt(&computer_use, enable_gui),
+            };
+            codex_app_server::run_grpc_main(
+                codex_linux_sandbox_exe,
+                interactive.config_overrides,
+                options,
+            )
+            .await?;
+            return Ok(());
+        }
+        None => {}
+    }
+    let mut computer_use = resolve_computer_use_config(&interactive).await?;
+    if enable_gui && !computer_use.setup_completed && std::io::stdin().is_terminal() {
+        run_first_run_setup(&find_codex_home()?, &computer_use).await?;
+        computer_use = resolve_computer_use_config(&interactive).await?;
+    }
+    let profile = interactive.config_profile.clone();
+    let _tunnel = attach_remote_target(
+        &mut computer_use,
+        &mut interactive.config_overrides,
+        profile.as_deref(),
+    )?;
+    let _virtual_output = attach_virtual_output(
+        &mut computer_use,
+        &mut interactive.config_overrides,
+        profile.as_deref(),
+    )?;
+    if enable_gui {
+        orient_canvas(
+            &mut computer_use,
+            &mut interactive.config_overrides,
+            profile.as_deref(),
+        );
+    }
+    apply_computer_use_instructions(&mut interactive, &computer_use, enable_gui);
+    start_preview_if_enabled(&computer_use)?;
 
     let exit_info = run_interactive_tui(interactive, codex_linux_sandbox_exe).await?;
     handle_app_exit(exit_info)?;
@@ -110,12 +609,16 @@ async fn cli_main(codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()
 fn prepare_interactive(
     mut config_overrides: CliConfigOverrides,
     feature_toggles: FeatureToggles,
+    computer_use_flags: ComputerUseFlags,
     mut interactive: TuiCli,
     gui: bool,
     headless: bool,
 ) -> anyhow::Result<TuiCli> {
     let toggle_overrides = feature_toggles.to_overrides()?;
     config_overrides.raw_overrides.extend(toggle_overrides);
+    config_overrides
+        .raw_overrides
+        .extend(computer_use_flags.to_overrides(interactive.config_profile.as_deref()));
 
     interactive.config_overrides = config_overrides;
     let enable_gui = gui && !headless;
@@ -123,11 +626,488 @@ fn prepare_interactive(
         .config_overrides
         .raw_overrides
         .push(format!("features.computer_use_gui={enable_gui}"));
-    interactive.base_instructions_override = Some(COMPUTER_USE_PROMPT.to_string());
 
     Ok(interactive)
 }
 
+async fn run_calibrate(
+    command: CalibrateCommand,
+    computer_use: ComputerUseConfig,
+    codex_home: &Path,
+) -> anyhow::Result<()> {
+    if command.reset {
+        ConfigEditsBuilder::new(codex_home)
+            .set_computer_use_calibration(None)
+            .apply()
+            .await?;
+        println!("Removed the coordinate calibration.");
+        return Ok(());
+    }
+    println!(
+        "A test pattern covers the screen for a few seconds while it is clicked; leave the mouse alone."
+    );
+    let report =
+        tokio::task::spawn_blocking(move || computer_use_calibration::calibrate(&computer_use))
+            .await??;
+    for sample in &report.samples {
+        let (x, y) = sample.actual;
+        let (sent_x, sent_y) = sample.predicted;
+        let (landed_x, landed_y) = sample.observed;
+        println!(
+            "{:>8} marker at {x:.0},{y:.0}: click sent to {sent_x:.0},{sent_y:.0} landed at {landed_x:.0},{landed_y:.0}",
+            sample.name
+        );
+    }
+    ConfigEditsBuilder::new(codex_home)
+        .set_computer_use_calibration(report.calibration.as_ref())
+        .apply()
+        .await?;
+    match &report.calibration {
+        Some(calibration) => println!(
+            "Clicks missed by up to {:.1} px; with the stored correction (x * {:.4} {:+.1}, y * {:.4} {:+.1} on {}) they miss by {:.1} px.",
+            report.max_error_before,
+            calibration.scale_x,
+            calibration.offset_x,
+            calibration.scale_y,
+            calibration.offset_y,
+            calibration.screen,
+            report.max_error_after
+        ),
+        None => println!(
+            "Clicks land within {:.1} px of their targets; no correction is needed.",
+            report.max_error_before
+        ),
+    }
+    Ok(())
+}
+
+fn run_rollback(
+    command: RollbackCommand,
+    computer_use: &ComputerUseConfig,
+    codex_home: &Path,
+) -> anyhow::Result<()> {
+    let Some(vm) = &computer_use.vm else {
+        anyhow::bail!(
+            "no VM configured; set computer_use.vm_provider and computer_use.vm_name in config.toml"
+        );
+    };
+    if command.list {
+        let checkpoints = computer_use_snapshots::list_checkpoints(vm, codex_home)?;
+        if checkpoints.is_empty() {
+            println!("No computex checkpoints recorded for {}.", vm.name);
+        }
+        for checkpoint in checkpoints {
+            let created = checkpoint.created_at.format("%Y-%m-%d %H:%M:%S UTC");
+            println!("{}  {created}  {}", checkpoint.name, checkpoint.label);
+        }
+        return Ok(());
+    }
+    let checkpoint =
+        computer_use_snapshots::rollback(vm, codex_home, command.checkpoint.as_deref())?;
+    println!(
+        "Restored {} to {} ({}).",
+        vm.name, checkpoint.name, checkpoint.label
+    );
+    Ok(())
+}
+
+async fn run_setup(
+    command: SetupCommand,
+    computer_use: &ComputerUseConfig,
+    codex_home: &Path,
+) -> anyhow::Result<()> {
+    // Install first, so the wizard's backend check can pick the helper up.
+    match install_input_helper(codex_home, command.helper.as_deref()) {
+        Ok(path) => println!("Installed the input helper to {}.\n", path.display()),
+        Err(err) if command.helper.is_some() => return Err(err),
+        Err(err) => eprintln!("Skipped the bundled input helper: {err:#}\n"),
+    }
+    if computer_use.browser_bridge {
+        match install_browser_bridge(codex_home) {
+            Ok(installed) => print_browser_bridge(&installed),
+            Err(err) => eprintln!("Skipped the browser bridge: {err:#}\n"),
+        }
+    }
+    if std::io::stdin().is_terminal() {
+        run_first_run_setup(codex_home, computer_use).await?;
+    }
+    Ok(())
+}
+
+fn print_browser_bridge(installed: &computer_use_browser_bridge::Installed) {
+    println!(
+        "Unpacked the browser extension to {}.",
+        installed.extension_dir.display()
+    );
+    if installed.host_manifests.is_empty() {
+        println!(
+            "No Chromium or Firefox profile was found; rerun `computex setup` after starting the browser once."
+        );
+    }
+    for manifest in &installed.host_manifests {
+        println!("Registered the native host in {}.", manifest.display());
+    }
+    println!(
+        "Load the extension once: in Chromium, enable Developer mode on chrome://extensions and \
+         choose \"Load unpacked\"; in Firefox, use \"Load Temporary Add-on\" on about:debugging.\n"
+    );
+}
+
+fn run_decrypt(command: DecryptCommand) -> anyhow::Result<()> {
+    let output = match command.output {
+        Some(output) => output,
+        None if command.file.extension().and_then(|ext| ext.to_str())
+            == Some(computer_use_encryption::ENCRYPTED_EXTENSION) =>
+        {
+            command.file.with_extension("")
+        }
+        None => anyhow::bail!(
+            "{} has no .{} extension; name the output with --output",
+            command.file.display(),
+            computer_use_encryption::ENCRYPTED_EXTENSION
+        ),
+    };
+    let data = std::fs::read(&command.file)?;
+    if !computer_use_encryption::is_encrypted(&data) {
+        anyhow::bail!("{} is not an encrypted capture", command.file.display());
+    }
+    let image = computer_use_encryption::decrypt(&data)?;
+    std::fs::write(&output, image)?;
+    println!("Wrote {}.", output.display());
+    Ok(())
+}
+
+fn run_unlock_secret(command: UnlockSecretCommand) -> anyhow::Result<()> {
+    match command {
+        UnlockSecretCommand::Set => {
+            let secret = read_secret()?;
+            if secret.is_empty() {
+                anyhow::bail!("no secret given; nothing stored");
+            }
+            computer_use_unlock::save_secret(&secret)?;
+            println!(
+                "Stored the unlock secret. Set computer_use.unlock_helper = true to let the agent use it."
+            );
+        }
+        UnlockSecretCommand::Clear => {
+            if computer_use_unlock::delete_secret()? {
+                println!("Removed the unlock secret.");
+            } else {
+                println!("No unlock secret was stored.");
+            }
+        }
+        UnlockSecretCommand::Status => {
+            if computer_use_unlock::has_secret()? {
+                println!("An unlock secret is stored.");
+            } else {
+                println!("No unlock secret is stored.");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reads one line from stdin, turning off echo while a terminal types it.
+fn read_secret() -> anyhow::Result<String> {
+    let interactive = std::io::stdin().is_terminal();
+    if interactive {
+        eprint!("Unlock secret: ");
+        set_terminal_echo(false);
+    }
+    let mut line = String::new();
+    let read = std::io::stdin().lock().read_line(&mut line);
+    if interactive {
+        set_terminal_echo(true);
+        eprintln!();
+    }
+    read?;
+    Ok(line.trim_end_matches(['\n', '\r']).to_string())
+}
+
+fn set_terminal_echo(on: bool) {
+    let _ = std::process::Command::new("stty")
+        .arg(if on { "echo" } else { "-echo" })
+        .stdin(std::process::Stdio::inherit())
+        .status();
+}
+
+/// Resolves a session id or rollout file path to the rollout file.
+async fn session_path(codex_home: &Path, session: &str) -> anyhow::Result<PathBuf> {
+    if Path::new(session).is_file() {
+        return Ok(PathBuf::from(session));
+    }
+    find_conversation_path_by_id_str(codex_home, session)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("no recorded session `{session}`"))
+}
+
+async fn run_export_script(
+    command: ExportScriptCommand,
+    computer_use: &ComputerUseConfig,
+    codex_home: &Path,
+) -> anyhow::Result<()> {
+    let path = session_path(codex_home, &command.session).await?;
+    let history = RolloutRecorder::get_rollout_history(&path).await?;
+    let exported = computer_use_export::export_script(
+        &history.get_rollout_items(),
+        command.format,
+        computer_use,
+    );
+    for skipped in &exported.skipped {
+        eprintln!("skipped {skipped}");
+    }
+    match &command.output {
+        Some(output) => {
+            std::fs::write(output, &exported.script)?;
+            eprintln!(
+                "Wrote {} GUI action(s) to {}.",
+                exported.actions,
+                output.display()
+            );
+        }
+        None => print!("{}", exported.script),
+    }
+    Ok(())
+}
+
+async fn run_stats(command: StatsCommand, codex_home: &Path) -> anyhow::Result<()> {
+    let paths = if command.sessions.is_empty() {
+        RolloutRecorder::list_conversations(codex_home, command.last, None, &[], None, "")
+            .await?
+            .items
+            .into_iter()
+            .map(|item| item.path)
+            .collect()
+    } else {
+        let mut paths = Vec::new();
+        for session in &command.sessions {
+            paths.push(session_path(codex_home, session).await?);
+        }
+        paths
+    };
+    let mut stats = computer_use_stats::GuiStats::default();
+    for path in &paths {
+        match RolloutRecorder::get_rollout_history(path).await {
+            Ok(history) => stats.add_session(&history.get_rollout_items()),
+            Err(err) => eprintln!("skipped {}: {err}", path.display()),
+        }
+    }
+    print!("{}", stats.report());
+    Ok(())
+}
+
+async fn run_timelapse(command: TimelapseCommand, codex_home: &Path) -> anyhow::Result<()> {
+    let format = TimelapseFormat::for_path(&command.output).ok_or_else(|| {
+        anyhow::anyhow!(
+            "{}: the output must end in .gif or .webm",
+            command.output.display()
+        )
+    })?;
+    if !command.seconds_per_frame.is_finite() || command.seconds_per_frame <= 0.0 {
+        anyhow::bail!("--seconds-per-frame must be positive");
+    }
+    let path = session_path(codex_home, &command.session).await?;
+    let rollout = std::fs::read_to_string(&path)?;
+    let frames = computer_use_timelapse::collect_frames(&rollout);
+    let count = frames.len();
+    let output = command.output.clone();
+    tokio::task::spawn_blocking(move || {
+        computer_use_timelapse::render(&frames, format, command.seconds_per_frame, &output)
+    })
+    .await??;
+    eprintln!(
+        "Wrote {count} screenshot(s) to {}.",
+        command.output.display()
+    );
+    Ok(())
+}
+
+fn run_script(command: RunScriptCommand, computer_use: &ComputerUseConfig) -> anyhow::Result<()> {
+    let text = std::fs::read_to_string(&command.file)?;
+    let syntax = command
+        .syntax
+        .unwrap_or_else(|| ScriptSyntax::for_path(&command.file));
+    let script = ActionScript::parse(&text, syntax, computer_use.coordinate_space)
+        .map_err(|err| anyhow::anyhow!("{}: {err}", command.file.display()))?;
+    if command.dry_run {
+        print!("{}", script.to_native());
+        return Ok(());
+    }
+    let mut audit = ScriptAudit::open(&find_codex_home()?, &command.file)?;
+    let ran = computer_use_script::run_script(
+        &script,
+        computer_use,
+        command.allow_destructive_keys,
+        &mut audit,
+        |line| println!("{line}"),
+    )
+    .map_err(|err| anyhow::anyhow!("{err}"))?;
+    eprintln!("Ran {ran} action(s) from {}.", command.file.display());
+    Ok(())
+}
+
+/// Runs `computex exec`. Root flags (`-c`, `--gui`, feature toggles, ...) are
+/// applied before the subcommand's own overrides so the latter win.
+/// Starts the live preview when `computer_use.preview_listen` is set and
+/// prints where to open it.
+fn start_preview_if_enabled(computer_use: &ComputerUseConfig) -> anyhow::Result<()> {
+    let Some(listen) = computer_use.preview_listen else {
+        return Ok(());
+    };
+    let url = start_preview(listen, computer_use.clone())?;
+    eprintln!("Live preview (click the picture to pause GUI input): {url}");
+    if !listen.ip().is_loopback() {
+        eprintln!(
+            "warning: {listen} is reachable from other hosts; anyone with the URL can watch the display"
+        );
+    }
+    Ok(())
+}
+
+/// Forwards the display of `computer_use.target` over SSH and points
+/// `computer_use.display` at the local end. The tunnel closes when the
+/// returned guard is dropped at exit.
+fn attach_remote_target(
+    computer_use: &mut ComputerUseConfig,
+    overrides: &mut CliConfigOverrides,
+    config_profile: Option<&str>,
+) -> anyhow::Result<Option<Tunnel>> {
+    let Some(target) = &computer_use.target else {
+        return Ok(None);
+    };
+    let tunnel = Tunnel::open(target)?;
+    eprintln!(
+        "Driving {target} through display {}; shell commands run there too.",
+        tunnel.display()
+    );
+    let value = toml::Value::String(tunnel.display().to_string());
+    overrides.raw_overrides.push(format!(
+        "{}.display={value}",
+        computer_use_prefix(config_profile)
+    ));
+    computer_use.display = Some(tunnel.display().to_string());
+    Ok(Some(tunnel))
+}
+
+/// Adds the virtual output when `computer_use.virtual_output` is set and
+/// points `computer_use.viewport` at it. The output is removed when the
+/// returned guard is dropped at exit.
+fn attach_virtual_output(
+    computer_use: &mut ComputerUseConfig,
+    overrides: &mut CliConfigOverrides,
+    config_profile: Option<&str>,
+) -> anyhow::Result<Option<VirtualOutput>> {
+    if !computer_use.virtual_output {
+        return Ok(None);
+    }
+    let output = VirtualOutput::create(computer_use)?;
+    let viewport = output.viewport();
+    eprintln!(
+        "Added virtual output {} at {viewport}; GUI tools stay on it.",
+        output.name()
+    );
+    let value = toml::Value::String(viewport.to_string());
+    overrides.raw_overrides.push(format!(
+        "{}.viewport={value}",
+        computer_use_prefix(config_profile)
+    ));
+    computer_use.viewport = Some(viewport);
+    Ok(Some(output))
+}
+
+/// Turns `computer_use.resolution` to the screen's orientation, so a
+/// portrait monitor isn't squeezed into a landscape canvas.
+fn orient_canvas(
+    computer_use: &mut ComputerUseConfig,
+    overrides: &mut CliConfigOverrides,
+    config_profile: Option<&str>,
+) {
+    let Some(canvas) = computer_use_rotation::oriented_canvas(computer_use) else {
+        return;
+    };
+    eprintln!(
+        "The screen is in portrait orientation; using a {canvas} canvas for GUI coordinates."
+    );
+    let value = toml::Value::String(canvas.to_string());
+    overrides.raw_overrides.push(format!(
+        "{}.resolution={value}",
+        computer_use_prefix(config_profile)
+    ));
+    computer_use.resolution = canvas;
+}
+
+async fn run_exec(
+    mut exec_cli: ExecCli,
+    interactive: TuiCli,
+    enable_gui: bool,
+    codex_linux_sandbox_exe: Option<PathBuf>,
+) -> anyhow::Result<()> {
+    exec_cli
+        .config_overrides
+        .raw_overrides
+        .splice(0..0, interactive.config_overrides.raw_overrides);
+    if exec_cli.config_profile.is_none() {
+        exec_cli.config_profile = interactive.config_profile;
+    }
+    let (codex_home, config_cwd, cli_kv_overrides) =
+        config_load_inputs(&exec_cli.config_overrides, exec_cli.cwd.as_deref())?;
+    let mut computer_use = load_computer_use_config(
+        &codex_home,
+        &config_cwd,
+        cli_kv_overrides,
+        exec_cli.config_profile.clone(),
+    )
+    .await?;
+    let _tunnel = attach_remote_target(
+        &mut computer_use,
+        &mut exec_cli.config_overrides,
+        exec_cli.config_profile.as_deref(),
+    )?;
+    let _virtual_output = attach_virtual_output(
+        &mut computer_use,
+        &mut exec_cli.config_overrides,
+        exec_cli.config_profile.as_deref(),
+    )?;
+    if enable_gui {
+        orient_canvas(
+            &mut computer_use,
+            &mut exec_cli.config_overrides,
+            exec_cli.config_profile.as_deref(),
+        );
+    }
+    start_preview_if_enabled(&computer_use)?;
+    let harness_overrides = codex_exec::HarnessOverrides {
+        base_instructions: Some(computer_use_prompt(&computer_use, enable_gui)),
+        ..Default::default()
+    };
+    codex_exec::run_main_with_harness_overrides(
+        exec_cli,
+        codex_linux_sandbox_exe,
+        harness_overrides,
+    )
+    .await
+}
+
+fn apply_computer_use_instructions(
+    interactive: &mut TuiCli,
+    computer_use: &ComputerUseConfig,
+    gui_enabled: bool,
+) {
+    interactive.base_instructions_override = Some(computer_use_prompt(computer_use, gui_enabled));
+}
+
+async fn resolve_computer_use_config(cli: &TuiCli) -> std::io::Result<ComputerUseConfig> {
+    let (codex_home, config_cwd, cli_kv_overrides) =
+        config_load_inputs(&cli.config_overrides, cli.cwd.as_deref())?;
+    load_computer_use_config(
+        &codex_home,
+        &config_cwd,
+        cli_kv_overrides,
+        cli.config_profile.clone(),
+    )
+    .await
+}
+
 async fn run_interactive_tui(
     interactive: TuiCli,
     codex_linux_sandbox_exe: Option<PathBuf>,
@@ -141,38 +1121,51 @@ async fn run_interactive_tui(
 }
 
 async fn is_tui2_enabled(cli: &TuiCli) -> std::io::Result<bool> {
-    let raw_overrides = cli.config_overrides.raw_overrides.clone();
-    let overrides_cli = codex_common::CliConfigOverrides { raw_overrides };
-    let cli_kv_overrides = overrides_cli
+    let (codex_home, config_cwd, cli_kv_overrides) =
+        config_load_inputs(&cli.config_overrides, cli.cwd.as_deref())?;
+    let config_toml =
+        load_config_as_toml_with_cli_overrides(&codex_home, &config_cwd, cli_kv_overrides).await?;
+    let config_profile = config_toml.get_config_profile(cli.config_profile.clone())?;
+    let overrides = FeatureOverrides::default();
+    let features = Features::from_config(&config_toml, &config_profile, overrides);
+    Ok(features.enabled(Feature::Tui2))
+}
+
+/// `$CODEX_HOME`, config cwd, and parsed `-c` overrides for a lightweight config load.
+type ConfigLoadInputs = (PathBuf, AbsolutePathBuf, Vec<(String, toml::Value)>);
+
+fn config_load_inputs(
+    config_overrides: &CliConfigOverrides,
+    cwd: Option<&Path>,
+) -> std::io::Result<ConfigLoadInputs> {
+    let cli_kv_overrides = config_overrides
         .parse_overrides()
         .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
 
     let codex_home = find_codex_home()?;
-    let cwd = cli.cwd.clone();
-    let config_cwd = match cwd.as_deref() {
+    let config_cwd = match cwd {
         Some(path) => AbsolutePathBuf::from_absolute_path(path)?,
         None => AbsolutePathBuf::current_dir()?,
     };
-    let config_toml =
-        load_config_as_toml_with_cli_overrides(&codex_home, &config_cwd, cli_kv_overrides).await?;
-    let config_profile = config_toml.get_config_profile(cli.config_profile.clone())?;
-    let overrides = FeatureOverrides::default();
-    let features = Features::from_config(&config_toml, &config_profile, overrides);
-    Ok(features.enabled(Feature::Tui2))
+    Ok((codex_home, config_cwd, cli_kv_overrides))
 }
 
 fn format_exit_messages(exit_info: AppExitInfo, color_enabled: bool) -> Vec<String> {
     let AppExitInfo {
         token_usage,
+        gui_usage,
         conversation_id,
         ..
     } = exit_info;
 
-    if token_usage.is_zero() {
+    if token_usage.is_zero() && gui_usage.is_zero() {
         return Vec::new();
     }
 
-    let mut lines = vec![format!("{}", FinalOutput::from(token_usage))];
+    let mut lines = vec![format!(
+        "{}",
+        FinalOutput::from(token_usage).with_gui_usage(gui_usage)
+    )];
 
     if let Some(session_id) = conversation_id {
         let resume_cmd = format!("codex resume {session_id}");
@@ -228,22 +1221,27 @@ fn run_update_action(action: UpdateAction) -> anyhow::Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use codex_core::protocol::GuiUsage;
+    use codex_core::protocol::TokenUsage;
     use pretty_assertions::assert_eq;
 
     #[test]
     fn computex_gui_sets_instructions_and_flag() -> anyhow::Result<()> {
         let cli = ComputexCli::parse_from(["computex", "--gui", "hello"]);
-        let interactive = prepare_interactive(
+        let mut interactive = prepare_interactive(
             cli.config_overrides,
             cli.feature_toggles,
+            cli.computer_use_flags,
             cli.interactive,
             cli.gui,
             cli.headless,
         )?;
+        let computer_use = ComputerUseConfig::default();
+        apply_computer_use_instructions(&mut interactive, &computer_use, true);
 
         assert_eq!(
-            interactive.base_instructions_override.as_deref(),
-            Some(COMPUTER_USE_PROMPT)
+            interactive.base_instructions_override,
+            Some(computer_use_prompt(&computer_use, true))
         );
         assert!(
             interactive
@@ -258,14 +1256,22 @@ mod tests {
     #[test]
     fn computex_headless_disables_gui_tools() -> anyhow::Result<()> {
         let cli = ComputexCli::parse_from(["computex", "--headless", "hello"]);
-        let interactive = prepare_interactive(
+        let mut interactive = prepare_interactive(
             cli.config_overrides,
             cli.feature_toggles,
+            cli.computer_use_flags,
             cli.interactive,
             cli.gui,
             cli.headless,
         )?;
+        apply_computer_use_instructions(&mut interactive, &ComputerUseConfig::default(), false);
 
+        assert!(
+            interactive
+                .base_instructions_override
+                .as_deref()
+                .is_some_and(|prompt| !prompt.contains("`computer_click`"))
+        );
         assert!(
             interactive
                 .config_overrides
@@ -275,4 +1281,312 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn computex_computer_use_flags_map_to_overrides() -> anyhow::Result<()> {
+        let cli = ComputexCli::parse_from([
+            "computex",
+            "--gui",
+            "--display",
+            ":1",
+            "--target",
+            "ssh://me@lab:2222/:1",
+            "--resolution",
+            "1920x1080",
+            "--backend",
+            "x11",
+            "--qmp-socket",
+            "/run/vm/qmp.sock",
+            "--console",
+            "serial:/dev/ttyUSB0@9600",
+            "--keyboard-only",
+            "--preview",
+            "0.0.0.0:8790",
+            "--virtual-output",
+            "--ocr-scrub",
+            "email,card_number",
+        ]);
+        let interactive = prepare_interactive(
+            cli.config_overrides,
+            cli.feature_toggles,
+            cli.computer_use_flags,
+            cli.interactive,
+            cli.gui,
+            cli.headless,
+        )?;
+
+        let overrides = &interactive.config_overrides.raw_overrides;
+        for expected in [
+            "computer_use.display=\":1\"",
+            "computer_use.target=\"ssh://me@lab:2222/:1\"",
+            "computer_use.resolution=\"1920x1080\"",
+            "computer_use.backend=\"x11\"",
+            "computer_use.qmp_socket=\"/run/vm/qmp.sock\"",
+            "computer_use.console=\"serial:/dev/ttyUSB0@9600\"",
+            "computer_use.keyboard_only=true",
+            "computer_use.preview_listen=\"0.0.0.0:8790\"",
+            "computer_use.virtual_output=true",
+            "computer_use.ocr_scrubbers=[\"email\", \"card_number\"]",
+        ] {
+            assert!(
+                overrides.iter().any(|value| value == expected),
+                "missing {expected} in {overrides:?}"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn computex_parses_export_script_subcommand() {
+        let cli = ComputexCli::parse_from([
+            "computex",
+            "export-script",
+            "0199a213-81c0-7800-8aa1-bbab2a035a53",
+            "--format",
+            "playwright",
+            "-o",
+            "replay.py",
+        ]);
+        let Some(ComputexCommand::ExportScript(export)) = cli.command else {
+            panic!("expected export-script");
+        };
+        assert_eq!(export.format, ScriptFormat::Playwright);
+        assert_eq!(export.output, Some(PathBuf::from("replay.py")));
+
+        let cli = ComputexCli::parse_from(["computex", "export-script", "session.jsonl"]);
+        let Some(ComputexCommand::ExportScript(export)) = cli.command else {
+            panic!("expected export-script");
+        };
+        assert_eq!(export.format, ScriptFormat::Pyautogui);
+        assert!(
+            ComputexCli::try_parse_from(["computex", "export-script", "x", "--format", "selenium"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn computex_parses_timelapse_subcommand() {
+        let cli = ComputexCli::parse_from([
+            "computex",
+            "timelapse",
+            "0199a213-81c0-7800-8aa1-bbab2a035a53",
+            "-o",
+            "bug.webm",
+            "--seconds-per-frame",
+            "0.5",
+        ]);
+        let Some(ComputexCommand::Timelapse(timelapse)) = cli.command else {
+            panic!("expected timelapse");
+        };
+        assert_eq!(timelapse.output, PathBuf::from("bug.webm"));
+        assert_eq!(timelapse.seconds_per_frame, 0.5);
+
+        let cli = ComputexCli::parse_from(["computex", "timelapse", "session.jsonl"]);
+        let Some(ComputexCommand::Timelapse(timelapse)) = cli.command else {
+            panic!("expected timelapse");
+        };
+        assert_eq!(timelapse.output, PathBuf::from("timelapse.gif"));
+        assert_eq!(timelapse.seconds_per_frame, 1.0);
+    }
+
+    #[test]
+    fn computex_parses_decrypt_subcommand() {
+        let cli = ComputexCli::parse_from(["computex", "decrypt", "shots/1-0001.png.enc"]);
+        let Some(ComputexCommand::Decrypt(decrypt)) = cli.command else {
+            panic!("expected decrypt");
+        };
+        assert_eq!(decrypt.file, PathBuf::from("shots/1-0001.png.enc"));
+        assert_eq!(decrypt.output, None);
+    }
+
+    #[test]
+    fn computex_parses_run_script_subcommand() {
+        let cli = ComputexCli::parse_from(["computex", "run-script", "login.ahk", "--dry-run"]);
+        let Some(ComputexCommand::RunScript(run)) = cli.command else {
+            panic!("expected run-script");
+        };
+        assert_eq!(run.file, PathBuf::from("login.ahk"));
+        assert_eq!(run.syntax, None);
+        assert!(run.dry_run);
+        assert!(!run.allow_destructive_keys);
+
+        let cli = ComputexCli::parse_from([
+            "computex",
+            "run-script",
+            "steps.txt",
+            "--syntax",
+            "pyautogui",
+            "--allow-destructive-keys",
+        ]);
+        let Some(ComputexCommand::RunScript(run)) = cli.command else {
+            panic!("expected run-script");
+        };
+        assert_eq!(run.syntax, Some(ScriptSyntax::Pyautogui));
+        assert!(run.allow_destructive_keys);
+    }
+
+    #[test]
+    fn computex_parses_rollback_subcommand() {
+        let cli = ComputexCli::parse_from(["computex", "rollback", "--list"]);
+        assert!(matches!(
+            cli.command,
+            Some(ComputexCommand::Rollback(RollbackCommand {
+                checkpoint: None,
+                list: true,
+            }))
+        ));
+
+        let cli = ComputexCli::parse_from(["computex", "rollback", "computex-20260101-120000"]);
+        let Some(ComputexCommand::Rollback(rollback)) = cli.command else {
+            panic!("expected rollback");
+        };
+        assert_eq!(
+            rollback.checkpoint.as_deref(),
+            Some("computex-20260101-120000")
+        );
+
+        let cli = ComputexCli::parse_from(["computex", "fix the build"]);
+        assert!(cli.command.is_none());
+        assert_eq!(cli.interactive.prompt.as_deref(), Some("fix the build"));
+    }
+
+    #[test]
+    fn computex_parses_scheduled_exec_invocation() {
+        // The argument shape `computex schedule run` launches for each job.
+        let cli = ComputexCli::parse_from([
+            "computex",
+            "--gui",
+            "exec",
+            "--json",
+            "--skip-git-repo-check",
+            "--output-last-message",
+            "/runs/1/last-message.md",
+            "--profile",
+            "kiosk",
+            "--",
+            "-check the dashboard",
+        ]);
+        assert!(cli.gui);
+        let Some(ComputexCommand::Exec(exec)) = cli.command else {
+            panic!("expected exec");
+        };
+        assert!(exec.json);
+        assert_eq!(exec.config_profile.as_deref(), Some("kiosk"));
+        assert_eq!(exec.prompt.as_deref(), Some("-check the dashboard"));
+
+        let cli = ComputexCli::parse_from([
+            "computex",
+            "schedule",
+            "add",
+            "--watch",
+            "inbox",
+            "summarize new files",
+        ]);
+        assert!(matches!(cli.command, Some(ComputexCommand::Schedule(_))));
+    }
+
+    #[test]
+    fn computex_computer_use_flags_target_selected_profile() {
+        let flags = ComputerUseFlags {
+            display: Some(":2".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            flags.to_overrides(Some("kiosk")),
+            vec!["profiles.kiosk.computer_use.display=\":2\"".to_string()]
+        );
+
+        let flags = ComputerUseFlags {
+            no_ocr_scrub: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            flags.to_overrides(None),
+            vec!["computer_use.ocr_scrubbers=[]".to_string()]
+        );
+    }
+
+    #[test]
+    fn computex_stats_parses_sessions_or_last() {
+        let cli = ComputexCli::parse_from(["computex", "stats", "--last", "5"]);
+        let Some(ComputexCommand::Stats(stats)) = cli.command else {
+            panic!("expected stats");
+        };
+        assert_eq!(stats.last, 5);
+        assert!(stats.sessions.is_empty());
+        assert!(ComputexCli::try_parse_from(["computex", "stats", "abc", "--last", "5"]).is_err());
+    }
+
+    #[test]
+    fn computex_setup_parses_helper() {
+        let cli =
+            ComputexCli::parse_from(["computex", "setup", "--helper", "/tmp/computex-inject"]);
+        let Some(ComputexCommand::Setup(setup)) = cli.command else {
+            panic!("expected setup");
+        };
+        assert_eq!(setup.helper, Some(PathBuf::from("/tmp/computex-inject")));
+        let cli = ComputexCli::parse_from(["computex", "setup"]);
+        assert!(matches!(
+            cli.command,
+            Some(ComputexCommand::Setup(SetupCommand { helper: None }))
+        ));
+    }
+
+    #[test]
+    fn computex_calibrate_parses_reset() {
+        let cli = ComputexCli::parse_from(["computex", "calibrate", "--reset"]);
+        assert!(matches!(
+            cli.command,
+            Some(ComputexCommand::Calibrate(CalibrateCommand { reset: true }))
+        ));
+        let cli = ComputexCli::parse_from(["computex", "calibrate"]);
+        assert!(matches!(
+            cli.command,
+            Some(ComputexCommand::Calibrate(CalibrateCommand {
+                reset: false
+            }))
+        ));
+    }
+
+    #[test]
+    fn computex_unlock_secret_parses_actions() {
+        let cli = ComputexCli::parse_from(["computex", "unlock-secret", "set"]);
+        assert!(matches!(
+            cli.command,
+            Some(ComputexCommand::UnlockSecret(UnlockSecretCommand::Set))
+        ));
+        assert!(ComputexCli::try_parse_from(["computex", "unlock-secret"]).is_err());
+    }
+
+    #[test]
+    fn exit_messages_include_gui_usage() {
+        let exit_info = AppExitInfo {
+            token_usage: TokenUsage {
+                output_tokens: 2,
+                total_tokens: 2,
+                ..Default::default()
+            },
+            gui_usage: GuiUsage {
+                actions: 14,
+                screenshots: 3,
+                image_bytes: 3 * 1024 * 1024,
+                automation_ms: 75_000,
+            },
+            conversation_id: None,
+            update_action: None,
+        };
+        assert_eq!(
+            format_exit_messages(exit_info, false),
+            vec![
+                "Token usage: total=2 input=0 output=2\nGUI automation: actions=14 screenshots=3 images=3.0 MB time=1m 15s"
+                    .to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn computex_rejects_unknown_backend() {
+        let result = ComputexCli::try_parse_from(["computex", "--backend", "wayland"]);
+        assert!(result.is_err());
+    }
 }
@@ -0,0 +1,137 @@
+//! Live preview of the automated desktop for `computer_use.preview_listen`.
+//!
+//! A supervisor on another machine opens the printed URL in a browser to
+//! watch the display while the agent works, and clicks the picture (or the
+//! button) to pause or resume GUI input. Frames are fresh screenshots, scaled
+//! like the ones the model sees. Every request needs the `token` query
+//! parameter from the URL.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use codex_core::computer_use_pause;
+use codex_core::config::types::ComputerUseConfig;
+use serde_json::json;
+use tiny_http::Method;
+use tiny_http::Request;
+use tiny_http::Response;
+use tiny_http::Server;
+use tiny_http::StatusCode;
+
+use crate::computer_use_serve::generate_token;
+use crate::computer_use_serve::screenshot;
+use crate::computer_use_serve::tokens_match;
+use crate::computer_use_serve::with_content_type;
+
+/// Viewer page. It asks for the next frame once the previous one is shown and
+/// polls the pause state so pauses from other viewers show up.
+const PAGE: &str = r#"<!doctype html>
+<meta charset="utf-8">
+<title>computex preview</title>
+<style>
+  body { margin: 0; background: #111; color: #eee; font: 14px sans-serif; }
+  #bar { padding: 6px 10px; display: flex; gap: 10px; align-items: center; }
+  #screen { display: block; max-width: 100%; cursor: pointer; }
+  body.paused #screen { outline: 4px solid #e5a50a; opacity: 0.6; }
+</style>
+<div id="bar"><button id="toggle">Pause</button><span id="status">connecting…</span></div>
+<img id="screen" alt="automated display" title="Click to pause or resume GUI input">
+<script>
+  const query = location.search;
+  const screen = document.getElementById("screen");
+  const toggle = document.getElementById("toggle");
+  const status = document.getElementById("status");
+  let paused = false;
+  function show(state) {
+    paused = state.paused;
+    document.body.classList.toggle("paused", paused);
+    toggle.textContent = paused ? "Resume" : "Pause";
+    status.textContent = paused ? "GUI input paused" : "agent running";
+  }
+  function next() {
+    const frame = new Image();
+    frame.onload = () => { screen.src = frame.src; setTimeout(next, 250); };
+    frame.onerror = () => { status.textContent = "no frame; retrying"; setTimeout(next, 2000); };
+    frame.src = "/frame" + query + "&t=" + Date.now();
+  }
+  function flip() {
+    fetch((paused ? "/resume" : "/pause") + query, { method: "POST" })
+      .then((response) => response.json())
+      .then(show);
+  }
+  function poll() {
+    fetch("/status" + query).then((response) => response.json()).then(show).catch(() => {});
+  }
+  toggle.onclick = flip;
+  screen.onclick = flip;
+  poll();
+  setInterval(poll, 1000);
+  next();
+</script>
+"#;
+
+/// Starts the preview server on a background thread and returns the URL to
+/// open, token included.
+pub fn start_preview(
+    listen: SocketAddr,
+    computer_use: ComputerUseConfig,
+) -> anyhow::Result<String> {
+    let server = Server::http(listen)
+        .map_err(|err| anyhow::anyhow!("failed to start the preview on {listen}: {err}"))?;
+    let token = generate_token();
+    let url = format!("http://{listen}/?token={token}");
+    let state = Arc::new((token, computer_use));
+    std::thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let state = Arc::clone(&state);
+            std::thread::spawn(move || handle_request(&state.0, &state.1, request));
+        }
+    });
+    Ok(url)
+}
+
+fn handle_request(token: &str, computer_use: &ComputerUseConfig, request: Request) {
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((url.as_str(), ""));
+    let presented = query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("token="))
+        .unwrap_or_default();
+    let result = if !tokens_match(token, presented) {
+        request.respond(Response::from_string("missing or invalid token").with_status_code(403))
+    } else {
+        let method = request.method().clone();
+        match (method, path) {
+            (Method::Get, "/") => {
+                let page = with_content_type(Response::from_string(PAGE), "text/html");
+                request.respond(page)
+            }
+            (Method::Get, "/frame") => match screenshot(computer_use) {
+                Ok(bytes) => {
+                    request.respond(with_content_type(Response::from_data(bytes), "image/png"))
+                }
+                Err(err) => request.respond(
+                    Response::from_string(format!("{err:#}")).with_status_code(StatusCode(503)),
+                ),
+            },
+            (Method::Get, "/status") => request.respond(pause_state()),
+            (Method::Post, "/pause") => {
+                computer_use_pause::set_paused(true);
+                request.respond(pause_state())
+            }
+            (Method::Post, "/resume") => {
+                computer_use_pause::set_paused(false);
+                request.respond(pause_state())
+            }
+            _ => request.respond(Response::from_string("not found").with_status_code(404)),
+        }
+    };
+    if let Err(err) = result {
+        tracing::debug!("failed to send preview response: {err}");
+    }
+}
+
+fn pause_state() -> Response<std::io::Cursor<Vec<u8>>> {
+    let body = json!({ "paused": computer_use_pause::is_paused() }).to_string();
+    with_content_type(Response::from_string(body), "application/json")
+}
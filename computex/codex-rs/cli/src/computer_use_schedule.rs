@@ -0,0 +1,810 @@
+//! Recurring automation for `computex schedule`.
+//!
+//! Jobs pair a trigger (a cron expression, a watched path, or a D-Bus match
+//! rule) with a prompt and live in `$CODEX_HOME/computer_use/schedule.json`.
+//! `computex schedule run` is a small foreground daemon: it polls the triggers
+//! and launches one headless `computex exec` per firing, writing the run's
+//! events, final message, and a `report.json` under
+//! `$CODEX_HOME/computer_use/runs/<job>/<timestamp>/`.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use anyhow::Context;
+use chrono::DateTime;
+use chrono::Datelike;
+use chrono::Local;
+use chrono::NaiveDateTime;
+use chrono::TimeZone;
+use chrono::Timelike;
+use chrono::Utc;
+use clap::Parser;
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::BufReader;
+use tokio::process::Child;
+use tokio::process::Command;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Location of the job list, relative to `$CODEX_HOME`.
+pub const SCHEDULE_FILE: &str = "computer_use/schedule.json";
+/// Per-run output directories, relative to `$CODEX_HOME`.
+pub const RUNS_DIR: &str = "computer_use/runs";
+
+/// How often the daemon re-reads the job list and checks triggers.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// How far ahead a cron expression is searched before it is considered
+/// unsatisfiable (e.g. `0 0 31 2 *`). Covers leap days.
+const CRON_SEARCH_DAYS: i64 = 5 * 366;
+
+const MONTH_NAMES: &[&str] = &[
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAY_NAMES: &[&str] = &["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+#[derive(Debug, Parser)]
+pub struct ScheduleCommand {
+    #[clap(subcommand)]
+    pub action: ScheduleAction,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum ScheduleAction {
+    /// Add a job: `"<cron> <prompt>"`, or a prompt with `--watch`/`--dbus`.
+    Add(AddJobArgs),
+    /// List scheduled jobs.
+    List,
+    /// Remove a job by id.
+    Remove {
+        #[arg(value_name = "ID")]
+        id: String,
+    },
+    /// Run the scheduler in the foreground until interrupted.
+    Run,
+}
+
+#[derive(Debug, Parser)]
+pub struct AddJobArgs {
+    /// Five cron fields (or `@hourly`, `@daily`, ...) followed by the prompt,
+    /// e.g. `"0 9 * * mon-fri check the build dashboard"`. With `--watch` or
+    /// `--dbus`, the whole argument is the prompt.
+    #[arg(value_name = "SPEC")]
+    pub spec: String,
+
+    /// Run whenever this file or directory changes instead of on a schedule.
+    #[arg(long, value_name = "PATH", conflicts_with = "dbus")]
+    pub watch: Option<PathBuf>,
+
+    /// Run whenever a session-bus signal matches this rule, e.g.
+    /// `"type='signal',interface='org.gnome.ScreenSaver'"`.
+    #[arg(long, value_name = "MATCH_RULE")]
+    pub dbus: Option<String>,
+}
+
+/// Settings from the surrounding `computex` invocation that scheduled runs
+/// inherit.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JobDefaults {
+    pub gui: bool,
+    pub profile: Option<String>,
+    pub cwd: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Trigger {
+    Cron { expression: String },
+    Watch { path: PathBuf },
+    Dbus { match_rule: String },
+}
+
+impl std::fmt::Display for Trigger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Trigger::Cron { expression } => write!(f, "cron `{expression}`"),
+            Trigger::Watch { path } => write!(f, "watch {}", path.display()),
+            Trigger::Dbus { match_rule } => write!(f, "dbus `{match_rule}`"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduledJob {
+    pub id: String,
+    pub trigger: Trigger,
+    pub prompt: String,
+    /// Whether runs get the GUI tools (`computex --gui`).
+    pub gui: bool,
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// Working directory for the run.
+    pub cwd: PathBuf,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ScheduleStore {
+    #[serde(default)]
+    jobs: Vec<ScheduledJob>,
+}
+
+/// Written to `report.json` when a run finishes.
+#[derive(Debug, Serialize)]
+pub(crate) struct RunReport<'a> {
+    pub job: &'a str,
+    pub trigger: String,
+    pub prompt: &'a str,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub exit_code: Option<i32>,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+pub async fn run_schedule_command(
+    command: ScheduleCommand,
+    codex_home: &Path,
+    defaults: JobDefaults,
+) -> anyhow::Result<()> {
+    match command.action {
+        ScheduleAction::Add(args) => {
+            let job = add_job(codex_home, args, defaults)?;
+            println!("Added job {} ({}).", job.id, job.trigger);
+            if let Trigger::Cron { expression } = &job.trigger {
+                let schedule = CronSchedule::parse(expression).map_err(anyhow::Error::msg)?;
+                if let Some(next) = schedule.next_after(Local::now()) {
+                    println!("Next run: {}", next.format("%Y-%m-%d %H:%M %Z"));
+                }
+            }
+            println!("Start the scheduler with `computex schedule run`.");
+        }
+        ScheduleAction::List => {
+            let jobs = load(&codex_home.join(SCHEDULE_FILE))?.jobs;
+            if jobs.is_empty() {
+                println!("No scheduled jobs.");
+            }
+            for job in jobs {
+                let mode = if job.gui { "gui" } else { "headless" };
+                println!("{}  {}  [{mode}]  {}", job.id, job.trigger, job.prompt);
+            }
+        }
+        ScheduleAction::Remove { id } => {
+            remove_job(codex_home, &id)?;
+            println!("Removed job {id}.");
+        }
+        ScheduleAction::Run => {
+            let exe = std::env::current_exe().context("failed to locate the computex binary")?;
+            run_daemon(codex_home, &exe).await?;
+        }
+    }
+    Ok(())
+}
+
+fn add_job(
+    codex_home: &Path,
+    args: AddJobArgs,
+    defaults: JobDefaults,
+) -> anyhow::Result<ScheduledJob> {
+    let (trigger, prompt) = match (args.watch, args.dbus) {
+        (Some(path), _) => (
+            Trigger::Watch {
+                path: defaults.cwd.join(path),
+            },
+            args.spec.trim().to_string(),
+        ),
+        (None, Some(match_rule)) => (Trigger::Dbus { match_rule }, args.spec.trim().to_string()),
+        (None, None) => {
+            let (expression, prompt) = split_cron_spec(&args.spec)?;
+            (Trigger::Cron { expression }, prompt)
+        }
+    };
+    if prompt.is_empty() {
+        anyhow::bail!("the job needs a prompt");
+    }
+    let path = codex_home.join(SCHEDULE_FILE);
+    let mut store = load(&path)?;
+    let next_id = store
+        .jobs
+        .iter()
+        .filter_map(|job| job.id.parse::<u64>().ok())
+        .max()
+        .unwrap_or(0)
+        + 1;
+    let job = ScheduledJob {
+        id: next_id.to_string(),
+        trigger,
+        prompt,
+        gui: defaults.gui,
+        profile: defaults.profile,
+        cwd: defaults.cwd,
+        created_at: Utc::now(),
+    };
+    store.jobs.push(job.clone());
+    persist(&path, &store)?;
+    Ok(job)
+}
+
+fn remove_job(codex_home: &Path, id: &str) -> anyhow::Result<()> {
+    let path = codex_home.join(SCHEDULE_FILE);
+    let mut store = load(&path)?;
+    let before = store.jobs.len();
+    store.jobs.retain(|job| job.id != id);
+    if store.jobs.len() == before {
+        anyhow::bail!("no scheduled job with id `{id}`");
+    }
+    persist(&path, &store)?;
+    Ok(())
+}
+
+/// Splits `"<cron> <prompt>"` into the cron expression and the prompt. The
+/// expression is validated but kept as written.
+fn split_cron_spec(spec: &str) -> anyhow::Result<(String, String)> {
+    let field_count = if spec.trim_start().starts_with('@') {
+        1
+    } else {
+        5
+    };
+    let mut rest = spec.trim_start();
+    let mut fields = Vec::with_capacity(field_count);
+    for _ in 0..field_count {
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        if end == 0 {
+            break;
+        }
+        fields.push(&rest[..end]);
+        rest = rest[end..].trim_start();
+    }
+    if fields.len() < field_count {
+        anyhow::bail!(
+            "expected `\"<minute> <hour> <day> <month> <weekday> <prompt>\"`, e.g. `\"0 9 * * mon-fri check the dashboard\"`"
+        );
+    }
+    let expression = fields.join(" ");
+    CronSchedule::parse(&expression)
+        .map_err(|err| anyhow::anyhow!("invalid cron expression `{expression}`: {err}"))?;
+    Ok((expression, rest.trim_end().to_string()))
+}
+
+/// A standard five-field cron expression, evaluated in local time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    /// Like cron, a job whose day-of-month and day-of-week are both
+    /// restricted runs when either matches.
+    either_day: bool,
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let expanded = match expression.trim() {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            other if other.starts_with('@') => return Err(format!("unknown macro `{other}`")),
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields.as_slice() else {
+            return Err(format!("expected 5 fields, got {}", fields.len()));
+        };
+        let mut days_of_week = parse_field(weekday, 0, 7, WEEKDAY_NAMES)?;
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week | 1) & !(1 << 7);
+        }
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59, &[])?,
+            hours: parse_field(hour, 0, 23, &[])?,
+            days_of_month: parse_field(day, 1, 31, &[])?,
+            months: parse_field(month, 1, 12, MONTH_NAMES)?,
+            days_of_week,
+            either_day: !day.starts_with('*') && !weekday.starts_with('*'),
+        })
+    }
+
+    /// The first matching minute strictly after `after`. Wall-clock minutes
+    /// skipped by a DST change never match.
+    pub fn next_after(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        let mut naive = after.naive_local();
+        loop {
+            naive = self.next_naive(naive)?;
+            if let Some(next) = Local.from_local_datetime(&naive).earliest() {
+                return Some(next);
+            }
+        }
+    }
+
+    fn next_naive(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let start = after.with_second(0)?.with_nanosecond(0)? + chrono::Duration::minutes(1);
+        let limit = start + chrono::Duration::days(CRON_SEARCH_DAYS);
+        let mut candidate = start;
+        while candidate < limit {
+            if !self.day_matches(candidate) {
+                candidate = candidate.date().succ_opt()?.and_hms_opt(0, 0, 0)?;
+                continue;
+            }
+            if !has(self.hours, candidate.hour()) {
+                candidate = candidate.with_minute(0)? + chrono::Duration::hours(1);
+                continue;
+            }
+            if has(self.minutes, candidate.minute()) {
+                return Some(candidate);
+            }
+            candidate += chrono::Duration::minutes(1);
+        }
+        None
+    }
+
+    fn day_matches(&self, at: NaiveDateTime) -> bool {
+        if !has(self.months, at.month()) {
+            return false;
+        }
+        let day = has(self.days_of_month, at.day());
+        let weekday = has(self.days_of_week, at.weekday().num_days_from_sunday());
+        if self.either_day {
+            day || weekday
+        } else {
+            day && weekday
+        }
+    }
+}
+
+fn has(bits: u64, value: u32) -> bool {
+    bits & (1 << value) != 0
+}
+
+/// Parses one cron field (`*`, `5`, `1-5`, `*/15`, `mon-fri`, lists of
+/// these) into a bit set.
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64, String> {
+    let value = |token: &str| -> Result<u32, String> {
+        let lower = token.to_ascii_lowercase();
+        let parsed = match names.iter().position(|name| *name == lower) {
+            Some(index) => min + index as u32,
+            None => token
+                .parse::<u32>()
+                .map_err(|_| format!("`{token}` is not a number"))?,
+        };
+        if parsed < min || parsed > max {
+            return Err(format!("`{token}` is outside {min}-{max}"));
+        }
+        Ok(parsed)
+    };
+    let mut bits = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step = step
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| format!("invalid step in `{part}`"))?;
+                (range, Some(step))
+            }
+            None => (part, None),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (value(start)?, value(end)?),
+                None if step.is_some() => (value(range)?, max),
+                None => {
+                    let single = value(range)?;
+                    (single, single)
+                }
+            },
+        };
+        if start > end {
+            return Err(format!("`{range}` is an empty range"));
+        }
+        for selected in (start..=end).step_by(step.unwrap_or(1) as usize) {
+            bits |= 1 << selected;
+        }
+    }
+    Ok(bits)
+}
+
+/// Trigger bookkeeping the daemon keeps per job.
+#[derive(Default)]
+struct JobState {
+    next_due: Option<DateTime<Local>>,
+    /// Last seen modification time of a watched path; `None` if it was missing.
+    last_modified: Option<Option<SystemTime>>,
+    dbus: Option<DbusWatch>,
+    run: Option<JoinHandle<()>>,
+}
+
+struct DbusWatch {
+    /// Held so the monitor is killed when the job goes away.
+    _monitor: Child,
+    signals: mpsc::UnboundedReceiver<()>,
+}
+
+async fn run_daemon(codex_home: &Path, exe: &Path) -> anyhow::Result<()> {
+    let store_path = codex_home.join(SCHEDULE_FILE);
+    let mut states: HashMap<String, JobState> = HashMap::new();
+    println!(
+        "computex scheduler watching {} (Ctrl+C to stop)",
+        store_path.display()
+    );
+    loop {
+        // Re-read every tick so `schedule add`/`remove` apply without a restart.
+        let jobs = match load(&store_path) {
+            Ok(store) => store.jobs,
+            Err(err) => {
+                eprintln!("failed to read {}: {err}", store_path.display());
+                Vec::new()
+            }
+        };
+        states.retain(|id, _| jobs.iter().any(|job| &job.id == id));
+        for job in &jobs {
+            let state = states.entry(job.id.clone()).or_default();
+            if !poll_trigger(job, state) {
+                continue;
+            }
+            if state.run.as_ref().is_some_and(|run| !run.is_finished()) {
+                println!("job {}: still running, skipping this trigger", job.id);
+                continue;
+            }
+            match start_run(job, codex_home, exe) {
+                Ok(run) => state.run = Some(run),
+                Err(err) => eprintln!("job {}: failed to start run: {err:#}", job.id),
+            }
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+    Ok(())
+}
+
+/// Whether `job`'s trigger fired since the last poll. The first poll only
+/// arms the trigger.
+fn poll_trigger(job: &ScheduledJob, state: &mut JobState) -> bool {
+    match &job.trigger {
+        Trigger::Cron { expression } => {
+            let Ok(schedule) = CronSchedule::parse(expression) else {
+                return false;
+            };
+            let now = Local::now();
+            match state.next_due {
+                Some(due) if now >= due => {
+                    state.next_due = schedule.next_after(now);
+                    true
+                }
+                Some(_) => false,
+                None => {
+                    state.next_due = schedule.next_after(now);
+                    false
+                }
+            }
+        }
+        Trigger::Watch { path } => {
+            let modified = fs::metadata(path).and_then(|meta| meta.modified()).ok();
+            match state.last_modified.replace(modified) {
+                Some(previous) => previous != modified,
+                None => false,
+            }
+        }
+        Trigger::Dbus { match_rule } => {
+            if state.dbus.is_none() {
+                match spawn_dbus_monitor(match_rule) {
+                    Ok(watch) => state.dbus = Some(watch),
+                    Err(err) => {
+                        eprintln!("job {}: {err:#}", job.id);
+                        return false;
+                    }
+                }
+            }
+            let Some(watch) = state.dbus.as_mut() else {
+                return false;
+            };
+            let mut fired = false;
+            while watch.signals.try_recv().is_ok() {
+                fired = true;
+            }
+            fired
+        }
+    }
+}
+
+fn spawn_dbus_monitor(match_rule: &str) -> anyhow::Result<DbusWatch> {
+    let program = which::which("dbus-monitor")
+        .context("dbus-monitor not found; install the dbus tools (e.g. dbus-bin)")?;
+    let mut monitor = Command::new(program)
+        .args(["--session", match_rule])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed to start dbus-monitor")?;
+    let stdout = monitor
+        .stdout
+        .take()
+        .context("dbus-monitor has no stdout")?;
+    let (tx, signals) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(stdout).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if is_matching_signal(&line) && tx.send(()).is_err() {
+                break;
+            }
+        }
+    });
+    Ok(DbusWatch {
+        _monitor: monitor,
+        signals,
+    })
+}
+
+/// dbus-monitor prints one header line per message; the monitor's own
+/// connection bookkeeping is not a trigger.
+fn is_matching_signal(line: &str) -> bool {
+    line.starts_with("signal ")
+        && !line.contains("member=NameAcquired")
+        && !line.contains("member=NameLost")
+}
+
+/// Arguments for a headless `computex exec` run writing into `run_dir`.
+/// `computex serve` launches runs the same way.
+pub(crate) fn exec_args(
+    gui: bool,
+    profile: Option<&str>,
+    prompt: &str,
+    run_dir: &Path,
+) -> Vec<String> {
+    let mut args = vec![
+        if gui { "--gui" } else { "--headless" }.to_string(),
+        "exec".to_string(),
+        "--json".to_string(),
+        "--skip-git-repo-check".to_string(),
+        "--output-last-message".to_string(),
+        run_dir.join("last-message.md").display().to_string(),
+    ];
+    if let Some(profile) = profile {
+        args.push("--profile".to_string());
+        args.push(profile.to_string());
+    }
+    args.push("--".to_string());
+    args.push(prompt.to_string());
+    args
+}
+
+fn start_run(job: &ScheduledJob, codex_home: &Path, exe: &Path) -> anyhow::Result<JoinHandle<()>> {
+    let started_at = Utc::now();
+    let run_dir = unique_run_dir(
+        &codex_home.join(RUNS_DIR).join(&job.id),
+        started_at.with_timezone(&Local),
+    );
+    fs::create_dir_all(&run_dir)
+        .with_context(|| format!("failed to create {}", run_dir.display()))?;
+    let events = fs::File::create(run_dir.join("events.jsonl"))?;
+    let stderr = fs::File::create(run_dir.join("stderr.log"))?;
+    let mut command = Command::new(exe);
+    command
+        .args(exec_args(
+            job.gui,
+            job.profile.as_deref(),
+            &job.prompt,
+            &run_dir,
+        ))
+        .current_dir(&job.cwd)
+        .stdin(Stdio::null())
+        .stdout(events)
+        .stderr(stderr);
+    println!(
+        "job {}: {} fired, writing {}",
+        job.id,
+        job.trigger,
+        run_dir.display()
+    );
+    let job = job.clone();
+    Ok(tokio::spawn(async move {
+        let (exit_code, success, error) = match command.status().await {
+            Ok(status) => (status.code(), status.success(), None),
+            Err(err) => (None, false, Some(err.to_string())),
+        };
+        let report = RunReport {
+            job: &job.id,
+            trigger: job.trigger.to_string(),
+            prompt: &job.prompt,
+            started_at,
+            finished_at: Utc::now(),
+            exit_code,
+            success,
+            error,
+        };
+        let outcome = if success { "succeeded" } else { "failed" };
+        println!("job {}: run {outcome}", job.id);
+        if let Err(err) = write_report(&run_dir, &report) {
+            eprintln!("job {}: failed to write report: {err}", job.id);
+        }
+    }))
+}
+
+pub(crate) fn write_report(run_dir: &Path, report: &RunReport<'_>) -> io::Result<()> {
+    let contents = serde_json::to_string_pretty(report).map_err(io::Error::other)?;
+    fs::write(run_dir.join("report.json"), contents)
+}
+
+/// `<YYYYmmdd-HHMMSS>`, with a suffix if that directory already exists.
+pub(crate) fn unique_run_dir(job_dir: &Path, at: DateTime<Local>) -> PathBuf {
+    let base = at.format("%Y%m%d-%H%M%S").to_string();
+    let mut dir = job_dir.join(&base);
+    let mut suffix = 2;
+    while dir.exists() {
+        dir = job_dir.join(format!("{base}-{suffix}"));
+        suffix += 1;
+    }
+    dir
+}
+
+fn load(path: &Path) -> io::Result<ScheduleStore> {
+    match fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(ScheduleStore::default()),
+        Err(err) => Err(err),
+    }
+}
+
+fn persist(path: &Path, store: &ScheduleStore) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let contents = serde_json::to_string_pretty(store)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    fs::write(path, contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use pretty_assertions::assert_eq;
+
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        // 2026-03-02 is a Monday.
+        NaiveDate::from_ymd_opt(2026, 3, day)
+            .and_then(|date| date.and_hms_opt(hour, minute, 0))
+            .unwrap()
+    }
+
+    fn next(expression: &str, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        CronSchedule::parse(expression).unwrap().next_naive(after)
+    }
+
+    #[test]
+    fn computes_next_cron_times() {
+        let monday_noon = at(2, 12, 0);
+        assert_eq!(next("*/15 * * * *", monday_noon), Some(at(2, 12, 15)));
+        assert_eq!(next("0 9 * * mon-fri", monday_noon), Some(at(3, 9, 0)));
+        assert_eq!(next("30 8 * * sat,sun", monday_noon), Some(at(7, 8, 30)));
+        assert_eq!(
+            next("@monthly", monday_noon),
+            NaiveDate::from_ymd_opt(2026, 4, 1).and_then(|date| date.and_hms_opt(0, 0, 0))
+        );
+        // Day-of-month and day-of-week both restricted: either matches.
+        assert_eq!(next("0 0 15 * 3", monday_noon), Some(at(4, 0, 0)));
+        // Sunday may be written as 7.
+        assert_eq!(next("0 0 * * 7", monday_noon), Some(at(8, 0, 0)));
+        assert_eq!(next("0 0 31 2 *", monday_noon), None);
+    }
+
+    #[test]
+    fn rejects_invalid_cron_expressions() {
+        assert!(CronSchedule::parse("* * * *").is_err());
+        assert!(CronSchedule::parse("60 * * * *").is_err());
+        assert!(CronSchedule::parse("*/0 * * * *").is_err());
+        assert!(CronSchedule::parse("0 0 * * funday").is_err());
+        assert!(CronSchedule::parse("@often").is_err());
+    }
+
+    #[test]
+    fn adds_lists_and_removes_jobs() -> anyhow::Result<()> {
+        let home = tempfile::tempdir()?;
+        let defaults = JobDefaults {
+            gui: true,
+            profile: Some("kiosk".to_string()),
+            cwd: PathBuf::from("/work"),
+        };
+        let cron = add_job(
+            home.path(),
+            AddJobArgs {
+                spec: "0 9 * * mon-fri  check the  build dashboard ".to_string(),
+                watch: None,
+                dbus: None,
+            },
+            defaults.clone(),
+        )?;
+        assert_eq!(
+            (cron.id.as_str(), &cron.trigger, cron.prompt.as_str()),
+            (
+                "1",
+                &Trigger::Cron {
+                    expression: "0 9 * * mon-fri".to_string()
+                },
+                "check the  build dashboard"
+            )
+        );
+        let watch = add_job(
+            home.path(),
+            AddJobArgs {
+                spec: "summarize the new report".to_string(),
+                watch: Some(PathBuf::from("/inbox")),
+                dbus: None,
+            },
+            defaults.clone(),
+        )?;
+        assert_eq!(watch.id, "2");
+        assert!(
+            add_job(
+                home.path(),
+                AddJobArgs {
+                    spec: "0 9 * *".to_string(),
+                    watch: None,
+                    dbus: None,
+                },
+                defaults,
+            )
+            .is_err()
+        );
+
+        remove_job(home.path(), "1")?;
+        let ids: Vec<String> = load(&home.path().join(SCHEDULE_FILE))?
+            .jobs
+            .into_iter()
+            .map(|job| job.id)
+            .collect();
+        assert_eq!(ids, vec!["2"]);
+        assert!(remove_job(home.path(), "1").is_err());
+
+        assert_eq!(
+            exec_args(
+                watch.gui,
+                watch.profile.as_deref(),
+                &watch.prompt,
+                Path::new("/runs/2/x")
+            ),
+            vec![
+                "--gui",
+                "exec",
+                "--json",
+                "--skip-git-repo-check",
+                "--output-last-message",
+                "/runs/2/x/last-message.md",
+                "--profile",
+                "kiosk",
+                "--",
+                "summarize the new report",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn ignores_dbus_monitor_bookkeeping() {
+        assert!(is_matching_signal(
+            "signal time=1.0 sender=:1.5 -> destination=(null destination) serial=9 path=/org/gnome/ScreenSaver; interface=org.gnome.ScreenSaver; member=ActiveChanged"
+        ));
+        assert!(!is_matching_signal(
+            "signal time=1.0 sender=org.freedesktop.DBus -> destination=:1.9 serial=2 path=/org/freedesktop/DBus; interface=org.freedesktop.DBus; member=NameAcquired"
+        ));
+        assert!(!is_matching_signal("   string \"hello\""));
+    }
+}
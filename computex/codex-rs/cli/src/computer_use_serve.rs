@@ -0,0 +1,563 @@
+//! HTTP control API for `computex serve`.
+//!
+//! Other systems start automation runs, follow their event stream, fetch
+//! screenshots of the desktop, and cancel runs over a small JSON API. Every
+//! request needs `Authorization: Bearer <token>`. Runs are headless
+//! `computex exec` processes laid out like scheduled runs, under
+//! `$CODEX_HOME/computer_use/runs/serve/<id>/`.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::io::Read;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Child;
+use std::process::Command;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::time::Duration;
+
+use anyhow::Context;
+use base64::Engine;
+use chrono::DateTime;
+use chrono::Local;
+use chrono::Utc;
+use clap::Parser;
+use codex_core::capture_operator_screenshot;
+use codex_core::config::types::ComputerUseConfig;
+use rand::RngCore;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::json;
+use tiny_http::Header;
+use tiny_http::Method;
+use tiny_http::Request;
+use tiny_http::Response;
+use tiny_http::Server;
+use tiny_http::StatusCode;
+
+use crate::computer_use_schedule::JobDefaults;
+use crate::computer_use_schedule::RUNS_DIR;
+use crate::computer_use_schedule::RunReport;
+use crate::computer_use_schedule::exec_args;
+use crate::computer_use_schedule::unique_run_dir;
+use crate::computer_use_schedule::write_report;
+
+pub const DEFAULT_LISTEN: &str = "127.0.0.1:8787";
+/// Fixed bearer token; a random one is generated and printed when unset.
+pub const TOKEN_ENV_VAR: &str = "COMPUTEX_SERVE_TOKEN";
+
+/// How often finished runs are reaped and followed event streams re-read.
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+const MAX_BODY_BYTES: u64 = 1024 * 1024;
+
+#[derive(Debug, Parser)]
+pub struct ServeCommand {
+    /// Address to listen on. Anything other than loopback exposes desktop
+    /// control to the network; put TLS in front of it.
+    #[arg(long, value_name = "ADDR", default_value = DEFAULT_LISTEN)]
+    pub listen: SocketAddr,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum RunStatus {
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+struct Run {
+    prompt: String,
+    gui: bool,
+    dir: PathBuf,
+    started_at: DateTime<Utc>,
+    finished_at: Option<DateTime<Utc>>,
+    exit_code: Option<i32>,
+    status: RunStatus,
+    /// Present while the process has not been reaped.
+    child: Option<Child>,
+}
+
+/// A run as returned by the API.
+#[derive(Debug, Serialize)]
+struct RunView {
+    id: String,
+    prompt: String,
+    gui: bool,
+    status: RunStatus,
+    started_at: DateTime<Utc>,
+    finished_at: Option<DateTime<Utc>>,
+    exit_code: Option<i32>,
+    /// The agent's final message, once the run has finished.
+    last_message: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StartRun {
+    prompt: String,
+    /// Overrides the server's `--gui`/`--headless` mode for this run.
+    #[serde(default)]
+    gui: Option<bool>,
+}
+
+/// What a request resolves to, before it is written to the connection.
+enum Reply {
+    Json(u16, serde_json::Value),
+    Png(Vec<u8>),
+    Events(EventStream),
+}
+
+struct ServeState {
+    token: String,
+    codex_home: PathBuf,
+    defaults: JobDefaults,
+    computer_use: ComputerUseConfig,
+    exe: PathBuf,
+    /// Keyed by run id, which sorts by start time.
+    runs: Mutex<BTreeMap<String, Run>>,
+}
+
+pub fn run_serve(
+    command: ServeCommand,
+    codex_home: &Path,
+    defaults: JobDefaults,
+    computer_use: ComputerUseConfig,
+) -> anyhow::Result<()> {
+    let (token, generated) = serve_token();
+    let server = Server::http(command.listen)
+        .map_err(|err| anyhow::anyhow!("failed to listen on {}: {err}", command.listen))?;
+    let state = Arc::new(ServeState {
+        token,
+        codex_home: codex_home.to_path_buf(),
+        defaults,
+        computer_use,
+        exe: std::env::current_exe().context("failed to locate the computex binary")?,
+        runs: Mutex::new(BTreeMap::new()),
+    });
+
+    eprintln!("computex serve listening on http://{}", command.listen);
+    if !command.listen.ip().is_loopback() {
+        eprintln!("warning: {} is reachable from other hosts", command.listen);
+    }
+    if generated {
+        eprintln!(
+            "bearer token: {} (set {TOKEN_ENV_VAR} to choose one)",
+            state.token
+        );
+    }
+
+    for request in server.incoming_requests() {
+        let state = Arc::clone(&state);
+        // Event streams block until their run finishes.
+        std::thread::spawn(move || handle_request(&state, request));
+    }
+    anyhow::bail!("server stopped unexpectedly")
+}
+
+/// The bearer token from `COMPUTEX_SERVE_TOKEN`, or a random one. The flag
+/// tells the caller to print a generated token.
+pub fn serve_token() -> (String, bool) {
+    match std::env::var(TOKEN_ENV_VAR) {
+        Ok(token) if !token.is_empty() => (token, false),
+        _ => (generate_token(), true),
+    }
+}
+
+pub(crate) fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::rng().fill_bytes(&mut bytes);
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+}
+
+fn handle_request(state: &Arc<ServeState>, mut request: Request) {
+    let authorization = request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Authorization"))
+        .map(|header| header.value.as_str().to_string());
+    let reply = if !authorized(&state.token, authorization.as_deref()) {
+        error(401, "missing or invalid bearer token")
+    } else {
+        let mut body = String::new();
+        let read = request
+            .as_reader()
+            .take(MAX_BODY_BYTES)
+            .read_to_string(&mut body);
+        match read {
+            Ok(_) => route(state, request.method(), request.url(), &body),
+            Err(err) => error(400, &format!("failed to read body: {err}")),
+        }
+    };
+    let result = match reply {
+        Reply::Json(status, value) => request.respond(with_content_type(
+            Response::from_string(value.to_string()).with_status_code(StatusCode(status)),
+            "application/json",
+        )),
+        Reply::Png(bytes) => {
+            request.respond(with_content_type(Response::from_data(bytes), "image/png"))
+        }
+        Reply::Events(stream) => request.respond(with_content_type(
+            Response::new(StatusCode(200), Vec::new(), stream, None, None),
+            "application/x-ndjson",
+        )),
+    };
+    if let Err(err) = result {
+        eprintln!("failed to send response: {err}");
+    }
+}
+
+pub(crate) fn with_content_type<R: Read>(mut response: Response<R>, value: &str) -> Response<R> {
+    if let Ok(header) = Header::from_bytes(&b"Content-Type"[..], value.as_bytes()) {
+        response.add_header(header);
+    }
+    response
+}
+
+/// Constant-time comparison of the presented bearer token.
+fn authorized(token: &str, authorization: Option<&str>) -> bool {
+    let Some(presented) = authorization.and_then(|value| value.strip_prefix("Bearer ")) else {
+        return false;
+    };
+    tokens_match(token, presented)
+}
+
+/// Constant-time comparison of a presented token with the expected one.
+pub(crate) fn tokens_match(token: &str, presented: &str) -> bool {
+    presented.len() == token.len()
+        && presented
+            .bytes()
+            .zip(token.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn route(state: &Arc<ServeState>, method: &Method, url: &str, body: &str) -> Reply {
+    let path = url.split('?').next().unwrap_or_default();
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match (method, segments.as_slice()) {
+        (Method::Post, ["v1", "runs"]) => match serde_json::from_str::<StartRun>(body) {
+            Ok(start) if start.prompt.trim().is_empty() => error(400, "prompt must not be empty"),
+            Ok(start) => match start_run(state, start) {
+                Ok(view) => Reply::Json(201, json!(view)),
+                Err(err) => error(500, &format!("{err:#}")),
+            },
+            Err(err) => error(400, &format!("expected {{\"prompt\": ...}}: {err}")),
+        },
+        (Method::Get, ["v1", "runs"]) => {
+            let runs = lock_runs(state);
+            let views: Vec<RunView> = runs.iter().map(|(id, run)| view(id, run)).collect();
+            Reply::Json(200, json!({ "runs": views }))
+        }
+        (Method::Get, ["v1", "runs", id]) => match lock_runs(state).get(*id) {
+            Some(run) => Reply::Json(200, json!(view(id, run))),
+            None => not_found(id),
+        },
+        (Method::Get, ["v1", "runs", id, "events"]) => {
+            let dir = match lock_runs(state).get(*id) {
+                Some(run) => run.dir.clone(),
+                None => return not_found(id),
+            };
+            match fs::File::open(dir.join("events.jsonl")) {
+                Ok(file) => Reply::Events(EventStream {
+                    file,
+                    state: Arc::clone(state),
+                    id: (*id).to_string(),
+                }),
+                Err(err) => error(500, &format!("failed to open events: {err}")),
+            }
+        }
+        (Method::Post, ["v1", "runs", id, "cancel"]) => cancel_run(state, id),
+        (Method::Get, ["v1", "screenshot"]) => match screenshot(&state.computer_use) {
+            Ok(bytes) => Reply::Png(bytes),
+            Err(err) => error(500, &format!("{err:#}")),
+        },
+        _ => error(404, &format!("no route for {method} {path}")),
+    }
+}
+
+fn error(status: u16, message: &str) -> Reply {
+    Reply::Json(status, json!({ "error": message }))
+}
+
+fn not_found(id: &str) -> Reply {
+    error(404, &format!("no run with id `{id}`"))
+}
+
+fn lock_runs(state: &ServeState) -> MutexGuard<'_, BTreeMap<String, Run>> {
+    state
+        .runs
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+fn view(id: &str, run: &Run) -> RunView {
+    let last_message = match run.status {
+        RunStatus::Running => None,
+        _ => fs::read_to_string(run.dir.join("last-message.md")).ok(),
+    };
+    RunView {
+        id: id.to_string(),
+        prompt: run.prompt.clone(),
+        gui: run.gui,
+        status: run.status,
+        started_at: run.started_at,
+        finished_at: run.finished_at,
+        exit_code: run.exit_code,
+        last_message,
+    }
+}
+
+fn start_run(state: &Arc<ServeState>, start: StartRun) -> anyhow::Result<RunView> {
+    let gui = start.gui.unwrap_or(state.defaults.gui);
+    let started_at = Utc::now();
+    let mut runs = lock_runs(state);
+    // Picked and created under the lock so concurrent requests get distinct ids.
+    let dir = unique_run_dir(
+        &state.codex_home.join(RUNS_DIR).join("serve"),
+        started_at.with_timezone(&Local),
+    );
+    fs::create_dir_all(&dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    let id = dir
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .context("run directory has no name")?;
+    let child = Command::new(&state.exe)
+        .args(exec_args(
+            gui,
+            state.defaults.profile.as_deref(),
+            &start.prompt,
+            &dir,
+        ))
+        .current_dir(&state.defaults.cwd)
+        .stdin(Stdio::null())
+        .stdout(fs::File::create(dir.join("events.jsonl"))?)
+        .stderr(fs::File::create(dir.join("stderr.log"))?)
+        .spawn()
+        .context("failed to start computex exec")?;
+    let run = Run {
+        prompt: start.prompt,
+        gui,
+        dir,
+        started_at,
+        finished_at: None,
+        exit_code: None,
+        status: RunStatus::Running,
+        child: Some(child),
+    };
+    let view = view(&id, &run);
+    runs.insert(id.clone(), run);
+    drop(runs);
+
+    let state = Arc::clone(state);
+    std::thread::spawn(move || reap_when_finished(&state, &id));
+    Ok(view)
+}
+
+/// Waits for the run's process to exit, then records the outcome.
+fn reap_when_finished(state: &ServeState, id: &str) {
+    loop {
+        std::thread::sleep(POLL_INTERVAL);
+        let mut runs = lock_runs(state);
+        let Some(run) = runs.get_mut(id) else {
+            return;
+        };
+        let Some(child) = run.child.as_mut() else {
+            return;
+        };
+        let (exit_code, success, error) = match child.try_wait() {
+            Ok(None) => continue,
+            Ok(Some(status)) => (status.code(), status.success(), None),
+            Err(err) => (None, false, Some(err.to_string())),
+        };
+        run.child = None;
+        run.exit_code = exit_code;
+        run.finished_at = Some(Utc::now());
+        if run.status == RunStatus::Running {
+            run.status = if success {
+                RunStatus::Succeeded
+            } else {
+                RunStatus::Failed
+            };
+        }
+        let report = RunReport {
+            job: id,
+            trigger: "http".to_string(),
+            prompt: &run.prompt,
+            started_at: run.started_at,
+            finished_at: Utc::now(),
+            exit_code,
+            success: run.status == RunStatus::Succeeded,
+            error,
+        };
+        if let Err(err) = write_report(&run.dir, &report) {
+            eprintln!("run {id}: failed to write report: {err}");
+        }
+        return;
+    }
+}
+
+fn cancel_run(state: &ServeState, id: &str) -> Reply {
+    let mut runs = lock_runs(state);
+    let Some(run) = runs.get_mut(id) else {
+        return not_found(id);
+    };
+    let Some(child) = run.child.as_mut() else {
+        return error(409, &format!("run `{id}` already finished"));
+    };
+    if let Err(err) = child.kill() {
+        return error(500, &format!("failed to cancel run `{id}`: {err}"));
+    }
+    run.status = RunStatus::Cancelled;
+    Reply::Json(200, json!(view(id, run)))
+}
+
+pub(crate) fn screenshot(config: &ComputerUseConfig) -> anyhow::Result<Vec<u8>> {
+    let path = capture_operator_screenshot(config)?;
+    let bytes = fs::read(&path);
+    let _ = fs::remove_file(&path);
+    Ok(bytes?)
+}
+
+/// Follows a run's `events.jsonl` like `tail -f` and ends once the run has
+/// finished and everything it wrote has been sent.
+struct EventStream {
+    file: fs::File,
+    state: Arc<ServeState>,
+    id: String,
+}
+
+impl Read for EventStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            // Checked before reading so output written just before exit is not lost.
+            let running = lock_runs(&self.state)
+                .get(&self.id)
+                .is_some_and(|run| run.child.is_some());
+            let read = self.file.read(buf)?;
+            if read > 0 || !running {
+                return Ok(read);
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn json_of(reply: Reply) -> (u16, serde_json::Value) {
+        match reply {
+            Reply::Json(status, value) => (status, value),
+            Reply::Png(_) => (200, json!("png")),
+            Reply::Events(mut stream) => {
+                let mut events = String::new();
+                let _ = stream.read_to_string(&mut events);
+                (200, json!(events))
+            }
+        }
+    }
+
+    fn state(home: &Path, exe: &str) -> Arc<ServeState> {
+        Arc::new(ServeState {
+            token: "secret".to_string(),
+            codex_home: home.to_path_buf(),
+            defaults: JobDefaults {
+                gui: false,
+                profile: None,
+                cwd: home.to_path_buf(),
+            },
+            computer_use: ComputerUseConfig::default(),
+            exe: which::which(exe).unwrap(),
+            runs: Mutex::new(BTreeMap::new()),
+        })
+    }
+
+    #[test]
+    fn requires_the_bearer_token() {
+        assert!(authorized("secret", Some("Bearer secret")));
+        assert!(!authorized("secret", Some("Bearer secrets")));
+        assert!(!authorized("secret", Some("secret")));
+        assert!(!authorized("secret", None));
+    }
+
+    #[test]
+    fn runs_report_their_outcome() -> anyhow::Result<()> {
+        let home = tempfile::tempdir()?;
+        // `true` ignores the exec arguments and exits successfully.
+        let state = state(home.path(), "true");
+
+        let (status, created) = json_of(route(
+            &state,
+            &Method::Post,
+            "/v1/runs",
+            r#"{"prompt": "open the settings"}"#,
+        ));
+        assert_eq!((status, created["status"].clone()), (201, json!("running")));
+        let id = created["id"].as_str().unwrap_or_default().to_string();
+
+        // The event stream ends once the run has been reaped.
+        let (_, events) = json_of(route(
+            &state,
+            &Method::Get,
+            &format!("/v1/runs/{id}/events"),
+            "",
+        ));
+        assert_eq!(events, json!(""));
+        let (status, finished) =
+            json_of(route(&state, &Method::Get, &format!("/v1/runs/{id}"), ""));
+        assert_eq!(
+            (
+                status,
+                finished["status"].clone(),
+                finished["exit_code"].clone()
+            ),
+            (200, json!("succeeded"), json!(0))
+        );
+        assert!(
+            home.path()
+                .join(RUNS_DIR)
+                .join("serve")
+                .join(&id)
+                .join("report.json")
+                .exists()
+        );
+
+        assert_eq!(
+            json_of(route(
+                &state,
+                &Method::Post,
+                &format!("/v1/runs/{id}/cancel"),
+                ""
+            ))
+            .0,
+            409
+        );
+        assert_eq!(
+            json_of(route(
+                &state,
+                &Method::Post,
+                "/v1/runs",
+                r#"{"prompt": " "}"#
+            ))
+            .0,
+            400
+        );
+        assert_eq!(
+            json_of(route(&state, &Method::Get, "/v1/runs/missing", "")).0,
+            404
+        );
+        assert_eq!(
+            json_of(route(&state, &Method::Delete, "/v1/runs", "")).0,
+            404
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,292 @@
+//! First-run setup wizard for `computex --gui`.
+//!
+//! Checks that the GUI backend works, optionally runs a click/screenshot round
+//! trip against a throwaway window, and records the user's safety defaults in
+//! `config.toml` so later launches skip straight to the TUI. `computex setup`
+//! reruns it after installing the bundled input helper and, when enabled, the
+//! browser extension bridge.
+
+use std::io::BufRead;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use codex_core::computer_use_browser_bridge;
+use codex_core::computer_use_bundled;
+use codex_core::computer_use_diagnostics;
+use codex_core::config::edit::ConfigEditsBuilder;
+use codex_core::config::types::ComputerUseConfig;
+use codex_core::protocol::AskForApproval;
+
+/// Approval modes offered by the wizard, in menu order.
+const APPROVAL_CHOICES: &[(AskForApproval, &str)] = &[
+    (
+        AskForApproval::UnlessTrusted,
+        "ask before anything except known-safe read-only commands",
+    ),
+    (
+        AskForApproval::OnRequest,
+        "the agent decides when to ask (recommended)",
+    ),
+    (
+        AskForApproval::Never,
+        "never ask; failures go straight back to the agent",
+    ),
+];
+const DEFAULT_APPROVAL_CHOICE: usize = 1;
+
+/// Environment checks run by the wizard; swapped out in tests.
+pub struct SetupChecks {
+    pub detect_backend: fn(&ComputerUseConfig) -> anyhow::Result<String>,
+    pub round_trip: fn(&ComputerUseConfig) -> anyhow::Result<String>,
+}
+
+impl Default for SetupChecks {
+    fn default() -> Self {
+        Self {
+            detect_backend: computer_use_diagnostics::detect_backend,
+            round_trip: computer_use_diagnostics::round_trip_check,
+        }
+    }
+}
+
+/// Safety defaults chosen in the wizard.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetupAnswers {
+    pub approval_policy: AskForApproval,
+    pub blocked_apps: Vec<String>,
+}
+
+/// Runs the wizard on the terminal and persists the result under `codex_home`.
+pub async fn run_first_run_setup(
+    codex_home: &Path,
+    config: &ComputerUseConfig,
+) -> anyhow::Result<()> {
+    let answers = {
+        let stdin = std::io::stdin();
+        let mut input = stdin.lock();
+        let mut output = std::io::stdout();
+        run_wizard(&mut input, &mut output, config, &SetupChecks::default())?
+    };
+    let mut edits = ConfigEditsBuilder::new(codex_home).set_computer_use_setup_completed(true);
+    if let Some(answers) = &answers {
+        edits = edits
+            .set_approval_policy(answers.approval_policy)
+            .set_computer_use_blocked_apps(&answers.blocked_apps);
+    }
+    edits.apply().await?;
+    if answers.is_some() {
+        let path = codex_home.join("config.toml");
+        println!("Saved computer-use setup to {}.\n", path.display());
+    }
+    Ok(())
+}
+
+/// Installs the `computex-inject` helper under `codex_home`: `source`, or
+/// one built next to the running executable.
+pub fn install_input_helper(codex_home: &Path, source: Option<&Path>) -> anyhow::Result<PathBuf> {
+    let source = match source {
+        Some(source) => source.to_path_buf(),
+        None => std::env::current_exe()
+            .context("cannot locate the computex executable")?
+            .with_file_name(computer_use_bundled::HELPER_NAME),
+    };
+    if !source.is_file() {
+        anyhow::bail!(
+            "{} not found; install it with `cargo install --path codex-rs/computer-use-inject`, or build it with `cargo build --release -p codex-computer-use-inject` and pass its path with --helper",
+            source.display()
+        );
+    }
+    computer_use_bundled::install(codex_home, &source)
+        .with_context(|| format!("failed to install {}", source.display()))
+}
+
+/// Unpacks the browser extension under `codex_home` and registers its native
+/// messaging host, pointing at the running executable, with the browsers
+/// found in the home directory.
+pub fn install_browser_bridge(
+    codex_home: &Path,
+) -> anyhow::Result<computer_use_browser_bridge::Installed> {
+    let home = std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .context("HOME is not set")?;
+    let computex = std::env::current_exe().context("cannot locate the computex executable")?;
+    computer_use_browser_bridge::install(codex_home, &home, &computex)
+        .context("failed to install the browser bridge")
+}
+
+/// Drives the prompts over `input`/`output`. Returns `None` when the user skips setup.
+pub fn run_wizard<R: BufRead, W: Write>(
+    input: &mut R,
+    output: &mut W,
+    config: &ComputerUseConfig,
+    checks: &SetupChecks,
+) -> std::io::Result<Option<SetupAnswers>> {
+    writeln!(output, "Welcome to computex GUI setup.")?;
+    let start = prompt(
+        input,
+        output,
+        "Press Enter to check your desktop and choose safety defaults, or type `skip`: ",
+    )?;
+    if start.eq_ignore_ascii_case("skip") {
+        writeln!(
+            output,
+            "Skipping setup. Edit [computer_use] in config.toml to change settings later.\n"
+        )?;
+        return Ok(None);
+    }
+
+    writeln!(output, "\n[1/3] Detecting GUI backend…")?;
+    let backend_ok = match (checks.detect_backend)(config) {
+        Ok(summary) => {
+            writeln!(output, "  ✓ {summary}")?;
+            true
+        }
+        Err(err) => {
+            writeln!(output, "  ✗ {err}")?;
+            writeln!(
+                output,
+                "  GUI tools will fail until this is fixed; the shell still works."
+            )?;
+            false
+        }
+    };
+
+    writeln!(output, "\n[2/3] Click/screenshot round trip")?;
+    if backend_ok {
+        let answer = prompt(
+            input,
+            output,
+            "  Open a temporary window, click inside it, and take a screenshot? [Y/n] ",
+        )?;
+        if answer.is_empty() || answer.eq_ignore_ascii_case("y") {
+            match (checks.round_trip)(config) {
+                Ok(summary) => writeln!(output, "  ✓ {summary}")?,
+                Err(err) => writeln!(output, "  ✗ {err}")?,
+            }
+        } else {
+            writeln!(output, "  Skipped.")?;
+        }
+    } else {
+        writeln!(output, "  Skipped because the backend is unavailable.")?;
+    }
+
+    writeln!(output, "\n[3/3] Safety defaults")?;
+    writeln!(
+        output,
+        "  When should the agent ask before running commands?"
+    )?;
+    for (index, (policy, description)) in APPROVAL_CHOICES.iter().enumerate() {
+        let number = index + 1;
+        writeln!(output, "    {number}) {policy} – {description}")?;
+    }
+    let approval_policy = loop {
+        let default = DEFAULT_APPROVAL_CHOICE + 1;
+        let answer = prompt(input, output, &format!("  Choose [{default}]: "))?;
+        if answer.is_empty() {
+            break APPROVAL_CHOICES[DEFAULT_APPROVAL_CHOICE].0;
+        }
+        match answer.parse::<usize>() {
+            Ok(choice) if (1..=APPROVAL_CHOICES.len()).contains(&choice) => {
+                break APPROVAL_CHOICES[choice - 1].0;
+            }
+            _ => writeln!(output, "  Please enter 1-{}.", APPROVAL_CHOICES.len())?,
+        }
+    };
+    let blocked = prompt(
+        input,
+        output,
+        "  Applications the agent must never control (window classes, comma-separated, blank for none): ",
+    )?;
+
+    Ok(Some(SetupAnswers {
+        approval_policy,
+        blocked_apps: parse_app_list(&blocked),
+    }))
+}
+
+fn prompt<R: BufRead, W: Write>(
+    input: &mut R,
+    output: &mut W,
+    message: &str,
+) -> std::io::Result<String> {
+    write!(output, "{message}")?;
+    output.flush()?;
+    let mut line = String::new();
+    input.read_line(&mut line)?;
+    Ok(line.trim().to_string())
+}
+
+fn parse_app_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|app| !app.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn checks(backend_available: bool) -> SetupChecks {
+        SetupChecks {
+            detect_backend: if backend_available {
+                |_| Ok("x11 on display :0 (1920x1080)".to_string())
+            } else {
+                |_| anyhow::bail!("DISPLAY is not set")
+            },
+            round_trip: |_| Ok("round trip ok".to_string()),
+        }
+    }
+
+    fn run(input: &str, checks: &SetupChecks) -> (Option<SetupAnswers>, String) {
+        let mut output = Vec::new();
+        let answers = run_wizard(
+            &mut input.as_bytes(),
+            &mut output,
+            &ComputerUseConfig::default(),
+            checks,
+        )
+        .expect("wizard should not fail on in-memory io");
+        (answers, String::from_utf8(output).expect("utf8 output"))
+    }
+
+    #[test]
+    fn wizard_collects_safety_defaults() {
+        let (answers, output) = run("\n\n1\nfirefox, , xterm\n", &checks(true));
+        assert_eq!(
+            answers,
+            Some(SetupAnswers {
+                approval_policy: AskForApproval::UnlessTrusted,
+                blocked_apps: vec!["firefox".to_string(), "xterm".to_string()],
+            })
+        );
+        assert!(output.contains("✓ x11 on display :0"));
+        assert!(output.contains("✓ round trip ok"));
+    }
+
+    #[test]
+    fn wizard_skips_round_trip_when_backend_missing() {
+        let (answers, output) = run("\n9\n\n\n", &checks(false));
+        assert_eq!(
+            answers,
+            Some(SetupAnswers {
+                approval_policy: AskForApproval::OnRequest,
+                blocked_apps: Vec::new(),
+            })
+        );
+        assert!(output.contains("✗ DISPLAY is not set"));
+        assert!(output.contains("Skipped because the backend is unavailable."));
+        assert!(output.contains("Please enter 1-3."));
+    }
+
+    #[test]
+    fn wizard_can_be_skipped() {
+        let (answers, _) = run("skip\n", &checks(true));
+        assert_eq!(answers, None);
+    }
+}
@@ -1,3 +1,7 @@
+pub mod computer_use_preview;
+pub mod computer_use_schedule;
+pub mod computer_use_serve;
+pub mod computer_use_setup;
 pub mod debug_sandbox;
 mod exit_status;
 pub mod login;
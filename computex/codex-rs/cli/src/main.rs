@@ -36,11 +36,13 @@ mod wsl_paths;
 
 use crate::mcp_cmd::McpCli;
 
-use codex_core::COMPUTER_USE_PROMPT;
+use codex_core::computer_use_prompt;
 use codex_core::config::Config;
 use codex_core::config::ConfigOverrides;
 use codex_core::config::find_codex_home;
+use codex_core::config::load_computer_use_config;
 use codex_core::config::load_config_as_toml_with_cli_overrides;
+use codex_core::config::types::ComputerUseConfig;
 use codex_core::features::Feature;
 use codex_core::features::FeatureOverrides;
 use codex_core::features::Features;
@@ -491,8 +493,9 @@ async fn cli_main(codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()
                 .config_overrides
                 .raw_overrides
                 .push(format!("features.computer_use_gui={enable_gui}"));
+            let computer_use = resolve_computer_use_config(&computer_cli.exec).await?;
             let harness_overrides = codex_exec::HarnessOverrides {
-                base_instructions: Some(COMPUTER_USE_PROMPT.to_string()),
+                base_instructions: Some(computer_use_prompt(&computer_use, enable_gui)),
                 ..Default::default()
             };
             codex_exec::run_main_with_harness_overrides(
@@ -742,6 +745,27 @@ async fn is_tui2_enabled(cli: &TuiCli) -> std::io::Result<bool> {
     Ok(features.enabled(Feature::Tui2))
 }
 
+/// Resolves the computer-use settings used to render the `computer-use` base instructions.
+async fn resolve_computer_use_config(cli: &ExecCli) -> std::io::Result<ComputerUseConfig> {
+    let cli_kv_overrides = cli
+        .config_overrides
+        .parse_overrides()
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+
+    let codex_home = find_codex_home()?;
+    let config_cwd = match cli.cwd.as_deref() {
+        Some(path) => AbsolutePathBuf::from_absolute_path(path)?,
+        None => AbsolutePathBuf::current_dir()?,
+    };
+    load_computer_use_config(
+        &codex_home,
+        &config_cwd,
+        cli_kv_overrides,
+        cli.config_profile.clone(),
+    )
+    .await
+}
+
 /// Build the final `TuiCli` for a `codex resume` invocation.
 fn finalize_resume_interactive(
     mut interactive: TuiCli,
@@ -866,6 +890,7 @@ mod tests {
         };
         AppExitInfo {
             token_usage,
+            gui_usage: Default::default(),
             conversation_id: conversation
                 .map(ConversationId::from_string)
                 .map(Result::unwrap),
@@ -877,6 +902,7 @@ mod tests {
     fn format_exit_messages_skips_zero_usage() {
         let exit_info = AppExitInfo {
             token_usage: TokenUsage::default(),
+            gui_usage: Default::default(),
             conversation_id: None,
             update_action: None,
         };
@@ -0,0 +1,351 @@
+//! Parsing the xdotool command lines computex sends.
+
+use std::time::Duration;
+
+/// xdotool's default pause between repeated clicks and keys, and between
+/// typed characters.
+pub const DEFAULT_DELAY: Duration = Duration::from_millis(12);
+
+/// Commands this helper understands, which end the argument list of the
+/// command before them when chained.
+const COMMANDS: &[&str] = &[
+    "mousemove",
+    "click",
+    "mousedown",
+    "mouseup",
+    "key",
+    "keydown",
+    "keyup",
+    "type",
+    "sleep",
+    "getdisplaygeometry",
+    "getmouselocation",
+    "getactivewindow",
+    "getwindowname",
+    "getwindowclassname",
+    "windowmap",
+    "windowactivate",
+];
+
+/// Whether a key command taps its chords or only presses or releases them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyAction {
+    Tap,
+    Press,
+    Release,
+}
+
+/// One xdotool command.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    MoveTo {
+        x: i32,
+        y: i32,
+    },
+    Click {
+        button: u8,
+        repeat: u32,
+        delay: Duration,
+    },
+    Button {
+        button: u8,
+        press: bool,
+    },
+    /// Key chords such as `ctrl+shift+t`, each split into its key names.
+    Keys {
+        chords: Vec<Vec<String>>,
+        action: KeyAction,
+        repeat: u32,
+        delay: Duration,
+        clear_modifiers: bool,
+    },
+    /// `None` text is read from stdin, as with `type --file -`.
+    Type {
+        text: Option<String>,
+        delay: Duration,
+        clear_modifiers: bool,
+    },
+    Sleep(Duration),
+    DisplayGeometry,
+    MouseLocation {
+        shell: bool,
+    },
+    ActiveWindow,
+    WindowName(u32),
+    WindowClassName(u32),
+    MapWindow(u32),
+    ActivateWindow {
+        window: u32,
+        sync: bool,
+    },
+}
+
+impl Command {
+    /// The xdotool command this came from.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Command::MoveTo { .. } => "mousemove",
+            Command::Click { .. } => "click",
+            Command::Button { press: true, .. } => "mousedown",
+            Command::Button { press: false, .. } => "mouseup",
+            Command::Keys { action, .. } => match action {
+                KeyAction::Tap => "key",
+                KeyAction::Press => "keydown",
+                KeyAction::Release => "keyup",
+            },
+            Command::Type { .. } => "type",
+            Command::Sleep(_) => "sleep",
+            Command::DisplayGeometry => "getdisplaygeometry",
+            Command::MouseLocation { .. } => "getmouselocation",
+            Command::ActiveWindow => "getactivewindow",
+            Command::WindowName(_) => "getwindowname",
+            Command::WindowClassName(_) => "getwindowclassname",
+            Command::MapWindow(_) => "windowmap",
+            Command::ActivateWindow { .. } => "windowactivate",
+        }
+    }
+}
+
+/// Parses a chain of xdotool commands. Returns `None` when any part of it is
+/// something this helper doesn't do, so none of it runs.
+pub fn parse(args: &[String]) -> Option<Vec<Command>> {
+    let mut commands = Vec::new();
+    let mut args = args.iter().map(String::as_str).peekable();
+    while let Some(command) = args.next() {
+        let parsed = match command {
+            "mousemove" => {
+                args.next_if_eq(&"--sync");
+                let x = args.next()?.parse().ok()?;
+                let y = args.next()?.parse().ok()?;
+                Command::MoveTo { x, y }
+            }
+            "click" => {
+                let mut repeat = 1;
+                let mut delay = DEFAULT_DELAY;
+                loop {
+                    match args.peek().copied() {
+                        Some("--repeat") => {
+                            args.next();
+                            repeat = args.next()?.parse().ok()?;
+                        }
+                        Some("--delay") => {
+                            args.next();
+                            delay = millis(args.next()?)?;
+                        }
+                        _ => break,
+                    }
+                }
+                let button = args.next()?.parse().ok()?;
+                Command::Click {
+                    button,
+                    repeat,
+                    delay,
+                }
+            }
+            "mousedown" | "mouseup" => Command::Button {
+                button: args.next()?.parse().ok()?,
+                press: command == "mousedown",
+            },
+            "key" | "keydown" | "keyup" => {
+                let action = match command {
+                    "keydown" => KeyAction::Press,
+                    "keyup" => KeyAction::Release,
+                    _ => KeyAction::Tap,
+                };
+                let mut repeat = 1;
+                let mut delay = DEFAULT_DELAY;
+                let mut clear_modifiers = false;
+                loop {
+                    match args.peek().copied() {
+                        Some("--repeat") => {
+                            args.next();
+                            repeat = args.next()?.parse().ok()?;
+                        }
+                        Some("--delay") => {
+                            args.next();
+                            delay = millis(args.next()?)?;
+                        }
+                        Some("--clearmodifiers") => {
+                            args.next();
+                            clear_modifiers = true;
+                        }
+                        _ => break,
+                    }
+                }
+                let mut chords = Vec::new();
+                while let Some(chord) = args.next_if(|arg| !COMMANDS.contains(arg)) {
+                    chords.push(chord.split('+').map(str::to_string).collect());
+                }
+                if chords.is_empty() {
+                    return None;
+                }
+                Command::Keys {
+                    chords,
+                    action,
+                    repeat,
+                    delay,
+                    clear_modifiers,
+                }
+            }
+            "type" => {
+                let mut delay = DEFAULT_DELAY;
+                let mut clear_modifiers = false;
+                let mut stdin = false;
+                loop {
+                    match args.peek().copied() {
+                        Some("--delay") => {
+                            args.next();
+                            delay = millis(args.next()?)?;
+                        }
+                        Some("--clearmodifiers") => {
+                            args.next();
+                            clear_modifiers = true;
+                        }
+                        Some("--file") => {
+                            args.next();
+                            if args.next()? != "-" {
+                                return None;
+                            }
+                            stdin = true;
+                        }
+                        Some("--") => {
+                            args.next();
+                            break;
+                        }
+                        _ => break,
+                    }
+                }
+                let text = if stdin {
+                    None
+                } else {
+                    // xdotool types the rest of the line, one argument after
+                    // another.
+                    Some(args.by_ref().collect::<String>())
+                };
+                Command::Type {
+                    text,
+                    delay,
+                    clear_modifiers,
+                }
+            }
+            "sleep" => {
+                let seconds: f64 = args.next()?.parse().ok()?;
+                Command::Sleep(Duration::try_from_secs_f64(seconds).ok()?)
+            }
+            "getdisplaygeometry" => Command::DisplayGeometry,
+            "getmouselocation" => Command::MouseLocation {
+                shell: args.next_if_eq(&"--shell").is_some(),
+            },
+            "getactivewindow" => Command::ActiveWindow,
+            "getwindowname" => Command::WindowName(window_id(args.next()?)?),
+            "getwindowclassname" => Command::WindowClassName(window_id(args.next()?)?),
+            "windowmap" => Command::MapWindow(window_id(args.next()?)?),
+            "windowactivate" => {
+                let sync = args.next_if_eq(&"--sync").is_some();
+                Command::ActivateWindow {
+                    window: window_id(args.next()?)?,
+                    sync,
+                }
+            }
+            _ => return None,
+        };
+        commands.push(parsed);
+    }
+    Some(commands)
+}
+
+fn millis(value: &str) -> Option<Duration> {
+    value.parse().ok().map(Duration::from_millis)
+}
+
+/// A window id, in decimal as `getactivewindow` prints it or in hex.
+fn window_id(value: &str) -> Option<u32> {
+    match value.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn args(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| (*word).to_string()).collect()
+    }
+
+    #[test]
+    fn parses_command_chains() {
+        assert_eq!(
+            parse(&args(&[
+                "mousemove",
+                "--sync",
+                "10",
+                "20",
+                "click",
+                "--repeat",
+                "2",
+                "1"
+            ])),
+            Some(vec![
+                Command::MoveTo { x: 10, y: 20 },
+                Command::Click {
+                    button: 1,
+                    repeat: 2,
+                    delay: DEFAULT_DELAY,
+                },
+            ])
+        );
+        assert_eq!(
+            parse(&args(&[
+                "key",
+                "--clearmodifiers",
+                "ctrl+l",
+                "Escape",
+                "sleep",
+                "0.5"
+            ])),
+            Some(vec![
+                Command::Keys {
+                    chords: vec![
+                        vec!["ctrl".to_string(), "l".to_string()],
+                        vec!["Escape".to_string()],
+                    ],
+                    action: KeyAction::Tap,
+                    repeat: 1,
+                    delay: DEFAULT_DELAY,
+                    clear_modifiers: true,
+                },
+                Command::Sleep(Duration::from_millis(500)),
+            ])
+        );
+        assert_eq!(
+            parse(&args(&["type", "--delay", "40", "--", "--not a flag"])),
+            Some(vec![Command::Type {
+                text: Some("--not a flag".to_string()),
+                delay: Duration::from_millis(40),
+                clear_modifiers: false,
+            }])
+        );
+        assert_eq!(
+            parse(&args(&[
+                "windowmap",
+                "42",
+                "windowactivate",
+                "--sync",
+                "0x2a"
+            ])),
+            Some(vec![
+                Command::MapWindow(42),
+                Command::ActivateWindow {
+                    window: 42,
+                    sync: true,
+                },
+            ])
+        );
+        assert_eq!(parse(&args(&["search", "--class", "firefox"])), None);
+        assert_eq!(parse(&args(&["key"])), None);
+        assert_eq!(parse(&args(&["type", "--file", "notes.txt"])), None);
+    }
+}
@@ -0,0 +1,206 @@
+//! Key names, X keysyms, and Linux key codes.
+
+/// Keysyms of the modifiers xdotool accepts by their short names.
+const CONTROL_L: u32 = 0xffe3;
+const SHIFT_L: u32 = 0xffe1;
+const ALT_L: u32 = 0xffe9;
+const META_L: u32 = 0xffe7;
+const SUPER_L: u32 = 0xffeb;
+
+/// Keysym names for keys that aren't a single character.
+const NAMED_KEYSYMS: &[(&str, u32)] = &[
+    ("BackSpace", 0xff08),
+    ("Tab", 0xff09),
+    ("Return", 0xff0d),
+    ("Pause", 0xff13),
+    ("Escape", 0xff1b),
+    ("Delete", 0xffff),
+    ("Home", 0xff50),
+    ("Left", 0xff51),
+    ("Up", 0xff52),
+    ("Right", 0xff53),
+    ("Down", 0xff54),
+    ("Prior", 0xff55),
+    ("Page_Up", 0xff55),
+    ("Next", 0xff56),
+    ("Page_Down", 0xff56),
+    ("End", 0xff57),
+    ("Print", 0xff61),
+    ("Insert", 0xff63),
+    ("Menu", 0xff67),
+    ("Shift_L", SHIFT_L),
+    ("Shift_R", 0xffe2),
+    ("Control_L", CONTROL_L),
+    ("Control_R", 0xffe4),
+    ("Caps_Lock", 0xffe5),
+    ("Meta_L", META_L),
+    ("Alt_L", ALT_L),
+    ("Alt_R", 0xffea),
+    ("Super_L", SUPER_L),
+    ("Super_R", 0xffec),
+    ("space", 0x20),
+    ("exclam", 0x21),
+    ("quotedbl", 0x22),
+    ("numbersign", 0x23),
+    ("dollar", 0x24),
+    ("percent", 0x25),
+    ("ampersand", 0x26),
+    ("apostrophe", 0x27),
+    ("parenleft", 0x28),
+    ("parenright", 0x29),
+    ("asterisk", 0x2a),
+    ("plus", 0x2b),
+    ("comma", 0x2c),
+    ("minus", 0x2d),
+    ("period", 0x2e),
+    ("slash", 0x2f),
+    ("colon", 0x3a),
+    ("semicolon", 0x3b),
+    ("less", 0x3c),
+    ("equal", 0x3d),
+    ("greater", 0x3e),
+    ("question", 0x3f),
+    ("at", 0x40),
+    ("bracketleft", 0x5b),
+    ("backslash", 0x5c),
+    ("bracketright", 0x5d),
+    ("asciicircum", 0x5e),
+    ("underscore", 0x5f),
+    ("grave", 0x60),
+    ("braceleft", 0x7b),
+    ("bar", 0x7c),
+    ("braceright", 0x7d),
+    ("asciitilde", 0x7e),
+];
+
+/// Linux key codes of keysyms outside the character rows.
+const NAMED_KEY_CODES: &[(u32, u16)] = &[
+    (0xff08, 14),
+    (0xff09, 15),
+    (0xff0d, 28),
+    (0xff13, 119),
+    (0xff1b, 1),
+    (0xffff, 111),
+    (0xff50, 102),
+    (0xff51, 105),
+    (0xff52, 103),
+    (0xff53, 106),
+    (0xff54, 108),
+    (0xff55, 104),
+    (0xff56, 109),
+    (0xff57, 107),
+    (0xff61, 99),
+    (0xff63, 110),
+    (0xff67, 127),
+    (SHIFT_L, 42),
+    (0xffe2, 54),
+    (CONTROL_L, 29),
+    (0xffe4, 97),
+    (0xffe5, 58),
+    (META_L, 125),
+    (ALT_L, 56),
+    (0xffea, 100),
+    (SUPER_L, 125),
+    (0xffec, 126),
+    (0x20, 57),
+];
+
+/// US layout character rows: unshifted, shifted, and the key code of the
+/// first key.
+const KEY_ROWS: [(&str, &str, u16); 4] = [
+    ("1234567890-=", "!@#$%^&*()_+", 2),
+    ("qwertyuiop[]", "QWERTYUIOP{}", 16),
+    ("asdfghjkl;'`", "ASDFGHJKL:\"~", 30),
+    ("\\zxcvbnm,./", "|ZXCVBNM<>?", 43),
+];
+
+/// The keysym xdotool would send for `name`: a modifier alias, a keysym
+/// name, `F1` to `F24`, or a single character.
+pub fn keysym(name: &str) -> Option<u32> {
+    match name.to_ascii_lowercase().as_str() {
+        "ctrl" | "control" => return Some(CONTROL_L),
+        "shift" => return Some(SHIFT_L),
+        "alt" => return Some(ALT_L),
+        "super" => return Some(SUPER_L),
+        "meta" => return Some(META_L),
+        _ => {}
+    }
+    if let Some(&(_, keysym)) = NAMED_KEYSYMS.iter().find(|(named, _)| *named == name) {
+        return Some(keysym);
+    }
+    if let Some(number) = name.strip_prefix('F')
+        && let Ok(number @ 1..=24) = number.parse::<u32>()
+    {
+        return Some(0xffbe + number - 1);
+    }
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => char_keysym(c),
+        _ => None,
+    }
+}
+
+/// The keysym that types `c`.
+pub fn char_keysym(c: char) -> Option<u32> {
+    match c {
+        '\n' => Some(0xff0d),
+        '\t' => Some(0xff09),
+        c if c.is_control() => None,
+        // Latin-1 keysyms equal their code points; the rest of Unicode is
+        // offset by 0x0100_0000.
+        c if u32::from(c) <= 0xff => Some(u32::from(c)),
+        c => Some(0x0100_0000 | u32::from(c)),
+    }
+}
+
+/// The Linux key code that produces `keysym` on a US layout, and whether
+/// Shift must be held for it.
+pub fn key_code(keysym: u32) -> Option<(u16, bool)> {
+    if let Some(&(_, code)) = NAMED_KEY_CODES.iter().find(|(named, _)| *named == keysym) {
+        return Some((code, false));
+    }
+    match keysym {
+        0xffbe..=0xffc7 => {
+            return u16::try_from(keysym - 0xffbe)
+                .ok()
+                .map(|index| (59 + index, false));
+        }
+        0xffc8 => return Some((87, false)),
+        0xffc9 => return Some((88, false)),
+        _ => {}
+    }
+    let c = char::from_u32(keysym).filter(char::is_ascii_graphic)?;
+    KEY_ROWS.iter().find_map(|(plain, shifted, first)| {
+        let (index, shift) = match plain.chars().position(|key| key == c) {
+            Some(index) => (index, false),
+            None => (shifted.chars().position(|key| key == c)?, true),
+        };
+        u16::try_from(index)
+            .ok()
+            .map(|index| (first + index, shift))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn maps_names_to_keysyms_and_key_codes() {
+        assert_eq!(keysym("Ctrl"), Some(CONTROL_L));
+        assert_eq!(keysym("Page_Down"), Some(0xff56));
+        assert_eq!(keysym("F12"), Some(0xffc9));
+        assert_eq!(keysym("minus"), Some(u32::from('-')));
+        assert_eq!(keysym("é"), Some(0xe9));
+        assert_eq!(keysym("€"), Some(0x0100_20ac));
+        assert_eq!(keysym("XF86AudioMute"), None);
+
+        assert_eq!(key_code(u32::from('a')), Some((30, false)));
+        assert_eq!(key_code(u32::from('A')), Some((30, true)));
+        assert_eq!(key_code(u32::from('?')), Some((53, true)));
+        assert_eq!(key_code(0xffbe + 4), Some((63, false)));
+        assert_eq!(key_code(0xff0d), Some((28, false)));
+        assert_eq!(key_code(0xe9), None);
+    }
+}
@@ -0,0 +1,274 @@
+//! Input injection for computex without xdotool.
+//!
+//! computex drives the GUI with xdotool command lines. This crate performs
+//! the subset computex sends on its common path itself: pointer and key
+//! input, typing, and the focus queries around them. It injects through
+//! XTEST when an X display is reachable and through `/dev/uinput`
+//! otherwise. The `computex-inject` binary speaks the X11 protocol itself and
+//! links only against the C library, so it needs no distro packages, and
+//! `computex setup` installs it under `$CODEX_HOME/bin`.
+
+#![deny(clippy::print_stdout)]
+
+mod command;
+mod keys;
+#[cfg(target_os = "linux")]
+mod uinput;
+mod xtest;
+
+use std::fmt;
+use std::io::Read;
+use std::io::Write;
+use std::thread;
+
+pub use command::Command;
+pub use command::KeyAction;
+pub use command::parse;
+pub use keys::char_keysym;
+pub use keys::key_code;
+pub use keys::keysym;
+
+/// Name of the helper binary.
+pub const HELPER_NAME: &str = "computex-inject";
+
+/// Exit status for command lines the helper can't run. Nothing was sent, so
+/// the caller can hand the same command line to xdotool.
+pub const UNSUPPORTED_EXIT_CODE: i32 = 2;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The command line asks for something this helper doesn't do.
+    Unsupported(String),
+    /// Input or a query failed, possibly partway through.
+    Failed(String),
+}
+
+impl Error {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Unsupported(_) => UNSUPPORTED_EXIT_CODE,
+            Error::Failed(_) => 1,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Unsupported(message) | Error::Failed(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// A way into the input stack: XTEST or uinput.
+trait Injector {
+    /// Whether `command` can run here, besides its keys.
+    fn supports(&self, command: &Command) -> bool;
+    /// Whether `keysym` can be sent.
+    fn can_send(&self, keysym: u32) -> bool;
+    fn move_to(&mut self, x: i32, y: i32) -> Result<(), Error>;
+    fn button(&mut self, button: u8, press: bool) -> Result<(), Error>;
+    /// Presses or releases the key for `keysym`, with Shift if it needs it.
+    fn key(&mut self, keysym: u32, press: bool) -> Result<(), Error>;
+    /// Releases the modifiers the user holds, returning what to press again.
+    fn clear_modifiers(&mut self) -> Result<Vec<u8>, Error>;
+    fn restore_modifiers(&mut self, held: &[u8]) -> Result<(), Error>;
+    /// Waits until everything sent so far has been processed.
+    fn flush(&mut self) -> Result<(), Error>;
+    /// Runs a query or window command, writing what xdotool would print.
+    fn query(&mut self, command: &Command, out: &mut dyn Write) -> Result<(), Error>;
+}
+
+/// Runs an xdotool command line, optionally preceded by `--screen WxH`,
+/// which uinput needs for absolute pointer moves.
+pub fn run(args: &[String], out: &mut impl Write) -> Result<(), Error> {
+    let (screen, args) = match args {
+        [flag, size, rest @ ..] if flag == "--screen" => (Some(screen_size(size)?), rest),
+        _ => (None, args),
+    };
+    let mut commands = parse(args).ok_or_else(|| {
+        Error::Unsupported(format!(
+            "can't run `{}`; use xdotool for it",
+            args.join(" ")
+        ))
+    })?;
+    for command in &mut commands {
+        if let Command::Type {
+            text: text @ None, ..
+        } = command
+        {
+            let mut stdin = String::new();
+            std::io::stdin()
+                .read_to_string(&mut stdin)
+                .map_err(|err| Error::Failed(format!("failed to read stdin: {err}")))?;
+            *text = Some(stdin);
+        }
+    }
+    let mut injector = open(screen)?;
+    check(injector.as_ref(), &commands)?;
+    for command in &commands {
+        execute(injector.as_mut(), command, out)?;
+    }
+    injector.flush()
+}
+
+fn screen_size(value: &str) -> Result<(u32, u32), Error> {
+    value
+        .split_once('x')
+        .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)))
+        .filter(|&(width, height)| width > 0 && height > 0)
+        .ok_or_else(|| Error::Failed(format!("--screen takes WIDTHxHEIGHT, not `{value}`")))
+}
+
+fn open(screen: Option<(u32, u32)>) -> Result<Box<dyn Injector>, Error> {
+    let x11 = match xtest::Xtest::open() {
+        Ok(injector) => return Ok(Box::new(injector)),
+        Err(err) => err,
+    };
+    #[cfg(target_os = "linux")]
+    {
+        match uinput::Uinput::open(screen) {
+            Ok(injector) => Ok(Box::new(injector)),
+            // Nothing was sent, so a provider with another way in can try.
+            Err(err) => Err(Error::Unsupported(format!("{x11}; {err}"))),
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = screen;
+        Err(Error::Unsupported(x11))
+    }
+}
+
+/// Rejects the whole command line up front if any of it can't be sent, so
+/// it is either replayed whole or left to xdotool.
+fn check(injector: &dyn Injector, commands: &[Command]) -> Result<(), Error> {
+    for command in commands {
+        if !injector.supports(command) {
+            return Err(Error::Unsupported(format!(
+                "can't run `{}` here; use xdotool for it",
+                command.name()
+            )));
+        }
+        let keysyms: Vec<(String, Option<u32>)> = match command {
+            Command::Keys { chords, .. } => chords
+                .iter()
+                .flatten()
+                .map(|name| (name.clone(), keys::keysym(name)))
+                .collect(),
+            Command::Type {
+                text: Some(text), ..
+            } => text
+                .chars()
+                .map(|c| (c.to_string(), keys::char_keysym(c)))
+                .collect(),
+            _ => Vec::new(),
+        };
+        for (name, keysym) in keysyms {
+            if !keysym.is_some_and(|keysym| injector.can_send(keysym)) {
+                return Err(Error::Unsupported(format!(
+                    "can't send the key `{name}`; use xdotool for it"
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn execute(
+    injector: &mut dyn Injector,
+    command: &Command,
+    out: &mut dyn Write,
+) -> Result<(), Error> {
+    match command {
+        Command::MoveTo { x, y } => injector.move_to(*x, *y),
+        Command::Click {
+            button,
+            repeat,
+            delay,
+        } => {
+            for index in 0..*repeat {
+                if index > 0 {
+                    injector.flush()?;
+                    thread::sleep(*delay);
+                }
+                injector.button(*button, true)?;
+                injector.button(*button, false)?;
+            }
+            Ok(())
+        }
+        Command::Button { button, press } => injector.button(*button, *press),
+        Command::Keys {
+            chords,
+            action,
+            repeat,
+            delay,
+            clear_modifiers,
+        } => {
+            let held = if *clear_modifiers {
+                injector.clear_modifiers()?
+            } else {
+                Vec::new()
+            };
+            let keysyms = |chord: &[String]| -> Vec<u32> {
+                chord.iter().filter_map(|name| keys::keysym(name)).collect()
+            };
+            let taps = (0..*repeat).flat_map(|_| chords.iter());
+            for (index, chord) in taps.enumerate() {
+                if index > 0 {
+                    injector.flush()?;
+                    thread::sleep(*delay);
+                }
+                let chord = keysyms(chord);
+                if *action != KeyAction::Release {
+                    for keysym in &chord {
+                        injector.key(*keysym, true)?;
+                    }
+                }
+                if *action != KeyAction::Press {
+                    for keysym in chord.iter().rev() {
+                        injector.key(*keysym, false)?;
+                    }
+                }
+            }
+            injector.restore_modifiers(&held)
+        }
+        Command::Type {
+            text,
+            delay,
+            clear_modifiers,
+        } => {
+            let held = if *clear_modifiers {
+                injector.clear_modifiers()?
+            } else {
+                Vec::new()
+            };
+            for c in text.as_deref().unwrap_or_default().chars() {
+                if let Some(keysym) = keys::char_keysym(c) {
+                    injector.key(keysym, true)?;
+                    injector.key(keysym, false)?;
+                    injector.flush()?;
+                    thread::sleep(*delay);
+                }
+            }
+            injector.restore_modifiers(&held)
+        }
+        Command::Sleep(duration) => {
+            injector.flush()?;
+            thread::sleep(*duration);
+            Ok(())
+        }
+        Command::DisplayGeometry
+        | Command::MouseLocation { .. }
+        | Command::ActiveWindow
+        | Command::WindowName(_)
+        | Command::WindowClassName(_)
+        | Command::MapWindow(_)
+        | Command::ActivateWindow { .. } => {
+            injector.flush()?;
+            injector.query(command, out)
+        }
+    }
+}
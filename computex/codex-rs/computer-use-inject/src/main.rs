@@ -0,0 +1,18 @@
+use std::env;
+use std::io;
+use std::process;
+
+use codex_computer_use_inject::HELPER_NAME;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() {
+        eprintln!("Usage: {HELPER_NAME} [--screen WIDTHxHEIGHT] <xdotool command>...");
+        process::exit(1);
+    }
+    let stdout = io::stdout();
+    if let Err(err) = codex_computer_use_inject::run(&args, &mut stdout.lock()) {
+        eprintln!("{HELPER_NAME}: {err}");
+        process::exit(err.exit_code());
+    }
+}
@@ -0,0 +1,368 @@
+//! Injecting through a virtual `/dev/uinput` device.
+//!
+//! The kernel accepts uinput events under any display server, including
+//! Wayland compositors without XTEST, as long as the user can write to
+//! `/dev/uinput`. The device is a keyboard plus an absolute pointer sized to
+//! the screen, so the compositor maps coordinates one to one. Keys follow a
+//! US layout, and there are no windows to ask about.
+
+use std::fs;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::mem::size_of;
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::OpenOptionsExt;
+use std::thread;
+use std::time::Duration;
+
+use crate::Command;
+use crate::Error;
+use crate::Injector;
+use crate::keys;
+
+const UINPUT_PATH: &str = "/dev/uinput";
+const DRM_PATH: &str = "/sys/class/drm";
+
+/// How long the compositor gets to pick up a new device before input is
+/// sent to it.
+const DEVICE_SETTLE: Duration = Duration::from_millis(200);
+
+// ioctl requests from <linux/uinput.h>.
+const UI_DEV_CREATE: libc::c_ulong = 0x5501;
+const UI_DEV_DESTROY: libc::c_ulong = 0x5502;
+const UI_DEV_SETUP: libc::c_ulong = 0x405c_5503;
+const UI_ABS_SETUP: libc::c_ulong = 0x401c_5504;
+const UI_SET_EVBIT: libc::c_ulong = 0x4004_5564;
+const UI_SET_KEYBIT: libc::c_ulong = 0x4004_5565;
+const UI_SET_RELBIT: libc::c_ulong = 0x4004_5566;
+const UI_SET_ABSBIT: libc::c_ulong = 0x4004_5567;
+
+// Event types and codes from <linux/input-event-codes.h>.
+const EV_SYN: u16 = 0x00;
+const EV_KEY: u16 = 0x01;
+const EV_REL: u16 = 0x02;
+const EV_ABS: u16 = 0x03;
+const SYN_REPORT: u16 = 0;
+const REL_HWHEEL: u16 = 0x06;
+const REL_WHEEL: u16 = 0x08;
+const ABS_X: u16 = 0x00;
+const ABS_Y: u16 = 0x01;
+const BTN_LEFT: u16 = 0x110;
+const BTN_RIGHT: u16 = 0x111;
+const BTN_MIDDLE: u16 = 0x112;
+const BTN_SIDE: u16 = 0x113;
+const BTN_EXTRA: u16 = 0x114;
+const KEY_LEFTSHIFT: u16 = 42;
+/// Keyboard keys the device declares.
+const KEY_MAX: u16 = 0xff;
+const BUS_VIRTUAL: u16 = 0x06;
+
+#[repr(C)]
+struct InputId {
+    bustype: u16,
+    vendor: u16,
+    product: u16,
+    version: u16,
+}
+
+#[repr(C)]
+struct UinputSetup {
+    id: InputId,
+    name: [u8; 80],
+    ff_effects_max: u32,
+}
+
+#[repr(C)]
+struct AbsInfo {
+    value: i32,
+    minimum: i32,
+    maximum: i32,
+    fuzz: i32,
+    flat: i32,
+    resolution: i32,
+}
+
+#[repr(C)]
+struct UinputAbsSetup {
+    code: u16,
+    absinfo: AbsInfo,
+}
+
+#[repr(C)]
+struct InputEvent {
+    time: libc::timeval,
+    kind: u16,
+    code: u16,
+    value: i32,
+}
+
+pub(crate) struct Uinput {
+    device: File,
+    /// Screen size, without which the pointer can't be placed.
+    screen: Option<(u32, u32)>,
+}
+
+impl Uinput {
+    /// Creates the device. Without `screen`, the pointer is sized to the
+    /// first connected display.
+    pub(crate) fn open(screen: Option<(u32, u32)>) -> Result<Self, String> {
+        let screen = screen.or_else(drm_screen);
+        let device = OpenOptions::new()
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(UINPUT_PATH)
+            .map_err(|err| format!("cannot open {UINPUT_PATH}: {err}"))?;
+        let uinput = Self { device, screen };
+        uinput.create()?;
+        thread::sleep(DEVICE_SETTLE);
+        Ok(uinput)
+    }
+
+    fn create(&self) -> Result<(), String> {
+        let set = |request, value: u16| self.ioctl_value(request, value);
+        set(UI_SET_EVBIT, EV_KEY)?;
+        set(UI_SET_EVBIT, EV_REL)?;
+        set(UI_SET_EVBIT, EV_SYN)?;
+        for key in 1..=KEY_MAX {
+            set(UI_SET_KEYBIT, key)?;
+        }
+        for button in [BTN_LEFT, BTN_RIGHT, BTN_MIDDLE, BTN_SIDE, BTN_EXTRA] {
+            set(UI_SET_KEYBIT, button)?;
+        }
+        set(UI_SET_RELBIT, REL_WHEEL)?;
+        set(UI_SET_RELBIT, REL_HWHEEL)?;
+        if let Some((width, height)) = self.screen {
+            set(UI_SET_EVBIT, EV_ABS)?;
+            for (axis, size) in [(ABS_X, width), (ABS_Y, height)] {
+                set(UI_SET_ABSBIT, axis)?;
+                let setup = UinputAbsSetup {
+                    code: axis,
+                    absinfo: AbsInfo {
+                        value: 0,
+                        minimum: 0,
+                        maximum: i32::try_from(size - 1).map_err(|err| err.to_string())?,
+                        fuzz: 0,
+                        flat: 0,
+                        resolution: 0,
+                    },
+                };
+                self.ioctl_struct(UI_ABS_SETUP, &setup)?;
+            }
+        }
+        let mut name = [0; 80];
+        let label = crate::HELPER_NAME.as_bytes();
+        name[..label.len()].copy_from_slice(label);
+        let setup = UinputSetup {
+            id: InputId {
+                bustype: BUS_VIRTUAL,
+                vendor: 0,
+                product: 0,
+                version: 1,
+            },
+            name,
+            ff_effects_max: 0,
+        };
+        self.ioctl_struct(UI_DEV_SETUP, &setup)?;
+        self.ioctl_value(UI_DEV_CREATE, 0)
+    }
+
+    fn ioctl_value(&self, request: libc::c_ulong, value: u16) -> Result<(), String> {
+        // SAFETY: the uinput requests used with this take an int by value.
+        let result = unsafe {
+            libc::ioctl(
+                self.device.as_raw_fd(),
+                request as _,
+                libc::c_int::from(value),
+            )
+        };
+        check(result)
+    }
+
+    fn ioctl_struct<T>(&self, request: libc::c_ulong, value: &T) -> Result<(), String> {
+        // SAFETY: `value` is the `repr(C)` struct `request` reads, and it
+        // outlives the call.
+        let result = unsafe {
+            libc::ioctl(
+                self.device.as_raw_fd(),
+                request as _,
+                std::ptr::from_ref(value),
+            )
+        };
+        check(result)
+    }
+
+    fn emit(&mut self, kind: u16, code: u16, value: i32) -> Result<(), Error> {
+        let event = InputEvent {
+            time: libc::timeval {
+                tv_sec: 0,
+                tv_usec: 0,
+            },
+            kind,
+            code,
+            value,
+        };
+        // SAFETY: `InputEvent` is `repr(C)` plain data, read here as the
+        // bytes the kernel expects.
+        let bytes = unsafe {
+            std::slice::from_raw_parts(
+                std::ptr::from_ref(&event).cast::<u8>(),
+                size_of::<InputEvent>(),
+            )
+        };
+        self.device
+            .write_all(bytes)
+            .map_err(|err| Error::Failed(format!("failed to write to {UINPUT_PATH}: {err}")))
+    }
+
+    fn report(&mut self) -> Result<(), Error> {
+        self.emit(EV_SYN, SYN_REPORT, 0)
+    }
+
+    fn press(&mut self, code: u16, press: bool) -> Result<(), Error> {
+        self.emit(EV_KEY, code, i32::from(press))?;
+        self.report()
+    }
+}
+
+/// The preferred mode of the first connected display, from sysfs.
+fn drm_screen() -> Option<(u32, u32)> {
+    let mut connectors: Vec<_> = fs::read_dir(DRM_PATH)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .collect();
+    connectors.sort();
+    connectors.iter().find_map(|connector| {
+        let status = fs::read_to_string(connector.join("status")).ok()?;
+        if status.trim() != "connected" {
+            return None;
+        }
+        let modes = fs::read_to_string(connector.join("modes")).ok()?;
+        parse_mode(modes.lines().next()?)
+    })
+}
+
+/// `1920x1080`, or `1920x1080i` for an interlaced mode.
+fn parse_mode(mode: &str) -> Option<(u32, u32)> {
+    let (width, height) = mode.split_once('x')?;
+    let height = height.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    Some((width.parse().ok()?, height.parse().ok()?))
+}
+
+fn check(result: libc::c_int) -> Result<(), String> {
+    if result < 0 {
+        Err(format!(
+            "uinput setup failed: {}",
+            std::io::Error::last_os_error()
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+impl Injector for Uinput {
+    fn supports(&self, command: &Command) -> bool {
+        match command {
+            Command::MoveTo { .. } => self.screen.is_some(),
+            Command::Click { button, .. } | Command::Button { button, .. } => {
+                matches!(button, 1..=9)
+            }
+            Command::Keys { .. } | Command::Type { .. } | Command::Sleep(_) => true,
+            _ => false,
+        }
+    }
+
+    fn can_send(&self, keysym: u32) -> bool {
+        keys::key_code(keysym).is_some()
+    }
+
+    fn move_to(&mut self, x: i32, y: i32) -> Result<(), Error> {
+        self.emit(EV_ABS, ABS_X, x)?;
+        self.emit(EV_ABS, ABS_Y, y)?;
+        self.report()
+    }
+
+    fn button(&mut self, button: u8, press: bool) -> Result<(), Error> {
+        let code = match button {
+            1 => BTN_LEFT,
+            2 => BTN_MIDDLE,
+            3 => BTN_RIGHT,
+            8 => BTN_SIDE,
+            9 => BTN_EXTRA,
+            4..=7 => {
+                // X wheel buttons scroll once per press.
+                if !press {
+                    return Ok(());
+                }
+                let (axis, value) = match button {
+                    4 => (REL_WHEEL, 1),
+                    5 => (REL_WHEEL, -1),
+                    6 => (REL_HWHEEL, -1),
+                    _ => (REL_HWHEEL, 1),
+                };
+                self.emit(EV_REL, axis, value)?;
+                return self.report();
+            }
+            _ => return Err(Error::Unsupported(format!("no mouse button {button}"))),
+        };
+        self.press(code, press)
+    }
+
+    fn key(&mut self, keysym: u32, press: bool) -> Result<(), Error> {
+        let (code, shift) = keys::key_code(keysym)
+            .ok_or_else(|| Error::Unsupported(format!("no key for keysym {keysym:#x}")))?;
+        if shift && press {
+            self.press(KEY_LEFTSHIFT, true)?;
+        }
+        self.press(code, press)?;
+        if shift && !press {
+            self.press(KEY_LEFTSHIFT, false)?;
+        }
+        Ok(())
+    }
+
+    fn clear_modifiers(&mut self) -> Result<Vec<u8>, Error> {
+        // The keyboard state isn't readable from here; nothing is held by
+        // this device.
+        Ok(Vec::new())
+    }
+
+    fn restore_modifiers(&mut self, _held: &[u8]) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn query(&mut self, command: &Command, _out: &mut dyn Write) -> Result<(), Error> {
+        Err(Error::Unsupported(format!(
+            "`{}` needs an X display",
+            command.name()
+        )))
+    }
+}
+
+impl Drop for Uinput {
+    fn drop(&mut self) {
+        // Let the last events through before the device goes away.
+        thread::sleep(DEVICE_SETTLE);
+        let _ = self.ioctl_value(UI_DEV_DESTROY, 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn reads_display_modes() {
+        assert_eq!(parse_mode("1920x1080"), Some((1920, 1080)));
+        assert_eq!(parse_mode("1920x1080i"), Some((1920, 1080)));
+        assert_eq!(parse_mode("preferred"), None);
+        assert_eq!(size_of::<UinputSetup>(), 92);
+        assert_eq!(size_of::<UinputAbsSetup>(), 28);
+    }
+}
@@ -0,0 +1,388 @@
+//! Injecting through the XTEST extension.
+//!
+//! Keys are found in the server's keyboard mapping, with Shift for those on
+//! the second level. Characters the layout lacks are typed the way xdotool
+//! types them: an unused keycode is bound to the character for the keypress
+//! and unbound again on exit.
+
+use std::io::Write;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use x11rb::connection::Connection;
+use x11rb::connection::RequestConnection;
+use x11rb::protocol::xproto;
+use x11rb::protocol::xproto::AtomEnum;
+use x11rb::protocol::xproto::ClientMessageEvent;
+use x11rb::protocol::xproto::ConnectionExt as _;
+use x11rb::protocol::xproto::EventMask;
+use x11rb::protocol::xtest;
+use x11rb::protocol::xtest::ConnectionExt as _;
+use x11rb::rust_connection::RustConnection;
+
+use crate::Command;
+use crate::Error;
+use crate::Injector;
+
+/// How long apps get to pick up a remapped keycode before it is pressed.
+const REMAP_SETTLE: Duration = Duration::from_millis(20);
+
+/// How long `windowactivate --sync` waits for the window manager.
+const ACTIVATE_TIMEOUT: Duration = Duration::from_secs(2);
+const ACTIVATE_POLL: Duration = Duration::from_millis(20);
+
+const SHIFT_L: u32 = 0xffe1;
+
+fn x_error(err: impl std::fmt::Display) -> Error {
+    Error::Failed(format!("X request failed: {err}"))
+}
+
+pub(crate) struct Xtest {
+    conn: RustConnection,
+    screen: usize,
+    root: xproto::Window,
+    size: (u16, u16),
+    min_keycode: u8,
+    keysyms_per_keycode: usize,
+    /// The keyboard mapping, `keysyms_per_keycode` keysyms per keycode.
+    keysyms: Vec<u32>,
+    /// A keycode with nothing bound, for characters the layout lacks.
+    spare: Option<u8>,
+    /// What is bound to `spare` right now.
+    remapped: Option<u32>,
+    net_active_window: xproto::Atom,
+    net_wm_name: xproto::Atom,
+    utf8_string: xproto::Atom,
+}
+
+impl Xtest {
+    /// Connects to `$DISPLAY`, failing if it is unreachable or lacks XTEST.
+    pub(crate) fn open() -> Result<Self, String> {
+        let (conn, screen) =
+            x11rb::connect(None).map_err(|err| format!("cannot open X display: {err}"))?;
+        let has_xtest = conn
+            .extension_information(xtest::X11_EXTENSION_NAME)
+            .map_err(|err| err.to_string())?
+            .is_some();
+        if !has_xtest {
+            return Err("X display lacks the XTEST extension".to_string());
+        }
+        let setup = conn.setup();
+        let (root, size) = setup
+            .roots
+            .get(screen)
+            .map(|root| (root.root, (root.width_in_pixels, root.height_in_pixels)))
+            .ok_or_else(|| format!("X display has no screen {screen}"))?;
+        let (min_keycode, max_keycode) = (setup.min_keycode, setup.max_keycode);
+        let mapping = conn
+            .get_keyboard_mapping(min_keycode, max_keycode - min_keycode + 1)
+            .map_err(|err| err.to_string())?
+            .reply()
+            .map_err(|err| err.to_string())?;
+        let atom = |name: &[u8]| -> Result<xproto::Atom, String> {
+            Ok(conn
+                .intern_atom(false, name)
+                .map_err(|err| err.to_string())?
+                .reply()
+                .map_err(|err| err.to_string())?
+                .atom)
+        };
+        let net_active_window = atom(b"_NET_ACTIVE_WINDOW")?;
+        let net_wm_name = atom(b"_NET_WM_NAME")?;
+        let utf8_string = atom(b"UTF8_STRING")?;
+        let keysyms_per_keycode = usize::from(mapping.keysyms_per_keycode.max(1));
+        let spare = (min_keycode..=max_keycode)
+            .zip(mapping.keysyms.chunks(keysyms_per_keycode))
+            .rev()
+            .find(|(_, keysyms)| keysyms.iter().all(|keysym| *keysym == 0))
+            .map(|(keycode, _)| keycode);
+        Ok(Self {
+            conn,
+            screen,
+            root,
+            size,
+            min_keycode,
+            keysyms_per_keycode,
+            keysyms: mapping.keysyms,
+            spare,
+            remapped: None,
+            net_active_window,
+            net_wm_name,
+            utf8_string,
+        })
+    }
+
+    /// The keycode that sends `keysym`, and whether it needs Shift.
+    fn keycode(&self, keysym: u32) -> Option<(u8, bool)> {
+        if self.remapped == Some(keysym) {
+            return self.spare.map(|spare| (spare, false));
+        }
+        for (level, shift) in [(0, false), (1, true)] {
+            let found = self
+                .keysyms
+                .chunks(self.keysyms_per_keycode)
+                .position(|keysyms| keysyms.get(level) == Some(&keysym));
+            if let Some(index) = found
+                && let Ok(offset) = u8::try_from(index)
+            {
+                return Some((self.min_keycode + offset, shift));
+            }
+        }
+        None
+    }
+
+    fn fake(&self, kind: u8, detail: u8, x: i16, y: i16) -> Result<(), Error> {
+        self.conn
+            .xtest_fake_input(kind, detail, x11rb::CURRENT_TIME, self.root, x, y, 0)
+            .map_err(x_error)?;
+        Ok(())
+    }
+
+    fn fake_key(&self, keycode: u8, press: bool) -> Result<(), Error> {
+        let kind = if press {
+            xproto::KEY_PRESS_EVENT
+        } else {
+            xproto::KEY_RELEASE_EVENT
+        };
+        self.fake(kind, keycode, 0, 0)
+    }
+
+    /// Binds `keysym` to the spare keycode.
+    fn remap(&mut self, keysym: u32) -> Result<u8, Error> {
+        let spare = self
+            .spare
+            .ok_or_else(|| Error::Unsupported("the keyboard has no unused keycode".to_string()))?;
+        let keysyms = vec![keysym; self.keysyms_per_keycode];
+        self.bind(spare, &keysyms)?;
+        self.remapped = Some(keysym);
+        thread::sleep(REMAP_SETTLE);
+        Ok(spare)
+    }
+
+    fn bind(&self, keycode: u8, keysyms: &[u32]) -> Result<(), Error> {
+        let per_keycode = u8::try_from(keysyms.len()).map_err(x_error)?;
+        self.conn
+            .change_keyboard_mapping(1, keycode, per_keycode, keysyms)
+            .map_err(x_error)?;
+        self.round_trip()
+    }
+
+    fn round_trip(&self) -> Result<(), Error> {
+        self.conn
+            .get_input_focus()
+            .map_err(x_error)?
+            .reply()
+            .map_err(x_error)?;
+        Ok(())
+    }
+
+    fn active_window(&self) -> Result<Option<xproto::Window>, Error> {
+        let active = self
+            .conn
+            .get_property(
+                false,
+                self.root,
+                self.net_active_window,
+                AtomEnum::WINDOW,
+                0,
+                1,
+            )
+            .map_err(x_error)?
+            .reply()
+            .map_err(x_error)?;
+        Ok(active
+            .value32()
+            .and_then(|mut ids| ids.next())
+            .filter(|id| *id != 0))
+    }
+
+    fn property(
+        &self,
+        window: xproto::Window,
+        property: impl Into<xproto::Atom>,
+        kind: impl Into<xproto::Atom>,
+    ) -> Result<Vec<u8>, Error> {
+        Ok(self
+            .conn
+            .get_property(false, window, property, kind, 0, 1024)
+            .map_err(x_error)?
+            .reply()
+            .map_err(x_error)?
+            .value)
+    }
+}
+
+impl Injector for Xtest {
+    fn supports(&self, _command: &Command) -> bool {
+        true
+    }
+
+    fn can_send(&self, keysym: u32) -> bool {
+        match self.keycode(keysym) {
+            Some((_, shift)) => !shift || self.keycode(SHIFT_L).is_some(),
+            None => self.spare.is_some(),
+        }
+    }
+
+    fn move_to(&mut self, x: i32, y: i32) -> Result<(), Error> {
+        let narrow = |value: i32| {
+            i16::try_from(value).map_err(|_| Error::Failed(format!("{x},{y} is off screen")))
+        };
+        self.fake(xproto::MOTION_NOTIFY_EVENT, 0, narrow(x)?, narrow(y)?)
+    }
+
+    fn button(&mut self, button: u8, press: bool) -> Result<(), Error> {
+        let kind = if press {
+            xproto::BUTTON_PRESS_EVENT
+        } else {
+            xproto::BUTTON_RELEASE_EVENT
+        };
+        self.fake(kind, button, 0, 0)
+    }
+
+    fn key(&mut self, keysym: u32, press: bool) -> Result<(), Error> {
+        let (keycode, shift) = match self.keycode(keysym) {
+            Some(found) => found,
+            None => (self.remap(keysym)?, false),
+        };
+        let shift = if shift { self.keycode(SHIFT_L) } else { None };
+        match shift {
+            Some((shift, _)) if press => {
+                self.fake_key(shift, true)?;
+                self.fake_key(keycode, true)
+            }
+            Some((shift, _)) => {
+                self.fake_key(keycode, false)?;
+                self.fake_key(shift, false)
+            }
+            None => self.fake_key(keycode, press),
+        }
+    }
+
+    fn clear_modifiers(&mut self) -> Result<Vec<u8>, Error> {
+        let pressed = self
+            .conn
+            .query_keymap()
+            .map_err(x_error)?
+            .reply()
+            .map_err(x_error)?
+            .keys;
+        let modifiers = self
+            .conn
+            .get_modifier_mapping()
+            .map_err(x_error)?
+            .reply()
+            .map_err(x_error)?
+            .keycodes;
+        let mut held: Vec<u8> = modifiers
+            .into_iter()
+            .filter(|&keycode| {
+                keycode != 0 && pressed[usize::from(keycode / 8)] & (1 << (keycode % 8)) != 0
+            })
+            .collect();
+        held.dedup();
+        for keycode in &held {
+            self.fake_key(*keycode, false)?;
+        }
+        Ok(held)
+    }
+
+    fn restore_modifiers(&mut self, held: &[u8]) -> Result<(), Error> {
+        for keycode in held {
+            self.fake_key(*keycode, true)?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.round_trip()
+    }
+
+    fn query(&mut self, command: &Command, out: &mut dyn Write) -> Result<(), Error> {
+        let text = match command {
+            Command::DisplayGeometry => format!("{} {}", self.size.0, self.size.1),
+            Command::MouseLocation { shell } => {
+                let pointer = self
+                    .conn
+                    .query_pointer(self.root)
+                    .map_err(x_error)?
+                    .reply()
+                    .map_err(x_error)?;
+                let (x, y, screen, window) =
+                    (pointer.root_x, pointer.root_y, self.screen, pointer.child);
+                if *shell {
+                    format!("X={x}\nY={y}\nSCREEN={screen}\nWINDOW={window}")
+                } else {
+                    format!("x:{x} y:{y} screen:{screen} window:{window}")
+                }
+            }
+            Command::ActiveWindow => self
+                .active_window()?
+                .ok_or_else(|| Error::Failed("no window is active".to_string()))?
+                .to_string(),
+            Command::WindowName(window) => {
+                let mut name = self.property(*window, self.net_wm_name, self.utf8_string)?;
+                if name.is_empty() {
+                    name = self.property(*window, AtomEnum::WM_NAME, AtomEnum::ANY)?;
+                }
+                String::from_utf8_lossy(&name).into_owned()
+            }
+            Command::WindowClassName(window) => {
+                // WM_CLASS is "instance\0class\0"; xdotool prints the class.
+                let class = self.property(*window, AtomEnum::WM_CLASS, AtomEnum::STRING)?;
+                class
+                    .split(|byte| *byte == 0)
+                    .nth(1)
+                    .map(|class| String::from_utf8_lossy(class).into_owned())
+                    .unwrap_or_default()
+            }
+            Command::MapWindow(window) => {
+                self.conn.map_window(*window).map_err(x_error)?;
+                return self.round_trip();
+            }
+            Command::ActivateWindow { window, sync } => {
+                // Source 2 asks the window manager to treat the request as
+                // coming from the user, as xdotool does.
+                let event = ClientMessageEvent::new(
+                    32,
+                    *window,
+                    self.net_active_window,
+                    [2, x11rb::CURRENT_TIME, 0, 0, 0],
+                );
+                self.conn
+                    .send_event(
+                        false,
+                        self.root,
+                        EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+                        event,
+                    )
+                    .map_err(x_error)?;
+                self.round_trip()?;
+                if *sync {
+                    let started = Instant::now();
+                    while self.active_window()? != Some(*window) {
+                        if started.elapsed() > ACTIVATE_TIMEOUT {
+                            return Err(Error::Failed(format!(
+                                "window {window} did not become active"
+                            )));
+                        }
+                        thread::sleep(ACTIVATE_POLL);
+                    }
+                }
+                return Ok(());
+            }
+            _ => return Ok(()),
+        };
+        writeln!(out, "{text}").map_err(|err| Error::Failed(err.to_string()))
+    }
+}
+
+impl Drop for Xtest {
+    fn drop(&mut self) {
+        if let (Some(spare), Some(_)) = (self.spare, self.remapped) {
+            let unbound = vec![0; self.keysyms_per_keycode];
+            let _ = self.bind(spare, &unbound);
+        }
+    }
+}
@@ -102,7 +102,9 @@ use crate::protocol::ErrorEvent;
 use crate::protocol::Event;
 use crate::protocol::EventMsg;
 use crate::protocol::ExecApprovalRequestEvent;
+use crate::protocol::GuiActionQueueReviewEvent;
 use crate::protocol::Op;
+use crate::protocol::QueuedGuiAction;
 use crate::protocol::RateLimitSnapshot;
 use crate::protocol::ReasoningContentDeltaEvent;
 use crate::protocol::ReasoningRawContentDeltaEvent;
@@ -137,6 +139,9 @@ use crate::tasks::SessionTask;
 use crate::tasks::SessionTaskContext;
 use crate::tools::ToolRouter;
 use crate::tools::context::SharedTurnDiffTracker;
+use crate::tools::handlers::computer_use::ComputerUseState;
+use crate::tools::handlers::computer_use::restore_context as restore_computer_use_context;
+use crate::tools::handlers::computer_use::summarize_turn;
 use crate::tools::parallel::ToolCallRuntime;
 use crate::tools::sandboxing::ApprovalStore;
 use crate::tools::spec::ToolsConfig;
@@ -509,7 +514,8 @@ impl Session {
         let tools_config = ToolsConfig::new(&ToolsConfigParams {
             model_family: &model_family,
             features: &per_turn_config.features,
-        });
+        })
+        .with_computer_use(&per_turn_config.computer_use);
 
         TurnContext {
             sub_id,
@@ -666,6 +672,7 @@ impl Session {
             models_manager: Arc::clone(&models_manager),
             tool_approvals: Mutex::new(ApprovalStore::default()),
             skills_manager,
+            computer_use: Mutex::new(ComputerUseState::default()),
         };
 
         let sess = Arc::new(Session {
@@ -733,6 +740,10 @@ impl Session {
         self.tx_event.clone()
     }
 
+    pub(crate) fn conversation_id(&self) -> ConversationId {
+        self.conversation_id
+    }
+
     /// Ensure all rollout writes are durably flushed.
     pub(crate) async fn flush_rollout(&self) {
         let recorder = {
@@ -807,6 +818,7 @@ impl Session {
                     self.record_into_history(&reconstructed_history, &turn_context)
                         .await;
                 }
+                restore_computer_use_context(self, &turn_context, &rollout_items).await;
 
                 // If persisting, persist all rollout items as-is (recorder filters)
                 if persist && !rollout_items.is_empty() {
@@ -1147,6 +1159,67 @@ impl Session {
         }
     }
 
+    /// Emit a GUI action queue review event and await the user's verdict:
+    /// the actions to run (possibly edited), or `None` if the queue was
+    /// rejected. Like command approvals, the request is keyed by `sub_id`, and
+    /// an aborted task resolves to `None`.
+    pub async fn request_gui_queue_review(
+        &self,
+        turn_context: &TurnContext,
+        call_id: String,
+        actions: Vec<QueuedGuiAction>,
+    ) -> Option<Vec<QueuedGuiAction>> {
+        let sub_id = turn_context.sub_id.clone();
+        let (tx_review, rx_review) = oneshot::channel();
+        let event_id = sub_id.clone();
+        let prev_entry = {
+            let mut active = self.active_turn.lock().await;
+            match active.as_mut() {
+                Some(at) => {
+                    let mut ts = at.turn_state.lock().await;
+                    ts.insert_pending_gui_queue_review(sub_id, tx_review)
+                }
+                None => None,
+            }
+        };
+        if prev_entry.is_some() {
+            warn!("Overwriting existing pending GUI queue review for sub_id: {event_id}");
+        }
+
+        let event = EventMsg::GuiActionQueueReview(GuiActionQueueReviewEvent {
+            call_id,
+            turn_id: turn_context.sub_id.clone(),
+            actions,
+        });
+        self.send_event(turn_context, event).await;
+        rx_review.await.unwrap_or_default()
+    }
+
+    pub async fn notify_gui_queue_review(
+        &self,
+        sub_id: &str,
+        actions: Option<Vec<QueuedGuiAction>>,
+    ) {
+        let entry = {
+            let mut active = self.active_turn.lock().await;
+            match active.as_mut() {
+                Some(at) => {
+                    let mut ts = at.turn_state.lock().await;
+                    ts.remove_pending_gui_queue_review(sub_id)
+                }
+                None => None,
+            }
+        };
+        match entry {
+            Some(tx_review) => {
+                tx_review.send(actions).ok();
+            }
+            None => {
+                warn!("No pending GUI queue review found for sub_id: {sub_id}");
+            }
+        }
+    }
+
     pub async fn resolve_elicitation(
         &self,
         server_name: String,
@@ -1374,7 +1447,13 @@ impl Session {
             let state = self.state.lock().await;
             state.token_info_and_rate_limits()
         };
-        let event = EventMsg::TokenCount(TokenCountEvent { info, rate_limits });
+        let gui_usage =
+            Some(self.services.computer_use.lock().await.usage()).filter(|usage| !usage.is_zero());
+        let event = EventMsg::TokenCount(TokenCountEvent {
+            info,
+            rate_limits,
+            gui_usage,
+        });
         self.send_event(turn_context, event).await;
     }
 
@@ -1619,6 +1698,9 @@ async fn submission_loop(sess: Arc<Session>, config: Arc<Config>, rx_sub: Receiv
             Op::PatchApproval { id, decision } => {
                 handlers::patch_approval(&sess, id, decision).await;
             }
+            Op::ResolveGuiActionQueue { id, actions } => {
+                handlers::resolve_gui_action_queue(&sess, id, actions).await;
+            }
             Op::AddToHistory { text } => {
                 handlers::add_to_history(&sess, &config, text).await;
             }
@@ -1687,6 +1769,7 @@ mod handlers {
     use crate::tasks::RegularTask;
     use crate::tasks::UndoTask;
     use crate::tasks::UserShellCommandTask;
+    use crate::tools::handlers::computer_use::take_deferred_input as take_deferred_computer_use_input;
     use codex_protocol::custom_prompts::CustomPrompt;
     use codex_protocol::protocol::CodexErrorInfo;
     use codex_protocol::protocol::ErrorEvent;
@@ -1695,6 +1778,7 @@ mod handlers {
     use codex_protocol::protocol::ListCustomPromptsResponseEvent;
     use codex_protocol::protocol::ListSkillsResponseEvent;
     use codex_protocol::protocol::Op;
+    use codex_protocol::protocol::QueuedGuiAction;
     use codex_protocol::protocol::ReviewDecision;
     use codex_protocol::protocol::ReviewRequest;
     use codex_protocol::protocol::SkillsListEntry;
@@ -1772,6 +1856,16 @@ mod handlers {
             .get_otel_manager()
             .user_prompt(&items);
 
+        // Screenshots captured after the last task ended go in ahead of the
+        // user's message.
+        let mut deferred = take_deferred_computer_use_input(sess).await;
+        let items = if deferred.is_empty() {
+            items
+        } else {
+            deferred.extend(items);
+            deferred
+        };
+
         // Attempt to inject input into current task
         if let Err(items) = sess.inject_input(items).await {
             if let Some(env_item) =
@@ -1865,6 +1959,14 @@ mod handlers {
         }
     }
 
+    pub async fn resolve_gui_action_queue(
+        sess: &Arc<Session>,
+        id: String,
+        actions: Option<Vec<QueuedGuiAction>>,
+    ) {
+        sess.notify_gui_queue_review(&id, actions).await;
+    }
+
     pub async fn add_to_history(sess: &Arc<Session>, config: &Arc<Config>, text: String) {
         let id = sess.conversation_id;
         let config = Arc::clone(config);
@@ -2098,7 +2200,8 @@ async fn spawn_review_thread(
     let tools_config = ToolsConfig::new(&ToolsConfigParams {
         model_family: &review_model_family,
         features: &review_features,
-    });
+    })
+    .with_computer_use(&config.computer_use);
 
     let base_instructions = REVIEW_PROMPT.to_string();
     let review_prompt = resolved.prompt.clone();
@@ -2299,6 +2402,9 @@ pub(crate) async fn run_task(
                     needs_follow_up,
                     last_agent_message: turn_last_agent_message,
                 } = turn_output;
+                if needs_follow_up {
+                    summarize_turn(&sess, &turn_context.tools_config.computer_use).await;
+                }
                 let total_usage_tokens = sess.get_total_token_usage().await;
                 let token_limit_reached = total_usage_tokens >= auto_compact_limit;
 
@@ -3143,6 +3249,7 @@ mod tests {
             models_manager,
             tool_approvals: Mutex::new(ApprovalStore::default()),
             skills_manager,
+            computer_use: Mutex::new(ComputerUseState::default()),
         };
 
         let turn_context = Session::make_turn_context(
@@ -3230,6 +3337,7 @@ mod tests {
             models_manager,
             tool_approvals: Mutex::new(ApprovalStore::default()),
             skills_manager,
+            computer_use: Mutex::new(ComputerUseState::default()),
         };
 
         let turn_context = Arc::new(Session::make_turn_context(
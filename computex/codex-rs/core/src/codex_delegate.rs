@@ -33,8 +33,10 @@ use codex_protocol::protocol::InitialHistory;
 /// The returned `events_rx` yields non-approval events emitted by the sub-agent.
 /// Approval requests are handled via `parent_session` and are not surfaced.
 /// The returned `ops_tx` allows the caller to submit additional `Op`s to the sub-agent.
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn run_codex_conversation_interactive(
     config: Config,
+    source: SubAgentSource,
     auth_manager: Arc<AuthManager>,
     models_manager: Arc<ModelsManager>,
     parent_session: Arc<Session>,
@@ -51,7 +53,7 @@ pub(crate) async fn run_codex_conversation_interactive(
         models_manager,
         Arc::clone(&parent_session.services.skills_manager),
         initial_history.unwrap_or(InitialHistory::New),
-        SessionSource::SubAgent(SubAgentSource::Review),
+        SessionSource::SubAgent(source),
     )
     .await?;
     let codex = Arc::new(codex);
@@ -95,6 +97,7 @@ pub(crate) async fn run_codex_conversation_interactive(
 #[allow(clippy::too_many_arguments)]
 pub(crate) async fn run_codex_conversation_one_shot(
     config: Config,
+    source: SubAgentSource,
     auth_manager: Arc<AuthManager>,
     models_manager: Arc<ModelsManager>,
     input: Vec<UserInput>,
@@ -108,6 +111,7 @@ pub(crate) async fn run_codex_conversation_one_shot(
     let child_cancel = cancel_token.child_token();
     let io = run_codex_conversation_interactive(
         config,
+        source,
         auth_manager,
         models_manager,
         parent_session,
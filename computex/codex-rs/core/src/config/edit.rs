@@ -1,6 +1,8 @@
 use crate::config::CONFIG_TOML_FILE;
+use crate::config::types::CoordinateCalibration;
 use crate::config::types::McpServerConfig;
 use crate::config::types::Notice;
+use crate::protocol::AskForApproval;
 use anyhow::Context;
 use codex_protocol::config_types::TrustLevel;
 use codex_protocol::openai_models::ReasoningEffort;
@@ -664,6 +666,63 @@ impl ConfigEditsBuilder {
         self
     }
 
+    pub fn set_approval_policy(mut self, policy: AskForApproval) -> Self {
+        self.edits.push(ConfigEdit::SetPath {
+            segments: vec!["approval_policy".to_string()],
+            value: value(policy.to_string()),
+        });
+        self
+    }
+
+    /// Replace `computer_use.blocked_apps`, removing the key when `apps` is empty.
+    pub fn set_computer_use_blocked_apps(mut self, apps: &[String]) -> Self {
+        let segments = vec!["computer_use".to_string(), "blocked_apps".to_string()];
+        if apps.is_empty() {
+            self.edits.push(ConfigEdit::ClearPath { segments });
+        } else {
+            let apps = apps.iter().cloned().collect::<toml_edit::Array>();
+            self.edits.push(ConfigEdit::SetPath {
+                segments,
+                value: value(apps),
+            });
+        }
+        self
+    }
+
+    /// Replace `computer_use.calibration`, removing it when `calibration` is
+    /// `None`.
+    pub fn set_computer_use_calibration(
+        mut self,
+        calibration: Option<&CoordinateCalibration>,
+    ) -> Self {
+        let segments = vec!["computer_use".to_string(), "calibration".to_string()];
+        match calibration {
+            Some(calibration) => {
+                let mut table = toml_edit::InlineTable::new();
+                table.insert("screen", calibration.screen.to_string().into());
+                table.insert("scale_x", calibration.scale_x.into());
+                table.insert("offset_x", calibration.offset_x.into());
+                table.insert("scale_y", calibration.scale_y.into());
+                table.insert("offset_y", calibration.offset_y.into());
+                self.edits.push(ConfigEdit::SetPath {
+                    segments,
+                    value: value(table),
+                });
+            }
+            None => self.edits.push(ConfigEdit::ClearPath { segments }),
+        }
+        self
+    }
+
+    /// Record that the computex first-run setup has been completed or skipped.
+    pub fn set_computer_use_setup_completed(mut self, completed: bool) -> Self {
+        self.edits.push(ConfigEdit::SetPath {
+            segments: vec!["computer_use".to_string(), "setup_completed".to_string()],
+            value: value(completed),
+        });
+        self
+    }
+
     pub fn with_edits<I>(mut self, edits: I) -> Self
     where
         I: IntoIterator<Item = ConfigEdit>,
@@ -719,6 +778,61 @@ model_reasoning_effort = "high"
         assert_eq!(contents, expected);
     }
 
+    #[test]
+    fn builder_records_computer_use_setup() {
+        let tmp = tempdir().expect("tmpdir");
+        let codex_home = tmp.path();
+
+        ConfigEditsBuilder::new(codex_home)
+            .set_approval_policy(AskForApproval::UnlessTrusted)
+            .set_computer_use_blocked_apps(&["firefox".to_string(), "xterm".to_string()])
+            .set_computer_use_setup_completed(true)
+            .apply_blocking()
+            .expect("persist");
+
+        let contents =
+            std::fs::read_to_string(codex_home.join(CONFIG_TOML_FILE)).expect("read config");
+        let expected = r#"approval_policy = "untrusted"
+
+[computer_use]
+blocked_apps = ["firefox", "xterm"]
+setup_completed = true
+"#;
+        assert_eq!(contents, expected);
+
+        let calibration = CoordinateCalibration {
+            screen: crate::config::types::Resolution {
+                width: 1920,
+                height: 1080,
+            },
+            scale_x: 0.5,
+            offset_x: 0.0,
+            scale_y: 1.0,
+            offset_y: -28.0,
+        };
+        ConfigEditsBuilder::new(codex_home)
+            .set_computer_use_calibration(Some(&calibration))
+            .apply_blocking()
+            .expect("persist");
+        let contents =
+            std::fs::read_to_string(codex_home.join(CONFIG_TOML_FILE)).expect("read config");
+        let parsed: crate::config::ConfigToml = toml::from_str(&contents).expect("parse config");
+        assert_eq!(
+            parsed
+                .computer_use
+                .and_then(|computer_use| computer_use.calibration),
+            Some(calibration)
+        );
+
+        ConfigEditsBuilder::new(codex_home)
+            .set_computer_use_calibration(None)
+            .apply_blocking()
+            .expect("persist");
+        let contents =
+            std::fs::read_to_string(codex_home.join(CONFIG_TOML_FILE)).expect("read config");
+        assert!(!contents.contains("calibration"));
+    }
+
     #[test]
     fn builder_with_edits_applies_custom_paths() {
         let tmp = tempdir().expect("tmpdir");
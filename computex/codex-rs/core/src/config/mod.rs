@@ -1,4 +1,6 @@
 use crate::auth::AuthCredentialsStoreMode;
+use crate::config::types::ComputerUseConfig;
+use crate::config::types::ComputerUseToml;
 use crate::config::types::DEFAULT_OTEL_ENVIRONMENT;
 use crate::config::types::History;
 use crate::config::types::McpServerConfig;
@@ -355,6 +357,9 @@ pub struct Config {
 
     /// OTEL configuration (exporter type, endpoint, headers, etc.).
     pub otel: crate::config::types::OtelConfig,
+
+    /// Settings for the computer-use GUI tools.
+    pub computer_use: ComputerUseConfig,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -477,6 +482,26 @@ pub async fn load_config_as_toml_with_cli_overrides(
     Ok(cfg)
 }
 
+/// Resolves the computer-use settings for launchers that need them before a
+/// full [`Config`] is built, e.g. to render the computer-use base instructions.
+pub async fn load_computer_use_config(
+    codex_home: &Path,
+    cwd: &AbsolutePathBuf,
+    cli_overrides: Vec<(String, TomlValue)>,
+    config_profile: Option<String>,
+) -> std::io::Result<ComputerUseConfig> {
+    let cfg = load_config_as_toml_with_cli_overrides(codex_home, cwd, cli_overrides).await?;
+    let profile = cfg.get_config_profile(config_profile)?;
+    let mut computer_use: ComputerUseConfig = cfg
+        .computer_use
+        .unwrap_or_default()
+        .merge(profile.computer_use)
+        .into();
+    computer_use.recipes = crate::computer_use_recipes::load(codex_home);
+    computer_use.plugins = crate::computer_use_plugins::load(codex_home);
+    Ok(computer_use)
+}
+
 fn deserialize_config_toml_with_base(
     root_value: TomlValue,
     config_base_dir: &Path,
@@ -816,6 +841,9 @@ pub struct ConfigToml {
     /// OTEL configuration.
     pub otel: Option<crate::config::types::OtelConfigToml>,
 
+    /// Settings for the computer-use GUI tools.
+    pub computer_use: Option<ComputerUseToml>,
+
     /// Tracks whether the Windows onboarding screen has been acknowledged.
     pub windows_wsl_setup_acknowledged: Option<bool>,
 
@@ -1311,6 +1339,8 @@ impl Config {
         constrained_sandbox_policy
             .set(sandbox_policy)
             .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("{e}")))?;
+        let recipes = crate::computer_use_recipes::load(&codex_home);
+        let plugins = crate::computer_use_plugins::load(&codex_home);
 
         let config = Self {
             model,
@@ -1433,6 +1463,15 @@ impl Config {
                     trace_exporter,
                 }
             },
+            computer_use: ComputerUseConfig {
+                recipes,
+                plugins,
+                ..cfg
+                    .computer_use
+                    .unwrap_or_default()
+                    .merge(config_profile.computer_use)
+                    .into()
+            },
         };
         Ok(config)
     }
@@ -1536,6 +1575,7 @@ mod tests {
     use crate::config::edit::ConfigEdit;
     use crate::config::edit::ConfigEditsBuilder;
     use crate::config::edit::apply_blocking;
+    use crate::config::types::CoordinateSpace;
     use crate::config::types::HistoryPersistence;
     use crate::config::types::McpServerTransportConfig;
     use crate::config::types::Notifications;
@@ -1890,6 +1930,45 @@ trust_level = "trusted"
         Ok(())
     }
 
+    #[test]
+    fn profile_computer_use_overrides_base() -> std::io::Result<()> {
+        let codex_home = TempDir::new()?;
+        let cfg: ConfigToml = toml::from_str(
+            r#"
+profile = "kiosk"
+
+[computer_use]
+coordinate_space = "normalized"
+display = ":0"
+
+[profiles.kiosk.computer_use]
+display = ":1"
+allowed_apps = ["firefox"]
+confirm_destructive_keys = false
+"#,
+        )
+        .expect("TOML deserialization should succeed");
+
+        let config = Config::load_from_base_config_with_overrides(
+            cfg,
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )?;
+
+        assert_eq!(
+            config.computer_use,
+            ComputerUseConfig {
+                coordinate_space: CoordinateSpace::Normalized,
+                display: Some(":1".to_string()),
+                allowed_apps: vec!["firefox".to_string()],
+                confirm_destructive_keys: false,
+                ..Default::default()
+            }
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn profile_sandbox_mode_overrides_base() -> std::io::Result<()> {
         let codex_home = TempDir::new()?;
@@ -3212,6 +3291,7 @@ model_verbosity = "high"
                 tui_scroll_wheel_like_max_duration_ms: None,
                 tui_scroll_invert: false,
                 otel: OtelConfig::default(),
+                computer_use: ComputerUseConfig::default(),
             },
             o3_profile_config
         );
@@ -3295,6 +3375,7 @@ model_verbosity = "high"
             tui_scroll_wheel_like_max_duration_ms: None,
             tui_scroll_invert: false,
             otel: OtelConfig::default(),
+            computer_use: ComputerUseConfig::default(),
         };
 
         assert_eq!(expected_gpt3_profile_config, gpt3_profile_config);
@@ -3393,6 +3474,7 @@ model_verbosity = "high"
             tui_scroll_wheel_like_max_duration_ms: None,
             tui_scroll_invert: false,
             otel: OtelConfig::default(),
+            computer_use: ComputerUseConfig::default(),
         };
 
         assert_eq!(expected_zdr_profile_config, zdr_profile_config);
@@ -3477,6 +3559,7 @@ model_verbosity = "high"
             tui_scroll_wheel_like_max_duration_ms: None,
             tui_scroll_invert: false,
             otel: OtelConfig::default(),
+            computer_use: ComputerUseConfig::default(),
         };
 
         assert_eq!(expected_gpt5_profile_config, gpt5_profile_config);
@@ -32,6 +32,8 @@ pub struct ConfigProfile {
     /// Optional feature toggles scoped to this profile.
     #[serde(default)]
     pub features: Option<crate::features::FeaturesToml>,
+    /// Computer-use settings scoped to this profile; merged over the top-level `[computer_use]`.
+    pub computer_use: Option<crate::config::types::ComputerUseToml>,
     pub oss_provider: Option<String>,
 }
 
@@ -3,9 +3,13 @@
 // Note this file should generally be restricted to simple struct/enum
 // definitions that do not contain business logic.
 
+use crate::tools::handlers::computer_use::confinement;
+use crate::tools::handlers::computer_use::plugins::Plugin;
+use crate::tools::handlers::computer_use::recipes::Recipe;
 use codex_utils_absolute_path::AbsolutePathBuf;
 use std::collections::BTreeMap;
 use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::time::Duration;
 use wildmatch::WildMatchPattern;
@@ -16,6 +20,12 @@ use serde::Serialize;
 use serde::de::Error as SerdeError;
 
 pub const DEFAULT_OTEL_ENVIRONMENT: &str = "dev";
+pub const DEFAULT_COMPUTER_USE_BREADCRUMBS: usize = 3;
+pub const DEFAULT_STUCK_GRAB_TIMEOUT_SECS: u64 = 30;
+pub const DEFAULT_OPERATOR_SCREENSHOT_KEY: &str = "ctrl+alt+s";
+pub const DEFAULT_SCREENSHOT_NAME: &str = "{session}/{turn}-{seq}.png";
+pub const DEFAULT_DELEGATE_MAX_ACTIONS: u32 = 50;
+pub const DEFAULT_DELEGATE_TIMEOUT_SECS: u64 = 600;
 
 #[derive(Serialize, Debug, Clone, PartialEq)]
 pub struct McpServerConfig {
@@ -350,6 +360,1560 @@ impl Default for OtelConfig {
     }
 }
 
+// ===== Computer-use configuration =====
+
+/// Coordinate space used by the computer-use tools for both the arguments they
+/// accept and the coordinates they echo back.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CoordinateSpace {
+    /// Virtual canvas sized by `computer_use.resolution` (1280x720 by default)
+    /// matching the scaled screenshots.
+    #[default]
+    #[serde(alias = "virtual_1280x720")]
+    Virtual,
+    /// Fractions of the screen width and height in the range `0.0..=1.0`.
+    Normalized,
+    /// Physical display pixels; screenshots are captured without scaling.
+    Native,
+}
+
+/// Width and height in pixels, written as `"<width>x<height>"` in config.toml.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub struct Resolution {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for Resolution {
+    fn default() -> Self {
+        Self {
+            width: 1280,
+            height: 720,
+        }
+    }
+}
+
+impl std::fmt::Display for Resolution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+impl std::str::FromStr for Resolution {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid resolution `{s}`; expected WIDTHxHEIGHT, e.g. 1280x720");
+        let (width, height) = s.trim().split_once(['x', 'X']).ok_or_else(invalid)?;
+        let width: u32 = width.trim().parse().map_err(|_| invalid())?;
+        let height: u32 = height.trim().parse().map_err(|_| invalid())?;
+        if width == 0 || height == 0 {
+            return Err(invalid());
+        }
+        Ok(Self { width, height })
+    }
+}
+
+impl TryFrom<String> for Resolution {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Resolution> for String {
+    fn from(value: Resolution) -> Self {
+        value.to_string()
+    }
+}
+
+/// Correction `computex calibrate` measured for one screen size: a click
+/// meant for screen pixel `(x, y)` is sent to
+/// `(scale_x * x + offset_x, scale_y * y + offset_y)`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CoordinateCalibration {
+    /// Screen (or viewport) size it was measured on. It is ignored on any
+    /// other size, since a resolution change invalidates it.
+    pub screen: Resolution,
+    pub scale_x: f64,
+    pub offset_x: f64,
+    pub scale_y: f64,
+    pub offset_y: f64,
+}
+
+impl CoordinateCalibration {
+    pub fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            self.scale_x * x + self.offset_x,
+            self.scale_y * y + self.offset_y,
+        )
+    }
+
+    /// Whether it was measured on a screen of this size.
+    pub fn matches(&self, width: f64, height: f64) -> bool {
+        f64::from(self.screen.width) == width.round()
+            && f64::from(self.screen.height) == height.round()
+    }
+}
+
+/// How `computer_type` checks that the text arrived in the focused field.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TypingVerification {
+    /// Trust xdotool.
+    #[default]
+    Off,
+    /// Select the typed line, copy it and compare the clipboard.
+    Clipboard,
+    /// Look for the typed line on screen with tesseract.
+    Ocr,
+}
+
+/// Kind of personal data removed from OCR text before the model sees it.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OcrScrubber {
+    /// Email addresses.
+    Email,
+    /// Payment card numbers that pass the Luhn check.
+    CardNumber,
+    /// Phone numbers of ten or more digits, or eight or more after a `+`.
+    Phone,
+}
+
+/// All scrubbers, the default for `computer_use.ocr_scrubbers`.
+pub const DEFAULT_OCR_SCRUBBERS: [OcrScrubber; 3] = [
+    OcrScrubber::Email,
+    OcrScrubber::CardNumber,
+    OcrScrubber::Phone,
+];
+
+/// Local UI element detection model for `computer_detect_elements`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct ElementDetector {
+    /// ONNX object detection model in the YOLO export layout: one RGB input
+    /// of `input_size` pixels square, and one output holding each box's
+    /// center, size, and per-class scores.
+    pub model: PathBuf,
+    /// Class names in the model's output order, such as `"button"`.
+    #[serde(default)]
+    pub labels: Vec<String>,
+    /// Side of the square input the model was trained on. Defaults to `640`.
+    pub input_size: Option<u32>,
+}
+
+/// A way `computer_locate` can find an element, tried in the configured
+/// order.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LocatorStrategy {
+    /// The AT-SPI accessibility tree of the focused application.
+    Accessibility,
+    /// On-screen text read with tesseract.
+    Ocr,
+    /// A saved PNG template of the element.
+    Template,
+    /// The configured `element_detector` model.
+    Detector,
+}
+
+/// The default order of `computer_use.locator_strategies`, from exact to
+/// approximate.
+pub const DEFAULT_LOCATOR_STRATEGIES: [LocatorStrategy; 4] = [
+    LocatorStrategy::Accessibility,
+    LocatorStrategy::Ocr,
+    LocatorStrategy::Template,
+    LocatorStrategy::Detector,
+];
+
+/// A way to send GUI input, tried in the order of
+/// `computer_use.input_providers`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InputProvider {
+    /// XTEST over the session's own X connection, while `persistent_input`
+    /// is on.
+    Native,
+    /// The `computex-inject` helper that `computex setup` installs, which
+    /// injects through XTEST or `/dev/uinput` without distro packages.
+    Bundled,
+    /// The `xdotool` command.
+    Xdotool,
+    /// The `ydotool` command, which injects through `/dev/uinput` and needs
+    /// `ydotoold` running.
+    Ydotool,
+    /// A session of the xdg remote desktop portal, the way out of a Flatpak
+    /// or Snap sandbox. The desktop asks the user before the first one.
+    Portal,
+    /// `input-send-event` on the QMP socket of the `qemu` backend's VM.
+    Qmp,
+}
+
+impl std::fmt::Display for InputProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            InputProvider::Native => "native",
+            InputProvider::Bundled => "bundled",
+            InputProvider::Xdotool => "xdotool",
+            InputProvider::Ydotool => "ydotool",
+            InputProvider::Portal => "portal",
+            InputProvider::Qmp => "qmp",
+        })
+    }
+}
+
+/// The default order of `computer_use.input_providers`.
+pub const DEFAULT_INPUT_PROVIDERS: [InputProvider; 4] = [
+    InputProvider::Native,
+    InputProvider::Bundled,
+    InputProvider::Xdotool,
+    InputProvider::Ydotool,
+];
+
+/// The default order of `computer_use.input_providers` inside a Flatpak or
+/// Snap sandbox, where xdotool and `/dev/uinput` are usually out of reach.
+pub const CONFINED_INPUT_PROVIDERS: [InputProvider; 5] = [
+    InputProvider::Native,
+    InputProvider::Bundled,
+    InputProvider::Portal,
+    InputProvider::Xdotool,
+    InputProvider::Ydotool,
+];
+
+/// QMP for the `qemu` backend. For `x11`, [`CONFINED_INPUT_PROVIDERS`] when
+/// computex runs confined, and [`DEFAULT_INPUT_PROVIDERS`] otherwise.
+pub fn default_input_providers(backend: ComputerUseBackend) -> Vec<InputProvider> {
+    if backend == ComputerUseBackend::Qemu {
+        return vec![InputProvider::Qmp];
+    }
+    match confinement::detect() {
+        Some(_) => CONFINED_INPUT_PROVIDERS.to_vec(),
+        None => DEFAULT_INPUT_PROVIDERS.to_vec(),
+    }
+}
+
+/// How an output is rotated, named as `xrandr --rotate` names it.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Rotation {
+    #[default]
+    Normal,
+    /// Turned 90 degrees counter-clockwise.
+    Left,
+    /// Turned 180 degrees.
+    Inverted,
+    /// Turned 90 degrees clockwise.
+    Right,
+}
+
+impl Rotation {
+    /// Whether the rotation swaps width and height.
+    pub fn is_sideways(self) -> bool {
+        matches!(self, Rotation::Left | Rotation::Right)
+    }
+}
+
+/// When the pointer is moved out of the way, for `computer_use.park_cursor`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CursorParking {
+    /// The pointer stays where the last action left it.
+    #[default]
+    Off,
+    /// After every pointer action (click, drag, scroll, and gestures).
+    AfterAction,
+    /// Before each `computer_screenshot`.
+    BeforeScreenshot,
+}
+
+/// Corner of the screen, or of the viewport, the pointer is parked in.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ScreenCorner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+}
+
+/// How hover effects are cleared for a capture, for
+/// `computer_use.hover_suppression`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum HoverSuppression {
+    /// Captures show whatever the pointer hovers.
+    #[default]
+    Off,
+    /// Move the pointer into `park_cursor_corner` for the capture, then back.
+    MoveAway,
+    /// Tell the windows under the pointer that it left, without moving it.
+    LeaveEvent,
+}
+
+/// How much detail GUI tool results carry, for
+/// `computer_use.result_verbosity`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ResultVerbosity {
+    /// Input actions that succeed answer just `ok`, and errors keep only
+    /// their first line.
+    Terse,
+    /// Coordinates, focus recovery notes, and error messages.
+    #[default]
+    Normal,
+    /// Also the input sent and how, the window it went to, the arguments
+    /// and exit status of failed commands, and how long each call took.
+    Debug,
+}
+
+/// What `computer_screenshot` gives the model, for
+/// `computer_use.screenshot_format`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ScreenshotFormat {
+    /// The captured image.
+    #[default]
+    Image,
+    /// The text on screen and where it is, read with OCR, with no image
+    /// attached. For models without image input.
+    Text,
+}
+
+/// A way to capture the screen, tried in the order of
+/// `computer_use.screenshot_providers`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScreenshotProvider {
+    /// `GetImage` on the root window over computex's own X connection.
+    Native,
+    /// The `grim` command, in wlroots Wayland sessions.
+    Grim,
+    /// ImageMagick's `import` command.
+    Import,
+    /// The `scrot` command.
+    Scrot,
+    /// The xdg screenshot portal, the way out of a Flatpak or Snap sandbox.
+    Portal,
+    /// `screendump` on the QMP socket of the `qemu` backend's VM.
+    Qmp,
+}
+
+/// The default order of `computer_use.screenshot_providers`.
+pub const DEFAULT_SCREENSHOT_PROVIDERS: [ScreenshotProvider; 4] = [
+    ScreenshotProvider::Native,
+    ScreenshotProvider::Grim,
+    ScreenshotProvider::Import,
+    ScreenshotProvider::Scrot,
+];
+
+/// The default order of `computer_use.screenshot_providers` inside a
+/// Flatpak or Snap sandbox, where the capture commands are missing.
+pub const CONFINED_SCREENSHOT_PROVIDERS: [ScreenshotProvider; 5] = [
+    ScreenshotProvider::Native,
+    ScreenshotProvider::Portal,
+    ScreenshotProvider::Grim,
+    ScreenshotProvider::Import,
+    ScreenshotProvider::Scrot,
+];
+
+/// QMP for the `qemu` backend. For `x11`, [`CONFINED_SCREENSHOT_PROVIDERS`]
+/// when computex runs confined, and [`DEFAULT_SCREENSHOT_PROVIDERS`]
+/// otherwise.
+pub fn default_screenshot_providers(backend: ComputerUseBackend) -> Vec<ScreenshotProvider> {
+    if backend == ComputerUseBackend::Qemu {
+        return vec![ScreenshotProvider::Qmp];
+    }
+    match confinement::detect() {
+        Some(_) => CONFINED_SCREENSHOT_PROVIDERS.to_vec(),
+        None => DEFAULT_SCREENSHOT_PROVIDERS.to_vec(),
+    }
+}
+
+/// Rectangle of the X screen the GUI tools are confined to, written as
+/// `"<width>x<height>+<x>+<y>"` in config.toml like an X geometry.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub struct Viewport {
+    pub width: u32,
+    pub height: u32,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl std::fmt::Display for Viewport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x{}+{}+{}", self.width, self.height, self.x, self.y)
+    }
+}
+
+impl std::str::FromStr for Viewport {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            || format!("invalid viewport `{s}`; expected WIDTHxHEIGHT+X+Y, e.g. 1280x720+1920+0");
+        let mut parts = s.trim().split('+');
+        let size: Resolution = parts
+            .next()
+            .unwrap_or_default()
+            .parse()
+            .map_err(|_| invalid())?;
+        let mut offset = || -> Result<u32, String> {
+            parts
+                .next()
+                .ok_or_else(invalid)?
+                .trim()
+                .parse()
+                .map_err(|_| invalid())
+        };
+        let (x, y) = (offset()?, offset()?);
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+        Ok(Self {
+            width: size.width,
+            height: size.height,
+            x,
+            y,
+        })
+    }
+}
+
+impl TryFrom<String> for Viewport {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Viewport> for String {
+    fn from(value: Viewport) -> Self {
+        value.to_string()
+    }
+}
+
+/// Remote machine driven over SSH, written as
+/// `"ssh://[user@]host[:port][/:display]"` in config.toml. The display is
+/// the X display on that machine and defaults to `:0`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub struct RemoteTarget {
+    pub user: Option<String>,
+    pub host: String,
+    pub port: Option<u16>,
+    pub display: u32,
+}
+
+impl RemoteTarget {
+    /// `user@host`, or `host`, as `ssh` takes it.
+    pub fn destination(&self) -> String {
+        match &self.user {
+            Some(user) => format!("{user}@{}", self.host),
+            None => self.host.clone(),
+        }
+    }
+}
+
+impl std::fmt::Display for RemoteTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ssh://{}", self.destination())?;
+        if let Some(port) = self.port {
+            write!(f, ":{port}")?;
+        }
+        if self.display != 0 {
+            write!(f, "/:{}", self.display)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for RemoteTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            || format!("invalid target `{s}`; expected ssh://[user@]host[:port][/:display]");
+        let url = url::Url::parse(s.trim()).map_err(|_| invalid())?;
+        if url.scheme() != "ssh" || url.password().is_some() || url.query().is_some() {
+            return Err(invalid());
+        }
+        let host = url
+            .host_str()
+            .filter(|host| !host.is_empty())
+            .ok_or_else(invalid)?
+            .to_string();
+        let display = match url.path().trim_start_matches('/') {
+            "" => 0,
+            path => path
+                .strip_prefix(':')
+                .and_then(|number| number.parse().ok())
+                .ok_or_else(invalid)?,
+        };
+        Ok(Self {
+            user: Some(url.username())
+                .filter(|user| !user.is_empty())
+                .map(str::to_string),
+            host,
+            port: url.port(),
+            display,
+        })
+    }
+}
+
+impl TryFrom<String> for RemoteTarget {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<RemoteTarget> for String {
+    fn from(value: RemoteTarget) -> Self {
+        value.to_string()
+    }
+}
+
+/// Text console the `console` backend drives, written as `"tmux:<pane>"` or
+/// `"serial:<device>[@<baud>]"` in config.toml. The pane is any tmux target
+/// such as `installer:0.1`, and the baud rate defaults to 115200.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub enum ConsoleTarget {
+    Tmux { pane: String },
+    Serial { device: PathBuf, baud: u32 },
+}
+
+/// Baud rate of a serial console that doesn't name one.
+pub const DEFAULT_SERIAL_BAUD: u32 = 115_200;
+
+impl std::fmt::Display for ConsoleTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConsoleTarget::Tmux { pane } => write!(f, "tmux:{pane}"),
+            ConsoleTarget::Serial { device, baud } => {
+                write!(f, "serial:{}", device.display())?;
+                if *baud != DEFAULT_SERIAL_BAUD {
+                    write!(f, "@{baud}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for ConsoleTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            || format!("invalid console `{s}`; expected tmux:<pane> or serial:<device>[@<baud>]");
+        let (kind, rest) = s.trim().split_once(':').ok_or_else(invalid)?;
+        if rest.is_empty() {
+            return Err(invalid());
+        }
+        match kind {
+            "tmux" => Ok(Self::Tmux {
+                pane: rest.to_string(),
+            }),
+            "serial" => {
+                let (device, baud) = match rest.rsplit_once('@') {
+                    Some((device, baud)) => (
+                        device,
+                        baud.parse()
+                            .ok()
+                            .filter(|baud| *baud > 0)
+                            .ok_or_else(invalid)?,
+                    ),
+                    None => (rest, DEFAULT_SERIAL_BAUD),
+                };
+                if device.is_empty() {
+                    return Err(invalid());
+                }
+                Ok(Self::Serial {
+                    device: PathBuf::from(device),
+                    baud,
+                })
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl TryFrom<String> for ConsoleTarget {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<ConsoleTarget> for String {
+    fn from(value: ConsoleTarget) -> Self {
+        value.to_string()
+    }
+}
+
+/// Mechanism the computer-use tools use to capture the screen and send input.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ComputerUseBackend {
+    /// X11 via `xdotool` for input and ImageMagick `import` for screenshots.
+    #[default]
+    X11,
+    /// The console of a local QEMU/KVM virtual machine, over its QMP socket
+    /// (`computer_use.qmp_socket`).
+    Qemu,
+    /// A text console, a tmux pane or a serial port
+    /// (`computer_use.console`), read as text instead of pixels.
+    Console,
+}
+
+/// Operating system of the machine the computer-use tools drive. Decides how
+/// named shortcuts such as `copy` and the `cmd` modifier are pressed.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TargetOs {
+    Linux,
+    Macos,
+    Windows,
+}
+
+impl Default for TargetOs {
+    /// The OS computex itself runs on, since it normally runs inside the
+    /// machine it drives.
+    fn default() -> Self {
+        if cfg!(target_os = "macos") {
+            Self::Macos
+        } else if cfg!(windows) {
+            Self::Windows
+        } else {
+            Self::Linux
+        }
+    }
+}
+
+impl std::fmt::Display for TargetOs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TargetOs::Linux => f.write_str("Linux"),
+            TargetOs::Macos => f.write_str("macOS"),
+            TargetOs::Windows => f.write_str("Windows"),
+        }
+    }
+}
+
+/// Approval rule for a class of GUI actions with indirect side effects, such
+/// as typing into a terminal, which the command sandbox can't contain.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GuiSideEffectPolicy {
+    /// Allow when the sandbox policy already grants full disk write and
+    /// network access. Otherwise ask, or deny when the approval policy is
+    /// `never`.
+    #[default]
+    Auto,
+    Allow,
+    Ask,
+    Deny,
+}
+
+/// Hypervisor whose command-line tool snapshots and restores the target VM.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum VmProvider {
+    /// libvirt (KVM/QEMU) via `virsh`.
+    Libvirt,
+    /// VirtualBox via `VBoxManage`.
+    Virtualbox,
+}
+
+impl std::fmt::Display for VmProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VmProvider::Libvirt => f.write_str("libvirt"),
+            VmProvider::Virtualbox => f.write_str("virtualbox"),
+        }
+    }
+}
+
+/// VM that computex snapshots before risky GUI sequences.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VmTarget {
+    pub provider: VmProvider,
+    /// libvirt domain or VirtualBox VM name.
+    pub name: String,
+}
+
+/// Computer-use settings loaded from config.toml. Fields are optional so we can apply defaults.
+///
+/// The same table may appear under `[profiles.<name>.computer_use]`, in which
+/// case the profile's fields take precedence over the top-level ones.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct ComputerUseToml {
+    /// Coordinate space for GUI tool arguments and results. Defaults to `virtual`.
+    pub coordinate_space: Option<CoordinateSpace>,
+
+    /// Size of the virtual canvas screenshots are scaled to. Defaults to `1280x720`.
+    pub resolution: Option<Resolution>,
+
+    /// Capture/input backend. Defaults to `x11`.
+    pub backend: Option<ComputerUseBackend>,
+
+    /// QMP socket of the VM the `qemu` backend drives, as passed to
+    /// `-qmp unix:<path>,server,wait=off`. Required for that backend.
+    pub qmp_socket: Option<PathBuf>,
+
+    /// Rotation the VM's guest applies to its display, e.g. `left` for a
+    /// portrait kiosk. QEMU's console shows the framebuffer unrotated, so
+    /// screenshots are turned upright with it. Defaults to `normal`.
+    pub qmp_rotation: Option<Rotation>,
+
+    /// Console the `console` backend drives, as `"tmux:<pane>"` or
+    /// `"serial:<device>[@<baud>]"`. Required for that backend.
+    pub console: Option<ConsoleTarget>,
+
+    /// OS of the driven machine (`linux`, `macos`, or `windows`), used to
+    /// translate named shortcuts and `cmd` in `computer_key`. Defaults to the
+    /// OS computex runs on.
+    pub target_os: Option<TargetOs>,
+
+    /// X11 display the GUI tools target (for example `:1`). Defaults to `$DISPLAY`.
+    pub display: Option<String>,
+
+    /// Remote machine to drive, as `"ssh://[user@]host[:port][/:display]"`.
+    /// Its X display is forwarded over SSH for the GUI tools, and shell
+    /// commands run there too. Unset by default, which drives this machine.
+    pub target: Option<RemoteTarget>,
+
+    /// Window classes (`WM_CLASS`) GUI input may be sent to. Unset or empty allows any window.
+    pub allowed_apps: Option<Vec<String>>,
+
+    /// Window classes (`WM_CLASS`) GUI input must never be sent to.
+    pub blocked_apps: Option<Vec<String>>,
+
+    /// Require `confirm=true` for destructive key combos. Defaults to `true`.
+    pub confirm_destructive_keys: Option<bool>,
+
+    /// Set once the computex first-run setup has been completed or skipped.
+    pub setup_completed: Option<bool>,
+
+    /// Offer the `computer_landmarks` tool, which remembers UI element
+    /// positions across sessions. Defaults to `true`.
+    pub landmarks: Option<bool>,
+
+    /// Number of earlier pointer actions echoed in each mutating tool result.
+    /// `0` disables the breadcrumbs. Defaults to `3`.
+    pub breadcrumbs: Option<usize>,
+
+    /// Local Chrome DevTools Protocol port (`--remote-debugging-port`) used by
+    /// `computer_browser_state` to list tabs without touching the address bar.
+    pub browser_debug_port: Option<u16>,
+
+    /// Disable pointer tools and steer the model toward Tab/arrow/Enter
+    /// navigation. Defaults to `false`.
+    pub keyboard_only: Option<bool>,
+
+    /// Offer emulated touch gestures (`computer_swipe`, `computer_long_press`,
+    /// `computer_pinch`) for touch-first UIs. Defaults to `false`.
+    pub gestures: Option<bool>,
+
+    /// Offer `computer_magnifier`, which controls the GNOME or KDE screen
+    /// magnifier for accessibility testing. Defaults to `false`.
+    pub magnifier: Option<bool>,
+
+    /// Offer `computer_set_theme`, which switches the GNOME or KDE theme
+    /// between dark, light, and high contrast. Defaults to `false`.
+    pub theme_switching: Option<bool>,
+
+    /// Offer `computer_system_control`, which reads and sets the audio
+    /// volume, mute, and screen brightness. Defaults to `false`.
+    pub system_controls: Option<bool>,
+
+    /// Offer `computer_announce`, which speaks a short warning with
+    /// `spd-say` or `espeak` before the agent takes over a shared desktop.
+    /// Defaults to `false`.
+    pub announcements: Option<bool>,
+
+    /// Offer `computer_camera_snapshot`, which attaches a frame from a V4L2
+    /// camera. Defaults to `false`.
+    pub camera: Option<bool>,
+
+    /// Offer `computer_watch_demo`, which records the user's keyboard and
+    /// mouse input while they demonstrate a flow. Defaults to `false`.
+    pub demonstrations: Option<bool>,
+
+    /// TUI key binding that lets the human attach a screenshot of the desktop
+    /// to the composer, e.g. `ctrl+alt+s` (the default). An empty string
+    /// disables it.
+    pub operator_screenshot_key: Option<String>,
+
+    /// Queue GUI input actions instead of running them, so the user can
+    /// review, reorder, and edit them before `computer_run_queue` executes
+    /// them. Defaults to `false`.
+    pub action_queue: Option<bool>,
+
+    /// Hypervisor of the VM the agent drives. Together with `vm_name`, this
+    /// enables `computer_checkpoint` snapshots and `computex rollback`.
+    pub vm_provider: Option<VmProvider>,
+
+    /// libvirt domain or VirtualBox VM name to snapshot.
+    pub vm_name: Option<String>,
+
+    /// Offer `computer_delegate` in sessions without GUI tools, so a
+    /// headless agent can hand GUI subtasks to a GUI sub-session. Defaults
+    /// to `false`.
+    pub delegate: Option<bool>,
+
+    /// Default GUI action budget for a delegated sub-session. Defaults to `50`.
+    pub delegate_max_actions: Option<u32>,
+
+    /// Default wall-clock budget for a delegated sub-session, in seconds.
+    /// Defaults to `600`.
+    pub delegate_timeout_secs: Option<u64>,
+
+    /// Images `computer_screenshot`, `computer_recent_frames`,
+    /// `computer_camera_snapshot`, `computer_watch_demo`, `computer_compare`
+    /// diffs, and failure screenshots may attach per turn, together. Unset or `0` means no limit.
+    pub max_screenshots_per_turn: Option<u32>,
+
+    /// After GUI input, capture a screenshot in the background and serve it
+    /// to the next `computer_screenshot` if it is at most this many
+    /// milliseconds old. Unset or `0` disables prefetching.
+    pub screenshot_prefetch_ms: Option<u64>,
+
+    /// Keep `computer_screenshot` images under this absolute directory,
+    /// named by `screenshot_name`, instead of in the temp directory.
+    pub screenshot_dir: Option<PathBuf>,
+
+    /// Path of each kept screenshot under `screenshot_dir`, with `{session}`,
+    /// `{turn}` and `{seq}` placeholders. Defaults to
+    /// `"{session}/{turn}-{seq}.png"`.
+    pub screenshot_name: Option<String>,
+
+    /// Encrypt screenshots kept in `screenshot_dir` and the images stored in
+    /// session rollouts with a key held in the OS keyring. `computex`
+    /// commands that read sessions decrypt them transparently. Defaults to
+    /// `false`.
+    pub encrypt_captures: Option<bool>,
+
+    /// Send pointer and key input through a helper that keeps one X
+    /// connection open for the session instead of running `xdotool` for each
+    /// action. Input the helper can't send still uses `xdotool`. Defaults to
+    /// `true`.
+    pub persistent_input: Option<bool>,
+
+    /// Approval rule for typing or pressing keys while a terminal emulator
+    /// is focused, since that runs commands outside the sandbox: `auto`
+    /// (default), `allow`, `ask`, or `deny`.
+    pub terminal_input_policy: Option<GuiSideEffectPolicy>,
+
+    /// Check text typed into a terminal emulator with `computer_type` against
+    /// the exec policy, as if it were a shell command, in addition to
+    /// `terminal_input_policy`. Defaults to `false`.
+    pub terminal_typing_exec_policy: Option<bool>,
+
+    /// Approval rule for clicking download, save, or install controls in a
+    /// browser: `auto` (default), `allow`, `ask`, or `deny`.
+    pub download_policy: Option<GuiSideEffectPolicy>,
+
+    /// Extra window classes to treat as terminal emulators, on top of the
+    /// built-in list.
+    pub terminal_apps: Option<Vec<String>>,
+
+    /// Address for the live preview server, e.g. `"0.0.0.0:8790"`. When set,
+    /// `computex` serves a page that shows the display in near real time and
+    /// can pause GUI input. Off by default.
+    pub preview_listen: Option<SocketAddr>,
+
+    /// Check for a locked screen before each GUI input action and suspend
+    /// input until the user confirms after unlocking. Defaults to `true`.
+    pub lock_detection: Option<bool>,
+
+    /// Offer `computer_unlock`, which types the unlock secret stored with
+    /// `computex unlock-secret set` when the screen locks. Off by default.
+    pub unlock_helper: Option<bool>,
+
+    /// Refuse `computer_type` into a focused password field unless the call
+    /// sets `confirm_secret=true`. Defaults to `true`.
+    pub password_guard: Option<bool>,
+
+    /// Give agent actions their own clipboard and put the user's contents
+    /// back after each one. Defaults to `true`.
+    pub preserve_clipboard: Option<bool>,
+
+    /// Seconds another app may hold a pointer or keyboard grab across GUI
+    /// actions before computex tries to break it. `0` disables the check.
+    /// Defaults to `30`.
+    pub stuck_grab_timeout_secs: Option<u64>,
+
+    /// Keep the monitor from blanking or powering down while GUI tools run,
+    /// restoring the screen saver and DPMS settings when the session ends.
+    /// Defaults to `true`.
+    pub inhibit_display_sleep: Option<bool>,
+
+    /// Confine screenshots and input to this part of the X screen, e.g.
+    /// `"1280x720+1920+0"` for a monitor right of a 1920-pixel one.
+    /// Coordinates stay relative to its top-left corner.
+    pub viewport: Option<Viewport>,
+
+    /// Click correction written by `computex calibrate`, as
+    /// `{ screen = "1920x1080", scale_x = 1.0, offset_x = 0.0, scale_y = 1.0,
+    /// offset_y = -28.0 }`. Defaults to none.
+    pub calibration: Option<CoordinateCalibration>,
+
+    /// Device pixels per input pixel, e.g. `1.5` for a Wayland output scaled
+    /// by 150%, for screenshots that can't be measured against the X screen.
+    /// Defaults to detecting it.
+    pub display_scale: Option<f64>,
+
+    /// Add a 1280x720 virtual output with `xrandr` for the session and
+    /// confine the GUI tools to it. Off by default.
+    pub virtual_output: Option<bool>,
+
+    /// Window class moved onto the virtual output when it is created.
+    pub virtual_output_app: Option<String>,
+
+    /// Read the text back after `computer_type` and report dropped or
+    /// substituted characters: `"off"` (default), `"clipboard"` or `"ocr"`.
+    pub verify_typing: Option<TypingVerification>,
+
+    /// Personal data replaced by placeholders in OCR text returned to the
+    /// model: any of `"email"`, `"card_number"` and `"phone"`. Defaults to
+    /// all three; `[]` turns scrubbing off.
+    pub ocr_scrubbers: Option<Vec<OcrScrubber>>,
+
+    /// Raise and remember the key delay for an app whose window class
+    /// dropped characters during verified typing. Defaults to `true`.
+    pub tune_typing_delay: Option<bool>,
+
+    /// Switch an active ibus or fcitx5 input method to direct input while
+    /// `computer_type` types Chinese, Japanese, or Korean text. Defaults to
+    /// `true`.
+    pub ime_passthrough: Option<bool>,
+
+    /// When `computer_type` or `computer_key` names a `window` that is not
+    /// focused, activate it instead of failing. Defaults to `true`.
+    pub refocus_expected_window: Option<bool>,
+
+    /// After a model turn that sent GUI input, tell the model what changed
+    /// on screen (windows opened, closed or retitled, focus, changed
+    /// regions) without another screenshot. Defaults to `false`.
+    pub turn_change_summary: Option<bool>,
+
+    /// Attach a small capture of the area an input action targeted when it
+    /// fails, or when a click leaves that area unchanged. Defaults to `true`.
+    pub failure_screenshots: Option<bool>,
+
+    /// Before each `computer_click`, look up the accessible element under
+    /// the click point and record how far from its center the click lands,
+    /// for `computex stats`. Defaults to `false`.
+    pub click_accuracy: Option<bool>,
+
+    /// `text` makes `computer_screenshot` return the on-screen text and its
+    /// positions, read with OCR, instead of attaching an image, for models
+    /// without image input. Defaults to `image`.
+    pub screenshot_format: Option<ScreenshotFormat>,
+
+    /// How much detail GUI tool results include: `terse` answers successful
+    /// input actions with just `ok`, `debug` adds the commands run and
+    /// their output. Defaults to `normal`.
+    pub result_verbosity: Option<ResultVerbosity>,
+
+    /// Move the pointer into a corner `after_action` or `before_screenshot`,
+    /// so it doesn't cover elements or leave hover effects in captures.
+    /// Defaults to `off`.
+    pub park_cursor: Option<CursorParking>,
+
+    /// Corner the pointer is parked in. Defaults to `bottom_right`.
+    pub park_cursor_corner: Option<ScreenCorner>,
+
+    /// Clear hover highlights and tooltips right before each screenshot:
+    /// `move_away` moves the pointer off for the capture and back, and
+    /// `leave_event` sends the hovered windows a synthetic leave event.
+    /// Defaults to `off`.
+    pub hover_suppression: Option<HoverSuppression>,
+
+    /// Capture a low-resolution frame about once a second while GUI tools are
+    /// in use, and offer `computer_recent_frames` to look back at the last
+    /// half minute. Defaults to `false`.
+    pub recent_frames: Option<bool>,
+
+    /// Offer `computer_dom_query` and `computer_dom_click`, which act on
+    /// the page in the browser's active tab through the computex browser
+    /// extension (installed by `computex setup`). Defaults to `false`.
+    pub browser_bridge: Option<bool>,
+
+    /// Offer `computer_office`, which opens documents, selects spreadsheet
+    /// cells, and exports PDFs through LibreOffice's UNO API instead of its
+    /// dialogs. Needs Python with the `uno` module. Defaults to `false`.
+    pub office_bridge: Option<bool>,
+
+    /// Local element detection model for `computer_detect_elements`, run
+    /// in process.
+    pub element_detector: Option<ElementDetector>,
+
+    /// Command that vets each GUI action that changes something before it
+    /// runs, as the program followed by its arguments. It reads the action,
+    /// the focused window, and a capture of the area the action aims at as
+    /// JSON on stdin, and answers `approve`, `deny`, or `ask`.
+    pub action_classifier: Option<Vec<String>>,
+
+    /// Strategies `computer_locate` tries, in order: any of
+    /// `"accessibility"`, `"ocr"`, `"template"` and `"detector"`. Defaults to
+    /// all four in that order.
+    pub locator_strategies: Option<Vec<LocatorStrategy>>,
+
+    /// Ways to send input, tried in order until one can: any of `"native"`,
+    /// `"bundled"`, `"xdotool"`, `"ydotool"` and `"portal"`. Defaults to the
+    /// first four in that order, or to `"native"`, `"bundled"`, `"portal"`,
+    /// `"xdotool"` and `"ydotool"` inside a Flatpak or Snap sandbox.
+    pub input_providers: Option<Vec<InputProvider>>,
+
+    /// Ways to capture the screen, tried in order until one works: any of
+    /// `"native"`, `"grim"`, `"import"`, `"scrot"` and `"portal"`. Defaults to
+    /// the first four in that order, with `"portal"` second inside a Flatpak
+    /// or Snap sandbox.
+    pub screenshot_providers: Option<Vec<ScreenshotProvider>>,
+}
+
+impl ComputerUseToml {
+    /// Overlays `profile` on top of `self`, preferring any field the profile sets.
+    pub fn merge(self, profile: Option<ComputerUseToml>) -> ComputerUseToml {
+        let Some(profile) = profile else {
+            return self;
+        };
+        ComputerUseToml {
+            coordinate_space: profile.coordinate_space.or(self.coordinate_space),
+            resolution: profile.resolution.or(self.resolution),
+            backend: profile.backend.or(self.backend),
+            qmp_socket: profile.qmp_socket.or(self.qmp_socket),
+            qmp_rotation: profile.qmp_rotation.or(self.qmp_rotation),
+            console: profile.console.or(self.console),
+            target_os: profile.target_os.or(self.target_os),
+            display: profile.display.or(self.display),
+            target: profile.target.or(self.target),
+            allowed_apps: profile.allowed_apps.or(self.allowed_apps),
+            blocked_apps: profile.blocked_apps.or(self.blocked_apps),
+            confirm_destructive_keys: profile
+                .confirm_destructive_keys
+                .or(self.confirm_destructive_keys),
+            setup_completed: profile.setup_completed.or(self.setup_completed),
+            landmarks: profile.landmarks.or(self.landmarks),
+            breadcrumbs: profile.breadcrumbs.or(self.breadcrumbs),
+            browser_debug_port: profile.browser_debug_port.or(self.browser_debug_port),
+            keyboard_only: profile.keyboard_only.or(self.keyboard_only),
+            gestures: profile.gestures.or(self.gestures),
+            magnifier: profile.magnifier.or(self.magnifier),
+            theme_switching: profile.theme_switching.or(self.theme_switching),
+            system_controls: profile.system_controls.or(self.system_controls),
+            announcements: profile.announcements.or(self.announcements),
+            camera: profile.camera.or(self.camera),
+            demonstrations: profile.demonstrations.or(self.demonstrations),
+            operator_screenshot_key: profile
+                .operator_screenshot_key
+                .or(self.operator_screenshot_key),
+            action_queue: profile.action_queue.or(self.action_queue),
+            vm_provider: profile.vm_provider.or(self.vm_provider),
+            vm_name: profile.vm_name.or(self.vm_name),
+            delegate: profile.delegate.or(self.delegate),
+            delegate_max_actions: profile.delegate_max_actions.or(self.delegate_max_actions),
+            delegate_timeout_secs: profile.delegate_timeout_secs.or(self.delegate_timeout_secs),
+            max_screenshots_per_turn: profile
+                .max_screenshots_per_turn
+                .or(self.max_screenshots_per_turn),
+            screenshot_prefetch_ms: profile
+                .screenshot_prefetch_ms
+                .or(self.screenshot_prefetch_ms),
+            screenshot_dir: profile.screenshot_dir.or(self.screenshot_dir),
+            screenshot_name: profile.screenshot_name.or(self.screenshot_name),
+            encrypt_captures: profile.encrypt_captures.or(self.encrypt_captures),
+            persistent_input: profile.persistent_input.or(self.persistent_input),
+            terminal_input_policy: profile.terminal_input_policy.or(self.terminal_input_policy),
+            terminal_typing_exec_policy: profile
+                .terminal_typing_exec_policy
+                .or(self.terminal_typing_exec_policy),
+            download_policy: profile.download_policy.or(self.download_policy),
+            terminal_apps: profile.terminal_apps.or(self.terminal_apps),
+            preview_listen: profile.preview_listen.or(self.preview_listen),
+            lock_detection: profile.lock_detection.or(self.lock_detection),
+            unlock_helper: profile.unlock_helper.or(self.unlock_helper),
+            password_guard: profile.password_guard.or(self.password_guard),
+            preserve_clipboard: profile.preserve_clipboard.or(self.preserve_clipboard),
+            stuck_grab_timeout_secs: profile
+                .stuck_grab_timeout_secs
+                .or(self.stuck_grab_timeout_secs),
+            inhibit_display_sleep: profile.inhibit_display_sleep.or(self.inhibit_display_sleep),
+            viewport: profile.viewport.or(self.viewport),
+            calibration: profile.calibration.or(self.calibration),
+            display_scale: profile.display_scale.or(self.display_scale),
+            virtual_output: profile.virtual_output.or(self.virtual_output),
+            virtual_output_app: profile.virtual_output_app.or(self.virtual_output_app),
+            verify_typing: profile.verify_typing.or(self.verify_typing),
+            ocr_scrubbers: profile.ocr_scrubbers.or(self.ocr_scrubbers),
+            tune_typing_delay: profile.tune_typing_delay.or(self.tune_typing_delay),
+            ime_passthrough: profile.ime_passthrough.or(self.ime_passthrough),
+            refocus_expected_window: profile
+                .refocus_expected_window
+                .or(self.refocus_expected_window),
+            turn_change_summary: profile.turn_change_summary.or(self.turn_change_summary),
+            failure_screenshots: profile.failure_screenshots.or(self.failure_screenshots),
+            click_accuracy: profile.click_accuracy.or(self.click_accuracy),
+            screenshot_format: profile.screenshot_format.or(self.screenshot_format),
+            result_verbosity: profile.result_verbosity.or(self.result_verbosity),
+            park_cursor: profile.park_cursor.or(self.park_cursor),
+            park_cursor_corner: profile.park_cursor_corner.or(self.park_cursor_corner),
+            hover_suppression: profile.hover_suppression.or(self.hover_suppression),
+            recent_frames: profile.recent_frames.or(self.recent_frames),
+            browser_bridge: profile.browser_bridge.or(self.browser_bridge),
+            office_bridge: profile.office_bridge.or(self.office_bridge),
+            element_detector: profile.element_detector.or(self.element_detector),
+            action_classifier: profile.action_classifier.or(self.action_classifier),
+            locator_strategies: profile.locator_strategies.or(self.locator_strategies),
+            input_providers: profile.input_providers.or(self.input_providers),
+            screenshot_providers: profile.screenshot_providers.or(self.screenshot_providers),
+        }
+    }
+}
+
+/// Effective computer-use settings after defaults are applied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComputerUseConfig {
+    pub coordinate_space: CoordinateSpace,
+
+    /// Virtual canvas size used by [`CoordinateSpace::Virtual`].
+    pub resolution: Resolution,
+
+    pub backend: ComputerUseBackend,
+
+    /// QMP socket of the VM, for [`ComputerUseBackend::Qemu`].
+    pub qmp_socket: Option<PathBuf>,
+
+    /// Rotation of the VM's display relative to its console.
+    pub qmp_rotation: Rotation,
+
+    /// Console for [`ComputerUseBackend::Console`].
+    pub console: Option<ConsoleTarget>,
+
+    pub target_os: TargetOs,
+
+    /// Explicit X11 display, or `None` to inherit `$DISPLAY`.
+    pub display: Option<String>,
+
+    /// Remote machine the GUI tools and the shell run on, if any.
+    pub target: Option<RemoteTarget>,
+
+    /// Window classes GUI input may target; empty allows any window.
+    pub allowed_apps: Vec<String>,
+
+    /// Window classes GUI input must never target.
+    pub blocked_apps: Vec<String>,
+
+    pub confirm_destructive_keys: bool,
+
+    pub setup_completed: bool,
+
+    pub landmarks: bool,
+
+    /// Earlier pointer actions echoed in mutating tool results.
+    pub breadcrumbs: usize,
+
+    /// Chrome DevTools Protocol port for browser introspection, if any.
+    pub browser_debug_port: Option<u16>,
+
+    /// Pointer tools are disabled in favor of keyboard navigation.
+    pub keyboard_only: bool,
+
+    /// Emulated touch gesture tools are offered.
+    pub gestures: bool,
+
+    /// Whether `computer_magnifier` is offered.
+    pub magnifier: bool,
+
+    /// Whether `computer_set_theme` is offered.
+    pub theme_switching: bool,
+
+    /// Whether `computer_system_control` is offered.
+    pub system_controls: bool,
+
+    /// Whether `computer_announce` is offered.
+    pub announcements: bool,
+
+    /// Whether `computer_camera_snapshot` is offered.
+    pub camera: bool,
+
+    /// Whether `computer_watch_demo` is offered.
+    pub demonstrations: bool,
+
+    /// TUI key binding for operator screenshots; empty when disabled.
+    pub operator_screenshot_key: String,
+
+    /// GUI input actions are queued for user review instead of running.
+    pub action_queue: bool,
+
+    /// VM snapshotted by `computer_checkpoint`, when configured.
+    pub vm: Option<VmTarget>,
+
+    /// `computer_delegate` is offered when GUI tools are disabled.
+    pub delegate: bool,
+
+    /// Default GUI action budget for `computer_delegate`.
+    pub delegate_max_actions: u32,
+
+    /// Default wall-clock budget for `computer_delegate`, in seconds.
+    pub delegate_timeout_secs: u64,
+
+    /// Per-turn screenshot budget, if any.
+    pub max_screenshots_per_turn: Option<u32>,
+
+    /// Freshness window for prefetched screenshots, if prefetching is on.
+    pub screenshot_prefetch_ms: Option<u64>,
+
+    /// Where screenshots are kept, if not in the temp directory.
+    pub screenshot_dir: Option<PathBuf>,
+
+    /// File name template for screenshots kept in `screenshot_dir`.
+    pub screenshot_name: String,
+
+    /// Kept screenshots and rollout images are encrypted at rest.
+    pub encrypt_captures: bool,
+
+    /// Input goes through the session's long-lived X helper when possible.
+    pub persistent_input: bool,
+
+    /// Approval rule for input to terminal emulators.
+    pub terminal_input_policy: GuiSideEffectPolicy,
+
+    /// Whether text typed into terminals goes through the exec policy.
+    pub terminal_typing_exec_policy: bool,
+
+    /// Approval rule for clicks on browser download controls.
+    pub download_policy: GuiSideEffectPolicy,
+
+    /// Window classes treated as terminals besides the built-in ones.
+    pub terminal_apps: Vec<String>,
+
+    /// Where the live preview server listens, if enabled.
+    pub preview_listen: Option<SocketAddr>,
+
+    /// Whether GUI input is suspended while the screen is locked.
+    pub lock_detection: bool,
+
+    /// Whether `computer_unlock` may type the stored unlock secret.
+    pub unlock_helper: bool,
+
+    /// Whether `computer_type` checks for a focused password field first.
+    pub password_guard: bool,
+
+    /// Whether input actions run against a private agent clipboard.
+    pub preserve_clipboard: bool,
+
+    /// How long a grab may persist before it is broken; `0` disables it.
+    pub stuck_grab_timeout_secs: u64,
+
+    /// Whether the display is kept awake during GUI sessions.
+    pub inhibit_display_sleep: bool,
+
+    /// Part of the X screen the GUI tools see and act on; the whole screen
+    /// when `None`.
+    pub viewport: Option<Viewport>,
+
+    /// Correction applied to every point the GUI tools click.
+    pub calibration: Option<CoordinateCalibration>,
+
+    /// Configured device pixels per input pixel; detected when `None`.
+    pub display_scale: Option<f64>,
+
+    /// Whether computex adds a virtual output for the session.
+    pub virtual_output: bool,
+
+    /// Window class moved onto the virtual output, if any.
+    pub virtual_output_app: Option<String>,
+
+    /// How `computer_type` reads typed text back by default.
+    pub verify_typing: TypingVerification,
+
+    /// Personal data removed from OCR text returned to the model.
+    pub ocr_scrubbers: Vec<OcrScrubber>,
+
+    /// Whether dropped characters raise a saved per-app key delay.
+    pub tune_typing_delay: bool,
+
+    /// Whether CJK text is typed with the input method in direct mode.
+    pub ime_passthrough: bool,
+
+    /// Whether an expected `window` that lost focus is activated rather than
+    /// failing the call.
+    pub refocus_expected_window: bool,
+
+    /// Whether screen changes are summarized after turns that sent input.
+    pub turn_change_summary: bool,
+
+    /// Whether failed input actions come back with a capture of their target.
+    pub failure_screenshots: bool,
+
+    /// Whether clicks are measured against the element under them.
+    pub click_accuracy: bool,
+
+    /// Whether screenshots come back as images or as text.
+    pub screenshot_format: ScreenshotFormat,
+
+    /// How much detail GUI tool results include.
+    pub result_verbosity: ResultVerbosity,
+
+    /// When the pointer is parked out of the way.
+    pub park_cursor: CursorParking,
+
+    /// Where the pointer is parked.
+    pub park_cursor_corner: ScreenCorner,
+
+    /// How hover effects are cleared before screenshots.
+    pub hover_suppression: HoverSuppression,
+
+    /// Whether frames are recorded for `computer_recent_frames`.
+    pub recent_frames: bool,
+
+    /// Whether the DOM tools of the browser extension are offered.
+    pub browser_bridge: bool,
+
+    /// Whether `computer_office` drives LibreOffice over UNO.
+    pub office_bridge: bool,
+
+    /// The local element detection model, if one is configured.
+    pub element_detector: Option<ElementDetector>,
+
+    /// Command that approves, denies, or asks about each action that
+    /// changes something, if one is configured.
+    pub action_classifier: Option<Vec<String>>,
+
+    /// Strategies `computer_locate` tries, in order.
+    pub locator_strategies: Vec<LocatorStrategy>,
+
+    /// Ways to send input, in the order they are tried.
+    pub input_providers: Vec<InputProvider>,
+
+    /// Ways to capture the screen, in the order they are tried.
+    pub screenshot_providers: Vec<ScreenshotProvider>,
+
+    /// Per-app recipes offered as extra tools. Read from
+    /// `<codex_home>/computer_use/recipes` rather than from `config.toml`.
+    pub recipes: Vec<Recipe>,
+
+    /// Plugins whose WebAssembly modules provide extra tools. Read from
+    /// `<codex_home>/computer_use/plugins` rather than from `config.toml`.
+    pub plugins: Vec<Plugin>,
+}
+
+impl ComputerUseConfig {
+    /// Short human-readable label for the coordinate space, used in tool
+    /// descriptions, tool results, and instructions.
+    pub fn coordinate_label(&self) -> String {
+        match self.coordinate_space {
+            CoordinateSpace::Virtual => format!("{} space", self.resolution),
+            CoordinateSpace::Normalized => "normalized 0-1 space".to_string(),
+            CoordinateSpace::Native => "native screen pixels".to_string(),
+        }
+    }
+}
+
+impl Default for ComputerUseConfig {
+    fn default() -> Self {
+        Self {
+            coordinate_space: CoordinateSpace::default(),
+            resolution: Resolution::default(),
+            backend: ComputerUseBackend::default(),
+            qmp_socket: None,
+            qmp_rotation: Rotation::Normal,
+            console: None,
+            target_os: TargetOs::default(),
+            display: None,
+            target: None,
+            allowed_apps: Vec::new(),
+            blocked_apps: Vec::new(),
+            confirm_destructive_keys: true,
+            setup_completed: false,
+            landmarks: true,
+            breadcrumbs: DEFAULT_COMPUTER_USE_BREADCRUMBS,
+            browser_debug_port: None,
+            keyboard_only: false,
+            gestures: false,
+            magnifier: false,
+            theme_switching: false,
+            system_controls: false,
+            announcements: false,
+            camera: false,
+            demonstrations: false,
+            operator_screenshot_key: DEFAULT_OPERATOR_SCREENSHOT_KEY.to_string(),
+            action_queue: false,
+            vm: None,
+            delegate: false,
+            delegate_max_actions: DEFAULT_DELEGATE_MAX_ACTIONS,
+            delegate_timeout_secs: DEFAULT_DELEGATE_TIMEOUT_SECS,
+            max_screenshots_per_turn: None,
+            screenshot_prefetch_ms: None,
+            screenshot_dir: None,
+            screenshot_name: DEFAULT_SCREENSHOT_NAME.to_string(),
+            encrypt_captures: false,
+            persistent_input: true,
+            terminal_input_policy: GuiSideEffectPolicy::default(),
+            terminal_typing_exec_policy: false,
+            download_policy: GuiSideEffectPolicy::default(),
+            terminal_apps: Vec::new(),
+            preview_listen: None,
+            lock_detection: true,
+            unlock_helper: false,
+            password_guard: true,
+            preserve_clipboard: true,
+            stuck_grab_timeout_secs: DEFAULT_STUCK_GRAB_TIMEOUT_SECS,
+            inhibit_display_sleep: true,
+            viewport: None,
+            calibration: None,
+            display_scale: None,
+            virtual_output: false,
+            virtual_output_app: None,
+            verify_typing: TypingVerification::Off,
+            ocr_scrubbers: DEFAULT_OCR_SCRUBBERS.to_vec(),
+            tune_typing_delay: true,
+            ime_passthrough: true,
+            refocus_expected_window: true,
+            turn_change_summary: false,
+            failure_screenshots: true,
+            click_accuracy: false,
+            screenshot_format: ScreenshotFormat::Image,
+            result_verbosity: ResultVerbosity::Normal,
+            park_cursor: CursorParking::Off,
+            park_cursor_corner: ScreenCorner::BottomRight,
+            hover_suppression: HoverSuppression::Off,
+            recent_frames: false,
+            browser_bridge: false,
+            office_bridge: false,
+            element_detector: None,
+            action_classifier: None,
+            locator_strategies: DEFAULT_LOCATOR_STRATEGIES.to_vec(),
+            input_providers: default_input_providers(ComputerUseBackend::default()),
+            screenshot_providers: default_screenshot_providers(ComputerUseBackend::default()),
+            recipes: Vec::new(),
+            plugins: Vec::new(),
+        }
+    }
+}
+
+impl From<ComputerUseToml> for ComputerUseConfig {
+    fn from(toml: ComputerUseToml) -> Self {
+        let backend = toml.backend.unwrap_or_default();
+        Self {
+            coordinate_space: toml.coordinate_space.unwrap_or_default(),
+            resolution: toml.resolution.unwrap_or_default(),
+            backend,
+            qmp_socket: toml.qmp_socket,
+            qmp_rotation: toml.qmp_rotation.unwrap_or_default(),
+            console: toml.console,
+            target_os: toml.target_os.unwrap_or_default(),
+            display: toml.display,
+            target: toml.target,
+            allowed_apps: toml.allowed_apps.unwrap_or_default(),
+            blocked_apps: toml.blocked_apps.unwrap_or_default(),
+            confirm_destructive_keys: toml.confirm_destructive_keys.unwrap_or(true),
+            setup_completed: toml.setup_completed.unwrap_or(false),
+            landmarks: toml.landmarks.unwrap_or(true),
+            breadcrumbs: toml.breadcrumbs.unwrap_or(DEFAULT_COMPUTER_USE_BREADCRUMBS),
+            browser_debug_port: toml.browser_debug_port,
+            keyboard_only: toml.keyboard_only.unwrap_or(false),
+            gestures: toml.gestures.unwrap_or(false),
+            magnifier: toml.magnifier.unwrap_or(false),
+            theme_switching: toml.theme_switching.unwrap_or(false),
+            system_controls: toml.system_controls.unwrap_or(false),
+            announcements: toml.announcements.unwrap_or(false),
+            camera: toml.camera.unwrap_or(false),
+            demonstrations: toml.demonstrations.unwrap_or(false),
+            operator_screenshot_key: toml
+                .operator_screenshot_key
+                .unwrap_or_else(|| DEFAULT_OPERATOR_SCREENSHOT_KEY.to_string()),
+            action_queue: toml.action_queue.unwrap_or(false),
+            vm: match (toml.vm_provider, toml.vm_name) {
+                (Some(provider), Some(name)) if !name.trim().is_empty() => Some(VmTarget {
+                    provider,
+                    name: name.trim().to_string(),
+                }),
+                _ => None,
+            },
+            delegate: toml.delegate.unwrap_or(false),
+            delegate_max_actions: toml
+                .delegate_max_actions
+                .unwrap_or(DEFAULT_DELEGATE_MAX_ACTIONS),
+            delegate_timeout_secs: toml
+                .delegate_timeout_secs
+                .unwrap_or(DEFAULT_DELEGATE_TIMEOUT_SECS),
+            max_screenshots_per_turn: toml.max_screenshots_per_turn.filter(|limit| *limit > 0),
+            screenshot_prefetch_ms: toml.screenshot_prefetch_ms.filter(|ms| *ms > 0),
+            screenshot_dir: toml.screenshot_dir,
+            screenshot_name: toml
+                .screenshot_name
+                .unwrap_or_else(|| DEFAULT_SCREENSHOT_NAME.to_string()),
+            encrypt_captures: toml.encrypt_captures.unwrap_or(false),
+            persistent_input: toml.persistent_input.unwrap_or(true),
+            terminal_input_policy: toml.terminal_input_policy.unwrap_or_default(),
+            terminal_typing_exec_policy: toml.terminal_typing_exec_policy.unwrap_or(false),
+            download_policy: toml.download_policy.unwrap_or_default(),
+            terminal_apps: toml.terminal_apps.unwrap_or_default(),
+            preview_listen: toml.preview_listen,
+            lock_detection: toml.lock_detection.unwrap_or(true),
+            unlock_helper: toml.unlock_helper.unwrap_or(false),
+            password_guard: toml.password_guard.unwrap_or(true),
+            preserve_clipboard: toml.preserve_clipboard.unwrap_or(true),
+            stuck_grab_timeout_secs: toml
+                .stuck_grab_timeout_secs
+                .unwrap_or(DEFAULT_STUCK_GRAB_TIMEOUT_SECS),
+            inhibit_display_sleep: toml.inhibit_display_sleep.unwrap_or(true),
+            viewport: toml.viewport,
+            calibration: toml.calibration,
+            display_scale: toml
+                .display_scale
+                .filter(|scale| scale.is_finite() && *scale > 0.0),
+            virtual_output: toml.virtual_output.unwrap_or(false),
+            virtual_output_app: toml.virtual_output_app,
+            verify_typing: toml.verify_typing.unwrap_or_default(),
+            ocr_scrubbers: toml
+                .ocr_scrubbers
+                .unwrap_or_else(|| DEFAULT_OCR_SCRUBBERS.to_vec()),
+            tune_typing_delay: toml.tune_typing_delay.unwrap_or(true),
+            ime_passthrough: toml.ime_passthrough.unwrap_or(true),
+            refocus_expected_window: toml.refocus_expected_window.unwrap_or(true),
+            turn_change_summary: toml.turn_change_summary.unwrap_or(false),
+            failure_screenshots: toml.failure_screenshots.unwrap_or(true),
+            click_accuracy: toml.click_accuracy.unwrap_or(false),
+            screenshot_format: toml.screenshot_format.unwrap_or_default(),
+            result_verbosity: toml.result_verbosity.unwrap_or_default(),
+            park_cursor: toml.park_cursor.unwrap_or_default(),
+            park_cursor_corner: toml.park_cursor_corner.unwrap_or_default(),
+            hover_suppression: toml.hover_suppression.unwrap_or_default(),
+            recent_frames: toml.recent_frames.unwrap_or(false),
+            browser_bridge: toml.browser_bridge.unwrap_or(false),
+            office_bridge: toml.office_bridge.unwrap_or(false),
+            element_detector: toml.element_detector,
+            action_classifier: toml.action_classifier.filter(|command| !command.is_empty()),
+            locator_strategies: toml
+                .locator_strategies
+                .unwrap_or_else(|| DEFAULT_LOCATOR_STRATEGIES.to_vec()),
+            input_providers: toml
+                .input_providers
+                .unwrap_or_else(|| default_input_providers(backend)),
+            screenshot_providers: toml
+                .screenshot_providers
+                .unwrap_or_else(|| default_screenshot_providers(backend)),
+            recipes: Vec::new(),
+            plugins: Vec::new(),
+        }
+    }
+}
+
 #[derive(Serialize, Debug, Clone, PartialEq, Eq, Deserialize)]
 #[serde(untagged)]
 pub enum Notifications {
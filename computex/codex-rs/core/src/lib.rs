@@ -60,7 +60,25 @@ pub use model_provider_info::OLLAMA_OSS_PROVIDER_ID;
 pub use model_provider_info::WireApi;
 pub use model_provider_info::built_in_model_providers;
 pub use model_provider_info::create_oss_provider_with_base_url;
-pub use prompts::COMPUTER_USE_PROMPT;
+pub use prompts::computer_use_prompt;
+pub use tools::handlers::computer_use::browser_bridge as computer_use_browser_bridge;
+pub use tools::handlers::computer_use::bundled as computer_use_bundled;
+pub use tools::handlers::computer_use::calibration as computer_use_calibration;
+pub use tools::handlers::computer_use::capture_operator_screenshot;
+pub use tools::handlers::computer_use::diagnostics as computer_use_diagnostics;
+pub use tools::handlers::computer_use::encryption as computer_use_encryption;
+pub use tools::handlers::computer_use::export as computer_use_export;
+pub use tools::handlers::computer_use::pause as computer_use_pause;
+pub use tools::handlers::computer_use::plugins as computer_use_plugins;
+pub use tools::handlers::computer_use::recipes as computer_use_recipes;
+pub use tools::handlers::computer_use::remote as computer_use_remote;
+pub use tools::handlers::computer_use::rotation as computer_use_rotation;
+pub use tools::handlers::computer_use::script as computer_use_script;
+pub use tools::handlers::computer_use::snapshots as computer_use_snapshots;
+pub use tools::handlers::computer_use::stats as computer_use_stats;
+pub use tools::handlers::computer_use::timelapse as computer_use_timelapse;
+pub use tools::handlers::computer_use::unlock as computer_use_unlock;
+pub use tools::handlers::computer_use::virtual_output as computer_use_virtual_output;
 mod conversation_manager;
 mod event_mapping;
 pub mod review_format;
@@ -1 +1,522 @@
-pub const COMPUTER_USE_PROMPT: &str = include_str!("../computer_use_prompt.md");
+//! Base instructions for computer-use sessions.
+//!
+//! The instructions are rendered from the active [`ComputerUseConfig`] so the
+//! tools, coordinate space, and safety rules described to the model always
+//! match what the computer-use handler actually does.
+
+use crate::config::types::ComputerUseConfig;
+use crate::config::types::CoordinateSpace;
+use crate::config::types::ScreenshotFormat;
+use crate::config::types::TargetOs;
+use crate::tools::handlers::computer_use::DESTRUCTIVE_KEY_COMBOS;
+use crate::tools::handlers::computer_use::backend_description;
+use crate::tools::handlers::computer_use::enabled_tools;
+
+const COMPUTER_USE_PROMPT_TEMPLATE: &str = include_str!("../computer_use_prompt.md");
+const CAPABILITY_PLACEHOLDER: &str = "{{computer_use_capability}}";
+const WORKFLOW_PLACEHOLDER: &str = "{{computer_use_workflow}}";
+
+/// Renders the computer-use base instructions for `config`. When `gui_enabled`
+/// is false the instructions steer the model to shell-only work.
+pub fn computer_use_prompt(config: &ComputerUseConfig, gui_enabled: bool) -> String {
+    COMPUTER_USE_PROMPT_TEMPLATE
+        .replace(
+            CAPABILITY_PLACEHOLDER,
+            &capability_line(config, gui_enabled),
+        )
+        .replace(WORKFLOW_PLACEHOLDER, &workflow_section(config, gui_enabled))
+}
+
+fn capability_line(config: &ComputerUseConfig, gui_enabled: bool) -> String {
+    if gui_enabled {
+        "- Take on-demand screenshots and send mouse/keyboard input via the computer-use tools when GUI interaction is needed.".to_string()
+    } else if config.delegate {
+        "- GUI tools are disabled for this session; work happens through the shell, and GUI steps are delegated to a GUI sub-session with `computer_delegate`.".to_string()
+    } else {
+        "- GUI tools are disabled for this session, so all work happens through the shell."
+            .to_string()
+    }
+}
+
+fn workflow_section(config: &ComputerUseConfig, gui_enabled: bool) -> String {
+    let mut lines = vec!["## Computer-use workflow".to_string(), String::new()];
+    if !gui_enabled {
+        if config.delegate {
+            lines.extend([
+                "- GUI tools are not available in this session. Use the shell for all work you can.".to_string(),
+                "- When a step needs a graphical interface (an installer, a settings dialog, a web page), call `computer_delegate` with a self-contained task. A GUI sub-session performs it and returns a summary; it cannot see this conversation, so include paths, names, and what to report back. Check its result before relying on it.".to_string(),
+            ]);
+        } else {
+            lines.push(
+                "- GUI tools are not available in this session. Use the shell for all work, and if a task requires interacting with a graphical interface, explain the limitation to the user."
+                    .to_string(),
+            );
+        }
+        lines.extend(remote_line(config));
+        return lines.join("\n");
+    }
+
+    let enabled = enabled_tools(config);
+    let tools = enabled
+        .iter()
+        .map(|name| format!("`{name}`"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let combos = DESTRUCTIVE_KEY_COMBOS
+        .iter()
+        .map(|combo| format_combo(combo))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let backend = backend_description(config.backend);
+    let backend = match &config.display {
+        Some(display) => format!("{backend} (display `{display}`)"),
+        None => backend.to_string(),
+    };
+    let destructive = if config.confirm_destructive_keys {
+        format!(
+            "- Destructive key combos ({combos}) require `confirm=true` and explicit user confirmation."
+        )
+    } else {
+        format!(
+            "- Destructive key combos ({combos}) are not gated by the harness in this session; still ask the user before closing windows or applications."
+        )
+    };
+
+    lines.extend([
+        "- Prefer the shell for CLI tasks; use GUI tools only when necessary.".to_string(),
+        "- Before any GUI action, take a `computer_screenshot`. Do not assume continuous vision."
+            .to_string(),
+        "- To locate a labeled element such as a button or menu item, call `computer_find_text` instead of reading coordinates off a full screenshot."
+            .to_string(),
+        "- Pick the screenshot `detail` to match the need: `low` to check which window or page is showing, `high` with a region to read small text. When the image size differs from the coordinate space, the result says how to convert image pixels."
+            .to_string(),
+        "- After navigating in a browser, confirm the page with `computer_browser_state` instead of reading the URL off a screenshot."
+            .to_string(),
+        "- Before a long GUI task, or when GUI actions fail unexpectedly, run `computer_self_test` to check the environment."
+            .to_string(),
+        coordinate_guidance(config),
+        format!("- GUI tools available in this session: {tools}."),
+        format!("- GUI actions run against {backend}."),
+        "- After any action that changes the screen, take a new screenshot before making more precise clicks."
+            .to_string(),
+        "- Before typing into a field that may already hold text, empty it with `computer_clear_field`; clicking and typing appends to the existing content."
+            .to_string(),
+        destructive,
+        format!(
+            "- The GUI runs on {os}. In `computer_key`, `cmd` is the primary shortcut modifier ({primary} on {os}), and named shortcuts such as `copy`, `paste`, `undo`, `new_tab`, `close_tab`, and `address_bar` are translated to {os}'s keys, so prefer them over spelling out platform-specific combos.",
+            os = config.target_os,
+            primary = match config.target_os {
+                TargetOs::Macos => "Cmd",
+                TargetOs::Linux | TargetOs::Windows => "Ctrl",
+            },
+        ),
+        "- If GUI tools are unavailable, explain the limitation and fall back to shell-only actions."
+            .to_string(),
+    ]);
+    lines.extend(remote_line(config));
+    if let Some(limit) = config.max_screenshots_per_turn {
+        let budgeted = [
+            ("computer_screenshot", ""),
+            ("computer_recent_frames", ""),
+            ("computer_camera_snapshot", ""),
+            ("computer_watch_demo", ""),
+            ("computer_compare", " diffs"),
+        ]
+        .iter()
+        .filter(|(tool, _)| enabled.contains(tool))
+        .map(|(tool, what)| format!("`{tool}`{what}, "))
+        .collect::<String>();
+        lines.push(format!(
+            "- Screenshots are budgeted: at most {limit} image(s) per turn across {budgeted}and captures attached to failed actions, and each result says how many remain. Batch actions between screenshots and look up labels with text search where you can."
+        ));
+    }
+    if config.screenshot_format == ScreenshotFormat::Text {
+        lines.push(
+            "- Screenshots come back as text in this session: `computer_screenshot` lists the on-screen text with positions and attaches no image. Controls without a visible label don't appear, so reach them with `computer_locate` or keyboard shortcuts."
+                .to_string(),
+        );
+    }
+    if config.keyboard_only {
+        lines.push(
+            "- Keyboard-only mode: pointer tools are disabled. Move between controls with `computer_focus_next` and `computer_focus_prev` (Tab and Shift+Tab), and use `computer_key` for arrow keys, Enter, Space, and Escape. Take a screenshot to check which element has focus before activating it."
+                .to_string(),
+        );
+    }
+    lines.push(
+        "- To check which app or window has focus, or where a window is, call `computer_active_window` instead of taking a screenshot."
+            .to_string(),
+    );
+    lines.push(
+        "- If a window you need isn't on screen, call `computer_workspace_list`; it may be on another workspace. Switch there with `computer_workspace_switch` or bring it over with `computer_workspace_move_window`."
+            .to_string(),
+    );
+    lines.push(
+        "- For system tray icons, call `computer_tray_list` and `computer_tray_activate` instead of clicking the icon or its menu."
+            .to_string(),
+    );
+    lines.push(
+        "- To check whether a network, Wi-Fi, or VPN change took effect, call `computer_network_state` instead of reading the panel icon."
+            .to_string(),
+    );
+    lines.push(
+        "- Keyboard shortcuts are more reliable than clicks. Call `computer_list_shortcuts` to find the desktop's and the focused app's shortcuts for an action."
+            .to_string(),
+    );
+    lines.push(
+        "- To choose an entry from an app's menu bar, call `computer_menu_select` with the labels from the menu bar down instead of clicking through nested menus."
+            .to_string(),
+    );
+    lines.push(
+        "- When a file open or save dialog is focused, call `computer_file_dialog` with the absolute path instead of navigating folders by clicking."
+            .to_string(),
+    );
+    lines.push(
+        "- When a task stays in one app, call `computer_set_target` with its window so screenshots and coordinates are relative to that window and input can't go elsewhere; clear it before switching apps."
+            .to_string(),
+    );
+    lines.push(
+        "- When `computer_find_text` can't find a control, e.g. an unlabeled icon or a widget whose label differs from what is drawn, call `computer_locate`; it tries the accessibility tree, OCR, saved templates, and the element detector in turn and says which one found it."
+            .to_string(),
+    );
+    lines.push(
+        "- After starting a browser download, call `computer_wait_download` to get the finished file instead of watching the download bar with screenshots."
+            .to_string(),
+    );
+    lines.push(
+        "- While an app compiles, imports, or installs behind a progress bar that barely moves, call `computer_wait_app_idle` instead of taking screenshots until it looks done."
+            .to_string(),
+    );
+    if !config.keyboard_only {
+        lines.push(
+            "- To save a document as a PDF, call `computer_print_dialog_handle` with the target path instead of clicking through the print dialog."
+                .to_string(),
+        );
+        lines.push(
+            "- To choose from a right-click menu, call `computer_context_menu` with the point and the entry's label instead of right-clicking and clicking separately."
+                .to_string(),
+        );
+    }
+    if config.gestures && !config.keyboard_only {
+        lines.push(
+            "- Touch gestures are emulated with the pointer: use `computer_swipe` to scroll or dismiss touch-first views, `computer_long_press` for context menus, and `computer_pinch` to zoom. Rotation is not supported."
+                .to_string(),
+        );
+    }
+    if config.magnifier {
+        lines.push(
+            "- `computer_magnifier` controls the desktop screen magnifier for accessibility checks. Take a screenshot after changing it, and turn it off again when the check is done."
+                .to_string(),
+        );
+    }
+    if config.announcements {
+        lines.push(
+            "- A person may be using this desktop. Before your first input action, and before resuming after a long pause, call `computer_announce` with a short warning such as \"Computex is taking over the mouse and keyboard.\""
+                .to_string(),
+        );
+    }
+    if config.turn_change_summary {
+        lines.push(
+            "- After a turn that sent GUI input you may get a note listing what changed on screen since your last screenshot. When it shows the change you expected, continue without a screenshot."
+                .to_string(),
+        );
+    }
+    if config.element_detector.is_some() {
+        lines.push(
+            "- For icons and controls without readable text, or in custom-drawn apps where `computer_find_text` finds nothing, call `computer_detect_elements` before falling back to a screenshot."
+                .to_string(),
+        );
+    }
+    if config.recent_frames {
+        lines.push(
+            "- When something may have flashed by after an action (a toast, a spinner, an error that closed itself), call `computer_recent_frames` to look at the last few seconds instead of retrying blindly."
+                .to_string(),
+        );
+    }
+    if config.office_bridge {
+        lines.push(
+            "- For LibreOffice documents, use `computer_office` to open files, jump to spreadsheet cells, and export PDFs; continue with the GUI tools for editing, and switch to them entirely if it reports the integration is unavailable."
+                .to_string(),
+        );
+    }
+    if config.browser_bridge {
+        lines.push(
+            "- In the browser, find links, buttons, and fields with `computer_dom_query` and click them with `computer_dom_click` instead of reading coordinates off a screenshot; both act on the active tab. Type into a field after clicking it with `computer_type`."
+                .to_string(),
+        );
+    }
+    if !config.recipes.is_empty() {
+        lines.push(
+            "- `recipe_*` tools run steps the user saved for one app. When the app a recipe names is focused and a recipe does what you need, call it instead of clicking through the steps yourself."
+                .to_string(),
+        );
+    }
+    if config.demonstrations {
+        lines.push(
+            "- When the user offers to show you a flow, or describing it fails, call `computer_watch_demo`; it records their input while they perform it and returns the steps with screenshots. Only call it when the user is at the desktop."
+                .to_string(),
+        );
+    }
+    if config.theme_switching {
+        lines.push(
+            "- `computer_set_theme` switches the desktop between dark, light, and high-contrast themes. Take a fresh screenshot after each switch before judging the result."
+                .to_string(),
+        );
+    }
+    if config.system_controls {
+        lines.push(
+            "- To change the volume, mute audio, or dim the screen, call `computer_system_control` instead of dragging a slider."
+                .to_string(),
+        );
+    }
+    if config.password_guard {
+        lines.push(
+            "- `computer_type` refuses to type into password fields. Only pass `confirm_secret=true` when the user gave you the secret to enter; otherwise ask them to type it."
+                .to_string(),
+        );
+    }
+    if config.preserve_clipboard {
+        lines.push(
+            "- Copy and paste shortcuts in GUI actions use a clipboard of your own: pastes insert what you last copied, never the user's clipboard, and the user's clipboard is restored after each action."
+                .to_string(),
+        );
+    }
+    if config.unlock_helper {
+        lines.push(
+            "- If an action fails because the screen is locked, call `computer_unlock` once to unlock it with the user's stored secret, then take a screenshot. If it fails, stop and tell the user."
+                .to_string(),
+        );
+    }
+    if let Some(vm) = &config.vm {
+        lines.push(format!(
+            "- This session drives the `{}` VM. Before a risky or destructive GUI sequence (installers, system settings, deleting data), call `computer_checkpoint` so the user can roll back with `computex rollback`.",
+            vm.name
+        ));
+    }
+    if config.action_queue {
+        lines.push(
+            "- Action queue mode: GUI input tools are queued instead of run immediately. Plan the sequence from the latest screenshot, queue every step, then call `computer_run_queue`. The user reviews the queue and may reorder, delete, or edit actions, so check the result to see what actually ran and take a new screenshot before queueing more."
+                .to_string(),
+        );
+    }
+    if config.landmarks && !config.keyboard_only {
+        lines.push(
+            "- Use `computer_landmarks` to look up elements located in earlier sessions before searching screenshots, and save a landmark once a click lands on the intended element. Saved positions can go stale, so confirm them with a screenshot."
+                .to_string(),
+        );
+    }
+    if !config.allowed_apps.is_empty() {
+        let apps = config.allowed_apps.join(", ");
+        lines.push(format!(
+            "- GUI input is only accepted while one of these applications is focused: {apps}. Input to any other window is rejected."
+        ));
+    }
+    if !config.blocked_apps.is_empty() {
+        let apps = config.blocked_apps.join(", ");
+        lines.push(format!(
+            "- Never interact with these applications; GUI input is rejected while they are focused: {apps}."
+        ));
+    }
+    // Drop guidance about tools this backend or config doesn't offer, so the
+    // model isn't pointed at a tool it can't call.
+    lines.retain(|line| mentioned_tools(line).all(|tool| enabled.contains(&tool)));
+    lines.join("\n")
+}
+
+/// The `computer_*` tools a line of guidance names in backticks.
+fn mentioned_tools(line: &str) -> impl Iterator<Item = &str> {
+    line.split('`').skip(1).step_by(2).filter(|name| {
+        name.starts_with("computer_") && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+fn coordinate_guidance(config: &ComputerUseConfig) -> String {
+    match config.coordinate_space {
+        CoordinateSpace::Virtual => {
+            let resolution = config.resolution;
+            format!(
+                "- The GUI coordinate space is always {resolution}. All computer-use tools expect coordinates in that space."
+            )
+        }
+        CoordinateSpace::Normalized => "- GUI coordinates are normalized: x and y are fractions of the screen width and height between 0.0 and 1.0 (0,0 is the top-left corner, 1,1 the bottom-right). Tool results echo coordinates in the same form.".to_string(),
+        CoordinateSpace::Native => "- GUI coordinates are native screen pixels. Screenshots are captured at the display's full resolution, so use pixel positions exactly as they appear in the latest screenshot.".to_string(),
+    }
+}
+
+fn format_combo(combo: &[&str]) -> String {
+    combo
+        .iter()
+        .map(|key| {
+            let mut chars = key.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("+")
+}
+
+/// Where shell commands run when the session drives another machine.
+fn remote_line(config: &ComputerUseConfig) -> Option<String> {
+    let target = config.target.as_ref()?;
+    Some(format!(
+        "- This session drives `{host}` over SSH: the screen is that machine's, and shell commands run there too, in the same directory if it exists and in the remote home directory otherwise. `apply_patch` still edits local files, so change files on `{host}` with shell commands.",
+        host = target.host,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::types::ComputerUseBackend;
+
+    #[test]
+    fn gui_prompt_lists_tools_and_safety_rules() {
+        let prompt = computer_use_prompt(&ComputerUseConfig::default(), true);
+        assert!(!prompt.contains("{{"));
+        assert!(prompt.contains("The GUI coordinate space is always 1280x720."));
+        assert!(prompt.contains("`computer_screenshot`, `computer_click`"));
+        assert!(
+            prompt.contains("(Alt+F4, Ctrl+W, Ctrl+Q, Ctrl+Shift+Q, Super+Q, Super+W, Super+Shift+W, Ctrl+Alt+Backspace)")
+        );
+    }
+
+    #[test]
+    fn prompt_names_the_target_os_for_shortcuts() {
+        let prompt = computer_use_prompt(
+            &ComputerUseConfig {
+                target_os: TargetOs::Macos,
+                ..Default::default()
+            },
+            true,
+        );
+        assert!(prompt.contains("The GUI runs on macOS. In `computer_key`, `cmd` is the primary shortcut modifier (Cmd on macOS)"));
+    }
+
+    #[test]
+    fn normalized_prompt_replaces_coordinate_guidance() {
+        let prompt = computer_use_prompt(
+            &ComputerUseConfig {
+                coordinate_space: CoordinateSpace::Normalized,
+                ..Default::default()
+            },
+            true,
+        );
+        assert!(!prompt.contains("always 1280x720"));
+        assert!(prompt.contains("GUI coordinates are normalized"));
+    }
+
+    #[test]
+    fn prompt_reflects_profile_display_and_allowed_apps() {
+        let prompt = computer_use_prompt(
+            &ComputerUseConfig {
+                display: Some(":1".to_string()),
+                allowed_apps: vec!["firefox".to_string()],
+                ..Default::default()
+            },
+            true,
+        );
+        assert!(prompt.contains("(display `:1`)"));
+        assert!(prompt.contains("focused: firefox."));
+    }
+
+    #[test]
+    fn remote_target_prompt_says_where_commands_run() {
+        let config = ComputerUseConfig {
+            target: Some("ssh://me@lab/:1".parse().expect("target")),
+            ..Default::default()
+        };
+        for gui_enabled in [true, false] {
+            let prompt = computer_use_prompt(&config, gui_enabled);
+            assert!(prompt.contains("This session drives `lab` over SSH"));
+        }
+        assert!(!computer_use_prompt(&ComputerUseConfig::default(), true).contains("over SSH"));
+    }
+
+    #[test]
+    fn keyboard_only_prompt_steers_toward_focus_navigation() {
+        let prompt = computer_use_prompt(
+            &ComputerUseConfig {
+                keyboard_only: true,
+                ..Default::default()
+            },
+            true,
+        );
+        assert!(!prompt.contains("`computer_click`"));
+        assert!(!prompt.contains("`computer_landmarks`"));
+        assert!(prompt.contains("Keyboard-only mode: pointer tools are disabled."));
+        assert!(prompt.contains("`computer_focus_next`, `computer_focus_prev`"));
+    }
+
+    #[test]
+    fn console_prompt_only_names_console_tools() {
+        let config = ComputerUseConfig {
+            backend: ComputerUseBackend::Console,
+            max_screenshots_per_turn: Some(8),
+            ..Default::default()
+        };
+        let prompt = computer_use_prompt(&config, true);
+        let enabled = enabled_tools(&config);
+        for tool in mentioned_tools(&prompt) {
+            assert!(enabled.contains(&tool), "{tool} is not offered");
+        }
+        assert!(!prompt.contains("`computer_tray_list`"));
+        assert!(!prompt.contains("`computer_find_text`"));
+        assert!(prompt.contains("at most 8 image(s) per turn across `computer_screenshot`, and"));
+    }
+
+    #[test]
+    fn screenshot_budget_is_announced() {
+        let prompt = computer_use_prompt(
+            &ComputerUseConfig {
+                max_screenshots_per_turn: Some(8),
+                ..Default::default()
+            },
+            true,
+        );
+        assert!(prompt.contains("at most 8 image(s) per turn"));
+        assert!(
+            !computer_use_prompt(&ComputerUseConfig::default(), true)
+                .contains("Screenshots are budgeted")
+        );
+    }
+
+    #[test]
+    fn text_screenshots_are_announced() {
+        let prompt = computer_use_prompt(
+            &ComputerUseConfig {
+                screenshot_format: ScreenshotFormat::Text,
+                ..Default::default()
+            },
+            true,
+        );
+        assert!(prompt.contains("Screenshots come back as text in this session"));
+        assert!(
+            !computer_use_prompt(&ComputerUseConfig::default(), true)
+                .contains("Screenshots come back as text")
+        );
+    }
+
+    #[test]
+    fn headless_prompt_omits_gui_tools() {
+        let prompt = computer_use_prompt(&ComputerUseConfig::default(), false);
+        assert!(!prompt.contains("`computer_click`"));
+        assert!(prompt.contains("GUI tools are not available in this session."));
+        assert!(!prompt.contains("`computer_delegate`"));
+    }
+
+    #[test]
+    fn headless_prompt_with_delegate_points_at_sub_sessions() {
+        let prompt = computer_use_prompt(
+            &ComputerUseConfig {
+                delegate: true,
+                ..Default::default()
+            },
+            false,
+        );
+        assert!(!prompt.contains("`computer_click`"));
+        assert!(!prompt.contains("explain the limitation"));
+        assert!(prompt.contains("call `computer_delegate` with a self-contained task"));
+    }
+}
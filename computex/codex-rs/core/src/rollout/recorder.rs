@@ -26,6 +26,8 @@ use super::policy::is_persisted_response_item;
 use crate::config::Config;
 use crate::default_client::originator;
 use crate::git_info::collect_git_info;
+use crate::tools::handlers::computer_use::encryption;
+use crate::tools::handlers::computer_use::encryption::CaptureKey;
 use codex_protocol::protocol::InitialHistory;
 use codex_protocol::protocol::ResumedHistory;
 use codex_protocol::protocol::RolloutItem;
@@ -169,10 +171,25 @@ impl RolloutRecorder {
         // perform *blocking* I/O on the caller's thread.
         let (tx, rx) = mpsc::channel::<RolloutCmd>(256);
 
+        // Reading the capture key from the keyring blocks, so do it once here
+        // on a blocking thread rather than for each item the writer stores.
+        let image_key = if config.computer_use.encrypt_captures {
+            let key = tokio::task::spawn_blocking(|| encryption::capture_key(true))
+                .await
+                .map_err(anyhow::Error::from)
+                .and_then(|key| key);
+            if let Err(err) = &key {
+                warn!("screenshots will be left out of the rollout: {err:#}");
+            }
+            Some(key)
+        } else {
+            None
+        };
+
         // Spawn a Tokio task that owns the file handle and performs async
         // writes. Using `tokio::fs::File` keeps everything on the async I/O
         // driver instead of blocking the runtime.
-        tokio::task::spawn(rollout_writer(file, rx, meta, cwd));
+        tokio::task::spawn(rollout_writer(file, rx, meta, cwd, image_key));
 
         Ok(Self { tx, rollout_path })
     }
@@ -251,6 +268,9 @@ impl RolloutRecorder {
                     RolloutItem::EventMsg(_ev) => {
                         items.push(RolloutItem::EventMsg(_ev));
                     }
+                    RolloutItem::GuiAction(item) => {
+                        items.push(RolloutItem::GuiAction(item));
+                    }
                 },
                 Err(e) => {
                     warn!("failed to parse rollout line: {v:?}, error: {e}");
@@ -269,6 +289,15 @@ impl RolloutRecorder {
         if items.is_empty() {
             return Ok(InitialHistory::New);
         }
+        if items.iter().any(encryption::has_encrypted_images) {
+            let key = tokio::task::spawn_blocking(|| encryption::capture_key(false))
+                .await
+                .map_err(anyhow::Error::from)
+                .and_then(|key| key);
+            for item in &mut items {
+                encryption::decrypt_images(item, &key);
+            }
+        }
 
         info!("Resumed rollout successfully from {path:?}");
         Ok(InitialHistory::Resumed(ResumedHistory {
@@ -351,8 +380,9 @@ async fn rollout_writer(
     mut rx: mpsc::Receiver<RolloutCmd>,
     mut meta: Option<SessionMeta>,
     cwd: std::path::PathBuf,
+    image_key: Option<anyhow::Result<CaptureKey>>,
 ) -> std::io::Result<()> {
-    let mut writer = JsonlWriter { file };
+    let mut writer = JsonlWriter { file, image_key };
 
     // If we have a meta, collect git info asynchronously and write meta first
     if let Some(session_meta) = meta.take() {
@@ -397,10 +427,16 @@ async fn rollout_writer(
 
 struct JsonlWriter {
     file: tokio::fs::File,
+    /// Key images are stored encrypted with, for
+    /// `computer_use.encrypt_captures`; an error when it couldn't be loaded.
+    image_key: Option<anyhow::Result<CaptureKey>>,
 }
 
 impl JsonlWriter {
-    async fn write_rollout_item(&mut self, rollout_item: RolloutItem) -> std::io::Result<()> {
+    async fn write_rollout_item(&mut self, mut rollout_item: RolloutItem) -> std::io::Result<()> {
+        if let Some(key) = &self.image_key {
+            encryption::encrypt_images(&mut rollout_item, key);
+        }
         let timestamp_format: &[FormatItem] = format_description!(
             "[year]-[month]-[day]T[hour]:[minute]:[second].[subsecond digits:3]Z"
         );
@@ -12,6 +12,7 @@ use codex_protocol::models::ResponseInputItem;
 use tokio::sync::oneshot;
 
 use crate::codex::TurnContext;
+use crate::protocol::QueuedGuiAction;
 use crate::protocol::ReviewDecision;
 use crate::tasks::SessionTask;
 
@@ -67,6 +68,7 @@ impl ActiveTurn {
 #[derive(Default)]
 pub(crate) struct TurnState {
     pending_approvals: HashMap<String, oneshot::Sender<ReviewDecision>>,
+    pending_gui_queue_reviews: HashMap<String, oneshot::Sender<Option<Vec<QueuedGuiAction>>>>,
     pending_input: Vec<ResponseInputItem>,
 }
 
@@ -86,8 +88,24 @@ impl TurnState {
         self.pending_approvals.remove(key)
     }
 
+    pub(crate) fn insert_pending_gui_queue_review(
+        &mut self,
+        key: String,
+        tx: oneshot::Sender<Option<Vec<QueuedGuiAction>>>,
+    ) -> Option<oneshot::Sender<Option<Vec<QueuedGuiAction>>>> {
+        self.pending_gui_queue_reviews.insert(key, tx)
+    }
+
+    pub(crate) fn remove_pending_gui_queue_review(
+        &mut self,
+        key: &str,
+    ) -> Option<oneshot::Sender<Option<Vec<QueuedGuiAction>>>> {
+        self.pending_gui_queue_reviews.remove(key)
+    }
+
     pub(crate) fn clear_pending(&mut self) {
         self.pending_approvals.clear();
+        self.pending_gui_queue_reviews.clear();
         self.pending_input.clear();
     }
 
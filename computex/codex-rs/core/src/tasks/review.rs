@@ -11,6 +11,7 @@ use codex_protocol::protocol::EventMsg;
 use codex_protocol::protocol::ExitedReviewModeEvent;
 use codex_protocol::protocol::ItemCompletedEvent;
 use codex_protocol::protocol::ReviewOutputEvent;
+use codex_protocol::protocol::SubAgentSource;
 use tokio_util::sync::CancellationToken;
 
 use crate::codex::Session;
@@ -94,6 +95,7 @@ async fn start_review_conversation(
     sub_agent_config.model = Some(config.review_model.clone());
     (run_codex_conversation_one_shot(
         sub_agent_config,
+        SubAgentSource::Review,
         session.auth_manager(),
         session.models_manager(),
         input,
@@ -0,0 +1,424 @@
+//! Element lookup through the AT-SPI accessibility tree, the first locator
+//! strategy.
+//!
+//! GTK, Qt, Firefox, Chromium, and LibreOffice publish their widgets on the
+//! accessibility bus with names, roles, and screen extents, which is exact
+//! where OCR guesses. Only the application owning the active window is
+//! searched, and only showing elements are descended into. Apps that hang
+//! can stall D-Bus calls indefinitely, so the walk runs on its own thread
+//! under a deadline.
+
+use std::sync::mpsc;
+use std::time::Duration;
+use std::time::Instant;
+
+use zbus::blocking::Connection;
+use zbus::zvariant::OwnedObjectPath;
+use zbus::zvariant::OwnedValue;
+
+use super::find_text::similarity;
+
+const REGISTRY: &str = "org.a11y.atspi.Registry";
+const ROOT_PATH: &str = "/org/a11y/atspi/accessible/root";
+const ACCESSIBLE: &str = "org.a11y.atspi.Accessible";
+const COMPONENT: &str = "org.a11y.atspi.Component";
+const ACTION: &str = "org.a11y.atspi.Action";
+
+/// Bits of the AT-SPI state set.
+const STATE_ACTIVE: u32 = 1;
+const STATE_FOCUSED: u32 = 12;
+const STATE_SHOWING: u32 = 25;
+
+/// Elements inspected before the walk gives up, to bound its cost in large
+/// documents.
+const MAX_NODES: usize = 3000;
+
+/// How long the whole lookup may take.
+const DEADLINE: Duration = Duration::from_secs(4);
+
+/// How long the focus lookup may take. It runs before every typing call, so
+/// it gives up sooner.
+const FOCUS_DEADLINE: Duration = Duration::from_millis(1500);
+
+/// A named element on screen, with its extents in screen pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct AccessibleElement {
+    pub name: String,
+    pub role: String,
+    pub left: i64,
+    pub top: i64,
+    pub width: i64,
+    pub height: i64,
+    /// Similarity of the name to the query, in `0..=1`.
+    pub score: f64,
+}
+
+/// A menu item with a keyboard accelerator.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct MenuAccelerator {
+    /// Labels of the menus leading to the item, then the item's own.
+    pub path: Vec<String>,
+    /// What the app reports, e.g. `o;<Alt>f:o;<Control>o` from GTK.
+    pub binding: String,
+}
+
+/// The showing element of the focused application whose name best matches
+/// `query`, if any has a name.
+pub(super) fn find(query: &str) -> Result<Option<AccessibleElement>, String> {
+    let query = query.trim().to_lowercase();
+    on_thread(DEADLINE, move |bus| {
+        let mut best: Option<AccessibleElement> = None;
+        walk(bus, DEADLINE, |name, path, _| {
+            let beat = best.as_ref().map_or(0.0, |best| best.score);
+            if let Some(element) = element(bus, name, path, &query, beat) {
+                best = Some(element);
+            }
+            false
+        })?;
+        Ok(best)
+    })
+}
+
+/// The role of the element with keyboard focus in the active window, such
+/// as `password text`, or `None` when no showing element has focus.
+pub(super) fn focused_role() -> Result<Option<String>, String> {
+    on_thread(FOCUS_DEADLINE, |bus| {
+        let mut role = None;
+        walk(bus, FOCUS_DEADLINE, |name, path, state| {
+            if !has(state, STATE_FOCUSED) {
+                return false;
+            }
+            role = bus
+                .call_method(Some(name), path, Some(ACCESSIBLE), "GetRoleName", &())
+                .ok()
+                .and_then(|reply| reply.body().deserialize().ok());
+            role.is_some()
+        })?;
+        Ok(role)
+    })
+}
+
+/// The smallest showing element with an action whose extents contain the
+/// screen point `(x, y)`: the control a click there operates. Its name may
+/// be empty and its score is 1.
+pub(super) fn element_at(x: i64, y: i64) -> Result<Option<AccessibleElement>, String> {
+    on_thread(FOCUS_DEADLINE, move |bus| {
+        let mut smallest: Option<AccessibleElement> = None;
+        walk(bus, FOCUS_DEADLINE, |name, path, _| {
+            let Some((left, top, width, height)) = extents(bus, name, path) else {
+                return false;
+            };
+            let inside = (left..left + width).contains(&x) && (top..top + height).contains(&y);
+            let smaller = smallest
+                .as_ref()
+                .is_none_or(|best| width * height < best.width * best.height);
+            if inside && smaller && n_actions(bus, name, path) > 0 {
+                smallest = Some(AccessibleElement {
+                    name: label(bus, name, path).unwrap_or_default(),
+                    role: role_name(bus, name, path).unwrap_or_default(),
+                    left,
+                    top,
+                    width,
+                    height,
+                    score: 1.0,
+                });
+            }
+            false
+        })?;
+        Ok(smallest)
+    })
+}
+
+/// The name of the application owning the active window and the
+/// accelerators of its menu items. Menus are searched even while closed,
+/// since their items stay in the tree.
+pub(super) fn menu_accelerators() -> Result<(String, Vec<MenuAccelerator>), String> {
+    on_thread(DEADLINE, |bus| {
+        let deadline = Instant::now() + DEADLINE;
+        let apps = children(bus, REGISTRY, ROOT_PATH).map_err(|err| err.to_string())?;
+        let mut visited = 0;
+        for (app_name, app_path) in apps {
+            let Ok(windows) = children(bus, &app_name, app_path.as_str()) else {
+                continue;
+            };
+            let active: Vec<_> = windows
+                .into_iter()
+                .filter(|(name, path)| {
+                    state(bus, name, path.as_str()).is_ok_and(|state| has(&state, STATE_ACTIVE))
+                })
+                .map(|(name, path)| (name, path, Vec::new()))
+                .collect();
+            if active.is_empty() {
+                continue;
+            }
+            let app = label(bus, &app_name, app_path.as_str()).unwrap_or_default();
+            let mut found = Vec::new();
+            let mut pending = active;
+            while let Some((name, path, menus)) = pending.pop() {
+                visited += 1;
+                if visited > MAX_NODES || Instant::now() > deadline {
+                    break;
+                }
+                let path = path.as_str();
+                let role = role_name(bus, &name, path).unwrap_or_default();
+                // Closed menus aren't showing, but their items are still there.
+                if !role.contains("menu")
+                    && !state(bus, &name, path).is_ok_and(|state| has(&state, STATE_SHOWING))
+                {
+                    continue;
+                }
+                let mut menus: Vec<String> = menus;
+                let item = label(bus, &name, path);
+                if role.ends_with("menu item")
+                    && let Some(item) = &item
+                    && let Some(binding) = key_binding(bus, &name, path)
+                {
+                    let mut path = menus.clone();
+                    path.push(item.clone());
+                    found.push(MenuAccelerator { path, binding });
+                }
+                if role == "menu"
+                    && let Some(item) = item
+                {
+                    menus.push(item);
+                }
+                if let Ok(kids) = children(bus, &name, path) {
+                    // Reversed, so the stack visits them in menu order.
+                    pending.extend(
+                        kids.into_iter()
+                            .rev()
+                            .map(|(name, path)| (name, path, menus.clone())),
+                    );
+                }
+            }
+            return Ok((app, found));
+        }
+        Err("no application with an active window is on the accessibility bus".to_string())
+    })
+}
+
+/// Runs `lookup` against the accessibility bus on its own thread, giving up
+/// after `deadline`.
+fn on_thread<T: Send + 'static>(
+    deadline: Duration,
+    lookup: impl FnOnce(&Connection) -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    let (sender, receiver) = mpsc::channel();
+    let spawned = std::thread::Builder::new()
+        .name("computex-atspi".to_string())
+        .spawn(move || {
+            let result = connect()
+                .map_err(|err| format!("the accessibility bus is unavailable: {err}"))
+                .and_then(|bus| lookup(&bus));
+            let _ = sender.send(result);
+        });
+    if let Err(err) = spawned {
+        return Err(format!("failed to start the accessibility lookup: {err}"));
+    }
+    match receiver.recv_timeout(deadline) {
+        Ok(result) => result,
+        Err(_) => Err(format!(
+            "the accessibility tree did not answer within {:.1} s",
+            deadline.as_secs_f64()
+        )),
+    }
+}
+
+/// Calls `visit` with the bus name, path, and state of each showing element
+/// of the active window until it returns true, `MAX_NODES` elements were
+/// seen, or `budget` runs out.
+fn walk(
+    bus: &Connection,
+    budget: Duration,
+    mut visit: impl FnMut(&str, &str, &[u32]) -> bool,
+) -> Result<(), String> {
+    let deadline = Instant::now() + budget;
+    let apps = children(bus, REGISTRY, ROOT_PATH).map_err(|err| err.to_string())?;
+    let mut visited = 0;
+    for (app_name, app_path) in apps {
+        let Ok(windows) = children(bus, &app_name, app_path.as_str()) else {
+            continue;
+        };
+        let active: Vec<_> = windows
+            .into_iter()
+            .filter(|(name, path)| {
+                state(bus, name, path.as_str()).is_ok_and(|state| has(&state, STATE_ACTIVE))
+            })
+            .collect();
+        let mut pending = active;
+        while let Some((name, path)) = pending.pop() {
+            visited += 1;
+            if visited > MAX_NODES || Instant::now() > deadline {
+                return Ok(());
+            }
+            let path = path.as_str();
+            let Ok(state) = state(bus, &name, path) else {
+                continue;
+            };
+            if !has(&state, STATE_SHOWING) {
+                continue;
+            }
+            if visit(&name, path, &state) {
+                return Ok(());
+            }
+            if let Ok(kids) = children(bus, &name, path) {
+                pending.extend(kids);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Connects to the accessibility bus, whose address the session bus hands out.
+fn connect() -> zbus::Result<Connection> {
+    let session = Connection::session()?;
+    let address: String = session
+        .call_method(
+            Some("org.a11y.Bus"),
+            "/org/a11y/bus",
+            Some("org.a11y.Bus"),
+            "GetAddress",
+            &(),
+        )?
+        .body()
+        .deserialize()?;
+    zbus::blocking::connection::Builder::address(address.as_str())?.build()
+}
+
+fn children(
+    bus: &Connection,
+    name: &str,
+    path: &str,
+) -> zbus::Result<Vec<(String, OwnedObjectPath)>> {
+    bus.call_method(Some(name), path, Some(ACCESSIBLE), "GetChildren", &())?
+        .body()
+        .deserialize()
+}
+
+fn state(bus: &Connection, name: &str, path: &str) -> zbus::Result<Vec<u32>> {
+    bus.call_method(Some(name), path, Some(ACCESSIBLE), "GetState", &())?
+        .body()
+        .deserialize()
+}
+
+/// The element's trimmed name, if it has one.
+fn label(bus: &Connection, name: &str, path: &str) -> Option<String> {
+    let value: OwnedValue = bus
+        .call_method(
+            Some(name),
+            path,
+            Some("org.freedesktop.DBus.Properties"),
+            "Get",
+            &(ACCESSIBLE, "Name"),
+        )
+        .ok()?
+        .body()
+        .deserialize()
+        .ok()?;
+    let label = String::try_from(value).ok()?;
+    let label = label.trim();
+    (!label.is_empty()).then(|| label.to_string())
+}
+
+fn role_name(bus: &Connection, name: &str, path: &str) -> Option<String> {
+    bus.call_method(Some(name), path, Some(ACCESSIBLE), "GetRoleName", &())
+        .ok()?
+        .body()
+        .deserialize()
+        .ok()
+}
+
+fn n_actions(bus: &Connection, name: &str, path: &str) -> i32 {
+    bus.call_method(
+        Some(name),
+        path,
+        Some("org.freedesktop.DBus.Properties"),
+        "Get",
+        &(ACTION, "NActions"),
+    )
+    .ok()
+    .and_then(|reply| reply.body().deserialize::<OwnedValue>().ok())
+    .and_then(|value| i32::try_from(value).ok())
+    .unwrap_or(0)
+}
+
+/// The element's screen extents as left, top, width, and height, if it has
+/// a nonempty area.
+fn extents(bus: &Connection, name: &str, path: &str) -> Option<(i64, i64, i64, i64)> {
+    let (left, top, width, height): (i32, i32, i32, i32) = bus
+        // Coordinate type 0 is screen coordinates.
+        .call_method(Some(name), path, Some(COMPONENT), "GetExtents", &(0u32,))
+        .ok()?
+        .body()
+        .deserialize()
+        .ok()?;
+    if width <= 0 || height <= 0 {
+        return None;
+    }
+    Some((
+        i64::from(left),
+        i64::from(top),
+        i64::from(width),
+        i64::from(height),
+    ))
+}
+
+/// The key binding of the element's first action, if it has one.
+fn key_binding(bus: &Connection, name: &str, path: &str) -> Option<String> {
+    let binding: String = bus
+        .call_method(Some(name), path, Some(ACTION), "GetKeyBinding", &(0i32,))
+        .ok()?
+        .body()
+        .deserialize()
+        .ok()?;
+    (!binding.trim().is_empty()).then_some(binding)
+}
+
+fn has(state: &[u32], bit: u32) -> bool {
+    let word = (bit / 32) as usize;
+    state
+        .get(word)
+        .is_some_and(|bits| bits & (1 << (bit % 32)) != 0)
+}
+
+/// The element at `path` scored against `query`, if it has a name, scores
+/// above `beat`, and has screen extents.
+fn element(
+    bus: &Connection,
+    name: &str,
+    path: &str,
+    query: &str,
+    beat: f64,
+) -> Option<AccessibleElement> {
+    let label = label(bus, name, path)?;
+    let score = similarity(&label.to_lowercase(), query);
+    if score <= beat {
+        return None;
+    }
+    let (left, top, width, height) = extents(bus, name, path)?;
+    let role = role_name(bus, name, path).unwrap_or_default();
+    Some(AccessibleElement {
+        name: label,
+        role,
+        left,
+        top,
+        width,
+        height,
+        score,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_state_bits_across_words() {
+        let state = [1 << STATE_ACTIVE | 1 << STATE_SHOWING, 1 << 2];
+        assert!(has(&state, STATE_ACTIVE));
+        assert!(has(&state, STATE_SHOWING));
+        assert!(has(&state, 34));
+        assert!(!has(&state, 30));
+        assert!(!has(&state, 70));
+    }
+}
@@ -0,0 +1,77 @@
+//! Click accuracy samples, for `computer_use.click_accuracy`.
+//!
+//! The model aims a click at the middle of a control it sees in a
+//! screenshot, so how far the click lands from the center of the control
+//! under it measures how well the model grounds what it sees. Right before
+//! `computer_click` sends input, the accessibility tree is asked for the
+//! smallest actionable element at the target pixel; the sample goes into
+//! the GUI action timeline, and `computex stats` sums the samples per
+//! coordinate space so different virtual resolutions can be compared.
+//! Clicks on apps without accessibility support, or on empty areas, give
+//! no sample.
+
+use super::accessibility;
+use super::accessibility::AccessibleElement;
+use super::coordinates::MappedPoint;
+use crate::config::types::ComputerUseBackend;
+use crate::config::types::ComputerUseConfig;
+use crate::protocol::GuiClickAccuracy;
+
+/// The sample for a click at `point`, when the config asks for one and an
+/// element is there.
+pub(super) fn measure(config: &ComputerUseConfig, point: &MappedPoint) -> Option<GuiClickAccuracy> {
+    if !config.click_accuracy || config.backend != ComputerUseBackend::X11 {
+        return None;
+    }
+    let element = accessibility::element_at(point.screen_x, point.screen_y)
+        .inspect_err(|err| tracing::debug!("no click accuracy sample: {err}"))
+        .ok()??;
+    Some(sample(config, point, &element))
+}
+
+fn sample(
+    config: &ComputerUseConfig,
+    point: &MappedPoint,
+    element: &AccessibleElement,
+) -> GuiClickAccuracy {
+    GuiClickAccuracy {
+        coordinate_space: config.coordinate_label(),
+        clicked_x: point.screen_x,
+        clicked_y: point.screen_y,
+        center_x: element.left + element.width / 2,
+        center_y: element.top + element.height / 2,
+        element_width: element.width,
+        element_height: element.height,
+        role: element.role.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn samples_the_offset_from_the_element_center() {
+        let button = AccessibleElement {
+            name: "Save".to_string(),
+            role: "push button".to_string(),
+            left: 100,
+            top: 40,
+            width: 80,
+            height: 30,
+            score: 1.0,
+        };
+        let point = MappedPoint {
+            model_x: 400.0,
+            model_y: 200.0,
+            screen_x: 170,
+            screen_y: 55,
+        };
+        let sample = sample(&ComputerUseConfig::default(), &point, &button);
+        assert_eq!((sample.center_x, sample.center_y), (140, 55));
+        assert_eq!(sample.role, "push button");
+        assert_eq!(sample.distance(), 30.0);
+        assert_eq!(sample.relative_offset(), 0.75);
+    }
+}
@@ -0,0 +1,167 @@
+//! Properties of the focused window for `computer_active_window`.
+//!
+//! Title, class, process, geometry, and monitor come from a handful of
+//! xdotool and xrandr queries, with no capture, so the model can check where
+//! it is for a fraction of a screenshot's cost.
+
+use std::path::Path;
+
+use which::which;
+
+use super::recovery::xdotool_output;
+use super::virtual_output;
+use crate::config::types::ComputerUseConfig;
+use crate::config::types::Viewport;
+use crate::function_tool::FunctionCallError;
+
+/// The focused window, with its geometry in screen pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct WindowInfo {
+    pub id: String,
+    pub title: String,
+    pub class: String,
+    /// From `_NET_WM_PID`, which some clients don't set.
+    pub pid: Option<u32>,
+    pub geometry: Geometry,
+    /// The xrandr output holding the window's center, with its geometry.
+    pub monitor: Option<(String, Viewport)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Geometry {
+    pub x: i64,
+    pub y: i64,
+    pub width: i64,
+    pub height: i64,
+}
+
+/// Reads the focused window's properties.
+pub(super) fn read(
+    xdotool: &Path,
+    config: &ComputerUseConfig,
+) -> Result<WindowInfo, FunctionCallError> {
+    let id = xdotool_output(xdotool, config, &["getactivewindow"])?;
+    if id.is_empty() {
+        return Err(FunctionCallError::RespondToModel(
+            "no window has focus".to_string(),
+        ));
+    }
+    let title = xdotool_output(xdotool, config, &["getwindowname", &id]).unwrap_or_default();
+    let class = xdotool_output(xdotool, config, &["getwindowclassname", &id]).unwrap_or_default();
+    let pid = xdotool_output(xdotool, config, &["getwindowpid", &id])
+        .ok()
+        .and_then(|pid| pid.parse().ok());
+    let shell = xdotool_output(xdotool, config, &["getwindowgeometry", "--shell", &id])?;
+    let geometry = parse_geometry(&shell).ok_or_else(|| {
+        FunctionCallError::RespondToModel(format!(
+            "xdotool getwindowgeometry printed unexpected output: {shell}"
+        ))
+    })?;
+    let monitor = which("xrandr")
+        .ok()
+        .and_then(|xrandr| virtual_output::query_outputs(&xrandr, config).ok())
+        .and_then(|outputs| {
+            let monitors: Vec<(String, Viewport)> = outputs
+                .into_iter()
+                .filter_map(|output| Some((output.name, output.geometry?)))
+                .collect();
+            monitor_of(&monitors, geometry)
+        });
+    Ok(WindowInfo {
+        id,
+        title,
+        class,
+        pid,
+        geometry,
+        monitor,
+    })
+}
+
+/// Reads `xdotool getwindowgeometry --shell` output (`X=10`, `Y=20`,
+/// `WIDTH=800`, `HEIGHT=600`, one per line).
+fn parse_geometry(shell: &str) -> Option<Geometry> {
+    let value = |key: &str| {
+        shell.lines().find_map(|line| {
+            line.strip_prefix(key)?
+                .strip_prefix('=')?
+                .trim()
+                .parse::<i64>()
+                .ok()
+        })
+    };
+    Some(Geometry {
+        x: value("X")?,
+        y: value("Y")?,
+        width: value("WIDTH")?,
+        height: value("HEIGHT")?,
+    })
+}
+
+/// The monitor that holds the center of `window`, or the first one it
+/// overlaps when the center is off screen.
+fn monitor_of(monitors: &[(String, Viewport)], window: Geometry) -> Option<(String, Viewport)> {
+    let contains = |area: &Viewport, x: i64, y: i64| {
+        let (left, top) = (i64::from(area.x), i64::from(area.y));
+        (left..left + i64::from(area.width)).contains(&x)
+            && (top..top + i64::from(area.height)).contains(&y)
+    };
+    let center = (window.x + window.width / 2, window.y + window.height / 2);
+    monitors
+        .iter()
+        .find(|(_, area)| contains(area, center.0, center.1))
+        .or_else(|| {
+            monitors.iter().find(|(_, area)| {
+                window.x < i64::from(area.x) + i64::from(area.width)
+                    && i64::from(area.x) < window.x + window.width
+                    && window.y < i64::from(area.y) + i64::from(area.height)
+                    && i64::from(area.y) < window.y + window.height
+            })
+        })
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn reads_geometry_and_picks_the_monitor() {
+        let geometry =
+            parse_geometry("WINDOW=62914567\nX=2100\nY=40\nWIDTH=1200\nHEIGHT=900\nSCREEN=0\n");
+        assert_eq!(
+            geometry,
+            Some(Geometry {
+                x: 2100,
+                y: 40,
+                width: 1200,
+                height: 900,
+            })
+        );
+        assert_eq!(parse_geometry("WINDOW=1\nX=0\n"), None);
+
+        let monitor = |name: &str, x, width| {
+            (
+                name.to_string(),
+                Viewport {
+                    width,
+                    height: 1080,
+                    x,
+                    y: 0,
+                },
+            )
+        };
+        let monitors = [monitor("eDP-1", 0, 1920), monitor("DP-1", 1920, 2560)];
+        let window = |x| Geometry {
+            x,
+            y: 100,
+            width: 800,
+            height: 600,
+        };
+        let name = |window| monitor_of(&monitors, window).map(|(name, _)| name);
+        assert_eq!(name(window(100)), Some("eDP-1".to_string()));
+        assert_eq!(name(window(1700)), Some("DP-1".to_string()));
+        assert_eq!(name(window(4400)), Some("DP-1".to_string()));
+        assert_eq!(name(window(5000)), None);
+    }
+}
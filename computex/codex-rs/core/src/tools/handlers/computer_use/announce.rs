@@ -0,0 +1,68 @@
+//! Spoken announcements for `computer_announce`.
+//!
+//! On a shared desktop the human may be using the mouse when the agent takes
+//! over. The tool speaks a short warning through speech-dispatcher's
+//! `spd-say`, or `espeak-ng`/`espeak` where speech-dispatcher isn't
+//! installed, and waits until it has been spoken so input starts after the
+//! human has heard it.
+
+use std::path::PathBuf;
+
+use which::which;
+
+use super::gui_command;
+use crate::config::types::ComputerUseConfig;
+use crate::function_tool::FunctionCallError;
+
+/// Longest text spoken in one call.
+const MAX_ANNOUNCEMENT_CHARS: usize = 300;
+
+/// Speech programs in order of preference, with the flags that make them
+/// block until the text has been spoken.
+const SPEAKERS: &[(&str, &[&str])] = &[
+    ("spd-say", &["--wait", "--"]),
+    ("espeak-ng", &["--"]),
+    ("espeak", &["--"]),
+];
+
+/// Speaks `text` and returns the name of the program that spoke it.
+pub(super) fn announce(
+    config: &ComputerUseConfig,
+    text: &str,
+) -> Result<&'static str, FunctionCallError> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(FunctionCallError::RespondToModel(
+            "computer_announce needs non-empty text".to_string(),
+        ));
+    }
+    if text.chars().count() > MAX_ANNOUNCEMENT_CHARS {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "announcements are limited to {MAX_ANNOUNCEMENT_CHARS} characters; keep it to a short warning"
+        )));
+    }
+    let (name, program, flags) = speaker().ok_or_else(|| {
+        FunctionCallError::RespondToModel(
+            "computer_announce needs spd-say (speech-dispatcher), espeak-ng, or espeak".to_string(),
+        )
+    })?;
+    let output = gui_command(&program, config)
+        .args(flags)
+        .arg(text)
+        .output()
+        .map_err(|err| FunctionCallError::RespondToModel(format!("failed to run {name}: {err}")))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(FunctionCallError::RespondToModel(format!(
+            "{name} failed: {}",
+            stderr.trim()
+        )));
+    }
+    Ok(name)
+}
+
+fn speaker() -> Option<(&'static str, PathBuf, &'static [&'static str])> {
+    SPEAKERS
+        .iter()
+        .find_map(|(name, flags)| which(name).ok().map(|program| (*name, program, *flags)))
+}
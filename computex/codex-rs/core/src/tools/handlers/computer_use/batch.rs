@@ -0,0 +1,354 @@
+//! `computer_batch`: several input actions in one call, with conditions
+//! checked locally.
+//!
+//! Common branching ("dismiss the cookie banner if there is one") otherwise
+//! costs a screenshot and a model turn. A step may carry a condition,
+//! `if_window_exists`, `if_text_visible` or `if_pixel`, and runs only when it
+//! holds; otherwise it is skipped. `retry` tries a step again, after a pause,
+//! until its condition holds and it succeeds. Every step runs as the tool it
+//! names, with the same checks as when the model calls that tool.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use codex_utils_image::screenshot::RawImage;
+
+use super::ComputerUseHandler;
+use super::QUEUED_TOOLS;
+use super::capture_root_window;
+use super::check_side_effects;
+use super::classifier;
+use super::coordinates::map_point;
+use super::find_text;
+use super::focus_guard;
+use super::input_command;
+use super::schema::BatchArgs;
+use super::schema::BatchStep;
+use super::schema::PixelCondition;
+use super::schema::check_args;
+use super::schema::parse_args;
+use super::screen_geometry;
+use crate::codex::Session;
+use crate::codex::TurnContext;
+use crate::config::types::ComputerUseConfig;
+use crate::function_tool::FunctionCallError;
+use crate::tools::context::ToolOutput;
+
+/// Most steps one batch may hold.
+const MAX_STEPS: usize = 50;
+
+/// Most tries `retry` may ask for.
+const MAX_ATTEMPTS: u32 = 10;
+
+/// Pause between tries unless the step sets one.
+const DEFAULT_RETRY_DELAY_MS: u64 = 500;
+
+/// Longest pause between tries.
+const MAX_RETRY_DELAY_MS: u64 = 5_000;
+
+/// Per-channel difference under which `if_pixel` matches by default.
+const DEFAULT_PIXEL_TOLERANCE: u8 = 16;
+
+/// Runs the steps of a `computer_batch` call in order, stopping at the first
+/// failure.
+pub(super) async fn run(
+    session: &Arc<Session>,
+    turn: &Arc<TurnContext>,
+    call_id: String,
+    arguments: &str,
+) -> Result<ToolOutput, FunctionCallError> {
+    let args: BatchArgs = parse_args(arguments)?;
+    check_steps(&args.steps)?;
+    let config = &turn.tools_config.computer_use;
+    let total = args.steps.len();
+    let mut lines = vec![format!("ran a batch of {total} step(s):")];
+    for (index, step) in args.steps.iter().enumerate() {
+        let number = index + 1;
+        let attempts = step
+            .retry
+            .as_ref()
+            .map_or(1, |retry| retry.attempts.clamp(1, MAX_ATTEMPTS));
+        let delay = Duration::from_millis(
+            step.retry
+                .as_ref()
+                .and_then(|retry| retry.delay_ms)
+                .unwrap_or(DEFAULT_RETRY_DELAY_MS)
+                .min(MAX_RETRY_DELAY_MS),
+        );
+        let step_arguments = serde_json::Value::Object(step.arguments.clone()).to_string();
+        let mut outcome = None;
+        for attempt in 1..=attempts {
+            if attempt > 1 {
+                tokio::time::sleep(delay).await;
+            }
+            match unmet_condition(session, config, step).await {
+                Ok(None) => {}
+                Ok(Some(reason)) => {
+                    outcome = Some(Ok(format!("skipped: {reason}")));
+                    continue;
+                }
+                Err(err) => {
+                    outcome = Some(Err(step_error(err)?));
+                    continue;
+                }
+            }
+            let checked = async {
+                classifier::check(session, turn, &call_id, &step.tool, &step_arguments).await?;
+                check_side_effects(session, turn, &call_id, &step.tool, &step_arguments).await
+            }
+            .await;
+            // A refused step isn't tried again.
+            if let Err(err) = checked {
+                outcome = Some(Err(step_error(err)?));
+                break;
+            }
+            let result = ComputerUseHandler::run_counted(
+                Arc::clone(session),
+                Arc::clone(turn),
+                call_id.clone(),
+                step.tool.clone(),
+                step_arguments.clone(),
+            )
+            .await;
+            match result {
+                Ok(ToolOutput::Function { content, .. }) => {
+                    outcome = Some(Ok(content));
+                    break;
+                }
+                Ok(_) => {
+                    outcome = Some(Ok("done".to_string()));
+                    break;
+                }
+                Err(err) => outcome = Some(Err(step_error(err)?)),
+            }
+        }
+        let tries = if attempts > 1 {
+            format!(" (after up to {attempts} tries)")
+        } else {
+            String::new()
+        };
+        match outcome {
+            Some(Ok(content)) => lines.push(format!("{number}. {}: {content}", step.tool)),
+            Some(Err(err)) => {
+                lines.push(format!("{number}. {} failed{tries}: {err}", step.tool));
+                if number < total {
+                    lines.push(format!(
+                        "stopped; the remaining {} step(s) did not run",
+                        total - number
+                    ));
+                }
+                return Ok(ToolOutput::Function {
+                    content: lines.join("\n"),
+                    content_items: None,
+                    success: Some(false),
+                });
+            }
+            None => lines.push(format!("{number}. {}: did not run", step.tool)),
+        }
+    }
+    Ok(ToolOutput::Function {
+        content: lines.join("\n"),
+        content_items: None,
+        success: Some(true),
+    })
+}
+
+/// The message of a step's error, reported after the steps that already ran,
+/// or the error itself when it must end the turn.
+fn step_error(err: FunctionCallError) -> Result<String, FunctionCallError> {
+    match err {
+        FunctionCallError::RespondToModel(err) | FunctionCallError::Denied(err) => Ok(err),
+        err => Err(err),
+    }
+}
+
+/// Checks every step before any runs, so a typo in the last step doesn't
+/// leave the batch half done.
+fn check_steps(steps: &[BatchStep]) -> Result<(), FunctionCallError> {
+    if steps.is_empty() {
+        return Err(FunctionCallError::RespondToModel(
+            "computer_batch requires at least one step".to_string(),
+        ));
+    }
+    if steps.len() > MAX_STEPS {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "computer_batch takes at most {MAX_STEPS} steps, got {}",
+            steps.len()
+        )));
+    }
+    for (index, step) in steps.iter().enumerate() {
+        let number = index + 1;
+        if !QUEUED_TOOLS.contains(&step.tool.as_str()) {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "step {number}: `{}` can't run in a batch (available: {})",
+                step.tool,
+                QUEUED_TOOLS.join(", ")
+            )));
+        }
+        let arguments = serde_json::Value::Object(step.arguments.clone()).to_string();
+        check_args(&step.tool, &arguments).map_err(|err| match err {
+            FunctionCallError::RespondToModel(err) => {
+                FunctionCallError::RespondToModel(format!("step {number}: {err}"))
+            }
+            err => err,
+        })?;
+        if let Some(condition) = &step.if_pixel {
+            parse_color(&condition.color).map_err(|err| {
+                FunctionCallError::RespondToModel(format!("step {number}: {err}"))
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Why the step's condition doesn't hold, or `None` when it does or the
+/// step has none.
+async fn unmet_condition(
+    session: &Session,
+    config: &ComputerUseConfig,
+    step: &BatchStep,
+) -> Result<Option<String>, FunctionCallError> {
+    if let Some(pattern) = &step.if_window_exists {
+        let xdotool = input_command(config)?;
+        if focus_guard::find_window(&xdotool, config, pattern).is_none() {
+            return Ok(Some(format!("no window matches `{pattern}`")));
+        }
+    }
+    if let Some(text) = &step.if_text_visible {
+        let image = capture_root_window(config, None, None)?;
+        let tsv = find_text::recognize(&image);
+        let _ = std::fs::remove_file(&image);
+        if find_text::find_matches(&tsv?, text, find_text::DEFAULT_MIN_SIMILARITY).is_empty() {
+            return Ok(Some(format!("`{text}` is not on screen")));
+        }
+    }
+    if let Some(condition) = &step.if_pixel {
+        let found = pixel(session, config, condition).await?;
+        let expected = parse_color(&condition.color).map_err(FunctionCallError::RespondToModel)?;
+        let tolerance = condition.tolerance.unwrap_or(DEFAULT_PIXEL_TOLERANCE);
+        if !color_matches(found, expected, tolerance) {
+            return Ok(Some(format!(
+                "the pixel is {} rather than {}",
+                format_color(found),
+                condition.color
+            )));
+        }
+    }
+    Ok(None)
+}
+
+/// The color of the pixel `condition` points at.
+async fn pixel(
+    session: &Session,
+    config: &ComputerUseConfig,
+    condition: &PixelCondition,
+) -> Result<[u8; 3], FunctionCallError> {
+    let xdotool = input_command(config)?;
+    let (screen_w, screen_h) = screen_geometry(session, &xdotool, config).await?;
+    let point = map_point(config, condition.x, condition.y, screen_w, screen_h);
+    let path = capture_root_window(
+        config,
+        Some(format!("1x1+{}+{}", point.screen_x, point.screen_y)),
+        None,
+    )?;
+    let image = RawImage::open(&path);
+    let _ = std::fs::remove_file(&path);
+    let image = image.map_err(|err| {
+        FunctionCallError::RespondToModel(format!("failed to read the pixel: {err}"))
+    })?;
+    match image.pixels.get(..3) {
+        Some([r, g, b]) => Ok([*r, *g, *b]),
+        _ => Err(FunctionCallError::RespondToModel(
+            "failed to read the pixel: the capture is empty".to_string(),
+        )),
+    }
+}
+
+fn parse_color(color: &str) -> Result<[u8; 3], String> {
+    let invalid = || format!("invalid color `{color}`; expected \"#rrggbb\"");
+    let hex = color.trim().strip_prefix('#').ok_or_else(invalid)?;
+    if hex.len() != 6 || !hex.is_ascii() {
+        return Err(invalid());
+    }
+    let channel = |at: usize| u8::from_str_radix(&hex[at..at + 2], 16).map_err(|_| invalid());
+    Ok([channel(0)?, channel(2)?, channel(4)?])
+}
+
+fn format_color([r, g, b]: [u8; 3]) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+fn color_matches(found: [u8; 3], expected: [u8; 3], tolerance: u8) -> bool {
+    found
+        .iter()
+        .zip(expected)
+        .all(|(found, expected)| found.abs_diff(expected) <= tolerance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn steps(value: serde_json::Value) -> Vec<BatchStep> {
+        serde_json::from_value(value).expect("steps should parse")
+    }
+
+    #[test]
+    fn checks_every_step_before_running() {
+        assert_eq!(
+            check_steps(&steps(json!([
+                {"tool": "computer_key", "arguments": {"keys": ["Escape"]}, "if_text_visible": "Accept cookies"},
+                {"tool": "computer_click", "arguments": {"x": 10, "y": 20}, "retry": {"attempts": 3}},
+            ]))),
+            Ok(())
+        );
+        assert!(check_steps(&[]).is_err());
+        assert!(
+            check_steps(&steps(json!([{"tool": "computer_screenshot"}])))
+                .is_err_and(|err| err.to_string().contains("can't run in a batch"))
+        );
+        assert!(
+            check_steps(&steps(json!([
+                {"tool": "computer_key", "arguments": {"keys": ["Escape"]}},
+                {"tool": "computer_key", "arguments": {"keys": 1}},
+            ])))
+            .is_err_and(|err| err.to_string().starts_with("step 2:"))
+        );
+        assert!(
+            check_steps(&steps(json!([{
+                "tool": "computer_key",
+                "arguments": {"keys": ["Escape"]},
+                "if_pixel": {"x": 1, "y": 2, "color": "red"},
+            }])))
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn refusals_are_reported_with_the_steps_before_them() {
+        assert_eq!(
+            step_error(FunctionCallError::RespondToModel("declined".to_string())),
+            Ok("declined".to_string())
+        );
+        assert_eq!(
+            step_error(FunctionCallError::Denied("denied".to_string())),
+            Ok("denied".to_string())
+        );
+        assert_eq!(
+            step_error(FunctionCallError::Fatal("gone".to_string())),
+            Err(FunctionCallError::Fatal("gone".to_string()))
+        );
+    }
+
+    #[test]
+    fn pixel_colors_match_within_tolerance() {
+        assert_eq!(parse_color("#FF8000"), Ok([255, 128, 0]));
+        assert!(parse_color("ff8000").is_err());
+        assert!(parse_color("#ff80").is_err());
+        assert_eq!(format_color([255, 128, 0]), "#ff8000");
+        assert!(color_matches([250, 130, 0], [255, 128, 0], 16));
+        assert!(!color_matches([200, 128, 0], [255, 128, 0], 16));
+    }
+}
@@ -0,0 +1,217 @@
+//! Browser introspection for `computer_browser_state`.
+//!
+//! When a Chromium-family browser exposes the DevTools protocol
+//! (`computer_use.browser_debug_port`), tabs are listed from its `/json/list`
+//! endpoint. Otherwise the URL is copied out of the focused browser's address
+//! bar through the clipboard, and the page title is taken from the window name.
+
+use std::io::Write;
+use std::path::Path;
+use std::process::Stdio;
+use std::thread;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use super::gui_command;
+use super::require_command;
+use super::run_command;
+use crate::config::types::ComputerUseConfig;
+use crate::function_tool::FunctionCallError;
+
+/// Known browser window classes (lowercase) and their display names.
+const BROWSERS: &[(&str, &str)] = &[
+    ("firefox", "Firefox"),
+    ("firefox-esr", "Firefox"),
+    ("navigator", "Firefox"),
+    ("librewolf", "LibreWolf"),
+    ("chromium", "Chromium"),
+    ("chromium-browser", "Chromium"),
+    ("google-chrome", "Google Chrome"),
+    ("brave-browser", "Brave"),
+    ("microsoft-edge", "Microsoft Edge"),
+    ("vivaldi-stable", "Vivaldi"),
+    ("opera", "Opera"),
+];
+
+/// Gives the browser time to update the clipboard after the copy shortcut.
+pub(super) const CLIPBOARD_SETTLE: Duration = Duration::from_millis(150);
+const CDP_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub(super) struct BrowserTab {
+    #[serde(default)]
+    pub title: String,
+    pub url: String,
+    #[serde(rename = "type", default)]
+    kind: String,
+}
+
+/// Display name of the browser owning a window of `class`, if it is one.
+pub(super) fn browser_name(class: &str) -> Option<&'static str> {
+    let class = class.trim().to_ascii_lowercase();
+    BROWSERS
+        .iter()
+        .find(|(known, _)| *known == class)
+        .map(|(_, name)| *name)
+}
+
+/// Open page tabs reported by the DevTools endpoint on `port`. Chromium lists
+/// them most recently focused first.
+pub(super) async fn cdp_tabs(port: u16) -> Result<Vec<BrowserTab>, String> {
+    let url = format!("http://127.0.0.1:{port}/json/list");
+    let client = reqwest::Client::builder()
+        .timeout(CDP_TIMEOUT)
+        .build()
+        .map_err(|err| err.to_string())?;
+    let tabs: Vec<BrowserTab> = client
+        .get(&url)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|err| err.to_string())?
+        .json()
+        .await
+        .map_err(|err| err.to_string())?;
+    Ok(page_tabs(tabs))
+}
+
+fn page_tabs(tabs: Vec<BrowserTab>) -> Vec<BrowserTab> {
+    tabs.into_iter().filter(|tab| tab.kind == "page").collect()
+}
+
+/// Page title from a browser window name such as
+/// `Example Domain — Mozilla Firefox`.
+pub(super) fn title_from_window_name(window_name: &str) -> String {
+    let name = window_name.trim();
+    [" — ", " - "]
+        .iter()
+        .filter_map(|separator| name.rsplit_once(separator))
+        .find(|(_, suffix)| is_browser_suffix(suffix))
+        .map_or(name, |(title, _)| title)
+        .trim()
+        .to_string()
+}
+
+fn is_browser_suffix(suffix: &str) -> bool {
+    let suffix = suffix.to_ascii_lowercase();
+    [
+        "firefox",
+        "librewolf",
+        "chromium",
+        "chrome",
+        "brave",
+        "edge",
+        "vivaldi",
+        "opera",
+    ]
+    .iter()
+    .any(|browser| suffix.contains(browser))
+}
+
+/// Copies the focused browser's address bar through the clipboard: focus the
+/// bar, copy, then Escape to leave the page as it was. The previous clipboard
+/// contents are restored afterwards.
+pub(super) fn read_address_bar(
+    xdotool: &Path,
+    config: &ComputerUseConfig,
+) -> Result<String, FunctionCallError> {
+    let xclip = require_command("xclip")?;
+    let previous = read_clipboard(&xclip, config).ok();
+    let keys = ["key", "--clearmodifiers", "ctrl+l", "ctrl+c", "Escape"].map(str::to_string);
+    run_command(xdotool, &keys, config)?;
+    thread::sleep(CLIPBOARD_SETTLE);
+    let url = read_clipboard(&xclip, config);
+    if let Some(previous) = previous {
+        let _ = write_clipboard(&xclip, config, &previous);
+    }
+    let url = url?.trim().to_string();
+    if url.is_empty() {
+        return Err(FunctionCallError::RespondToModel(
+            "the address bar was empty or could not be copied".to_string(),
+        ));
+    }
+    Ok(url)
+}
+
+pub(super) fn read_clipboard(
+    xclip: &Path,
+    config: &ComputerUseConfig,
+) -> Result<String, FunctionCallError> {
+    let output = gui_command(xclip, config)
+        .args(["-selection", "clipboard", "-o"])
+        .output()
+        .map_err(|err| FunctionCallError::RespondToModel(format!("failed to run xclip: {err}")))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(FunctionCallError::RespondToModel(format!(
+            "xclip failed to read the clipboard: {stderr}"
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+pub(super) fn write_clipboard(
+    xclip: &Path,
+    config: &ComputerUseConfig,
+    contents: &str,
+) -> std::io::Result<()> {
+    // xclip keeps serving the selection from a forked child, so its output
+    // must not be captured or the wait would block on the open pipe.
+    let mut child = gui_command(xclip, config)
+        .args(["-selection", "clipboard", "-i"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(contents.as_bytes())?;
+    }
+    child.wait()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn recognizes_browser_classes() {
+        assert_eq!(browser_name("Firefox"), Some("Firefox"));
+        assert_eq!(browser_name("Google-chrome"), Some("Google Chrome"));
+        assert_eq!(browser_name("xterm"), None);
+    }
+
+    #[test]
+    fn strips_browser_suffix_from_window_name() {
+        assert_eq!(
+            title_from_window_name("Example Domain — Mozilla Firefox"),
+            "Example Domain"
+        );
+        assert_eq!(
+            title_from_window_name("Pull requests - GitHub - Google Chrome"),
+            "Pull requests - GitHub"
+        );
+        assert_eq!(title_from_window_name("Untitled"), "Untitled");
+    }
+
+    #[test]
+    fn keeps_only_page_tabs_from_devtools_listing() {
+        let listing = r#"[
+            {"type": "page", "title": "Docs", "url": "https://example.com/docs", "id": "A"},
+            {"type": "service_worker", "url": "https://example.com/sw.js"},
+            {"type": "page", "url": "about:blank"}
+        ]"#;
+        let tabs: Vec<BrowserTab> = serde_json::from_str(listing).expect("valid listing");
+        let tabs = page_tabs(tabs);
+        let summary: Vec<_> = tabs
+            .iter()
+            .map(|tab| (tab.title.as_str(), tab.url.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("Docs", "https://example.com/docs"), ("", "about:blank")]
+        );
+    }
+}
@@ -0,0 +1,546 @@
+//! The computex browser extension and its native messaging bridge, behind
+//! `computer_dom_query` and `computer_dom_click`.
+//!
+//! Browsers only let an extension talk to programs they start themselves,
+//! over stdin and stdout with length-prefixed JSON messages. So the extension
+//! starts `computex browser-bridge` as its native messaging host when the
+//! browser starts, and the host listens on `$CODEX_HOME/browser-bridge.sock`
+//! for requests from computex sessions, relaying each one to the extension
+//! and its answer back. The extension runs the request in the active tab of
+//! the user's own browser profile, logins and all, so DOM-level and
+//! desktop-level actions mix in one session. `computex setup` unpacks the
+//! extension and registers the host with the browsers it finds.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::time::Duration;
+use std::time::Instant;
+
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use serde_json::json;
+
+use crate::function_tool::FunctionCallError;
+
+/// Name the host is registered under with the browsers.
+pub const HOST_NAME: &str = "com.computex.bridge";
+/// Socket the host listens on, under `$CODEX_HOME`.
+pub const SOCKET_FILE: &str = "browser-bridge.sock";
+/// Directory under `$CODEX_HOME` the extension is unpacked to.
+pub const EXTENSION_DIR: &str = "browser-extension";
+/// Script under `$CODEX_HOME/bin` that browsers start as the host.
+const WRAPPER_NAME: &str = "computex-browser-bridge";
+
+/// Chromium derives extension ids from the manifest `key`; this is the id of
+/// the key in `templates/browser_extension/manifest.json`.
+const CHROMIUM_EXTENSION_ID: &str = "llabgiacnclebdkgpbkaoopjgfdoojai";
+const GECKO_EXTENSION_ID: &str = "bridge@computex";
+const EXTENSION_MANIFEST: &str =
+    include_str!("../../../../templates/browser_extension/manifest.json");
+const EXTENSION_SCRIPT: &str =
+    include_str!("../../../../templates/browser_extension/background.js");
+
+/// Native messaging host directories of Chromium-family browsers and of
+/// Firefox, relative to the home directory. A manifest is only written for
+/// browsers whose profile directory exists.
+#[cfg(target_os = "macos")]
+const CHROMIUM_PROFILES: &[&str] = &[
+    "Library/Application Support/Google/Chrome",
+    "Library/Application Support/Chromium",
+    "Library/Application Support/BraveSoftware/Brave-Browser",
+    "Library/Application Support/Microsoft Edge",
+    "Library/Application Support/Vivaldi",
+];
+#[cfg(target_os = "macos")]
+const CHROMIUM_HOSTS_DIR: &str = "NativeMessagingHosts";
+#[cfg(target_os = "macos")]
+const FIREFOX_HOSTS: (&str, &str) = (
+    "Library/Application Support/Mozilla",
+    "NativeMessagingHosts",
+);
+#[cfg(not(target_os = "macos"))]
+const CHROMIUM_PROFILES: &[&str] = &[
+    ".config/google-chrome",
+    ".config/chromium",
+    ".config/BraveSoftware/Brave-Browser",
+    ".config/microsoft-edge",
+    ".config/vivaldi",
+];
+#[cfg(not(target_os = "macos"))]
+const CHROMIUM_HOSTS_DIR: &str = "NativeMessagingHosts";
+#[cfg(not(target_os = "macos"))]
+const FIREFOX_HOSTS: (&str, &str) = (".mozilla", "native-messaging-hosts");
+
+/// How long the host waits for the extension, and a session for the host.
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(10);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Elements `computer_dom_query` returns when the call doesn't say.
+const DEFAULT_QUERY_LIMIT: u32 = 20;
+
+pub fn socket_path(codex_home: &Path) -> PathBuf {
+    codex_home.join(SOCKET_FILE)
+}
+
+/// Where [`install`] put the extension and the host manifests.
+#[derive(Debug)]
+pub struct Installed {
+    pub extension_dir: PathBuf,
+    pub host_manifests: Vec<PathBuf>,
+}
+
+/// Unpacks the extension under `codex_home`, writes the host script that
+/// starts `computex` in bridge mode, and registers it with every browser
+/// that has a profile under `home`.
+pub fn install(codex_home: &Path, home: &Path, computex: &Path) -> io::Result<Installed> {
+    let extension_dir = codex_home.join(EXTENSION_DIR);
+    std::fs::create_dir_all(&extension_dir)?;
+    std::fs::write(extension_dir.join("manifest.json"), EXTENSION_MANIFEST)?;
+    std::fs::write(extension_dir.join("background.js"), EXTENSION_SCRIPT)?;
+
+    // Browsers start the host without arguments of our choosing, so a
+    // script supplies the subcommand and the computex home.
+    let wrapper = codex_home.join("bin").join(WRAPPER_NAME);
+    if let Some(dir) = wrapper.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let quote = |path: &Path| {
+        let path = path.to_string_lossy();
+        shlex::try_quote(&path)
+            .map(std::borrow::Cow::into_owned)
+            .unwrap_or_else(|_| path.into_owned())
+    };
+    std::fs::write(
+        &wrapper,
+        format!(
+            "#!/bin/sh\nCODEX_HOME={} exec {} browser-bridge \"$@\"\n",
+            quote(codex_home),
+            quote(computex)
+        ),
+    )?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&wrapper, std::fs::Permissions::from_mode(0o755))?;
+    }
+
+    let mut host_manifests = Vec::new();
+    let description = "computex browser bridge";
+    let path = wrapper.to_string_lossy();
+    let chromium = json!({
+        "name": HOST_NAME,
+        "description": description,
+        "path": path,
+        "type": "stdio",
+        "allowed_origins": [format!("chrome-extension://{CHROMIUM_EXTENSION_ID}/")],
+    });
+    let firefox = json!({
+        "name": HOST_NAME,
+        "description": description,
+        "path": path,
+        "type": "stdio",
+        "allowed_extensions": [GECKO_EXTENSION_ID],
+    });
+    let targets = CHROMIUM_PROFILES
+        .iter()
+        .map(|profile| (home.join(profile), CHROMIUM_HOSTS_DIR, &chromium))
+        .chain([(home.join(FIREFOX_HOSTS.0), FIREFOX_HOSTS.1, &firefox)]);
+    for (profile, hosts_dir, manifest) in targets {
+        if !profile.is_dir() {
+            continue;
+        }
+        let dir = profile.join(hosts_dir);
+        std::fs::create_dir_all(&dir)?;
+        let target = dir.join(format!("{HOST_NAME}.json"));
+        std::fs::write(&target, format!("{manifest:#}\n"))?;
+        host_manifests.push(target);
+    }
+    Ok(Installed {
+        extension_dir,
+        host_manifests,
+    })
+}
+
+/// Writes one native messaging message: its length as a native-endian
+/// `u32`, then the JSON.
+fn write_message(writer: &mut impl Write, message: &JsonValue) -> io::Result<()> {
+    let bytes = serde_json::to_vec(message)?;
+    let len = u32::try_from(bytes.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "message too large"))?;
+    writer.write_all(&len.to_ne_bytes())?;
+    writer.write_all(&bytes)?;
+    writer.flush()
+}
+
+/// Reads one native messaging message, or `None` when the browser closed the
+/// port.
+fn read_message(reader: &mut impl Read) -> io::Result<Option<JsonValue>> {
+    let mut len = [0u8; 4];
+    match reader.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err),
+    }
+    let mut bytes = vec![0u8; u32::from_ne_bytes(len) as usize];
+    reader.read_exact(&mut bytes)?;
+    Ok(Some(serde_json::from_slice(&bytes)?))
+}
+
+/// Runs the native messaging host until the browser closes the port.
+#[cfg(unix)]
+pub fn serve_native_host(codex_home: &Path) -> anyhow::Result<()> {
+    use anyhow::Context;
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::net::UnixListener;
+
+    let path = socket_path(codex_home);
+    // A host left behind by an earlier browser gives way to this one.
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path)
+        .with_context(|| format!("failed to listen on {}", path.display()))?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+
+    let (responses, received) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut stdin = io::stdin().lock();
+        while let Ok(Some(message)) = read_message(&mut stdin) {
+            if responses.send(message).is_err() {
+                break;
+            }
+        }
+        // The browser closed the port, so nothing can answer requests any
+        // more; the accept loop would otherwise wait forever. The socket is
+        // left in place, since a newer host may already have replaced it.
+        std::process::exit(0);
+    });
+    let mut stdout = io::stdout().lock();
+    let mut next_id: u64 = 0;
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        next_id += 1;
+        if let Err(err) = relay(&stream, next_id, &mut stdout, &received) {
+            tracing::debug!("browser bridge request failed: {err}");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn serve_native_host(_codex_home: &Path) -> anyhow::Result<()> {
+    anyhow::bail!("the browser bridge is only supported on Unix hosts")
+}
+
+/// Passes one request line from a session to the extension as request `id`,
+/// and the extension's answer back as a line.
+#[cfg(unix)]
+fn relay(
+    stream: &std::os::unix::net::UnixStream,
+    id: u64,
+    browser: &mut impl Write,
+    responses: &mpsc::Receiver<JsonValue>,
+) -> io::Result<()> {
+    stream.set_read_timeout(Some(RESPONSE_TIMEOUT))?;
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    let response = match serde_json::from_str(&line) {
+        Ok(JsonValue::Object(mut request)) => {
+            request.insert("id".to_string(), id.into());
+            write_message(browser, &JsonValue::Object(request))?;
+            wait_for_response(responses, id)
+        }
+        _ => json!({ "ok": false, "error": "malformed bridge request" }),
+    };
+    let mut writer = stream;
+    writeln!(writer, "{response}")
+}
+
+fn wait_for_response(responses: &mpsc::Receiver<JsonValue>, id: u64) -> JsonValue {
+    let deadline = Instant::now() + RESPONSE_TIMEOUT;
+    loop {
+        match responses.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(response) if response.get("id").and_then(JsonValue::as_u64) == Some(id) => {
+                return response;
+            }
+            // A late answer to a request that already timed out.
+            Ok(_) => {}
+            Err(_) => {
+                return json!({
+                    "ok": false,
+                    "error": "the browser extension did not answer in time",
+                });
+            }
+        }
+    }
+}
+
+/// Sends `request` through the host and returns the extension's result.
+#[cfg(unix)]
+fn send(codex_home: &Path, request: &JsonValue) -> Result<JsonValue, String> {
+    let stream = std::os::unix::net::UnixStream::connect(socket_path(codex_home)).map_err(|_| {
+        "the computex browser extension isn't connected; run `computex setup`, load the extension it unpacks into the browser, and keep the browser open".to_string()
+    })?;
+    stream
+        .set_read_timeout(Some(REQUEST_TIMEOUT))
+        .and_then(|()| stream.set_write_timeout(Some(REQUEST_TIMEOUT)))
+        .map_err(|err| err.to_string())?;
+    let mut writer = &stream;
+    writeln!(writer, "{request}").map_err(|err| format!("failed to reach the bridge: {err}"))?;
+    let mut line = String::new();
+    BufReader::new(&stream)
+        .read_line(&mut line)
+        .map_err(|err| format!("the bridge did not answer: {err}"))?;
+    let response: JsonValue = serde_json::from_str(&line)
+        .map_err(|err| format!("the bridge sent an invalid answer: {err}"))?;
+    if response.get("ok").and_then(JsonValue::as_bool) == Some(true) {
+        Ok(response.get("result").cloned().unwrap_or(JsonValue::Null))
+    } else {
+        Err(response
+            .get("error")
+            .and_then(JsonValue::as_str)
+            .unwrap_or("the browser extension failed")
+            .to_string())
+    }
+}
+
+#[cfg(not(unix))]
+fn send(_codex_home: &Path, _request: &JsonValue) -> Result<JsonValue, String> {
+    Err("the browser bridge is only supported on Unix hosts".to_string())
+}
+
+/// The page the extension ran in and the elements it matched.
+#[derive(Debug, Deserialize)]
+struct PageResult {
+    url: String,
+    title: String,
+    total: usize,
+    #[serde(default)]
+    elements: Vec<Element>,
+    element: Option<Element>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Element {
+    index: usize,
+    tag: String,
+    text: String,
+    #[serde(default)]
+    attributes: BTreeMap<String, String>,
+    visible: bool,
+    rect: Rect,
+}
+
+#[derive(Debug, Deserialize)]
+struct Rect {
+    x: i64,
+    y: i64,
+    width: i64,
+    height: i64,
+}
+
+impl Element {
+    /// One line such as `[0] <button id="go"> "Sign in" at 412,310 96x32`.
+    fn describe(&self) -> String {
+        let attributes: String = self
+            .attributes
+            .iter()
+            .map(|(name, value)| format!(" {name}={value:?}"))
+            .collect();
+        let text = if self.text.is_empty() {
+            String::new()
+        } else {
+            format!(" {:?}", self.text)
+        };
+        let Rect {
+            x,
+            y,
+            width,
+            height,
+        } = self.rect;
+        let hidden = if self.visible { "" } else { " (hidden)" };
+        format!(
+            "[{}] <{}{attributes}>{text} at {x},{y} {width}x{height}{hidden}",
+            self.index, self.tag
+        )
+    }
+}
+
+fn matching(selector: &str, text: Option<&str>) -> String {
+    match text {
+        Some(text) => format!("`{selector}` with text {text:?}"),
+        None => format!("`{selector}`"),
+    }
+}
+
+fn parse_result(result: JsonValue) -> Result<PageResult, FunctionCallError> {
+    serde_json::from_value(result).map_err(|err| {
+        FunctionCallError::RespondToModel(format!(
+            "the browser extension sent an unexpected result: {err}"
+        ))
+    })
+}
+
+/// Elements matching `selector` in the active tab, for `computer_dom_query`.
+pub(super) fn query(
+    codex_home: &Path,
+    selector: &str,
+    text: Option<&str>,
+    limit: Option<u32>,
+) -> Result<String, FunctionCallError> {
+    let limit = limit.unwrap_or(DEFAULT_QUERY_LIMIT).max(1);
+    let request = json!({
+        "action": "query",
+        "selector": selector,
+        "text": text,
+        "limit": limit,
+    });
+    let page =
+        parse_result(send(codex_home, &request).map_err(FunctionCallError::RespondToModel)?)?;
+    Ok(format_query(&page, selector, text))
+}
+
+fn format_query(page: &PageResult, selector: &str, text: Option<&str>) -> String {
+    let matching = matching(selector, text);
+    let page_name = format!("{:?} ({})", page.title, page.url);
+    if page.total == 0 {
+        return format!("no element matches {matching} in {page_name}");
+    }
+    let shown = if page.elements.len() < page.total {
+        format!(", showing the first {}", page.elements.len())
+    } else {
+        String::new()
+    };
+    let mut lines = vec![format!(
+        "found {} element(s) matching {matching} in {page_name}{shown}; positions are CSS pixels in the page viewport:",
+        page.total
+    )];
+    lines.extend(page.elements.iter().map(Element::describe));
+    lines.join("\n")
+}
+
+/// Clicks the `index`th element matching `selector` in the active tab, for
+/// `computer_dom_click`.
+pub(super) fn click(
+    codex_home: &Path,
+    selector: &str,
+    text: Option<&str>,
+    index: Option<u32>,
+) -> Result<String, FunctionCallError> {
+    let request = json!({
+        "action": "click",
+        "selector": selector,
+        "text": text,
+        "index": index.unwrap_or(0),
+    });
+    let page =
+        parse_result(send(codex_home, &request).map_err(FunctionCallError::RespondToModel)?)?;
+    let Some(element) = &page.element else {
+        return Err(FunctionCallError::RespondToModel(
+            "the browser extension did not say what it clicked".to_string(),
+        ));
+    };
+    Ok(format!(
+        "clicked {} of {} element(s) matching {} in {:?} ({}); take a screenshot or query again to see the result",
+        element.describe(),
+        page.total,
+        matching(selector, text),
+        page.title,
+        page.url
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn installs_extension_and_host_manifests() {
+        let codex_home = tempfile::tempdir().expect("codex home");
+        let home = tempfile::tempdir().expect("home");
+        std::fs::create_dir_all(home.path().join(CHROMIUM_PROFILES[0])).expect("chrome profile");
+        std::fs::create_dir_all(home.path().join(FIREFOX_HOSTS.0)).expect("firefox profile");
+        let installed =
+            install(codex_home.path(), home.path(), Path::new("/opt/computex")).expect("install");
+
+        assert_eq!(
+            installed.extension_dir,
+            codex_home.path().join(EXTENSION_DIR)
+        );
+        let manifest: JsonValue = serde_json::from_str(
+            &std::fs::read_to_string(installed.extension_dir.join("manifest.json")).expect("read"),
+        )
+        .expect("extension manifest");
+        assert_eq!(
+            manifest["browser_specific_settings"]["gecko"]["id"],
+            GECKO_EXTENSION_ID
+        );
+        assert_eq!(installed.host_manifests.len(), 2);
+        let chrome: JsonValue = serde_json::from_str(
+            &std::fs::read_to_string(&installed.host_manifests[0]).expect("read"),
+        )
+        .expect("host manifest");
+        assert_eq!(chrome["name"], HOST_NAME);
+        assert_eq!(
+            chrome["allowed_origins"][0],
+            format!("chrome-extension://{CHROMIUM_EXTENSION_ID}/")
+        );
+        let wrapper = std::fs::read_to_string(codex_home.path().join("bin").join(WRAPPER_NAME))
+            .expect("wrapper");
+        assert!(wrapper.ends_with("exec /opt/computex browser-bridge \"$@\"\n"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn relays_requests_to_the_extension_and_back() {
+        let (session, host) = std::os::unix::net::UnixStream::pair().expect("pair");
+        let (responses, received) = mpsc::channel();
+        responses
+            .send(json!({ "id": 7, "ok": true, "result": { "total": 0 } }))
+            .expect("queue response");
+        let mut writer = &session;
+        writeln!(writer, "{}", json!({ "action": "query", "selector": "a" })).expect("write");
+
+        let mut browser = Vec::new();
+        relay(&host, 7, &mut browser, &received).expect("relay");
+        assert_eq!(
+            read_message(&mut browser.as_slice()).expect("read"),
+            Some(json!({ "action": "query", "selector": "a", "id": 7 }))
+        );
+        let mut line = String::new();
+        BufReader::new(&session)
+            .read_line(&mut line)
+            .expect("answer");
+        assert_eq!(
+            serde_json::from_str::<JsonValue>(&line).expect("json")["result"],
+            json!({ "total": 0 })
+        );
+
+        let page: PageResult = serde_json::from_value(json!({
+            "url": "https://example.com/login",
+            "title": "Sign in",
+            "total": 3,
+            "elements": [{
+                "index": 0,
+                "tag": "button",
+                "text": "Sign in",
+                "attributes": { "id": "go", "type": "submit" },
+                "visible": true,
+                "rect": { "x": 412, "y": 310, "width": 96, "height": 32 },
+            }],
+        }))
+        .expect("page");
+        assert_eq!(
+            format_query(&page, "button", None),
+            "found 3 element(s) matching `button` in \"Sign in\" (https://example.com/login), showing the first 1; positions are CSS pixels in the page viewport:\n[0] <button id=\"go\" type=\"submit\"> \"Sign in\" at 412,310 96x32"
+        );
+    }
+}
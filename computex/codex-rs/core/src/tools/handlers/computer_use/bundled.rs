@@ -0,0 +1,104 @@
+//! The `computex-inject` helper behind the `bundled` input provider.
+//!
+//! `computex setup` copies the helper into `$CODEX_HOME/bin`, so GUI input
+//! works on machines without xdotool or ydotool. It takes the xdotool command lines the handler builds and exits
+//! with [`UNSUPPORTED_EXIT_CODE`] before sending anything when a command
+//! line is beyond it, which moves on to the next provider. Where xdotool
+//! isn't installed, the helper also stands in for it for the focus queries
+//! around each action.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+pub use codex_computer_use_inject::HELPER_NAME;
+use codex_computer_use_inject::UNSUPPORTED_EXIT_CODE;
+use which::which;
+
+use super::gui_command;
+use crate::config::find_codex_home;
+use crate::config::types::ComputerUseConfig;
+use crate::function_tool::FunctionCallError;
+
+/// Where `computex setup` installs the helper.
+pub fn install_path(codex_home: &Path) -> PathBuf {
+    codex_home.join("bin").join(HELPER_NAME)
+}
+
+/// Copies the helper at `source` to [`install_path`], returning where it
+/// went.
+pub fn install(codex_home: &Path, source: &Path) -> std::io::Result<PathBuf> {
+    let target = install_path(codex_home);
+    if let Some(dir) = target.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    // Copy beside the target and rename, so a helper that is running keeps
+    // its old file.
+    let staging = target.with_extension("new");
+    std::fs::copy(source, &staging)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&staging, std::fs::Permissions::from_mode(0o755))?;
+    }
+    std::fs::rename(&staging, &target)?;
+    Ok(target)
+}
+
+/// The installed helper, or one on `PATH`.
+pub(super) fn helper() -> Option<PathBuf> {
+    find_codex_home()
+        .ok()
+        .map(|codex_home| install_path(&codex_home))
+        .filter(|path| path.is_file())
+        .or_else(|| which(HELPER_NAME).ok())
+}
+
+/// Sends xdotool `args` with the helper. `None` when the helper can't,
+/// in which case nothing was sent.
+pub(super) fn send(
+    helper: &Path,
+    args: &[String],
+    config: &ComputerUseConfig,
+) -> Option<Result<(), FunctionCallError>> {
+    let output = match gui_command(helper, config).args(args).output() {
+        Ok(output) => output,
+        Err(err) => {
+            return Some(Err(FunctionCallError::RespondToModel(format!(
+                "failed to run {HELPER_NAME}: {err}"
+            ))));
+        }
+    };
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if output.status.code() == Some(UNSUPPORTED_EXIT_CODE) {
+        tracing::debug!("{}", stderr.trim());
+        return None;
+    }
+    if output.status.success() {
+        Some(Ok(()))
+    } else {
+        Some(Err(FunctionCallError::RespondToModel(format!(
+            "{HELPER_NAME} failed: {}",
+            stderr.trim()
+        ))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn installs_under_codex_home() {
+        let codex_home = tempfile::tempdir().expect("tempdir");
+        let source = codex_home.path().join("built");
+        std::fs::write(&source, b"helper").expect("write helper");
+        let installed = install(codex_home.path(), &source).expect("install");
+        assert_eq!(installed, codex_home.path().join("bin").join(HELPER_NAME));
+        assert_eq!(std::fs::read(&installed).expect("read"), b"helper");
+        // Installing again replaces the helper in place.
+        std::fs::write(&source, b"newer").expect("write helper");
+        install(codex_home.path(), &source).expect("reinstall");
+        assert_eq!(std::fs::read(&installed).expect("read"), b"newer");
+    }
+}
@@ -0,0 +1,428 @@
+//! Coordinate calibration against a displayed test pattern, for
+//! `computex calibrate`.
+//!
+//! Model coordinates reach the screen through two separate paths: the
+//! screenshot the model looks at, and the input tool that clicks. HiDPI
+//! scaling, panning, and panels or monitors the capture doesn't account for
+//! make them disagree, so clicks land beside what the model aimed at. The
+//! calibration covers the screen with a pattern of colored markers at known
+//! pixels, finds them in a screenshot the way the model would, clicks where
+//! [`map_point`] puts them, and reads where the clicks actually arrived from
+//! the pattern window's own button events. A per-axis scale and offset
+//! fitted to the misses is stored as `computer_use.calibration`, which
+//! [`map_point`] applies from then on.
+
+use std::fs;
+use std::time::Duration;
+use std::time::Instant;
+
+use anyhow::Context;
+use codex_utils_image::screenshot::RawImage;
+use x11rb::connection::Connection;
+use x11rb::protocol::Event;
+use x11rb::protocol::xproto::ConnectionExt as _;
+use x11rb::protocol::xproto::CreateWindowAux;
+use x11rb::protocol::xproto::EventMask;
+use x11rb::protocol::xproto::WindowClass;
+use x11rb::rust_connection::RustConnection;
+
+use super::capture_screenshot;
+use super::coordinates::map_point;
+use super::coordinates::viewport_origin;
+use super::display_geometry;
+use super::require_command;
+use super::run_command;
+use crate::config::types::ComputerUseBackend;
+use crate::config::types::ComputerUseConfig;
+use crate::config::types::CoordinateCalibration;
+use crate::config::types::CoordinateSpace;
+use crate::config::types::Resolution;
+
+/// Marker colors and where they sit, as fractions of the screen. Three
+/// distinct positions per axis let the fit see both scale and offset.
+const MARKERS: &[(&str, [u8; 3], (f64, f64))] = &[
+    ("red", [0xff, 0x00, 0x00], (0.1, 0.1)),
+    ("green", [0x00, 0xff, 0x00], (0.9, 0.1)),
+    ("blue", [0x00, 0x00, 0xff], (0.5, 0.5)),
+    ("yellow", [0xff, 0xff, 0x00], (0.1, 0.9)),
+    ("magenta", [0xff, 0x00, 0xff], (0.9, 0.9)),
+];
+const BACKGROUND: u32 = 0x80_80_80;
+const MARKER_SIZE: u16 = 32;
+
+/// How far a channel may drift from the marker color, for captures that
+/// were scaled or passed through a color-managed compositor.
+const COLOR_TOLERANCE: u8 = 48;
+
+/// Time for the window manager and compositor to show the pattern.
+const SHOW_DELAY: Duration = Duration::from_millis(700);
+const CLICK_TIMEOUT: Duration = Duration::from_secs(2);
+const EVENT_POLL: Duration = Duration::from_millis(20);
+
+/// Corrections smaller than this are left out, since rounding alone causes
+/// them.
+const IDENTITY_SCALE_TOLERANCE: f64 = 0.002;
+const IDENTITY_OFFSET_TOLERANCE: f64 = 1.0;
+
+/// One marker: where it is, where the uncorrected mapping sent the click,
+/// and where the click arrived, all in screen pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample {
+    pub name: &'static str,
+    pub actual: (f64, f64),
+    pub predicted: (f64, f64),
+    pub observed: (f64, f64),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalibrationReport {
+    pub samples: Vec<Sample>,
+    /// The fitted correction, or `None` when clicks already land on target.
+    pub calibration: Option<CoordinateCalibration>,
+    /// Largest miss without the correction, and as predicted with it.
+    pub max_error_before: f64,
+    pub max_error_after: f64,
+}
+
+/// Shows the pattern, clicks each marker, and fits the correction.
+pub fn calibrate(config: &ComputerUseConfig) -> anyhow::Result<CalibrationReport> {
+    anyhow::ensure!(
+        config.backend == ComputerUseBackend::X11,
+        "calibration needs the x11 backend"
+    );
+    // Measure the mapping as it is without any earlier correction.
+    let config = &ComputerUseConfig {
+        calibration: None,
+        ..config.clone()
+    };
+    let xdotool = require_command("xdotool")?;
+    let (screen_width, screen_height) = display_geometry(&xdotool, config)?;
+    let (origin_x, origin_y) = viewport_origin(config);
+    let markers: Vec<_> = MARKERS
+        .iter()
+        .map(|&(name, color, (fx, fy))| {
+            let x = origin_x as f64 + (fx * screen_width).round();
+            let y = origin_y as f64 + (fy * screen_height).round();
+            (name, color, (x, y))
+        })
+        .collect();
+
+    let pattern = Pattern::show(config, &markers)?;
+    std::thread::sleep(SHOW_DELAY);
+    let path = capture_screenshot(config)?;
+    let screenshot = RawImage::open(&path);
+    let _ = fs::remove_file(&path);
+    let screenshot = screenshot.context("failed to read the calibration screenshot")?;
+
+    let mut samples = Vec::new();
+    for &(name, color, actual) in &markers {
+        let (image_x, image_y) = find_marker(&screenshot, color).with_context(|| {
+            format!("the {name} marker is not in the screenshot; is another window covering it?")
+        })?;
+        let (model_x, model_y) = image_to_model(config, image_x, image_y, &screenshot);
+        let point = map_point(config, model_x, model_y, screen_width, screen_height);
+        let predicted = (point.screen_x as f64, point.screen_y as f64);
+        run_command(
+            &xdotool,
+            &[
+                "mousemove".to_string(),
+                "--sync".to_string(),
+                point.screen_x.to_string(),
+                point.screen_y.to_string(),
+                "click".to_string(),
+                "1".to_string(),
+            ],
+            config,
+        )?;
+        let observed = pattern.next_click().with_context(|| {
+            format!(
+                "the click aimed at the {name} marker ({},{}) never reached the pattern",
+                point.screen_x, point.screen_y
+            )
+        })?;
+        samples.push(Sample {
+            name,
+            actual,
+            predicted,
+            observed,
+        });
+    }
+    drop(pattern);
+
+    let screen = Resolution {
+        width: screen_width.round() as u32,
+        height: screen_height.round() as u32,
+    };
+    let Fit {
+        calibration,
+        landed,
+    } = fit(&samples, screen)?;
+    let max_error_before = max_error(&samples, samples.iter().map(|sample| sample.observed));
+    let max_error_after = max_error(&samples, landed);
+    let identity = (calibration.scale_x - 1.0).abs() < IDENTITY_SCALE_TOLERANCE
+        && (calibration.scale_y - 1.0).abs() < IDENTITY_SCALE_TOLERANCE
+        && calibration.offset_x.abs() < IDENTITY_OFFSET_TOLERANCE
+        && calibration.offset_y.abs() < IDENTITY_OFFSET_TOLERANCE;
+    Ok(CalibrationReport {
+        samples,
+        calibration: (!identity).then_some(calibration),
+        max_error_before,
+        max_error_after: if identity {
+            max_error_before
+        } else {
+            max_error_after
+        },
+    })
+}
+
+/// A fitted correction and where the corrected clicks are expected to land.
+#[derive(Debug)]
+struct Fit {
+    calibration: CoordinateCalibration,
+    landed: Vec<(f64, f64)>,
+}
+
+/// Fits the correction that sends each predicted point to its marker.
+///
+/// Per axis, clicks arrive at `observed = a * sent + b` and the markers sit
+/// at `actual = c * predicted + d`, so sending `(c * p + d - b) / a` instead
+/// of `p` lands on the marker, at `c * p + d` up to the fit's residual.
+fn fit(samples: &[Sample], screen: Resolution) -> anyhow::Result<Fit> {
+    let axis = |get: fn((f64, f64)) -> f64| -> anyhow::Result<(f64, f64, Vec<f64>)> {
+        let predicted: Vec<f64> = samples.iter().map(|s| get(s.predicted)).collect();
+        let observed: Vec<f64> = samples.iter().map(|s| get(s.observed)).collect();
+        let actual: Vec<f64> = samples.iter().map(|s| get(s.actual)).collect();
+        let (a, b) = linear_fit(&predicted, &observed)
+            .context("the markers were clicked at too few distinct positions")?;
+        let (c, d) = linear_fit(&predicted, &actual)
+            .context("the markers were found at too few distinct positions")?;
+        anyhow::ensure!(a.abs() > f64::EPSILON, "clicks did not follow the pointer");
+        let landed = predicted.iter().map(|p| c * p + d).collect();
+        Ok((c / a, (d - b) / a, landed))
+    };
+    let (scale_x, offset_x, landed_x) = axis(|(x, _)| x)?;
+    let (scale_y, offset_y, landed_y) = axis(|(_, y)| y)?;
+    Ok(Fit {
+        calibration: CoordinateCalibration {
+            screen,
+            scale_x,
+            offset_x,
+            scale_y,
+            offset_y,
+        },
+        landed: landed_x.into_iter().zip(landed_y).collect(),
+    })
+}
+
+/// Least-squares slope and intercept of `ys` over `xs`.
+fn linear_fit(xs: &[f64], ys: &[f64]) -> Option<(f64, f64)> {
+    let n = xs.len() as f64;
+    let mean_x = xs.iter().sum::<f64>() / n;
+    let mean_y = ys.iter().sum::<f64>() / n;
+    let spread: f64 = xs.iter().map(|x| (x - mean_x).powi(2)).sum();
+    if spread < 1.0 {
+        return None;
+    }
+    let covariance: f64 = xs
+        .iter()
+        .zip(ys)
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+    let slope = covariance / spread;
+    Some((slope, mean_y - slope * mean_x))
+}
+
+/// Largest distance between a marker and where its click landed.
+fn max_error(samples: &[Sample], landed: impl IntoIterator<Item = (f64, f64)>) -> f64 {
+    samples
+        .iter()
+        .zip(landed)
+        .map(|(sample, (x, y))| (x - sample.actual.0).hypot(y - sample.actual.1))
+        .fold(0.0, f64::max)
+}
+
+/// Center of the pixels within [`COLOR_TOLERANCE`] of `color`.
+fn find_marker(image: &RawImage, color: [u8; 3]) -> Option<(f64, f64)> {
+    let channels = usize::from(image.channels);
+    let (mut sum_x, mut sum_y, mut count) = (0.0, 0.0, 0u64);
+    for (index, pixel) in image.pixels.chunks_exact(channels).enumerate() {
+        if pixel[..3]
+            .iter()
+            .zip(color)
+            .all(|(&value, wanted)| value.abs_diff(wanted) <= COLOR_TOLERANCE)
+        {
+            sum_x += (index % image.width as usize) as f64;
+            sum_y += (index / image.width as usize) as f64;
+            count += 1;
+        }
+    }
+    (count > 0).then(|| (sum_x / count as f64, sum_y / count as f64))
+}
+
+/// Model coordinates of a screenshot pixel, as the model would read them.
+fn image_to_model(config: &ComputerUseConfig, x: f64, y: f64, image: &RawImage) -> (f64, f64) {
+    let (width, height) = (f64::from(image.width), f64::from(image.height));
+    match config.coordinate_space {
+        CoordinateSpace::Virtual => (
+            x / width * f64::from(config.resolution.width),
+            y / height * f64::from(config.resolution.height),
+        ),
+        CoordinateSpace::Normalized => (x / (width - 1.0).max(1.0), y / (height - 1.0).max(1.0)),
+        CoordinateSpace::Native => (x, y),
+    }
+}
+
+/// The full-screen pattern window, destroyed on drop.
+struct Pattern {
+    conn: RustConnection,
+    window: u32,
+}
+
+impl Pattern {
+    fn show(
+        config: &ComputerUseConfig,
+        markers: &[(&'static str, [u8; 3], (f64, f64))],
+    ) -> anyhow::Result<Self> {
+        let (conn, screen) =
+            x11rb::connect(config.display.as_deref()).context("cannot open the X display")?;
+        let root = conn
+            .setup()
+            .roots
+            .get(screen)
+            .with_context(|| format!("X display has no screen {screen}"))?;
+        let (root_window, width, height, depth) = (
+            root.root,
+            root.width_in_pixels,
+            root.height_in_pixels,
+            root.root_depth,
+        );
+        anyhow::ensure!(
+            depth >= 24,
+            "calibration needs a 24-bit display, this one is {depth}-bit"
+        );
+        let window = conn.generate_id()?;
+        // Override-redirect keeps the window manager from moving the pattern
+        // or giving it a frame, so marker pixels are known exactly.
+        conn.create_window(
+            x11rb::COPY_DEPTH_FROM_PARENT,
+            window,
+            root_window,
+            0,
+            0,
+            width,
+            height,
+            0,
+            WindowClass::INPUT_OUTPUT,
+            x11rb::COPY_FROM_PARENT,
+            &CreateWindowAux::new()
+                .background_pixel(BACKGROUND)
+                .override_redirect(1)
+                .event_mask(EventMask::BUTTON_PRESS),
+        )?;
+        // Markers are child windows painted by the server in their
+        // background color; their clicks propagate to the pattern.
+        let half = i32::from(MARKER_SIZE / 2);
+        for &(_, [red, green, blue], (x, y)) in markers {
+            let marker = conn.generate_id()?;
+            conn.create_window(
+                x11rb::COPY_DEPTH_FROM_PARENT,
+                marker,
+                window,
+                (x as i32 - half) as i16,
+                (y as i32 - half) as i16,
+                MARKER_SIZE,
+                MARKER_SIZE,
+                0,
+                WindowClass::INPUT_OUTPUT,
+                x11rb::COPY_FROM_PARENT,
+                &CreateWindowAux::new().background_pixel(
+                    u32::from(red) << 16 | u32::from(green) << 8 | u32::from(blue),
+                ),
+            )?;
+        }
+        conn.map_subwindows(window)?;
+        conn.map_window(window)?;
+        conn.flush()?;
+        Ok(Self { conn, window })
+    }
+
+    /// Root coordinates of the next click on the pattern.
+    fn next_click(&self) -> anyhow::Result<(f64, f64)> {
+        let deadline = Instant::now() + CLICK_TIMEOUT;
+        loop {
+            while let Some(event) = self.conn.poll_for_event()? {
+                if let Event::ButtonPress(press) = event {
+                    return Ok((f64::from(press.root_x), f64::from(press.root_y)));
+                }
+            }
+            anyhow::ensure!(
+                Instant::now() < deadline,
+                "no click within {CLICK_TIMEOUT:?}"
+            );
+            std::thread::sleep(EVENT_POLL);
+        }
+    }
+}
+
+impl Drop for Pattern {
+    fn drop(&mut self) {
+        let _ = self.conn.destroy_window(self.window);
+        let _ = self.conn.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn finds_markers_and_fits_corrections() {
+        let mut pixels = vec![0x80; 40 * 20 * 3];
+        for y in 4..8 {
+            for x in 10..14 {
+                pixels[(y * 40 + x) * 3..][..3].copy_from_slice(&[0xf0, 0x10, 0x08]);
+            }
+        }
+        let image = RawImage::new(40, 20, 3, pixels).expect("image");
+        assert_eq!(find_marker(&image, [0xff, 0, 0]), Some((11.5, 5.5)));
+        assert_eq!(find_marker(&image, [0, 0, 0xff]), None);
+
+        // The screenshot is true, but clicks land 2x scaled on x and 30 px
+        // low on y, as with an unscaled input device and a top panel.
+        let samples: Vec<Sample> = [(100.0, 100.0), (900.0, 100.0), (500.0, 500.0)]
+            .into_iter()
+            .zip(["a", "b", "c"])
+            .map(|((x, y), name)| Sample {
+                name,
+                actual: (x, y),
+                predicted: (x, y),
+                observed: (2.0 * x, y + 30.0),
+            })
+            .collect();
+        let screen = Resolution {
+            width: 1000,
+            height: 600,
+        };
+        let Fit {
+            calibration,
+            landed,
+        } = fit(&samples, screen).expect("fit");
+        let rounded = |value: f64| (value * 1000.0).round() / 1000.0;
+        assert_eq!(calibration.screen, screen);
+        assert_eq!(
+            [
+                calibration.scale_x,
+                calibration.offset_x,
+                calibration.scale_y,
+                calibration.offset_y,
+            ]
+            .map(rounded),
+            [0.5, 0.0, 1.0, -30.0]
+        );
+        let (x, y) = calibration.apply(900.0, 100.0);
+        assert_eq!((rounded(x), rounded(y)), (450.0, 70.0));
+        assert!(max_error(&samples, landed) < 0.001);
+        assert!(fit(&samples[..1], screen).is_err());
+    }
+}
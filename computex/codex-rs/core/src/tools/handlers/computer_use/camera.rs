@@ -0,0 +1,113 @@
+//! Webcam frames for `computer_camera_snapshot`.
+//!
+//! Agents driving video-conferencing or kiosk apps need to know what the
+//! camera actually sees, independently of the preview the app draws. A
+//! single frame is grabbed from a V4L2 device with `ffmpeg`, or with
+//! `fswebcam` where ffmpeg isn't installed, and attached like a screenshot.
+//! The device must be free: an app holding it exclusively makes the grab
+//! fail with "device busy".
+
+use std::env;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+
+use uuid::Uuid;
+use which::which;
+
+use crate::function_tool::FunctionCallError;
+
+const DEFAULT_DEVICE: &str = "/dev/video0";
+
+/// Grabs one frame from `device` (default `/dev/video0`) into a temporary
+/// PNG and returns its path and the resolved device.
+pub(super) fn snapshot(device: Option<&str>) -> Result<(PathBuf, String), FunctionCallError> {
+    let device =
+        device_path(device.unwrap_or(DEFAULT_DEVICE)).map_err(FunctionCallError::RespondToModel)?;
+    if !Path::new(&device).exists() {
+        let available = video_devices();
+        let available = if available.is_empty() {
+            "no video devices are present".to_string()
+        } else {
+            format!("available: {}", available.join(", "))
+        };
+        return Err(FunctionCallError::RespondToModel(format!(
+            "no camera at {device}; {available}"
+        )));
+    }
+    let path = env::temp_dir().join(format!("codex-camera-{}.png", Uuid::new_v4()));
+    let output = if let Ok(ffmpeg) = which("ffmpeg") {
+        Command::new(ffmpeg)
+            .args(["-hide_banner", "-loglevel", "error", "-f", "v4l2", "-i"])
+            .arg(&device)
+            .args(["-frames:v", "1", "-y"])
+            .arg(&path)
+            .output()
+    } else if let Ok(fswebcam) = which("fswebcam") {
+        Command::new(fswebcam)
+            .args(["--no-banner", "--png", "0", "-d"])
+            .arg(&device)
+            .arg(&path)
+            .output()
+    } else {
+        return Err(FunctionCallError::RespondToModel(
+            "computer_camera_snapshot needs ffmpeg or fswebcam".to_string(),
+        ));
+    }
+    .map_err(|err| FunctionCallError::RespondToModel(format!("failed to grab a frame: {err}")))?;
+    if !output.status.success() || !path.exists() {
+        let _ = std::fs::remove_file(&path);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(FunctionCallError::RespondToModel(format!(
+            "failed to grab a frame from {device}: {}",
+            stderr.trim()
+        )));
+    }
+    Ok((path, device))
+}
+
+/// Accepts `/dev/video2`, `video2`, or `2`. Anything else is rejected so the
+/// tool can't be pointed at other files.
+fn device_path(device: &str) -> Result<String, String> {
+    let device = device.trim();
+    let index = device
+        .strip_prefix("/dev/video")
+        .or_else(|| device.strip_prefix("video"))
+        .unwrap_or(device);
+    if index.is_empty() || !index.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!(
+            "unknown camera `{device}`; use a V4L2 device such as /dev/video0"
+        ));
+    }
+    Ok(format!("/dev/video{index}"))
+}
+
+/// `/dev/video*` nodes, sorted.
+fn video_devices() -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir("/dev") else {
+        return Vec::new();
+    };
+    let mut devices: Vec<String> = entries
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter(|name| name.starts_with("video") && device_path(name).is_ok())
+        .map(|name| format!("/dev/{name}"))
+        .collect();
+    devices.sort();
+    devices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn accepts_only_v4l2_device_names() {
+        assert_eq!(device_path("/dev/video2"), Ok("/dev/video2".to_string()));
+        assert_eq!(device_path("video1"), Ok("/dev/video1".to_string()));
+        assert_eq!(device_path("0"), Ok("/dev/video0".to_string()));
+        assert!(device_path("/etc/passwd").is_err());
+        assert!(device_path("/dev/video0/../sda").is_err());
+        assert!(device_path("").is_err());
+    }
+}
@@ -0,0 +1,301 @@
+//! Screen capture through the providers listed in
+//! `computer_use.screenshot_providers`.
+//!
+//! No one capture tool ships everywhere: ImageMagick is missing from minimal
+//! images, `scrot` is the usual choice on lightweight desktops, and `grim`
+//! is the only one that works in wlroots Wayland sessions. The `native`
+//! provider needs none of them and reads the root window over computex's own
+//! X connection. The `portal` provider asks the xdg screenshot portal, the
+//! way out of a Flatpak or Snap sandbox, and the `qmp` provider dumps the
+//! console of the `qemu` backend's VM. Each provider either writes the
+//! PNG itself or hands back raw pixels for the caller to scale and encode.
+
+use std::path::Path;
+use std::process::Command;
+
+use codex_utils_image::screenshot::RawImage;
+use which::which;
+use x11rb::connection::Connection;
+use x11rb::protocol::xproto::ConnectionExt as _;
+use x11rb::protocol::xproto::ImageFormat;
+use x11rb::protocol::xproto::ImageOrder;
+
+use super::gui_command;
+use super::portal;
+use super::qmp;
+use super::scaling;
+use crate::config::types::ComputerUseConfig;
+use crate::config::types::ScreenshotProvider;
+use crate::config::types::Viewport;
+
+/// What a provider produced.
+pub(super) enum Captured {
+    /// The PNG was written to the requested path.
+    File,
+    Raw(RawImage),
+}
+
+pub(super) fn provider_name(provider: ScreenshotProvider) -> &'static str {
+    match provider {
+        ScreenshotProvider::Native => "native",
+        ScreenshotProvider::Grim => "grim",
+        ScreenshotProvider::Import => "import",
+        ScreenshotProvider::Scrot => "scrot",
+        ScreenshotProvider::Portal => "portal",
+        ScreenshotProvider::Qmp => "qmp",
+    }
+}
+
+/// Captures `crop` of the screen, or all of it, with `provider`. `raw`
+/// asks for pixels instead of a file where the provider can give them.
+pub(super) fn capture(
+    provider: ScreenshotProvider,
+    config: &ComputerUseConfig,
+    crop: Option<Viewport>,
+    path: &Path,
+    raw: bool,
+) -> Result<Captured, String> {
+    match provider {
+        ScreenshotProvider::Native => native(config, crop).map(Captured::Raw),
+        ScreenshotProvider::Import => import(config, crop, path, raw),
+        ScreenshotProvider::Grim => {
+            if std::env::var_os("WAYLAND_DISPLAY").is_none() {
+                return Err("not in a Wayland session".to_string());
+            }
+            let mut command = tool("grim", config)?;
+            if let Some(crop) = crop {
+                command.args(["-g", &grim_geometry(crop)]);
+            }
+            command.arg(path);
+            run(command, "grim").map(|()| Captured::File)
+        }
+        ScreenshotProvider::Scrot => {
+            let mut command = tool("scrot", config)?;
+            // scrot refuses to replace an existing file unless asked to.
+            command.arg("--overwrite");
+            if let Some(crop) = crop {
+                command.args(["-a", &scrot_geometry(crop)]);
+            }
+            command.arg(path);
+            run(command, "scrot").map(|()| Captured::File)
+        }
+        ScreenshotProvider::Portal => {
+            portal::screenshot(path)?;
+            // The portal always captures the whole screen, in device pixels
+            // that are scaled to the X screen's before cropping to its
+            // coordinates.
+            match crop {
+                Some(area) => RawImage::open(path)
+                    .map_err(|err| format!("the portal wrote an unreadable image: {err}"))
+                    .and_then(|image| {
+                        match scaling::input_grid(config, None, image.width, image.height) {
+                            Some(size) => image
+                                .resize(size.width, size.height)
+                                .map_err(|err| format!("failed to scale the capture: {err}")),
+                            None => Ok(image),
+                        }
+                    })
+                    .and_then(|image| crop_image(&image, area))
+                    .map(Captured::Raw),
+                None => Ok(Captured::File),
+            }
+        }
+        ScreenshotProvider::Qmp => {
+            let image = qmp::screendump(config)?;
+            match crop {
+                Some(area) => crop_image(&image, area),
+                None => Ok(image),
+            }
+            .map(Captured::Raw)
+        }
+    }
+}
+
+/// Whether `provider` could run here, without capturing anything.
+pub(super) fn available(provider: ScreenshotProvider, config: &ComputerUseConfig) -> bool {
+    match provider {
+        ScreenshotProvider::Native => x11rb::connect(config.display.as_deref()).is_ok(),
+        ScreenshotProvider::Grim => {
+            std::env::var_os("WAYLAND_DISPLAY").is_some() && which("grim").is_ok()
+        }
+        ScreenshotProvider::Import => which("import").is_ok(),
+        ScreenshotProvider::Scrot => which("scrot").is_ok(),
+        ScreenshotProvider::Portal => portal::version(portal::SCREENSHOT).is_some(),
+        ScreenshotProvider::Qmp => qmp::version(config).is_ok(),
+    }
+}
+
+fn tool(name: &str, config: &ComputerUseConfig) -> Result<Command, String> {
+    let program = which(name).map_err(|_| "not installed".to_string())?;
+    Ok(gui_command(&program, config))
+}
+
+fn run(mut command: Command, name: &str) -> Result<(), String> {
+    let output = command
+        .output()
+        .map_err(|err| format!("failed to run {name}: {err}"))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{name} failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+fn import(
+    config: &ComputerUseConfig,
+    crop: Option<Viewport>,
+    path: &Path,
+    raw: bool,
+) -> Result<Captured, String> {
+    let mut command = tool("import", config)?;
+    command.args(["-window", "root"]);
+    if let Some(crop) = crop {
+        command.args(["-crop", &crop.to_string(), "+repage"]);
+    }
+    if raw {
+        command.args(["-depth", "8", "pam:-"]);
+    } else {
+        command.arg(path);
+    }
+    let output = command
+        .output()
+        .map_err(|err| format!("failed to run import: {err}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("import failed: {}", stderr.trim()));
+    }
+    if raw {
+        RawImage::from_pam(output.stdout)
+            .map(Captured::Raw)
+            .map_err(|err| format!("import wrote an unreadable image: {err}"))
+    } else {
+        Ok(Captured::File)
+    }
+}
+
+/// Reads the root window with `GetImage`. Only the common 24-bit true-color
+/// layout with 32-bit little-endian pixels is handled; other servers fall
+/// through to the next provider.
+fn native(config: &ComputerUseConfig, crop: Option<Viewport>) -> Result<RawImage, String> {
+    let (conn, screen) = x11rb::connect(config.display.as_deref())
+        .map_err(|err| format!("cannot open X display: {err}"))?;
+    let setup = conn.setup();
+    let screen = setup
+        .roots
+        .get(screen)
+        .ok_or_else(|| format!("X display has no screen {screen}"))?;
+    let depth = screen.root_depth;
+    let packed = setup
+        .pixmap_formats
+        .iter()
+        .any(|format| format.depth == depth && format.bits_per_pixel == 32);
+    let red_mask = screen
+        .allowed_depths
+        .iter()
+        .flat_map(|allowed| &allowed.visuals)
+        .find(|visual| visual.visual_id == screen.root_visual)
+        .map(|visual| visual.red_mask);
+    if !matches!(depth, 24 | 32)
+        || !packed
+        || setup.image_byte_order != ImageOrder::LSB_FIRST
+        || red_mask != Some(0xff_0000)
+    {
+        return Err(format!("unsupported {depth}-bit pixel layout"));
+    }
+    let area = crop.unwrap_or(Viewport {
+        width: u32::from(screen.width_in_pixels),
+        height: u32::from(screen.height_in_pixels),
+        x: 0,
+        y: 0,
+    });
+    let narrow = |value: u32| i16::try_from(value).map_err(|_| format!("{area} is off screen"));
+    let size = |value: u32| u16::try_from(value).map_err(|_| format!("{area} is too large"));
+    let reply = conn
+        .get_image(
+            ImageFormat::Z_PIXMAP,
+            screen.root,
+            narrow(area.x)?,
+            narrow(area.y)?,
+            size(area.width)?,
+            size(area.height)?,
+            u32::MAX,
+        )
+        .map_err(|err| format!("GetImage failed: {err}"))?
+        .reply()
+        .map_err(|err| format!("GetImage failed: {err}"))?;
+    RawImage::new(area.width, area.height, 3, bgrx_to_rgb(&reply.data))
+        .map_err(|err| format!("GetImage returned an unexpected image: {err}"))
+}
+
+/// The `area` of `image`.
+fn crop_image(image: &RawImage, area: Viewport) -> Result<RawImage, String> {
+    if area.x + area.width > image.width || area.y + area.height > image.height {
+        return Err(format!("{area} is off screen"));
+    }
+    let channels = usize::from(image.channels);
+    let stride = image.width as usize * channels;
+    let row = area.width as usize * channels;
+    let pixels = (area.y..area.y + area.height)
+        .flat_map(|y| {
+            let start = y as usize * stride + area.x as usize * channels;
+            &image.pixels[start..start + row]
+        })
+        .copied()
+        .collect();
+    RawImage::new(area.width, area.height, image.channels, pixels).map_err(|err| err.to_string())
+}
+
+/// Drops the padding byte of each little-endian `0x00RRGGBB` pixel.
+fn bgrx_to_rgb(data: &[u8]) -> Vec<u8> {
+    data.chunks_exact(4)
+        .flat_map(|pixel| [pixel[2], pixel[1], pixel[0]])
+        .collect()
+}
+
+/// `X,Y WxH`, as `grim -g` takes it.
+fn grim_geometry(area: Viewport) -> String {
+    format!("{},{} {}x{}", area.x, area.y, area.width, area.height)
+}
+
+/// `X,Y,W,H`, as `scrot -a` takes it.
+fn scrot_geometry(area: Viewport) -> String {
+    format!("{},{},{},{}", area.x, area.y, area.width, area.height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn converts_pixels_and_geometry() {
+        assert_eq!(
+            bgrx_to_rgb(&[0x10, 0x20, 0x30, 0x00, 0xff, 0x00, 0x00, 0x00]),
+            vec![0x30, 0x20, 0x10, 0x00, 0x00, 0xff]
+        );
+        let area = Viewport {
+            width: 1280,
+            height: 720,
+            x: 1920,
+            y: 0,
+        };
+        assert_eq!(grim_geometry(area), "1920,0 1280x720");
+        assert_eq!(scrot_geometry(area), "1920,0,1280,720");
+
+        let image = RawImage::new(3, 2, 3, (0..18).collect()).expect("image");
+        let corner = Viewport {
+            width: 2,
+            height: 1,
+            x: 1,
+            y: 1,
+        };
+        assert_eq!(
+            crop_image(&image, corner).map(|cropped| cropped.pixels),
+            Ok(vec![12, 13, 14, 15, 16, 17])
+        );
+        assert!(crop_image(&image, area).is_err());
+    }
+}
@@ -0,0 +1,261 @@
+//! The action classifier hook, `computer_use.action_classifier`.
+//!
+//! Organizations often have rules about what an agent may do on a desktop
+//! that no built-in policy can know: never press Send in the payroll app,
+//! ask before touching anything in the admin console. The classifier is a
+//! command they supply. Before each GUI action that changes something, it
+//! gets the action on stdin as JSON, along with the focused window and a
+//! capture of the area the action aims at:
+//!
+//! ```json
+//! {"tool": "computer_click", "arguments": {"x": 640, "y": 400}, "window": {"class": "firefox", "title": "Payroll"}, "screenshot": "/tmp/codex-screenshot-….png"}
+//! ```
+//!
+//! It answers on stdout with `{"decision": "approve" | "deny" | "ask",
+//! "reason": "…"}`. A denied action fails with the reason, and `ask` puts the
+//! action in front of the user. A classifier that can't be run, times out,
+//! or answers something else counts as `ask`, so a broken guardrail never
+//! lets actions through silently. Computex links no WebAssembly runtime; a
+//! classifier compiled to WASI runs through one on the command line, such
+//! as `["wasmtime", "run", "guard.wasm"]`.
+
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::Value;
+use serde_json::json;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use super::capture_root_window;
+use super::detail::LOW_DETAIL;
+use super::evidence::target_box;
+use super::recovery::xdotool_output;
+use super::require_command;
+use crate::codex::Session;
+use crate::codex::TurnContext;
+use crate::config::types::ComputerUseBackend;
+use crate::config::types::ComputerUseConfig;
+use crate::function_tool::FunctionCallError;
+use crate::protocol::AskForApproval;
+use crate::protocol::ReviewDecision;
+
+/// How long the classifier gets per action.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What the classifier decided about an action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Decision {
+    Approve,
+    Deny,
+    Ask,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+struct Verdict {
+    decision: Decision,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+/// Runs the configured classifier on a pending `tool_name` call, and asks
+/// the user when it says to. Returns an error when the action must not run.
+pub(super) async fn check(
+    session: &Session,
+    turn: &TurnContext,
+    call_id: &str,
+    tool_name: &str,
+    arguments: &str,
+) -> Result<(), FunctionCallError> {
+    let config = &turn.tools_config.computer_use;
+    let Some(command) = &config.action_classifier else {
+        return Ok(());
+    };
+    let screenshot = crop(config, arguments);
+    let input = request(
+        tool_name,
+        arguments,
+        focused_window(config),
+        screenshot.as_ref(),
+    );
+    let verdict = run(command, &input).await;
+    if let Some(path) = &screenshot {
+        let _ = std::fs::remove_file(path);
+    }
+    let verdict = verdict.unwrap_or_else(|err| {
+        tracing::warn!("action classifier failed: {err}");
+        Verdict {
+            decision: Decision::Ask,
+            reason: Some(format!("the action classifier failed ({err})")),
+        }
+    });
+    let reason = verdict
+        .reason
+        .as_deref()
+        .map(str::trim)
+        .filter(|reason| !reason.is_empty())
+        .unwrap_or("no reason given");
+    match verdict.decision {
+        Decision::Approve => Ok(()),
+        Decision::Deny => Err(FunctionCallError::RespondToModel(format!(
+            "{tool_name} was blocked by the action classifier: {reason}. Do not retry it; tell the user what you need done instead"
+        ))),
+        Decision::Ask if turn.approval_policy == AskForApproval::Never => {
+            Err(FunctionCallError::RespondToModel(format!(
+                "{tool_name} needs the user's confirmation ({reason}), and this session can't ask for it. Do not retry it; tell the user what you need done instead"
+            )))
+        }
+        Decision::Ask => {
+            let decision = session
+                .request_command_approval(
+                    turn,
+                    call_id.to_string(),
+                    vec![tool_name.to_string(), arguments.to_string()],
+                    turn.cwd.clone(),
+                    Some(format!(
+                        "the action classifier asks for confirmation: {reason}"
+                    )),
+                    None,
+                )
+                .await;
+            match decision {
+                ReviewDecision::Approved
+                | ReviewDecision::ApprovedExecpolicyAmendment { .. }
+                | ReviewDecision::ApprovedForSession => Ok(()),
+                ReviewDecision::Denied | ReviewDecision::Abort => {
+                    Err(FunctionCallError::RespondToModel(format!(
+                        "the user declined {tool_name} after the action classifier asked: {reason}"
+                    )))
+                }
+            }
+        }
+    }
+}
+
+/// A capture of the area the action aims at, or of the whole screen at low
+/// detail for actions without a point. `None` when there's nothing to
+/// capture.
+fn crop(config: &ComputerUseConfig, arguments: &str) -> Option<PathBuf> {
+    // A text console has no pixels to capture.
+    if config.backend == ComputerUseBackend::Console {
+        return None;
+    }
+    let captured = match target_box(config, arguments) {
+        Some(area) => capture_root_window(config, Some(area.to_string()), None),
+        None => capture_root_window(config, None, Some(LOW_DETAIL)),
+    };
+    captured
+        .inspect_err(|err| tracing::debug!("no capture for the action classifier: {err:?}"))
+        .ok()
+}
+
+fn focused_window(config: &ComputerUseConfig) -> Option<(String, String)> {
+    let xdotool = require_command("xdotool").ok()?;
+    let id = xdotool_output(&xdotool, config, &["getactivewindow"]).ok()?;
+    let class = xdotool_output(&xdotool, config, &["getwindowclassname", &id]).unwrap_or_default();
+    let title = xdotool_output(&xdotool, config, &["getwindowname", &id]).unwrap_or_default();
+    Some((class, title))
+}
+
+/// The JSON the classifier reads on stdin.
+fn request(
+    tool_name: &str,
+    arguments: &str,
+    window: Option<(String, String)>,
+    screenshot: Option<&PathBuf>,
+) -> Value {
+    let arguments = serde_json::from_str::<Value>(arguments)
+        .unwrap_or_else(|_| Value::String(arguments.to_string()));
+    json!({
+        "tool": tool_name,
+        "arguments": arguments,
+        "window": window.map(|(class, title)| json!({"class": class, "title": title})),
+        "screenshot": screenshot,
+    })
+}
+
+async fn run(command: &[String], input: &Value) -> Result<Verdict, String> {
+    let (program, args) = command
+        .split_first()
+        .ok_or_else(|| "no command is configured".to_string())?;
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|err| format!("failed to run `{program}`: {err}"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(input.to_string().as_bytes())
+            .await
+            .map_err(|err| format!("failed to write to `{program}`: {err}"))?;
+    }
+    let output = tokio::time::timeout(TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| format!("`{program}` didn't answer within {} s", TIMEOUT.as_secs()))?
+        .map_err(|err| format!("failed to run `{program}`: {err}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("`{program}` failed: {}", stderr.trim()));
+    }
+    parse(&String::from_utf8_lossy(&output.stdout))
+}
+
+fn parse(stdout: &str) -> Result<Verdict, String> {
+    serde_json::from_str(stdout.trim()).map_err(|err| format!("unreadable answer: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn sends_the_action_and_reads_the_decision() {
+        let input = request(
+            "computer_click",
+            r#"{"x": 640, "y": 400}"#,
+            Some(("firefox".to_string(), "Payroll".to_string())),
+            Some(&PathBuf::from("/tmp/crop.png")),
+        );
+        assert_eq!(
+            input,
+            json!({
+                "tool": "computer_click",
+                "arguments": {"x": 640, "y": 400},
+                "window": {"class": "firefox", "title": "Payroll"},
+                "screenshot": "/tmp/crop.png",
+            })
+        );
+        assert_eq!(
+            request("computer_key", "not json", None, None),
+            json!({
+                "tool": "computer_key",
+                "arguments": "not json",
+                "window": null,
+                "screenshot": null,
+            })
+        );
+
+        assert_eq!(
+            parse("{\"decision\": \"deny\", \"reason\": \"payroll is off limits\"}\n"),
+            Ok(Verdict {
+                decision: Decision::Deny,
+                reason: Some("payroll is off limits".to_string()),
+            })
+        );
+        assert_eq!(
+            parse(r#"{"decision": "approve"}"#),
+            Ok(Verdict {
+                decision: Decision::Approve,
+                reason: None,
+            })
+        );
+        assert!(parse(r#"{"decision": "maybe"}"#).is_err());
+    }
+}
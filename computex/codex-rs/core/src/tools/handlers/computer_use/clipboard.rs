@@ -0,0 +1,136 @@
+//! Clipboard isolation for `computer_use.preserve_clipboard`.
+//!
+//! The agent copies and pastes through the same system clipboard as the
+//! user, so a copy shortcut in an agent action overwrites whatever the user
+//! had copied, and a paste drops the user's text into the app. With
+//! isolation on, each input action runs against the agent's own clipboard:
+//! its latest copy is put on the clipboard first, anything the action
+//! copies is kept on a private stack, and the user's contents are put back
+//! afterwards. Only text survives the round trip; a copied image or file
+//! list is replaced.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use super::browser::CLIPBOARD_SETTLE;
+use super::browser::read_clipboard;
+use super::browser::write_clipboard;
+use super::require_command;
+use crate::config::types::ComputerUseConfig;
+
+/// How many agent copies are kept.
+const AGENT_CLIPBOARD_DEPTH: usize = 10;
+
+/// Text the agent copied, newest last.
+#[derive(Debug, Default)]
+pub(super) struct AgentClipboard {
+    stack: Vec<String>,
+}
+
+impl AgentClipboard {
+    /// What an agent paste should insert.
+    pub(super) fn top(&self) -> Option<&str> {
+        self.stack.last().map(String::as_str)
+    }
+
+    pub(super) fn push(&mut self, copied: String) {
+        if self.top() == Some(copied.as_str()) {
+            return;
+        }
+        self.stack.push(copied);
+        if self.stack.len() > AGENT_CLIPBOARD_DEPTH {
+            self.stack.remove(0);
+        }
+    }
+}
+
+/// The user's clipboard, set aside while an agent action runs.
+pub(super) struct Swap {
+    xclip: PathBuf,
+    user: Option<String>,
+    /// What the action started with on the clipboard.
+    started: Option<String>,
+}
+
+/// Sets the user's clipboard aside and puts the agent's latest copy in its
+/// place. `None` when xclip is missing, so nothing is isolated.
+pub(super) fn swap_in(config: &ComputerUseConfig, agent: Option<String>) -> Option<Swap> {
+    let xclip = require_command("xclip")
+        .inspect_err(|err| tracing::debug!("clipboard isolation is off: {err}"))
+        .ok()?;
+    let user = read_clipboard(&xclip, config).ok();
+    let started = match agent {
+        Some(agent) if Some(&agent) != user.as_ref() => {
+            if let Err(err) = write_clipboard(&xclip, config, &agent) {
+                tracing::debug!("failed to load the agent clipboard: {err}");
+                user.clone()
+            } else {
+                Some(agent)
+            }
+        }
+        _ => user.clone(),
+    };
+    Some(Swap {
+        xclip,
+        user,
+        started,
+    })
+}
+
+impl Swap {
+    /// Puts the user's clipboard back once the action is done, returning
+    /// the text the action copied, if any.
+    pub(super) async fn restore(self, config: &ComputerUseConfig) -> Option<String> {
+        // Apps take the clipboard a moment after the copy shortcut.
+        tokio::time::sleep(CLIPBOARD_SETTLE).await;
+        let now = read_clipboard(&self.xclip, config).ok();
+        if now != self.user {
+            restore_user(&self.xclip, config, self.user.as_deref());
+        }
+        copied(self.started, now)
+    }
+}
+
+fn restore_user(xclip: &Path, config: &ComputerUseConfig, user: Option<&str>) {
+    // A clipboard that couldn't be read as text is cleared rather than left
+    // holding the agent's copy.
+    if let Err(err) = write_clipboard(xclip, config, user.unwrap_or_default()) {
+        tracing::warn!("failed to restore the user's clipboard: {err}");
+    }
+}
+
+/// The text an action copied: whatever the clipboard holds now that it
+/// didn't when the action started.
+fn copied(started: Option<String>, now: Option<String>) -> Option<String> {
+    now.filter(|now| !now.is_empty() && Some(now) != started.as_ref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn keeps_what_the_agent_copied() {
+        let mut agent = AgentClipboard::default();
+        assert_eq!(agent.top(), None);
+        agent.push("first".to_string());
+        agent.push("second".to_string());
+        agent.push("second".to_string());
+        assert_eq!(agent.top(), Some("second"));
+        assert_eq!(agent.stack.len(), 2);
+        for index in 0..AGENT_CLIPBOARD_DEPTH {
+            agent.push(index.to_string());
+        }
+        assert_eq!(agent.stack.len(), AGENT_CLIPBOARD_DEPTH);
+        assert_eq!(agent.stack.first().map(String::as_str), Some("0"));
+
+        let user = Some("user text".to_string());
+        assert_eq!(copied(user.clone(), user.clone()), None);
+        assert_eq!(
+            copied(user, Some("agent text".to_string())),
+            Some("agent text".to_string())
+        );
+        assert_eq!(copied(None, Some(String::new())), None);
+    }
+}
@@ -0,0 +1,139 @@
+//! Baseline comparison for `computer_compare`, the UI regression check.
+//!
+//! The first comparison against a missing baseline stores the capture as the
+//! baseline. Later comparisons report how much of the screen changed and
+//! write a diff image with the changed pixels painted red.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_utils_image::compare::ImageDiff;
+use codex_utils_image::screenshot::RawImage;
+use uuid::Uuid;
+
+use crate::function_tool::FunctionCallError;
+
+/// Per-channel difference ignored by default, enough to absorb font
+/// antialiasing and cursor blink halos.
+pub(super) const DEFAULT_TOLERANCE: u8 = 16;
+
+pub(super) enum Outcome {
+    /// No baseline existed, so the capture became the baseline.
+    BaselineSaved { width: u32, height: u32 },
+    Compared {
+        diff: ImageDiff,
+        /// Size of the baseline, which the capture was scaled to if needed.
+        size: (u32, u32),
+        /// The capture's own size when it differed from the baseline's.
+        rescaled_from: Option<(u32, u32)>,
+        /// Diff image, written only when something changed.
+        diff_path: Option<PathBuf>,
+    },
+}
+
+/// Compares the PNG at `capture` with `baseline`, or stores it as the
+/// baseline when there is none yet.
+pub(super) fn compare_to_baseline(
+    capture: &Path,
+    baseline: &Path,
+    tolerance: u8,
+) -> Result<Outcome, FunctionCallError> {
+    let current = RawImage::open(capture).map_err(image_error)?;
+    if !baseline.exists() {
+        if let Some(parent) = baseline.parent() {
+            std::fs::create_dir_all(parent).map_err(|err| write_error(baseline, err))?;
+        }
+        let png = current.encode_png().map_err(image_error)?;
+        std::fs::write(baseline, png).map_err(|err| write_error(baseline, err))?;
+        return Ok(Outcome::BaselineSaved {
+            width: current.width,
+            height: current.height,
+        });
+    }
+
+    let expected = RawImage::open(baseline).map_err(image_error)?;
+    let size = (expected.width, expected.height);
+    let (current, rescaled_from) = if (current.width, current.height) == size {
+        (current, None)
+    } else {
+        let from = (current.width, current.height);
+        let scaled = current.resize(size.0, size.1).map_err(image_error)?;
+        (scaled, Some(from))
+    };
+    let diff =
+        codex_utils_image::compare::compare(&expected, &current, tolerance).map_err(image_error)?;
+    let diff_path = if diff.changed_pixels > 0 {
+        let path = std::env::temp_dir().join(format!("codex-compare-diff-{}.png", Uuid::new_v4()));
+        let png = diff.diff.encode_png().map_err(image_error)?;
+        std::fs::write(&path, png).map_err(|err| write_error(&path, err))?;
+        Some(path)
+    } else {
+        None
+    };
+    Ok(Outcome::Compared {
+        diff,
+        size,
+        rescaled_from,
+        diff_path,
+    })
+}
+
+fn image_error(err: codex_utils_image::error::ImageProcessingError) -> FunctionCallError {
+    FunctionCallError::RespondToModel(format!("screen comparison failed: {err}"))
+}
+
+fn write_error(path: &Path, err: std::io::Error) -> FunctionCallError {
+    FunctionCallError::RespondToModel(format!("failed to write {}: {err}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn write_png(path: &Path, width: u32, height: u32, value: u8) {
+        let pixels = vec![value; width as usize * height as usize * 3];
+        let png = RawImage::new(width, height, 3, pixels)
+            .and_then(|image| image.encode_png())
+            .expect("encode png");
+        std::fs::write(path, png).expect("write png");
+    }
+
+    #[test]
+    fn first_run_saves_baseline_and_later_runs_compare() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let capture = dir.path().join("capture.png");
+        let baseline = dir.path().join("baselines/login.png");
+        write_png(&capture, 8, 4, 200);
+
+        let Outcome::BaselineSaved { width, height } =
+            compare_to_baseline(&capture, &baseline, DEFAULT_TOLERANCE).expect("save")
+        else {
+            panic!("expected the baseline to be saved");
+        };
+        assert_eq!((width, height), (8, 4));
+
+        let Outcome::Compared {
+            diff, diff_path, ..
+        } = compare_to_baseline(&capture, &baseline, DEFAULT_TOLERANCE).expect("compare")
+        else {
+            panic!("expected a comparison");
+        };
+        assert_eq!(diff.similarity, 1.0);
+        assert_eq!(diff_path, None);
+
+        write_png(&capture, 16, 8, 20);
+        let Outcome::Compared {
+            diff,
+            rescaled_from,
+            diff_path,
+            ..
+        } = compare_to_baseline(&capture, &baseline, DEFAULT_TOLERANCE).expect("compare")
+        else {
+            panic!("expected a comparison");
+        };
+        assert_eq!(diff.similarity, 0.0);
+        assert_eq!(rescaled_from, Some((16, 8)));
+        assert!(diff_path.is_some_and(|path| path.is_file()));
+    }
+}
@@ -0,0 +1,167 @@
+//! Detecting a Flatpak or Snap sandbox around computex.
+//!
+//! Inside a sandbox, host commands such as xdotool and scrot aren't on
+//! `PATH`, and the X socket and `/dev/uinput` are only there when the
+//! packager granted them. The xdg desktop portals are the sanctioned way out,
+//! so when computex runs confined the default provider chains try the
+//! screenshot and remote desktop portals right after the ways in that need no
+//! host packages, and the backend report says which of them the sandbox lets
+//! through.
+
+use std::fmt;
+use std::fs::OpenOptions;
+use std::path::Path;
+
+use which::which;
+
+use super::bundled;
+use super::portal;
+use crate::config::types::ComputerUseConfig;
+
+/// Written into every Flatpak sandbox, with the app id under
+/// `[Application]`.
+const FLATPAK_INFO: &str = "/.flatpak-info";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Sandbox {
+    Flatpak { app_id: String },
+    Snap { name: String },
+}
+
+impl fmt::Display for Sandbox {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Sandbox::Flatpak { app_id } => write!(f, "the Flatpak sandbox of {app_id}"),
+            Sandbox::Snap { name } => write!(f, "the {name} snap"),
+        }
+    }
+}
+
+/// The sandbox computex runs in, if any.
+pub fn detect() -> Option<Sandbox> {
+    detect_from(Path::new(FLATPAK_INFO), |name| std::env::var(name).ok())
+}
+
+fn detect_from(flatpak_info: &Path, var: impl Fn(&str) -> Option<String>) -> Option<Sandbox> {
+    if let Some(app_id) = var("FLATPAK_ID").or_else(|| {
+        std::fs::read_to_string(flatpak_info)
+            .ok()
+            .and_then(|info| flatpak_app_id(&info))
+    }) {
+        return Some(Sandbox::Flatpak { app_id });
+    }
+    let name = var("SNAP_NAME")?;
+    // Classic snaps run unconfined, and say so in their metadata.
+    let metadata = Path::new(&var("SNAP")?).join("meta").join("snap.yaml");
+    let classic = std::fs::read_to_string(metadata).is_ok_and(|yaml| {
+        yaml.lines()
+            .any(|line| line.trim() == "confinement: classic")
+    });
+    (!classic).then_some(Sandbox::Snap { name })
+}
+
+/// The `name` under `[Application]` in a `.flatpak-info` file.
+fn flatpak_app_id(info: &str) -> Option<String> {
+    let mut in_application = false;
+    for line in info.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_application = line == "[Application]";
+        } else if in_application && let Some(name) = line.strip_prefix("name=") {
+            return Some(name.trim().to_string());
+        }
+    }
+    None
+}
+
+/// What the sandbox lets computex reach, for the backend report: the X
+/// display, `/dev/uinput`, xdotool, the bundled helper, and the portals.
+pub(super) fn capabilities(sandbox: &Sandbox, config: &ComputerUseConfig) -> String {
+    let state = |reachable: bool| if reachable { "reachable" } else { "blocked" };
+    let found = |present: bool| if present { "found" } else { "missing" };
+    let portal = |interface| match portal::version(interface) {
+        Some(version) => format!("v{version}"),
+        None => "unavailable".to_string(),
+    };
+    format!(
+        "confined in {sandbox}: X display {}, /dev/uinput {}, xdotool {}, bundled helper {}, screenshot portal {}, remote desktop portal {}",
+        state(x11rb::connect(config.display.as_deref()).is_ok()),
+        state(OpenOptions::new().write(true).open("/dev/uinput").is_ok()),
+        found(which("xdotool").is_ok()),
+        found(bundled::helper().is_some()),
+        portal(portal::SCREENSHOT),
+        portal(portal::REMOTE_DESKTOP),
+    )
+}
+
+/// Adds what to do about the sandbox to `message`, when computex runs in
+/// one.
+pub(super) fn explain(message: String) -> String {
+    let Some(sandbox) = detect() else {
+        return message;
+    };
+    let grant = match &sandbox {
+        Sandbox::Flatpak { app_id } => {
+            format!("grant it the X socket with `flatpak override --user --socket=x11 {app_id}`")
+        }
+        Sandbox::Snap { name } => format!("connect its X plug with `sudo snap connect {name}:x11`"),
+    };
+    format!(
+        "{message}; computex runs inside {sandbox}, so {grant}, run `computex setup` to install the bundled input helper, or allow the desktop's screenshot and remote desktop portals"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::collections::HashMap;
+
+    #[test]
+    fn detects_flatpak_and_snap() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let info = dir.path().join(".flatpak-info");
+        let env = |vars: &[(&str, &str)]| {
+            let vars: HashMap<String, String> = vars
+                .iter()
+                .map(|(name, value)| ((*name).to_string(), (*value).to_string()))
+                .collect();
+            move |name: &str| vars.get(name).cloned()
+        };
+
+        assert_eq!(detect_from(&info, env(&[])), None);
+        std::fs::write(
+            &info,
+            "[Instance]\nname=ignored\n\n[Application]\nname=org.example.Computex\nruntime=runtime/org.freedesktop.Platform\n",
+        )
+        .expect("write info");
+        assert_eq!(
+            detect_from(&info, env(&[])),
+            Some(Sandbox::Flatpak {
+                app_id: "org.example.Computex".to_string()
+            })
+        );
+
+        let missing = dir.path().join("missing");
+        let snap_dir = dir.path().join("snap");
+        std::fs::create_dir_all(snap_dir.join("meta")).expect("create snap");
+        let snap_path = snap_dir.to_string_lossy().into_owned();
+        let snap = [("SNAP", snap_path.as_str()), ("SNAP_NAME", "computex")];
+        std::fs::write(
+            snap_dir.join("meta/snap.yaml"),
+            "name: computex\nconfinement: strict\n",
+        )
+        .expect("write snap.yaml");
+        assert_eq!(
+            detect_from(&missing, env(&snap)),
+            Some(Sandbox::Snap {
+                name: "computex".to_string()
+            })
+        );
+        std::fs::write(
+            snap_dir.join("meta/snap.yaml"),
+            "name: computex\nconfinement: classic\n",
+        )
+        .expect("write snap.yaml");
+        assert_eq!(detect_from(&missing, env(&snap)), None);
+    }
+}
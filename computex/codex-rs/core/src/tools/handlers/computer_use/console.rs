@@ -0,0 +1,587 @@
+//! The `console` backend: a tmux pane or a serial port instead of a display,
+//! for TUI installers, bootloaders, and network appliances.
+//!
+//! A text console has characters instead of pixels, so `computer_screenshot`
+//! returns the rendered screen as text with the cursor position, and
+//! `computer_type`, `computer_key`, and `computer_clear_field` write the
+//! bytes a terminal would send for them. A tmux pane is read back with
+//! `tmux capture-pane`. A serial port has no screen to read back, so from
+//! the first call on a background thread feeds everything the device prints
+//! through a VT100 emulator until the session ends. Tools that need pixels
+//! or windows are turned off.
+
+use std::fmt;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::mpsc;
+
+use super::clear_backspaces;
+use super::keys;
+use super::pause;
+use super::require_command;
+use super::requires_confirmation;
+use super::schema::ClearFieldArgs;
+use super::schema::KeyArgs;
+use super::schema::TypeArgs;
+use super::schema::parse_args;
+use crate::codex::Session;
+use crate::config::types::ComputerUseConfig;
+use crate::config::types::ConsoleTarget;
+use crate::config::types::TargetOs;
+use crate::function_tool::FunctionCallError;
+use crate::tools::context::ToolOutput;
+
+/// The tools a text console supports; the rest need pixels or windows.
+pub(super) const CONSOLE_TOOLS: &[&str] = &[
+    "computer_screenshot",
+    "computer_type",
+    "computer_key",
+    "computer_clear_field",
+    "computer_self_test",
+    "computer_checkpoint",
+];
+
+/// Size of the emulated serial screen, the size a serial getty assumes.
+const SERIAL_ROWS: u16 = 24;
+const SERIAL_COLS: u16 = 80;
+/// Bytes read from the serial port at a time.
+const READ_CHUNK: usize = 4096;
+
+/// What a console showed when it was read.
+#[derive(Debug, PartialEq)]
+struct Screen {
+    rows: u16,
+    cols: u16,
+    /// Zero-based row and column of the cursor.
+    cursor: (u16, u16),
+    contents: String,
+}
+
+impl Screen {
+    /// The screen for the model: a header with the size and 1-based cursor
+    /// position, then the rows without trailing blanks.
+    fn render(&self, target: &ConsoleTarget) -> String {
+        let mut lines: Vec<&str> = self.contents.lines().map(str::trim_end).collect();
+        while lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+        format!(
+            "{target} screen ({}x{}, cursor at row {}, column {}):\n{}",
+            self.cols,
+            self.rows,
+            self.cursor.0 + 1,
+            self.cursor.1 + 1,
+            lines.join("\n")
+        )
+    }
+}
+
+/// A serial port read into an emulated screen until dropped.
+pub(super) struct SerialConsole {
+    device: PathBuf,
+    baud: u32,
+    port: File,
+    screen: Arc<Mutex<vt100::Parser>>,
+    /// Dropping the sender stops the reader thread.
+    _stop: mpsc::Sender<()>,
+}
+
+impl fmt::Debug for SerialConsole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SerialConsole")
+            .field("device", &self.device)
+            .field("baud", &self.baud)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SerialConsole {
+    pub(super) fn open(device: &Path, baud: u32) -> Result<Self, String> {
+        configure_port(device, baud)?;
+        let port = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(device)
+            .map_err(|err| format!("failed to open {}: {err}", device.display()))?;
+        let mut reader = port
+            .try_clone()
+            .map_err(|err| format!("failed to open {}: {err}", device.display()))?;
+        let screen = Arc::new(Mutex::new(vt100::Parser::new(SERIAL_ROWS, SERIAL_COLS, 0)));
+        let (stop, stopped) = mpsc::channel::<()>();
+        let buffer = Arc::clone(&screen);
+        let spawned = std::thread::Builder::new()
+            .name("computex-serial".to_string())
+            .spawn(move || {
+                let mut chunk = [0u8; READ_CHUNK];
+                // Reads time out (see `configure_port`), so the stop signal
+                // is seen even while the console is quiet.
+                while matches!(stopped.try_recv(), Err(mpsc::TryRecvError::Empty)) {
+                    match reader.read(&mut chunk) {
+                        Ok(0) => {}
+                        Ok(read) => match buffer.lock() {
+                            Ok(mut parser) => parser.process(&chunk[..read]),
+                            Err(_) => break,
+                        },
+                        Err(err) => {
+                            tracing::warn!("reading the serial console failed: {err}");
+                            break;
+                        }
+                    }
+                }
+            });
+        if let Err(err) = spawned {
+            return Err(format!("failed to start the serial reader: {err}"));
+        }
+        Ok(Self {
+            device: device.to_path_buf(),
+            baud,
+            port,
+            screen,
+            _stop: stop,
+        })
+    }
+
+    pub(super) fn is_for(&self, device: &Path, baud: u32) -> bool {
+        self.device == device && self.baud == baud
+    }
+
+    fn screen(&self) -> Result<Screen, String> {
+        let parser = self
+            .screen
+            .lock()
+            .map_err(|_| "the serial reader stopped".to_string())?;
+        let screen = parser.screen();
+        let (rows, cols) = screen.size();
+        Ok(Screen {
+            rows,
+            cols,
+            cursor: screen.cursor_position(),
+            contents: screen.contents(),
+        })
+    }
+
+    fn write(&self, bytes: &[u8]) -> Result<(), String> {
+        (&self.port)
+            .write_all(bytes)
+            .and_then(|()| (&self.port).flush())
+            .map_err(|err| format!("failed to write to {}: {err}", self.device.display()))
+    }
+}
+
+/// Sets the baud rate and raw mode, and makes reads return after 0.2 s
+/// without data so the reader thread can stop.
+#[cfg(unix)]
+fn configure_port(device: &Path, baud: u32) -> Result<(), String> {
+    let stty = require_command("stty").map_err(|err| err.to_string())?;
+    let device_flag = if cfg!(target_os = "macos") {
+        "-f"
+    } else {
+        "-F"
+    };
+    let output = Command::new(stty)
+        .arg(device_flag)
+        .arg(device)
+        .args([
+            baud.to_string().as_str(),
+            "raw",
+            "-echo",
+            "clocal",
+            "min",
+            "0",
+            "time",
+            "2",
+        ])
+        .output()
+        .map_err(|err| format!("failed to run stty: {err}"))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "stty could not set up {} at {baud} baud: {}",
+            device.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+#[cfg(not(unix))]
+fn configure_port(_device: &Path, _baud: u32) -> Result<(), String> {
+    Err("serial consoles are only supported on Unix hosts".to_string())
+}
+
+/// Runs `tool_name` against the configured console.
+pub(super) async fn run(
+    session: &Session,
+    config: &ComputerUseConfig,
+    tool_name: &str,
+    arguments: &str,
+) -> Result<ToolOutput, FunctionCallError> {
+    let Some(target) = &config.console else {
+        return Err(FunctionCallError::RespondToModel(
+            "the console backend requires computer_use.console".to_string(),
+        ));
+    };
+    let content = match tool_name {
+        "computer_screenshot" => read_screen(session, target).await?.render(target),
+        "computer_type" => {
+            let args: TypeArgs = parse_args(arguments)?;
+            pause::wait_while_paused().await;
+            // Terminals send a carriage return for Enter.
+            let text = args.text.replace("\r\n", "\r").replace('\n', "\r");
+            match target {
+                ConsoleTarget::Tmux { pane } => {
+                    tmux(&["send-keys", "-t", pane, "-l", "--", &text])
+                        .map_err(FunctionCallError::RespondToModel)?;
+                }
+                ConsoleTarget::Serial { device, baud } => {
+                    write_serial(session, device, *baud, text.as_bytes()).await?;
+                }
+            }
+            format!(
+                "typed {} characters into {target}; take a screenshot to see the result",
+                args.text.chars().count()
+            )
+        }
+        "computer_key" => {
+            let args: KeyArgs = parse_args(arguments)?;
+            let parsed =
+                keys::parse_chord(&args.keys).map_err(FunctionCallError::RespondToModel)?;
+            // Terminals take Ctrl shortcuts whatever OS runs behind them.
+            let chord = parsed.keys_for(TargetOs::Linux);
+            if config.confirm_destructive_keys
+                && requires_confirmation(&chord)
+                && !matches!(args.confirm, Some(true))
+            {
+                return Err(FunctionCallError::RespondToModel(
+                    "destructive key combo requires confirm=true after user approval".to_string(),
+                ));
+            }
+            let bytes = key_bytes(&chord).map_err(FunctionCallError::RespondToModel)?;
+            pause::wait_while_paused().await;
+            send_bytes(session, target, &bytes).await?;
+            format!("pressed {} in {target}", chord.join("+"))
+        }
+        "computer_clear_field" => {
+            let args: ClearFieldArgs = parse_args(arguments)?;
+            let count = clear_backspaces(args.count);
+            pause::wait_while_paused().await;
+            send_bytes(session, target, &vec![0x7f; count as usize]).await?;
+            format!(
+                "pressed Backspace {count} time(s) in {target}; take a screenshot to confirm the line is empty before typing"
+            )
+        }
+        _ => {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "{tool_name} needs a graphical display; on the text console {target} use {}",
+                CONSOLE_TOOLS[..4].join(", ")
+            )));
+        }
+    };
+    Ok(ToolOutput::Function {
+        content,
+        content_items: None,
+        success: Some(true),
+    })
+}
+
+/// One line describing the console, for the backend checks.
+pub(super) fn describe(config: &ComputerUseConfig) -> Result<String, String> {
+    match &config.console {
+        None => Err("the console backend requires computer_use.console".to_string()),
+        Some(target @ ConsoleTarget::Tmux { pane }) => {
+            let screen = tmux_screen(pane)?;
+            Ok(format!(
+                "console {target} ({}x{}); read with tmux capture-pane and driven with tmux send-keys",
+                screen.cols, screen.rows
+            ))
+        }
+        Some(target @ ConsoleTarget::Serial { device, baud }) => {
+            OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open(device)
+                .map_err(|err| format!("failed to open {}: {err}", device.display()))?;
+            Ok(format!(
+                "console {target} at {baud} baud; read through a {SERIAL_COLS}x{SERIAL_ROWS} terminal emulator"
+            ))
+        }
+    }
+}
+
+async fn read_screen(
+    session: &Session,
+    target: &ConsoleTarget,
+) -> Result<Screen, FunctionCallError> {
+    match target {
+        ConsoleTarget::Tmux { pane } => tmux_screen(pane),
+        ConsoleTarget::Serial { device, baud } => session
+            .services
+            .computer_use
+            .lock()
+            .await
+            .serial_console(device, *baud)
+            .and_then(SerialConsole::screen),
+    }
+    .map_err(FunctionCallError::RespondToModel)
+}
+
+/// Sends the bytes of a key press.
+async fn send_bytes(
+    session: &Session,
+    target: &ConsoleTarget,
+    bytes: &[u8],
+) -> Result<(), FunctionCallError> {
+    match target {
+        ConsoleTarget::Tmux { pane } => {
+            // `-H` takes each byte in hex, so control characters and escape
+            // sequences arrive unchanged.
+            let hex: Vec<String> = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+            let mut args = vec!["send-keys", "-t", pane, "-H"];
+            args.extend(hex.iter().map(String::as_str));
+            tmux(&args)
+                .map(drop)
+                .map_err(FunctionCallError::RespondToModel)
+        }
+        ConsoleTarget::Serial { device, baud } => write_serial(session, device, *baud, bytes).await,
+    }
+}
+
+async fn write_serial(
+    session: &Session,
+    device: &Path,
+    baud: u32,
+    bytes: &[u8],
+) -> Result<(), FunctionCallError> {
+    session
+        .services
+        .computer_use
+        .lock()
+        .await
+        .serial_console(device, baud)
+        .and_then(|console| console.write(bytes))
+        .map_err(FunctionCallError::RespondToModel)
+}
+
+fn tmux_screen(pane: &str) -> Result<Screen, String> {
+    let geometry = tmux(&[
+        "display-message",
+        "-p",
+        "-t",
+        pane,
+        "#{pane_width} #{pane_height} #{cursor_x} #{cursor_y}",
+    ])?;
+    let numbers: Vec<u16> = geometry
+        .split_whitespace()
+        .filter_map(|number| number.parse().ok())
+        .collect();
+    let [cols, rows, x, y] = numbers[..] else {
+        return Err(format!(
+            "tmux returned unexpected pane geometry `{}`",
+            geometry.trim()
+        ));
+    };
+    let contents = tmux(&["capture-pane", "-p", "-t", pane])?;
+    Ok(Screen {
+        rows,
+        cols,
+        cursor: (y, x),
+        contents,
+    })
+}
+
+/// Runs `tmux args` and returns its output.
+fn tmux(args: &[&str]) -> Result<String, String> {
+    let tmux = require_command("tmux").map_err(|err| err.to_string())?;
+    let output = Command::new(tmux)
+        .args(args)
+        .output()
+        .map_err(|err| format!("failed to run tmux: {err}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "tmux {} failed: {}",
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The bytes a terminal sends for the xdotool `chord`, e.g. `\x03` for
+/// Ctrl+C and `\x1b[A` for Up. Modified cursor and editing keys use xterm's
+/// `CSI 1;<modifier>` form.
+fn key_bytes(chord: &[String]) -> Result<Vec<u8>, String> {
+    let mut ctrl = false;
+    let mut shift = false;
+    let mut alt = false;
+    let mut key = None;
+    for name in chord {
+        match name.as_str() {
+            "ctrl" => ctrl = true,
+            "shift" => shift = true,
+            "alt" => alt = true,
+            "super" | "ISO_Level3_Shift" => {
+                return Err(format!("a text console has no `{name}` key"));
+            }
+            _ if key.is_some() => {
+                return Err(
+                    "a text console takes one key at a time, with ctrl, shift, or alt".to_string(),
+                );
+            }
+            _ => key = Some(name.as_str()),
+        }
+    }
+    let Some(key) = key else {
+        return Err("a text console can't press a modifier on its own".to_string());
+    };
+    let modifier = 1 + u8::from(shift) + 2 * u8::from(alt) + 4 * u8::from(ctrl);
+    let csi = |code: &str, suffix: char| {
+        if modifier == 1 {
+            format!("\x1b[{code}{suffix}")
+        } else {
+            format!(
+                "\x1b[{};{modifier}{suffix}",
+                if code.is_empty() { "1" } else { code }
+            )
+        }
+    };
+    let sequence = match key {
+        "Up" => csi("", 'A'),
+        "Down" => csi("", 'B'),
+        "Right" => csi("", 'C'),
+        "Left" => csi("", 'D'),
+        "Home" => csi("", 'H'),
+        "End" => csi("", 'F'),
+        "Insert" => csi("2", '~'),
+        "Delete" => csi("3", '~'),
+        "Page_Up" => csi("5", '~'),
+        "Page_Down" => csi("6", '~'),
+        "F1" => "\x1bOP".to_string(),
+        "F2" => "\x1bOQ".to_string(),
+        "F3" => "\x1bOR".to_string(),
+        "F4" => "\x1bOS".to_string(),
+        "F5" => csi("15", '~'),
+        "F6" => csi("17", '~'),
+        "F7" => csi("18", '~'),
+        "F8" => csi("19", '~'),
+        "F9" => csi("20", '~'),
+        "F10" => csi("21", '~'),
+        "F11" => csi("23", '~'),
+        "F12" => csi("24", '~'),
+        "Tab" if shift => "\x1b[Z".to_string(),
+        _ => {
+            let byte = match key {
+                "Return" => b'\r',
+                "Tab" => b'\t',
+                "BackSpace" => 0x7f,
+                "Escape" => 0x1b,
+                "space" => b' ',
+                "plus" => b'+',
+                "minus" => b'-',
+                "comma" => b',',
+                "period" => b'.',
+                "slash" => b'/',
+                "backslash" => b'\\',
+                "semicolon" => b';',
+                "apostrophe" => b'\'',
+                "grave" => b'`',
+                "equal" => b'=',
+                "bracketleft" => b'[',
+                "bracketright" => b']',
+                _ => match key.as_bytes() {
+                    [byte] if byte.is_ascii_graphic() => *byte,
+                    _ => return Err(format!("`{key}` has no terminal sequence")),
+                },
+            };
+            let byte = if shift && byte.is_ascii_lowercase() {
+                byte.to_ascii_uppercase()
+            } else {
+                byte
+            };
+            let byte = match (ctrl, byte) {
+                (false, byte) => byte,
+                (true, b' ' | b'@') => 0,
+                (true, byte @ (b'a'..=b'z' | b'A'..=b'Z' | b'[' | b'\\' | b']')) => byte & 0x1f,
+                (true, _) => return Err(format!("ctrl+{key} has no terminal sequence")),
+            };
+            let mut bytes = Vec::new();
+            if alt {
+                bytes.push(0x1b);
+            }
+            bytes.push(byte);
+            return Ok(bytes);
+        }
+    };
+    Ok(sequence.into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn bytes(chord: &[&str]) -> Result<Vec<u8>, String> {
+        key_bytes(
+            &chord
+                .iter()
+                .map(|key| (*key).to_string())
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    #[test]
+    fn parses_consoles_and_encodes_keys() {
+        let serial: ConsoleTarget = "serial:/dev/ttyUSB0@9600".parse().expect("serial");
+        assert_eq!(
+            serial,
+            ConsoleTarget::Serial {
+                device: PathBuf::from("/dev/ttyUSB0"),
+                baud: 9600,
+            }
+        );
+        assert_eq!(serial.to_string(), "serial:/dev/ttyUSB0@9600");
+        assert_eq!(
+            "serial:/dev/ttyS0"
+                .parse::<ConsoleTarget>()
+                .map(|target| target.to_string()),
+            Ok("serial:/dev/ttyS0".to_string())
+        );
+        assert_eq!(
+            "tmux:installer:0.1".parse::<ConsoleTarget>(),
+            Ok(ConsoleTarget::Tmux {
+                pane: "installer:0.1".to_string()
+            })
+        );
+        assert!("serial:/dev/ttyS0@fast".parse::<ConsoleTarget>().is_err());
+        assert!("tmux:".parse::<ConsoleTarget>().is_err());
+        assert!("/dev/ttyS0".parse::<ConsoleTarget>().is_err());
+
+        assert_eq!(bytes(&["ctrl", "c"]), Ok(vec![0x03]));
+        assert_eq!(bytes(&["Return"]), Ok(b"\r".to_vec()));
+        assert_eq!(bytes(&["Up"]), Ok(b"\x1b[A".to_vec()));
+        assert_eq!(bytes(&["ctrl", "Left"]), Ok(b"\x1b[1;5D".to_vec()));
+        assert_eq!(bytes(&["shift", "Tab"]), Ok(b"\x1b[Z".to_vec()));
+        assert_eq!(bytes(&["alt", "x"]), Ok(b"\x1bx".to_vec()));
+        assert_eq!(bytes(&["F10"]), Ok(b"\x1b[21~".to_vec()));
+        assert_eq!(bytes(&["shift", "a"]), Ok(b"A".to_vec()));
+        assert!(bytes(&["super", "l"]).is_err());
+        assert!(bytes(&["ctrl"]).is_err());
+
+        let screen = Screen {
+            rows: 24,
+            cols: 80,
+            cursor: (1, 6),
+            contents: "login: root\nPassword:   \n\n\n".to_string(),
+        };
+        assert_eq!(
+            screen.render(&serial),
+            "serial:/dev/ttyUSB0@9600 screen (80x24, cursor at row 2, column 7):\nlogin: root\nPassword:"
+        );
+    }
+}
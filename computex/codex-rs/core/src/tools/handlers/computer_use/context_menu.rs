@@ -0,0 +1,158 @@
+//! Finding the entry of a freshly opened context menu for
+//! `computer_context_menu`.
+//!
+//! Context menus open at the pointer, take a moment to render, and close on
+//! the next stray click, so a right-click, a screenshot, and a click are
+//! three round trips that often miss. Here the entry is read off the screen
+//! right after the right-click with OCR, retried briefly while the menu
+//! renders, keeping the match closest to the click since the label may also
+//! show elsewhere. The accessibility tree, which is slower to walk, is the
+//! fallback when OCR finds nothing.
+
+use std::time::Duration;
+
+use super::accessibility;
+use super::capture_root_window;
+use super::coordinates;
+use super::find_text;
+use super::find_text::TextMatch;
+use crate::config::types::ComputerUseConfig;
+
+/// Pauses before each look at the screen after the right-click. Menus with
+/// an open animation need the later ones.
+const ATTEMPT_DELAYS_MS: [u64; 3] = [150, 350, 700];
+
+/// How far from the click, in screen pixels, an entry may be. Menus open
+/// beside the pointer, so matches farther away are other text on screen.
+const MAX_REACH: i64 = 800;
+
+/// A menu entry on screen, in screen pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct MenuEntry {
+    pub text: String,
+    pub left: i64,
+    pub top: i64,
+    pub right: i64,
+    pub bottom: i64,
+    /// `ocr` or `accessibility`.
+    pub via: &'static str,
+}
+
+impl MenuEntry {
+    pub(super) fn center(&self) -> (i64, i64) {
+        ((self.left + self.right) / 2, (self.top + self.bottom) / 2)
+    }
+}
+
+/// Looks for `item` in the menu that a right-click at `click` (screen
+/// pixels) opened, retrying while it renders. On failure, returns why each
+/// strategy came up empty.
+pub(super) async fn wait_for_entry(
+    config: &ComputerUseConfig,
+    item: &str,
+    click: (i64, i64),
+) -> Result<MenuEntry, String> {
+    let mut ocr_misses = Vec::new();
+    for delay in ATTEMPT_DELAYS_MS {
+        tokio::time::sleep(Duration::from_millis(delay)).await;
+        match by_ocr(config, item, click) {
+            Ok(entry) => return Ok(entry),
+            Err(miss) => ocr_misses.push(miss),
+        }
+    }
+    ocr_misses.dedup();
+    let ocr_miss = ocr_misses.join("; ");
+    let element = accessibility::find(item)
+        .map_err(|err| format!("ocr: {ocr_miss}; accessibility: {err}"))?
+        .filter(|element| element.score >= find_text::DEFAULT_MIN_SIMILARITY)
+        .map(|element| MenuEntry {
+            text: element.name,
+            left: element.left,
+            top: element.top,
+            right: element.left + element.width,
+            bottom: element.top + element.height,
+            via: "accessibility",
+        })
+        .filter(|entry| distance(entry, click) <= MAX_REACH);
+    element.ok_or_else(|| format!("ocr: {ocr_miss}; accessibility: no entry near the click"))
+}
+
+fn by_ocr(config: &ComputerUseConfig, item: &str, click: (i64, i64)) -> Result<MenuEntry, String> {
+    let capture = capture_root_window(config, None, None).map_err(|err| err.to_string())?;
+    let tsv = find_text::recognize(&capture);
+    let _ = std::fs::remove_file(&capture);
+    let tsv = tsv.map_err(|err| err.to_string())?;
+    // Captures cover the viewport, so their pixels start at its origin.
+    let (origin_x, origin_y) = coordinates::viewport_origin(config);
+    let matches = find_text::find_matches(&tsv, item, find_text::DEFAULT_MIN_SIMILARITY)
+        .into_iter()
+        .map(|found| TextMatch {
+            left: found.left + origin_x,
+            top: found.top + origin_y,
+            right: found.right + origin_x,
+            bottom: found.bottom + origin_y,
+            ..found
+        });
+    closest(matches, click).ok_or_else(|| "no matching text near the click".to_string())
+}
+
+/// The match nearest `click`, if one is within reach.
+fn closest(matches: impl Iterator<Item = TextMatch>, click: (i64, i64)) -> Option<MenuEntry> {
+    matches
+        .map(|found| MenuEntry {
+            text: found.text,
+            left: found.left,
+            top: found.top,
+            right: found.right,
+            bottom: found.bottom,
+            via: "ocr",
+        })
+        .filter(|entry| distance(entry, click) <= MAX_REACH)
+        .min_by_key(|entry| distance(entry, click))
+}
+
+/// Distance from `(x, y)` to the nearest point of `entry`'s box.
+fn distance(entry: &MenuEntry, (x, y): (i64, i64)) -> i64 {
+    let dx = (entry.left - x).max(x - entry.right).max(0);
+    let dy = (entry.top - y).max(y - entry.bottom).max(0);
+    ((dx * dx + dy * dy) as f64).sqrt().round() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn text(left: i64, top: i64) -> TextMatch {
+        TextMatch {
+            text: "Copy".to_string(),
+            left,
+            top,
+            right: left + 40,
+            bottom: top + 16,
+            confidence: 0.9,
+        }
+    }
+
+    #[test]
+    fn prefers_the_entry_beside_the_click() {
+        // "Copy" in a toolbar far away, and in the menu opened at (500, 300).
+        let found = closest(vec![text(20, 40), text(512, 330)].into_iter(), (500, 300));
+        assert_eq!(found.map(|entry| entry.center()), Some((532, 338)));
+        assert_eq!(closest(vec![text(1400, 900)].into_iter(), (100, 100)), None);
+        assert_eq!(
+            distance(
+                &MenuEntry {
+                    text: String::new(),
+                    left: 10,
+                    top: 10,
+                    right: 50,
+                    bottom: 30,
+                    via: "ocr",
+                },
+                (20, 20)
+            ),
+            0
+        );
+    }
+}
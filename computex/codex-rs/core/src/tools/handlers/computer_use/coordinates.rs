@@ -0,0 +1,270 @@
+//! Conversions between the model-facing coordinate space and screen pixels.
+//!
+//! Screen pixels are absolute on the X screen. With `computer_use.viewport`
+//! set, the screen size passed in is the viewport's and model coordinates are
+//! relative to its top-left corner. A `computer_use.calibration` measured on
+//! the same screen size corrects the pixels points are sent to.
+
+use crate::config::types::ComputerUseConfig;
+use crate::config::types::CoordinateSpace;
+use crate::config::types::Resolution;
+
+/// A model-provided point after clamping, together with the physical pixel it
+/// maps to on the current display.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct MappedPoint {
+    pub model_x: f64,
+    pub model_y: f64,
+    pub screen_x: i64,
+    pub screen_y: i64,
+}
+
+impl MappedPoint {
+    /// Formats the clamped model-space point the way the model should refer to it.
+    pub fn display(&self, space: CoordinateSpace) -> String {
+        format_point(space, self.model_x, self.model_y)
+    }
+}
+
+pub(super) fn map_point(
+    config: &ComputerUseConfig,
+    x: f64,
+    y: f64,
+    screen_width: f64,
+    screen_height: f64,
+) -> MappedPoint {
+    let virtual_width = f64::from(config.resolution.width);
+    let virtual_height = f64::from(config.resolution.height);
+    let (max_x, max_y) = match config.coordinate_space {
+        CoordinateSpace::Virtual => (virtual_width - 1.0, virtual_height - 1.0),
+        CoordinateSpace::Normalized => (1.0, 1.0),
+        CoordinateSpace::Native => (screen_width - 1.0, screen_height - 1.0),
+    };
+    let model_x = x.clamp(0.0, max_x.max(0.0));
+    let model_y = y.clamp(0.0, max_y.max(0.0));
+    let (screen_x, screen_y) = match config.coordinate_space {
+        CoordinateSpace::Virtual => (
+            (model_x / virtual_width) * screen_width,
+            (model_y / virtual_height) * screen_height,
+        ),
+        CoordinateSpace::Normalized => (
+            model_x * (screen_width - 1.0).max(0.0),
+            model_y * (screen_height - 1.0).max(0.0),
+        ),
+        CoordinateSpace::Native => (model_x, model_y),
+    };
+    let (origin_x, origin_y) = viewport_origin(config);
+    let (mut screen_x, mut screen_y) = (screen_x + origin_x as f64, screen_y + origin_y as f64);
+    if let Some(calibration) = &config.calibration
+        && calibration.matches(screen_width, screen_height)
+    {
+        (screen_x, screen_y) = calibration.apply(screen_x, screen_y);
+    }
+    MappedPoint {
+        model_x,
+        model_y,
+        screen_x: screen_x.round() as i64,
+        screen_y: screen_y.round() as i64,
+    }
+}
+
+/// Top-left corner of the configured viewport in screen pixels.
+pub(super) fn viewport_origin(config: &ComputerUseConfig) -> (i64, i64) {
+    config.viewport.map_or((0, 0), |viewport| {
+        (i64::from(viewport.x), i64::from(viewport.y))
+    })
+}
+
+/// Converts a physical screen pixel back into the configured model space and
+/// formats it for the model.
+pub(super) fn screen_to_model(
+    config: &ComputerUseConfig,
+    screen_x: i64,
+    screen_y: i64,
+    screen_width: f64,
+    screen_height: f64,
+) -> String {
+    let (model_x, model_y) =
+        screen_to_model_xy(config, screen_x, screen_y, screen_width, screen_height);
+    format_point(config.coordinate_space, model_x, model_y)
+}
+
+/// Converts a physical screen pixel back into unrounded model-space coordinates.
+pub(super) fn screen_to_model_xy(
+    config: &ComputerUseConfig,
+    screen_x: i64,
+    screen_y: i64,
+    screen_width: f64,
+    screen_height: f64,
+) -> (f64, f64) {
+    let (origin_x, origin_y) = viewport_origin(config);
+    let (x, y) = ((screen_x - origin_x) as f64, (screen_y - origin_y) as f64);
+    match config.coordinate_space {
+        CoordinateSpace::Virtual => (
+            x / screen_width * f64::from(config.resolution.width),
+            y / screen_height * f64::from(config.resolution.height),
+        ),
+        CoordinateSpace::Normalized => (
+            x / (screen_width - 1.0).max(1.0),
+            y / (screen_height - 1.0).max(1.0),
+        ),
+        CoordinateSpace::Native => (x, y),
+    }
+}
+
+pub(super) fn format_point(space: CoordinateSpace, x: f64, y: f64) -> String {
+    match space {
+        CoordinateSpace::Normalized => format!("{x:.3},{y:.3}"),
+        CoordinateSpace::Virtual | CoordinateSpace::Native => {
+            let x = x.round() as i64;
+            let y = y.round() as i64;
+            format!("{x},{y}")
+        }
+    }
+}
+
+/// Size screenshots are scaled to (ignoring aspect ratio), or `None` to keep
+/// native pixels.
+pub(super) fn screenshot_resize(config: &ComputerUseConfig) -> Option<Resolution> {
+    match config.coordinate_space {
+        CoordinateSpace::Virtual | CoordinateSpace::Normalized => Some(config.resolution),
+        CoordinateSpace::Native => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::types::CoordinateCalibration;
+    use crate::config::types::Resolution;
+    use pretty_assertions::assert_eq;
+
+    fn config(space: CoordinateSpace) -> ComputerUseConfig {
+        ComputerUseConfig {
+            coordinate_space: space,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn virtual_space_scales_to_screen() {
+        let point = map_point(
+            &config(CoordinateSpace::Virtual),
+            640.0,
+            360.0,
+            2560.0,
+            1440.0,
+        );
+        assert_eq!((point.screen_x, point.screen_y), (1280, 720));
+        assert_eq!(point.display(CoordinateSpace::Virtual), "640,360");
+    }
+
+    #[test]
+    fn virtual_space_uses_configured_resolution() {
+        let config = ComputerUseConfig {
+            resolution: Resolution {
+                width: 1920,
+                height: 1080,
+            },
+            ..Default::default()
+        };
+        let point = map_point(&config, 960.0, 5000.0, 3840.0, 2160.0);
+        assert_eq!((point.screen_x, point.screen_y), (1920, 2158));
+        assert_eq!(point.display(CoordinateSpace::Virtual), "960,1079");
+        assert_eq!(screenshot_resize(&config), Some(config.resolution));
+    }
+
+    #[test]
+    fn screen_points_convert_back_to_model_space() {
+        let point = map_point(
+            &config(CoordinateSpace::Virtual),
+            640.0,
+            360.0,
+            2560.0,
+            1440.0,
+        );
+        assert_eq!(
+            screen_to_model(
+                &config(CoordinateSpace::Virtual),
+                point.screen_x,
+                point.screen_y,
+                2560.0,
+                1440.0
+            ),
+            "640,360"
+        );
+        assert_eq!(
+            screen_to_model(
+                &config(CoordinateSpace::Normalized),
+                1920,
+                540,
+                1921.0,
+                1081.0
+            ),
+            "1.000,0.500"
+        );
+    }
+
+    #[test]
+    fn viewport_offsets_screen_pixels() {
+        let config = ComputerUseConfig {
+            coordinate_space: CoordinateSpace::Native,
+            viewport: Some("1280x720+1920+0".parse().unwrap()),
+            ..Default::default()
+        };
+        let point = map_point(&config, 2000.0, 10.0, 1280.0, 720.0);
+        assert_eq!((point.screen_x, point.screen_y), (1920 + 1279, 10));
+        assert_eq!(screen_to_model(&config, 1920 + 5, 7, 1280.0, 720.0), "5,7");
+    }
+
+    #[test]
+    fn calibration_corrects_points_on_its_screen() {
+        let config = ComputerUseConfig {
+            coordinate_space: CoordinateSpace::Native,
+            calibration: Some(CoordinateCalibration {
+                screen: Resolution {
+                    width: 1920,
+                    height: 1080,
+                },
+                scale_x: 0.5,
+                offset_x: 0.0,
+                scale_y: 1.0,
+                offset_y: -28.0,
+            }),
+            ..Default::default()
+        };
+        let point = map_point(&config, 1000.0, 500.0, 1920.0, 1080.0);
+        assert_eq!((point.screen_x, point.screen_y), (500, 472));
+        assert_eq!(point.display(CoordinateSpace::Native), "1000,500");
+        // A different resolution makes the measurement stale.
+        let point = map_point(&config, 1000.0, 500.0, 2560.0, 1440.0);
+        assert_eq!((point.screen_x, point.screen_y), (1000, 500));
+    }
+
+    #[test]
+    fn normalized_space_clamps_and_scales() {
+        let point = map_point(
+            &config(CoordinateSpace::Normalized),
+            1.5,
+            0.5,
+            1921.0,
+            1081.0,
+        );
+        assert_eq!((point.screen_x, point.screen_y), (1920, 540));
+        assert_eq!(point.display(CoordinateSpace::Normalized), "1.000,0.500");
+    }
+
+    #[test]
+    fn native_space_passes_through_within_bounds() {
+        let point = map_point(
+            &config(CoordinateSpace::Native),
+            5000.0,
+            12.4,
+            1920.0,
+            1080.0,
+        );
+        assert_eq!((point.screen_x, point.screen_y), (1919, 12));
+        assert_eq!(point.display(CoordinateSpace::Native), "1919,12");
+        assert_eq!(screenshot_resize(&config(CoordinateSpace::Native)), None);
+    }
+}
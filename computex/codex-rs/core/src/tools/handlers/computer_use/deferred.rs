@@ -0,0 +1,142 @@
+//! Images captured while no task is running.
+//!
+//! Screenshots reach the model as input injected into the running task, and
+//! a capture that finishes just as a turn ends, or after it was interrupted,
+//! has no task to go to. Rather than fail the tool call and lose the image,
+//! it is queued and handed to the next task along with the user's message.
+//! The images are read into memory when they are queued, because the file
+//! may be moved into `computer_use.screenshot_dir` or deleted right after,
+//! and only the newest few are kept so a long gap can't pile them up.
+
+use std::collections::VecDeque;
+use std::path::PathBuf;
+
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::ResponseInputItem;
+use codex_protocol::user_input::UserInput;
+
+use crate::codex::Session;
+
+/// Most images held for the next task; older ones are dropped first.
+const MAX_DEFERRED: usize = 6;
+
+const LABEL: &str = "Screen captures taken after the previous turn ended, oldest first:";
+
+/// Images waiting for the next task, oldest first.
+#[derive(Debug, Default)]
+pub(super) struct DeferredImages {
+    images: VecDeque<UserInput>,
+    dropped: usize,
+}
+
+impl DeferredImages {
+    pub(super) fn push(&mut self, images: Vec<UserInput>) {
+        self.images.extend(images);
+        while self.images.len() > MAX_DEFERRED {
+            self.images.pop_front();
+            self.dropped += 1;
+        }
+    }
+
+    /// The queued images behind a line saying what they are, or nothing.
+    pub(super) fn take(&mut self) -> Vec<UserInput> {
+        if self.images.is_empty() {
+            return Vec::new();
+        }
+        let label = match std::mem::take(&mut self.dropped) {
+            0 => LABEL.to_string(),
+            dropped => format!("{LABEL} ({dropped} older ones were dropped)"),
+        };
+        std::iter::once(UserInput::Text { text: label })
+            .chain(self.images.drain(..))
+            .collect()
+    }
+}
+
+/// Attaches the images at `paths` for the model, or queues them for the
+/// next task when none is running.
+pub(super) async fn attach_images(session: &Session, paths: &[PathBuf]) {
+    let images = paths
+        .iter()
+        .map(|path| UserInput::LocalImage { path: path.clone() })
+        .collect();
+    let Err(images) = session.inject_input(images).await else {
+        return;
+    };
+    tracing::debug!(
+        "no active task; holding {} image(s) for the next one",
+        images.len()
+    );
+    let loaded = load(images);
+    session
+        .services
+        .computer_use
+        .lock()
+        .await
+        .defer_images(loaded);
+}
+
+/// Takes the images queued since the last task ended, to go in front of
+/// the input that starts the next one.
+pub(crate) async fn take_deferred_input(session: &Session) -> Vec<UserInput> {
+    session
+        .services
+        .computer_use
+        .lock()
+        .await
+        .take_deferred_images()
+}
+
+/// Reads local images into data URLs, keeping the placeholder text for any
+/// that can't be read.
+fn load(images: Vec<UserInput>) -> Vec<UserInput> {
+    let ResponseInputItem::Message { content, .. } = ResponseInputItem::from(images) else {
+        return Vec::new();
+    };
+    content
+        .into_iter()
+        .filter_map(|item| match item {
+            ContentItem::InputImage { image_url } => Some(UserInput::Image { image_url }),
+            ContentItem::InputText { text } => Some(UserInput::Text { text }),
+            ContentItem::OutputText { .. } => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn image(n: usize) -> UserInput {
+        UserInput::Image {
+            image_url: format!("data:image/png;base64,{n}"),
+        }
+    }
+
+    #[test]
+    fn keeps_the_newest_images_behind_a_label() {
+        let mut deferred = DeferredImages::default();
+        assert_eq!(deferred.take(), Vec::new());
+
+        deferred.push((0..4).map(image).collect());
+        deferred.push((4..8).map(image).collect());
+        let mut expected = vec![UserInput::Text {
+            text: format!("{LABEL} (2 older ones were dropped)"),
+        }];
+        expected.extend((2..8).map(image));
+        assert_eq!(deferred.take(), expected);
+
+        deferred.push(vec![image(8)]);
+        assert_eq!(
+            deferred.take(),
+            vec![
+                UserInput::Text {
+                    text: LABEL.to_string()
+                },
+                image(8)
+            ]
+        );
+        assert_eq!(deferred.take(), Vec::new());
+    }
+}
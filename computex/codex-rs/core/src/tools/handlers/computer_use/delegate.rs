@@ -0,0 +1,299 @@
+//! `computer_delegate`: lets a session without GUI tools hand a scoped GUI
+//! subtask to a one-shot sub-session that has them, then continue with the
+//! sub-session's summary.
+
+use async_trait::async_trait;
+use codex_protocol::models::ResponseItem;
+use codex_protocol::protocol::AgentMessageEvent;
+use codex_protocol::protocol::BackgroundEventEvent;
+use codex_protocol::protocol::ErrorEvent;
+use codex_protocol::protocol::EventMsg;
+use codex_protocol::protocol::RawResponseItemEvent;
+use codex_protocol::protocol::SubAgentSource;
+use codex_protocol::protocol::TaskCompleteEvent;
+use codex_protocol::protocol::TokenCountEvent;
+use codex_protocol::user_input::UserInput;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use crate::codex::Codex;
+use crate::codex_delegate::run_codex_conversation_one_shot;
+use crate::features::Feature;
+use crate::function_tool::FunctionCallError;
+use crate::prompts::computer_use_prompt;
+use crate::tools::context::ToolInvocation;
+use crate::tools::context::ToolOutput;
+use crate::tools::context::ToolPayload;
+use crate::tools::registry::ToolHandler;
+use crate::tools::registry::ToolKind;
+
+use super::schema::DelegateArgs;
+use super::schema::parse_args;
+
+pub(crate) const DELEGATE_TOOL: &str = "computer_delegate";
+
+/// Session source label for delegated sub-sessions.
+const DELEGATE_SOURCE: &str = "computer_delegate";
+
+pub struct ComputerDelegateHandler;
+
+/// Why a delegated sub-session stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DelegateEnd {
+    Completed,
+    ActionBudget,
+    TimedOut,
+    Aborted,
+    Closed,
+}
+
+/// What the parent learns about a delegated sub-session while following it.
+#[derive(Debug, Default)]
+struct DelegateRun {
+    actions: u32,
+    tokens: i64,
+    last_message: Option<String>,
+    error: Option<String>,
+}
+
+#[async_trait]
+impl ToolHandler for ComputerDelegateHandler {
+    fn kind(&self) -> ToolKind {
+        ToolKind::Function
+    }
+
+    async fn is_mutating(&self, _invocation: &ToolInvocation) -> bool {
+        true
+    }
+
+    async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
+        let ToolInvocation {
+            session,
+            turn,
+            payload,
+            tool_name,
+            ..
+        } = invocation;
+
+        let ToolPayload::Function { arguments } = payload else {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "unsupported payload for {tool_name}"
+            )));
+        };
+        let args: DelegateArgs = parse_args(&arguments)?;
+        let task = args.task.trim();
+        if task.is_empty() {
+            return Err(FunctionCallError::RespondToModel(
+                "computer_delegate requires a non-empty task".to_string(),
+            ));
+        }
+
+        let computer_use = &turn.tools_config.computer_use;
+        let max_actions = args
+            .max_actions
+            .unwrap_or(computer_use.delegate_max_actions)
+            .clamp(1, computer_use.delegate_max_actions.max(1));
+        let timeout_secs = args
+            .timeout_seconds
+            .unwrap_or(computer_use.delegate_timeout_secs)
+            .clamp(1, computer_use.delegate_timeout_secs.max(1));
+
+        let mut sub_config = turn.client.config().as_ref().clone();
+        sub_config.features.enable(Feature::ComputerUseGui);
+        // The sub-session has GUI tools, so it never delegates further.
+        sub_config.computer_use.delegate = false;
+        sub_config.base_instructions = Some(computer_use_prompt(&sub_config.computer_use, true));
+
+        // Dropping the tool call (e.g. the parent turn is interrupted) stops
+        // the sub-session too.
+        let cancel = CancellationToken::new();
+        let _stop_on_drop = cancel.clone().drop_guard();
+        let io = run_codex_conversation_one_shot(
+            sub_config,
+            SubAgentSource::Other(DELEGATE_SOURCE.to_string()),
+            Arc::clone(&session.services.auth_manager),
+            Arc::clone(&session.services.models_manager),
+            vec![UserInput::Text {
+                text: delegated_task_prompt(task, max_actions, timeout_secs),
+            }],
+            Arc::clone(&session),
+            Arc::clone(&turn),
+            cancel.clone(),
+            None,
+        )
+        .await
+        .map_err(|err| {
+            FunctionCallError::RespondToModel(format!("failed to start GUI sub-session: {err}"))
+        })?;
+
+        session
+            .send_event(
+                turn.as_ref(),
+                EventMsg::BackgroundEvent(BackgroundEventEvent {
+                    message: format!("Delegating GUI subtask: {task}"),
+                }),
+            )
+            .await;
+
+        let mut run = DelegateRun::default();
+        let end = match tokio::time::timeout(
+            Duration::from_secs(timeout_secs),
+            run.follow(&io, max_actions, |message| {
+                let session = Arc::clone(&session);
+                let turn = Arc::clone(&turn);
+                async move {
+                    session
+                        .send_event(
+                            turn.as_ref(),
+                            EventMsg::BackgroundEvent(BackgroundEventEvent {
+                                message: format!("GUI sub-session: {message}"),
+                            }),
+                        )
+                        .await;
+                }
+            }),
+        )
+        .await
+        {
+            Ok(end) => end,
+            Err(_) => DelegateEnd::TimedOut,
+        };
+        cancel.cancel();
+
+        let (content, success) = run.report(end, max_actions, timeout_secs);
+        Ok(ToolOutput::Function {
+            content,
+            content_items: None,
+            success: Some(success),
+        })
+    }
+}
+
+impl DelegateRun {
+    /// Follows sub-session events until it finishes, aborts, or uses up
+    /// `max_actions` GUI tool calls. Agent messages are passed to `progress`
+    /// so the parent's user can see what the sub-session is doing.
+    async fn follow<F, Fut>(&mut self, io: &Codex, max_actions: u32, progress: F) -> DelegateEnd
+    where
+        F: Fn(String) -> Fut,
+        Fut: std::future::Future<Output = ()>,
+    {
+        while let Ok(event) = io.next_event().await {
+            match event.msg {
+                EventMsg::RawResponseItem(RawResponseItemEvent {
+                    item: ResponseItem::FunctionCall { name, .. },
+                }) if name.starts_with("computer_") => {
+                    self.actions += 1;
+                    if self.actions >= max_actions {
+                        return DelegateEnd::ActionBudget;
+                    }
+                }
+                EventMsg::AgentMessage(AgentMessageEvent { message }) => {
+                    progress(message.clone()).await;
+                    self.last_message = Some(message);
+                }
+                EventMsg::TokenCount(TokenCountEvent {
+                    info: Some(info), ..
+                }) => self.tokens = info.total_token_usage.total_tokens,
+                EventMsg::Error(ErrorEvent { message, .. }) => self.error = Some(message),
+                EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }) => {
+                    if last_agent_message.is_some() {
+                        self.last_message = last_agent_message;
+                    }
+                    return DelegateEnd::Completed;
+                }
+                EventMsg::TurnAborted(_) => return DelegateEnd::Aborted,
+                _ => {}
+            }
+        }
+        DelegateEnd::Closed
+    }
+
+    /// Tool result for the parent model, and whether the subtask finished.
+    fn report(&self, end: DelegateEnd, max_actions: u32, timeout_secs: u64) -> (String, bool) {
+        let status = match end {
+            DelegateEnd::Completed => "GUI sub-session finished".to_string(),
+            DelegateEnd::ActionBudget => format!(
+                "GUI sub-session stopped: it used its budget of {max_actions} GUI action(s) before finishing"
+            ),
+            DelegateEnd::TimedOut => {
+                format!("GUI sub-session stopped: it ran past its {timeout_secs}s time budget")
+            }
+            DelegateEnd::Aborted => "GUI sub-session was interrupted before finishing".to_string(),
+            DelegateEnd::Closed => "GUI sub-session ended unexpectedly".to_string(),
+        };
+        let mut lines = vec![format!(
+            "{status} ({} GUI action(s), {} tokens).",
+            self.actions, self.tokens
+        )];
+        if let Some(error) = &self.error {
+            lines.push(format!("Last error: {error}"));
+        }
+        let heading = if end == DelegateEnd::Completed {
+            "Summary:"
+        } else {
+            "Last report before stopping (the screen may be mid-task; take stock before retrying):"
+        };
+        lines.push(String::new());
+        lines.push(heading.to_string());
+        lines.push(
+            self.last_message
+                .clone()
+                .unwrap_or_else(|| "(the sub-session did not report anything)".to_string()),
+        );
+        (lines.join("\n"), end == DelegateEnd::Completed)
+    }
+}
+
+/// First message of a delegated sub-session: the task plus the ground rules
+/// for reporting back.
+fn delegated_task_prompt(task: &str, max_actions: u32, timeout_secs: u64) -> String {
+    format!(
+        "You are a GUI sub-session. Another agent, which cannot see the screen, delegated this task to you:\n\n{task}\n\nStay within the task. You have a budget of {max_actions} GUI tool calls (screenshots included) and {timeout_secs} seconds; the session is stopped when either runs out. When the task is done, or you are blocked, reply with a concise summary of what you did, the state the screen is left in, and anything the other agent needs to continue."
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn completed_run_reports_summary() {
+        let run = DelegateRun {
+            actions: 7,
+            tokens: 1200,
+            last_message: Some("Installed Foo with default options.".to_string()),
+            error: None,
+        };
+        assert_eq!(
+            run.report(DelegateEnd::Completed, 50, 600),
+            (
+                "GUI sub-session finished (7 GUI action(s), 1200 tokens).\n\nSummary:\nInstalled Foo with default options."
+                    .to_string(),
+                true
+            )
+        );
+    }
+
+    #[test]
+    fn budget_stop_is_reported_as_failure() {
+        let run = DelegateRun {
+            actions: 5,
+            ..Default::default()
+        };
+        let (content, success) = run.report(DelegateEnd::ActionBudget, 5, 600);
+        assert!(!success);
+        assert!(content.starts_with("GUI sub-session stopped: it used its budget of 5"));
+        assert!(content.ends_with("(the sub-session did not report anything)"));
+    }
+
+    #[test]
+    fn task_prompt_states_budget() {
+        let prompt = delegated_task_prompt("open the installer", 20, 300);
+        assert!(prompt.contains("\n\nopen the installer\n\n"));
+        assert!(prompt.contains("budget of 20 GUI tool calls"));
+        assert!(prompt.contains("300 seconds"));
+    }
+}
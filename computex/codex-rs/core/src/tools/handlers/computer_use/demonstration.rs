@@ -0,0 +1,838 @@
+//! Recording a human demonstration for `computer_watch_demo`.
+//!
+//! When the user would rather show a flow than describe it, the tool records
+//! their keyboard and mouse input through the X RECORD extension, takes a
+//! screenshot at the start, every few seconds while they are active, and at
+//! the end, and condenses the raw events into steps in the same vocabulary
+//! as the GUI tools: clicks, drags, scrolls, typed text, and key chords. The
+//! handler attaches the screenshots and returns the numbered trace, so the
+//! model can repeat the flow with its own tools.
+//!
+//! RECORD needs two X connections: one streams the recorded events, the
+//! other controls the recording context. A watchdog thread owns the control
+//! connection and disables the context when the user presses Pause, stops
+//! for [`IDLE_FINISH`], or the time limit runs out.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use std::time::Instant;
+
+use x11rb::connection::Connection;
+use x11rb::connection::RequestConnection;
+use x11rb::protocol::record;
+use x11rb::protocol::record::ConnectionExt as _;
+use x11rb::protocol::xproto;
+use x11rb::protocol::xproto::ConnectionExt as _;
+use x11rb::rust_connection::RustConnection;
+use x11rb::wrapper::ConnectionExt as _;
+use x11rb::x11_utils::TryParse;
+
+use super::capture_screenshot;
+use crate::config::types::ComputerUseConfig;
+use crate::function_tool::FunctionCallError;
+
+/// Default and maximum recording length.
+pub(super) const DEFAULT_MAX: Duration = Duration::from_secs(120);
+const MAX_LIMIT: Duration = Duration::from_secs(600);
+
+/// Recording ends after this long without input, once input has started.
+pub(super) const IDLE_FINISH: Duration = Duration::from_secs(15);
+
+/// Minimum gap between screenshots taken during the demonstration.
+const SCREENSHOT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Screenshots attached per demonstration, the final one included.
+const MAX_SCREENSHOTS: usize = 8;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Keysym of the Pause key, which ends the recording.
+const PAUSE_KEYSYM: u32 = 0xff13;
+
+/// Pointer travel, in screen pixels, that turns a click into a drag.
+const DRAG_THRESHOLD: i64 = 5;
+
+/// Clicks closer together than this in time and space merge into a double
+/// (or triple) click.
+const MULTI_CLICK_WINDOW: Duration = Duration::from_millis(400);
+const MULTI_CLICK_DISTANCE: i64 = 4;
+
+/// Scroll ticks this close together merge into one scroll step.
+const SCROLL_MERGE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Modifier bits of a core event's `state`.
+const SHIFT_MASK: u16 = 1;
+const LOCK_MASK: u16 = 1 << 1;
+const CONTROL_MASK: u16 = 1 << 2;
+const MOD1_MASK: u16 = 1 << 3;
+const MOD4_MASK: u16 = 1 << 6;
+
+/// RECORD reply categories.
+const FROM_SERVER: u8 = 0;
+
+/// Why the recording stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum EndReason {
+    PauseKey,
+    Idle,
+    TimeLimit,
+}
+
+impl std::fmt::Display for EndReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EndReason::PauseKey => write!(f, "stopped with Pause"),
+            EndReason::Idle => write!(f, "ended after {} s without input", IDLE_FINISH.as_secs()),
+            EndReason::TimeLimit => write!(f, "reached the time limit"),
+        }
+    }
+}
+
+/// A recorded demonstration.
+#[derive(Debug)]
+pub(super) struct Demonstration {
+    pub steps: Vec<(Duration, Step)>,
+    /// Screenshots in the order taken, with when they were taken.
+    pub screenshots: Vec<(Duration, PathBuf)>,
+    pub duration: Duration,
+    pub ended: EndReason,
+}
+
+/// One step of a demonstration, in screen pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Step {
+    Click {
+        button: u8,
+        x: i64,
+        y: i64,
+        count: u32,
+    },
+    Drag {
+        button: u8,
+        from: (i64, i64),
+        to: (i64, i64),
+    },
+    Scroll {
+        direction: &'static str,
+        ticks: u32,
+        x: i64,
+        y: i64,
+    },
+    Type(String),
+    Key(String),
+}
+
+impl Step {
+    /// Describes the step for the model, with `point` formatting screen
+    /// pixels in its coordinate space.
+    pub(super) fn describe(&self, point: impl Fn(i64, i64) -> String) -> String {
+        match self {
+            Step::Click {
+                button,
+                x,
+                y,
+                count,
+            } => {
+                let kind = match count {
+                    1 => "click",
+                    2 => "double-click",
+                    _ => "triple-click",
+                };
+                format!("{} {kind} at {}", button_name(*button), point(*x, *y))
+            }
+            Step::Drag { button, from, to } => format!(
+                "{} drag from {} to {}",
+                button_name(*button),
+                point(from.0, from.1),
+                point(to.0, to.1)
+            ),
+            Step::Scroll {
+                direction,
+                ticks,
+                x,
+                y,
+            } => format!("scroll {direction} {ticks} at {}", point(*x, *y)),
+            Step::Type(text) => format!("type {text:?}"),
+            Step::Key(chord) => format!("key {chord}"),
+        }
+    }
+}
+
+/// A raw input event, relative to the start of the recording.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RawEvent {
+    Key {
+        at: Duration,
+        keysym: u32,
+        state: u16,
+    },
+    ButtonPress {
+        at: Duration,
+        button: u8,
+        x: i64,
+        y: i64,
+    },
+    ButtonRelease {
+        at: Duration,
+        button: u8,
+        x: i64,
+        y: i64,
+    },
+}
+
+/// State shared between the recorder, the watchdog, and the caller.
+#[derive(Debug, Default)]
+struct Shared {
+    /// Set when the Pause key is seen.
+    pause_pressed: AtomicBool,
+    /// Milliseconds from the start to the latest event, 0 before any.
+    last_event_ms: AtomicU64,
+    /// Events seen so far.
+    events: AtomicU64,
+    /// Set by the watchdog when it ends the recording for lack of input.
+    idle: AtomicBool,
+}
+
+/// Records the user's input until they press Pause, stop for
+/// [`IDLE_FINISH`], or `max_seconds` (default [`DEFAULT_MAX`]) runs out.
+pub(super) async fn watch(
+    config: &ComputerUseConfig,
+    max_seconds: Option<u64>,
+) -> Result<Demonstration, FunctionCallError> {
+    let limit = max_seconds
+        .map_or(DEFAULT_MAX, Duration::from_secs)
+        .min(MAX_LIMIT);
+    let mut screenshots = vec![(Duration::ZERO, capture_screenshot(config)?)];
+    let shared = Arc::new(Shared::default());
+    let recorder = start(config.display.as_deref(), limit, Arc::clone(&shared))
+        .map_err(FunctionCallError::RespondToModel)?;
+    let started = Instant::now();
+    let mut last_shot = Instant::now();
+    let mut events_at_last_shot = 0;
+    while !recorder.is_finished() {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let events = shared.events.load(Ordering::Relaxed);
+        if events != events_at_last_shot
+            && last_shot.elapsed() >= SCREENSHOT_INTERVAL
+            && screenshots.len() < MAX_SCREENSHOTS - 1
+        {
+            match capture_screenshot(config) {
+                Ok(path) => screenshots.push((started.elapsed(), path)),
+                Err(err) => tracing::debug!("demonstration screenshot failed: {err:?}"),
+            }
+            last_shot = Instant::now();
+            events_at_last_shot = events;
+        }
+    }
+    let raw = tokio::task::spawn_blocking(move || recorder.join())
+        .await
+        .map_err(|err| {
+            FunctionCallError::RespondToModel(format!("demonstration recorder failed: {err}"))
+        })?
+        .map_err(|_| {
+            FunctionCallError::RespondToModel("demonstration recorder panicked".to_string())
+        })?
+        .map_err(FunctionCallError::RespondToModel)?;
+    let duration = started.elapsed();
+    screenshots.push((duration, capture_screenshot(config)?));
+
+    let ended = if shared.pause_pressed.load(Ordering::Relaxed) {
+        EndReason::PauseKey
+    } else if shared.idle.load(Ordering::Relaxed) {
+        EndReason::Idle
+    } else {
+        EndReason::TimeLimit
+    };
+    Ok(Demonstration {
+        steps: summarize(&raw),
+        screenshots,
+        duration,
+        ended,
+    })
+}
+
+/// Formats `demonstration` as the numbered trace returned to the model.
+pub(super) fn format_trace(
+    demonstration: &Demonstration,
+    task: Option<&str>,
+    point: impl Fn(i64, i64) -> String,
+) -> String {
+    let task = task
+        .map(str::trim)
+        .filter(|task| !task.is_empty())
+        .map_or(String::new(), |task| format!(" of {task:?}"));
+    let mut trace = format!(
+        "recorded a {} s demonstration{task} ({}): {} steps and {} screenshots, attached in order",
+        demonstration.duration.as_secs(),
+        demonstration.ended,
+        demonstration.steps.len(),
+        demonstration.screenshots.len()
+    );
+    let mut screenshots = demonstration.screenshots.iter().enumerate().peekable();
+    let mut steps = demonstration.steps.iter().peekable();
+    let mut number = 0;
+    loop {
+        let next_shot = screenshots.peek().map(|(_, (at, _))| *at);
+        let next_step = steps.peek().map(|(at, _)| *at);
+        let line = match (next_shot, next_step) {
+            (Some(shot), step) if step.is_none_or(|step| shot <= step) => {
+                let Some((index, (at, _))) = screenshots.next() else {
+                    break;
+                };
+                (*at, format!("screenshot {}", index + 1))
+            }
+            (_, Some(_)) => {
+                let Some((at, step)) = steps.next() else {
+                    break;
+                };
+                (*at, step.describe(&point))
+            }
+            _ => break,
+        };
+        number += 1;
+        let _ = write!(
+            trace,
+            "\n{number}. [{:.1} s] {}",
+            line.0.as_secs_f64(),
+            line.1
+        );
+    }
+    trace.push_str(
+        "\nRepeat the flow with the GUI tools; positions may differ on the current screen, so check them against a fresh screenshot.",
+    );
+    trace
+}
+
+/// Opens the RECORD connections and starts the recorder and watchdog
+/// threads. The returned thread yields the raw events.
+fn start(
+    display: Option<&str>,
+    limit: Duration,
+    shared: Arc<Shared>,
+) -> Result<JoinHandle<Result<Vec<RawEvent>, String>>, String> {
+    let connect = || {
+        x11rb::connect(display)
+            .map(|(conn, _)| conn)
+            .map_err(|err| format!("cannot open X display: {err}"))
+    };
+    let control = connect()?;
+    let data = connect()?;
+    let has_record = control
+        .extension_information(record::X11_EXTENSION_NAME)
+        .map_err(|err| err.to_string())?
+        .is_some();
+    if !has_record {
+        return Err(
+            "the X server lacks the RECORD extension, so demonstrations can't be recorded"
+                .to_string(),
+        );
+    }
+    let keymap = Keymap::load(&control)?;
+    let context = control.generate_id().map_err(|err| err.to_string())?;
+    let empty = record::Range8 { first: 0, last: 0 };
+    let empty_ext = record::ExtRange {
+        major: empty,
+        minor: record::Range16 { first: 0, last: 0 },
+    };
+    let range = record::Range {
+        core_requests: empty,
+        core_replies: empty,
+        ext_requests: empty_ext,
+        ext_replies: empty_ext,
+        delivered_events: empty,
+        device_events: record::Range8 {
+            first: xproto::KEY_PRESS_EVENT,
+            last: xproto::BUTTON_RELEASE_EVENT,
+        },
+        errors: empty,
+        client_started: false,
+        client_died: false,
+    };
+    control
+        .record_create_context(context, 0, &[record::CS::ALL_CLIENTS.into()], &[range])
+        .map_err(|err| err.to_string())?
+        .check()
+        .map_err(|err| format!("cannot create a RECORD context: {err}"))?;
+
+    let started = Instant::now();
+    let watchdog_shared = Arc::clone(&shared);
+    thread::spawn(move || watchdog(control, context, started, limit, &watchdog_shared));
+    Ok(thread::spawn(move || {
+        record_events(&data, context, &keymap, started, &shared)
+    }))
+}
+
+/// Ends the recording once it should stop.
+fn watchdog(
+    control: RustConnection,
+    context: record::Context,
+    started: Instant,
+    limit: Duration,
+    shared: &Shared,
+) {
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        let elapsed = started.elapsed();
+        let last_event = shared.last_event_ms.load(Ordering::Relaxed);
+        let idle = last_event > 0
+            && elapsed.saturating_sub(Duration::from_millis(last_event)) >= IDLE_FINISH;
+        if idle {
+            shared.idle.store(true, Ordering::Relaxed);
+        }
+        if idle || elapsed >= limit || shared.pause_pressed.load(Ordering::Relaxed) {
+            break;
+        }
+    }
+    let _ = control.record_disable_context(context);
+    let _ = control.sync();
+    let _ = control.record_free_context(context);
+    let _ = control.flush();
+}
+
+/// Reads recorded events until the context is disabled.
+fn record_events(
+    data: &RustConnection,
+    context: record::Context,
+    keymap: &Keymap,
+    started: Instant,
+    shared: &Shared,
+) -> Result<Vec<RawEvent>, String> {
+    let mut events = Vec::new();
+    let replies = data
+        .record_enable_context(context)
+        .map_err(|err| format!("cannot start recording: {err}"))?;
+    for reply in replies {
+        let reply = reply.map_err(|err| format!("recording failed: {err}"))?;
+        if reply.client_swapped || reply.category != FROM_SERVER {
+            continue;
+        }
+        let at = started.elapsed();
+        for chunk in reply.data.chunks_exact(32) {
+            let Some(event) = parse_event(chunk, at, keymap) else {
+                continue;
+            };
+            if let RawEvent::Key { keysym, .. } = event
+                && keysym == PAUSE_KEYSYM
+            {
+                shared.pause_pressed.store(true, Ordering::Relaxed);
+                continue;
+            }
+            shared
+                .last_event_ms
+                .store(at.as_millis().max(1) as u64, Ordering::Relaxed);
+            shared.events.fetch_add(1, Ordering::Relaxed);
+            events.push(event);
+        }
+    }
+    Ok(events)
+}
+
+fn parse_event(chunk: &[u8], at: Duration, keymap: &Keymap) -> Option<RawEvent> {
+    let kind = chunk.first()? & 0x7f;
+    match kind {
+        xproto::KEY_PRESS_EVENT => {
+            let (event, _) = xproto::KeyPressEvent::try_parse(chunk).ok()?;
+            let state = u16::from(event.state);
+            Some(RawEvent::Key {
+                at,
+                keysym: keymap.keysym(event.detail, state)?,
+                state,
+            })
+        }
+        xproto::BUTTON_PRESS_EVENT | xproto::BUTTON_RELEASE_EVENT => {
+            let (event, _) = xproto::ButtonPressEvent::try_parse(chunk).ok()?;
+            let (button, x, y) = (
+                event.detail,
+                i64::from(event.root_x),
+                i64::from(event.root_y),
+            );
+            Some(if kind == xproto::BUTTON_PRESS_EVENT {
+                RawEvent::ButtonPress { at, button, x, y }
+            } else {
+                RawEvent::ButtonRelease { at, button, x, y }
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Keysyms per keycode, from the server's keyboard mapping.
+struct Keymap {
+    keysyms: HashMap<u8, Vec<u32>>,
+}
+
+impl Keymap {
+    fn load(conn: &RustConnection) -> Result<Self, String> {
+        let setup = conn.setup();
+        let (min, max) = (setup.min_keycode, setup.max_keycode);
+        let mapping = conn
+            .get_keyboard_mapping(min, max - min + 1)
+            .map_err(|err| err.to_string())?
+            .reply()
+            .map_err(|err| format!("cannot read the keyboard mapping: {err}"))?;
+        let per_keycode = usize::from(mapping.keysyms_per_keycode.max(1));
+        let keysyms = (min..=max)
+            .zip(mapping.keysyms.chunks(per_keycode))
+            .map(|(keycode, keysyms)| (keycode, keysyms.to_vec()))
+            .collect();
+        Ok(Self { keysyms })
+    }
+
+    /// The keysym `keycode` produces with modifier `state`.
+    fn keysym(&self, keycode: u8, state: u16) -> Option<u32> {
+        let keysyms = self.keysyms.get(&keycode)?;
+        let lower = *keysyms.first().filter(|keysym| **keysym != 0)?;
+        let upper = keysyms
+            .get(1)
+            .copied()
+            .filter(|keysym| *keysym != 0)
+            .unwrap_or(lower);
+        let is_letter = char::from_u32(lower).is_some_and(|c| c.is_ascii_lowercase());
+        let shifted = state & SHIFT_MASK != 0;
+        let caps = state & LOCK_MASK != 0 && is_letter;
+        Some(if shifted != caps { upper } else { lower })
+    }
+}
+
+/// Condenses raw events into steps.
+fn summarize(events: &[RawEvent]) -> Vec<(Duration, Step)> {
+    let mut steps: Vec<(Duration, Step)> = Vec::new();
+    let mut typed: Option<(Duration, String)> = None;
+    let mut pressed: Option<(Duration, u8, i64, i64)> = None;
+    let mut last_click_at = Duration::ZERO;
+    let flush = |typed: &mut Option<(Duration, String)>, steps: &mut Vec<(Duration, Step)>| {
+        if let Some((at, text)) = typed.take()
+            && !text.is_empty()
+        {
+            steps.push((at, Step::Type(text)));
+        }
+    };
+    for event in events {
+        match *event {
+            RawEvent::Key { at, keysym, state } => {
+                if is_modifier(keysym) {
+                    continue;
+                }
+                let chord_mods = state & (CONTROL_MASK | MOD1_MASK | MOD4_MASK);
+                if chord_mods == 0 {
+                    if let Some(c) = printable(keysym) {
+                        typed.get_or_insert_with(|| (at, String::new())).1.push(c);
+                        continue;
+                    }
+                    if keysym == 0xff08
+                        && let Some((_, text)) = typed.as_mut()
+                        && text.pop().is_some()
+                    {
+                        continue;
+                    }
+                }
+                flush(&mut typed, &mut steps);
+                let mut chord = Vec::new();
+                for (mask, name) in [
+                    (CONTROL_MASK, "ctrl"),
+                    (MOD1_MASK, "alt"),
+                    (MOD4_MASK, "super"),
+                ] {
+                    if state & mask != 0 {
+                        chord.push(name.to_string());
+                    }
+                }
+                if state & SHIFT_MASK != 0 && (chord_mods != 0 || printable(keysym).is_none()) {
+                    chord.push("shift".to_string());
+                }
+                chord.push(key_name(keysym));
+                steps.push((at, Step::Key(chord.join("+"))));
+            }
+            RawEvent::ButtonPress { at, button, x, y } => {
+                flush(&mut typed, &mut steps);
+                let Some(direction) = scroll_direction(button) else {
+                    pressed = Some((at, button, x, y));
+                    continue;
+                };
+                if let Some((
+                    last_at,
+                    Step::Scroll {
+                        direction: last_direction,
+                        ticks,
+                        ..
+                    },
+                )) = steps.last_mut()
+                    && *last_direction == direction
+                    && at.saturating_sub(*last_at) <= SCROLL_MERGE_WINDOW
+                {
+                    *ticks += 1;
+                    *last_at = at;
+                    continue;
+                }
+                steps.push((
+                    at,
+                    Step::Scroll {
+                        direction,
+                        ticks: 1,
+                        x,
+                        y,
+                    },
+                ));
+            }
+            RawEvent::ButtonRelease { at, button, x, y } => {
+                let Some((pressed_at, pressed_button, from_x, from_y)) = pressed.take() else {
+                    continue;
+                };
+                if pressed_button != button {
+                    continue;
+                }
+                if (x - from_x).abs().max((y - from_y).abs()) > DRAG_THRESHOLD {
+                    steps.push((
+                        pressed_at,
+                        Step::Drag {
+                            button,
+                            from: (from_x, from_y),
+                            to: (x, y),
+                        },
+                    ));
+                    continue;
+                }
+                if let Some((
+                    _,
+                    Step::Click {
+                        button: last_button,
+                        x: last_x,
+                        y: last_y,
+                        count,
+                    },
+                )) = steps.last_mut()
+                    && *last_button == button
+                    && *count < 3
+                    && pressed_at.saturating_sub(last_click_at) <= MULTI_CLICK_WINDOW
+                    && (from_x - *last_x).abs().max((from_y - *last_y).abs())
+                        <= MULTI_CLICK_DISTANCE
+                {
+                    *count += 1;
+                    last_click_at = at;
+                    continue;
+                }
+                steps.push((
+                    pressed_at,
+                    Step::Click {
+                        button,
+                        x: from_x,
+                        y: from_y,
+                        count: 1,
+                    },
+                ));
+                last_click_at = at;
+            }
+        }
+    }
+    flush(&mut typed, &mut steps);
+    steps
+}
+
+fn scroll_direction(button: u8) -> Option<&'static str> {
+    match button {
+        4 => Some("up"),
+        5 => Some("down"),
+        6 => Some("left"),
+        7 => Some("right"),
+        _ => None,
+    }
+}
+
+fn button_name(button: u8) -> String {
+    match button {
+        1 => "left".to_string(),
+        2 => "middle".to_string(),
+        3 => "right".to_string(),
+        other => format!("button {other}"),
+    }
+}
+
+fn is_modifier(keysym: u32) -> bool {
+    (0xffe1..=0xffee).contains(&keysym) || keysym == 0xfe03
+}
+
+/// The character a keysym types, for Latin-1 and Unicode keysyms.
+fn printable(keysym: u32) -> Option<char> {
+    let code = match keysym {
+        0x20..=0x7e | 0xa0..=0xff => keysym,
+        0x0100_0000..=0x0110_ffff => keysym - 0x0100_0000,
+        _ => return None,
+    };
+    char::from_u32(code).filter(|c| !c.is_control())
+}
+
+/// The X key name xdotool and `computer_key` use for `keysym`.
+fn key_name(keysym: u32) -> String {
+    let name = match keysym {
+        0xff08 => "BackSpace",
+        0xff09 => "Tab",
+        0xff0d => "Return",
+        0xff1b => "Escape",
+        0xffff => "Delete",
+        0xff50 => "Home",
+        0xff51 => "Left",
+        0xff52 => "Up",
+        0xff53 => "Right",
+        0xff54 => "Down",
+        0xff55 => "Page_Up",
+        0xff56 => "Page_Down",
+        0xff57 => "End",
+        0xff63 => "Insert",
+        0xff67 => "Menu",
+        0x20 => "space",
+        0xffbe..=0xffd5 => return format!("F{}", keysym - 0xffbe + 1),
+        _ => {
+            return printable(keysym)
+                .map_or_else(|| format!("0x{keysym:x}"), |c| c.to_lowercase().to_string());
+        }
+    };
+    name.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn ms(value: u64) -> Duration {
+        Duration::from_millis(value)
+    }
+
+    fn key(at: u64, keysym: u32, state: u16) -> RawEvent {
+        RawEvent::Key {
+            at: ms(at),
+            keysym,
+            state,
+        }
+    }
+
+    fn click(at: u64, button: u8, x: i64, y: i64) -> [RawEvent; 2] {
+        [
+            RawEvent::ButtonPress {
+                at: ms(at),
+                button,
+                x,
+                y,
+            },
+            RawEvent::ButtonRelease {
+                at: ms(at + 50),
+                button,
+                x,
+                y,
+            },
+        ]
+    }
+
+    #[test]
+    fn condenses_input_into_tool_steps() {
+        let mut events = Vec::new();
+        events.extend(click(0, 1, 100, 200));
+        events.extend(click(200, 1, 101, 200));
+        // "Hellp", corrected to "Hello", then Enter.
+        for (at, keysym, state) in [
+            (1000, 0xffe1, 0),
+            (1010, u32::from('H'), SHIFT_MASK),
+            (1100, u32::from('e'), 0),
+            (1200, u32::from('l'), 0),
+            (1300, u32::from('l'), 0),
+            (1400, u32::from('p'), 0),
+            (1500, 0xff08, 0),
+            (1600, u32::from('o'), 0),
+            (1700, 0xff0d, 0),
+            (2000, u32::from('s'), CONTROL_MASK),
+        ] {
+            events.push(key(at, keysym, state));
+        }
+        events.extend(click(3000, 5, 50, 60));
+        events.extend(click(3300, 5, 50, 60));
+        events.push(RawEvent::ButtonPress {
+            at: ms(4000),
+            button: 1,
+            x: 10,
+            y: 10,
+        });
+        events.push(RawEvent::ButtonRelease {
+            at: ms(4300),
+            button: 1,
+            x: 300,
+            y: 40,
+        });
+
+        let steps: Vec<Step> = summarize(&events)
+            .into_iter()
+            .map(|(_, step)| step)
+            .collect();
+        assert_eq!(
+            steps,
+            vec![
+                Step::Click {
+                    button: 1,
+                    x: 100,
+                    y: 200,
+                    count: 2,
+                },
+                Step::Type("Hello".to_string()),
+                Step::Key("Return".to_string()),
+                Step::Key("ctrl+s".to_string()),
+                Step::Scroll {
+                    direction: "down",
+                    ticks: 2,
+                    x: 50,
+                    y: 60,
+                },
+                Step::Drag {
+                    button: 1,
+                    from: (10, 10),
+                    to: (300, 40),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn trace_interleaves_screenshots_and_steps() {
+        let demonstration = Demonstration {
+            steps: vec![
+                (
+                    ms(1200),
+                    Step::Click {
+                        button: 1,
+                        x: 10,
+                        y: 20,
+                        count: 1,
+                    },
+                ),
+                (ms(6000), Step::Key("ctrl+s".to_string())),
+            ],
+            screenshots: vec![
+                (Duration::ZERO, PathBuf::from("a.png")),
+                (ms(5000), PathBuf::from("b.png")),
+                (ms(9000), PathBuf::from("c.png")),
+            ],
+            duration: ms(9000),
+            ended: EndReason::PauseKey,
+        };
+        let trace = format_trace(&demonstration, Some("save the file"), |x, y| {
+            format!("{x},{y}")
+        });
+        assert_eq!(
+            trace.lines().take(6).collect::<Vec<_>>(),
+            vec![
+                "recorded a 9 s demonstration of \"save the file\" (stopped with Pause): 2 steps and 3 screenshots, attached in order",
+                "1. [0.0 s] screenshot 1",
+                "2. [1.2 s] left click at 10,20",
+                "3. [5.0 s] screenshot 2",
+                "4. [6.0 s] key ctrl+s",
+                "5. [9.0 s] screenshot 3",
+            ]
+        );
+    }
+}
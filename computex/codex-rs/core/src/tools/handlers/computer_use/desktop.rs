@@ -0,0 +1,84 @@
+//! Which desktop environment the session drives, for tools that change its
+//! settings rather than send input.
+
+use std::path::Path;
+
+use super::gui_command;
+use crate::config::types::ComputerUseConfig;
+use crate::function_tool::FunctionCallError;
+
+/// A desktop whose settings computex knows how to change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Desktop {
+    Gnome,
+    Kde,
+}
+
+impl Desktop {
+    /// The desktop named by `$XDG_CURRENT_DESKTOP`, or an error for `tool`
+    /// naming the unsupported one.
+    pub(super) fn current(tool: &str) -> Result<Self, FunctionCallError> {
+        let current = std::env::var("XDG_CURRENT_DESKTOP").unwrap_or_default();
+        Self::from_xdg(&current).ok_or_else(|| {
+            FunctionCallError::RespondToModel(format!(
+                "{tool} supports GNOME and KDE Plasma; XDG_CURRENT_DESKTOP is `{current}`"
+            ))
+        })
+    }
+
+    /// Reads `$XDG_CURRENT_DESKTOP`, e.g. `ubuntu:GNOME` or `KDE`.
+    fn from_xdg(current: &str) -> Option<Self> {
+        current
+            .split(':')
+            .find_map(|name| match name.trim().to_ascii_lowercase().as_str() {
+                "gnome" | "gnome-classic" | "unity" => Some(Desktop::Gnome),
+                "kde" => Some(Desktop::Kde),
+                _ => None,
+            })
+    }
+
+    pub(super) fn label(self) -> &'static str {
+        match self {
+            Desktop::Gnome => "GNOME",
+            Desktop::Kde => "KDE",
+        }
+    }
+}
+
+/// Runs `program` with `args` and returns its stdout, or an error quoting
+/// its stderr.
+pub(super) fn output(
+    program: &Path,
+    config: &ComputerUseConfig,
+    args: &[&str],
+) -> Result<String, FunctionCallError> {
+    let output = gui_command(program, config)
+        .args(args)
+        .output()
+        .map_err(|err| {
+            FunctionCallError::RespondToModel(format!("failed to run {program:?}: {err}"))
+        })?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let name = program.file_name().unwrap_or_default().to_string_lossy();
+        return Err(FunctionCallError::RespondToModel(format!(
+            "{name} {} failed: {}",
+            args.join(" "),
+            stderr.trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn reads_the_desktop_from_xdg_current_desktop() {
+        assert_eq!(Desktop::from_xdg("ubuntu:GNOME"), Some(Desktop::Gnome));
+        assert_eq!(Desktop::from_xdg("KDE"), Some(Desktop::Kde));
+        assert_eq!(Desktop::from_xdg("XFCE"), None);
+    }
+}
@@ -0,0 +1,265 @@
+//! Screenshot detail tiers for `computer_screenshot`.
+//!
+//! `low` trades legibility for a small image when the model only needs to
+//! orient itself, `high` captures native pixels (optionally of a region) so
+//! small text stays readable, and `auto` picks a tier from how well OCR read
+//! the screen earlier in the turn.
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use super::coordinates::format_point;
+use super::coordinates::map_point;
+use super::coordinates::screen_to_model_xy;
+use super::coordinates::viewport_origin;
+use crate::config::types::ComputerUseConfig;
+use crate::config::types::Resolution;
+
+/// Image size for `detail: "low"`.
+pub(super) const LOW_DETAIL: Resolution = Resolution {
+    width: 640,
+    height: 360,
+};
+
+/// `auto` drops to `low` once OCR matched text at least this confidently,
+/// since the model can locate labels with `computer_find_text` instead.
+const AUTO_LOW_CONFIDENCE: f64 = 0.8;
+
+/// `auto` switches to `high` when OCR matched nothing better than this, since
+/// the text is likely too small or faint for the standard capture.
+const AUTO_HIGH_CONFIDENCE: f64 = 0.5;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub(super) enum ScreenshotDetail {
+    Low,
+    High,
+    #[default]
+    Auto,
+}
+
+/// The capture `computer_screenshot` actually takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Tier {
+    Low,
+    /// The configured coordinate-space capture, as before tiers existed.
+    Standard,
+    High,
+}
+
+impl Tier {
+    pub(super) fn name(self) -> &'static str {
+        match self {
+            Tier::Low => "low",
+            Tier::Standard => "standard",
+            Tier::High => "high",
+        }
+    }
+}
+
+/// A rectangle in physical screen pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct ScreenRect {
+    pub left: i64,
+    pub top: i64,
+    pub width: i64,
+    pub height: i64,
+}
+
+impl ScreenRect {
+    pub(super) fn full(screen_width: f64, screen_height: f64) -> Self {
+        Self {
+            left: 0,
+            top: 0,
+            width: screen_width as i64,
+            height: screen_height as i64,
+        }
+    }
+
+    /// The whole area the tools see: the viewport when one is configured,
+    /// otherwise the full screen.
+    pub(super) fn visible(
+        config: &ComputerUseConfig,
+        screen_width: f64,
+        screen_height: f64,
+    ) -> Self {
+        let (left, top) = viewport_origin(config);
+        Self {
+            left,
+            top,
+            ..Self::full(screen_width, screen_height)
+        }
+    }
+
+    /// `import -crop` geometry for this rectangle.
+    pub(super) fn crop_geometry(&self) -> String {
+        format!("{}x{}+{}+{}", self.width, self.height, self.left, self.top)
+    }
+}
+
+/// Picks the tier for `detail`. `ocr_confidence` is the best
+/// `computer_find_text` match earlier in the turn (0 when nothing matched).
+pub(super) fn resolve_tier(
+    detail: ScreenshotDetail,
+    ocr_confidence: Option<f64>,
+    has_region: bool,
+) -> Tier {
+    match detail {
+        ScreenshotDetail::Low => Tier::Low,
+        ScreenshotDetail::High => Tier::High,
+        ScreenshotDetail::Auto if has_region => Tier::High,
+        ScreenshotDetail::Auto => match ocr_confidence {
+            Some(confidence) if confidence >= AUTO_LOW_CONFIDENCE => Tier::Low,
+            Some(confidence) if confidence < AUTO_HIGH_CONFIDENCE => Tier::High,
+            _ => Tier::Standard,
+        },
+    }
+}
+
+/// Maps a model-space region onto the screen, clamped to its bounds and at
+/// least one pixel in each direction.
+pub(super) fn region_to_screen(
+    config: &ComputerUseConfig,
+    (x, y, width, height): (f64, f64, f64, f64),
+    screen_width: f64,
+    screen_height: f64,
+) -> ScreenRect {
+    let start = map_point(config, x, y, screen_width, screen_height);
+    let end = map_point(
+        config,
+        x + width.max(0.0),
+        y + height.max(0.0),
+        screen_width,
+        screen_height,
+    );
+    ScreenRect {
+        left: start.screen_x,
+        top: start.screen_y,
+        width: (end.screen_x - start.screen_x).max(1),
+        height: (end.screen_y - start.screen_y).max(1),
+    }
+}
+
+/// Tells the model how pixels in an `image_width`x`image_height` capture of
+/// `rect` translate into tool coordinates.
+pub(super) fn mapping_note(
+    config: &ComputerUseConfig,
+    tier: Tier,
+    rect: ScreenRect,
+    (image_width, image_height): (u32, u32),
+    screen_width: f64,
+    screen_height: f64,
+) -> String {
+    let to_model = |x, y| screen_to_model_xy(config, x, y, screen_width, screen_height);
+    let (origin_x, origin_y) = to_model(rect.left, rect.top);
+    let (end_x, end_y) = to_model(rect.left + rect.width, rect.top + rect.height);
+    let scale_x = (end_x - origin_x) / f64::from(image_width.max(1));
+    let scale_y = (end_y - origin_y) / f64::from(image_height.max(1));
+    let space = config.coordinate_space;
+    let label = config.coordinate_label();
+    let shows = format!(
+        "detail {}: the {image_width}x{image_height} image shows {} to {} in {label}",
+        tier.name(),
+        format_point(space, origin_x, origin_y),
+        format_point(space, end_x, end_y),
+    );
+    let identity = origin_x == 0.0
+        && origin_y == 0.0
+        && (scale_x - 1.0).abs() < 1e-9
+        && (scale_y - 1.0).abs() < 1e-9;
+    if identity {
+        format!("{shows}; image pixels are tool coordinates")
+    } else {
+        format!(
+            "{shows}; image pixel (px,py) is at ({} + px*{}, {} + py*{})",
+            number(origin_x),
+            number(scale_x),
+            number(origin_y),
+            number(scale_y),
+        )
+    }
+}
+
+/// Formats `value` with up to six decimals and no trailing zeros.
+fn number(value: f64) -> String {
+    let formatted = format!("{value:.6}");
+    let trimmed = formatted.trim_end_matches('0').trim_end_matches('.');
+    if trimmed == "-0" {
+        "0".to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::types::CoordinateSpace;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn auto_follows_recent_ocr_confidence() {
+        let auto = ScreenshotDetail::Auto;
+        assert_eq!(resolve_tier(auto, None, false), Tier::Standard);
+        assert_eq!(resolve_tier(auto, Some(0.93), false), Tier::Low);
+        assert_eq!(resolve_tier(auto, Some(0.65), false), Tier::Standard);
+        assert_eq!(resolve_tier(auto, Some(0.0), false), Tier::High);
+        assert_eq!(resolve_tier(auto, Some(0.93), true), Tier::High);
+        assert_eq!(
+            resolve_tier(ScreenshotDetail::Low, Some(0.0), false),
+            Tier::Low
+        );
+    }
+
+    #[test]
+    fn low_detail_note_scales_to_virtual_space() {
+        let config = ComputerUseConfig::default();
+        let note = mapping_note(
+            &config,
+            Tier::Low,
+            ScreenRect::full(3840.0, 2160.0),
+            (LOW_DETAIL.width, LOW_DETAIL.height),
+            3840.0,
+            2160.0,
+        );
+        assert_eq!(
+            note,
+            "detail low: the 640x360 image shows 0,0 to 1280,720 in 1280x720 space; image pixel (px,py) is at (0 + px*2, 0 + py*2)"
+        );
+    }
+
+    #[test]
+    fn high_detail_region_maps_back_to_its_origin() {
+        let config = ComputerUseConfig::default();
+        let rect = region_to_screen(&config, (640.0, 360.0, 320.0, 180.0), 3840.0, 2160.0);
+        assert_eq!(
+            rect,
+            ScreenRect {
+                left: 1920,
+                top: 1080,
+                width: 960,
+                height: 540,
+            }
+        );
+        assert_eq!(rect.crop_geometry(), "960x540+1920+1080");
+        let note = mapping_note(&config, Tier::High, rect, (960, 540), 3840.0, 2160.0);
+        assert!(note.ends_with("(640 + px*0.333333, 360 + py*0.333333)"));
+    }
+
+    #[test]
+    fn native_full_screen_needs_no_translation() {
+        let config = ComputerUseConfig {
+            coordinate_space: CoordinateSpace::Native,
+            ..Default::default()
+        };
+        let note = mapping_note(
+            &config,
+            Tier::High,
+            ScreenRect::full(1920.0, 1080.0),
+            (1920, 1080),
+            1920.0,
+            1080.0,
+        );
+        assert!(note.ends_with("image pixels are tool coordinates"));
+    }
+}
@@ -0,0 +1,474 @@
+//! Local UI element detection for `computer_detect_elements`.
+//!
+//! OCR needs visible text, and custom-drawn apps and games often have icons
+//! and controls without any. `computer_use.element_detector` points at a
+//! small ONNX object detection model that proposes buttons, inputs, and
+//! icons with boxes. It runs in process on tract, so no inference runtime
+//! has to be installed, and is loaded once per session.
+//!
+//! Models in the YOLO export layout work as is: one `[1, 3, size, size]`
+//! RGB input scaled to `0..1`, and one output of shape
+//! `[1, 4 + classes, boxes]` (or its transpose) holding each box's center,
+//! size, and per-class scores in input pixels. The screen is letterboxed
+//! into the input, keeping its aspect ratio. Elements get the OCR text
+//! inside their box when tesseract finds any.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::LazyLock;
+use std::sync::Mutex;
+
+use codex_utils_image::screenshot::RawImage;
+use tract_onnx::prelude::*;
+
+use super::find_text;
+use crate::config::types::ComputerUseConfig;
+use crate::config::types::ElementDetector;
+use crate::function_tool::FunctionCallError;
+
+/// Elements below this confidence are dropped unless the caller overrides it.
+pub(super) const DEFAULT_MIN_CONFIDENCE: f64 = 0.5;
+
+/// Result count cap unless the caller overrides it.
+pub(super) const DEFAULT_MAX_RESULTS: usize = 20;
+
+/// Input side when `input_size` isn't set, the usual YOLO export size.
+const DEFAULT_INPUT_SIZE: u32 = 640;
+
+/// Boxes scoring below this are noise whatever the caller asks for.
+const MIN_SCORE: f64 = 0.1;
+
+/// Most boxes considered for overlap suppression.
+const MAX_CANDIDATES: usize = 300;
+
+/// A box overlapping a more confident one of the same class by more than
+/// this (intersection over union) is a duplicate.
+const MAX_OVERLAP: f64 = 0.45;
+
+/// Letterbox padding, the gray YOLO models are trained with.
+const PAD: f32 = 114.0 / 255.0;
+
+type LoadedModel = TypedRunnableModel<TypedModel>;
+
+/// Loaded models by path and input size.
+static MODELS: LazyLock<Mutex<HashMap<(PathBuf, u32), Arc<LoadedModel>>>> =
+    LazyLock::new(Default::default);
+
+/// One element proposed by the detector, in image pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct DetectedElement {
+    pub label: String,
+    pub text: Option<String>,
+    pub x: i64,
+    pub y: i64,
+    pub width: i64,
+    pub height: i64,
+    pub confidence: f64,
+}
+
+/// A box in model input pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Candidate {
+    class: usize,
+    left: f64,
+    top: f64,
+    right: f64,
+    bottom: f64,
+    score: f64,
+}
+
+/// Runs the configured model on `image` and returns its elements with at
+/// least `min_confidence` whose label matches `label`, most confident first.
+pub(super) fn detect(
+    config: &ComputerUseConfig,
+    image: &Path,
+    label: Option<&str>,
+    min_confidence: f64,
+) -> Result<Vec<DetectedElement>, FunctionCallError> {
+    let Some(detector) = &config.element_detector else {
+        return Err(FunctionCallError::RespondToModel(
+            "no element detector is configured; set computer_use.element_detector".to_string(),
+        ));
+    };
+    let elements =
+        run(detector, image, min_confidence).map_err(FunctionCallError::RespondToModel)?;
+    Ok(with_text(filter(elements, label, min_confidence), image))
+}
+
+fn run(
+    detector: &ElementDetector,
+    image: &Path,
+    min_confidence: f64,
+) -> Result<Vec<DetectedElement>, String> {
+    let size = detector.input_size.unwrap_or(DEFAULT_INPUT_SIZE).max(32);
+    let model = load(&detector.model, size)?;
+    let screen =
+        RawImage::open(image).map_err(|err| format!("failed to read the capture: {err}"))?;
+    let (input, scale) = letterbox(&screen, size)?;
+    let outputs = model
+        .run(tvec!(input.into()))
+        .map_err(|err| format!("the element detection model failed: {err}"))?;
+    let output = outputs
+        .first()
+        .ok_or_else(|| "the element detection model has no output".to_string())?;
+    let output = output
+        .to_array_view::<f32>()
+        .map_err(|err| format!("unexpected element detection model output: {err}"))?
+        .into_dimensionality::<tract_ndarray::Ix3>()
+        .map_err(|err| format!("expected a 3-dimensional model output: {err}"))?;
+    let candidates = decode(output, detector.labels.len(), min_confidence.max(MIN_SCORE))?;
+    Ok(candidates
+        .iter()
+        .map(|candidate| element(candidate, &detector.labels, scale, &screen))
+        .collect())
+}
+
+fn load(path: &Path, size: u32) -> Result<Arc<LoadedModel>, String> {
+    let mut models = MODELS
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    let key = (path.to_path_buf(), size);
+    if let Some(model) = models.get(&key) {
+        return Ok(Arc::clone(model));
+    }
+    let side = size as usize;
+    let model = tract_onnx::onnx()
+        .model_for_path(path)
+        .and_then(|model| model.with_input_fact(0, f32::fact([1, 3, side, side]).into()))
+        .and_then(|model| model.into_optimized())
+        .and_then(|model| model.into_runnable())
+        .map_err(|err| {
+            format!(
+                "failed to load the element detection model {}: {err}",
+                path.display()
+            )
+        })?;
+    let model = Arc::new(model);
+    models.insert(key, Arc::clone(&model));
+    Ok(model)
+}
+
+/// The model input for `screen`, scaled to fit a `size` square with the
+/// rest padded, and the scale used.
+fn letterbox(screen: &RawImage, size: u32) -> Result<(Tensor, f64), String> {
+    let scale = (f64::from(size) / f64::from(screen.width.max(1)))
+        .min(f64::from(size) / f64::from(screen.height.max(1)));
+    let fit = |side: u32| ((f64::from(side) * scale).round() as u32).clamp(1, size);
+    let resized = screen
+        .resize(fit(screen.width), fit(screen.height))
+        .map_err(|err| format!("failed to scale the capture: {err}"))?;
+    let (width, height) = (resized.width as usize, resized.height as usize);
+    let channels = usize::from(resized.channels);
+    let side = size as usize;
+    let input = tract_ndarray::Array4::from_shape_fn((1, 3, side, side), |(_, c, y, x)| {
+        if x < width && y < height {
+            resized
+                .pixels
+                .get((y * width + x) * channels + c)
+                .map_or(PAD, |value| f32::from(*value) / 255.0)
+        } else {
+            PAD
+        }
+    });
+    Ok((input.into(), scale))
+}
+
+/// The boxes in a YOLO-style output scoring at least `min_score`, without
+/// duplicates. `classes` is the number of configured labels, used to tell
+/// which axis holds the boxes; without labels the shorter axis is taken as
+/// the per-box values, as in every YOLO export.
+fn decode(
+    output: tract_ndarray::ArrayView3<f32>,
+    classes: usize,
+    min_score: f64,
+) -> Result<Vec<Candidate>, String> {
+    let (_, rows, columns) = output.dim();
+    let transposed = if classes > 0 && rows == classes + 4 {
+        false
+    } else if classes > 0 && columns == classes + 4 {
+        true
+    } else {
+        columns < rows
+    };
+    let (values, count) = if transposed {
+        (columns, rows)
+    } else {
+        (rows, columns)
+    };
+    if values < 5 {
+        return Err(format!(
+            "expected at least 5 values per box in the model output, got {values}"
+        ));
+    }
+    let value = |index: usize, at: usize| {
+        f64::from(if transposed {
+            output[[0, index, at]]
+        } else {
+            output[[0, at, index]]
+        })
+    };
+    let mut candidates: Vec<Candidate> = (0..count)
+        .filter_map(|index| {
+            let (class, score) = (4..values)
+                .map(|at| (at - 4, value(index, at)))
+                .max_by(|a, b| a.1.total_cmp(&b.1))?;
+            if score < min_score {
+                return None;
+            }
+            let (center_x, center_y) = (value(index, 0), value(index, 1));
+            let (width, height) = (value(index, 2), value(index, 3));
+            Some(Candidate {
+                class,
+                left: center_x - width / 2.0,
+                top: center_y - height / 2.0,
+                right: center_x + width / 2.0,
+                bottom: center_y + height / 2.0,
+                score,
+            })
+        })
+        .collect();
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    candidates.truncate(MAX_CANDIDATES);
+    let mut kept: Vec<Candidate> = Vec::new();
+    for candidate in candidates {
+        if kept.iter().all(|other| {
+            other.class != candidate.class || overlap(other, &candidate) <= MAX_OVERLAP
+        }) {
+            kept.push(candidate);
+        }
+    }
+    Ok(kept)
+}
+
+/// Intersection over union of two boxes.
+fn overlap(a: &Candidate, b: &Candidate) -> f64 {
+    let width = (a.right.min(b.right) - a.left.max(b.left)).max(0.0);
+    let height = (a.bottom.min(b.bottom) - a.top.max(b.top)).max(0.0);
+    let intersection = width * height;
+    let area = |c: &Candidate| (c.right - c.left) * (c.bottom - c.top);
+    let union = area(a) + area(b) - intersection;
+    if union <= 0.0 {
+        0.0
+    } else {
+        intersection / union
+    }
+}
+
+/// `candidate` in capture pixels, named from `labels`.
+fn element(
+    candidate: &Candidate,
+    labels: &[String],
+    scale: f64,
+    screen: &RawImage,
+) -> DetectedElement {
+    let to_screen =
+        |value: f64, limit: u32| (value / scale).round().clamp(0.0, f64::from(limit)) as i64;
+    let (left, top) = (
+        to_screen(candidate.left, screen.width),
+        to_screen(candidate.top, screen.height),
+    );
+    let (right, bottom) = (
+        to_screen(candidate.right, screen.width),
+        to_screen(candidate.bottom, screen.height),
+    );
+    DetectedElement {
+        label: labels
+            .get(candidate.class)
+            .cloned()
+            .unwrap_or_else(|| format!("class {}", candidate.class)),
+        text: None,
+        x: left,
+        y: top,
+        width: right - left,
+        height: bottom - top,
+        confidence: candidate.score.clamp(0.0, 1.0),
+    }
+}
+
+fn filter(
+    elements: Vec<DetectedElement>,
+    label: Option<&str>,
+    min_confidence: f64,
+) -> Vec<DetectedElement> {
+    let label = label.map(str::to_lowercase);
+    let mut elements: Vec<DetectedElement> = elements
+        .into_iter()
+        .filter(|element| element.width > 0 && element.height > 0)
+        .filter(|element| element.confidence >= min_confidence)
+        .filter(|element| {
+            label
+                .as_ref()
+                .is_none_or(|label| element.label.to_lowercase() == *label)
+        })
+        .collect();
+    elements.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    elements
+}
+
+/// Labels each element with the OCR lines centered inside its box. OCR is
+/// best effort: without tesseract the elements keep no text.
+fn with_text(mut elements: Vec<DetectedElement>, image: &Path) -> Vec<DetectedElement> {
+    if elements.is_empty() {
+        return elements;
+    }
+    let Ok(tsv) = find_text::recognize(image) else {
+        return elements;
+    };
+    let lines = find_text::text_lines(&tsv);
+    for element in &mut elements {
+        let inside: Vec<&str> = lines
+            .iter()
+            .filter(|line| {
+                let (x, y) = ((line.left + line.right) / 2, (line.top + line.bottom) / 2);
+                (element.x..element.x + element.width).contains(&x)
+                    && (element.y..element.y + element.height).contains(&y)
+            })
+            .map(|line| line.text.as_str())
+            .collect();
+        if !inside.is_empty() {
+            element.text = Some(inside.join(" "));
+        }
+    }
+    elements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// Three boxes as a `[1, 4 + 2 classes, 3]` output: a button, a weaker
+    /// duplicate of it, and an icon.
+    fn output() -> tract_ndarray::Array3<f32> {
+        tract_ndarray::Array3::from_shape_vec(
+            (1, 6, 3),
+            vec![
+                50.0, 52.0, 200.0, // center x
+                50.0, 50.0, 100.0, // center y
+                20.0, 20.0, 16.0, // width
+                10.0, 10.0, 16.0, // height
+                0.875, 0.75, 0.125, // button
+                0.125, 0.0625, 0.625, // icon
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn decodes_boxes_and_drops_duplicates() {
+        let output = output();
+        let expected = vec![
+            Candidate {
+                class: 0,
+                left: 40.0,
+                top: 45.0,
+                right: 60.0,
+                bottom: 55.0,
+                score: 0.875,
+            },
+            Candidate {
+                class: 1,
+                left: 192.0,
+                top: 92.0,
+                right: 208.0,
+                bottom: 108.0,
+                score: 0.625,
+            },
+        ];
+        assert_eq!(decode(output.view(), 2, MIN_SCORE), Ok(expected.clone()));
+        assert_eq!(
+            decode(output.view().permuted_axes([0, 2, 1]), 2, MIN_SCORE),
+            Ok(expected)
+        );
+        assert_eq!(
+            decode(output.view(), 2, 0.7).map(|candidates| candidates.len()),
+            Ok(1)
+        );
+
+        let screen = RawImage::new(1000, 500, 3, vec![0; 1000 * 500 * 3]).unwrap();
+        let labels = vec!["button".to_string()];
+        let elements: Vec<DetectedElement> = decode(output.view(), 2, MIN_SCORE)
+            .unwrap()
+            .iter()
+            .map(|candidate| element(candidate, &labels, 0.5, &screen))
+            .collect();
+        assert_eq!(
+            elements,
+            vec![
+                DetectedElement {
+                    label: "button".to_string(),
+                    text: None,
+                    x: 80,
+                    y: 90,
+                    width: 40,
+                    height: 20,
+                    confidence: 0.875,
+                },
+                DetectedElement {
+                    label: "class 1".to_string(),
+                    text: None,
+                    x: 384,
+                    y: 184,
+                    width: 32,
+                    height: 32,
+                    confidence: 0.625,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn letterbox_keeps_the_aspect_ratio() {
+        let screen = RawImage::new(200, 100, 3, vec![255; 200 * 100 * 3]).unwrap();
+        let (input, scale) = letterbox(&screen, 64).unwrap();
+        let input = input.to_array_view::<f32>().unwrap();
+        assert_eq!(
+            (
+                input.shape().to_vec(),
+                scale,
+                input[[0, 0, 31, 63]],
+                input[[0, 2, 32, 0]]
+            ),
+            (vec![1, 3, 64, 64], 0.32, 1.0, PAD)
+        );
+    }
+
+    #[test]
+    fn filters_by_label_and_confidence() {
+        let element = |label: &str, width: i64, confidence: f64| DetectedElement {
+            label: label.to_string(),
+            text: None,
+            x: 0,
+            y: 0,
+            width,
+            height: 8,
+            confidence,
+        };
+        let elements = vec![
+            element("icon", 24, 0.62),
+            element("Button", 96, 0.91),
+            element("button", 0, 0.99),
+            element("input", 300, 0.3),
+        ];
+        let labels = |elements: Vec<DetectedElement>| -> Vec<(String, f64)> {
+            elements
+                .into_iter()
+                .map(|element| (element.label, element.confidence))
+                .collect()
+        };
+        assert_eq!(
+            labels(filter(elements.clone(), None, DEFAULT_MIN_CONFIDENCE)),
+            vec![("Button".to_string(), 0.91), ("icon".to_string(), 0.62)]
+        );
+        assert_eq!(
+            labels(filter(elements.clone(), Some("button"), 0.0)),
+            vec![("Button".to_string(), 0.91)]
+        );
+        assert_eq!(
+            labels(filter(elements, Some("input"), 0.2)),
+            vec![("input".to_string(), 0.3)]
+        );
+    }
+}
@@ -0,0 +1,386 @@
+//! Environment checks for the computer-use backend, used by the computex
+//! first-run setup and by the `computer_self_test` tool.
+
+use std::fs;
+use std::path::Path;
+use std::process::Child;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use anyhow::Context;
+
+use super::bundled;
+use super::capture;
+use super::capture_screenshot;
+use super::confinement;
+use super::console;
+use super::display_geometry;
+use super::ensure_display;
+use super::gui_command;
+use super::portal;
+use super::qmp;
+use super::require_command;
+use super::rotation;
+use super::run_command;
+use super::scaling;
+use crate::config::types::ComputerUseBackend;
+use crate::config::types::ComputerUseConfig;
+use crate::config::types::InputProvider;
+
+const SETUP_WINDOW_TITLE: &str = "computex-setup";
+const SETUP_WINDOW_TIMEOUT: Duration = Duration::from_secs(5);
+const SETUP_WINDOW_POLL: Duration = Duration::from_millis(100);
+
+/// Confirms the configured backend is usable and describes it, e.g.
+/// `x11 on display :0 (1920x1080); screenshots via native, input via native`.
+/// Inside a Flatpak or Snap sandbox, the summary or the error also lists
+/// what the sandbox lets through.
+pub fn detect_backend(config: &ComputerUseConfig) -> anyhow::Result<String> {
+    let detected = match config.backend {
+        ComputerUseBackend::X11 => detect_x11(config),
+        ComputerUseBackend::Qemu => detect_qemu(config),
+        ComputerUseBackend::Console => console::describe(config).map_err(anyhow::Error::msg),
+    };
+    let Some(sandbox) = confinement::detect() else {
+        return detected;
+    };
+    let capabilities = confinement::capabilities(&sandbox, config);
+    match detected {
+        Ok(summary) => Ok(format!("{summary}; {capabilities}")),
+        Err(err) => Err(anyhow::anyhow!("{err}; {capabilities}")),
+    }
+}
+
+fn detect_x11(config: &ComputerUseConfig) -> anyhow::Result<String> {
+    ensure_display(config)?;
+    let xdotool = require_command("xdotool")?;
+    let screenshots = config
+        .screenshot_providers
+        .iter()
+        .find(|provider| capture::available(**provider, config))
+        .map(|provider| capture::provider_name(*provider))
+        .context(
+            "no provider in computer_use.screenshot_providers is available; install one with `sudo apt-get install -y imagemagick`",
+        )?;
+    let input = config
+        .input_providers
+        .iter()
+        .find(|provider| match provider {
+            InputProvider::Native => config.persistent_input,
+            InputProvider::Bundled => bundled::helper().is_some(),
+            InputProvider::Xdotool => true,
+            InputProvider::Ydotool => which::which("ydotool").is_ok(),
+            InputProvider::Portal => portal::version(portal::REMOTE_DESKTOP).is_some(),
+            InputProvider::Qmp => qmp::version(config).is_ok(),
+        })
+        .map(|provider| match provider {
+            InputProvider::Native => "native",
+            InputProvider::Bundled => "bundled",
+            InputProvider::Xdotool => "xdotool",
+            InputProvider::Ydotool => "ydotool",
+            InputProvider::Portal => "portal",
+            InputProvider::Qmp => "qmp",
+        })
+        .context("no provider in computer_use.input_providers is available")?;
+    let (width, height) = display_geometry(&xdotool, config)?;
+    let display = config
+        .display
+        .clone()
+        .or_else(|| std::env::var("DISPLAY").ok())
+        .unwrap_or_default();
+    let mut summary = format!(
+        "x11 on display {display} ({width}x{height}); screenshots via {screenshots}, input via {input}"
+    );
+    let outputs = scaling::active_outputs(config);
+    let scaling = scaling::describe(&scaling::monitors(config, &outputs));
+    for line in scaling.into_iter().chain(rotation::describe(&outputs)) {
+        summary.push_str("; ");
+        summary.push_str(&line);
+    }
+    Ok(summary)
+}
+
+fn detect_qemu(config: &ComputerUseConfig) -> anyhow::Result<String> {
+    let version = qmp::version(config).map_err(anyhow::Error::msg)?;
+    let (width, height) = qmp::screen_size(config).map_err(anyhow::Error::msg)?;
+    let socket = config
+        .qmp_socket
+        .as_deref()
+        .unwrap_or(Path::new(""))
+        .display();
+    Ok(format!(
+        "qemu VM at {socket} ({version}, {width}x{height}); screenshots and input via QMP"
+    ))
+}
+
+/// Opens a throwaway `xmessage` window, clicks inside it, and captures a
+/// screenshot, returning a one-line summary of the round trip. A VM gets a
+/// Shift press instead, since nothing on this machine can open a window in
+/// it.
+pub fn round_trip_check(config: &ComputerUseConfig) -> anyhow::Result<String> {
+    let started = Instant::now();
+    if config.backend == ComputerUseBackend::Console {
+        let console = console::describe(config).map_err(anyhow::Error::msg)?;
+        let elapsed = started.elapsed().as_millis();
+        return Ok(format!("{console}; answered in {elapsed} ms"));
+    }
+    if config.backend == ComputerUseBackend::Qemu {
+        send_qmp(config, &["key", "shift"])?;
+        let screenshot = capture_screenshot(config)?;
+        let _ = fs::remove_file(&screenshot);
+        let elapsed = started.elapsed().as_millis();
+        return Ok(format!(
+            "pressed Shift in the VM and captured its console in {elapsed} ms"
+        ));
+    }
+    let xdotool = require_command("xdotool")?;
+    let xmessage = require_command("xmessage")?;
+    let _window = SetupWindow(
+        gui_command(&xmessage, config)
+            .args(["-title", SETUP_WINDOW_TITLE, "-center"])
+            .arg("computex setup: this window closes automatically")
+            .spawn()
+            .context("failed to open the setup window with xmessage")?,
+    );
+
+    let window_id = wait_for_window(&xdotool, config)?;
+    let (x, y, width, height) = window_geometry(&xdotool, &window_id, config)?;
+    // Aim at the message text in the upper third, away from the dismiss button.
+    let click_x = x + width / 2;
+    let click_y = y + height / 3;
+    run_command(
+        &xdotool,
+        &[
+            "mousemove".to_string(),
+            "--sync".to_string(),
+            click_x.to_string(),
+            click_y.to_string(),
+            "click".to_string(),
+            "1".to_string(),
+        ],
+        config,
+    )?;
+
+    let screenshot = capture_screenshot(config)?;
+    let _ = fs::remove_file(&screenshot);
+    let elapsed = started.elapsed().as_millis();
+    Ok(format!(
+        "clicked a temporary window at {click_x},{click_y} and captured a screenshot in {elapsed} ms"
+    ))
+}
+
+/// Outcome of a single `computer_self_test` step.
+#[derive(Debug)]
+pub(super) struct SelfTestStep {
+    name: &'static str,
+    elapsed: Duration,
+    result: Result<String, String>,
+}
+
+/// Runs the `computer_self_test` checks: backend detection, a screenshot, a
+/// cursor move to the top-left corner (restored afterwards), and a no-op
+/// Shift press. Later steps still run when earlier ones fail so the report
+/// lists every problem at once.
+pub(super) fn self_test(config: &ComputerUseConfig) -> Vec<SelfTestStep> {
+    if config.backend == ComputerUseBackend::Console {
+        // Reaching the console reads it, and it has no pointer or lone
+        // modifier keys to try.
+        return vec![timed_step("backend", || detect_backend(config))];
+    }
+    vec![
+        timed_step("backend", || detect_backend(config)),
+        timed_step("screenshot", || {
+            let path = capture_screenshot(config)?;
+            let _ = fs::remove_file(&path);
+            Ok(String::new())
+        }),
+        timed_step("cursor move", || {
+            // The VM's pointer position can't be read back to restore it.
+            if config.backend == ComputerUseBackend::Qemu {
+                send_qmp(config, &["mousemove", "0", "0"])?;
+                return Ok("moved to 0,0".to_string());
+            }
+            let xdotool = require_command("xdotool")?;
+            let original = mouse_location(&xdotool, config)?;
+            let corner = ["mousemove", "--sync", "0", "0"].map(str::to_string);
+            run_command(&xdotool, &corner, config)?;
+            let (x, y) = original;
+            let restore = ["mousemove".to_string(), x.to_string(), y.to_string()];
+            run_command(&xdotool, &restore, config)?;
+            Ok(format!("moved to 0,0 and back to {x},{y}"))
+        }),
+        timed_step("key (shift)", || {
+            if config.backend == ComputerUseBackend::Qemu {
+                send_qmp(config, &["key", "shift"])?;
+                return Ok(String::new());
+            }
+            let xdotool = require_command("xdotool")?;
+            run_command(&xdotool, &["key".to_string(), "shift".to_string()], config)?;
+            Ok(String::new())
+        }),
+    ]
+}
+
+/// Renders self-test steps as a short report for the model.
+pub(super) fn format_self_test(steps: &[SelfTestStep]) -> String {
+    let passed = steps.iter().filter(|step| step.result.is_ok()).count();
+    let total = steps.len();
+    let mut lines = vec![format!(
+        "computer self-test: {passed}/{total} checks passed"
+    )];
+    for step in steps {
+        let name = step.name;
+        let ms = step.elapsed.as_millis();
+        lines.push(match &step.result {
+            Ok(detail) if detail.is_empty() => format!("- {name}: ok in {ms} ms"),
+            Ok(detail) => format!("- {name}: ok in {ms} ms ({detail})"),
+            Err(err) => format!("- {name}: failed after {ms} ms: {err}"),
+        });
+    }
+    lines.join("\n")
+}
+
+pub(super) fn self_test_passed(steps: &[SelfTestStep]) -> bool {
+    steps.iter().all(|step| step.result.is_ok())
+}
+
+fn timed_step(name: &'static str, step: impl FnOnce() -> anyhow::Result<String>) -> SelfTestStep {
+    let started = Instant::now();
+    let result = step().map_err(|err| err.to_string());
+    SelfTestStep {
+        name,
+        elapsed: started.elapsed(),
+        result,
+    }
+}
+
+fn send_qmp(config: &ComputerUseConfig, args: &[&str]) -> anyhow::Result<()> {
+    let args: Vec<String> = args.iter().map(|arg| (*arg).to_string()).collect();
+    qmp::send(config, &args).context("QMP can't send this input")??;
+    Ok(())
+}
+
+fn mouse_location(xdotool: &Path, config: &ComputerUseConfig) -> anyhow::Result<(i64, i64)> {
+    let output = gui_command(xdotool, config)
+        .args(["getmouselocation", "--shell"])
+        .output()
+        .context("failed to run xdotool getmouselocation")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("xdotool getmouselocation failed: {stderr}");
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let x = shell_field(&stdout, "X");
+    let y = shell_field(&stdout, "Y");
+    x.zip(y)
+        .context("xdotool getmouselocation returned unexpected output")
+}
+
+/// Kills the sacrificial window when the check finishes, even on error.
+struct SetupWindow(Child);
+
+impl Drop for SetupWindow {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+fn wait_for_window(xdotool: &Path, config: &ComputerUseConfig) -> anyhow::Result<String> {
+    let deadline = Instant::now() + SETUP_WINDOW_TIMEOUT;
+    loop {
+        let output = gui_command(xdotool, config)
+            .args(["search", "--onlyvisible", "--name"])
+            .arg(format!("^{SETUP_WINDOW_TITLE}$"))
+            .output()
+            .context("failed to run xdotool search")?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        if let Some(id) = stdout.lines().next().map(str::trim)
+            && !id.is_empty()
+        {
+            return Ok(id.to_string());
+        }
+        if Instant::now() >= deadline {
+            anyhow::bail!("the setup window did not appear within {SETUP_WINDOW_TIMEOUT:?}");
+        }
+        thread::sleep(SETUP_WINDOW_POLL);
+    }
+}
+
+fn window_geometry(
+    xdotool: &Path,
+    window_id: &str,
+    config: &ComputerUseConfig,
+) -> anyhow::Result<(i64, i64, i64, i64)> {
+    let output = gui_command(xdotool, config)
+        .args(["getwindowgeometry", "--shell", window_id])
+        .output()
+        .context("failed to run xdotool getwindowgeometry")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("xdotool getwindowgeometry failed: {stderr}");
+    }
+    parse_shell_geometry(&String::from_utf8_lossy(&output.stdout))
+        .context("xdotool getwindowgeometry returned unexpected output")
+}
+
+/// Parses `xdotool getwindowgeometry --shell` output into `(x, y, width, height)`.
+fn parse_shell_geometry(output: &str) -> Option<(i64, i64, i64, i64)> {
+    Some((
+        shell_field(output, "X")?,
+        shell_field(output, "Y")?,
+        shell_field(output, "WIDTH")?,
+        shell_field(output, "HEIGHT")?,
+    ))
+}
+
+/// Reads a numeric `KEY=value` line from xdotool `--shell` output.
+fn shell_field(output: &str, name: &str) -> Option<i64> {
+    output.lines().find_map(|line| {
+        let (key, value) = line.split_once('=')?;
+        (key.trim() == name).then(|| value.trim().parse::<i64>().ok())?
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_shell_geometry() {
+        let output = "WINDOW=123\nX=100\nY=50\nWIDTH=300\nHEIGHT=120\nSCREEN=0\n";
+        assert_eq!(parse_shell_geometry(output), Some((100, 50, 300, 120)));
+        assert_eq!(parse_shell_geometry("X=1\nY=2\n"), None);
+    }
+
+    #[test]
+    fn formats_self_test_report() {
+        let steps = vec![
+            SelfTestStep {
+                name: "backend",
+                elapsed: Duration::from_millis(4),
+                result: Ok("x11 on display :0 (1920x1080)".to_string()),
+            },
+            SelfTestStep {
+                name: "screenshot",
+                elapsed: Duration::from_millis(120),
+                result: Ok(String::new()),
+            },
+            SelfTestStep {
+                name: "key (shift)",
+                elapsed: Duration::from_millis(2),
+                result: Err("required command `xdotool` not found".to_string()),
+            },
+        ];
+        assert!(!self_test_passed(&steps));
+        assert_eq!(
+            format_self_test(&steps),
+            "computer self-test: 2/3 checks passed\n\
+             - backend: ok in 4 ms (x11 on display :0 (1920x1080))\n\
+             - screenshot: ok in 120 ms\n\
+             - key (shift): failed after 2 ms: required command `xdotool` not found"
+        );
+    }
+}
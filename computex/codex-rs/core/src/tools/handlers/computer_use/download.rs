@@ -0,0 +1,224 @@
+//! Waiting for browser downloads, for `computer_wait_download`.
+//!
+//! Browsers write a download under a temporary name (`.crdownload` for
+//! Chromium, `.part` for Firefox) and rename it when it finishes, so the
+//! download directory is watched with inotify and rescanned on every change
+//! instead of screenshotting the download shelf. A file counts as finished
+//! once it matches the pattern, has no partial name or partial sibling, is
+//! new or changed since the call started, and has stopped growing.
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+
+use notify::RecursiveMode;
+use notify::Watcher;
+use wildmatch::WildMatch;
+
+use crate::function_tool::FunctionCallError;
+
+/// Default and maximum wait.
+pub(super) const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+const MAX_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// How long a finished file's size must hold still.
+const STABLE_FOR: Duration = Duration::from_millis(500);
+
+/// Rescan interval while no events arrive, so stability is noticed.
+const RESCAN_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Suffixes browsers give files still being downloaded.
+const PARTIAL_SUFFIXES: &[&str] = &[".crdownload", ".part", ".download", ".partial"];
+
+/// Waits for a download matching `pattern` (default `*`) to finish in `dir`
+/// (default the XDG download directory). Returns the tool result.
+pub(super) async fn wait(
+    dir: Option<&str>,
+    pattern: Option<&str>,
+    timeout_seconds: Option<u64>,
+) -> Result<String, FunctionCallError> {
+    let dir = download_dir(dir).map_err(FunctionCallError::RespondToModel)?;
+    let pattern = pattern.unwrap_or("*").to_string();
+    let timeout = timeout_seconds
+        .map_or(DEFAULT_TIMEOUT, Duration::from_secs)
+        .min(MAX_TIMEOUT);
+    let (path, size) =
+        tokio::task::spawn_blocking(move || watch(&dir, &WildMatch::new(&pattern), timeout))
+            .await
+            .map_err(|err| {
+                FunctionCallError::RespondToModel(format!("download watcher failed: {err}"))
+            })?
+            .map_err(FunctionCallError::RespondToModel)?;
+    Ok(format!("downloaded {} ({size} bytes)", path.display()))
+}
+
+fn download_dir(dir: Option<&str>) -> Result<PathBuf, String> {
+    let dir = match dir {
+        Some(dir) => PathBuf::from(dir.trim()),
+        None => dirs::download_dir()
+            .or_else(|| dirs::home_dir().map(|home| home.join("Downloads")))
+            .ok_or("could not determine the download directory; pass dir")?,
+    };
+    if !dir.is_absolute() {
+        return Err(format!("dir must be absolute, got `{}`", dir.display()));
+    }
+    if !dir.is_dir() {
+        return Err(format!("`{}` is not a directory", dir.display()));
+    }
+    Ok(dir)
+}
+
+fn watch(dir: &Path, pattern: &WildMatch, timeout: Duration) -> Result<(PathBuf, u64), String> {
+    let baseline = modification_times(dir)?;
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |res| {
+        let _ = tx.send(res);
+    })
+    .map_err(|err| format!("failed to watch {}: {err}", dir.display()))?;
+    watcher
+        .watch(dir, RecursiveMode::NonRecursive)
+        .map_err(|err| format!("failed to watch {}: {err}", dir.display()))?;
+
+    let deadline = Instant::now() + timeout;
+    // Size of each candidate and when it was first seen at that size.
+    let mut sizes: HashMap<PathBuf, (u64, Instant)> = HashMap::new();
+    loop {
+        for (path, size) in finished_candidates(dir, pattern, &baseline)? {
+            match sizes.get(&path) {
+                Some((seen, since)) if *seen == size && since.elapsed() >= STABLE_FOR => {
+                    return Ok((path, size));
+                }
+                Some((seen, _)) if *seen == size => {}
+                _ => {
+                    sizes.insert(path, (size, Instant::now()));
+                }
+            }
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            break;
+        }
+        if let Ok(Err(err)) = rx.recv_timeout(RESCAN_INTERVAL.min(deadline - now)) {
+            return Err(format!("watching {} failed: {err}", dir.display()));
+        }
+    }
+
+    let in_progress = partial_files(dir);
+    let hint = if in_progress.is_empty() {
+        "nothing is being downloaded there".to_string()
+    } else {
+        format!("still in progress: {}", in_progress.join(", "))
+    };
+    Err(format!(
+        "no finished download matching `{}` appeared in {} within {} s; {hint}",
+        pattern,
+        dir.display(),
+        timeout.as_secs()
+    ))
+}
+
+fn modification_times(dir: &Path) -> Result<HashMap<OsString, SystemTime>, String> {
+    let entries =
+        fs::read_dir(dir).map_err(|err| format!("failed to read {}: {err}", dir.display()))?;
+    Ok(entries
+        .flatten()
+        .filter_map(|entry| {
+            let modified = entry.metadata().ok()?.modified().ok()?;
+            Some((entry.file_name(), modified))
+        })
+        .collect())
+}
+
+/// Non-empty files in `dir` matching `pattern` that look finished and are
+/// new or modified relative to `baseline`, with their sizes.
+fn finished_candidates(
+    dir: &Path,
+    pattern: &WildMatch,
+    baseline: &HashMap<OsString, SystemTime>,
+) -> Result<Vec<(PathBuf, u64)>, String> {
+    let entries =
+        fs::read_dir(dir).map_err(|err| format!("failed to read {}: {err}", dir.display()))?;
+    Ok(entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let name_str = name.to_str()?;
+            if is_partial(name_str) || !pattern.matches(name_str) {
+                return None;
+            }
+            let metadata = entry.metadata().ok()?;
+            if !metadata.is_file() || metadata.len() == 0 {
+                return None;
+            }
+            if baseline.get(&name) == metadata.modified().ok().as_ref() {
+                return None;
+            }
+            // Firefox keeps an empty placeholder under the final name, and
+            // the real data in `<name>.part`, until the download finishes.
+            if PARTIAL_SUFFIXES
+                .iter()
+                .any(|suffix| dir.join(format!("{name_str}{suffix}")).exists())
+            {
+                return None;
+            }
+            Some((entry.path(), metadata.len()))
+        })
+        .collect())
+}
+
+fn partial_files(dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| is_partial(name))
+        .collect();
+    names.sort();
+    names
+}
+
+fn is_partial(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    PARTIAL_SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    #[test]
+    fn only_new_finished_files_are_candidates() -> std::io::Result<()> {
+        let dir = tempdir()?;
+        fs::write(dir.path().join("old.pdf"), b"old")?;
+        let baseline = modification_times(dir.path()).map_err(std::io::Error::other)?;
+        fs::write(dir.path().join("report.pdf"), b"")?;
+        fs::write(dir.path().join("report.pdf.part"), b"data")?;
+        fs::write(dir.path().join("Unconfirmed 1.crdownload"), b"data")?;
+        fs::write(dir.path().join("notes.txt"), b"data")?;
+        let pattern = WildMatch::new("*.pdf");
+        let candidates = || finished_candidates(dir.path(), &pattern, &baseline);
+        assert_eq!(candidates().map_err(std::io::Error::other)?, Vec::new());
+
+        fs::remove_file(dir.path().join("report.pdf.part"))?;
+        fs::write(dir.path().join("report.pdf"), b"data")?;
+        assert_eq!(
+            candidates().map_err(std::io::Error::other)?,
+            vec![(dir.path().join("report.pdf"), 4)]
+        );
+        assert_eq!(
+            partial_files(dir.path()),
+            vec!["Unconfirmed 1.crdownload".to_string()]
+        );
+        Ok(())
+    }
+}
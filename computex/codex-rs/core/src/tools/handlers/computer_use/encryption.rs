@@ -0,0 +1,257 @@
+//! At-rest encryption of captures, for `computer_use.encrypt_captures`.
+//!
+//! Desktop screenshots routinely show mail, chats, and credentials. With the
+//! option on, screenshots kept in `screenshot_dir` are written as `.enc`
+//! files and the images stored in session rollouts become encrypted data
+//! URLs. Both use ChaCha20-Poly1305 with a key that is generated on first use
+//! and kept in the OS keyring, so a copied session directory is useless
+//! without the user's login. Resuming a session and the `computex` commands
+//! that read sessions decrypt transparently; `computex decrypt` restores a
+//! kept screenshot.
+
+use std::sync::OnceLock;
+
+use anyhow::Context;
+use base64::Engine;
+use codex_keyring_store::DefaultKeyringStore;
+use codex_keyring_store::KeyringStore;
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::ResponseItem;
+use codex_protocol::protocol::RolloutItem;
+use rand::RngCore;
+use ring::aead::Aad;
+use ring::aead::CHACHA20_POLY1305;
+use ring::aead::LessSafeKey;
+use ring::aead::NONCE_LEN;
+use ring::aead::Nonce;
+use ring::aead::UnboundKey;
+
+const KEYRING_SERVICE: &str = "Computex Computer Use";
+const KEYRING_ACCOUNT: &str = "capture-key";
+
+/// Starts every encrypted file, followed by the nonce and the sealed data.
+const MAGIC: &[u8] = b"CPXENC1\n";
+
+/// Extension appended to kept screenshots that are encrypted.
+pub const ENCRYPTED_EXTENSION: &str = "enc";
+
+/// Prefix of an encrypted image in a rollout. The sealed data is the
+/// original data URL, so its media type survives.
+const DATA_URL_PREFIX: &str = "data:application/x-computex-encrypted;base64,";
+
+const KEY_LEN: usize = 32;
+
+/// The key, once read from or stored in the keyring by this process.
+static KEY: OnceLock<CaptureKey> = OnceLock::new();
+
+/// The capture key, as read from the keyring by [`capture_key`].
+#[derive(Clone, Copy)]
+pub struct CaptureKey([u8; KEY_LEN]);
+
+/// Encrypts `plaintext` with the capture key, creating the key if the
+/// keyring has none.
+pub fn encrypt(plaintext: &[u8]) -> anyhow::Result<Vec<u8>> {
+    seal(&capture_key(true)?.0, plaintext)
+}
+
+/// Decrypts data written by [`encrypt`].
+pub fn decrypt(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    open(&capture_key(false)?.0, data)
+}
+
+/// Whether `data` was written by [`encrypt`].
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// Replaces the images in a rollout item with encrypted data URLs. An image
+/// that can't be encrypted, or any image when the key couldn't be loaded, is
+/// left out rather than stored in the clear.
+pub(crate) fn encrypt_images(item: &mut RolloutItem, key: &anyhow::Result<CaptureKey>) {
+    let RolloutItem::ResponseItem(ResponseItem::Message { content, .. }) = item else {
+        return;
+    };
+    for content in content.iter_mut() {
+        let ContentItem::InputImage { image_url } = content else {
+            continue;
+        };
+        if image_url.starts_with(DATA_URL_PREFIX) {
+            continue;
+        }
+        let sealed = match key {
+            Ok(key) => seal(&key.0, image_url.as_bytes()),
+            Err(err) => Err(anyhow::anyhow!("{err:#}")),
+        };
+        match sealed {
+            Ok(sealed) => {
+                *image_url = format!(
+                    "{DATA_URL_PREFIX}{}",
+                    base64::engine::general_purpose::STANDARD.encode(sealed)
+                );
+            }
+            Err(err) => {
+                tracing::warn!("leaving a screenshot out of the rollout: {err:#}");
+                *content = ContentItem::InputText {
+                    text: "[screenshot not stored: encryption failed]".to_string(),
+                };
+            }
+        }
+    }
+}
+
+/// Whether a rollout item holds images [`encrypt_images`] sealed, so the key
+/// has to be loaded to read it.
+pub fn has_encrypted_images(item: &RolloutItem) -> bool {
+    let RolloutItem::ResponseItem(ResponseItem::Message { content, .. }) = item else {
+        return false;
+    };
+    content.iter().any(|content| {
+        matches!(content, ContentItem::InputImage { image_url } if image_url.starts_with(DATA_URL_PREFIX))
+    })
+}
+
+/// Restores the images [`encrypt_images`] sealed. An image that can't be
+/// decrypted, or any image when the key couldn't be loaded, is replaced by
+/// a note saying so.
+pub fn decrypt_images(item: &mut RolloutItem, key: &anyhow::Result<CaptureKey>) {
+    let RolloutItem::ResponseItem(ResponseItem::Message { content, .. }) = item else {
+        return;
+    };
+    for content in content.iter_mut() {
+        let ContentItem::InputImage { image_url } = content else {
+            continue;
+        };
+        let Some(data) = image_url.strip_prefix(DATA_URL_PREFIX) else {
+            continue;
+        };
+        let url = match key {
+            Ok(key) => decrypt_data_url(key, data),
+            Err(err) => Err(anyhow::anyhow!("{err:#}")),
+        };
+        match url {
+            Ok(url) => *image_url = url,
+            Err(err) => {
+                *content = ContentItem::InputText {
+                    text: format!("[encrypted screenshot could not be decrypted: {err:#}]"),
+                };
+            }
+        }
+    }
+}
+
+fn decrypt_data_url(key: &CaptureKey, data: &str) -> anyhow::Result<String> {
+    let sealed = base64::engine::general_purpose::STANDARD
+        .decode(data)
+        .context("invalid encrypted image data")?;
+    String::from_utf8(open(&key.0, &sealed)?).context("decrypted image is not a data URL")
+}
+
+/// Reads the capture key from the keyring, creating it when `create` is set
+/// and there is none. Keyring access blocks, so async callers run this on a
+/// blocking thread once and pass the key along; later calls in the process
+/// return the cached key.
+pub fn capture_key(create: bool) -> anyhow::Result<CaptureKey> {
+    if let Some(key) = KEY.get() {
+        return Ok(*key);
+    }
+    let store = DefaultKeyringStore;
+    let stored = store
+        .load(KEYRING_SERVICE, KEYRING_ACCOUNT)
+        .map_err(|err| anyhow::anyhow!("failed to read the capture key from the keyring: {err}"))?;
+    let key = match stored {
+        Some(hex) => CaptureKey(parse_key(&hex)?),
+        None if create => {
+            let mut key = [0u8; KEY_LEN];
+            rand::rng().fill_bytes(&mut key);
+            store
+                .save(KEYRING_SERVICE, KEYRING_ACCOUNT, &to_hex(&key))
+                .map_err(|err| {
+                    anyhow::anyhow!("failed to save the capture key to the keyring: {err}")
+                })?;
+            CaptureKey(key)
+        }
+        None => anyhow::bail!("no capture encryption key is stored in the keyring"),
+    };
+    Ok(*KEY.get_or_init(|| key))
+}
+
+fn parse_key(hex: &str) -> anyhow::Result<[u8; KEY_LEN]> {
+    let hex = hex.trim();
+    let mut key = [0u8; KEY_LEN];
+    if hex.len() != KEY_LEN * 2 || !hex.is_ascii() {
+        anyhow::bail!("the capture key in the keyring is malformed");
+    }
+    for (byte, pair) in key.iter_mut().zip(hex.as_bytes().chunks_exact(2)) {
+        let pair = std::str::from_utf8(pair)?;
+        *byte = u8::from_str_radix(pair, 16)
+            .map_err(|_| anyhow::anyhow!("the capture key in the keyring is malformed"))?;
+    }
+    Ok(key)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn aead_key(key: &[u8; KEY_LEN]) -> anyhow::Result<LessSafeKey> {
+    let key = UnboundKey::new(&CHACHA20_POLY1305, key)
+        .map_err(|_| anyhow::anyhow!("invalid capture key"))?;
+    Ok(LessSafeKey::new(key))
+}
+
+fn seal(key: &[u8; KEY_LEN], plaintext: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::rng().fill_bytes(&mut nonce);
+    let mut sealed = plaintext.to_vec();
+    aead_key(key)?
+        .seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(MAGIC),
+            &mut sealed,
+        )
+        .map_err(|_| anyhow::anyhow!("failed to encrypt the capture"))?;
+    let mut data = Vec::with_capacity(MAGIC.len() + NONCE_LEN + sealed.len());
+    data.extend_from_slice(MAGIC);
+    data.extend_from_slice(&nonce);
+    data.extend_from_slice(&sealed);
+    Ok(data)
+}
+
+fn open(key: &[u8; KEY_LEN], data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let Some(rest) = data.strip_prefix(MAGIC) else {
+        anyhow::bail!("not an encrypted capture");
+    };
+    if rest.len() < NONCE_LEN {
+        anyhow::bail!("the encrypted capture is truncated");
+    }
+    let (nonce, sealed) = rest.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce)
+        .map_err(|_| anyhow::anyhow!("the encrypted capture is truncated"))?;
+    let mut sealed = sealed.to_vec();
+    let plaintext = aead_key(key)?
+        .open_in_place(nonce, Aad::from(MAGIC), &mut sealed)
+        .map_err(|_| {
+            anyhow::anyhow!("the capture was encrypted with a different key or is corrupted")
+        })?;
+    Ok(plaintext.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn sealed_captures_open_only_with_their_key() -> anyhow::Result<()> {
+        let key = [7u8; KEY_LEN];
+        let sealed = seal(&key, b"\x89PNG pixels")?;
+        assert!(is_encrypted(&sealed));
+        assert!(!sealed.windows(6).any(|window| window == b"pixels"));
+        assert_eq!(open(&key, &sealed)?, b"\x89PNG pixels".to_vec());
+        assert!(open(&[8u8; KEY_LEN], &sealed).is_err());
+        assert!(open(&key, &sealed[..MAGIC.len() + 4]).is_err());
+        assert_eq!(parse_key(&to_hex(&key))?, key);
+        assert!(parse_key("abc").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,327 @@
+//! Captures attached to failed GUI actions, for
+//! `computer_use.failure_screenshots`.
+//!
+//! An error such as "xdotool failed" or "the typed text didn't match" leaves
+//! the model guessing at what the screen showed. When an input tool fails, a
+//! small capture of the area it acted on (a box around the point it aimed
+//! at, or a reduced view of the whole screen) is attached to the error, so
+//! the next step starts from what is actually there. Clicks are checked too:
+//! the box around the click is captured before and after, and a click that
+//! changed nothing there comes back with the capture and a warning that it
+//! may have missed.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use codex_utils_image::compare::compare;
+use codex_utils_image::screenshot::RawImage;
+use serde_json::Value;
+
+use super::capture_root_window;
+use super::compare::DEFAULT_TOLERANCE;
+use super::coordinates::map_point;
+use super::deferred::attach_images;
+use super::detail::LOW_DETAIL;
+use super::display_geometry;
+use super::record_attached_image;
+use super::release_screenshots;
+use super::require_command;
+use super::reserve_screenshots;
+use super::screenshot_budget_note;
+use super::sticky_target;
+use crate::codex::Session;
+use crate::config::types::ComputerUseConfig;
+use crate::config::types::Viewport;
+use crate::function_tool::FunctionCallError;
+use crate::tools::context::ToolOutput;
+
+/// Size of the box captured around a target point, in screen pixels.
+const BOX_WIDTH: u32 = 320;
+const BOX_HEIGHT: u32 = 240;
+
+/// Tools whose target box is compared before and after the action.
+const CHECKED_TOOLS: &[&str] = &["computer_click"];
+
+/// How long a click gets to show its effect before the box is compared.
+const SETTLE: Duration = Duration::from_millis(300);
+
+/// Share of the box that must match for it to count as unchanged, which
+/// leaves room for a blinking caret.
+const UNCHANGED_SIMILARITY: f64 = 0.999;
+
+/// What is known about an action's target before it runs.
+pub(super) struct Evidence {
+    config: ComputerUseConfig,
+    /// Box around the target point, in absolute screen pixels.
+    target: Option<Viewport>,
+    /// The box before a checked tool ran.
+    before: Option<RawImage>,
+}
+
+impl Evidence {
+    /// Locates the target of `tool_name` and, for clicks, captures it.
+    pub(super) async fn start(
+        session: &Session,
+        config: &ComputerUseConfig,
+        tool_name: &str,
+        arguments: &str,
+    ) -> Self {
+        let config = scoped(session, config).await;
+        let target = target_box(&config, arguments);
+        let before = target
+            .filter(|_| CHECKED_TOOLS.contains(&tool_name))
+            .and_then(|target| capture_raw(&config, target));
+        Self {
+            config,
+            target,
+            before,
+        }
+    }
+
+    /// Attaches a capture to a failed `result`, or to a checked click that
+    /// left its target unchanged. Captures count against `turn_id`'s
+    /// screenshot budget; without room the model gets the note alone.
+    pub(super) async fn finish(
+        self,
+        session: &Session,
+        turn_id: &str,
+        result: Result<ToolOutput, FunctionCallError>,
+    ) -> Result<ToolOutput, FunctionCallError> {
+        match result {
+            Err(FunctionCallError::RespondToModel(message)) => {
+                let note = self.attach_capture(session, turn_id).await;
+                Err(FunctionCallError::RespondToModel(format!(
+                    "{message}\n{note}"
+                )))
+            }
+            Ok(ToolOutput::Function {
+                content,
+                content_items,
+                success,
+            }) if self.before.is_some() => {
+                let content = match self.check_click(session, turn_id).await {
+                    Some(note) => format!("{content}\n{note}"),
+                    None => content,
+                };
+                Ok(ToolOutput::Function {
+                    content,
+                    content_items,
+                    success,
+                })
+            }
+            other => other,
+        }
+    }
+
+    /// A warning with the attached capture when the click's box looks the
+    /// same as before it.
+    async fn check_click(&self, session: &Session, turn_id: &str) -> Option<String> {
+        let (before, target) = (self.before.as_ref()?, self.target?);
+        tokio::time::sleep(SETTLE).await;
+        let path = capture_root_window(&self.config, Some(target.to_string()), None).ok()?;
+        let after = RawImage::open(&path).ok();
+        if after.is_none_or(|after| !unchanged(before, &after)) {
+            let _ = std::fs::remove_file(&path);
+            return None;
+        }
+        let warning = format!(
+            "warning: nothing changed around the click within {} ms, so it may have missed its target or the app may be busy",
+            SETTLE.as_millis()
+        );
+        let budget = reserve_screenshots(
+            &mut *session.services.computer_use.lock().await,
+            turn_id,
+            &self.config,
+            1,
+        );
+        if let Some((0, _, _)) = budget {
+            let _ = std::fs::remove_file(&path);
+            return Some(format!("{warning}; {}", self.budget_used_up()));
+        }
+        attach(session, path).await;
+        Some(format!(
+            "{warning}; attached a capture of {}{}",
+            self.area(),
+            budget_suffix(budget)
+        ))
+    }
+
+    /// Captures the target after a failure and attaches it, when the
+    /// screenshot budget has room, and says what was attached.
+    async fn attach_capture(&self, session: &Session, turn_id: &str) -> String {
+        let budget = reserve_screenshots(
+            &mut *session.services.computer_use.lock().await,
+            turn_id,
+            &self.config,
+            1,
+        );
+        if let Some((0, _, _)) = budget {
+            return self.budget_used_up();
+        }
+        match self.capture() {
+            Ok(path) => {
+                attach(session, path).await;
+                format!(
+                    "{}{}",
+                    self.describe("after the failure"),
+                    budget_suffix(budget)
+                )
+            }
+            Err(err) => {
+                release_screenshots(
+                    &mut *session.services.computer_use.lock().await,
+                    turn_id,
+                    budget,
+                );
+                format!("no capture of the target: {err}")
+            }
+        }
+    }
+
+    fn budget_used_up(&self) -> String {
+        format!(
+            "no capture of {} attached: the screenshot budget for this turn is used up",
+            self.area()
+        )
+    }
+
+    fn capture(&self) -> Result<PathBuf, FunctionCallError> {
+        match self.target {
+            Some(target) => capture_root_window(&self.config, Some(target.to_string()), None),
+            None => capture_root_window(&self.config, None, Some(LOW_DETAIL)),
+        }
+    }
+
+    fn describe(&self, when: &str) -> String {
+        format!("attached a capture of {} taken {when}", self.area())
+    }
+
+    fn area(&self) -> String {
+        match self.target {
+            Some(target) => format!(
+                "the {}x{} px area around the target",
+                target.width, target.height
+            ),
+            None => format!("the screen at {LOW_DETAIL}"),
+        }
+    }
+}
+
+/// `config` narrowed to the sticky target window, as the tools see it.
+async fn scoped(session: &Session, config: &ComputerUseConfig) -> ComputerUseConfig {
+    let sticky = session.services.computer_use.lock().await.sticky_target();
+    let resolved = sticky.and_then(|window| {
+        let xdotool = require_command("xdotool").ok()?;
+        sticky_target::resolve(&xdotool, config, &window).ok()
+    });
+    match resolved {
+        Some(target) => sticky_target::scoped_config(config, &target),
+        None => config.clone(),
+    }
+}
+
+/// The box around the point an action aims at: `x`/`y`, or the end of a
+/// drag or swipe. `None` for actions without one.
+pub(super) fn target_box(config: &ComputerUseConfig, arguments: &str) -> Option<Viewport> {
+    let args: Value = serde_json::from_str(arguments).ok()?;
+    let coordinate = |name: &str| args.get(name).and_then(Value::as_f64);
+    let (x, y) = match (coordinate("x"), coordinate("y")) {
+        (Some(x), Some(y)) => (x, y),
+        _ => (coordinate("to_x")?, coordinate("to_y")?),
+    };
+    let xdotool = require_command("xdotool").unwrap_or_else(|_| PathBuf::from("xdotool"));
+    let (width, height) = display_geometry(&xdotool, config).ok()?;
+    let point = map_point(config, x, y, width, height);
+    let area = config.viewport.unwrap_or(Viewport {
+        width: width as u32,
+        height: height as u32,
+        x: 0,
+        y: 0,
+    });
+    box_around(point.screen_x, point.screen_y, area)
+}
+
+/// A `BOX_WIDTH` x `BOX_HEIGHT` box centered on a point, moved inside `area`
+/// and cut down to it where `area` is smaller.
+fn box_around(x: i64, y: i64, area: Viewport) -> Option<Viewport> {
+    let width = BOX_WIDTH.min(area.width);
+    let height = BOX_HEIGHT.min(area.height);
+    if width == 0 || height == 0 {
+        return None;
+    }
+    let place = |center: i64, size: u32, start: u32, span: u32| {
+        let lowest = i64::from(start);
+        let highest = i64::from(start + span - size);
+        (center - i64::from(size / 2)).clamp(lowest, highest) as u32
+    };
+    Some(Viewport {
+        width,
+        height,
+        x: place(x, width, area.x, area.width),
+        y: place(y, height, area.y, area.height),
+    })
+}
+
+fn capture_raw(config: &ComputerUseConfig, target: Viewport) -> Option<RawImage> {
+    let path = capture_root_window(config, Some(target.to_string()), None).ok()?;
+    let image = RawImage::open(&path).ok();
+    let _ = std::fs::remove_file(&path);
+    image
+}
+
+/// Whether two captures of the same box show the same thing.
+fn unchanged(before: &RawImage, after: &RawImage) -> bool {
+    compare(before, after, DEFAULT_TOLERANCE)
+        .is_ok_and(|diff| diff.similarity >= UNCHANGED_SIMILARITY)
+}
+
+/// Attaches the capture at `path` for the model.
+async fn attach(session: &Session, path: PathBuf) {
+    attach_images(session, std::slice::from_ref(&path)).await;
+    record_attached_image(session, &path, false).await;
+}
+
+/// The remaining budget after an attached capture, when there is a budget.
+fn budget_suffix(budget: Option<(u32, u32, u32)>) -> String {
+    budget.map_or(String::new(), |(_, limit, remaining)| {
+        screenshot_budget_note(limit, remaining)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn target_boxes_stay_inside_the_area() {
+        let screen = Viewport {
+            width: 1920,
+            height: 1080,
+            x: 0,
+            y: 0,
+        };
+        let at = |x, y, width, height| Viewport {
+            width,
+            height,
+            x,
+            y,
+        };
+        assert_eq!(box_around(960, 540, screen), Some(at(800, 420, 320, 240)));
+        assert_eq!(box_around(5, 1079, screen), Some(at(0, 840, 320, 240)));
+        assert_eq!(box_around(1919, 0, screen), Some(at(1600, 0, 320, 240)));
+
+        let window = Viewport {
+            width: 200,
+            height: 600,
+            x: 1000,
+            y: 100,
+        };
+        assert_eq!(box_around(1010, 110, window), Some(at(1000, 100, 200, 240)));
+
+        let pixels = |value: u8| vec![value; 4 * 4 * 3];
+        let image = |value| RawImage::new(4, 4, 3, pixels(value)).expect("image");
+        assert!(unchanged(&image(100), &image(110)));
+        assert!(!unchanged(&image(100), &image(200)));
+    }
+}
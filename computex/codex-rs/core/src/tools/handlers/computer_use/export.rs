@@ -0,0 +1,777 @@
+//! Turns the GUI actions recorded in a session rollout into a standalone
+//! PyAutoGUI or Playwright script, for `computex export-script`.
+//!
+//! Only input actions that succeeded are replayed. Rollouts keep each tool's
+//! arguments and result text but not its success flag, so an action counts as
+//! successful when its result starts the way the handler words success.
+//! Screenshots and other observations become comments marking where the
+//! agent looked at the screen. Coordinates stay in the configured model
+//! space and are scaled to the real screen when the script runs.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use codex_protocol::models::ResponseItem;
+use codex_protocol::protocol::RolloutItem;
+use serde::de::DeserializeOwned;
+
+use super::keys;
+use super::schema::ClearFieldArgs;
+use super::schema::ClearMethod;
+use super::schema::ClickArgs;
+use super::schema::CompareArgs;
+use super::schema::DragArgs;
+use super::schema::FindTextArgs;
+use super::schema::FocusArgs;
+use super::schema::HeldAction;
+use super::schema::HeldActionKind;
+use super::schema::KeyArgs;
+use super::schema::LocateArgs;
+use super::schema::LongPressArgs;
+use super::schema::MouseButton;
+use super::schema::PinchArgs;
+use super::schema::ScrollArgs;
+use super::schema::SwipeArgs;
+use super::schema::TypeArgs;
+use super::schema::WithModifiersArgs;
+use super::verbosity::is_terse_ok;
+use crate::config::types::ComputerUseConfig;
+use crate::config::types::CoordinateSpace;
+use crate::config::types::TargetOs;
+
+/// Pause between replayed actions, in milliseconds. The agent took at least
+/// a model turn between actions, so the UI usually had time to settle.
+const STEP_DELAY_MS: u64 = 500;
+
+/// How the result of each replayable tool starts when it succeeded.
+pub(super) const SUCCESS_PREFIXES: &[(&str, &str)] = &[
+    ("computer_click", "clicked at "),
+    ("computer_drag", "dragged from "),
+    ("computer_with_modifiers", "with "),
+    ("computer_scroll", "scrolled "),
+    ("computer_swipe", "swiped from "),
+    ("computer_long_press", "long-pressed at "),
+    ("computer_pinch", "pinched "),
+    ("computer_type", "typed "),
+    ("computer_key", "pressed "),
+    ("computer_clear_field", "cleared "),
+    ("computer_focus_next", "moved focus "),
+    ("computer_focus_prev", "moved focus "),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptFormat {
+    /// Python PyAutoGUI, driving the whole desktop.
+    Pyautogui,
+    /// Python Playwright (sync API), driving a browser page.
+    Playwright,
+}
+
+impl FromStr for ScriptFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "pyautogui" => Ok(ScriptFormat::Pyautogui),
+            "playwright" => Ok(ScriptFormat::Playwright),
+            _ => Err(format!(
+                "unknown script format `{value}` (supported: playwright, pyautogui)"
+            )),
+        }
+    }
+}
+
+/// A generated script and what went into it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportedScript {
+    pub script: String,
+    /// GUI actions replayed by the script.
+    pub actions: usize,
+    /// Recorded GUI actions left out, with the reason.
+    pub skipped: Vec<String>,
+}
+
+/// One replayable step, with points in model space.
+#[derive(Debug, Clone, PartialEq)]
+enum Op {
+    Move(f64, f64),
+    Click {
+        x: f64,
+        y: f64,
+        button: MouseButton,
+        count: u32,
+    },
+    Down(MouseButton),
+    Up(MouseButton),
+    Wheel {
+        up: bool,
+        ticks: u32,
+    },
+    KeyDown(String),
+    KeyUp(String),
+    /// A chord of xdotool key names.
+    Press(Vec<String>),
+    Type(String),
+    Sleep(u64),
+    Comment(String),
+}
+
+/// Builds a `format` script from the GUI actions in `items`. `config`
+/// supplies the coordinate space and target OS the session used.
+pub fn export_script(
+    items: &[RolloutItem],
+    format: ScriptFormat,
+    config: &ComputerUseConfig,
+) -> ExportedScript {
+    let mut outputs: HashMap<&str, &str> = HashMap::new();
+    for item in items {
+        if let RolloutItem::ResponseItem(ResponseItem::FunctionCallOutput { call_id, output }) =
+            item
+        {
+            outputs.insert(call_id, &output.content);
+        }
+    }
+
+    let mut steps: Vec<Vec<Op>> = Vec::new();
+    let mut skipped = Vec::new();
+    let mut actions = 0;
+    for item in items {
+        let RolloutItem::ResponseItem(ResponseItem::FunctionCall {
+            name,
+            arguments,
+            call_id,
+            ..
+        }) = item
+        else {
+            continue;
+        };
+        if !name.starts_with("computer_") {
+            continue;
+        }
+        let output = outputs.get(call_id.as_str()).copied().unwrap_or_default();
+        if let Some(comment) = observation(name, arguments) {
+            steps.push(vec![Op::Comment(comment)]);
+            continue;
+        }
+        let Some((_, prefix)) = SUCCESS_PREFIXES.iter().find(|(tool, _)| tool == name) else {
+            continue;
+        };
+        if output.starts_with("queued ") {
+            skipped.push(format!(
+                "{name}: queued for review; the reviewed queue isn't recorded"
+            ));
+            continue;
+        }
+        if !output.starts_with(prefix) && !is_terse_ok(output) {
+            skipped.push(format!("{name}: the action failed"));
+            continue;
+        }
+        match replay(name, arguments, config.target_os) {
+            Ok(ops) => {
+                actions += 1;
+                steps.push(ops);
+            }
+            Err(err) => skipped.push(format!("{name}: {err}")),
+        }
+    }
+
+    let script = match format {
+        ScriptFormat::Pyautogui => pyautogui_script(&steps, config),
+        ScriptFormat::Playwright => playwright_script(&steps, config),
+    };
+    ExportedScript {
+        script,
+        actions,
+        skipped,
+    }
+}
+
+/// A comment for tools that only look at the screen.
+fn observation(name: &str, arguments: &str) -> Option<String> {
+    match name {
+        "computer_screenshot" => Some("The agent took a screenshot here.".to_string()),
+        "computer_find_text" => {
+            let query = parse::<FindTextArgs>(arguments).ok()?.query;
+            Some(format!("The agent looked for the text {query:?} here."))
+        }
+        "computer_active_window" => {
+            Some("The agent checked which window had focus here.".to_string())
+        }
+        "computer_workspace_list" => {
+            Some("The agent listed the workspaces and their windows here.".to_string())
+        }
+        "computer_tray_list" => Some("The agent listed the tray icons here.".to_string()),
+        "computer_network_state" => {
+            Some("The agent checked the network connection here.".to_string())
+        }
+        "computer_list_shortcuts" => {
+            Some("The agent looked up the keyboard shortcuts here.".to_string())
+        }
+        "computer_locate" => {
+            let query = parse::<LocateArgs>(arguments).ok()?.query;
+            Some(format!("The agent looked for {query:?} here."))
+        }
+        "computer_compare" => {
+            let baseline = parse::<CompareArgs>(arguments).ok()?.baseline_path;
+            Some(format!(
+                "The agent compared the screen with {baseline} here."
+            ))
+        }
+        _ => None,
+    }
+}
+
+fn replay(name: &str, arguments: &str, os: TargetOs) -> Result<Vec<Op>, String> {
+    let ops = match name {
+        "computer_click" => {
+            let args: ClickArgs = parse(arguments)?;
+            vec![Op::Click {
+                x: args.x,
+                y: args.y,
+                button: args.button.unwrap_or_default(),
+                count: if args.double.unwrap_or(false) { 2 } else { 1 },
+            }]
+        }
+        "computer_drag" => {
+            let args: DragArgs = parse(arguments)?;
+            drag(
+                (args.from_x, args.from_y),
+                (args.to_x, args.to_y),
+                args.button.unwrap_or_default(),
+            )
+        }
+        "computer_swipe" => {
+            let args: SwipeArgs = parse(arguments)?;
+            drag(
+                (args.from_x, args.from_y),
+                (args.to_x, args.to_y),
+                MouseButton::Left,
+            )
+        }
+        "computer_with_modifiers" => {
+            let args: WithModifiersArgs = parse(arguments)?;
+            let modifiers = super::modifiers::modifier_keys(&args.modifiers, os)?;
+            let mut ops: Vec<Op> = modifiers.iter().cloned().map(Op::KeyDown).collect();
+            for action in &args.actions {
+                ops.extend(held_action(action)?);
+            }
+            ops.extend(modifiers.into_iter().rev().map(Op::KeyUp));
+            ops
+        }
+        "computer_scroll" => {
+            let args: ScrollArgs = parse(arguments)?;
+            let mut ops = Vec::new();
+            if let (Some(x), Some(y)) = (args.x, args.y) {
+                ops.push(Op::Move(x, y));
+            }
+            ops.push(Op::Wheel {
+                up: args.direction == super::schema::ScrollDirection::Up,
+                ticks: args.amount.unwrap_or(super::DEFAULT_SCROLL_TICKS).max(1),
+            });
+            ops
+        }
+        "computer_long_press" => {
+            let args: LongPressArgs = parse(arguments)?;
+            let duration = args
+                .duration_ms
+                .unwrap_or(super::gestures::DEFAULT_LONG_PRESS_DURATION.as_millis() as u64);
+            vec![
+                Op::Move(args.x, args.y),
+                Op::Down(MouseButton::Left),
+                Op::Sleep(duration),
+                Op::Up(MouseButton::Left),
+            ]
+        }
+        "computer_pinch" => {
+            let args: PinchArgs = parse(arguments)?;
+            vec![
+                Op::Move(args.x, args.y),
+                Op::KeyDown("ctrl".to_string()),
+                Op::Wheel {
+                    up: args.direction.zooms_in(),
+                    ticks: args
+                        .amount
+                        .unwrap_or(super::gestures::DEFAULT_PINCH_TICKS)
+                        .max(1),
+                },
+                Op::KeyUp("ctrl".to_string()),
+            ]
+        }
+        "computer_type" => {
+            let args: TypeArgs = parse(arguments)?;
+            if args.confirm_secret == Some(true) {
+                return Err("typed a secret, which scripts leave out".to_string());
+            }
+            vec![Op::Type(args.text)]
+        }
+        "computer_key" => {
+            let args: KeyArgs = parse(arguments)?;
+            vec![Op::Press(keys::parse_chord(&args.keys)?.keys_for(os))]
+        }
+        "computer_clear_field" => {
+            let args: ClearFieldArgs = parse(arguments)?;
+            let chord = |name| keys::shortcut_keys(name, os).ok_or("unknown shortcut".to_string());
+            match args.method.unwrap_or_default() {
+                ClearMethod::SelectAll => vec![
+                    Op::Press(chord("select_all")?),
+                    Op::Press(vec!["BackSpace".to_string()]),
+                ],
+                ClearMethod::Backspace => {
+                    let count = super::clear_backspaces(args.count);
+                    std::iter::once(Op::Press(chord("line_end")?))
+                        .chain((0..count).map(|_| Op::Press(vec!["BackSpace".to_string()])))
+                        .collect()
+                }
+            }
+        }
+        "computer_focus_next" | "computer_focus_prev" => {
+            let count = parse::<FocusArgs>(arguments)?.count.unwrap_or(1).max(1);
+            let chord = if name == "computer_focus_next" {
+                vec!["Tab".to_string()]
+            } else {
+                vec!["shift".to_string(), "Tab".to_string()]
+            };
+            (0..count).map(|_| Op::Press(chord.clone())).collect()
+        }
+        _ => return Err("not a replayable action".to_string()),
+    };
+    Ok(ops)
+}
+
+fn held_action(action: &HeldAction) -> Result<Vec<Op>, String> {
+    let button = action.button.unwrap_or_default();
+    Ok(match action.action {
+        HeldActionKind::Move => vec![Op::Move(action.x, action.y)],
+        HeldActionKind::Click => vec![Op::Click {
+            x: action.x,
+            y: action.y,
+            button,
+            count: if action.double.unwrap_or(false) { 2 } else { 1 },
+        }],
+        HeldActionKind::Drag => {
+            let (Some(to_x), Some(to_y)) = (action.to_x, action.to_y) else {
+                return Err("drag without to_x and to_y".to_string());
+            };
+            drag((action.x, action.y), (to_x, to_y), button)
+        }
+    })
+}
+
+fn drag(from: (f64, f64), to: (f64, f64), button: MouseButton) -> Vec<Op> {
+    vec![
+        Op::Move(from.0, from.1),
+        Op::Down(button),
+        Op::Move(to.0, to.1),
+        Op::Up(button),
+    ]
+}
+
+fn parse<T: DeserializeOwned>(arguments: &str) -> Result<T, String> {
+    serde_json::from_str(arguments).map_err(|err| format!("unreadable arguments: {err}"))
+}
+
+/// Python for converting a model-space point into `width`x`height` pixels.
+fn scale_expression(config: &ComputerUseConfig) -> String {
+    match config.coordinate_space {
+        CoordinateSpace::Virtual => format!(
+            "round(x / {} * width), round(y / {} * height)",
+            config.resolution.width, config.resolution.height
+        ),
+        CoordinateSpace::Normalized => {
+            "round(x * (width - 1)), round(y * (height - 1))".to_string()
+        }
+        CoordinateSpace::Native => "round(x), round(y)".to_string(),
+    }
+}
+
+fn python_string(text: &str) -> String {
+    // JSON string escapes are a subset of Python's.
+    serde_json::to_string(text).unwrap_or_else(|_| "\"\"".to_string())
+}
+
+fn button_name(button: MouseButton) -> &'static str {
+    match button {
+        MouseButton::Left => "left",
+        MouseButton::Middle => "middle",
+        MouseButton::Right => "right",
+    }
+}
+
+fn pyautogui_script(steps: &[Vec<Op>], config: &ComputerUseConfig) -> String {
+    let scale = scale_expression(config);
+    let mut lines = vec![
+        "# Generated by `computex export-script` from a recorded GUI session.".to_string(),
+        format!(
+            "# Points were recorded in {} and are scaled to",
+            config.coordinate_label()
+        ),
+        "# the current screen. Review the script before running it.".to_string(),
+        "import time".to_string(),
+        String::new(),
+        "import pyautogui".to_string(),
+        String::new(),
+        format!("pyautogui.PAUSE = {}", STEP_DELAY_MS as f64 / 1000.0),
+        String::new(),
+        String::new(),
+        "def at(x, y):".to_string(),
+        "    width, height = pyautogui.size()".to_string(),
+        format!("    return {scale}"),
+        String::new(),
+        String::new(),
+        "def main():".to_string(),
+    ];
+    let mut body = Vec::new();
+    for step in steps {
+        for op in step {
+            body.push(match op {
+                Op::Move(x, y) => format!("pyautogui.moveTo(*at({x}, {y}))"),
+                Op::Click {
+                    x,
+                    y,
+                    button,
+                    count,
+                } => format!(
+                    "pyautogui.click(*at({x}, {y}), clicks={count}, button={:?})",
+                    button_name(*button)
+                ),
+                Op::Down(button) => {
+                    format!("pyautogui.mouseDown(button={:?})", button_name(*button))
+                }
+                Op::Up(button) => format!("pyautogui.mouseUp(button={:?})", button_name(*button)),
+                Op::Wheel { up, ticks } => {
+                    let clicks = if *up { *ticks as i64 } else { -(*ticks as i64) };
+                    format!("pyautogui.scroll({clicks})")
+                }
+                Op::KeyDown(key) => format!(
+                    "pyautogui.keyDown({})",
+                    python_string(&pyautogui_key(key, config.target_os))
+                ),
+                Op::KeyUp(key) => format!(
+                    "pyautogui.keyUp({})",
+                    python_string(&pyautogui_key(key, config.target_os))
+                ),
+                Op::Press(chord) => {
+                    let keys: Vec<String> = chord
+                        .iter()
+                        .map(|key| python_string(&pyautogui_key(key, config.target_os)))
+                        .collect();
+                    format!("pyautogui.hotkey({})", keys.join(", "))
+                }
+                Op::Type(text) => format!("pyautogui.write({})", python_string(text)),
+                Op::Sleep(ms) => format!("time.sleep({})", *ms as f64 / 1000.0),
+                Op::Comment(text) => format!("# {text}"),
+            });
+        }
+    }
+    finish(&mut lines, body, "main()")
+}
+
+fn playwright_script(steps: &[Vec<Op>], config: &ComputerUseConfig) -> String {
+    let scale = scale_expression(config);
+    let mut lines = vec![
+        "# Generated by `computex export-script` from a recorded GUI session.".to_string(),
+        format!(
+            "# Points were recorded on the whole desktop in {}",
+            config.coordinate_label()
+        ),
+        "# and are scaled to the page viewport, which matches only when the browser".to_string(),
+        "# filled the screen. Set START_URL and review the script before running it.".to_string(),
+        "from playwright.sync_api import sync_playwright".to_string(),
+        String::new(),
+        "START_URL = \"about:blank\"".to_string(),
+        format!("STEP_DELAY_MS = {STEP_DELAY_MS}"),
+        String::new(),
+        String::new(),
+        "def at(page, x, y):".to_string(),
+        "    width, height = page.viewport_size[\"width\"], page.viewport_size[\"height\"]"
+            .to_string(),
+        format!("    return {scale}"),
+        String::new(),
+        String::new(),
+        "def run(page):".to_string(),
+    ];
+    let mut body = Vec::new();
+    for (index, step) in steps.iter().enumerate() {
+        if index > 0 && !matches!(step.as_slice(), [Op::Comment(_)]) {
+            body.push("page.wait_for_timeout(STEP_DELAY_MS)".to_string());
+        }
+        for op in step {
+            body.push(match op {
+                Op::Move(x, y) => format!("page.mouse.move(*at(page, {x}, {y}))"),
+                Op::Click {
+                    x,
+                    y,
+                    button,
+                    count,
+                } => format!(
+                    "page.mouse.click(*at(page, {x}, {y}), button={:?}, click_count={count})",
+                    button_name(*button)
+                ),
+                Op::Down(button) => {
+                    format!("page.mouse.down(button={:?})", button_name(*button))
+                }
+                Op::Up(button) => format!("page.mouse.up(button={:?})", button_name(*button)),
+                Op::Wheel { up, ticks } => {
+                    let delta = if *up { -100 } else { 100 } * *ticks as i64;
+                    format!("page.mouse.wheel(0, {delta})")
+                }
+                Op::KeyDown(key) => format!(
+                    "page.keyboard.down({})",
+                    python_string(&playwright_key(key))
+                ),
+                Op::KeyUp(key) => {
+                    format!("page.keyboard.up({})", python_string(&playwright_key(key)))
+                }
+                Op::Press(chord) => {
+                    let chord: Vec<String> = chord.iter().map(|key| playwright_key(key)).collect();
+                    format!("page.keyboard.press({})", python_string(&chord.join("+")))
+                }
+                Op::Type(text) => format!("page.keyboard.type({})", python_string(text)),
+                Op::Sleep(ms) => format!("page.wait_for_timeout({ms})"),
+                Op::Comment(text) => format!("# {text}"),
+            });
+        }
+    }
+    let main = [
+        "with sync_playwright() as playwright:",
+        "    browser = playwright.chromium.launch(headless=False)",
+        "    page = browser.new_page()",
+        "    page.goto(START_URL)",
+        "    run(page)",
+        "    browser.close()",
+    ]
+    .join("\n");
+    finish(&mut lines, body, &main)
+}
+
+/// Appends the indented function `body` and the entry point to `lines`.
+fn finish(lines: &mut Vec<String>, body: Vec<String>, entry: &str) -> String {
+    if body.is_empty() {
+        lines.push("    pass  # the session had no replayable GUI actions".to_string());
+    }
+    lines.extend(body.into_iter().map(|line| format!("    {line}")));
+    lines.extend([String::new(), String::new()]);
+    if entry == "main()" {
+        lines.push("if __name__ == \"__main__\":".to_string());
+        lines.push("    main()".to_string());
+    } else {
+        lines.push(entry.to_string());
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+/// PyAutoGUI's name for an xdotool key.
+fn pyautogui_key(key: &str, os: TargetOs) -> String {
+    let name = match key {
+        "ctrl" => "ctrl",
+        "shift" => "shift",
+        "alt" => "alt",
+        "super" if os == TargetOs::Macos => "command",
+        "super" => "win",
+        "ISO_Level3_Shift" => "altright",
+        "Return" => "enter",
+        "Escape" => "esc",
+        "BackSpace" => "backspace",
+        "Page_Up" => "pageup",
+        "Page_Down" => "pagedown",
+        "Caps_Lock" => "capslock",
+        "Num_Lock" => "numlock",
+        "Scroll_Lock" => "scrolllock",
+        "Print" => "printscreen",
+        "Menu" => "apps",
+        _ => return punctuation(key).map_or_else(|| key.to_lowercase(), str::to_string),
+    };
+    name.to_string()
+}
+
+/// Playwright's name for an xdotool key.
+fn playwright_key(key: &str) -> String {
+    let name = match key {
+        "ctrl" => "Control",
+        "shift" => "Shift",
+        "alt" => "Alt",
+        "super" => "Meta",
+        "ISO_Level3_Shift" => "AltGraph",
+        "Return" => "Enter",
+        "BackSpace" => "Backspace",
+        "Page_Up" => "PageUp",
+        "Page_Down" => "PageDown",
+        "Up" => "ArrowUp",
+        "Down" => "ArrowDown",
+        "Left" => "ArrowLeft",
+        "Right" => "ArrowRight",
+        "space" => "Space",
+        "Caps_Lock" => "CapsLock",
+        "Num_Lock" => "NumLock",
+        "Scroll_Lock" => "ScrollLock",
+        "Print" => "PrintScreen",
+        "Menu" => "ContextMenu",
+        _ => return punctuation(key).unwrap_or(key).to_string(),
+    };
+    name.to_string()
+}
+
+/// The character for an xdotool punctuation keysym name.
+fn punctuation(key: &str) -> Option<&'static str> {
+    Some(match key {
+        "plus" => "+",
+        "minus" => "-",
+        "comma" => ",",
+        "period" => ".",
+        "slash" => "/",
+        "backslash" => "\\",
+        "semicolon" => ";",
+        "apostrophe" => "'",
+        "grave" => "`",
+        "equal" => "=",
+        "bracketleft" => "[",
+        "bracketright" => "]",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_protocol::models::FunctionCallOutputPayload;
+    use pretty_assertions::assert_eq;
+
+    fn call(name: &str, arguments: &str, call_id: &str, output: &str) -> [RolloutItem; 2] {
+        [
+            RolloutItem::ResponseItem(ResponseItem::FunctionCall {
+                id: None,
+                name: name.to_string(),
+                arguments: arguments.to_string(),
+                call_id: call_id.to_string(),
+            }),
+            RolloutItem::ResponseItem(ResponseItem::FunctionCallOutput {
+                call_id: call_id.to_string(),
+                output: FunctionCallOutputPayload {
+                    content: output.to_string(),
+                    ..Default::default()
+                },
+            }),
+        ]
+    }
+
+    fn session() -> Vec<RolloutItem> {
+        [
+            call(
+                "computer_screenshot",
+                "{}",
+                "1",
+                "captured screenshot at /tmp/a.png",
+            ),
+            call(
+                "computer_click",
+                r#"{"x": 640, "y": 360, "double": true}"#,
+                "2",
+                "clicked at 640,360",
+            ),
+            call(
+                "computer_click",
+                r#"{"x": 5, "y": 5}"#,
+                "3",
+                "no window has focus",
+            ),
+            call(
+                "computer_type",
+                r#"{"text": "hi \"there\""}"#,
+                "4",
+                "typed 10 characters",
+            ),
+            call(
+                "computer_key",
+                r#"{"keys": ["copy"]}"#,
+                "5",
+                "pressed ctrl+c",
+            ),
+            call(
+                "computer_key",
+                r#"{"keys": ["Return"]}"#,
+                "6",
+                "queued computer_key as action #1 (not run yet)",
+            ),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    #[test]
+    fn pyautogui_script_replays_successful_actions() {
+        let exported = export_script(
+            &session(),
+            ScriptFormat::Pyautogui,
+            &ComputerUseConfig {
+                target_os: TargetOs::Linux,
+                ..Default::default()
+            },
+        );
+        assert_eq!(exported.actions, 3);
+        assert_eq!(
+            exported.skipped,
+            vec![
+                "computer_click: the action failed".to_string(),
+                "computer_key: queued for review; the reviewed queue isn't recorded".to_string(),
+            ]
+        );
+        let body: Vec<&str> = exported
+            .script
+            .lines()
+            .skip_while(|line| *line != "def main():")
+            .skip(1)
+            .take(4)
+            .collect();
+        assert_eq!(
+            body,
+            vec![
+                "    # The agent took a screenshot here.",
+                "    pyautogui.click(*at(640, 360), clicks=2, button=\"left\")",
+                "    pyautogui.write(\"hi \\\"there\\\"\")",
+                "    pyautogui.hotkey(\"ctrl\", \"c\")",
+            ]
+        );
+        assert!(
+            exported
+                .script
+                .contains("    return round(x / 1280 * width), round(y / 720 * height)")
+        );
+    }
+
+    #[test]
+    fn playwright_script_uses_playwright_key_names() {
+        let items: Vec<RolloutItem> = [
+            call(
+                "computer_with_modifiers",
+                r#"{"modifiers": ["shift"], "actions": [{"action": "drag", "x": 1, "y": 2, "to_x": 3, "to_y": 4}]}"#,
+                "1",
+                "with shift held: drag from 1,2 to 3,4",
+            ),
+            call("computer_key", r#"{"keys": ["cmd", "PgDn"]}"#, "2", "pressed super+Page_Down"),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        let exported = export_script(
+            &items,
+            ScriptFormat::Playwright,
+            &ComputerUseConfig {
+                target_os: TargetOs::Macos,
+                ..Default::default()
+            },
+        );
+        assert_eq!(exported.actions, 2);
+        for line in [
+            "    page.keyboard.down(\"Shift\")",
+            "    page.mouse.down(button=\"left\")",
+            "    page.keyboard.up(\"Shift\")",
+            "    page.wait_for_timeout(STEP_DELAY_MS)",
+            "    page.keyboard.press(\"Meta+PageDown\")",
+        ] {
+            assert!(exported.script.contains(line), "missing {line}");
+        }
+        assert_eq!("Pyautogui".parse(), Ok(ScriptFormat::Pyautogui));
+    }
+}
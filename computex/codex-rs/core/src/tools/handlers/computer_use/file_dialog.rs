@@ -0,0 +1,220 @@
+//! File chooser automation for `computer_file_dialog`.
+//!
+//! Clicking through GTK and Qt file choosers (sidebar places, breadcrumb
+//! buttons, a file list that scrolls) is where pixel-driven runs most often
+//! go wrong. Both toolkits accept a typed path instead: GTK opens its
+//! location bar on Ctrl+L, KDE's dialog focuses its location field on the
+//! same chord, and save dialogs start with the name field focused, which
+//! takes a full path as well. The helper checks that a file chooser has
+//! focus, enters the path there, and waits for the dialog to close.
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::Instant;
+
+use super::keys::shortcut_keys;
+use super::recovery::xdotool_output;
+use super::require_command;
+use super::run_input;
+use super::schema::FileDialogAction;
+use crate::codex::Session;
+use crate::config::types::ComputerUseConfig;
+use crate::function_tool::FunctionCallError;
+
+/// How long to wait for a dialog to open or close.
+pub(super) const DIALOG_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub(super) const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Chord that focuses the location field in GTK and KDE file choosers.
+const LOCATION_BAR: &str = "ctrl+l";
+
+/// Enters `path` in the focused file chooser and accepts it. Returns the
+/// tool result.
+pub(super) async fn fill(
+    session: &Session,
+    config: &ComputerUseConfig,
+    action: FileDialogAction,
+    path: &str,
+) -> Result<String, FunctionCallError> {
+    let path = dialog_path(action, path).map_err(FunctionCallError::RespondToModel)?;
+    let xdotool = require_command("xdotool")?;
+    let window = xdotool_output(&xdotool, config, &["getactivewindow"])?;
+    let window = window.trim();
+    let title = xdotool_output(&xdotool, config, &["getwindowname", window])?;
+    let title = title.trim();
+    if !is_file_chooser(title) {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "the focused window (\"{title}\") does not look like a file chooser; open the dialog first and make sure it has focus"
+        )));
+    }
+
+    if action == FileDialogAction::Open {
+        // Save dialogs already focus the name field; in GTK's, Ctrl+L would
+        // toggle the location bar off.
+        run_input(
+            session,
+            &xdotool,
+            &["key".to_string(), LOCATION_BAR.to_string()],
+            config,
+            None,
+        )
+        .await?;
+    }
+    enter_path(session, &xdotool, config, &path).await?;
+
+    if !wait_for_close(&xdotool, config, window).await {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "entered {} but \"{title}\" is still open after {} s; it may be asking to replace the file or reporting an error. Take a screenshot to check",
+            path.display(),
+            DIALOG_TIMEOUT.as_secs()
+        )));
+    }
+    let verb = match action {
+        FileDialogAction::Open => "opened",
+        FileDialogAction::Save => "saved as",
+    };
+    Ok(format!(
+        "{verb} {} through the file chooser \"{title}\"",
+        path.display()
+    ))
+}
+
+/// Checks that `path` is absolute and, for open, exists; for save, that its
+/// directory exists and it doesn't name a directory.
+fn dialog_path(action: FileDialogAction, path: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(path.trim());
+    if !path.is_absolute() {
+        return Err(format!("path must be absolute, got `{}`", path.display()));
+    }
+    match action {
+        FileDialogAction::Open if !path.exists() => {
+            Err(format!("`{}` does not exist", path.display()))
+        }
+        FileDialogAction::Save if !path.parent().is_some_and(Path::is_dir) => Err(format!(
+            "the directory for `{}` does not exist",
+            path.display()
+        )),
+        FileDialogAction::Save if path.is_dir() => {
+            Err(format!("`{}` is a directory", path.display()))
+        }
+        _ => Ok(path),
+    }
+}
+
+/// GTK's "Select a filename" and "Open File", Chromium's "Save File", Qt's
+/// "Save As", upload pickers titled "File Upload".
+pub(super) fn is_file_chooser(title: &str) -> bool {
+    let title = title.to_lowercase();
+    [
+        "save",
+        "filename",
+        "select a file",
+        "export",
+        "open",
+        "upload",
+        "choose",
+        "import",
+    ]
+    .iter()
+    .any(|word| title.contains(word))
+}
+
+/// Replaces the file chooser's focused field with `path` and accepts it.
+pub(super) async fn enter_path(
+    session: &Session,
+    xdotool: &Path,
+    config: &ComputerUseConfig,
+    path: &Path,
+) -> Result<(), FunctionCallError> {
+    let select_all = shortcut_keys("select_all", config.target_os)
+        .map_or_else(|| "ctrl+a".to_string(), |keys| keys.join("+"));
+    run_input(
+        session,
+        xdotool,
+        &["key".to_string(), select_all],
+        config,
+        None,
+    )
+    .await?;
+    let typed = [
+        "type".to_string(),
+        "--".to_string(),
+        path.display().to_string(),
+    ];
+    run_input(session, xdotool, &typed, config, None).await?;
+    run_input(
+        session,
+        xdotool,
+        &["key".to_string(), "Return".to_string()],
+        config,
+        None,
+    )
+    .await
+    .map(drop)
+}
+
+/// Polls the focused window's title until `matches` accepts it, returning
+/// the title, or `None` after [`DIALOG_TIMEOUT`].
+pub(super) async fn wait_for_title(
+    xdotool: &Path,
+    config: &ComputerUseConfig,
+    matches: fn(&str) -> bool,
+) -> Option<String> {
+    let deadline = Instant::now() + DIALOG_TIMEOUT;
+    loop {
+        if let Ok(title) = xdotool_output(xdotool, config, &["getactivewindow", "getwindowname"])
+            && matches(&title)
+        {
+            return Some(title);
+        }
+        if Instant::now() >= deadline {
+            return None;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Waits until window `id` no longer has focus.
+async fn wait_for_close(xdotool: &Path, config: &ComputerUseConfig, id: &str) -> bool {
+    let deadline = Instant::now() + DIALOG_TIMEOUT;
+    loop {
+        if xdotool_output(xdotool, config, &["getactivewindow"])
+            .is_ok_and(|active| active.trim() != id)
+        {
+            return true;
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_paths_per_action() {
+        let dir = std::env::temp_dir();
+        let new = dir.join(format!("computex-dialog-{}.txt", uuid::Uuid::new_v4()));
+        assert_eq!(
+            dialog_path(FileDialogAction::Save, &new.display().to_string()),
+            Ok(new.clone())
+        );
+        assert!(dialog_path(FileDialogAction::Open, &new.display().to_string()).is_err());
+        assert_eq!(
+            dialog_path(FileDialogAction::Open, &dir.display().to_string()),
+            Ok(dir.clone())
+        );
+        assert!(dialog_path(FileDialogAction::Save, &dir.display().to_string()).is_err());
+        assert!(dialog_path(FileDialogAction::Save, "notes.txt").is_err());
+        assert!(dialog_path(FileDialogAction::Save, "/no/such/dir/notes.txt").is_err());
+        assert!(is_file_chooser("Select a filename"));
+        assert!(is_file_chooser("Open File"));
+        assert!(is_file_chooser("File Upload"));
+        assert!(!is_file_chooser("Print"));
+    }
+}
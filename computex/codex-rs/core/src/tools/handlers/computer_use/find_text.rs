@@ -0,0 +1,355 @@
+//! Screen text search for `computer_find_text`.
+//!
+//! The screen is captured at native resolution and passed through `tesseract`
+//! in TSV mode. Runs of consecutive words on the same OCR line are then
+//! fuzzy-matched against the query, so only the few matching boxes reach the
+//! model instead of a full transcript of the screen.
+
+use std::path::Path;
+use std::process::Command;
+
+use super::require_command;
+use crate::function_tool::FunctionCallError;
+
+/// Matches below this similarity are dropped unless the caller overrides it.
+pub(super) const DEFAULT_MIN_SIMILARITY: f64 = 0.8;
+
+/// Result count cap unless the caller overrides it.
+pub(super) const DEFAULT_MAX_RESULTS: usize = 10;
+
+/// A word recognized by tesseract, in screen pixels.
+#[derive(Debug, Clone, PartialEq)]
+struct OcrWord {
+    line: (u32, u32, u32),
+    text: String,
+    left: i64,
+    top: i64,
+    width: i64,
+    height: i64,
+    confidence: f64,
+}
+
+/// An occurrence of the query on screen, in screen pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct TextMatch {
+    pub text: String,
+    pub left: i64,
+    pub top: i64,
+    pub right: i64,
+    pub bottom: i64,
+    /// Fuzzy similarity to the query scaled by the OCR confidence, in `0..=1`.
+    pub confidence: f64,
+}
+
+/// Runs tesseract over `image` and returns its TSV output.
+pub(super) fn recognize(image: &Path) -> Result<String, FunctionCallError> {
+    let tesseract = require_command("tesseract")?;
+    // Page segmentation mode 11 finds sparse text, which suits UI labels
+    // better than the default paragraph layout analysis.
+    let output = Command::new(&tesseract)
+        .arg(image)
+        .args(["stdout", "--psm", "11", "tsv"])
+        .output()
+        .map_err(|err| {
+            FunctionCallError::RespondToModel(format!("failed to run tesseract: {err}"))
+        })?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(FunctionCallError::RespondToModel(format!(
+            "tesseract failed: {stderr}"
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Finds runs of words in tesseract `tsv` output that fuzzy-match `query`,
+/// best matches first.
+pub(super) fn find_matches(tsv: &str, query: &str, min_similarity: f64) -> Vec<TextMatch> {
+    let query_words: Vec<String> = query.split_whitespace().map(normalize).collect();
+    let query = query_words.join(" ");
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let words = parse_tsv(tsv);
+    let span = query_words.len();
+    let mut matches = Vec::new();
+    for start in 0..words.len() {
+        let Some(run) = words.get(start..start + span) else {
+            break;
+        };
+        if run.iter().any(|word| word.line != run[0].line) {
+            continue;
+        }
+        let candidate = run
+            .iter()
+            .map(|word| normalize(&word.text))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let score = similarity(&candidate, &query);
+        if score < min_similarity {
+            continue;
+        }
+        let ocr_confidence =
+            run.iter().map(|word| word.confidence).sum::<f64>() / span as f64 / 100.0;
+        matches.push(TextMatch {
+            text: run
+                .iter()
+                .map(|word| word.text.as_str())
+                .collect::<Vec<_>>()
+                .join(" "),
+            left: run.iter().map(|word| word.left).min().unwrap_or_default(),
+            top: run.iter().map(|word| word.top).min().unwrap_or_default(),
+            right: run
+                .iter()
+                .map(|word| word.left + word.width)
+                .max()
+                .unwrap_or_default(),
+            bottom: run
+                .iter()
+                .map(|word| word.top + word.height)
+                .max()
+                .unwrap_or_default(),
+            confidence: (score * ocr_confidence.clamp(0.0, 1.0) * 100.0).round() / 100.0,
+        });
+    }
+    matches.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    matches
+}
+
+/// The OCR lines in tesseract `tsv` output, in reading order, each with
+/// its box and mean word confidence.
+pub(super) fn text_lines(tsv: &str) -> Vec<TextMatch> {
+    let words = parse_tsv(tsv);
+    words
+        .chunk_by(|a, b| a.line == b.line)
+        .map(|line| TextMatch {
+            text: line
+                .iter()
+                .map(|word| word.text.as_str())
+                .collect::<Vec<_>>()
+                .join(" "),
+            left: line.iter().map(|word| word.left).min().unwrap_or_default(),
+            top: line.iter().map(|word| word.top).min().unwrap_or_default(),
+            right: line
+                .iter()
+                .map(|word| word.left + word.width)
+                .max()
+                .unwrap_or_default(),
+            bottom: line
+                .iter()
+                .map(|word| word.top + word.height)
+                .max()
+                .unwrap_or_default(),
+            confidence: (line.iter().map(|word| word.confidence).sum::<f64>() / line.len() as f64)
+                .clamp(0.0, 100.0)
+                .round()
+                / 100.0,
+        })
+        .collect()
+}
+
+/// Words in tesseract `tsv` output that end with `suffix`, ignoring case,
+/// e.g. file names ending in `.pdf`. Most confident first.
+pub(super) fn find_suffix(tsv: &str, suffix: &str) -> Vec<TextMatch> {
+    let suffix = suffix.to_lowercase();
+    let mut matches: Vec<TextMatch> = parse_tsv(tsv)
+        .into_iter()
+        .filter(|word| word.text.to_lowercase().ends_with(&suffix))
+        .map(|word| TextMatch {
+            left: word.left,
+            top: word.top,
+            right: word.left + word.width,
+            bottom: word.top + word.height,
+            confidence: (word.confidence.clamp(0.0, 100.0)).round() / 100.0,
+            text: word.text,
+        })
+        .collect();
+    matches.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    matches
+}
+
+/// Parses word rows (level 5) from tesseract TSV output, skipping empty words.
+fn parse_tsv(tsv: &str) -> Vec<OcrWord> {
+    tsv.lines()
+        .skip(1)
+        .filter_map(|row| {
+            let fields: Vec<&str> = row.split('\t').collect();
+            let [
+                level,
+                _page,
+                block,
+                par,
+                line,
+                _word,
+                left,
+                top,
+                width,
+                height,
+                conf,
+                text,
+            ] = fields.as_slice()
+            else {
+                return None;
+            };
+            if *level != "5" || text.trim().is_empty() {
+                return None;
+            }
+            Some(OcrWord {
+                line: (block.parse().ok()?, par.parse().ok()?, line.parse().ok()?),
+                text: text.trim().to_string(),
+                left: left.parse().ok()?,
+                top: top.parse().ok()?,
+                width: width.parse().ok()?,
+                height: height.parse().ok()?,
+                confidence: conf.parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+/// Lowercases a word and strips surrounding punctuation, so `Submit:` matches `submit`.
+fn normalize(word: &str) -> String {
+    word.trim_matches(|ch: char| !ch.is_alphanumeric())
+        .to_lowercase()
+}
+
+/// Normalized Levenshtein similarity between `a` and `b`, in `0..=1`.
+pub(super) fn similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    1.0 - previous[b.len()] as f64 / longest as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const HEADER: &str = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext";
+
+    fn tsv(rows: &[&str]) -> String {
+        std::iter::once(HEADER)
+            .chain(rows.iter().copied())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn finds_fuzzy_multi_word_matches_on_one_line() {
+        let output = tsv(&[
+            "1\t1\t0\t0\t0\t0\t0\t0\t1920\t1080\t-1\t",
+            "5\t1\t1\t1\t1\t1\t100\t40\t60\t20\t95\tSave",
+            "5\t1\t1\t1\t1\t2\t170\t42\t50\t18\t85\tdraft",
+            "5\t1\t2\t1\t1\t1\t600\t900\t80\t24\t90\tSubmit:",
+            "5\t1\t3\t1\t1\t1\t900\t900\t80\t24\t50\tSubmlt",
+            "5\t1\t4\t1\t1\t1\t10\t10\t50\t20\t96\tSave",
+            "5\t1\t4\t1\t2\t1\t10\t40\t50\t20\t96\tdraft",
+        ]);
+
+        let found = find_matches(&output, "submit", DEFAULT_MIN_SIMILARITY);
+        assert_eq!(
+            found,
+            vec![
+                TextMatch {
+                    text: "Submit:".to_string(),
+                    left: 600,
+                    top: 900,
+                    right: 680,
+                    bottom: 924,
+                    confidence: 0.9,
+                },
+                TextMatch {
+                    text: "Submlt".to_string(),
+                    left: 900,
+                    top: 900,
+                    right: 980,
+                    bottom: 924,
+                    confidence: 0.42,
+                },
+            ]
+        );
+
+        let found = find_matches(&output, "Save Draft", DEFAULT_MIN_SIMILARITY);
+        assert_eq!(
+            found,
+            vec![TextMatch {
+                text: "Save draft".to_string(),
+                left: 100,
+                top: 40,
+                right: 220,
+                bottom: 60,
+                confidence: 0.9,
+            }]
+        );
+        assert!(find_matches(&output, "  ", DEFAULT_MIN_SIMILARITY).is_empty());
+    }
+
+    #[test]
+    fn groups_words_into_lines() {
+        let output = tsv(&[
+            "5\t1\t1\t1\t1\t1\t100\t40\t60\t20\t90\tSave",
+            "5\t1\t1\t1\t1\t2\t170\t42\t50\t18\t70\tdraft",
+            "5\t1\t2\t1\t1\t1\t600\t900\t80\t24\t95\tSubmit",
+        ]);
+        assert_eq!(
+            text_lines(&output),
+            vec![
+                TextMatch {
+                    text: "Save draft".to_string(),
+                    left: 100,
+                    top: 40,
+                    right: 220,
+                    bottom: 60,
+                    confidence: 0.8,
+                },
+                TextMatch {
+                    text: "Submit".to_string(),
+                    left: 600,
+                    top: 900,
+                    right: 680,
+                    bottom: 924,
+                    confidence: 0.95,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_words_by_suffix() {
+        let output = tsv(&[
+            "5\t1\t1\t1\t1\t1\t100\t40\t60\t20\t80\toutput.PDF",
+            "5\t1\t1\t1\t1\t2\t170\t42\t50\t18\t85\tpdf",
+        ]);
+        assert_eq!(
+            find_suffix(&output, ".pdf"),
+            vec![TextMatch {
+                text: "output.PDF".to_string(),
+                left: 100,
+                top: 40,
+                right: 160,
+                bottom: 60,
+                confidence: 0.8,
+            }]
+        );
+    }
+
+    #[test]
+    fn similarity_is_normalized_edit_distance() {
+        assert_eq!(similarity("submit", "submit"), 1.0);
+        assert_eq!(similarity("submit", "submlt"), 1.0 - 1.0 / 6.0);
+        assert_eq!(similarity("", "ok"), 0.0);
+    }
+}
@@ -0,0 +1,113 @@
+//! Expected-window checks for `computer_type` and `computer_key`.
+//!
+//! A call can name the window its keystrokes are meant for. If a different
+//! window has focus (a notification stole it, or an earlier click landed in
+//! the wrong app), the keys are not sent there: the expected window is
+//! activated first when `computer_use.refocus_expected_window` is set, and
+//! the call fails otherwise. This keeps passwords and other text out of the
+//! wrong app.
+
+use std::path::Path;
+
+use super::recovery::activate;
+use super::recovery::active_window;
+use super::recovery::regex_escape;
+use super::recovery::xdotool_output;
+use crate::config::types::ComputerUseConfig;
+use crate::function_tool::FunctionCallError;
+
+/// Makes sure a window matching `expected` has focus, activating one if
+/// allowed. Returns a note for the tool result when focus was moved.
+pub(super) fn ensure_focused(
+    xdotool: &Path,
+    config: &ComputerUseConfig,
+    expected: &str,
+) -> Result<String, FunctionCallError> {
+    let expected = expected.trim();
+    let focused = active_window(xdotool, config).ok();
+    let title = focused
+        .as_ref()
+        .and_then(|window| xdotool_output(xdotool, config, &["getwindowname", &window.id]).ok())
+        .unwrap_or_default();
+    let class = focused.map(|window| window.class).unwrap_or_default();
+    if matches_window(expected, &class, &title) {
+        return Ok(String::new());
+    }
+    let describe_focused = if class.is_empty() {
+        "no window has focus".to_string()
+    } else {
+        format!("the focused window is `{class}` (\"{title}\")")
+    };
+    if !config.refocus_expected_window {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "{describe_focused}, not `{expected}`; no keys were sent. Focus the right window first"
+        )));
+    }
+
+    let Some(id) = find_window(xdotool, config, expected) else {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "{describe_focused} and no visible window matches `{expected}`; no keys were sent"
+        )));
+    };
+    activate(xdotool, config, &id)?;
+    let now = active_window(xdotool, config)?;
+    let title = xdotool_output(xdotool, config, &["getwindowname", &now.id]).unwrap_or_default();
+    if !matches_window(expected, &now.class, &title) {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "could not focus a window matching `{expected}`; the focused window is `{}` (\"{title}\"); no keys were sent",
+            now.class
+        )));
+    }
+    Ok(format!(" after focusing `{}` (\"{title}\")", now.class))
+}
+
+/// Whether `expected` names the window: its `WM_CLASS` exactly or part of
+/// its title, ignoring case.
+pub(super) fn matches_window(expected: &str, class: &str, title: &str) -> bool {
+    !expected.is_empty()
+        && (class.eq_ignore_ascii_case(expected)
+            || title.to_lowercase().contains(&expected.to_lowercase()))
+}
+
+/// A visible window whose class is `expected`, or else whose title contains
+/// it.
+pub(super) fn find_window(
+    xdotool: &Path,
+    config: &ComputerUseConfig,
+    expected: &str,
+) -> Option<String> {
+    let escaped = regex_escape(expected);
+    let class = format!("^{escaped}$");
+    [["--class", class.as_str()], ["--name", escaped.as_str()]]
+        .iter()
+        .find_map(|[flag, pattern]| {
+            xdotool_output(xdotool, config, &["search", "--onlyvisible", flag, pattern])
+                .ok()?
+                .lines()
+                .map(str::trim)
+                .find(|id| !id.is_empty())
+                .map(str::to_string)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_class_exactly_or_title_substring() {
+        assert!(matches_window(
+            "firefox",
+            "Firefox",
+            "Sign in – Mozilla Firefox"
+        ));
+        assert!(matches_window(
+            "sign in",
+            "Firefox",
+            "Sign in – Mozilla Firefox"
+        ));
+        assert!(!matches_window("fire", "Firefox", "Inbox"));
+        assert!(!matches_window("KeePassXC", "Gnome-terminal", "~"));
+        assert!(!matches_window("", "Firefox", "Inbox"));
+    }
+}
@@ -0,0 +1,164 @@
+//! Low-resolution frame history for `computer_recent_frames`.
+//!
+//! Screenshots only show the screen at the moments the model asks, so a
+//! toast that disappears after two seconds or a spinner that runs between
+//! two actions is easy to miss. With `computer_use.recent_frames` set, a
+//! background thread captures a 640x360 frame about once a second from the
+//! first GUI tool call on, keeping the last [`CAPACITY`] in a ring buffer.
+//! The tool attaches copies of the newest few, so evicting a frame can't
+//! delete an image the conversation still has to read.
+
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::mpsc;
+use std::time::Duration;
+use std::time::Instant;
+
+use uuid::Uuid;
+
+use super::capture_root_window;
+use super::detail::LOW_DETAIL;
+use crate::config::types::ComputerUseConfig;
+
+/// Time between frames.
+const FRAME_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Frames kept, so about half a minute of history.
+const CAPACITY: usize = 30;
+
+/// Frames returned when the call doesn't say, and the most it may ask for.
+pub(super) const DEFAULT_COUNT: usize = 3;
+pub(super) const MAX_COUNT: usize = 10;
+
+#[derive(Debug)]
+struct Frame {
+    path: PathBuf,
+    captured_at: Instant,
+}
+
+/// Captures frames until dropped, then deletes them.
+#[derive(Debug)]
+pub(super) struct FrameRecorder {
+    frames: Arc<Mutex<VecDeque<Frame>>>,
+    /// Dropping the sender stops the capture thread.
+    _stop: mpsc::Sender<()>,
+}
+
+impl FrameRecorder {
+    pub(super) fn start(config: &ComputerUseConfig) -> Self {
+        let frames = Arc::new(Mutex::new(VecDeque::with_capacity(CAPACITY + 1)));
+        let (stop, stopped) = mpsc::channel::<()>();
+        let config = config.clone();
+        let buffer = Arc::clone(&frames);
+        let spawned = std::thread::Builder::new()
+            .name("computex-frames".to_string())
+            .spawn(move || record(&config, &buffer, &stopped));
+        if let Err(err) = spawned {
+            tracing::warn!("failed to start the frame recorder: {err}");
+        }
+        Self {
+            frames,
+            _stop: stop,
+        }
+    }
+
+    /// Copies of the newest `count` frames, oldest first, with their ages.
+    pub(super) fn recent(&self, count: usize) -> Vec<(PathBuf, Duration)> {
+        let Ok(frames) = self.frames.lock() else {
+            return Vec::new();
+        };
+        let skip = frames.len().saturating_sub(count);
+        frames
+            .iter()
+            .skip(skip)
+            .filter_map(|frame| {
+                let copy = std::env::temp_dir().join(format!("codex-frame-{}.png", Uuid::new_v4()));
+                std::fs::copy(&frame.path, &copy).ok()?;
+                Some((copy, frame.captured_at.elapsed()))
+            })
+            .collect()
+    }
+}
+
+fn record(
+    config: &ComputerUseConfig,
+    frames: &Mutex<VecDeque<Frame>>,
+    stopped: &mpsc::Receiver<()>,
+) {
+    let mut next = Instant::now();
+    loop {
+        let wait = next.saturating_duration_since(Instant::now());
+        if !matches!(
+            stopped.recv_timeout(wait),
+            Err(mpsc::RecvTimeoutError::Timeout)
+        ) {
+            break;
+        }
+        next = Instant::now() + FRAME_INTERVAL;
+        let path = match capture_root_window(config, None, Some(LOW_DETAIL)) {
+            Ok(path) => path,
+            Err(err) => {
+                tracing::debug!("frame capture failed: {err:?}");
+                continue;
+            }
+        };
+        let Ok(mut frames) = frames.lock() else {
+            let _ = std::fs::remove_file(path);
+            break;
+        };
+        frames.push_back(Frame {
+            path,
+            captured_at: Instant::now(),
+        });
+        while frames.len() > CAPACITY {
+            if let Some(evicted) = frames.pop_front() {
+                let _ = std::fs::remove_file(evicted.path);
+            }
+        }
+    }
+    if let Ok(mut frames) = frames.lock() {
+        for frame in frames.drain(..) {
+            let _ = std::fs::remove_file(frame.path);
+        }
+    }
+}
+
+/// Result text for frames of the given ages, oldest first.
+pub(super) fn describe(ages: &[Duration]) -> String {
+    let ages: Vec<String> = ages
+        .iter()
+        .map(|age| format!("{:.1} s", age.as_secs_f64()))
+        .collect();
+    let when = match ages.as_slice() {
+        [] => String::new(),
+        [age] => age.clone(),
+        [rest @ .., last] => format!("{} and {last}", rest.join(", ")),
+    };
+    format!(
+        "attached the last {} frame(s), oldest first, captured {when} ago. They are {}x{} previews taken about once a second, for spotting toasts, spinners, and other short-lived states; take a screenshot before acting on positions in them",
+        ages.len(),
+        LOW_DETAIL.width,
+        LOW_DETAIL.height
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn describes_frame_ages() {
+        assert_eq!(
+            describe(&[
+                Duration::from_millis(2_900),
+                Duration::from_millis(1_940),
+                Duration::from_millis(900),
+            ]),
+            "attached the last 3 frame(s), oldest first, captured 2.9 s, 1.9 s and 0.9 s ago. They are 640x360 previews taken about once a second, for spotting toasts, spinners, and other short-lived states; take a screenshot before acting on positions in them"
+        );
+        assert!(describe(&[Duration::from_millis(400)]).contains("captured 0.4 s ago"));
+    }
+}
@@ -0,0 +1,156 @@
+//! Touch gesture emulation for `computer_swipe`, `computer_long_press`, and
+//! `computer_pinch`.
+//!
+//! The X11 backend has no multi-touch input, so gestures map onto the pointer
+//! events touch-first toolkits already accept: a swipe is a stepped drag, a long
+//! press holds the primary button, and a pinch is Ctrl+scroll, which desktop
+//! browsers and image viewers treat as zoom.
+
+use std::time::Duration;
+
+use super::coordinates::MappedPoint;
+
+pub(super) const DEFAULT_SWIPE_DURATION: Duration = Duration::from_millis(300);
+pub(super) const DEFAULT_LONG_PRESS_DURATION: Duration = Duration::from_millis(800);
+pub(super) const DEFAULT_PINCH_TICKS: u32 = 3;
+
+/// Intermediate pointer positions in a swipe, so toolkits that measure
+/// velocity see a continuous motion rather than a jump.
+const SWIPE_STEPS: u32 = 12;
+
+/// xdotool arguments for a swipe from `from` to `to` over `duration`.
+pub(super) fn swipe_args(from: &MappedPoint, to: &MappedPoint, duration: Duration) -> Vec<String> {
+    let pause = seconds(duration / SWIPE_STEPS);
+    let mut args = vec![
+        "mousemove".to_string(),
+        "--sync".to_string(),
+        from.screen_x.to_string(),
+        from.screen_y.to_string(),
+        "mousedown".to_string(),
+        "1".to_string(),
+    ];
+    for step in 1..=SWIPE_STEPS {
+        let fraction = f64::from(step) / f64::from(SWIPE_STEPS);
+        let x = from.screen_x + ((to.screen_x - from.screen_x) as f64 * fraction).round() as i64;
+        let y = from.screen_y + ((to.screen_y - from.screen_y) as f64 * fraction).round() as i64;
+        args.extend([
+            "sleep".to_string(),
+            pause.clone(),
+            "mousemove".to_string(),
+            x.to_string(),
+            y.to_string(),
+        ]);
+    }
+    args.extend(["mouseup".to_string(), "1".to_string()]);
+    args
+}
+
+/// xdotool arguments for holding the primary button at `point` for `duration`.
+pub(super) fn long_press_args(point: &MappedPoint, duration: Duration) -> Vec<String> {
+    vec![
+        "mousemove".to_string(),
+        "--sync".to_string(),
+        point.screen_x.to_string(),
+        point.screen_y.to_string(),
+        "mousedown".to_string(),
+        "1".to_string(),
+        "sleep".to_string(),
+        seconds(duration),
+        "mouseup".to_string(),
+        "1".to_string(),
+    ]
+}
+
+/// xdotool arguments for a pinch centered on `point`. Pinching out (zooming
+/// in) scrolls up with Ctrl held; pinching in scrolls down.
+pub(super) fn pinch_args(point: &MappedPoint, zoom_in: bool, ticks: u32) -> Vec<String> {
+    let button = if zoom_in { "4" } else { "5" };
+    vec![
+        "mousemove".to_string(),
+        "--sync".to_string(),
+        point.screen_x.to_string(),
+        point.screen_y.to_string(),
+        "keydown".to_string(),
+        "ctrl".to_string(),
+        "click".to_string(),
+        "--repeat".to_string(),
+        ticks.max(1).to_string(),
+        button.to_string(),
+        "keyup".to_string(),
+        "ctrl".to_string(),
+    ]
+}
+
+fn seconds(duration: Duration) -> String {
+    format!("{:.3}", duration.as_secs_f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn point(x: i64, y: i64) -> MappedPoint {
+        MappedPoint {
+            model_x: x as f64,
+            model_y: y as f64,
+            screen_x: x,
+            screen_y: y,
+        }
+    }
+
+    #[test]
+    fn swipe_steps_evenly_to_the_target() {
+        let args = swipe_args(&point(0, 100), &point(120, 100), Duration::from_millis(240));
+        assert_eq!(
+            &args[..6],
+            ["mousemove", "--sync", "0", "100", "mousedown", "1"]
+        );
+        assert_eq!(&args[6..11], ["sleep", "0.020", "mousemove", "10", "100"]);
+        assert_eq!(
+            &args[args.len() - 5..],
+            ["mousemove", "120", "100", "mouseup", "1"]
+        );
+        assert_eq!(args.len(), 6 + 5 * SWIPE_STEPS as usize + 2);
+    }
+
+    #[test]
+    fn long_press_holds_for_duration() {
+        assert_eq!(
+            long_press_args(&point(5, 6), Duration::from_millis(800)),
+            [
+                "mousemove",
+                "--sync",
+                "5",
+                "6",
+                "mousedown",
+                "1",
+                "sleep",
+                "0.800",
+                "mouseup",
+                "1",
+            ]
+        );
+    }
+
+    #[test]
+    fn pinch_maps_to_ctrl_scroll() {
+        assert_eq!(
+            pinch_args(&point(1, 2), false, 0),
+            [
+                "mousemove",
+                "--sync",
+                "1",
+                "2",
+                "keydown",
+                "ctrl",
+                "click",
+                "--repeat",
+                "1",
+                "5",
+                "keyup",
+                "ctrl",
+            ]
+        );
+    }
+}
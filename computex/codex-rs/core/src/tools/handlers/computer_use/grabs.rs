@@ -0,0 +1,193 @@
+//! Detecting pointer and keyboard grabs that swallow GUI input.
+//!
+//! While an app holds an X grab (a menu it forgot to close, a drag that lost
+//! its button release), every click and key goes to that app, wherever it
+//! was aimed, and xdotool still reports success. X has no request that names
+//! the grabbing client, but trying to grab the pointer and the keyboard
+//! answers `AlreadyGrabbed` while someone else holds them, and a grab that
+//! succeeds is released right away. A server grab stops the X server from
+//! answering other clients at all, so the probe runs on its own thread and
+//! a missed deadline is reported as one.
+
+use std::sync::mpsc;
+use std::time::Duration;
+
+use x11rb::connection::Connection;
+use x11rb::protocol::xproto::ConnectionExt as _;
+use x11rb::protocol::xproto::EventMask;
+use x11rb::protocol::xproto::GrabMode;
+use x11rb::protocol::xproto::GrabStatus;
+
+use crate::config::types::ComputerUseConfig;
+
+/// How long the X server gets to answer the probe.
+const PROBE_DEADLINE: Duration = Duration::from_secs(2);
+
+/// How far from the screen edge the dismissing click lands, so it misses
+/// hot corners and panels' edge buttons.
+const CORNER_INSET: i32 = 48;
+
+/// Which input devices another client holds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(super) struct Grabs {
+    pub pointer: bool,
+    pub keyboard: bool,
+    /// The X server didn't answer, as while a client grabs the whole server.
+    pub server: bool,
+}
+
+impl Grabs {
+    pub(super) fn any(self) -> bool {
+        self.pointer || self.keyboard || self.server
+    }
+
+    /// What is grabbed, as in "the pointer and keyboard".
+    pub(super) fn describe(self) -> String {
+        match (self.server, self.pointer, self.keyboard) {
+            (true, _, _) => "the whole X server".to_string(),
+            (_, true, true) => "the pointer and keyboard".to_string(),
+            (_, true, false) => "the pointer".to_string(),
+            (_, false, true) => "the keyboard".to_string(),
+            (_, false, false) => "nothing".to_string(),
+        }
+    }
+}
+
+/// What the display looked like when grabs were probed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Probe {
+    pub grabs: Grabs,
+    /// Pointer position and screen size, in screen pixels.
+    pub pointer: (i32, i32),
+    pub screen: (i32, i32),
+}
+
+/// Finds out whether another client grabs the pointer, the keyboard, or the
+/// server.
+pub(super) fn probe(config: &ComputerUseConfig) -> Result<Probe, String> {
+    let display = config.display.clone();
+    let (sender, receiver) = mpsc::channel();
+    let spawned = std::thread::Builder::new()
+        .name("computex-grabs".to_string())
+        .spawn(move || {
+            let _ = sender.send(probe_blocking(display.as_deref()));
+        });
+    if let Err(err) = spawned {
+        return Err(format!("failed to start the grab probe: {err}"));
+    }
+    match receiver.recv_timeout(PROBE_DEADLINE) {
+        Ok(result) => result,
+        Err(_) => Ok(Probe {
+            grabs: Grabs {
+                server: true,
+                ..Grabs::default()
+            },
+            pointer: (0, 0),
+            screen: (0, 0),
+        }),
+    }
+}
+
+fn probe_blocking(display: Option<&str>) -> Result<Probe, String> {
+    let (conn, screen) =
+        x11rb::connect(display).map_err(|err| format!("cannot open X display: {err}"))?;
+    let screen = conn
+        .setup()
+        .roots
+        .get(screen)
+        .ok_or_else(|| format!("X display has no screen {screen}"))?;
+    let root = screen.root;
+    let size = (
+        i32::from(screen.width_in_pixels),
+        i32::from(screen.height_in_pixels),
+    );
+    let error = |err: &dyn std::fmt::Display| format!("X request failed: {err}");
+    let pointer = conn
+        .grab_pointer(
+            false,
+            root,
+            EventMask::NO_EVENT,
+            GrabMode::ASYNC,
+            GrabMode::ASYNC,
+            x11rb::NONE,
+            x11rb::NONE,
+            x11rb::CURRENT_TIME,
+        )
+        .map_err(|err| error(&err))?
+        .reply()
+        .map_err(|err| error(&err))?
+        .status;
+    if pointer == GrabStatus::SUCCESS {
+        conn.ungrab_pointer(x11rb::CURRENT_TIME)
+            .map_err(|err| error(&err))?;
+    }
+    let keyboard = conn
+        .grab_keyboard(
+            false,
+            root,
+            x11rb::CURRENT_TIME,
+            GrabMode::ASYNC,
+            GrabMode::ASYNC,
+        )
+        .map_err(|err| error(&err))?
+        .reply()
+        .map_err(|err| error(&err))?
+        .status;
+    if keyboard == GrabStatus::SUCCESS {
+        conn.ungrab_keyboard(x11rb::CURRENT_TIME)
+            .map_err(|err| error(&err))?;
+    }
+    let at = conn
+        .query_pointer(root)
+        .map_err(|err| error(&err))?
+        .reply()
+        .map_err(|err| error(&err))?;
+    conn.flush().map_err(|err| error(&err))?;
+    let held = |status| status == GrabStatus::ALREADY_GRABBED || status == GrabStatus::FROZEN;
+    Ok(Probe {
+        grabs: Grabs {
+            pointer: held(pointer),
+            keyboard: held(keyboard),
+            server: false,
+        },
+        pointer: (i32::from(at.root_x), i32::from(at.root_y)),
+        screen: size,
+    })
+}
+
+/// Where a click should land to dismiss a stuck menu: near the screen
+/// corner farthest from the pointer, since menus open beside it.
+pub(super) fn dismiss_point(pointer: (i32, i32), screen: (i32, i32)) -> (i32, i32) {
+    let (width, height) = screen;
+    let near_start = |at: i32, size: i32| {
+        if at > size / 2 {
+            CORNER_INSET.min(size / 2)
+        } else {
+            (size - CORNER_INSET).max(size / 2)
+        }
+    };
+    (near_start(pointer.0, width), near_start(pointer.1, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn dismisses_away_from_the_pointer() {
+        assert_eq!(dismiss_point((1800, 1000), (1920, 1080)), (48, 48));
+        assert_eq!(dismiss_point((100, 900), (1920, 1080)), (1872, 48));
+        assert_eq!(dismiss_point((10, 10), (1920, 1080)), (1872, 1032));
+        assert_eq!(dismiss_point((60, 60), (80, 60)), (40, 30));
+        assert_eq!(
+            Grabs {
+                pointer: true,
+                keyboard: true,
+                server: false,
+            }
+            .describe(),
+            "the pointer and keyboard"
+        );
+    }
+}
@@ -0,0 +1,191 @@
+//! Clearing hover effects for a capture, for `computer_use.hover_suppression`.
+//!
+//! A highlighted row or a tooltip in a screenshot looks like app state: the
+//! model reads a hovered button as selected or a tooltip as a dialog. Right
+//! before `computer_screenshot` captures, either the pointer is moved into
+//! `park_cursor_corner` and put back once the capture is taken, or the
+//! windows under it are sent a synthetic `LeaveNotify`, which makes most X
+//! toolkits drop their hover state without the pointer moving at all. Some
+//! apps ignore synthetic events; `move_away` works for those.
+
+use x11rb::connection::Connection;
+use x11rb::protocol::xproto::ConnectionExt as _;
+use x11rb::protocol::xproto::EventMask;
+use x11rb::protocol::xproto::KeyButMask;
+use x11rb::protocol::xproto::LEAVE_NOTIFY_EVENT;
+use x11rb::protocol::xproto::LeaveNotifyEvent;
+use x11rb::protocol::xproto::NotifyDetail;
+use x11rb::protocol::xproto::NotifyMode;
+use x11rb::protocol::xproto::Window;
+
+use super::input_command;
+use super::input_helper;
+use super::parking;
+use super::pause;
+use crate::codex::Session;
+use crate::config::types::ComputerUseConfig;
+use crate::config::types::HoverSuppression;
+
+/// `same_screen_focus` bit saying the pointer is on the event's screen.
+const SAME_SCREEN: u8 = 0x2;
+
+/// Windows nest deeper than this only in a broken window tree.
+const MAX_DEPTH: usize = 32;
+
+/// Where to put the pointer back once the capture is taken.
+pub(super) struct Restore {
+    x: i16,
+    y: i16,
+}
+
+impl Restore {
+    /// Moves the pointer back to where it was before [`suppress`].
+    pub(super) async fn finish(self, session: &Session, config: &ComputerUseConfig) {
+        let Ok(xdotool) = input_command(config) else {
+            return;
+        };
+        let helper = super::input_helper(session, config).await;
+        let args = [
+            "mousemove".to_string(),
+            self.x.to_string(),
+            self.y.to_string(),
+        ];
+        if let Err(err) = input_helper::send_input(helper.as_ref(), &xdotool, &args, config) {
+            tracing::debug!("failed to put the pointer back after a capture: {err:?}");
+        }
+    }
+}
+
+/// Clears hover effects as `config.hover_suppression` says and waits for
+/// them to fade. Returns where the pointer goes back to after `move_away`.
+pub(super) async fn suppress(session: &Session, config: &ComputerUseConfig) -> Option<Restore> {
+    if pause::is_paused() {
+        return None;
+    }
+    let display = config.display.as_deref();
+    let restore = match config.hover_suppression {
+        HoverSuppression::Off => return None,
+        HoverSuppression::MoveAway => {
+            let (x, y) = pointer(display)
+                .map_err(|err| tracing::debug!("failed to read the pointer position: {err}"))
+                .ok()?;
+            if let Err(err) = parking::park(session, config).await {
+                tracing::debug!("failed to move the pointer away for a capture: {err:?}");
+                return None;
+            }
+            Some(Restore { x, y })
+        }
+        HoverSuppression::LeaveEvent => {
+            if let Err(err) = leave_hovered(display) {
+                tracing::debug!("failed to send leave events for a capture: {err}");
+                return None;
+            }
+            None
+        }
+    };
+    tokio::time::sleep(parking::HOVER_CLEAR).await;
+    restore
+}
+
+/// The pointer position on the root window.
+fn pointer(display: Option<&str>) -> Result<(i16, i16), String> {
+    let (conn, screen) =
+        x11rb::connect(display).map_err(|err| format!("cannot open X display: {err}"))?;
+    let root = conn
+        .setup()
+        .roots
+        .get(screen)
+        .ok_or_else(|| format!("X display has no screen {screen}"))?
+        .root;
+    let at = conn
+        .query_pointer(root)
+        .map_err(|err| format!("X request failed: {err}"))?
+        .reply()
+        .map_err(|err| format!("X request failed: {err}"))?;
+    Ok((at.root_x, at.root_y))
+}
+
+/// Sends `LeaveNotify` to each window under the pointer, innermost first.
+fn leave_hovered(display: Option<&str>) -> Result<(), String> {
+    let (conn, screen) =
+        x11rb::connect(display).map_err(|err| format!("cannot open X display: {err}"))?;
+    let root = conn
+        .setup()
+        .roots
+        .get(screen)
+        .ok_or_else(|| format!("X display has no screen {screen}"))?
+        .root;
+    let error = |err: &dyn std::fmt::Display| format!("X request failed: {err}");
+    let mut hovered = Vec::new();
+    let mut window = root;
+    for _ in 0..MAX_DEPTH {
+        let at = conn
+            .query_pointer(window)
+            .map_err(|err| error(&err))?
+            .reply()
+            .map_err(|err| error(&err))?;
+        if window != root {
+            hovered.push(leave_event(
+                root,
+                window,
+                (at.root_x, at.root_y),
+                (at.win_x, at.win_y),
+                at.mask,
+            ));
+        }
+        if at.child == x11rb::NONE {
+            break;
+        }
+        window = at.child;
+    }
+    for event in hovered.iter().rev() {
+        conn.send_event(false, event.event, EventMask::LEAVE_WINDOW, event)
+            .map_err(|err| error(&err))?;
+    }
+    conn.flush().map_err(|err| error(&err))
+}
+
+/// A `LeaveNotify` telling `window` the pointer moved to an unrelated
+/// window, as it would on a move to another app.
+fn leave_event(
+    root: Window,
+    window: Window,
+    at_root: (i16, i16),
+    at_window: (i16, i16),
+    state: KeyButMask,
+) -> LeaveNotifyEvent {
+    LeaveNotifyEvent {
+        response_type: LEAVE_NOTIFY_EVENT,
+        detail: NotifyDetail::NONLINEAR,
+        sequence: 0,
+        time: x11rb::CURRENT_TIME,
+        root,
+        event: window,
+        child: x11rb::NONE,
+        root_x: at_root.0,
+        root_y: at_root.1,
+        event_x: at_window.0,
+        event_y: at_window.1,
+        state,
+        mode: NotifyMode::NORMAL,
+        same_screen_focus: SAME_SCREEN,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn leave_events_address_the_hovered_window() {
+        let event = leave_event(0x100, 0x2a00005, (900, 500), (40, 12), KeyButMask::SHIFT);
+        let bytes: [u8; 32] = (&event).into();
+        assert_eq!(bytes[0], LEAVE_NOTIFY_EVENT);
+        assert_eq!(bytes[1], u8::from(NotifyDetail::NONLINEAR));
+        assert_eq!(&bytes[12..16], &0x2a00005u32.to_ne_bytes());
+        assert_eq!(&bytes[20..22], &900i16.to_ne_bytes());
+        assert_eq!(&bytes[24..26], &40i16.to_ne_bytes());
+        assert_eq!(bytes[31], SAME_SCREEN);
+    }
+}
@@ -0,0 +1,319 @@
+//! Waiting for an app to go quiet, for `computer_wait_app_idle`.
+//!
+//! A compile dialog, an import, or an installer can sit on an unchanging
+//! progress bar while it works, so visual stability says little about
+//! whether it is done. The process itself knows better: it stops using CPU
+//! and stops reading and writing the disk. The target process and all of
+//! its descendants are sampled from `/proc`, and the wait ends once their
+//! combined CPU and disk use has stayed under the thresholds for a while.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use std::time::Instant;
+
+use super::recovery::regex_escape;
+use super::recovery::xdotool_output;
+use crate::config::types::ComputerUseConfig;
+use crate::function_tool::FunctionCallError;
+
+/// Default and maximum wait.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+const MAX_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// CPU use, in percent of one core, below which the app counts as idle.
+const DEFAULT_THRESHOLD: f64 = 5.0;
+
+/// Disk throughput below which the app counts as idle.
+const IO_THRESHOLD: u64 = 256 * 1024;
+
+/// How long both must stay under their thresholds.
+const QUIET_FOR: Duration = Duration::from_secs(2);
+
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// `USER_HZ`, the unit of the CPU times in `/proc/<pid>/stat`. Linux fixes
+/// it at 100 for userspace on every architecture it still supports.
+const TICKS_PER_SECOND: f64 = 100.0;
+
+/// Resolves the process to watch: `pid`, the owner of the first window
+/// whose class is `wm_class`, or the owner of the focused window.
+pub(super) fn target(
+    xdotool: Option<&Path>,
+    config: &ComputerUseConfig,
+    pid: Option<u32>,
+    wm_class: Option<&str>,
+) -> Result<u32, FunctionCallError> {
+    let wm_class = match (pid, wm_class) {
+        (Some(_), Some(_)) => {
+            return Err(FunctionCallError::RespondToModel(
+                "pass either pid or wm_class, not both".to_string(),
+            ));
+        }
+        (Some(pid), None) => return Ok(pid),
+        (None, wm_class) => wm_class.map(str::trim),
+    };
+    let xdotool = xdotool.ok_or_else(|| {
+        FunctionCallError::RespondToModel(
+            "finding the app's process needs xdotool; pass pid instead".to_string(),
+        )
+    })?;
+    let id = match wm_class {
+        Some(class) => {
+            let pattern = format!("^{}$", regex_escape(class));
+            xdotool_output(xdotool, config, &["search", "--class", &pattern])
+                .unwrap_or_default()
+                .lines()
+                .next()
+                .map(str::to_string)
+                .ok_or_else(|| {
+                    FunctionCallError::RespondToModel(format!("no window has class `{class}`"))
+                })?
+        }
+        None => xdotool_output(xdotool, config, &["getactivewindow"])?,
+    };
+    xdotool_output(xdotool, config, &["getwindowpid", &id])?
+        .parse()
+        .map_err(|_| {
+            FunctionCallError::RespondToModel(format!(
+                "window {id} doesn't say which process owns it; pass pid instead"
+            ))
+        })
+}
+
+/// Waits until `pid` and its descendants settle under `threshold` percent
+/// CPU (default 5). Returns the tool result.
+pub(super) async fn wait(
+    pid: u32,
+    threshold: Option<f64>,
+    timeout_seconds: Option<u64>,
+) -> Result<String, FunctionCallError> {
+    let threshold = threshold.unwrap_or(DEFAULT_THRESHOLD);
+    if !threshold.is_finite() || threshold <= 0.0 {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "threshold must be a positive CPU percentage, got {threshold}"
+        )));
+    }
+    let timeout = timeout_seconds
+        .map_or(DEFAULT_TIMEOUT, Duration::from_secs)
+        .min(MAX_TIMEOUT);
+    tokio::task::spawn_blocking(move || watch(pid, threshold, timeout))
+        .await
+        .map_err(|err| FunctionCallError::RespondToModel(format!("idle watcher failed: {err}")))?
+        .map_err(FunctionCallError::RespondToModel)
+}
+
+fn watch(pid: u32, threshold: f64, timeout: Duration) -> Result<String, String> {
+    let start = Instant::now();
+    let name = command_name(pid).ok_or_else(|| format!("process {pid} is not running"))?;
+    let first = sample(pid).ok_or_else(|| format!("process {pid} is not running"))?;
+    let mut previous = (Instant::now(), first);
+    let mut quiet_since: Option<Instant> = None;
+    loop {
+        std::thread::sleep(SAMPLE_INTERVAL);
+        let now = Instant::now();
+        let Some(current) = sample(pid) else {
+            return Ok(format!(
+                "idle: {name} (pid {pid}) exited after {:.1} s",
+                start.elapsed().as_secs_f64()
+            ));
+        };
+        let last = usage(&previous.1, &current, now - previous.0);
+        previous = (now, current);
+        if last.cpu_percent < threshold && last.io_per_second < IO_THRESHOLD {
+            let since = *quiet_since.get_or_insert(now - SAMPLE_INTERVAL);
+            if now - since >= QUIET_FOR {
+                return Ok(format!(
+                    "idle: {name} (pid {pid}) settled after {:.1} s; {}",
+                    start.elapsed().as_secs_f64(),
+                    last.describe()
+                ));
+            }
+        } else {
+            quiet_since = None;
+        }
+        if start.elapsed() >= timeout {
+            return Err(format!(
+                "{name} (pid {pid}) was still busy after {} s; last reading {}",
+                timeout.as_secs(),
+                last.describe()
+            ));
+        }
+    }
+}
+
+/// Cumulative counters for a process tree at one moment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Sample {
+    /// User plus system CPU time, in ticks.
+    ticks: u64,
+    /// Bytes read from and written to storage.
+    bytes: u64,
+    processes: usize,
+}
+
+/// Rates between two samples.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Usage {
+    cpu_percent: f64,
+    io_per_second: u64,
+    processes: usize,
+}
+
+impl Usage {
+    fn describe(&self) -> String {
+        let processes = match self.processes {
+            1 => "1 process".to_string(),
+            count => format!("{count} processes"),
+        };
+        format!(
+            "{:.1}% CPU, {} KiB/s disk across {processes}",
+            self.cpu_percent,
+            self.io_per_second / 1024
+        )
+    }
+}
+
+fn usage(before: &Sample, after: &Sample, elapsed: Duration) -> Usage {
+    let seconds = elapsed.as_secs_f64().max(f64::EPSILON);
+    // Counters of processes that exited in between are gone from `after`.
+    let ticks = after.ticks.saturating_sub(before.ticks) as f64;
+    let bytes = after.bytes.saturating_sub(before.bytes) as f64;
+    Usage {
+        cpu_percent: ticks / TICKS_PER_SECOND / seconds * 100.0,
+        io_per_second: (bytes / seconds) as u64,
+        processes: after.processes,
+    }
+}
+
+/// Counters summed over `pid` and its descendants, or `None` once `pid`
+/// is gone.
+fn sample(pid: u32) -> Option<Sample> {
+    let root = stat(pid)?;
+    let stats: HashMap<u32, Stat> = fs::read_dir("/proc")
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+        .filter_map(|other| Some((other, stat(other)?)))
+        .collect();
+    let parents = stats
+        .iter()
+        .map(|(&other, stat)| (other, stat.parent))
+        .collect();
+    let mut total = Sample {
+        ticks: root.ticks,
+        bytes: io_bytes(pid),
+        processes: 1,
+    };
+    for child in descendants(pid, &parents) {
+        total.ticks += stats[&child].ticks;
+        total.bytes += io_bytes(child);
+        total.processes += 1;
+    }
+    Some(total)
+}
+
+/// The processes below `pid` in the tree given by each process's parent.
+fn descendants(pid: u32, parents: &HashMap<u32, u32>) -> Vec<u32> {
+    let mut found = Vec::new();
+    let mut frontier = vec![pid];
+    while let Some(parent) = frontier.pop() {
+        for (&child, _) in parents.iter().filter(|(_, p)| **p == parent) {
+            if child != pid && !found.contains(&child) {
+                found.push(child);
+                frontier.push(child);
+            }
+        }
+    }
+    found.sort_unstable();
+    found
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Stat {
+    parent: u32,
+    ticks: u64,
+}
+
+fn stat(pid: u32) -> Option<Stat> {
+    parse_stat(&fs::read_to_string(format!("/proc/{pid}/stat")).ok()?)
+}
+
+/// Parent and CPU ticks from a `/proc/<pid>/stat` line. The command name
+/// in parentheses may itself contain spaces and parentheses, so fields are
+/// counted from the last `)`.
+fn parse_stat(line: &str) -> Option<Stat> {
+    let fields: Vec<&str> = line[line.rfind(')')? + 1..].split_whitespace().collect();
+    // `state` is field 3 of the line; `ppid` 4, `utime` 14, `stime` 15.
+    let field = |number: usize| fields.get(number - 3)?.parse::<u64>().ok();
+    Some(Stat {
+        parent: u32::try_from(field(4)?).ok()?,
+        ticks: field(14)? + field(15)?,
+    })
+}
+
+/// Storage bytes `pid` has read and written; 0 when `/proc/<pid>/io` is
+/// unreadable, as it is for another user's processes.
+fn io_bytes(pid: u32) -> u64 {
+    fs::read_to_string(format!("/proc/{pid}/io"))
+        .map(|text| parse_io(&text))
+        .unwrap_or(0)
+}
+
+fn parse_io(text: &str) -> u64 {
+    text.lines()
+        .filter_map(|line| line.split_once(':'))
+        .filter(|(key, _)| matches!(*key, "read_bytes" | "write_bytes"))
+        .filter_map(|(_, value)| value.trim().parse::<u64>().ok())
+        .sum()
+}
+
+fn command_name(pid: u32) -> Option<String> {
+    let name = fs::read_to_string(format!("/proc/{pid}/comm")).ok()?;
+    Some(name.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn sums_cpu_and_disk_use_over_the_process_tree() {
+        let line = "4242 (Web Content (x)) S 4200 4200 4200 0 -1 4194560 9 0 0 0 150 37 0 0 20 0 12 0 800 0 0";
+        assert_eq!(
+            parse_stat(line),
+            Some(Stat {
+                parent: 4200,
+                ticks: 187
+            })
+        );
+        assert_eq!(parse_stat("4242 (truncated"), None);
+
+        let io = "rchar: 900\nwchar: 50\nsyscr: 3\nread_bytes: 4096\nwrite_bytes: 8192\ncancelled_write_bytes: 0\n";
+        assert_eq!(parse_io(io), 12288);
+
+        let parents = HashMap::from([(10, 1), (11, 10), (12, 11), (13, 1), (14, 10)]);
+        assert_eq!(descendants(10, &parents), vec![11, 12, 14]);
+
+        let before = Sample {
+            ticks: 1000,
+            bytes: 0,
+            processes: 3,
+        };
+        let after = Sample {
+            ticks: 1050,
+            bytes: 512 * 1024,
+            processes: 2,
+        };
+        let busy = usage(&before, &after, Duration::from_millis(500));
+        assert_eq!(busy.cpu_percent, 100.0);
+        assert_eq!(busy.io_per_second, 1024 * 1024);
+        assert_eq!(
+            busy.describe(),
+            "100.0% CPU, 1024 KiB/s disk across 2 processes"
+        );
+    }
+}
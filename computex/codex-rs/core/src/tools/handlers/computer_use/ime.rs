@@ -0,0 +1,167 @@
+//! Typing CJK text past an input method.
+//!
+//! With an IME active, the keys `computer_type` synthesizes go through the
+//! IME's composition first, so Chinese, Japanese, or Korean text turns into
+//! candidate popups and half-converted syllables. Neither ibus nor fcitx lets
+//! an outside client commit text into another app's input context, but both
+//! can be switched to direct input over their control interfaces: fcitx5's
+//! `org.fcitx.Fcitx.Controller1` on the session bus and the `ibus` command
+//! for ibus's private bus. While direct input is on, xdotool's keysym
+//! typing commits each character as-is. [`ImeSuspension`] switches the IME
+//! back when dropped.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use which::which;
+
+use super::gui_command;
+use crate::config::types::ComputerUseConfig;
+
+/// Engine ibus switches to for direct input.
+const IBUS_DIRECT_ENGINE: &str = "xkb:us::eng";
+
+const FCITX5_DEST: &str = "--dest=org.fcitx.Fcitx5";
+const FCITX5_PATH: &str = "/controller";
+
+/// Whether `text` contains characters an IME would compose.
+pub(super) fn has_cjk(text: &str) -> bool {
+    text.chars().any(|c| {
+        matches!(c,
+            '\u{1100}'..='\u{11FF}' // Hangul Jamo
+            | '\u{3040}'..='\u{30FF}' // Hiragana, Katakana
+            | '\u{3130}'..='\u{318F}' // Hangul Compatibility Jamo
+            | '\u{3400}'..='\u{4DBF}' // CJK Extension A
+            | '\u{4E00}'..='\u{9FFF}' // CJK Unified Ideographs
+            | '\u{AC00}'..='\u{D7AF}' // Hangul Syllables
+            | '\u{F900}'..='\u{FAFF}' // CJK Compatibility Ideographs
+            | '\u{FF65}'..='\u{FF9F}' // Halfwidth Katakana
+            | '\u{20000}'..='\u{2FA1F}' // CJK Extensions B and later
+        )
+    })
+}
+
+/// An IME switched to direct input, switched back on drop.
+#[derive(Debug)]
+pub(super) struct ImeSuspension {
+    config: ComputerUseConfig,
+    restore: Restore,
+}
+
+#[derive(Debug)]
+enum Restore {
+    /// Reactivate fcitx5 through `dbus-send`.
+    Fcitx5 { dbus_send: PathBuf },
+    /// Switch ibus back to `engine`.
+    Ibus { ibus: PathBuf, engine: String },
+}
+
+impl ImeSuspension {
+    /// Switches the active IME to direct input. `None` when no known IME is
+    /// composing.
+    pub(super) fn begin(config: &ComputerUseConfig) -> Option<Self> {
+        if let Ok(dbus_send) = which("dbus-send")
+            && fcitx5_active(&dbus_send, config)
+            && fcitx5_call(&dbus_send, config, "Deactivate").is_some()
+        {
+            return Some(Self {
+                config: config.clone(),
+                restore: Restore::Fcitx5 { dbus_send },
+            });
+        }
+        let ibus = which("ibus").ok()?;
+        let engine = ibus_engine(&ibus, config)?;
+        if is_direct_ibus_engine(&engine) {
+            return None;
+        }
+        gui_command(&ibus, config)
+            .args(["engine", IBUS_DIRECT_ENGINE])
+            .status()
+            .ok()
+            .filter(std::process::ExitStatus::success)?;
+        Some(Self {
+            config: config.clone(),
+            restore: Restore::Ibus { ibus, engine },
+        })
+    }
+
+    /// Name of the suspended IME, for tool results.
+    pub(super) fn name(&self) -> &'static str {
+        match self.restore {
+            Restore::Fcitx5 { .. } => "fcitx5",
+            Restore::Ibus { .. } => "ibus",
+        }
+    }
+}
+
+impl Drop for ImeSuspension {
+    fn drop(&mut self) {
+        let restored = match &self.restore {
+            Restore::Fcitx5 { dbus_send } => {
+                fcitx5_call(dbus_send, &self.config, "Activate").is_some()
+            }
+            Restore::Ibus { ibus, engine } => gui_command(ibus, &self.config)
+                .args(["engine", engine])
+                .status()
+                .is_ok_and(|status| status.success()),
+        };
+        if !restored {
+            tracing::warn!("failed to switch the {} input method back on", self.name());
+        }
+    }
+}
+
+/// Calls a no-argument `Controller1` method and returns its reply.
+fn fcitx5_call(dbus_send: &Path, config: &ComputerUseConfig, method: &str) -> Option<String> {
+    let output = gui_command(dbus_send, config)
+        .args([
+            "--session",
+            "--print-reply=literal",
+            "--reply-timeout=500",
+            FCITX5_DEST,
+            FCITX5_PATH,
+            &format!("org.fcitx.Fcitx.Controller1.{method}"),
+        ])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// `State` is 2 while an input method is composing, 1 for direct input.
+fn fcitx5_active(dbus_send: &Path, config: &ComputerUseConfig) -> bool {
+    fcitx5_call(dbus_send, config, "State").is_some_and(|reply| {
+        reply
+            .split_whitespace()
+            .last()
+            .is_some_and(|state| state == "2")
+    })
+}
+
+fn ibus_engine(ibus: &Path, config: &ComputerUseConfig) -> Option<String> {
+    let output = gui_command(ibus, config).arg("engine").output().ok()?;
+    let engine = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !engine.is_empty()).then_some(engine)
+}
+
+/// Keyboard-layout engines (`xkb:*`) type characters directly.
+fn is_direct_ibus_engine(engine: &str) -> bool {
+    engine.starts_with("xkb:")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_text_an_ime_would_compose() {
+        assert!(has_cjk("東京へ行く"));
+        assert!(has_cjk("name: 김민수"));
+        assert!(has_cjk("カタカナ"));
+        assert!(!has_cjk("café, naïve – ok"));
+        assert!(is_direct_ibus_engine("xkb:de::ger"));
+        assert!(!is_direct_ibus_engine("mozc-jp"));
+    }
+}
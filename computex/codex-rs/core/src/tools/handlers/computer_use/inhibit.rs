@@ -0,0 +1,159 @@
+//! Keeps the display awake while a session drives it.
+//!
+//! A blanked or powered-down monitor makes screenshots black and, on many
+//! desktops, locks the screen. On the first GUI action of a session the
+//! handler takes an idle inhibitor through `systemd-inhibit`, which GNOME, KDE
+//! and other logind-aware desktops honor, and turns off the X screen saver and
+//! DPMS with `xset`. Dropping the inhibitor, when the session ends, restores
+//! the `xset` settings and releases the idle lock.
+
+use std::path::Path;
+use std::process::Child;
+use std::process::Stdio;
+
+use which::which;
+
+use super::gui_command;
+use crate::config::types::ComputerUseConfig;
+
+/// X screen saver and DPMS settings as reported by `xset q`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct XsetState {
+    timeout: u32,
+    cycle: u32,
+    blanking: bool,
+    dpms: bool,
+}
+
+/// Held for the duration of a GUI session; restores the display's power
+/// settings when dropped.
+#[derive(Debug)]
+pub(super) struct DisplayInhibitor {
+    config: ComputerUseConfig,
+    /// `systemd-inhibit` holding the idle lock until its stdin closes.
+    idle_lock: Option<Child>,
+    /// Settings to put back, when `xset` changed them.
+    saved_xset: Option<XsetState>,
+}
+
+impl DisplayInhibitor {
+    /// Inhibits blanking with whatever tools are installed. Missing tools are
+    /// skipped; the result is never an error.
+    pub(super) fn acquire(config: &ComputerUseConfig) -> Self {
+        let idle_lock = which("systemd-inhibit").ok().and_then(|program| {
+            std::process::Command::new(program)
+                .args([
+                    "--what=idle",
+                    "--who=computex",
+                    "--why=GUI automation in progress",
+                    "--mode=block",
+                    "cat",
+                ])
+                .stdin(Stdio::piped())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+                .inspect_err(|err| tracing::debug!("failed to start systemd-inhibit: {err}"))
+                .ok()
+        });
+        let saved_xset = which("xset")
+            .ok()
+            .and_then(|xset| disable_xset_blanking(&xset, config));
+        Self {
+            config: config.clone(),
+            idle_lock,
+            saved_xset,
+        }
+    }
+}
+
+impl Drop for DisplayInhibitor {
+    fn drop(&mut self) {
+        if let Some(mut child) = self.idle_lock.take() {
+            // Closing stdin ends `cat`, which releases the inhibitor.
+            drop(child.stdin.take());
+            let _ = child.wait();
+        }
+        if let Some(saved) = self.saved_xset.take()
+            && let Ok(xset) = which("xset")
+        {
+            restore_xset(&xset, &self.config, &saved);
+        }
+    }
+}
+
+/// Turns off the screen saver and DPMS, returning the settings to restore.
+fn disable_xset_blanking(xset: &Path, config: &ComputerUseConfig) -> Option<XsetState> {
+    let output = gui_command(xset, config).arg("q").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let saved = parse_xset_query(&String::from_utf8_lossy(&output.stdout))?;
+    for args in [&["s", "off"][..], &["-dpms"][..]] {
+        if let Err(err) = gui_command(xset, config).args(args).status() {
+            tracing::debug!("failed to run xset {args:?}: {err}");
+        }
+    }
+    Some(saved)
+}
+
+fn restore_xset(xset: &Path, config: &ComputerUseConfig, saved: &XsetState) {
+    let timeout = saved.timeout.to_string();
+    let cycle = saved.cycle.to_string();
+    let restore: [&[&str]; 3] = [
+        &["s", &timeout, &cycle],
+        &["s", if saved.blanking { "blank" } else { "noblank" }],
+        &[if saved.dpms { "+dpms" } else { "-dpms" }],
+    ];
+    for args in restore {
+        if let Err(err) = gui_command(xset, config).args(args).status() {
+            tracing::debug!("failed to run xset {args:?}: {err}");
+        }
+    }
+}
+
+fn parse_xset_query(output: &str) -> Option<XsetState> {
+    let value_after = |label: &str| {
+        output.lines().find_map(|line| {
+            let (_, rest) = line.split_once(label)?;
+            rest.split_whitespace().next().map(str::to_string)
+        })
+    };
+    Some(XsetState {
+        timeout: value_after("timeout:")?.parse().ok()?,
+        cycle: value_after("cycle:")?.parse().ok()?,
+        blanking: value_after("prefer blanking:").as_deref() == Some("yes"),
+        dpms: output.contains("DPMS is Enabled"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_screen_saver_and_dpms_settings() {
+        let output = "\
+Keyboard Control:
+  auto repeat:  on    key click percent:  0    LED mask:  00000000
+Screen Saver:
+  prefer blanking:  yes    allow exposures:  yes
+  timeout:  600    cycle:  600
+DPMS (Energy Star):
+  Standby: 600    Suspend: 600    Off: 600
+  DPMS is Enabled
+  Monitor is On
+";
+        assert_eq!(
+            parse_xset_query(output),
+            Some(XsetState {
+                timeout: 600,
+                cycle: 600,
+                blanking: true,
+                dpms: true,
+            })
+        );
+        assert_eq!(parse_xset_query("Keyboard Control:\n"), None);
+    }
+}
@@ -0,0 +1,675 @@
+//! Long-lived input helper for `computer_use.persistent_input`.
+//!
+//! Running `xdotool` costs a process start and an X connection handshake per
+//! call, several times per action once focus checks are counted, which adds
+//! up over long automation runs. The helper is a thread that owns one X
+//! connection for the session and injects input through the XTEST extension.
+//! The handler sends it the same xdotool argument lists it would otherwise
+//! run; anything the helper can't replay exactly (typing text, window
+//! management, keys missing from the keymap) still goes through `xdotool`.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use which::which;
+use x11rb::connection::Connection;
+use x11rb::connection::RequestConnection;
+use x11rb::errors::ConnectionError;
+use x11rb::errors::ReplyError;
+use x11rb::protocol::Event;
+use x11rb::protocol::xproto;
+use x11rb::protocol::xproto::AtomEnum;
+use x11rb::protocol::xproto::ConnectionExt as _;
+use x11rb::protocol::xtest;
+use x11rb::protocol::xtest::ConnectionExt as _;
+use x11rb::rust_connection::RustConnection;
+
+use super::bundled;
+use super::confinement;
+use super::portal;
+use super::qmp;
+use super::recovery;
+use super::recovery::TargetWindow;
+use super::run_command;
+use super::ydotool;
+use crate::config::types::ComputerUseBackend;
+use crate::config::types::ComputerUseConfig;
+use crate::config::types::InputProvider;
+use crate::function_tool::FunctionCallError;
+
+/// Pause between repeated clicks or key presses, matching xdotool's default
+/// `--delay`.
+const REPEAT_DELAY: Duration = Duration::from_millis(12);
+
+/// One input event, parsed from xdotool arguments.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum InputStep {
+    MoveTo { x: i16, y: i16 },
+    Button { button: u8, press: bool },
+    Key { name: String, press: bool },
+    Pause(Duration),
+}
+
+#[derive(Debug)]
+pub(super) enum HelperError {
+    /// The helper can't replay the request exactly; use xdotool instead.
+    Unsupported,
+    /// The X connection failed or the helper thread is gone.
+    Disconnected(String),
+}
+
+impl From<ConnectionError> for HelperError {
+    fn from(err: ConnectionError) -> Self {
+        HelperError::Disconnected(err.to_string())
+    }
+}
+
+impl From<ReplyError> for HelperError {
+    fn from(err: ReplyError) -> Self {
+        match err {
+            ReplyError::ConnectionError(err) => err.into(),
+            // e.g. BadWindow for a window that closed mid-request.
+            ReplyError::X11Error(_) => HelperError::Unsupported,
+        }
+    }
+}
+
+enum Request {
+    Input(Vec<InputStep>),
+    ScreenSize,
+    ActiveWindow,
+}
+
+enum Reply {
+    Done,
+    ScreenSize(f64, f64),
+    ActiveWindow(Option<TargetWindow>),
+}
+
+type Envelope = (Request, mpsc::Sender<Result<Reply, HelperError>>);
+
+/// Handle to the helper thread. Clones share the thread, which exits once
+/// every handle is dropped or its X connection fails.
+#[derive(Debug, Clone)]
+pub(super) struct InputHelper {
+    requests: mpsc::Sender<Envelope>,
+    alive: Arc<AtomicBool>,
+}
+
+impl InputHelper {
+    /// Starts a helper connected to `display` (or `$DISPLAY`), failing if the
+    /// display is unreachable or lacks XTEST.
+    pub(super) fn spawn(display: Option<&str>) -> Result<Self, String> {
+        let (ready_tx, ready_rx) = mpsc::channel();
+        let (requests, inbox) = mpsc::channel::<Envelope>();
+        let alive = Arc::new(AtomicBool::new(true));
+        let thread_alive = Arc::clone(&alive);
+        let display = display.map(str::to_string);
+        thread::Builder::new()
+            .name("computer-use-input".to_string())
+            .spawn(move || {
+                match XtestInjector::open(display.as_deref()) {
+                    Ok(mut injector) => {
+                        let _ = ready_tx.send(Ok(()));
+                        injector.serve(inbox);
+                    }
+                    Err(err) => {
+                        let _ = ready_tx.send(Err(err));
+                    }
+                }
+                thread_alive.store(false, Ordering::Release);
+            })
+            .map_err(|err| format!("failed to start input helper: {err}"))?;
+        ready_rx
+            .recv()
+            .map_err(|_| "input helper exited during startup".to_string())??;
+        Ok(Self { requests, alive })
+    }
+
+    pub(super) fn is_alive(&self) -> bool {
+        self.alive.load(Ordering::Acquire)
+    }
+
+    fn ask(&self, request: Request) -> Result<Reply, HelperError> {
+        let gone = || HelperError::Disconnected("input helper exited".to_string());
+        let (reply_tx, reply_rx) = mpsc::channel();
+        self.requests
+            .send((request, reply_tx))
+            .map_err(|_| gone())?;
+        reply_rx.recv().map_err(|_| gone())?
+    }
+
+    fn run(&self, steps: Vec<InputStep>) -> Result<(), HelperError> {
+        self.ask(Request::Input(steps)).map(|_| ())
+    }
+
+    pub(super) fn screen_size(&self) -> Result<(f64, f64), HelperError> {
+        match self.ask(Request::ScreenSize)? {
+            Reply::ScreenSize(width, height) => Ok((width, height)),
+            _ => Err(HelperError::Unsupported),
+        }
+    }
+
+    fn active_window(&self) -> Result<Option<TargetWindow>, HelperError> {
+        match self.ask(Request::ActiveWindow)? {
+            Reply::ActiveWindow(window) => Ok(window),
+            _ => Err(HelperError::Unsupported),
+        }
+    }
+}
+
+/// Sends xdotool `args` through the first of `computer_use.input_providers`
+/// that can replay them: `helper`, the bundled helper, `xdotool`, ydotool,
+/// or the remote desktop portal.
+pub(super) fn send_input(
+    helper: Option<&InputHelper>,
+    xdotool: &Path,
+    args: &[String],
+    config: &ComputerUseConfig,
+) -> Result<(), FunctionCallError> {
+    send_input_via(helper, xdotool, args, config).map(|_| ())
+}
+
+/// [`send_input`], returning the provider that delivered the input.
+pub(super) fn send_input_via(
+    helper: Option<&InputHelper>,
+    xdotool: &Path,
+    args: &[String],
+    config: &ComputerUseConfig,
+) -> Result<InputProvider, FunctionCallError> {
+    // Providers that can't send `args` are skipped. One that ran and failed
+    // ends the search, since part of the input may have gone through.
+    for provider in &config.input_providers {
+        match provider {
+            InputProvider::Native => {
+                if let Some(helper) = helper
+                    && let Some(steps) = parse_steps(args)
+                    && helper.run(steps).is_ok()
+                {
+                    return Ok(*provider);
+                }
+            }
+            InputProvider::Bundled => {
+                if let Some(result) =
+                    bundled::helper().and_then(|helper| bundled::send(&helper, args, config))
+                {
+                    return result.map(|()| *provider);
+                }
+            }
+            InputProvider::Xdotool => {
+                return run_command(xdotool, args, config).map(|()| *provider);
+            }
+            InputProvider::Ydotool => {
+                if let Some(result) = which("ydotool")
+                    .ok()
+                    .and_then(|ydotool| ydotool::send(&ydotool, args, config))
+                {
+                    return result.map(|()| *provider);
+                }
+            }
+            InputProvider::Portal => {
+                if let Some(result) = portal::send(args) {
+                    return result.map(|()| *provider);
+                }
+            }
+            InputProvider::Qmp => {
+                if let Some(result) = qmp::send(config, args) {
+                    return result.map(|()| *provider);
+                }
+            }
+        }
+    }
+    Err(FunctionCallError::RespondToModel(confinement::explain(
+        "none of computer_use.input_providers can send this input; add \"xdotool\" to the list"
+            .to_string(),
+    )))
+}
+
+/// [`recovery::active_window`], answered by `helper` when it's running.
+pub(super) fn active_window(
+    helper: Option<&InputHelper>,
+    xdotool: &Path,
+    config: &ComputerUseConfig,
+) -> Result<TargetWindow, FunctionCallError> {
+    // A VM's console has no windows, and this machine's must not stand in.
+    if config.backend == ComputerUseBackend::Qemu {
+        return Err(FunctionCallError::RespondToModel(
+            "the QEMU console has no windows".to_string(),
+        ));
+    }
+    match helper.map(InputHelper::active_window) {
+        Some(Ok(Some(window))) => Ok(window),
+        Some(Ok(None)) => Err(FunctionCallError::RespondToModel(
+            "no window has focus".to_string(),
+        )),
+        _ => recovery::active_window(xdotool, config),
+    }
+}
+
+/// Parses the xdotool commands the handler builds for pointer and key input.
+/// Returns `None` for anything else, such as `type`.
+pub(super) fn parse_steps(args: &[String]) -> Option<Vec<InputStep>> {
+    let mut steps = Vec::new();
+    let mut args = args.iter().map(String::as_str).peekable();
+    while let Some(command) = args.next() {
+        match command {
+            "mousemove" => {
+                // XTEST requests are processed in order, so `--sync` holds.
+                args.next_if_eq(&"--sync");
+                let x = args.next()?.parse().ok()?;
+                let y = args.next()?.parse().ok()?;
+                steps.push(InputStep::MoveTo { x, y });
+            }
+            "click" => {
+                let repeat = repeat_count(&mut args)?;
+                let button = args.next()?.parse().ok()?;
+                for press in 0..repeat {
+                    if press > 0 {
+                        steps.push(InputStep::Pause(REPEAT_DELAY));
+                    }
+                    steps.push(InputStep::Button {
+                        button,
+                        press: true,
+                    });
+                    steps.push(InputStep::Button {
+                        button,
+                        press: false,
+                    });
+                }
+            }
+            "mousedown" | "mouseup" => {
+                let button = args.next()?.parse().ok()?;
+                steps.push(InputStep::Button {
+                    button,
+                    press: command == "mousedown",
+                });
+            }
+            "keydown" | "keyup" => {
+                let press = command == "keydown";
+                for name in args.next()?.split('+') {
+                    steps.push(InputStep::Key {
+                        name: name.to_string(),
+                        press,
+                    });
+                }
+            }
+            "key" => {
+                let repeat = repeat_count(&mut args)?;
+                let keys: Vec<&str> = args.next()?.split('+').collect();
+                for press in 0..repeat {
+                    if press > 0 {
+                        steps.push(InputStep::Pause(REPEAT_DELAY));
+                    }
+                    for name in &keys {
+                        steps.push(InputStep::Key {
+                            name: (*name).to_string(),
+                            press: true,
+                        });
+                    }
+                    for name in keys.iter().rev() {
+                        steps.push(InputStep::Key {
+                            name: (*name).to_string(),
+                            press: false,
+                        });
+                    }
+                }
+            }
+            "sleep" => {
+                let seconds: f64 = args.next()?.parse().ok()?;
+                steps.push(InputStep::Pause(Duration::try_from_secs_f64(seconds).ok()?));
+            }
+            _ => return None,
+        }
+    }
+    Some(steps)
+}
+
+fn repeat_count<'a>(args: &mut std::iter::Peekable<impl Iterator<Item = &'a str>>) -> Option<u32> {
+    if args.next_if_eq(&"--repeat").is_some() {
+        args.next()?.parse().ok()
+    } else {
+        Some(1)
+    }
+}
+
+/// The X keysym xdotool would send for `name`: its modifier aliases, common
+/// keysym names, and single unshifted characters.
+fn keysym(name: &str) -> Option<u32> {
+    let alias = match name.to_ascii_lowercase().as_str() {
+        "ctrl" | "control" => Some(0xffe3),
+        "shift" => Some(0xffe1),
+        "alt" => Some(0xffe9),
+        "super" => Some(0xffeb),
+        "meta" => Some(0xffe7),
+        _ => None,
+    };
+    if alias.is_some() {
+        return alias;
+    }
+    let named = match name {
+        "Control_L" => 0xffe3,
+        "Shift_L" => 0xffe1,
+        "Alt_L" => 0xffe9,
+        "Super_L" => 0xffeb,
+        "Return" => 0xff0d,
+        "Tab" => 0xff09,
+        "Escape" => 0xff1b,
+        "BackSpace" => 0xff08,
+        "Delete" => 0xffff,
+        "Insert" => 0xff63,
+        "Home" => 0xff50,
+        "Left" => 0xff51,
+        "Up" => 0xff52,
+        "Right" => 0xff53,
+        "Down" => 0xff54,
+        "Page_Up" | "Prior" => 0xff55,
+        "Page_Down" | "Next" => 0xff56,
+        "End" => 0xff57,
+        "space" => 0x20,
+        _ => {
+            if let Some(number) = name.strip_prefix('F')
+                && let Ok(number @ 1..=12) = number.parse::<u32>()
+            {
+                return Some(0xffbe + number - 1);
+            }
+            let mut chars = name.chars();
+            return match (chars.next(), chars.next()) {
+                (Some(c), None) if c.is_ascii_graphic() => Some(u32::from(c)),
+                _ => None,
+            };
+        }
+    };
+    Some(named)
+}
+
+/// A fully resolved XTEST request, or a pause between them.
+enum FakeEvent {
+    Input {
+        kind: u8,
+        detail: u8,
+        x: i16,
+        y: i16,
+    },
+    Pause(Duration),
+}
+
+/// The helper thread's side: one X connection and its keymap.
+struct XtestInjector {
+    conn: RustConnection,
+    root: xproto::Window,
+    net_active_window: xproto::Atom,
+    /// Keycode for each keysym reachable without modifiers.
+    keycodes: HashMap<u32, u8>,
+}
+
+impl XtestInjector {
+    fn open(display: Option<&str>) -> Result<Self, String> {
+        let (conn, screen) =
+            x11rb::connect(display).map_err(|err| format!("cannot open X display: {err}"))?;
+        let root = conn
+            .setup()
+            .roots
+            .get(screen)
+            .map(|screen| screen.root)
+            .ok_or_else(|| format!("X display has no screen {screen}"))?;
+        let has_xtest = conn
+            .extension_information(xtest::X11_EXTENSION_NAME)
+            .map_err(|err| err.to_string())?
+            .is_some();
+        if !has_xtest {
+            return Err("X display lacks the XTEST extension".to_string());
+        }
+        let net_active_window = conn
+            .intern_atom(false, b"_NET_ACTIVE_WINDOW")
+            .map_err(|err| err.to_string())?
+            .reply()
+            .map_err(|err| err.to_string())?
+            .atom;
+        let mut injector = Self {
+            conn,
+            root,
+            net_active_window,
+            keycodes: HashMap::new(),
+        };
+        injector.load_keymap().map_err(|err| match err {
+            HelperError::Disconnected(message) => message,
+            HelperError::Unsupported => "cannot read the keyboard mapping".to_string(),
+        })?;
+        Ok(injector)
+    }
+
+    fn serve(&mut self, inbox: mpsc::Receiver<Envelope>) {
+        for (request, reply) in inbox {
+            let result = self.handle(request);
+            let disconnected = matches!(result, Err(HelperError::Disconnected(_)));
+            let _ = reply.send(result);
+            if disconnected {
+                return;
+            }
+        }
+    }
+
+    fn handle(&mut self, request: Request) -> Result<Reply, HelperError> {
+        match request {
+            Request::Input(steps) => {
+                self.refresh_keymap()?;
+                let events = steps
+                    .iter()
+                    .map(|step| self.resolve(step))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or(HelperError::Unsupported)?;
+                for event in events {
+                    match event {
+                        FakeEvent::Input { kind, detail, x, y } => {
+                            self.conn
+                                .xtest_fake_input(kind, detail, 0, self.root, x, y, 0)?;
+                        }
+                        FakeEvent::Pause(duration) => {
+                            self.round_trip()?;
+                            thread::sleep(duration);
+                        }
+                    }
+                }
+                self.round_trip()?;
+                Ok(Reply::Done)
+            }
+            Request::ScreenSize => {
+                let geometry = self.conn.get_geometry(self.root)?.reply()?;
+                Ok(Reply::ScreenSize(
+                    f64::from(geometry.width),
+                    f64::from(geometry.height),
+                ))
+            }
+            Request::ActiveWindow => {
+                let active = self
+                    .conn
+                    .get_property(
+                        false,
+                        self.root,
+                        self.net_active_window,
+                        AtomEnum::WINDOW,
+                        0,
+                        1,
+                    )?
+                    .reply()?;
+                let Some(id) = active.value32().and_then(|mut ids| ids.next()) else {
+                    // No EWMH window manager; let xdotool report it.
+                    return Err(HelperError::Unsupported);
+                };
+                if id == 0 {
+                    return Ok(Reply::ActiveWindow(None));
+                }
+                let class = self
+                    .conn
+                    .get_property(false, id, AtomEnum::WM_CLASS, AtomEnum::STRING, 0, 256)?
+                    .reply()?;
+                // WM_CLASS is "instance\0class\0"; xdotool reports the class.
+                let class = class
+                    .value
+                    .split(|byte| *byte == 0)
+                    .nth(1)
+                    .map(|class| String::from_utf8_lossy(class).into_owned())
+                    .unwrap_or_default();
+                Ok(Reply::ActiveWindow(Some(TargetWindow {
+                    id: id.to_string(),
+                    class,
+                })))
+            }
+        }
+    }
+
+    /// Resolves every step up front, so a request is either replayed whole
+    /// or handed to xdotool untouched.
+    fn resolve(&self, step: &InputStep) -> Option<FakeEvent> {
+        let input = |kind, detail, x, y| FakeEvent::Input { kind, detail, x, y };
+        match step {
+            InputStep::MoveTo { x, y } => Some(input(xproto::MOTION_NOTIFY_EVENT, 0, *x, *y)),
+            InputStep::Button { button, press } => {
+                let kind = if *press {
+                    xproto::BUTTON_PRESS_EVENT
+                } else {
+                    xproto::BUTTON_RELEASE_EVENT
+                };
+                Some(input(kind, *button, 0, 0))
+            }
+            InputStep::Key { name, press } => {
+                let keycode = *self.keycodes.get(&keysym(name)?)?;
+                let kind = if *press {
+                    xproto::KEY_PRESS_EVENT
+                } else {
+                    xproto::KEY_RELEASE_EVENT
+                };
+                Some(input(kind, keycode, 0, 0))
+            }
+            InputStep::Pause(duration) => Some(FakeEvent::Pause(*duration)),
+        }
+    }
+
+    /// Reloads the keymap if the layout changed since the last request.
+    fn refresh_keymap(&mut self) -> Result<(), HelperError> {
+        let mut changed = false;
+        while let Some(event) = self.conn.poll_for_event()? {
+            changed |= matches!(event, Event::MappingNotify(_));
+        }
+        if changed {
+            self.load_keymap()?;
+        }
+        Ok(())
+    }
+
+    fn load_keymap(&mut self) -> Result<(), HelperError> {
+        let setup = self.conn.setup();
+        let (min, max) = (setup.min_keycode, setup.max_keycode);
+        let mapping = self
+            .conn
+            .get_keyboard_mapping(min, max - min + 1)?
+            .reply()?;
+        let per_keycode = usize::from(mapping.keysyms_per_keycode.max(1));
+        self.keycodes.clear();
+        for (keycode, keysyms) in (min..=max).zip(mapping.keysyms.chunks(per_keycode)) {
+            if let Some(&keysym) = keysyms.first()
+                && keysym != 0
+            {
+                self.keycodes.entry(keysym).or_insert(keycode);
+            }
+        }
+        Ok(())
+    }
+
+    /// Waits until the server has processed everything sent so far.
+    fn round_trip(&self) -> Result<(), HelperError> {
+        self.conn.get_input_focus()?.reply()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|arg| (*arg).to_string()).collect()
+    }
+
+    #[test]
+    fn parses_pointer_commands() {
+        let steps = parse_steps(&args(&[
+            "mousemove",
+            "--sync",
+            "10",
+            "20",
+            "click",
+            "--repeat",
+            "2",
+            "4",
+        ]));
+        assert_eq!(
+            steps,
+            Some(vec![
+                InputStep::MoveTo { x: 10, y: 20 },
+                InputStep::Button {
+                    button: 4,
+                    press: true
+                },
+                InputStep::Button {
+                    button: 4,
+                    press: false
+                },
+                InputStep::Pause(REPEAT_DELAY),
+                InputStep::Button {
+                    button: 4,
+                    press: true
+                },
+                InputStep::Button {
+                    button: 4,
+                    press: false
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn key_chords_release_in_reverse_order() {
+        let steps = parse_steps(&args(&["key", "shift+Tab"]));
+        let key = |name: &str, press| InputStep::Key {
+            name: name.to_string(),
+            press,
+        };
+        assert_eq!(
+            steps,
+            Some(vec![
+                key("shift", true),
+                key("Tab", true),
+                key("Tab", false),
+                key("shift", false),
+            ])
+        );
+    }
+
+    #[test]
+    fn typing_and_window_commands_stay_with_xdotool() {
+        assert_eq!(parse_steps(&args(&["type", "--", "hello"])), None);
+        assert_eq!(
+            parse_steps(&args(&["windowactivate", "--sync", "42"])),
+            None
+        );
+        assert_eq!(parse_steps(&args(&["mousemove", "99999", "0"])), None);
+    }
+
+    #[test]
+    fn keysyms_cover_aliases_names_and_plain_characters() {
+        assert_eq!(keysym("Ctrl"), Some(0xffe3));
+        assert_eq!(keysym("Return"), Some(0xff0d));
+        assert_eq!(keysym("F12"), Some(0xffc9));
+        assert_eq!(keysym("c"), Some(u32::from('c')));
+        assert_eq!(keysym("F13"), None);
+        assert_eq!(keysym("return"), None);
+    }
+}
@@ -0,0 +1,51 @@
+//! JSON files under `$CODEX_HOME/computer_use` that outlive a session, such
+//! as saved landmarks, tuned typing delays, and VM checkpoint records.
+//!
+//! Parallel tool calls can update the same file, so each store keeps a
+//! [`StoreLock`] that callers hold across a read-modify-write cycle. A
+//! missing file reads as an empty store.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::PoisonError;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+/// Serializes read-modify-write cycles on one store from parallel tool
+/// calls. Each store has its own, so a slow update to one doesn't hold up
+/// the others.
+pub(super) struct StoreLock(Mutex<()>);
+
+impl StoreLock {
+    pub(super) const fn new() -> Self {
+        Self(Mutex::new(()))
+    }
+
+    pub(super) fn lock(&self) -> MutexGuard<'_, ()> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Reads the store at `path`, or an empty one when the file doesn't exist.
+pub(super) fn load<T: DeserializeOwned + Default>(path: &Path) -> io::Result<T> {
+    match fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(T::default()),
+        Err(err) => Err(err),
+    }
+}
+
+/// Writes `store` to `path`, creating its directory.
+pub(super) fn persist<T: Serialize>(path: &Path, store: &T) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let contents = serde_json::to_string_pretty(store)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    fs::write(path, contents)
+}
@@ -0,0 +1,479 @@
+//! Key name normalization for `computer_key`.
+//!
+//! Models spell keys many ways ("Esc", "escape", "PgDn", "Win", "Strg"), and
+//! xdotool only understands X keysym names plus a few modifier aliases, so an
+//! unrecognized name used to be silently dropped. Every name is mapped to
+//! its canonical xdotool spelling here before dispatch. Unknown names are
+//! rejected with the closest known spelling as a suggestion.
+//!
+//! Chords may also name a platform-neutral shortcut ("copy", "new_tab") or
+//! use `cmd` as the primary modifier. Both are translated for the configured
+//! [`TargetOs`] just before dispatch, so the same prompt or macro works on
+//! Linux, macOS, and Windows targets.
+
+use crate::config::types::TargetOs;
+
+/// Alias → canonical xdotool key name. Aliases are matched after
+/// [`fold`], so case, spaces, `_`, and `-` don't matter. Includes the labels
+/// printed on German, French, Spanish, and Italian keyboards and the macOS
+/// modifier symbols.
+const KEY_ALIASES: &[(&str, &str)] = &[
+    // Modifiers (xdotool's own aliases, which it maps to the left keys).
+    ("ctrl", "ctrl"),
+    ("control", "ctrl"),
+    ("ctl", "ctrl"),
+    ("strg", "ctrl"),
+    ("⌃", "ctrl"),
+    ("shift", "shift"),
+    ("umschalt", "shift"),
+    ("maj", "shift"),
+    ("mayus", "shift"),
+    ("mayús", "shift"),
+    ("maiusc", "shift"),
+    ("⇧", "shift"),
+    ("alt", "alt"),
+    ("option", "alt"),
+    ("opt", "alt"),
+    ("⌥", "alt"),
+    ("altgr", "ISO_Level3_Shift"),
+    ("super", "super"),
+    ("win", "super"),
+    ("windows", "super"),
+    ("meta", "super"),
+    // The platform's primary shortcut modifier, resolved by [`Chord::keys_for`].
+    ("cmd", PRIMARY),
+    ("command", PRIMARY),
+    ("⌘", PRIMARY),
+    // Editing and navigation.
+    ("return", "Return"),
+    ("enter", "Return"),
+    ("eingabe", "Return"),
+    ("entree", "Return"),
+    ("entrée", "Return"),
+    ("intro", "Return"),
+    ("invio", "Return"),
+    ("⏎", "Return"),
+    ("↵", "Return"),
+    ("escape", "Escape"),
+    ("esc", "Escape"),
+    ("echap", "Escape"),
+    ("échap", "Escape"),
+    ("⎋", "Escape"),
+    ("tab", "Tab"),
+    ("tabulator", "Tab"),
+    ("⇥", "Tab"),
+    ("backspace", "BackSpace"),
+    ("bksp", "BackSpace"),
+    ("rucktaste", "BackSpace"),
+    ("rücktaste", "BackSpace"),
+    ("retroceso", "BackSpace"),
+    ("⌫", "BackSpace"),
+    ("delete", "Delete"),
+    ("del", "Delete"),
+    ("entf", "Delete"),
+    ("suppr", "Delete"),
+    ("supr", "Delete"),
+    ("canc", "Delete"),
+    ("⌦", "Delete"),
+    ("insert", "Insert"),
+    ("ins", "Insert"),
+    ("einfg", "Insert"),
+    ("inser", "Insert"),
+    ("home", "Home"),
+    ("pos1", "Home"),
+    ("origine", "Home"),
+    ("inicio", "Home"),
+    ("end", "End"),
+    ("ende", "End"),
+    ("fin", "End"),
+    ("fine", "End"),
+    ("pageup", "Page_Up"),
+    ("pgup", "Page_Up"),
+    ("prior", "Page_Up"),
+    ("bildauf", "Page_Up"),
+    ("bild↑", "Page_Up"),
+    ("repag", "Page_Up"),
+    ("pagedown", "Page_Down"),
+    ("pgdn", "Page_Down"),
+    ("pgdown", "Page_Down"),
+    ("next", "Page_Down"),
+    ("bildab", "Page_Down"),
+    ("bild↓", "Page_Down"),
+    ("avpag", "Page_Down"),
+    ("up", "Up"),
+    ("arrowup", "Up"),
+    ("uparrow", "Up"),
+    ("↑", "Up"),
+    ("down", "Down"),
+    ("arrowdown", "Down"),
+    ("downarrow", "Down"),
+    ("↓", "Down"),
+    ("left", "Left"),
+    ("arrowleft", "Left"),
+    ("leftarrow", "Left"),
+    ("←", "Left"),
+    ("right", "Right"),
+    ("arrowright", "Right"),
+    ("rightarrow", "Right"),
+    ("→", "Right"),
+    ("space", "space"),
+    ("spacebar", "space"),
+    ("leertaste", "space"),
+    ("espace", "space"),
+    ("espacio", "space"),
+    ("spazio", "space"),
+    // Locks and system keys.
+    ("capslock", "Caps_Lock"),
+    ("caps", "Caps_Lock"),
+    ("feststell", "Caps_Lock"),
+    ("verrmaj", "Caps_Lock"),
+    ("bloqmayus", "Caps_Lock"),
+    ("bloqmayús", "Caps_Lock"),
+    ("numlock", "Num_Lock"),
+    ("scrolllock", "Scroll_Lock"),
+    ("print", "Print"),
+    ("printscreen", "Print"),
+    ("prtsc", "Print"),
+    ("prtscn", "Print"),
+    ("druck", "Print"),
+    ("pause", "Pause"),
+    ("break", "Pause"),
+    ("menu", "Menu"),
+    ("apps", "Menu"),
+    ("contextmenu", "Menu"),
+    // Punctuation that is awkward to pass as a bare character.
+    ("plus", "plus"),
+    ("minus", "minus"),
+    ("comma", "comma"),
+    ("period", "period"),
+    ("slash", "slash"),
+    ("backslash", "backslash"),
+    ("semicolon", "semicolon"),
+    ("apostrophe", "apostrophe"),
+    ("grave", "grave"),
+    ("equal", "equal"),
+    ("bracketleft", "bracketleft"),
+    ("bracketright", "bracketright"),
+];
+
+/// Placeholder for Ctrl on Linux and Windows and Cmd (Super) on macOS.
+const PRIMARY: &str = "cmd";
+
+/// Platform-neutral shortcuts as (name, Linux, macOS, Windows) chords. Names
+/// are matched after [`fold`], like key aliases.
+const SHORTCUTS: &[(&str, &str, &str, &str)] = &[
+    ("copy", "ctrl+c", "super+c", "ctrl+c"),
+    ("cut", "ctrl+x", "super+x", "ctrl+x"),
+    ("paste", "ctrl+v", "super+v", "ctrl+v"),
+    ("undo", "ctrl+z", "super+z", "ctrl+z"),
+    ("redo", "ctrl+shift+z", "super+shift+z", "ctrl+y"),
+    ("select_all", "ctrl+a", "super+a", "ctrl+a"),
+    ("save", "ctrl+s", "super+s", "ctrl+s"),
+    ("find", "ctrl+f", "super+f", "ctrl+f"),
+    ("new_tab", "ctrl+t", "super+t", "ctrl+t"),
+    ("close_tab", "ctrl+w", "super+w", "ctrl+w"),
+    (
+        "reopen_tab",
+        "ctrl+shift+t",
+        "super+shift+t",
+        "ctrl+shift+t",
+    ),
+    ("next_tab", "ctrl+Tab", "ctrl+Tab", "ctrl+Tab"),
+    (
+        "previous_tab",
+        "ctrl+shift+Tab",
+        "ctrl+shift+Tab",
+        "ctrl+shift+Tab",
+    ),
+    ("new_window", "ctrl+n", "super+n", "ctrl+n"),
+    ("close_window", "alt+F4", "super+w", "alt+F4"),
+    ("quit", "ctrl+q", "super+q", "alt+F4"),
+    ("reload", "ctrl+r", "super+r", "ctrl+r"),
+    ("refresh", "ctrl+r", "super+r", "ctrl+r"),
+    ("address_bar", "ctrl+l", "super+l", "ctrl+l"),
+    ("zoom_in", "ctrl+plus", "super+plus", "ctrl+plus"),
+    ("zoom_out", "ctrl+minus", "super+minus", "ctrl+minus"),
+    ("switch_app", "alt+Tab", "super+Tab", "alt+Tab"),
+    ("word_left", "ctrl+Left", "alt+Left", "ctrl+Left"),
+    ("word_right", "ctrl+Right", "alt+Right", "ctrl+Right"),
+    ("line_start", "Home", "super+Left", "Home"),
+    ("line_end", "End", "super+Right", "End"),
+    ("document_start", "ctrl+Home", "super+Up", "ctrl+Home"),
+    ("document_end", "ctrl+End", "super+Down", "ctrl+End"),
+    (
+        "delete_word",
+        "ctrl+BackSpace",
+        "alt+BackSpace",
+        "ctrl+BackSpace",
+    ),
+    ("lock_screen", "super+l", "ctrl+super+q", "super+l"),
+    ("print_document", "ctrl+p", "super+p", "ctrl+p"),
+];
+
+/// A validated `computer_key` chord that still has to be translated for the
+/// target OS.
+#[derive(Debug, PartialEq)]
+pub(super) enum Chord {
+    /// Canonical key names, possibly including [`PRIMARY`].
+    Keys(Vec<String>),
+    /// Index into [`SHORTCUTS`].
+    Shortcut(usize),
+}
+
+impl Chord {
+    /// The xdotool key names to press on `os`.
+    pub(super) fn keys_for(&self, os: TargetOs) -> Vec<String> {
+        let primary = match os {
+            TargetOs::Macos => "super",
+            TargetOs::Linux | TargetOs::Windows => "ctrl",
+        };
+        match self {
+            Chord::Keys(keys) => keys
+                .iter()
+                .map(|key| if key == PRIMARY { primary } else { key })
+                .map(str::to_string)
+                .collect(),
+            Chord::Shortcut(index) => {
+                let (_, linux, macos, windows) = SHORTCUTS[*index];
+                let chord = match os {
+                    TargetOs::Linux => linux,
+                    TargetOs::Macos => macos,
+                    TargetOs::Windows => windows,
+                };
+                chord.split('+').map(str::to_string).collect()
+            }
+        }
+    }
+
+    /// The shortcut name, for tool results.
+    pub(super) fn shortcut_name(&self) -> Option<&'static str> {
+        match self {
+            Chord::Keys(_) => None,
+            Chord::Shortcut(index) => Some(SHORTCUTS[*index].0),
+        }
+    }
+}
+
+/// The keys for the named shortcut `name` on `os`, e.g. Ctrl+A or Cmd+A for
+/// `select_all`.
+pub(super) fn shortcut_keys(name: &str, os: TargetOs) -> Option<Vec<String>> {
+    let index = SHORTCUTS
+        .iter()
+        .position(|(shortcut, ..)| *shortcut == name)?;
+    Some(Chord::Shortcut(index).keys_for(os))
+}
+
+/// Parses a `computer_key` chord: either one named shortcut or canonical key
+/// names. Entries may themselves be chords (`"ctrl+shift+t"`).
+pub(super) fn parse_chord(keys: &[String]) -> Result<Chord, String> {
+    if keys.is_empty() {
+        return Err("computer_key needs at least one key".to_string());
+    }
+    if let Some(index) = keys
+        .iter()
+        .map(|key| fold(key))
+        .find_map(|folded| SHORTCUTS.iter().position(|(name, ..)| fold(name) == folded))
+    {
+        if keys.len() > 1 {
+            return Err(format!(
+                "`{}` is a named shortcut and can't be combined with other keys",
+                SHORTCUTS[index].0
+            ));
+        }
+        return Ok(Chord::Shortcut(index));
+    }
+    let mut chord = Vec::new();
+    for entry in keys {
+        let entry = entry.trim();
+        let names: Vec<&str> = if entry.chars().count() > 1 && entry.contains('+') {
+            entry.split('+').map(str::trim).collect()
+        } else {
+            vec![entry]
+        };
+        for name in names {
+            chord.push(canonical_key(name)?);
+        }
+    }
+    Ok(Chord::Keys(chord))
+}
+
+/// The canonical xdotool name for `name`, or an error with a suggestion.
+pub(super) fn canonical_key(name: &str) -> Result<String, String> {
+    if name.is_empty() {
+        return Err("empty key name".to_string());
+    }
+    let mut chars = name.chars();
+    let single = matches!((chars.next(), chars.next()), (Some(_), None));
+    // Symbols such as `⌘` are aliases too; other single characters, including
+    // separators that `fold` would drop, are typed as-is.
+    let folded = if single { name.to_string() } else { fold(name) };
+    if let Some((_, canonical)) = KEY_ALIASES.iter().find(|(alias, _)| *alias == folded) {
+        return Ok((*canonical).to_string());
+    }
+    if single {
+        return Ok(match name {
+            "+" => "plus".to_string(),
+            " " => "space".to_string(),
+            _ => name.to_string(),
+        });
+    }
+    if let Some(number) = folded.strip_prefix('f')
+        && let Ok(number @ 1..=24) = number.parse::<u32>()
+    {
+        return Ok(format!("F{number}"));
+    }
+    // Keysym families xdotool resolves itself: keypad keys and media keys.
+    if name.starts_with("KP_") || name.starts_with("XF86") {
+        return Ok(name.to_string());
+    }
+    Err(match suggestion(&folded) {
+        Some(suggested) => format!("unknown key `{name}`; did you mean `{suggested}`?"),
+        None => format!(
+            "unknown key `{name}`; use a single character or a key name such as Return, Escape, Tab, Page_Down, F5, ctrl, shift, alt, or super"
+        ),
+    })
+}
+
+/// Lowercases and drops separators so "Page Down", "page_down", and
+/// "PAGE-DOWN" all match.
+fn fold(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, ' ' | '_' | '-'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// The canonical key or shortcut name whose spelling is closest to
+/// `folded`, if any is close enough to be a plausible typo.
+fn suggestion(folded: &str) -> Option<&'static str> {
+    let limit = (folded.chars().count() / 3).max(1);
+    let keys = KEY_ALIASES
+        .iter()
+        .map(|(alias, canonical)| (edit_distance(folded, alias), *canonical));
+    let shortcuts = SHORTCUTS
+        .iter()
+        .map(|(name, ..)| (edit_distance(folded, &fold(name)), *name));
+    keys.chain(shortcuts)
+        .filter(|(distance, _)| *distance <= limit)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, canonical)| canonical)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn chord(keys: &[&str]) -> Result<Vec<String>, String> {
+        chord_for(keys, TargetOs::Linux)
+    }
+
+    fn chord_for(keys: &[&str], os: TargetOs) -> Result<Vec<String>, String> {
+        let keys: Vec<String> = keys.iter().map(|key| (*key).to_string()).collect();
+        parse_chord(&keys).map(|chord| chord.keys_for(os))
+    }
+
+    #[test]
+    fn aliases_map_to_xdotool_names() {
+        assert_eq!(
+            chord(&["Esc", "escape", "Del", "PgDn", "Win", "page down"]),
+            Ok(vec![
+                "Escape".to_string(),
+                "Escape".to_string(),
+                "Delete".to_string(),
+                "Page_Down".to_string(),
+                "super".to_string(),
+                "Page_Down".to_string(),
+            ])
+        );
+        assert_eq!(
+            chord(&["Strg", "Entf"]),
+            Ok(vec!["ctrl".to_string(), "Delete".to_string()])
+        );
+        assert_eq!(
+            chord(&["ctrl+shift+T"]),
+            Ok(vec![
+                "ctrl".to_string(),
+                "shift".to_string(),
+                "T".to_string()
+            ])
+        );
+        assert_eq!(
+            chord(&["ctrl", "+"]),
+            Ok(vec!["ctrl".to_string(), "plus".to_string()])
+        );
+        assert_eq!(chord(&["f12", "KP_Enter"]).map(|keys| keys.len()), Ok(2));
+    }
+
+    #[test]
+    fn shortcuts_and_cmd_follow_the_target_os() {
+        assert_eq!(
+            chord_for(&["cmd", "c"], TargetOs::Linux),
+            Ok(vec!["ctrl".to_string(), "c".to_string()])
+        );
+        assert_eq!(
+            chord_for(&["⌘+c"], TargetOs::Macos),
+            Ok(vec!["super".to_string(), "c".to_string()])
+        );
+        assert_eq!(
+            chord_for(&["Redo"], TargetOs::Windows),
+            Ok(vec!["ctrl".to_string(), "y".to_string()])
+        );
+        assert_eq!(
+            chord_for(&["new tab"], TargetOs::Macos),
+            Ok(vec!["super".to_string(), "t".to_string()])
+        );
+        assert_eq!(
+            chord_for(&["quit"], TargetOs::Windows),
+            Ok(vec!["alt".to_string(), "F4".to_string()])
+        );
+        assert!(chord(&["ctrl", "copy"]).is_err());
+        assert_eq!(
+            chord_for(&["print_document"], TargetOs::Macos),
+            Ok(vec!["super".to_string(), "p".to_string()])
+        );
+    }
+
+    #[test]
+    fn print_is_the_print_screen_key() {
+        assert_eq!(chord(&["Print"]), Ok(vec!["Print".to_string()]));
+        assert_eq!(
+            chord(&["shift", "Print"]),
+            Ok(vec!["shift".to_string(), "Print".to_string()])
+        );
+    }
+
+    #[test]
+    fn unknown_keys_get_a_suggestion() {
+        assert_eq!(
+            canonical_key("Retrun"),
+            Err("unknown key `Retrun`; did you mean `Return`?".to_string())
+        );
+        assert_eq!(
+            canonical_key("PgDwn"),
+            Err("unknown key `PgDwn`; did you mean `Page_Down`?".to_string())
+        );
+        assert_eq!(
+            canonical_key("selectall!"),
+            Err("unknown key `selectall!`; did you mean `select_all`?".to_string())
+        );
+        assert!(
+            canonical_key("frobnicate")
+                .is_err_and(|err| err.starts_with("unknown key `frobnicate`; use"))
+        );
+    }
+}
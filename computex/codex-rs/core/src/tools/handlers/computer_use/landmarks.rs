@@ -0,0 +1,146 @@
+//! Cross-session store of UI landmarks saved through `computer_landmarks`.
+//!
+//! Landmarks are keyed by the focused window's class and the physical screen
+//! size, and positions are stored in screen pixels so they stay valid when the
+//! configured coordinate space changes between sessions.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+
+use chrono::DateTime;
+use chrono::Utc;
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::json_store;
+use super::json_store::StoreLock;
+
+/// Location of the store, relative to `$CODEX_HOME`.
+pub(super) const LANDMARKS_FILE: &str = "computer_use/landmarks.json";
+
+/// Oldest landmarks are dropped once a window/resolution key exceeds this.
+const MAX_LANDMARKS_PER_KEY: usize = 200;
+
+static STORE_LOCK: StoreLock = StoreLock::new();
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(super) struct Landmark {
+    pub label: String,
+    pub screen_x: i64,
+    pub screen_y: i64,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct LandmarkStore {
+    #[serde(default)]
+    screens: BTreeMap<String, Vec<Landmark>>,
+}
+
+/// Store key for a window class on a screen of the given size, e.g. `firefox@1920x1080`.
+pub(super) fn landmark_key(window_class: &str, screen_width: f64, screen_height: f64) -> String {
+    let class = window_class.trim().to_ascii_lowercase();
+    let width = screen_width.round() as i64;
+    let height = screen_height.round() as i64;
+    format!("{class}@{width}x{height}")
+}
+
+/// Saves `label` at the given screen position, replacing any landmark with the same label.
+pub(super) fn save(
+    path: &Path,
+    key: &str,
+    label: &str,
+    screen_x: i64,
+    screen_y: i64,
+) -> io::Result<()> {
+    let _guard = STORE_LOCK.lock();
+    let mut store: LandmarkStore = json_store::load(path)?;
+    let entries = store.screens.entry(key.to_string()).or_default();
+    entries.retain(|landmark| !landmark.label.eq_ignore_ascii_case(label));
+    entries.push(Landmark {
+        label: label.to_string(),
+        screen_x,
+        screen_y,
+        updated_at: Utc::now(),
+    });
+    if entries.len() > MAX_LANDMARKS_PER_KEY {
+        entries.sort_by_key(|landmark| std::cmp::Reverse(landmark.updated_at));
+        entries.truncate(MAX_LANDMARKS_PER_KEY);
+    }
+    json_store::persist(path, &store)
+}
+
+/// Landmarks whose label contains `query` (case-insensitive), exact matches first,
+/// then most recently saved.
+pub(super) fn find(path: &Path, key: &str, query: &str) -> io::Result<Vec<Landmark>> {
+    let query = query.trim().to_lowercase();
+    let mut matches: Vec<Landmark> = list(path, key)?
+        .into_iter()
+        .filter(|landmark| landmark.label.to_lowercase().contains(&query))
+        .collect();
+    matches.sort_by_key(|landmark| landmark.label.to_lowercase() != query);
+    Ok(matches)
+}
+
+/// All landmarks for `key`, most recently saved first.
+pub(super) fn list(path: &Path, key: &str) -> io::Result<Vec<Landmark>> {
+    let _guard = STORE_LOCK.lock();
+    let mut entries = json_store::load::<LandmarkStore>(path)?
+        .screens
+        .remove(key)
+        .unwrap_or_default();
+    entries.sort_by_key(|landmark| std::cmp::Reverse(landmark.updated_at));
+    Ok(entries)
+}
+
+/// Removes the landmark named `label`. Returns whether one existed.
+pub(super) fn forget(path: &Path, key: &str, label: &str) -> io::Result<bool> {
+    let _guard = STORE_LOCK.lock();
+    let mut store: LandmarkStore = json_store::load(path)?;
+    let Some(entries) = store.screens.get_mut(key) else {
+        return Ok(false);
+    };
+    let before = entries.len();
+    entries.retain(|landmark| !landmark.label.eq_ignore_ascii_case(label));
+    let removed = entries.len() != before;
+    if entries.is_empty() {
+        store.screens.remove(key);
+    }
+    if removed {
+        json_store::persist(path, &store)?;
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    #[test]
+    fn landmarks_round_trip_per_window_and_resolution() -> io::Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join(LANDMARKS_FILE);
+        let key = landmark_key("Firefox", 1920.0, 1080.0);
+        assert_eq!(key, "firefox@1920x1080");
+
+        save(&path, &key, "Submit button", 100, 200)?;
+        save(&path, &key, "Submit", 300, 400)?;
+        save(&path, &key, "submit button", 110, 210)?;
+
+        let found = find(&path, &key, "submit")?;
+        let labels: Vec<_> = found.iter().map(|l| l.label.as_str()).collect();
+        assert_eq!(labels, vec!["Submit", "submit button"]);
+        assert_eq!((found[1].screen_x, found[1].screen_y), (110, 210));
+
+        let other = landmark_key("firefox", 1280.0, 720.0);
+        assert!(list(&path, &other)?.is_empty());
+
+        assert!(forget(&path, &key, "SUBMIT")?);
+        assert!(!forget(&path, &key, "missing")?);
+        assert_eq!(list(&path, &key)?.len(), 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,352 @@
+//! Element lookup with a fallback chain of strategies, for `computer_locate`.
+//!
+//! "Find the Save button" has several answers of different quality: the
+//! accessibility tree knows the exact widget when the app publishes one, OCR
+//! reads labels off the screen, a saved template matches icons, and a local
+//! detector proposes controls in custom-drawn apps. Strategies run in the
+//! order of `computer_use.locator_strategies` until one clears its confidence
+//! threshold, and the result names the strategy that succeeded and why the
+//! earlier ones didn't, so the model doesn't have to pick a tool per app.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_utils_image::screenshot::RawImage;
+
+use super::accessibility;
+use super::capture_root_window;
+use super::coordinates;
+use super::detector;
+use super::find_text;
+use super::find_text::similarity;
+use super::template;
+use crate::config::types::ComputerUseConfig;
+use crate::config::types::LocatorStrategy;
+
+/// Thresholds each strategy's confidence must reach unless the caller
+/// overrides them. Template scores run high even for poor matches, so its
+/// bar is higher.
+const ACCESSIBILITY_THRESHOLD: f64 = 0.8;
+const OCR_THRESHOLD: f64 = find_text::DEFAULT_MIN_SIMILARITY;
+const TEMPLATE_THRESHOLD: f64 = 0.9;
+const DETECTOR_THRESHOLD: f64 = 0.5;
+
+/// An element a strategy found, in screen pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Located {
+    pub strategy: LocatorStrategy,
+    /// What was matched, e.g. `push button "Save"`.
+    pub description: String,
+    pub left: i64,
+    pub top: i64,
+    pub right: i64,
+    pub bottom: i64,
+    pub confidence: f64,
+}
+
+/// The result of running the chain.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Outcome {
+    pub found: Option<Located>,
+    /// Why each strategy tried before the result (or all of them, when
+    /// nothing was found) came up empty.
+    pub misses: Vec<String>,
+}
+
+pub(super) fn strategy_name(strategy: LocatorStrategy) -> &'static str {
+    match strategy {
+        LocatorStrategy::Accessibility => "accessibility",
+        LocatorStrategy::Ocr => "ocr",
+        LocatorStrategy::Template => "template",
+        LocatorStrategy::Detector => "detector",
+    }
+}
+
+/// Tries the configured strategies in order for `query`. `min_confidence`
+/// replaces every strategy's own threshold.
+pub(super) fn locate(
+    config: &ComputerUseConfig,
+    codex_home: &Path,
+    query: &str,
+    min_confidence: Option<f64>,
+) -> Outcome {
+    let mut capture = Capture::default();
+    let mut misses = Vec::new();
+    let mut found = None;
+    for strategy in &config.locator_strategies {
+        let threshold = min_confidence.unwrap_or(match strategy {
+            LocatorStrategy::Accessibility => ACCESSIBILITY_THRESHOLD,
+            LocatorStrategy::Ocr => OCR_THRESHOLD,
+            LocatorStrategy::Template => TEMPLATE_THRESHOLD,
+            LocatorStrategy::Detector => DETECTOR_THRESHOLD,
+        });
+        let attempt = match strategy {
+            LocatorStrategy::Accessibility => by_accessibility(query, threshold),
+            LocatorStrategy::Ocr => by_ocr(config, &mut capture, query, threshold),
+            LocatorStrategy::Template => {
+                by_template(config, &mut capture, codex_home, query, threshold)
+            }
+            LocatorStrategy::Detector => by_detector(config, &mut capture, query, threshold),
+        };
+        match attempt {
+            Ok(located) => {
+                found = Some(located);
+                break;
+            }
+            Err(reason) => misses.push(format!("{}: {reason}", strategy_name(*strategy))),
+        }
+    }
+    capture.discard();
+    Outcome { found, misses }
+}
+
+/// One screen capture shared by the image-based strategies, taken when the
+/// first of them runs.
+#[derive(Default)]
+struct Capture {
+    path: Option<Result<PathBuf, String>>,
+}
+
+impl Capture {
+    fn path(&mut self, config: &ComputerUseConfig) -> Result<&Path, String> {
+        let path = self.path.get_or_insert_with(|| {
+            capture_root_window(config, None, None).map_err(|err| err.to_string())
+        });
+        match path {
+            Ok(path) => Ok(path.as_path()),
+            Err(err) => Err(format!("screen capture failed: {err}")),
+        }
+    }
+
+    fn discard(self) {
+        if let Some(Ok(path)) = self.path {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+fn by_accessibility(query: &str, threshold: f64) -> Result<Located, String> {
+    let element = accessibility::find(query)?
+        .ok_or_else(|| "the focused app exposes no named elements".to_string())?;
+    if element.score < threshold {
+        return Err(format!(
+            "best match {} \"{}\" scored {:.2}, below {threshold:.2}",
+            element.role, element.name, element.score
+        ));
+    }
+    Ok(Located {
+        strategy: LocatorStrategy::Accessibility,
+        description: format!("{} \"{}\"", element.role, element.name)
+            .trim()
+            .to_string(),
+        left: element.left,
+        top: element.top,
+        right: element.left + element.width,
+        bottom: element.top + element.height,
+        confidence: (element.score * 100.0).round() / 100.0,
+    })
+}
+
+fn by_ocr(
+    config: &ComputerUseConfig,
+    capture: &mut Capture,
+    query: &str,
+    threshold: f64,
+) -> Result<Located, String> {
+    let tsv = find_text::recognize(capture.path(config)?).map_err(|err| err.to_string())?;
+    let hit = find_text::find_matches(&tsv, query, 0.0)
+        .into_iter()
+        .next()
+        .ok_or_else(|| "no text on screen".to_string())?;
+    if hit.confidence < threshold {
+        return Err(format!(
+            "best match \"{}\" scored {:.2}, below {threshold:.2}",
+            hit.text, hit.confidence
+        ));
+    }
+    Ok(from_capture(
+        config,
+        LocatorStrategy::Ocr,
+        format!("text \"{}\"", hit.text),
+        (hit.left, hit.top, hit.right, hit.bottom),
+        hit.confidence,
+    ))
+}
+
+fn by_template(
+    config: &ComputerUseConfig,
+    capture: &mut Capture,
+    codex_home: &Path,
+    query: &str,
+    threshold: f64,
+) -> Result<Located, String> {
+    let path = template::template_path(codex_home, query);
+    if !path.exists() {
+        return Err(format!("no template at {}", path.display()));
+    }
+    let template = RawImage::open(&path).map_err(|err| err.to_string())?;
+    let screen = RawImage::open(capture.path(config)?).map_err(|err| err.to_string())?;
+    let found = template::find(&screen, &template)
+        .ok_or_else(|| "the template is larger than the screen".to_string())?;
+    if found.score < threshold {
+        return Err(format!(
+            "best placement scored {:.2}, below {threshold:.2}",
+            found.score
+        ));
+    }
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    Ok(from_capture(
+        config,
+        LocatorStrategy::Template,
+        format!("template {name}"),
+        (
+            found.left,
+            found.top,
+            found.left + found.width,
+            found.top + found.height,
+        ),
+        found.score,
+    ))
+}
+
+fn by_detector(
+    config: &ComputerUseConfig,
+    capture: &mut Capture,
+    query: &str,
+    threshold: f64,
+) -> Result<Located, String> {
+    if config.element_detector.is_none() {
+        return Err("computer_use.element_detector is not set".to_string());
+    }
+    let elements = detector::detect(config, capture.path(config)?, None, 0.0)
+        .map_err(|err| err.to_string())?;
+    let query = query.trim().to_lowercase();
+    let (element, score) = elements
+        .into_iter()
+        .map(|element| {
+            let score = detector_score(&element, &query);
+            (element, score)
+        })
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .ok_or_else(|| "the detector found no elements".to_string())?;
+    if score < threshold {
+        return Err(format!(
+            "best match {} scored {score:.2}, below {threshold:.2}",
+            element.label
+        ));
+    }
+    let description = match &element.text {
+        Some(text) => format!("{} \"{text}\"", element.label),
+        None => element.label.clone(),
+    };
+    Ok(from_capture(
+        config,
+        LocatorStrategy::Detector,
+        description,
+        (
+            element.x,
+            element.y,
+            element.x + element.width,
+            element.y + element.height,
+        ),
+        score,
+    ))
+}
+
+/// How well a detected element answers `query`: its text or kind compared
+/// with the query, whichever fits better (so "Save button" and "button"
+/// both work), scaled by the detector's confidence.
+fn detector_score(element: &detector::DetectedElement, query: &str) -> f64 {
+    let label = element.label.to_lowercase();
+    let mut names = vec![label.clone()];
+    if let Some(text) = &element.text {
+        let text = text.trim().to_lowercase();
+        names.push(format!("{text} {label}"));
+        names.push(text);
+    }
+    let fit = names
+        .iter()
+        .map(|name| similarity(name, query))
+        .fold(0.0, f64::max);
+    (fit * element.confidence.clamp(0.0, 1.0) * 100.0).round() / 100.0
+}
+
+/// A match in capture pixels, moved to screen pixels.
+fn from_capture(
+    config: &ComputerUseConfig,
+    strategy: LocatorStrategy,
+    description: String,
+    (left, top, right, bottom): (i64, i64, i64, i64),
+    confidence: f64,
+) -> Located {
+    // Captures cover the viewport, so their pixels start at its origin.
+    let (origin_x, origin_y) = coordinates::viewport_origin(config);
+    Located {
+        strategy,
+        description,
+        left: left + origin_x,
+        top: top + origin_y,
+        right: right + origin_x,
+        bottom: bottom + origin_y,
+        confidence: (confidence * 100.0).round() / 100.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn element(label: &str, text: Option<&str>, confidence: f64) -> detector::DetectedElement {
+        detector::DetectedElement {
+            label: label.to_string(),
+            text: text.map(str::to_string),
+            x: 0,
+            y: 0,
+            width: 10,
+            height: 10,
+            confidence,
+        }
+    }
+
+    #[test]
+    fn detector_matches_text_and_kind() {
+        assert_eq!(
+            detector_score(&element("button", Some("Save"), 0.9), "save button"),
+            0.9
+        );
+        assert_eq!(
+            detector_score(&element("button", Some("Save"), 0.9), "save"),
+            0.9
+        );
+        assert_eq!(detector_score(&element("icon", None, 0.8), "icon"), 0.8);
+        assert!(detector_score(&element("input", Some("Search"), 0.9), "save button") < 0.5);
+    }
+
+    #[test]
+    fn strategies_without_setup_explain_the_miss() {
+        let config = ComputerUseConfig::default();
+        let mut capture = Capture::default();
+        assert_eq!(
+            by_detector(&config, &mut capture, "Save", DETECTOR_THRESHOLD),
+            Err("computer_use.element_detector is not set".to_string())
+        );
+        assert_eq!(
+            by_template(
+                &config,
+                &mut capture,
+                Path::new("/nonexistent/codex-home"),
+                "Save",
+                TEMPLATE_THRESHOLD,
+            ),
+            Err(
+                "no template at /nonexistent/codex-home/computer_use/templates/save.png"
+                    .to_string()
+            )
+        );
+        assert!(capture.path.is_none());
+    }
+}
@@ -0,0 +1,136 @@
+//! Screen lock detection.
+//!
+//! Input sent while the screen is locked lands in the unlock prompt, or in
+//! the session of whoever unlocks it. Before each input action the handler
+//! asks the screen saver over D-Bus whether it is active, and checks whether
+//! a known locker owns the focused window. Once a lock is seen, GUI input
+//! stays suspended until the screen is unlocked and the user confirms.
+
+use std::path::Path;
+
+use which::which;
+
+use super::active_window_class;
+use super::gui_command;
+use crate::config::types::ComputerUseConfig;
+
+/// Screen saver services asked for `GetActive`: bus name, object path, and
+/// interface.
+const SCREENSAVERS: &[(&str, &str, &str)] = &[
+    (
+        "org.freedesktop.ScreenSaver",
+        "/org/freedesktop/ScreenSaver",
+        "org.freedesktop.ScreenSaver",
+    ),
+    (
+        "org.gnome.ScreenSaver",
+        "/org/gnome/ScreenSaver",
+        "org.gnome.ScreenSaver",
+    ),
+    (
+        "org.cinnamon.ScreenSaver",
+        "/org/cinnamon/ScreenSaver",
+        "org.cinnamon.ScreenSaver",
+    ),
+    (
+        "org.mate.ScreenSaver",
+        "/org/mate/ScreenSaver",
+        "org.mate.ScreenSaver",
+    ),
+];
+
+/// Window classes of X screen lockers (lowercase).
+const LOCKERS: &[&str] = &[
+    "xscreensaver",
+    "i3lock",
+    "xsecurelock",
+    "light-locker",
+    "slock",
+    "xlock",
+    "xtrlock",
+    "gnome-screensaver",
+    "mate-screensaver",
+    "cinnamon-screensaver",
+    "xfce4-screensaver",
+    "kscreenlocker_greet",
+];
+
+/// Why the screen looks locked, or `None` when it doesn't.
+pub(super) fn lock_reason(config: &ComputerUseConfig) -> Option<String> {
+    if let Ok(dbus_send) = which("dbus-send")
+        && let Some(service) = active_screensaver(&dbus_send, config)
+    {
+        return Some(format!("{service} reports the screen saver active"));
+    }
+    let xdotool = which("xdotool").ok()?;
+    let class = active_window_class(&xdotool, config).ok()?;
+    is_locker(&class).then(|| format!("the lock screen `{class}` has focus"))
+}
+
+/// The class of the focused window when it is a mapped window of one of the
+/// [`LOCKERS`], or `None`. Unlike [`lock_reason`] this ignores screen savers
+/// that only report themselves active: a blanked but unlocked screen has no
+/// password prompt to type into.
+pub(super) fn focused_locker(xdotool: &Path, config: &ComputerUseConfig) -> Option<String> {
+    let class = active_window_class(xdotool, config).ok()?;
+    if !is_locker(&class) {
+        return None;
+    }
+    let output = gui_command(xdotool, config)
+        .args(["search", "--onlyvisible", "--class", class.trim()])
+        .output()
+        .ok()?;
+    (output.status.success() && !String::from_utf8_lossy(&output.stdout).trim().is_empty())
+        .then_some(class)
+}
+
+/// The first screen saver service that reports itself active. Stops at the
+/// first service that answers.
+fn active_screensaver(dbus_send: &Path, config: &ComputerUseConfig) -> Option<&'static str> {
+    for (service, path, interface) in SCREENSAVERS {
+        let Ok(output) = gui_command(dbus_send, config)
+            .args([
+                "--session",
+                "--print-reply=literal",
+                "--reply-timeout=500",
+                &format!("--dest={service}"),
+                path,
+                &format!("{interface}.GetActive"),
+            ])
+            .output()
+        else {
+            return None;
+        };
+        if output.status.success() {
+            return reports_active(&String::from_utf8_lossy(&output.stdout)).then_some(service);
+        }
+    }
+    None
+}
+
+/// Whether a literal `dbus-send` reply to `GetActive` is true.
+fn reports_active(reply: &str) -> bool {
+    reply.split_whitespace().collect::<Vec<_>>() == ["boolean", "true"]
+}
+
+fn is_locker(class: &str) -> bool {
+    let class = class.trim();
+    LOCKERS
+        .iter()
+        .any(|locker| locker.eq_ignore_ascii_case(class))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_get_active_replies_and_locker_classes() {
+        assert!(reports_active("   boolean true\n"));
+        assert!(!reports_active("   boolean false\n"));
+        assert!(is_locker("XScreenSaver"));
+        assert!(is_locker("i3lock"));
+        assert!(!is_locker("firefox"));
+        assert!(!is_locker(""));
+    }
+}
@@ -0,0 +1,189 @@
+//! Desktop screen magnifier control for `computer_magnifier`.
+//!
+//! Lets accessibility test runs turn the desktop's own zoom on and off the
+//! way a low-vision user would. On GNOME the magnifier is driven through its
+//! `org.gnome.desktop.a11y` settings with `gsettings`; on KDE Plasma the KWin
+//! zoom effect is loaded over D-Bus and stepped with its global zoom
+//! shortcuts. Pointer input keeps using unmagnified desktop coordinates.
+
+use super::desktop::Desktop;
+use super::desktop::output;
+use super::require_command;
+use super::schema::MagnifierAction;
+use crate::config::types::ComputerUseConfig;
+use crate::function_tool::FunctionCallError;
+
+const GNOME_APPLICATIONS: &str = "org.gnome.desktop.a11y.applications";
+const GNOME_MAGNIFIER: &str = "org.gnome.desktop.a11y.magnifier";
+
+/// Factor KWin's zoom effect multiplies by per zoom-in step by default.
+const KWIN_ZOOM_STEP: f64 = 1.2;
+
+/// Largest magnification accepted, GNOME's upper bound.
+const MAX_FACTOR: f64 = 32.0;
+
+/// Carries out `action` and describes the magnifier afterwards.
+pub(super) fn apply(
+    config: &ComputerUseConfig,
+    action: MagnifierAction,
+    factor: Option<f64>,
+) -> Result<String, FunctionCallError> {
+    let desktop = Desktop::current("computer_magnifier")?;
+    if let Some(factor) = factor
+        && !(1.0..=MAX_FACTOR).contains(&factor)
+    {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "factor must be between 1 and {MAX_FACTOR}, got {factor}"
+        )));
+    }
+    if action == MagnifierAction::Zoom && factor.is_none() {
+        return Err(FunctionCallError::RespondToModel(
+            "computer_magnifier zoom needs a factor".to_string(),
+        ));
+    }
+    let state = match desktop {
+        Desktop::Gnome => gnome(config, action, factor)?,
+        Desktop::Kde => kde(config, action, factor)?,
+    };
+    Ok(format!("magnifier {state} ({})", desktop.label()))
+}
+
+fn gnome(
+    config: &ComputerUseConfig,
+    action: MagnifierAction,
+    factor: Option<f64>,
+) -> Result<String, FunctionCallError> {
+    let gsettings = require_command("gsettings")?;
+    if let Some(factor) = factor {
+        output(
+            &gsettings,
+            config,
+            &[
+                "set",
+                GNOME_MAGNIFIER,
+                "mag-factor",
+                &format!("{factor:.2}"),
+            ],
+        )?;
+    }
+    match action {
+        MagnifierAction::Enable | MagnifierAction::Zoom => {
+            output(
+                &gsettings,
+                config,
+                &[
+                    "set",
+                    GNOME_APPLICATIONS,
+                    "screen-magnifier-enabled",
+                    "true",
+                ],
+            )?;
+        }
+        MagnifierAction::Disable => {
+            output(
+                &gsettings,
+                config,
+                &[
+                    "set",
+                    GNOME_APPLICATIONS,
+                    "screen-magnifier-enabled",
+                    "false",
+                ],
+            )?;
+        }
+        MagnifierAction::Status => {}
+    }
+    let enabled = output(
+        &gsettings,
+        config,
+        &["get", GNOME_APPLICATIONS, "screen-magnifier-enabled"],
+    )?;
+    if enabled.trim() != "true" {
+        return Ok("off".to_string());
+    }
+    let factor = output(&gsettings, config, &["get", GNOME_MAGNIFIER, "mag-factor"])?;
+    Ok(format!("on at {}x", factor.trim()))
+}
+
+fn kde(
+    config: &ComputerUseConfig,
+    action: MagnifierAction,
+    factor: Option<f64>,
+) -> Result<String, FunctionCallError> {
+    let dbus_send = require_command("dbus-send")?;
+    let effects = |method: &str| {
+        output(
+            &dbus_send,
+            config,
+            &[
+                "--session",
+                "--print-reply=literal",
+                "--dest=org.kde.KWin",
+                "/Effects",
+                &format!("org.kde.kwin.Effects.{method}"),
+                "string:zoom",
+            ],
+        )
+    };
+    let shortcut = |name: &str| {
+        output(
+            &dbus_send,
+            config,
+            &[
+                "--session",
+                "--print-reply=literal",
+                "--dest=org.kde.kglobalaccel",
+                "/component/kwin",
+                "org.kde.kglobalaccel.Component.invokeShortcut",
+                &format!("string:{name}"),
+            ],
+        )
+    };
+    match action {
+        MagnifierAction::Enable | MagnifierAction::Zoom => {
+            effects("loadEffect")?;
+        }
+        MagnifierAction::Disable => {
+            shortcut("view_actual_size")?;
+            effects("unloadEffect")?;
+            return Ok("off".to_string());
+        }
+        MagnifierAction::Status => {
+            let loaded = effects("isEffectLoaded")?;
+            let state = if loaded.contains("true") {
+                "on; KWin does not report the zoom level"
+            } else {
+                "off"
+            };
+            return Ok(state.to_string());
+        }
+    }
+    let Some(factor) = factor else {
+        return Ok("on".to_string());
+    };
+    // KWin zooms in fixed steps, so reset and step up to the nearest level.
+    shortcut("view_actual_size")?;
+    let steps = zoom_steps(factor);
+    for _ in 0..steps {
+        shortcut("view_zoom_in")?;
+    }
+    Ok(format!("on at {:.2}x", KWIN_ZOOM_STEP.powi(steps as i32)))
+}
+
+/// Zoom-in steps from 1x that come closest to `factor`.
+fn zoom_steps(factor: f64) -> u32 {
+    (factor.ln() / KWIN_ZOOM_STEP.ln()).round().max(0.0) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn rounds_kwin_zoom_steps() {
+        assert_eq!(zoom_steps(1.0), 0);
+        assert_eq!(zoom_steps(2.0), 4);
+        assert_eq!(zoom_steps(1.44), 2);
+    }
+}
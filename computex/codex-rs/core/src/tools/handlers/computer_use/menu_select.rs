@@ -0,0 +1,146 @@
+//! Menu-bar navigation by access keys for `computer_menu_select`.
+//!
+//! Nested menus are hard to click: submenus open on hover, close when the
+//! pointer strays, and their entries are small. GTK, Qt, LibreOffice,
+//! Firefox, Electron, and Java apps all accept access keys instead: Alt plus
+//! a letter opens a menu-bar menu, and a letter picks an entry in an open
+//! menu. The access key is the underlined letter, which screenshots don't
+//! reliably show, so it defaults to the label's first letter and the model
+//! can mark another with an underscore (`E_xport`), as GTK does. Each key
+//! press is checked with OCR: the next label must newly appear, and the last
+//! one must disappear as its menu closes.
+
+use std::path::Path;
+
+use super::find_text;
+use super::find_text::TextMatch;
+
+/// Deepest menu path accepted.
+pub(super) const MAX_DEPTH: usize = 6;
+
+/// How far apart, in capture pixels, two matches of a label may be and still
+/// count as the same spot.
+const SAME_SPOT: i64 = 8;
+
+/// A label of a menu path with its access key.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct MenuLabel {
+    /// The label as it shows on screen, without the underscore.
+    pub text: String,
+    /// Lowercase ASCII letter or digit.
+    pub key: char,
+}
+
+/// Reads a path label: `E_xport` is "Export" with access key `x`, and a
+/// label without an underscore uses its first letter or digit.
+pub(super) fn parse_label(label: &str) -> Result<MenuLabel, String> {
+    let label = label.trim();
+    let marked = label
+        .find('_')
+        .and_then(|index| label[index + 1..].chars().next());
+    let text: String = label.replacen('_', "", 1).trim().to_string();
+    let key = marked
+        .or_else(|| text.chars().find(char::is_ascii_alphanumeric))
+        .map(|key| key.to_ascii_lowercase());
+    match key {
+        _ if text.is_empty() => Err("menu labels must not be empty".to_string()),
+        Some(key) if key.is_ascii_alphanumeric() => Ok(MenuLabel { text, key }),
+        _ => Err(format!(
+            "`{label}` has no letter or digit to use as its access key; mark one with an underscore, as in `E_xport`"
+        )),
+    }
+}
+
+/// The text of one screen capture, read with OCR.
+pub(super) struct Screen {
+    tsv: String,
+}
+
+impl Screen {
+    pub(super) fn read(image: &Path) -> Result<Self, String> {
+        let tsv = find_text::recognize(image).map_err(|err| err.to_string())?;
+        Ok(Self { tsv })
+    }
+
+    fn find(&self, label: &str) -> Vec<TextMatch> {
+        find_text::find_matches(&self.tsv, label, find_text::DEFAULT_MIN_SIMILARITY)
+    }
+
+    /// Whether `label` shows somewhere it didn't on `before`, as when the
+    /// menu holding it opens.
+    pub(super) fn gained(&self, before: &Screen, label: &str) -> bool {
+        let earlier = before.find(label);
+        self.find(label)
+            .iter()
+            .any(|found| !earlier.iter().any(|was| same_spot(found, was)))
+    }
+
+    /// Whether `label` is gone from a spot where it showed on `before`, as
+    /// when the menu holding it closes.
+    pub(super) fn lost(&self, before: &Screen, label: &str) -> bool {
+        let now = self.find(label);
+        before
+            .find(label)
+            .iter()
+            .any(|was| !now.iter().any(|found| same_spot(found, was)))
+    }
+}
+
+fn same_spot(a: &TextMatch, b: &TextMatch) -> bool {
+    (a.left - b.left).abs() <= SAME_SPOT && (a.top - b.top).abs() <= SAME_SPOT
+}
+
+/// The path as the model would write it, `File > Export > PDF`.
+pub(super) fn display(labels: &[MenuLabel]) -> String {
+    labels
+        .iter()
+        .map(|label| label.text.as_str())
+        .collect::<Vec<_>>()
+        .join(" > ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn screen(words: &[(&str, i64, i64)]) -> Screen {
+        let mut tsv = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext\n".to_string();
+        for (index, (word, left, top)) in words.iter().enumerate() {
+            tsv.push_str(&format!(
+                "5\t1\t{}\t1\t1\t1\t{left}\t{top}\t60\t14\t95\t{word}\n",
+                index + 1
+            ));
+        }
+        Screen { tsv }
+    }
+
+    #[test]
+    fn parses_access_keys_and_tracks_menus() {
+        assert_eq!(
+            parse_label(" E_xport "),
+            Ok(MenuLabel {
+                text: "Export".to_string(),
+                key: 'x',
+            })
+        );
+        assert_eq!(
+            parse_label("PDF…"),
+            Ok(MenuLabel {
+                text: "PDF…".to_string(),
+                key: 'p',
+            })
+        );
+        assert!(parse_label("…").is_err());
+        assert!(parse_label("").is_err());
+
+        // "Export" is also in the document; the menu adds a second one.
+        let closed = screen(&[("File", 10, 5), ("Export", 300, 400)]);
+        let open = screen(&[("File", 10, 5), ("Export", 300, 400), ("Export", 14, 60)]);
+        assert!(open.gained(&closed, "Export"));
+        assert!(!closed.gained(&open, "Export"));
+        assert!(closed.lost(&open, "Export"));
+        assert!(!open.lost(&closed, "Export"));
+        assert!(!open.gained(&closed, "Print"));
+    }
+}
@@ -0,0 +1,4098 @@
+use async_trait::async_trait;
+use codex_protocol::models::SandboxPermissions;
+use codex_utils_image::screenshot::RawImage;
+use std::collections::BTreeMap;
+use std::env;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+use uuid::Uuid;
+use which::which;
+
+use crate::codex::Session;
+use crate::codex::TurnContext;
+use crate::config::types::ComputerUseBackend;
+use crate::config::types::ComputerUseConfig;
+use crate::config::types::CursorParking;
+use crate::config::types::InputProvider;
+use crate::config::types::Resolution;
+use crate::config::types::ScreenshotFormat;
+use crate::config::types::TargetOs;
+use crate::config::types::TypingVerification;
+use crate::config::types::Viewport;
+use crate::function_tool::FunctionCallError;
+use crate::protocol::AskForApproval;
+use crate::protocol::BackgroundEventEvent;
+use crate::protocol::EventMsg;
+use crate::protocol::QueuedGuiAction;
+use crate::protocol::ReviewDecision;
+use crate::protocol::ViewImageToolCallEvent;
+use crate::tools::context::ToolInvocation;
+use crate::tools::context::ToolOutput;
+use crate::tools::context::ToolPayload;
+use crate::tools::registry::ToolHandler;
+use crate::tools::registry::ToolKind;
+use crate::tools::sandboxing::ExecApprovalRequirement;
+use capture::Captured;
+use coordinates::map_point;
+use coordinates::screen_to_model;
+use coordinates::screenshot_resize;
+use desktop::Desktop;
+use detail::ScreenRect;
+use detail::ScreenshotDetail;
+use detail::Tier;
+use evidence::Evidence;
+use modifiers::HeldStep;
+use recipes::Recipe;
+use recipes::RecipeAction;
+use recipes::RecipeStep;
+use schema::AnnounceArgs;
+use schema::CameraSnapshotArgs;
+use schema::CheckpointArgs;
+use schema::ClearFieldArgs;
+use schema::ClearMethod;
+use schema::ClickArgs;
+use schema::CompareArgs;
+use schema::ContextMenuArgs;
+use schema::DetectElementsArgs;
+use schema::DomClickArgs;
+use schema::DomQueryArgs;
+use schema::DragArgs;
+use schema::FileDialogArgs;
+use schema::FindTextArgs;
+use schema::FocusArgs;
+use schema::HeldActionKind;
+use schema::KeyArgs;
+use schema::LandmarkAction;
+use schema::LandmarkArgs;
+use schema::ListShortcutsArgs;
+use schema::LocateArgs;
+use schema::LongPressArgs;
+use schema::MagnifierArgs;
+use schema::MenuSelectArgs;
+use schema::OfficeArgs;
+use schema::PinchArgs;
+use schema::PrintDialogArgs;
+use schema::RecentFramesArgs;
+use schema::ScreenshotArgs;
+use schema::ScrollArgs;
+use schema::SetTargetArgs;
+use schema::SetThemeArgs;
+use schema::ShortcutScope;
+use schema::SwipeArgs;
+use schema::SystemControlArgs;
+use schema::SystemSetting;
+use schema::ThemeName;
+use schema::TrayActionArg;
+use schema::TrayActivateArgs;
+use schema::TypeArgs;
+use schema::WaitAppIdleArgs;
+use schema::WaitDownloadArgs;
+use schema::WatchDemoArgs;
+use schema::WithModifiersArgs;
+use schema::WorkspaceMoveWindowArgs;
+use schema::WorkspaceSwitchArgs;
+use schema::parse_args;
+use side_effects::Requirement;
+use side_effects::SideEffect;
+use system_control::Level;
+use verify::Verification;
+
+mod accessibility;
+mod accuracy;
+mod active_window;
+mod announce;
+mod batch;
+mod browser;
+pub mod browser_bridge;
+pub mod bundled;
+pub mod calibration;
+mod camera;
+mod capture;
+mod classifier;
+mod clipboard;
+mod compare;
+pub mod confinement;
+mod console;
+mod context_menu;
+mod coordinates;
+mod deferred;
+mod delegate;
+mod demonstration;
+mod desktop;
+mod detail;
+mod detector;
+pub mod diagnostics;
+mod download;
+pub mod encryption;
+mod evidence;
+pub mod export;
+mod file_dialog;
+mod find_text;
+mod focus_guard;
+mod frames;
+mod gestures;
+mod grabs;
+mod hover;
+mod idle;
+mod ime;
+mod inhibit;
+mod input_helper;
+mod json_store;
+mod keys;
+mod landmarks;
+mod locator;
+mod lock;
+mod magnifier;
+mod menu_select;
+mod modifiers;
+mod network;
+mod office;
+mod parking;
+mod password_guard;
+pub mod pause;
+mod pii;
+pub mod plugins;
+mod portal;
+mod prefetch;
+mod print_dialog;
+mod qmp;
+pub mod recipes;
+mod recovery;
+pub mod remote;
+pub mod rotation;
+mod scaling;
+mod schema;
+mod screen_text;
+mod screenshot_files;
+pub mod script;
+mod shortcuts;
+mod side_effects;
+pub mod snapshots;
+mod state;
+pub mod stats;
+mod sticky_target;
+mod system_control;
+mod template;
+mod theme;
+pub mod timelapse;
+mod timeline;
+mod tray;
+mod turn_summary;
+mod typing_delay;
+pub mod unlock;
+mod verbosity;
+mod verify;
+pub mod virtual_output;
+mod workspaces;
+mod ydotool;
+
+pub(crate) use deferred::take_deferred_input;
+pub use delegate::ComputerDelegateHandler;
+pub(crate) use delegate::DELEGATE_TOOL;
+pub(crate) use schema::tool_parameters;
+pub(crate) use state::ComputerUseState;
+pub(crate) use timeline::restore_context;
+pub(crate) use turn_summary::summarize_turn;
+
+const DEFAULT_SCROLL_TICKS: u32 = 3;
+
+/// Longest pause a recipe's `wait_ms` step may take.
+const MAX_RECIPE_WAIT_MS: u64 = 30_000;
+
+/// Time a menu gets to open or close after an access key.
+const MENU_SETTLE: Duration = Duration::from_millis(300);
+
+/// Every GUI tool served by [`ComputerUseHandler`], in the order they are
+/// advertised to the model.
+const COMPUTER_USE_TOOLS: &[&str] = &[
+    "computer_screenshot",
+    "computer_click",
+    "computer_drag",
+    "computer_scroll",
+    "computer_with_modifiers",
+    "computer_swipe",
+    "computer_long_press",
+    "computer_pinch",
+    "computer_type",
+    "computer_key",
+    "computer_clear_field",
+    "computer_focus_next",
+    "computer_focus_prev",
+    "computer_find_text",
+    "computer_detect_elements",
+    "computer_locate",
+    "computer_compare",
+    "computer_browser_state",
+    "computer_dom_query",
+    "computer_dom_click",
+    "computer_active_window",
+    "computer_workspace_list",
+    "computer_workspace_switch",
+    "computer_workspace_move_window",
+    "computer_tray_list",
+    "computer_tray_activate",
+    "computer_list_shortcuts",
+    "computer_network_state",
+    "computer_print_dialog_handle",
+    "computer_file_dialog",
+    "computer_office",
+    "computer_menu_select",
+    "computer_context_menu",
+    "computer_wait_download",
+    "computer_wait_app_idle",
+    "computer_set_target",
+    "computer_self_test",
+    "computer_landmarks",
+    "computer_checkpoint",
+    "computer_unlock",
+    "computer_magnifier",
+    "computer_set_theme",
+    "computer_system_control",
+    "computer_announce",
+    "computer_camera_snapshot",
+    "computer_watch_demo",
+    "computer_recent_frames",
+    "computer_batch",
+    "computer_run_queue",
+];
+
+/// Key chords that `computer_key` refuses to send without `confirm=true`.
+pub(crate) const DESTRUCTIVE_KEY_COMBOS: &[&[&str]] = &[
+    &["alt", "f4"],
+    &["ctrl", "w"],
+    &["ctrl", "q"],
+    &["ctrl", "shift", "q"],
+    &["super", "q"],
+    &["super", "w"],
+    &["super", "shift", "w"],
+    &["ctrl", "alt", "backspace"],
+];
+
+/// How `backend` reaches the display, surfaced in the instructions so the
+/// model knows which environment its actions land in.
+pub(crate) fn backend_description(backend: ComputerUseBackend) -> &'static str {
+    match backend {
+        ComputerUseBackend::X11 => {
+            "an X11 display driven by `xdotool` for input and ImageMagick `import` for screenshots"
+        }
+        ComputerUseBackend::Qemu => {
+            "the console of a QEMU virtual machine, captured with QMP `screendump` and driven with QMP `input-send-event`; there are no windows to query, so window and app tools don't apply"
+        }
+        ComputerUseBackend::Console => {
+            "a text console (a tmux pane or a serial port); `computer_screenshot` returns the screen as text with the cursor position, `computer_type` and `computer_key` send what a terminal keyboard would, and there is no pointer or window"
+        }
+    }
+}
+
+/// Tools that move or press the pointer, or record pointer positions.
+/// Disabled in keyboard-only mode.
+const POINTER_TOOLS: &[&str] = &[
+    "computer_click",
+    "computer_drag",
+    "computer_scroll",
+    "computer_with_modifiers",
+    "computer_swipe",
+    "computer_long_press",
+    "computer_pinch",
+    "computer_landmarks",
+    "computer_print_dialog_handle",
+    "computer_context_menu",
+];
+
+/// Tools that read the screen without sending input, which a target window
+/// from `computer_set_target` crops.
+const SCREEN_TOOLS: &[&str] = &[
+    "computer_screenshot",
+    "computer_find_text",
+    "computer_detect_elements",
+    "computer_locate",
+    "computer_compare",
+];
+
+/// Emulated touch gestures, offered when `computer_use.gestures` is set.
+const GESTURE_TOOLS: &[&str] = &["computer_swipe", "computer_long_press", "computer_pinch"];
+
+/// Page-level tools served by the browser extension, offered when
+/// `computer_use.browser_bridge` is set.
+const DOM_TOOLS: &[&str] = &["computer_dom_query", "computer_dom_click"];
+
+/// Focus helpers only offered in keyboard-only mode.
+const FOCUS_TOOLS: &[&str] = &["computer_focus_next", "computer_focus_prev"];
+
+/// Input tools that are held for review when `computer_use.action_queue` is
+/// set, instead of running immediately.
+const QUEUED_TOOLS: &[&str] = &[
+    "computer_click",
+    "computer_drag",
+    "computer_scroll",
+    "computer_with_modifiers",
+    "computer_swipe",
+    "computer_long_press",
+    "computer_pinch",
+    "computer_type",
+    "computer_key",
+    "computer_clear_field",
+    "computer_focus_next",
+    "computer_focus_prev",
+    "computer_print_dialog_handle",
+    "computer_file_dialog",
+    "computer_menu_select",
+    "computer_context_menu",
+];
+
+/// GUI tools enabled by `config`, in the order they are advertised to the model.
+pub(crate) fn enabled_tools(config: &ComputerUseConfig) -> Vec<&'static str> {
+    COMPUTER_USE_TOOLS
+        .iter()
+        .copied()
+        .filter(|name| config.landmarks || *name != "computer_landmarks")
+        .filter(|name| config.gestures || !GESTURE_TOOLS.contains(name))
+        .filter(|name| config.action_queue || *name != "computer_run_queue")
+        .filter(|name| !config.action_queue || *name != "computer_batch")
+        .filter(|name| config.vm.is_some() || *name != "computer_checkpoint")
+        .filter(|name| config.unlock_helper || *name != "computer_unlock")
+        .filter(|name| config.magnifier || *name != "computer_magnifier")
+        .filter(|name| config.theme_switching || *name != "computer_set_theme")
+        .filter(|name| config.system_controls || *name != "computer_system_control")
+        .filter(|name| config.announcements || *name != "computer_announce")
+        .filter(|name| config.camera || *name != "computer_camera_snapshot")
+        .filter(|name| config.demonstrations || *name != "computer_watch_demo")
+        .filter(|name| config.recent_frames || *name != "computer_recent_frames")
+        .filter(|name| config.browser_bridge || !DOM_TOOLS.contains(name))
+        .filter(|name| config.office_bridge || *name != "computer_office")
+        .filter(|name| config.element_detector.is_some() || *name != "computer_detect_elements")
+        .filter(|name| {
+            config.backend != ComputerUseBackend::Console || console::CONSOLE_TOOLS.contains(name)
+        })
+        .filter(|name| {
+            if config.keyboard_only {
+                !POINTER_TOOLS.contains(name)
+            } else {
+                !FOCUS_TOOLS.contains(name)
+            }
+        })
+        .collect()
+}
+
+/// Tools that only observe the screen or local state, or change desktop
+/// settings, and never send input, so they are not subject to the
+/// allowed/blocked app checks.
+fn sends_input(tool_name: &str) -> bool {
+    !matches!(
+        tool_name,
+        "computer_screenshot"
+            | "computer_find_text"
+            | "computer_compare"
+            | "computer_landmarks"
+            | "computer_magnifier"
+            | "computer_set_theme"
+            | "computer_system_control"
+            | "computer_announce"
+            | "computer_camera_snapshot"
+            | "computer_wait_download"
+            | "computer_wait_app_idle"
+            | "computer_set_target"
+            | "computer_watch_demo"
+            | "computer_recent_frames"
+            | "computer_detect_elements"
+            | "computer_dom_query"
+            | "computer_office"
+            | "computer_active_window"
+            | "computer_workspace_list"
+            | "computer_workspace_switch"
+            | "computer_workspace_move_window"
+            | "computer_tray_list"
+            | "computer_tray_activate"
+            | "computer_list_shortcuts"
+            | "computer_network_state"
+            | "computer_locate"
+    )
+}
+
+/// Whether the tool can change anything, as opposed to only reading the
+/// screen or the desktop's state.
+fn mutates(tool_name: &str) -> bool {
+    !matches!(
+        tool_name,
+        "computer_screenshot"
+            | "computer_find_text"
+            | "computer_detect_elements"
+            | "computer_locate"
+            | "computer_recent_frames"
+            | "computer_dom_query"
+            | "computer_active_window"
+            | "computer_workspace_list"
+            | "computer_tray_list"
+            | "computer_list_shortcuts"
+            | "computer_network_state"
+    )
+}
+
+pub struct ComputerUseHandler;
+
+#[async_trait]
+impl ToolHandler for ComputerUseHandler {
+    fn kind(&self) -> ToolKind {
+        ToolKind::Function
+    }
+
+    async fn is_mutating(&self, invocation: &ToolInvocation) -> bool {
+        mutates(&invocation.tool_name)
+    }
+
+    async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
+        let ToolInvocation {
+            session,
+            turn,
+            payload,
+            call_id,
+            tool_name,
+            ..
+        } = invocation;
+
+        let ToolPayload::Function { arguments } = payload else {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "unsupported payload for {tool_name}"
+            )));
+        };
+
+        let config = &turn.tools_config.computer_use;
+        // Queued actions are vetted as they are queued, while the model can
+        // still change its plan.
+        if mutates(&tool_name) && tool_name != "computer_run_queue" {
+            classifier::check(&session, &turn, &call_id, &tool_name, &arguments).await?;
+        }
+        if config.action_queue && QUEUED_TOOLS.contains(&tool_name.as_str()) {
+            ensure_enabled(config, &tool_name)?;
+            check_queued_args(&tool_name, &arguments)?;
+            let position = session
+                .services
+                .computer_use
+                .lock()
+                .await
+                .enqueue(QueuedGuiAction {
+                    tool: tool_name.clone(),
+                    arguments,
+                });
+            return Ok(ToolOutput::Function {
+                content: format!(
+                    "queued {tool_name} as action #{position} (not run yet); queue the remaining steps, then call computer_run_queue to have the user review and run them"
+                ),
+                content_items: None,
+                success: Some(true),
+            });
+        }
+        let prefetch_after = sends_input(&tool_name);
+        let started = Instant::now();
+        if prefetch_after && tool_name != "computer_run_queue" {
+            // Queued actions skip this: the user reviews them before they run.
+            check_side_effects(&session, &turn, &call_id, &tool_name, &arguments).await?;
+        }
+        let result = if tool_name == "computer_run_queue" {
+            ensure_enabled(config, &tool_name)?;
+            Self::run_queue(&session, &turn, call_id).await
+        } else if tool_name == "computer_batch" {
+            ensure_enabled(config, &tool_name)?;
+            batch::run(&session, &turn, call_id, &arguments).await
+        } else if let Some((recipe, action)) = recipes::find(&config.recipes, &tool_name) {
+            Self::run_recipe(&session, &turn, call_id, recipe, action, &arguments).await
+        } else if let Some((plugin, tool)) = plugins::find(&config.plugins, &tool_name) {
+            plugins::run(&session, &turn, call_id, plugin, tool, &arguments).await
+        } else {
+            Self::run_counted(
+                Arc::clone(&session),
+                Arc::clone(&turn),
+                call_id,
+                tool_name.clone(),
+                arguments,
+            )
+            .await
+        };
+        let result = verbosity::shape(
+            config.result_verbosity,
+            &tool_name,
+            prefetch_after,
+            started.elapsed(),
+            result,
+        );
+        let result = match result {
+            Ok(output) => Ok(add_app_guidance(&session, config, output).await),
+            Err(err) => Err(err),
+        };
+        // A prefetch would capture the whole screen, not the target window.
+        if prefetch_after
+            && result.is_ok()
+            && session
+                .services
+                .computer_use
+                .lock()
+                .await
+                .sticky_target()
+                .is_none()
+        {
+            prefetch::schedule(&session, config).await;
+        }
+        result
+    }
+}
+
+impl ComputerUseHandler {
+    /// Hands the queued actions to the user for review, then runs the
+    /// approved (possibly reordered or edited) actions in order, stopping at
+    /// the first failure.
+    async fn run_queue(
+        session: &Arc<Session>,
+        turn: &Arc<TurnContext>,
+        call_id: String,
+    ) -> Result<ToolOutput, FunctionCallError> {
+        let queued = session.services.computer_use.lock().await.take_queue();
+        if queued.is_empty() {
+            return Err(FunctionCallError::RespondToModel(
+                "the action queue is empty; queue GUI actions before calling computer_run_queue"
+                    .to_string(),
+            ));
+        }
+        let Some(approved) = session
+            .request_gui_queue_review(turn, call_id.clone(), queued.clone())
+            .await
+        else {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "the user rejected the queue; none of the {} queued action(s) ran",
+                queued.len()
+            )));
+        };
+        if approved.is_empty() {
+            return Err(FunctionCallError::RespondToModel(
+                "the user removed every queued action; nothing ran".to_string(),
+            ));
+        }
+
+        let total = approved.len();
+        let mut lines = vec![if approved == queued {
+            format!("ran {total} queued action(s) as approved by the user:")
+        } else {
+            format!(
+                "ran {total} queued action(s); the user edited the queue before running it, so compare with your plan:"
+            )
+        }];
+        for (index, action) in approved.into_iter().enumerate() {
+            let step = index + 1;
+            let tool = action.tool.clone();
+            match Self::run_counted(
+                Arc::clone(session),
+                Arc::clone(turn),
+                call_id.clone(),
+                action.tool,
+                action.arguments,
+            )
+            .await
+            {
+                Ok(ToolOutput::Function { content, .. }) => {
+                    lines.push(format!("{step}. {tool}: {content}"));
+                }
+                Ok(_) => lines.push(format!("{step}. {tool}: done")),
+                Err(FunctionCallError::RespondToModel(err)) => {
+                    lines.push(format!("{step}. {tool} failed: {err}"));
+                    if step < total {
+                        lines.push(format!(
+                            "stopped; the remaining {} action(s) did not run",
+                            total - step
+                        ));
+                    }
+                    return Ok(ToolOutput::Function {
+                        content: lines.join("\n"),
+                        content_items: None,
+                        success: Some(false),
+                    });
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(ToolOutput::Function {
+            content: lines.join("\n"),
+            content_items: None,
+            success: Some(true),
+        })
+    }
+
+    /// Runs the steps of a recipe action as GUI tools, stopping at the first
+    /// failure. Refuses unless the recipe's app is focused.
+    async fn run_recipe(
+        session: &Arc<Session>,
+        turn: &Arc<TurnContext>,
+        call_id: String,
+        recipe: &Recipe,
+        action: &RecipeAction,
+        arguments: &str,
+    ) -> Result<ToolOutput, FunctionCallError> {
+        let config = &turn.tools_config.computer_use;
+        let tool = recipe.tool_name(action);
+        if config.action_queue {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "{tool} can't run while computer_use.action_queue is set; queue its steps as individual actions instead"
+            )));
+        }
+        let arguments: BTreeMap<String, String> = parse_args(arguments)?;
+        if let Some(missing) = action
+            .parameters
+            .iter()
+            .find(|parameter| !arguments.contains_key(*parameter))
+        {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "{tool} requires `{missing}`"
+            )));
+        }
+        ensure_display(config)?;
+        let xdotool = input_command(config)?;
+        let class = active_window_class(&xdotool, config)?;
+        if !class.eq_ignore_ascii_case(&recipe.window_class) {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "{tool} only runs while a {} window is focused, but the focused window is {class}; focus the app first",
+                recipe.window_class
+            )));
+        }
+        let codex_home = turn.client.config().codex_home.clone();
+        let total = action.steps.len();
+        let mut lines = vec![format!(
+            "ran recipe {}.{} ({total} step(s)):",
+            recipe.app, action.name
+        )];
+        for (index, step) in action.steps.iter().enumerate() {
+            let number = index + 1;
+            let call = match step.fill(&arguments) {
+                RecipeStep::WaitMs(ms) => {
+                    tokio::time::sleep(Duration::from_millis(ms.min(MAX_RECIPE_WAIT_MS))).await;
+                    lines.push(format!("{number}. waited {ms} ms"));
+                    continue;
+                }
+                RecipeStep::Key(chord) => Ok((
+                    "computer_key",
+                    serde_json::json!({ "keys": [chord], "window": recipe.window_class }),
+                )),
+                RecipeStep::Type(text) => Ok((
+                    "computer_type",
+                    serde_json::json!({ "text": text, "window": recipe.window_class }),
+                )),
+                RecipeStep::Click(query) => {
+                    recipe_click(
+                        session,
+                        config,
+                        &xdotool,
+                        &codex_home,
+                        &query,
+                        "left",
+                        false,
+                    )
+                    .await
+                }
+                RecipeStep::DoubleClick(query) => {
+                    recipe_click(session, config, &xdotool, &codex_home, &query, "left", true).await
+                }
+                RecipeStep::RightClick(query) => {
+                    recipe_click(
+                        session,
+                        config,
+                        &xdotool,
+                        &codex_home,
+                        &query,
+                        "right",
+                        false,
+                    )
+                    .await
+                }
+            };
+            let result = match call {
+                Ok((step_tool, step_arguments)) => {
+                    let step_arguments = step_arguments.to_string();
+                    check_side_effects(session, turn, &call_id, step_tool, &step_arguments).await?;
+                    Self::run_counted(
+                        Arc::clone(session),
+                        Arc::clone(turn),
+                        call_id.clone(),
+                        step_tool.to_string(),
+                        step_arguments,
+                    )
+                    .await
+                    .map(|output| (step_tool, output))
+                }
+                Err(err) => Err(err),
+            };
+            match result {
+                Ok((step_tool, ToolOutput::Function { content, .. })) => {
+                    lines.push(format!("{number}. {step_tool}: {content}"));
+                }
+                Ok((step_tool, _)) => lines.push(format!("{number}. {step_tool}: done")),
+                Err(FunctionCallError::RespondToModel(err)) => {
+                    lines.push(format!("{number}. failed: {err}"));
+                    if number < total {
+                        lines.push(format!(
+                            "stopped; the remaining {} step(s) did not run",
+                            total - number
+                        ));
+                    }
+                    return Ok(ToolOutput::Function {
+                        content: lines.join("\n"),
+                        content_items: None,
+                        success: Some(false),
+                    });
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(ToolOutput::Function {
+            content: lines.join("\n"),
+            content_items: None,
+            success: Some(true),
+        })
+    }
+
+    /// Runs `tool_name` and adds the run to the session's GUI usage.
+    async fn run_counted(
+        session: Arc<Session>,
+        turn: Arc<TurnContext>,
+        call_id: String,
+        tool_name: String,
+        arguments: String,
+    ) -> Result<ToolOutput, FunctionCallError> {
+        let sent_input = sends_input(&tool_name);
+        let started = Instant::now();
+        let clipboard = if sent_input && turn.tools_config.computer_use.preserve_clipboard {
+            let agent = session.services.computer_use.lock().await.agent_clipboard();
+            clipboard::swap_in(&turn.tools_config.computer_use, agent)
+        } else {
+            None
+        };
+        let config = &turn.tools_config.computer_use;
+        // A text console has no pixels to capture.
+        let evidence = if sent_input
+            && config.failure_screenshots
+            && config.backend != ComputerUseBackend::Console
+        {
+            Some(Evidence::start(&session, config, &tool_name, &arguments).await)
+        } else {
+            None
+        };
+        let park_after = config.park_cursor == CursorParking::AfterAction
+            && config.backend != ComputerUseBackend::Console
+            && parking::PARKED_TOOLS.contains(&tool_name.as_str());
+        let result = Self::run_tool(
+            Arc::clone(&session),
+            Arc::clone(&turn),
+            call_id.clone(),
+            tool_name.clone(),
+            arguments,
+        )
+        .await;
+        let result = match evidence {
+            Some(evidence) => evidence.finish(&session, &turn.sub_id, result).await,
+            None => result,
+        };
+        if park_after
+            && result.is_ok()
+            && let Err(err) = parking::park(&session, config).await
+        {
+            tracing::debug!("failed to park the pointer: {err:?}");
+        }
+        let copied = match clipboard {
+            Some(swap) => swap.restore(&turn.tools_config.computer_use).await,
+            None => None,
+        };
+        {
+            let mut state = session.services.computer_use.lock().await;
+            if let Some(copied) = copied {
+                state.push_agent_clipboard(copied);
+            }
+            state.record_tool_run(started.elapsed(), sent_input && result.is_ok());
+        }
+        let succeeded = match &result {
+            Ok(ToolOutput::Function { success, .. }) => success.unwrap_or(true),
+            Ok(_) => true,
+            Err(_) => false,
+        };
+        timeline::record(
+            &session,
+            config,
+            call_id,
+            tool_name,
+            succeeded,
+            started.elapsed(),
+        )
+        .await;
+        result
+    }
+
+    async fn run_tool(
+        session: Arc<Session>,
+        turn: Arc<TurnContext>,
+        call_id: String,
+        tool_name: String,
+        arguments: String,
+    ) -> Result<ToolOutput, FunctionCallError> {
+        let config = &turn.tools_config.computer_use;
+        ensure_enabled(config, &tool_name)?;
+        if tool_name == "computer_self_test" {
+            // Reports problems (including a missing display) instead of failing.
+            let steps = diagnostics::self_test(config);
+            return Ok(ToolOutput::Function {
+                content: diagnostics::format_self_test(&steps),
+                content_items: None,
+                success: Some(diagnostics::self_test_passed(&steps)),
+            });
+        }
+        if tool_name == "computer_checkpoint" {
+            // Snapshots go through the hypervisor, not the display.
+            let args: CheckpointArgs = parse_args(&arguments)?;
+            let Some(vm) = &config.vm else {
+                return Err(FunctionCallError::RespondToModel(
+                    "computer_checkpoint requires computer_use.vm_provider and vm_name".to_string(),
+                ));
+            };
+            let label = args.label.trim();
+            let codex_home = turn.client.config().codex_home.clone();
+            let checkpoint = snapshots::create_checkpoint(vm, &codex_home, label)
+                .map_err(|err| FunctionCallError::RespondToModel(format!("{err:#}")))?;
+            return Ok(ToolOutput::Function {
+                content: format!(
+                    "snapshotted VM `{}` ({}) as `{}`; the user can restore it with `computex rollback`",
+                    vm.name, vm.provider, checkpoint.name
+                ),
+                content_items: None,
+                success: Some(true),
+            });
+        }
+        if config.backend == ComputerUseBackend::Console {
+            // A text console has no display, pointer, or windows.
+            return console::run(&session, config, &tool_name, &arguments).await;
+        }
+        ensure_display(config)?;
+        if config.inhibit_display_sleep {
+            session
+                .services
+                .computer_use
+                .lock()
+                .await
+                .hold_display_awake(config);
+        }
+        if config.recent_frames {
+            session
+                .services
+                .computer_use
+                .lock()
+                .await
+                .record_frames(config);
+        }
+        if tool_name == "computer_unlock" {
+            // Runs at the lock screen, so it skips the lock and window checks.
+            if !config.unlock_helper {
+                return Err(FunctionCallError::RespondToModel(
+                    "computer_unlock requires computer_use.unlock_helper".to_string(),
+                ));
+            }
+            pause::wait_while_paused().await;
+            let content = unlock::unlock(config)?;
+            session
+                .services
+                .computer_use
+                .lock()
+                .await
+                .clear_lock_suspension();
+            return Ok(ToolOutput::Function {
+                content,
+                content_items: None,
+                success: Some(true),
+            });
+        }
+        if tool_name == "computer_set_target" {
+            let args: SetTargetArgs = parse_args(&arguments)?;
+            let window = args
+                .window
+                .map(|window| window.trim().to_string())
+                .filter(|window| !window.is_empty());
+            let content = match &window {
+                Some(window) => {
+                    let xdotool = input_command(config)?;
+                    let target = sticky_target::resolve(&xdotool, config, window)?;
+                    format!(
+                        "targeting `{}` (\"{}\") at {}; screenshots and coordinates are now relative to this window and input is only sent while it has focus, until computer_set_target is called without `window`",
+                        target.class, target.title, target.viewport
+                    )
+                }
+                None => "cleared the target window; screenshots and coordinates cover the whole screen again".to_string(),
+            };
+            let mut state = session.services.computer_use.lock().await;
+            state.set_sticky_target(window);
+            // A prefetched screenshot covers the old area.
+            if let Some(stale) = state.note_input() {
+                let _ = std::fs::remove_file(stale);
+            }
+            return Ok(ToolOutput::Function {
+                content,
+                content_items: None,
+                success: Some(true),
+            });
+        }
+        let space = config.coordinate_space;
+        let sticky_target = session.services.computer_use.lock().await.sticky_target();
+        if sends_input(&tool_name) {
+            if pause::wait_while_paused().await {
+                tracing::debug!("{tool_name} resumed after a pause from the live preview");
+            }
+            ensure_unlocked(&session, &turn, &call_id).await?;
+            break_stuck_grab(&session, config).await?;
+            if let Some(window) = &sticky_target {
+                let xdotool = input_command(config)?;
+                let focused = focus_guard::ensure_focused(&xdotool, config, window)?;
+                if !focused.is_empty() {
+                    tracing::debug!("{tool_name} ran{focused}");
+                }
+            }
+            ensure_window_permitted(config)?;
+            if let Some(stale) = session.services.computer_use.lock().await.note_input() {
+                let _ = std::fs::remove_file(stale);
+            }
+            if config.turn_change_summary {
+                turn_summary::record_baseline(&session, config).await;
+            }
+        }
+        let scoped;
+        let config = match &sticky_target {
+            Some(window) => {
+                let xdotool = input_command(config)?;
+                match sticky_target::resolve(&xdotool, config, window) {
+                    Ok(target) => {
+                        scoped = sticky_target::scoped_config(config, &target);
+                        &scoped
+                    }
+                    // Tools that neither look at nor act on the screen don't
+                    // need the target.
+                    Err(_)
+                        if !sends_input(&tool_name)
+                            && !SCREEN_TOOLS.contains(&tool_name.as_str()) =>
+                    {
+                        config
+                    }
+                    Err(err) => return Err(err),
+                }
+            }
+            None => config,
+        };
+
+        match tool_name.as_str() {
+            "computer_screenshot" if config.screenshot_format == ScreenshotFormat::Text => {
+                let args: ScreenshotArgs = if arguments.trim().is_empty() {
+                    ScreenshotArgs::default()
+                } else {
+                    parse_args(&arguments)?
+                };
+                if args.region()?.is_some() {
+                    return Err(FunctionCallError::RespondToModel(
+                        "screenshots are returned as text here, which always covers the whole screen; call computer_screenshot without a region".to_string(),
+                    ));
+                }
+                let xdotool = input_command(config)?;
+                let (screen_w, screen_h) = screen_geometry(&session, &xdotool, config).await?;
+                // OCR the full-resolution screen; downscaled text reads poorly.
+                let image_path = capture_root_window(config, None, None)?;
+                let read = screen_text::read(&image_path);
+                let _ = std::fs::remove_file(&image_path);
+                let screen = read?;
+                let (origin_x, origin_y) = coordinates::viewport_origin(config);
+                let content = screen_text::describe(
+                    config,
+                    &screen,
+                    screen_text::focused_window(config),
+                    |x, y| screen_to_model(config, x + origin_x, y + origin_y, screen_w, screen_h),
+                );
+                Ok(ToolOutput::Function {
+                    content,
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_screenshot" => {
+                let args: ScreenshotArgs = if arguments.trim().is_empty() {
+                    ScreenshotArgs::default()
+                } else {
+                    parse_args(&arguments)?
+                };
+                let region = args.region()?;
+                if region.is_some() && args.detail == ScreenshotDetail::Low {
+                    return Err(FunctionCallError::RespondToModel(
+                        "a screenshot region needs detail \"high\" or \"auto\"".to_string(),
+                    ));
+                }
+                let ocr_confidence = session
+                    .services
+                    .computer_use
+                    .lock()
+                    .await
+                    .recent_ocr_confidence(&turn.sub_id);
+                let tier = detail::resolve_tier(args.detail, ocr_confidence, region.is_some());
+                let budget = reserve_screenshots(
+                    &mut *session.services.computer_use.lock().await,
+                    &turn.sub_id,
+                    config,
+                    1,
+                );
+                if let Some((0, limit, _)) = budget {
+                    return Err(screenshot_budget_exhausted(limit));
+                }
+                let prefetched = match config.screenshot_prefetch_ms {
+                    Some(window_ms) if tier == Tier::Standard => prefetch::fresh(
+                        session.services.computer_use.lock().await.take_prefetch(),
+                        Duration::from_millis(window_ms),
+                    ),
+                    _ => None,
+                };
+                let (image_path, mapping) = match prefetched {
+                    Some(prefetched) => {
+                        let age = prefetched.captured_at.elapsed().as_millis();
+                        (
+                            prefetched.path,
+                            Some(format!(
+                                "prefetched {age}ms ago; no GUI input was sent since"
+                            )),
+                        )
+                    }
+                    None => {
+                        let unscoped = &turn.tools_config.computer_use;
+                        if unscoped.park_cursor == CursorParking::BeforeScreenshot
+                            && unscoped.backend != ComputerUseBackend::Console
+                        {
+                            match parking::park(&session, unscoped).await {
+                                Ok(()) => tokio::time::sleep(parking::HOVER_CLEAR).await,
+                                Err(err) => {
+                                    tracing::debug!("failed to park the pointer: {err:?}");
+                                }
+                            }
+                        }
+                        // A parked pointer hovers nothing already.
+                        let restore = if unscoped.backend == ComputerUseBackend::X11
+                            && unscoped.park_cursor != CursorParking::BeforeScreenshot
+                        {
+                            hover::suppress(&session, unscoped).await
+                        } else {
+                            None
+                        };
+                        let captured = capture_at_detail(config, tier, region);
+                        if let Some(restore) = restore {
+                            restore.finish(&session, unscoped).await;
+                        }
+                        match captured {
+                            Ok(captured) => captured,
+                            Err(err) => {
+                                release_screenshots(
+                                    &mut *session.services.computer_use.lock().await,
+                                    &turn.sub_id,
+                                    budget,
+                                );
+                                return Err(err);
+                            }
+                        }
+                    }
+                };
+                deferred::attach_images(&session, std::slice::from_ref(&image_path)).await;
+                record_attached_image(&session, &image_path, true).await;
+                // The image was read when it was attached, so it can move now.
+                let image_path = if config.screenshot_dir.is_some() {
+                    let seq = session
+                        .services
+                        .computer_use
+                        .lock()
+                        .await
+                        .next_kept_screenshot();
+                    let kept = screenshot_files::keep(
+                        config,
+                        image_path,
+                        &session.conversation_id().to_string(),
+                        &turn.sub_id,
+                        seq,
+                    )?;
+                    session
+                        .services
+                        .computer_use
+                        .lock()
+                        .await
+                        .note_kept_screenshot(kept.clone());
+                    if seq == 1
+                        && let Some(scheme) = screenshot_files::scheme(config)
+                    {
+                        session
+                            .send_event(
+                                turn.as_ref(),
+                                EventMsg::BackgroundEvent(BackgroundEventEvent {
+                                    message: format!(
+                                        "computer_use screenshots are saved as {scheme}"
+                                    ),
+                                }),
+                            )
+                            .await;
+                    }
+                    kept
+                } else {
+                    image_path
+                };
+
+                session
+                    .send_event(
+                        turn.as_ref(),
+                        EventMsg::ViewImageToolCall(ViewImageToolCallEvent {
+                            call_id,
+                            path: image_path.clone(),
+                        }),
+                    )
+                    .await;
+
+                let display = image_path.display();
+                let label = config.coordinate_label();
+                let mut content =
+                    format!("captured screenshot at {display} (coordinates: {label})");
+                if let Some(mapping) = mapping {
+                    content.push('\n');
+                    content.push_str(&mapping);
+                }
+                if let Some((_, limit, remaining)) = budget {
+                    content.push_str(&screenshot_budget_note(limit, remaining));
+                }
+                Ok(ToolOutput::Function {
+                    content,
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_click" => {
+                let args: ClickArgs = parse_args(&arguments)?;
+                let xdotool = input_command(config)?;
+                let (screen_w, screen_h) = screen_geometry(&session, &xdotool, config).await?;
+                let point = map_point(config, args.x, args.y, screen_w, screen_h);
+                let button = args.button.unwrap_or_default().xdotool_button().to_string();
+                let mut cmd = vec![
+                    "mousemove".to_string(),
+                    "--sync".to_string(),
+                    point.screen_x.to_string(),
+                    point.screen_y.to_string(),
+                    "click".to_string(),
+                    button.clone(),
+                ];
+                if args.double.unwrap_or(false) {
+                    cmd.extend(["click".to_string(), button]);
+                }
+                let at = point.display(space);
+                let sample = accuracy::measure(config, &point);
+                let recovered = run_input(
+                    &session,
+                    &xdotool,
+                    &cmd,
+                    config,
+                    Some(format!("click at {at}")),
+                )
+                .await?;
+                if let Some(sample) = sample {
+                    session
+                        .services
+                        .computer_use
+                        .lock()
+                        .await
+                        .note_click_accuracy(sample);
+                }
+                Ok(ToolOutput::Function {
+                    content: format!("clicked at {at}{recovered}"),
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_drag" => {
+                let args: DragArgs = parse_args(&arguments)?;
+                let xdotool = input_command(config)?;
+                let (screen_w, screen_h) = screen_geometry(&session, &xdotool, config).await?;
+                let from = map_point(config, args.from_x, args.from_y, screen_w, screen_h);
+                let to = map_point(config, args.to_x, args.to_y, screen_w, screen_h);
+                let button = args.button.unwrap_or_default().xdotool_button().to_string();
+                let cmd = vec![
+                    "mousemove".to_string(),
+                    "--sync".to_string(),
+                    from.screen_x.to_string(),
+                    from.screen_y.to_string(),
+                    "mousedown".to_string(),
+                    button.clone(),
+                    "mousemove".to_string(),
+                    "--sync".to_string(),
+                    to.screen_x.to_string(),
+                    to.screen_y.to_string(),
+                    "mouseup".to_string(),
+                    button,
+                ];
+                let from = from.display(space);
+                let to = to.display(space);
+                let breadcrumb = format!("drag from {from} to {to}");
+                let recovered =
+                    run_input(&session, &xdotool, &cmd, config, Some(breadcrumb)).await?;
+                Ok(ToolOutput::Function {
+                    content: format!("dragged from {from} to {to}{recovered}"),
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_with_modifiers" => {
+                let args: WithModifiersArgs = parse_args(&arguments)?;
+                let modifiers = modifiers::modifier_keys(&args.modifiers, config.target_os)
+                    .map_err(FunctionCallError::RespondToModel)?;
+                if args.actions.is_empty() || args.actions.len() > modifiers::MAX_HELD_ACTIONS {
+                    return Err(FunctionCallError::RespondToModel(format!(
+                        "computer_with_modifiers takes between 1 and {} actions",
+                        modifiers::MAX_HELD_ACTIONS
+                    )));
+                }
+                let xdotool = input_command(config)?;
+                let (screen_w, screen_h) = screen_geometry(&session, &xdotool, config).await?;
+                let mut steps = Vec::new();
+                let mut performed = Vec::new();
+                for (index, action) in args.actions.iter().enumerate() {
+                    let at = map_point(config, action.x, action.y, screen_w, screen_h);
+                    let button = action.button.unwrap_or_default().xdotool_button();
+                    match action.action {
+                        HeldActionKind::Move => {
+                            performed.push(format!("move to {}", at.display(space)));
+                            steps.push(HeldStep::Move(at));
+                        }
+                        HeldActionKind::Click => {
+                            let double = action.double.unwrap_or(false);
+                            let verb = if double { "double-click" } else { "click" };
+                            performed.push(format!("{verb} at {}", at.display(space)));
+                            steps.push(HeldStep::Click { at, button, double });
+                        }
+                        HeldActionKind::Drag => {
+                            let (Some(to_x), Some(to_y)) = (action.to_x, action.to_y) else {
+                                return Err(FunctionCallError::RespondToModel(format!(
+                                    "`actions[{index}]`: drag needs to_x and to_y"
+                                )));
+                            };
+                            let to = map_point(config, to_x, to_y, screen_w, screen_h);
+                            performed.push(format!(
+                                "drag from {} to {}",
+                                at.display(space),
+                                to.display(space)
+                            ));
+                            steps.push(HeldStep::Drag {
+                                from: at,
+                                to,
+                                button,
+                            });
+                        }
+                    }
+                }
+                let held = modifiers.join("+");
+                let performed = performed.join("; ");
+                let cmd = modifiers::held_args(&modifiers, &steps);
+                let breadcrumb = format!("{performed} with {held} held");
+                match run_input(&session, &xdotool, &cmd, config, Some(breadcrumb)).await {
+                    Ok(recovered) => Ok(ToolOutput::Function {
+                        content: format!("with {held} held: {performed}{recovered}"),
+                        content_items: None,
+                        success: Some(true),
+                    }),
+                    Err(FunctionCallError::RespondToModel(err)) => {
+                        // The sequence may have stopped between its press and
+                        // release, so release everything it could have held.
+                        let helper = input_helper(&session, config).await;
+                        let release = modifiers::release_args(&modifiers, &steps);
+                        let released = match input_helper::send_input(
+                            helper.as_ref(),
+                            &xdotool,
+                            &release,
+                            config,
+                        ) {
+                            Ok(()) => format!("released {held}"),
+                            Err(_) => format!(
+                                "could not release {held}; it may still be held, so press it once with computer_key"
+                            ),
+                        };
+                        Err(FunctionCallError::RespondToModel(format!(
+                            "{err}; {released}"
+                        )))
+                    }
+                    Err(err) => Err(err),
+                }
+            }
+            "computer_scroll" => {
+                let args: ScrollArgs = parse_args(&arguments)?;
+                let direction = args.direction.xdotool_button().to_string();
+                let ticks = args.amount.unwrap_or(DEFAULT_SCROLL_TICKS).max(1);
+                let xdotool = input_command(config)?;
+                let mut cmd = Vec::new();
+                let mut position = String::new();
+                let mut breadcrumb = None;
+                if args.x.is_some() ^ args.y.is_some() {
+                    return Err(FunctionCallError::RespondToModel(
+                        "computer_scroll requires both x and y when positioning the cursor"
+                            .to_string(),
+                    ));
+                }
+                if let (Some(x), Some(y)) = (args.x, args.y) {
+                    let (screen_w, screen_h) = screen_geometry(&session, &xdotool, config).await?;
+                    let point = map_point(config, x, y, screen_w, screen_h);
+                    cmd.extend([
+                        "mousemove".to_string(),
+                        "--sync".to_string(),
+                        point.screen_x.to_string(),
+                        point.screen_y.to_string(),
+                    ]);
+                    position = format!(" at {}", point.display(space));
+                    breadcrumb = Some(format!("scroll {}{position}", args.direction.name()));
+                }
+                cmd.push("click".to_string());
+                if ticks > 1 {
+                    cmd.push("--repeat".to_string());
+                    cmd.push(ticks.to_string());
+                }
+                cmd.push(direction);
+                let recovered = run_input(&session, &xdotool, &cmd, config, breadcrumb).await?;
+                Ok(ToolOutput::Function {
+                    content: format!("scrolled {ticks} ticks{position}{recovered}"),
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_swipe" => {
+                let args: SwipeArgs = parse_args(&arguments)?;
+                let xdotool = input_command(config)?;
+                let (screen_w, screen_h) = screen_geometry(&session, &xdotool, config).await?;
+                let from = map_point(config, args.from_x, args.from_y, screen_w, screen_h);
+                let to = map_point(config, args.to_x, args.to_y, screen_w, screen_h);
+                let duration = args
+                    .duration_ms
+                    .map_or(gestures::DEFAULT_SWIPE_DURATION, Duration::from_millis);
+                let cmd = gestures::swipe_args(&from, &to, duration);
+                let from = from.display(space);
+                let to = to.display(space);
+                let breadcrumb = format!("swipe from {from} to {to}");
+                let recovered =
+                    run_input(&session, &xdotool, &cmd, config, Some(breadcrumb)).await?;
+                let ms = duration.as_millis();
+                Ok(ToolOutput::Function {
+                    content: format!("swiped from {from} to {to} over {ms} ms{recovered}"),
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_long_press" => {
+                let args: LongPressArgs = parse_args(&arguments)?;
+                let xdotool = input_command(config)?;
+                let (screen_w, screen_h) = screen_geometry(&session, &xdotool, config).await?;
+                let point = map_point(config, args.x, args.y, screen_w, screen_h);
+                let duration = args
+                    .duration_ms
+                    .map_or(gestures::DEFAULT_LONG_PRESS_DURATION, Duration::from_millis);
+                let cmd = gestures::long_press_args(&point, duration);
+                let at = point.display(space);
+                let recovered = run_input(
+                    &session,
+                    &xdotool,
+                    &cmd,
+                    config,
+                    Some(format!("long press at {at}")),
+                )
+                .await?;
+                let ms = duration.as_millis();
+                Ok(ToolOutput::Function {
+                    content: format!("long-pressed at {at} for {ms} ms{recovered}"),
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_pinch" => {
+                let args: PinchArgs = parse_args(&arguments)?;
+                let zoom_in = args.direction.zooms_in();
+                let ticks = args.amount.unwrap_or(gestures::DEFAULT_PINCH_TICKS).max(1);
+                let xdotool = input_command(config)?;
+                let (screen_w, screen_h) = screen_geometry(&session, &xdotool, config).await?;
+                let point = map_point(config, args.x, args.y, screen_w, screen_h);
+                let cmd = gestures::pinch_args(&point, zoom_in, ticks);
+                let at = point.display(space);
+                let direction = if zoom_in { "out" } else { "in" };
+                let recovered = run_input(
+                    &session,
+                    &xdotool,
+                    &cmd,
+                    config,
+                    Some(format!("pinch {direction} at {at}")),
+                )
+                .await?;
+                Ok(ToolOutput::Function {
+                    content: format!(
+                        "pinched {direction} at {at} ({ticks} Ctrl+scroll steps){recovered}"
+                    ),
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_type" => {
+                let args: TypeArgs = parse_args(&arguments)?;
+                let xdotool = input_command(config)?;
+                let focused = expect_window(&xdotool, config, args.window.as_deref())?;
+                let secret = args.confirm_secret.unwrap_or(false);
+                if config.password_guard
+                    && !secret
+                    && let Some(reason) = password_guard::detect(&xdotool, config)
+                {
+                    return Err(FunctionCallError::RespondToModel(format!(
+                        "nothing was typed: {reason}, and typed text stays in the transcript. If the user asked you to enter this secret, call computer_type again with confirm_secret=true; otherwise ask the user to type it themselves"
+                    )));
+                }
+                // Apps that dropped characters before get their tuned delay
+                // unless the call sets one.
+                let delays_path = turn
+                    .client
+                    .config()
+                    .codex_home
+                    .join(typing_delay::TYPING_DELAYS_FILE);
+                // Reading a secret back would copy it to the clipboard or
+                // quote it in a mismatch.
+                let method = if secret {
+                    TypingVerification::Off
+                } else {
+                    args.verify.map_or(config.verify_typing, Into::into)
+                };
+                let class = if config.tune_typing_delay
+                    && (method != TypingVerification::Off || delays_path.exists())
+                {
+                    active_window_class(&xdotool, config).ok()
+                } else {
+                    None
+                };
+                let tuned = match (&class, args.delay_ms) {
+                    (Some(class), None) => typing_delay::tuned_delay(&delays_path, class)
+                        .inspect_err(|err| tracing::debug!("failed to read typing delays: {err}"))
+                        .ok()
+                        .flatten(),
+                    _ => None,
+                };
+                let delay_ms = args.delay_ms.or(tuned);
+                let mut cmd = vec!["type".to_string()];
+                if let Some(delay_ms) = delay_ms {
+                    cmd.push("--delay".to_string());
+                    cmd.push(delay_ms.to_string());
+                }
+                cmd.push("--".to_string());
+                cmd.push(args.text.clone());
+                // CJK text goes past the IME, or its composition would
+                // swallow the synthesized keys.
+                let ime = if config.ime_passthrough && ime::has_cjk(&args.text) {
+                    ime::ImeSuspension::begin(config)
+                } else {
+                    None
+                };
+                let recovered = run_input(&session, &xdotool, &cmd, config, None).await;
+                let ime = ime.map_or(String::new(), |ime| {
+                    format!(
+                        " with the {} input method switched to direct input",
+                        ime.name()
+                    )
+                });
+                let recovered = recovered?;
+                let count = args.text.len();
+                let checked = if secret {
+                    "; not read back, since it is a secret".to_string()
+                } else if method == TypingVerification::Off {
+                    String::new()
+                } else {
+                    match verify::verify_typed(&xdotool, config, method, &args.text)? {
+                        Verification::Matched => "; verified by reading it back".to_string(),
+                        Verification::Skipped(reason) => format!("; not verified: {reason}"),
+                        Verification::Mismatched { observed, mismatch } => {
+                            let observed = pii::scrub(&observed, &config.ocr_scrubbers);
+                            let mut message =
+                                verify::mismatch_message(&args.text, &observed, mismatch);
+                            if let (verify::Mismatch::Dropped { .. }, Some(class)) =
+                                (mismatch, &class)
+                            {
+                                match typing_delay::raise(&delays_path, class, delay_ms) {
+                                    Ok(Some(raised)) => message.push_str(&format!(
+                                        "; raised the key delay for `{class}` to {raised} ms, which later calls without `delay_ms` use"
+                                    )),
+                                    Ok(None) => message.push_str(&format!(
+                                        "; the key delay for `{class}` is already at the {} ms maximum",
+                                        typing_delay::MAX_TUNED_DELAY_MS
+                                    )),
+                                    Err(err) => {
+                                        tracing::warn!("failed to save the typing delay: {err}")
+                                    }
+                                }
+                            }
+                            return Err(FunctionCallError::RespondToModel(message));
+                        }
+                    }
+                };
+                let tuned = match (tuned, &class) {
+                    (Some(tuned), Some(class)) => {
+                        format!(" with the {tuned} ms key delay tuned for `{class}`")
+                    }
+                    _ => String::new(),
+                };
+                Ok(ToolOutput::Function {
+                    content: format!(
+                        "typed {count} characters{focused}{tuned}{ime}{checked}{recovered}"
+                    ),
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_key" => {
+                let args: KeyArgs = parse_args(&arguments)?;
+                let parsed =
+                    keys::parse_chord(&args.keys).map_err(FunctionCallError::RespondToModel)?;
+                let chord = parsed.keys_for(config.target_os);
+                if config.confirm_destructive_keys
+                    && requires_confirmation(&chord)
+                    && !matches!(args.confirm, Some(true))
+                {
+                    return Err(FunctionCallError::RespondToModel(
+                        "destructive key combo requires confirm=true after user approval"
+                            .to_string(),
+                    ));
+                }
+                let xdotool = input_command(config)?;
+                let focused = expect_window(&xdotool, config, args.window.as_deref())?;
+                let combo = chord.join("+");
+                let cmd = ["key".to_string(), combo.clone()];
+                let recovered = run_input(&session, &xdotool, &cmd, config, None).await?;
+                let shortcut = match parsed.shortcut_name() {
+                    Some(name) => format!(" ({name} on {})", config.target_os),
+                    None => String::new(),
+                };
+                Ok(ToolOutput::Function {
+                    content: format!("pressed {combo}{shortcut}{focused}{recovered}"),
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_clear_field" => {
+                let args: ClearFieldArgs = parse_args(&arguments)?;
+                let method = args.method.unwrap_or_default();
+                let cmd = clear_field_keys(method, args.count, config.target_os);
+                let xdotool = input_command(config)?;
+                let recovered = run_input(&session, &xdotool, &cmd, config, None).await?;
+                let how = match method {
+                    ClearMethod::SelectAll => "selecting all and deleting".to_string(),
+                    ClearMethod::Backspace => format!(
+                        "pressing Backspace {} time(s) from the end",
+                        clear_backspaces(args.count)
+                    ),
+                };
+                Ok(ToolOutput::Function {
+                    content: format!(
+                        "cleared the focused field by {how}; take a screenshot to confirm it is empty before typing{recovered}"
+                    ),
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_focus_next" | "computer_focus_prev" => {
+                let args: FocusArgs = parse_args(&arguments)?;
+                let count = args.count.unwrap_or(1).max(1);
+                let (key, direction) = if tool_name == "computer_focus_next" {
+                    ("Tab", "forward")
+                } else {
+                    ("shift+Tab", "backward")
+                };
+                let xdotool = input_command(config)?;
+                let mut cmd = vec!["key".to_string()];
+                if count > 1 {
+                    cmd.extend(["--repeat".to_string(), count.to_string()]);
+                }
+                cmd.push(key.to_string());
+                let recovered = run_input(&session, &xdotool, &cmd, config, None).await?;
+                Ok(ToolOutput::Function {
+                    content: format!(
+                        "moved focus {direction} {count} time(s); take a screenshot to see the focused element{recovered}"
+                    ),
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_compare" => {
+                let args: CompareArgs = parse_args(&arguments)?;
+                let region = args.region()?;
+                let baseline = turn.resolve_path(Some(args.baseline_path.clone()));
+                let xdotool = input_command(config)?;
+                let (screen_w, screen_h) = screen_geometry(&session, &xdotool, config).await?;
+                let rect = region
+                    .map_or(ScreenRect::visible(config, screen_w, screen_h), |region| {
+                        detail::region_to_screen(config, region, screen_w, screen_h)
+                    });
+                // Compare native pixels so small regressions aren't scaled away.
+                let capture =
+                    capture_root_window(config, region.map(|_| rect.crop_geometry()), None)?;
+                let outcome = compare::compare_to_baseline(
+                    &capture,
+                    &baseline,
+                    args.tolerance.unwrap_or(compare::DEFAULT_TOLERANCE),
+                );
+                let _ = std::fs::remove_file(&capture);
+                let shown = baseline.display();
+                let content = match outcome? {
+                    compare::Outcome::BaselineSaved { width, height } => format!(
+                        "no baseline existed at {shown}; saved the current {width}x{height} capture as the baseline. Compare against it after the change under test"
+                    ),
+                    compare::Outcome::Compared {
+                        diff,
+                        size,
+                        rescaled_from,
+                        diff_path,
+                    } => {
+                        let percent = diff.similarity * 100.0;
+                        let mut lines = vec![format!(
+                            "similarity {percent:.2}% against {shown} ({} of {} pixels changed)",
+                            diff.changed_pixels,
+                            u64::from(size.0) * u64::from(size.1)
+                        )];
+                        if let Some((width, height)) = rescaled_from {
+                            lines.push(format!(
+                                "the capture was {width}x{height} and was scaled to the baseline's {}x{}; the screen resolution or region differs from when the baseline was taken",
+                                size.0, size.1
+                            ));
+                        }
+                        if let Some((left, top, width, height)) = diff.changed_bounds {
+                            let to_model = |x: u32, y: u32| {
+                                let screen_x =
+                                    rect.left + i64::from(x) * rect.width / i64::from(size.0);
+                                let screen_y =
+                                    rect.top + i64::from(y) * rect.height / i64::from(size.1);
+                                screen_to_model(config, screen_x, screen_y, screen_w, screen_h)
+                            };
+                            lines.push(format!(
+                                "changes span {} to {}",
+                                to_model(left, top),
+                                to_model(left + width, top + height)
+                            ));
+                        }
+                        let budget = match diff_path {
+                            Some(_) => reserve_screenshots(
+                                &mut *session.services.computer_use.lock().await,
+                                &turn.sub_id,
+                                config,
+                                1,
+                            ),
+                            None => None,
+                        };
+                        if let (Some(diff_path), Some((0, _, _))) = (&diff_path, budget) {
+                            lines.push(format!(
+                                "the diff image at {} was not attached: the screenshot budget for this turn is used up",
+                                diff_path.display()
+                            ));
+                        } else if let Some(diff_path) = diff_path {
+                            deferred::attach_images(&session, std::slice::from_ref(&diff_path))
+                                .await;
+                            record_attached_image(&session, &diff_path, false).await;
+                            session
+                                .send_event(
+                                    turn.as_ref(),
+                                    EventMsg::ViewImageToolCall(ViewImageToolCallEvent {
+                                        call_id,
+                                        path: diff_path.clone(),
+                                    }),
+                                )
+                                .await;
+                            lines.push(format!(
+                                "attached diff image {} (changed pixels in red)",
+                                diff_path.display()
+                            ));
+                            if let Some((_, limit, remaining)) = budget {
+                                lines.push(
+                                    screenshot_budget_note(limit, remaining)
+                                        .trim_start()
+                                        .to_string(),
+                                );
+                            }
+                        }
+                        lines.join("\n")
+                    }
+                };
+                Ok(ToolOutput::Function {
+                    content,
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_find_text" => {
+                let args: FindTextArgs = parse_args(&arguments)?;
+                let query = args.query.trim();
+                if query.is_empty() {
+                    return Err(FunctionCallError::RespondToModel(
+                        "computer_find_text requires a non-empty query".to_string(),
+                    ));
+                }
+                let min_similarity = args
+                    .min_similarity
+                    .unwrap_or(find_text::DEFAULT_MIN_SIMILARITY)
+                    .clamp(0.0, 1.0);
+                let max_results = args
+                    .max_results
+                    .unwrap_or(find_text::DEFAULT_MAX_RESULTS)
+                    .max(1);
+                let xdotool = input_command(config)?;
+                let (screen_w, screen_h) = screen_geometry(&session, &xdotool, config).await?;
+                // OCR the full-resolution screen; downscaled text reads poorly.
+                let image_path = capture_root_window(config, None, None)?;
+                let tsv = find_text::recognize(&image_path);
+                let _ = std::fs::remove_file(&image_path);
+                let found = find_text::find_matches(&tsv?, query, min_similarity);
+                let best = found.first().map_or(0.0, |hit| hit.confidence);
+                session
+                    .services
+                    .computer_use
+                    .lock()
+                    .await
+                    .record_ocr_confidence(&turn.sub_id, best);
+                let content = if found.is_empty() {
+                    format!(
+                        "no on-screen text matches `{query}`; take a screenshot if the element has no visible label"
+                    )
+                } else {
+                    let total = found.len();
+                    let mut lines = vec![format!(
+                        "found {total} match(es) for `{query}` (center; box from top-left to bottom-right):"
+                    )];
+                    // OCR positions are relative to the captured viewport.
+                    let (origin_x, origin_y) = coordinates::viewport_origin(config);
+                    lines.extend(found.iter().take(max_results).map(|hit| {
+                        let to_model = |x, y| {
+                            screen_to_model(
+                                config,
+                                x + origin_x,
+                                y + origin_y,
+                                screen_w,
+                                screen_h,
+                            )
+                        };
+                        let center =
+                            to_model((hit.left + hit.right) / 2, (hit.top + hit.bottom) / 2);
+                        let top_left = to_model(hit.left, hit.top);
+                        let bottom_right = to_model(hit.right, hit.bottom);
+                        format!(
+                            "- \"{}\" at {center}; box {top_left} to {bottom_right}; confidence {:.2}",
+                            pii::scrub(&hit.text, &config.ocr_scrubbers),
+                            hit.confidence
+                        )
+                    }));
+                    if total > max_results {
+                        lines.push(format!("({} more omitted)", total - max_results));
+                    }
+                    lines.join("\n")
+                };
+                Ok(ToolOutput::Function {
+                    content,
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_detect_elements" => {
+                let args: DetectElementsArgs = parse_args(&arguments)?;
+                let label = args
+                    .label
+                    .as_deref()
+                    .map(str::trim)
+                    .filter(|label| !label.is_empty());
+                let min_confidence = args
+                    .min_confidence
+                    .unwrap_or(detector::DEFAULT_MIN_CONFIDENCE)
+                    .clamp(0.0, 1.0);
+                let max_results = args
+                    .max_results
+                    .unwrap_or(detector::DEFAULT_MAX_RESULTS)
+                    .max(1);
+                let xdotool = input_command(config)?;
+                let (screen_w, screen_h) = screen_geometry(&session, &xdotool, config).await?;
+                // Detect on the full-resolution screen, like OCR.
+                let image_path = capture_root_window(config, None, None)?;
+                let found = detector::detect(config, &image_path, label, min_confidence);
+                let _ = std::fs::remove_file(&image_path);
+                let found = found?;
+                let kind = label.unwrap_or("element");
+                let content = if found.is_empty() {
+                    format!(
+                        "the detector found no {kind}s with confidence {min_confidence:.2} or higher; take a screenshot instead"
+                    )
+                } else {
+                    let total = found.len();
+                    let mut lines = vec![format!(
+                        "detected {total} {kind}(s) (center; box from top-left to bottom-right):"
+                    )];
+                    // Detector positions are relative to the captured viewport.
+                    let (origin_x, origin_y) = coordinates::viewport_origin(config);
+                    lines.extend(found.iter().take(max_results).map(|element| {
+                        let to_model = |x, y| {
+                            screen_to_model(
+                                config,
+                                x + origin_x,
+                                y + origin_y,
+                                screen_w,
+                                screen_h,
+                            )
+                        };
+                        let (right, bottom) =
+                            (element.x + element.width, element.y + element.height);
+                        let center = to_model((element.x + right) / 2, (element.y + bottom) / 2);
+                        let top_left = to_model(element.x, element.y);
+                        let bottom_right = to_model(right, bottom);
+                        let text = element
+                            .text
+                            .as_deref()
+                            .filter(|text| !text.trim().is_empty())
+                            .map(|text| {
+                                format!(" \"{}\"", pii::scrub(text, &config.ocr_scrubbers))
+                            })
+                            .unwrap_or_default();
+                        format!(
+                            "- {}{text} at {center}; box {top_left} to {bottom_right}; confidence {:.2}",
+                            element.label, element.confidence
+                        )
+                    }));
+                    if total > max_results {
+                        lines.push(format!("({} more omitted)", total - max_results));
+                    }
+                    lines.join("\n")
+                };
+                Ok(ToolOutput::Function {
+                    content,
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_locate" => {
+                let args: LocateArgs = parse_args(&arguments)?;
+                let query = args.query.trim();
+                if query.is_empty() {
+                    return Err(FunctionCallError::RespondToModel(
+                        "computer_locate requires a non-empty query".to_string(),
+                    ));
+                }
+                let min_confidence = args
+                    .min_confidence
+                    .map(|confidence| confidence.clamp(0.0, 1.0));
+                let xdotool = input_command(config)?;
+                let (screen_w, screen_h) = screen_geometry(&session, &xdotool, config).await?;
+                let codex_home = turn.client.config().codex_home.clone();
+                let outcome = locator::locate(config, &codex_home, query, min_confidence);
+                let misses = pii::scrub(&outcome.misses.join("; "), &config.ocr_scrubbers);
+                let content = match outcome.found {
+                    None => format!(
+                        "no element matches `{query}` ({misses}); take a screenshot instead"
+                    ),
+                    Some(found) => {
+                        let to_model = |x, y| screen_to_model(config, x, y, screen_w, screen_h);
+                        let center = to_model(
+                            (found.left + found.right) / 2,
+                            (found.top + found.bottom) / 2,
+                        );
+                        let top_left = to_model(found.left, found.top);
+                        let bottom_right = to_model(found.right, found.bottom);
+                        let mut content = format!(
+                            "found `{query}` with {}: {} at {center}; box {top_left} to {bottom_right}; confidence {:.2}",
+                            locator::strategy_name(found.strategy),
+                            pii::scrub(&found.description, &config.ocr_scrubbers),
+                            found.confidence
+                        );
+                        if !misses.is_empty() {
+                            content.push_str(&format!("\n(tried first: {misses})"));
+                        }
+                        content
+                    }
+                };
+                Ok(ToolOutput::Function {
+                    content,
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_magnifier" => {
+                let args: MagnifierArgs = parse_args(&arguments)?;
+                Ok(ToolOutput::Function {
+                    content: magnifier::apply(config, args.action, args.factor)?,
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_print_dialog_handle" => {
+                let args: PrintDialogArgs = parse_args(&arguments)?;
+                let content = print_dialog::print_to_pdf(
+                    &session,
+                    config,
+                    &args.path,
+                    args.open.unwrap_or(false),
+                )
+                .await?;
+                Ok(ToolOutput::Function {
+                    content,
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_office" => {
+                let args: OfficeArgs = parse_args(&arguments)?;
+                office::permitted(config).map_err(FunctionCallError::RespondToModel)?;
+                let content = office::run(config, &args)
+                    .await
+                    .map_err(FunctionCallError::RespondToModel)?;
+                Ok(ToolOutput::Function {
+                    content,
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_file_dialog" => {
+                let args: FileDialogArgs = parse_args(&arguments)?;
+                let content = file_dialog::fill(&session, config, args.action, &args.path).await?;
+                Ok(ToolOutput::Function {
+                    content,
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_menu_select" => {
+                let args: MenuSelectArgs = parse_args(&arguments)?;
+                let labels = args
+                    .path
+                    .iter()
+                    .map(|label| menu_select::parse_label(label))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(FunctionCallError::RespondToModel)?;
+                if labels.is_empty() || labels.len() > menu_select::MAX_DEPTH {
+                    return Err(FunctionCallError::RespondToModel(format!(
+                        "path must name 1 to {} menu labels",
+                        menu_select::MAX_DEPTH
+                    )));
+                }
+                if config.target_os == TargetOs::Macos {
+                    return Err(FunctionCallError::RespondToModel(
+                        "macOS menus have no access keys; click the menu bar instead".to_string(),
+                    ));
+                }
+                let xdotool = input_command(config)?;
+                let focused = expect_window(&xdotool, config, args.window.as_deref())?;
+                let read_screen = || {
+                    let capture = capture_root_window(config, None, None)?;
+                    let screen = menu_select::Screen::read(&capture);
+                    let _ = std::fs::remove_file(&capture);
+                    screen.map_err(FunctionCallError::RespondToModel)
+                };
+                let path = menu_select::display(&labels);
+                let mut before = read_screen()?;
+                for (depth, label) in labels.iter().enumerate() {
+                    let key = match depth {
+                        0 => format!("alt+{}", label.key),
+                        _ => label.key.to_string(),
+                    };
+                    run_input(
+                        &session,
+                        &xdotool,
+                        &["key".to_string(), key.clone()],
+                        config,
+                        None,
+                    )
+                    .await?;
+                    tokio::time::sleep(MENU_SETTLE).await;
+                    let after = read_screen()?;
+                    let problem = match labels.get(depth + 1) {
+                        Some(next) if !after.gained(&before, &next.text) => Some(format!(
+                            "`{}` did not show up after pressing {key} for `{}`: its access key may be another letter",
+                            next.text, label.text
+                        )),
+                        // A lone menu-bar label just opens its menu.
+                        None if depth > 0 && !after.lost(&before, &label.text) => Some(format!(
+                            "the menu stayed open after pressing {key} for `{}`: it may open a submenu (add its entry to the path), or its access key may be another letter",
+                            label.text
+                        )),
+                        _ => None,
+                    };
+                    if let Some(problem) = problem {
+                        // Close whatever opened so the model starts over
+                        // from the app.
+                        for _ in 0..=depth {
+                            let escape = ["key".to_string(), "Escape".to_string()];
+                            run_input(&session, &xdotool, &escape, config, None).await?;
+                        }
+                        return Err(FunctionCallError::RespondToModel(format!(
+                            "{problem}. Closed the menus; take a screenshot, find the underlined letter, and mark it with an underscore, as in `E_xport`, or click the menu instead"
+                        )));
+                    }
+                    before = after;
+                }
+                let content = match labels.len() {
+                    1 => format!(
+                        "opened the {path} menu; take a screenshot to choose an entry{focused}"
+                    ),
+                    _ => format!("selected {path}{focused}"),
+                };
+                Ok(ToolOutput::Function {
+                    content,
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_context_menu" => {
+                let args: ContextMenuArgs = parse_args(&arguments)?;
+                let item = args.item.trim();
+                if item.is_empty() {
+                    return Err(FunctionCallError::RespondToModel(
+                        "item must name the menu entry to choose".to_string(),
+                    ));
+                }
+                let xdotool = input_command(config)?;
+                let (screen_w, screen_h) = screen_geometry(&session, &xdotool, config).await?;
+                let point = map_point(config, args.x, args.y, screen_w, screen_h);
+                let at = point.display(space);
+                let right_click = [
+                    "mousemove".to_string(),
+                    "--sync".to_string(),
+                    point.screen_x.to_string(),
+                    point.screen_y.to_string(),
+                    "click".to_string(),
+                    "3".to_string(),
+                ];
+                let breadcrumb = format!("right-click at {at}");
+                let mut recovered =
+                    run_input(&session, &xdotool, &right_click, config, Some(breadcrumb)).await?;
+                let entry = match context_menu::wait_for_entry(
+                    config,
+                    item,
+                    (point.screen_x, point.screen_y),
+                )
+                .await
+                {
+                    Ok(entry) => entry,
+                    Err(misses) => {
+                        let escape = ["key".to_string(), "Escape".to_string()];
+                        run_input(&session, &xdotool, &escape, config, None).await?;
+                        return Err(FunctionCallError::RespondToModel(format!(
+                            "right-clicked at {at}, but found no menu entry matching `{item}` ({}); closed the menu. Take a screenshot to see what the menu offers",
+                            pii::scrub(&misses, &config.ocr_scrubbers)
+                        )));
+                    }
+                };
+                let (entry_x, entry_y) = entry.center();
+                let choose = [
+                    "mousemove".to_string(),
+                    "--sync".to_string(),
+                    entry_x.to_string(),
+                    entry_y.to_string(),
+                    "click".to_string(),
+                    "1".to_string(),
+                ];
+                let entry_at =
+                    coordinates::screen_to_model(config, entry_x, entry_y, screen_w, screen_h);
+                let breadcrumb = format!("choose \"{}\" at {entry_at}", entry.text);
+                recovered.push_str(
+                    &run_input(&session, &xdotool, &choose, config, Some(breadcrumb)).await?,
+                );
+                Ok(ToolOutput::Function {
+                    content: format!(
+                        "right-clicked at {at} and chose \"{}\" at {entry_at} (found with {}){recovered}",
+                        pii::scrub(&entry.text, &config.ocr_scrubbers),
+                        entry.via
+                    ),
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_wait_download" => {
+                let args: WaitDownloadArgs = parse_args(&arguments)?;
+                let content = download::wait(
+                    args.dir.as_deref(),
+                    args.pattern.as_deref(),
+                    args.timeout_seconds,
+                )
+                .await?;
+                Ok(ToolOutput::Function {
+                    content,
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_wait_app_idle" => {
+                let args: WaitAppIdleArgs = parse_args(&arguments)?;
+                let xdotool = input_command(config).ok();
+                let pid = idle::target(
+                    xdotool.as_deref(),
+                    config,
+                    args.pid,
+                    args.wm_class.as_deref(),
+                )?;
+                let content = idle::wait(pid, args.threshold, args.timeout_seconds).await?;
+                Ok(ToolOutput::Function {
+                    content,
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_camera_snapshot" => {
+                let args: CameraSnapshotArgs = parse_args(&arguments)?;
+                let budget = reserve_screenshots(
+                    &mut *session.services.computer_use.lock().await,
+                    &turn.sub_id,
+                    config,
+                    1,
+                );
+                if let Some((0, limit, _)) = budget {
+                    return Err(screenshot_budget_exhausted(limit));
+                }
+                let (image_path, device) = match camera::snapshot(args.device.as_deref()) {
+                    Ok(snapshot) => snapshot,
+                    Err(err) => {
+                        release_screenshots(
+                            &mut *session.services.computer_use.lock().await,
+                            &turn.sub_id,
+                            budget,
+                        );
+                        return Err(err);
+                    }
+                };
+                deferred::attach_images(&session, std::slice::from_ref(&image_path)).await;
+                record_attached_image(&session, &image_path, false).await;
+                session
+                    .send_event(
+                        turn.as_ref(),
+                        EventMsg::ViewImageToolCall(ViewImageToolCallEvent {
+                            call_id,
+                            path: image_path.clone(),
+                        }),
+                    )
+                    .await;
+                let mut content = format!(
+                    "captured camera frame from {device} at {}",
+                    image_path.display()
+                );
+                if let Some((_, limit, remaining)) = budget {
+                    content.push_str(&screenshot_budget_note(limit, remaining));
+                }
+                Ok(ToolOutput::Function {
+                    content,
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_watch_demo" => {
+                let args: WatchDemoArgs = parse_args(&arguments)?;
+                // Don't have the user demonstrate when no screenshot of it
+                // could be attached.
+                if let Some((_, limit, 0)) = reserve_screenshots(
+                    &mut *session.services.computer_use.lock().await,
+                    &turn.sub_id,
+                    config,
+                    0,
+                ) {
+                    return Err(screenshot_budget_exhausted(limit));
+                }
+                let xdotool = input_command(config)?;
+                let (screen_w, screen_h) = screen_geometry(&session, &xdotool, config).await?;
+                let task = args
+                    .task
+                    .as_deref()
+                    .map_or(String::new(), |task| format!(" of \"{}\"", task.trim()));
+                session
+                    .send_event(
+                        turn.as_ref(),
+                        EventMsg::BackgroundEvent(BackgroundEventEvent {
+                            message: format!(
+                                "Recording your demonstration{task}: go ahead now. Your keys, clicks, and screenshots are recorded. Press Pause or stop for {} s to finish.",
+                                demonstration::IDLE_FINISH.as_secs()
+                            ),
+                        }),
+                    )
+                    .await;
+                let mut recorded = demonstration::watch(config, args.max_seconds).await?;
+                if recorded.steps.is_empty() {
+                    for (_, path) in &recorded.screenshots {
+                        let _ = std::fs::remove_file(path);
+                    }
+                    return Err(FunctionCallError::RespondToModel(format!(
+                        "no input was recorded ({}); ask the user whether they want to try again",
+                        recorded.ended
+                    )));
+                }
+                let wanted = u32::try_from(recorded.screenshots.len()).unwrap_or(u32::MAX);
+                let budget = reserve_screenshots(
+                    &mut *session.services.computer_use.lock().await,
+                    &turn.sub_id,
+                    config,
+                    wanted,
+                );
+                let mut dropped = 0;
+                if let Some((granted, limit, _)) = budget {
+                    // Keep the first screenshots the budget allows, so the
+                    // trace still starts from what the user started from.
+                    let kept = (granted as usize).min(recorded.screenshots.len());
+                    for (_, path) in recorded.screenshots.drain(kept..) {
+                        let _ = std::fs::remove_file(path);
+                        dropped += 1;
+                    }
+                    if recorded.screenshots.is_empty() && dropped > 0 {
+                        return Err(screenshot_budget_exhausted(limit));
+                    }
+                }
+                let mut content =
+                    demonstration::format_trace(&recorded, args.task.as_deref(), |x, y| {
+                        coordinates::screen_to_model(config, x, y, screen_w, screen_h)
+                    });
+                if let Some((_, limit, remaining)) = budget {
+                    if dropped > 0 {
+                        content.push_str(&format!(
+                            "\n{dropped} later screenshot(s) were not attached: the screenshot budget for this turn is used up"
+                        ));
+                    }
+                    content.push_str(&screenshot_budget_note(limit, remaining));
+                }
+                let paths: Vec<PathBuf> = recorded
+                    .screenshots
+                    .iter()
+                    .map(|(_, path)| path.clone())
+                    .collect();
+                deferred::attach_images(&session, &paths).await;
+                for (_, path) in &recorded.screenshots {
+                    record_attached_image(&session, path, true).await;
+                    session
+                        .send_event(
+                            turn.as_ref(),
+                            EventMsg::ViewImageToolCall(ViewImageToolCallEvent {
+                                call_id: call_id.clone(),
+                                path: path.clone(),
+                            }),
+                        )
+                        .await;
+                }
+                Ok(ToolOutput::Function {
+                    content,
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_recent_frames" => {
+                let args: RecentFramesArgs = parse_args(&arguments)?;
+                let count = args
+                    .n
+                    .unwrap_or(frames::DEFAULT_COUNT)
+                    .clamp(1, frames::MAX_COUNT);
+                let (mut recent, budget) = {
+                    let mut state = session.services.computer_use.lock().await;
+                    let recent = state.recent_frames(count);
+                    if recent.is_empty() {
+                        return Err(FunctionCallError::RespondToModel(
+                            "no frames recorded yet; recording starts with the first GUI tool call and captures a frame about once a second, so try again shortly".to_string(),
+                        ));
+                    }
+                    let wanted = u32::try_from(recent.len()).unwrap_or(u32::MAX);
+                    let budget = reserve_screenshots(&mut state, &turn.sub_id, config, wanted);
+                    (recent, budget)
+                };
+                if let Some((granted, limit, _)) = budget {
+                    // Keep the newest frames the budget allows.
+                    let skip = recent.len().saturating_sub(granted as usize);
+                    for (path, _) in recent.drain(..skip) {
+                        let _ = std::fs::remove_file(path);
+                    }
+                    if recent.is_empty() {
+                        return Err(screenshot_budget_exhausted(limit));
+                    }
+                }
+                let paths: Vec<PathBuf> = recent.iter().map(|(path, _)| path.clone()).collect();
+                deferred::attach_images(&session, &paths).await;
+                for (path, _) in &recent {
+                    record_attached_image(&session, path, true).await;
+                    session
+                        .send_event(
+                            turn.as_ref(),
+                            EventMsg::ViewImageToolCall(ViewImageToolCallEvent {
+                                call_id: call_id.clone(),
+                                path: path.clone(),
+                            }),
+                        )
+                        .await;
+                }
+                let ages: Vec<Duration> = recent.iter().map(|(_, age)| *age).collect();
+                let mut content = frames::describe(&ages);
+                if let Some((_, limit, remaining)) = budget {
+                    content.push_str(&screenshot_budget_note(limit, remaining));
+                }
+                Ok(ToolOutput::Function {
+                    content,
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_announce" => {
+                let args: AnnounceArgs = parse_args(&arguments)?;
+                let speaker = announce::announce(config, &args.text)?;
+                Ok(ToolOutput::Function {
+                    content: format!("announced \"{}\" with {speaker}", args.text.trim()),
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_set_theme" => {
+                let args: SetThemeArgs = parse_args(&arguments)?;
+                let desktop = Desktop::current("computer_set_theme")?;
+                let mut restore = "; the previous theme is restored when the session ends";
+                {
+                    let mut state = session.services.computer_use.lock().await;
+                    if !state.has_saved_theme() {
+                        match theme::SavedTheme::capture(config, desktop) {
+                            Ok(saved) => state.save_theme(saved),
+                            Err(err) => {
+                                tracing::warn!("failed to read the desktop theme: {err}");
+                                restore = "; the previous theme could not be read, so it is not restored when the session ends";
+                            }
+                        }
+                    }
+                }
+                theme::apply(config, desktop, args.theme)?;
+                let name = match args.theme {
+                    ThemeName::Dark => "dark",
+                    ThemeName::Light => "light",
+                    ThemeName::HighContrast => "high contrast",
+                };
+                Ok(ToolOutput::Function {
+                    content: format!(
+                        "theme set to {name} ({}){restore}; take a screenshot once apps have redrawn",
+                        desktop.label()
+                    ),
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_network_state" => {
+                let state = network::read().map_err(FunctionCallError::RespondToModel)?;
+                Ok(ToolOutput::Function {
+                    content: network::describe(&state).join("\n"),
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_system_control" => {
+                let args: SystemControlArgs = parse_args(&arguments)?;
+                let (current, via) = system_control::read(config, args.setting)?;
+                let name = args.setting.label();
+                let Some(value) = args.value else {
+                    return Ok(ToolOutput::Function {
+                        content: format!("{name}: {current} ({via})"),
+                        content_items: None,
+                        success: Some(true),
+                    });
+                };
+                let level = system_control::requested(args.setting, value)?;
+                system_control::write(config, args.setting, level)?;
+                session
+                    .services
+                    .computer_use
+                    .lock()
+                    .await
+                    .save_system_setting(config, args.setting, current);
+                let content = match (args.setting, level) {
+                    (SystemSetting::Mute, Level::Muted(true)) => format!("audio muted ({via})"),
+                    (SystemSetting::Mute, _) => format!("audio unmuted ({via})"),
+                    _ => format!("{name} set to {level} (was {current}; {via})"),
+                };
+                Ok(ToolOutput::Function {
+                    content: format!(
+                        "{content}; the previous value is restored when the session ends"
+                    ),
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_active_window" => {
+                let xdotool = input_command(config)?;
+                let window = active_window::read(&xdotool, config)?;
+                let (screen_w, screen_h) = screen_geometry(&session, &xdotool, config).await?;
+                let to_model = |x, y| screen_to_model(config, x, y, screen_w, screen_h);
+                let geometry = window.geometry;
+                let mut lines = vec![
+                    format!("active window {}: \"{}\"", window.id, window.title),
+                    format!("class: {}", window.class),
+                    format!(
+                        "pid: {}",
+                        window
+                            .pid
+                            .map_or_else(|| "unknown".to_string(), |pid| pid.to_string())
+                    ),
+                    format!(
+                        "box {} to {} ({}x{} screen pixels)",
+                        to_model(geometry.x, geometry.y),
+                        to_model(geometry.x + geometry.width, geometry.y + geometry.height),
+                        geometry.width,
+                        geometry.height
+                    ),
+                ];
+                if let Some((name, area)) = &window.monitor {
+                    lines.push(format!("monitor: {name} ({area})"));
+                }
+                Ok(ToolOutput::Function {
+                    content: lines.join("\n"),
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_workspace_list" => {
+                let workspaces = workspaces::Ewmh::connect(config)?.list()?;
+                Ok(ToolOutput::Function {
+                    content: workspaces::describe(&workspaces).join("\n"),
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_workspace_switch" => {
+                let args: WorkspaceSwitchArgs = parse_args(&arguments)?;
+                let ewmh = workspaces::Ewmh::connect(config)?;
+                let listed = ewmh.list()?;
+                let index = workspaces::index(args.workspace, &listed)?;
+                let content = if index == listed.current {
+                    format!("already on workspace {}", args.workspace)
+                } else if ewmh.switch(index)? {
+                    let windows: Vec<String> = listed
+                        .windows
+                        .iter()
+                        .filter(|window| window.workspace == Some(index))
+                        .map(|window| format!("{} \"{}\"", window.class, window.title))
+                        .collect();
+                    if windows.is_empty() {
+                        format!(
+                            "switched to workspace {}; it has no windows",
+                            args.workspace
+                        )
+                    } else {
+                        format!(
+                            "switched to workspace {}; its windows: {}",
+                            args.workspace,
+                            windows.join(", ")
+                        )
+                    }
+                } else {
+                    return Err(FunctionCallError::RespondToModel(format!(
+                        "the window manager did not switch to workspace {}",
+                        args.workspace
+                    )));
+                };
+                Ok(ToolOutput::Function {
+                    content,
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_workspace_move_window" => {
+                let args: WorkspaceMoveWindowArgs = parse_args(&arguments)?;
+                let ewmh = workspaces::Ewmh::connect(config)?;
+                let listed = ewmh.list()?;
+                let index = workspaces::index(args.workspace, &listed)?;
+                let window = match args.window.as_deref().map(str::trim) {
+                    Some(expected) if !expected.is_empty() => {
+                        workspaces::find(&listed.windows, expected).ok_or_else(|| {
+                            FunctionCallError::RespondToModel(format!(
+                                "no window matches `{expected}` on any workspace; call computer_workspace_list to see them"
+                            ))
+                        })?
+                    }
+                    _ => {
+                        let active = ewmh.active_window()?;
+                        listed
+                            .windows
+                            .iter()
+                            .find(|window| Some(window.id) == active)
+                            .ok_or_else(|| {
+                                FunctionCallError::RespondToModel(
+                                    "no window has focus; pass `window`".to_string(),
+                                )
+                            })?
+                    }
+                };
+                if !ewmh.move_window(window.id, index)? {
+                    return Err(FunctionCallError::RespondToModel(format!(
+                        "the window manager did not move {} \"{}\" to workspace {}",
+                        window.class, window.title, args.workspace
+                    )));
+                }
+                let mut content = format!(
+                    "moved {} \"{}\" to workspace {}",
+                    window.class, window.title, args.workspace
+                );
+                if args.follow.unwrap_or(false) && index != listed.current {
+                    if ewmh.switch(index)? {
+                        content.push_str(" and switched there");
+                    } else {
+                        content.push_str(", but the window manager did not switch there");
+                    }
+                }
+                Ok(ToolOutput::Function {
+                    content,
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_tray_list" => {
+                let items = tray::list().map_err(FunctionCallError::RespondToModel)?;
+                Ok(ToolOutput::Function {
+                    content: tray::describe(&items).join("\n"),
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_list_shortcuts" => {
+                let args: ListShortcutsArgs = if arguments.trim().is_empty() {
+                    ListShortcutsArgs::default()
+                } else {
+                    parse_args(&arguments)?
+                };
+                let scope = args.scope.unwrap_or_default();
+                let mut groups = Vec::new();
+                let mut notes = Vec::new();
+                if scope != ShortcutScope::App {
+                    let desktop = shortcuts::desktop(config);
+                    if desktop.is_empty() {
+                        notes.push(
+                            "no desktop shortcuts found in the GNOME or KDE settings".to_string(),
+                        );
+                    }
+                    groups.extend(desktop);
+                }
+                if scope != ShortcutScope::Desktop {
+                    match shortcuts::app() {
+                        Ok(app) if app.shortcuts.is_empty() => notes.push(format!(
+                            "{} show no accelerators on the accessibility bus",
+                            app.source
+                        )),
+                        Ok(app) => groups.push(app),
+                        Err(err) => notes.push(format!("no menu accelerators: {err}")),
+                    }
+                }
+                if let Some(query) = args
+                    .query
+                    .as_deref()
+                    .filter(|query| !query.trim().is_empty())
+                {
+                    groups = shortcuts::filter(groups, query);
+                }
+                let mut lines = if groups.is_empty() {
+                    vec![match args.query.as_deref() {
+                        Some(query) if !query.trim().is_empty() => {
+                            format!("no shortcuts match {:?}", query.trim())
+                        }
+                        _ => "no shortcuts found".to_string(),
+                    }]
+                } else {
+                    shortcuts::describe(&groups)
+                };
+                lines.extend(notes);
+                Ok(ToolOutput::Function {
+                    content: lines.join("\n"),
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_tray_activate" => {
+                let args: TrayActivateArgs = parse_args(&arguments)?;
+                let menu_item = args
+                    .menu_item
+                    .as_deref()
+                    .map(str::trim)
+                    .filter(|path| !path.is_empty());
+                let action = match (menu_item, args.action.unwrap_or_default()) {
+                    (Some(path), _) => tray::TrayAction::MenuItem(
+                        path.split(tray::MENU_PATH_SEPARATOR)
+                            .map(|label| label.trim().to_string())
+                            .collect(),
+                    ),
+                    (None, TrayActionArg::Activate) => tray::TrayAction::Activate,
+                    (None, TrayActionArg::SecondaryActivate) => tray::TrayAction::SecondaryActivate,
+                    (None, TrayActionArg::ContextMenu) => tray::TrayAction::ContextMenu,
+                };
+                // Apps place the windows and menus they open near this
+                // point; the middle of the screen keeps them in view.
+                let size = match input_command(config) {
+                    Ok(xdotool) => screen_geometry(&session, &xdotool, config).await.ok(),
+                    Err(_) => None,
+                };
+                let at = size.map_or((0, 0), |(width, height)| {
+                    let (origin_x, origin_y) = coordinates::viewport_origin(config);
+                    (
+                        (origin_x as f64 + width / 2.0) as i32,
+                        (origin_y as f64 + height / 2.0) as i32,
+                    )
+                });
+                let allowed = config.allowed_apps.clone();
+                let blocked = config.blocked_apps.clone();
+                let permitted = move |id: &str| {
+                    if matches_app(&blocked, id) {
+                        return Err(format!(
+                            "tray item `{id}` is in computer_use.blocked_apps; using it is not permitted"
+                        ));
+                    }
+                    if !allowed.is_empty() && !matches_app(&allowed, id) {
+                        return Err(format!(
+                            "tray item `{id}` is not in computer_use.allowed_apps ({})",
+                            allowed.join(", ")
+                        ));
+                    }
+                    Ok(())
+                };
+                let content = tray::activate(&args.item, action, at, permitted)
+                    .map_err(FunctionCallError::RespondToModel)?;
+                Ok(ToolOutput::Function {
+                    content,
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_browser_state" => {
+                let xdotool = input_command(config)?;
+                let class = active_window_class(&xdotool, config)?;
+                let browser = browser::browser_name(&class);
+                let mut cdp_error = None;
+                if let Some(port) = config.browser_debug_port {
+                    match browser::cdp_tabs(port).await {
+                        Ok(tabs) if !tabs.is_empty() => {
+                            let mut lines = vec![format!(
+                                "{} open tab(s) from the DevTools endpoint on port {port} (most recently focused first):",
+                                tabs.len()
+                            )];
+                            lines.extend(
+                                tabs.iter()
+                                    .map(|tab| format!("- \"{}\" {}", tab.title, tab.url)),
+                            );
+                            return Ok(ToolOutput::Function {
+                                content: lines.join("\n"),
+                                content_items: None,
+                                success: Some(true),
+                            });
+                        }
+                        Ok(_) => cdp_error = Some("no open page tabs".to_string()),
+                        Err(err) => cdp_error = Some(err),
+                    }
+                }
+                let Some(browser) = browser else {
+                    let mut message = format!(
+                        "the focused window (`{class}`) is not a recognized browser; focus the browser window first"
+                    );
+                    if let Some(err) = cdp_error {
+                        message.push_str(&format!(" (DevTools endpoint unavailable: {err})"));
+                    }
+                    return Err(FunctionCallError::RespondToModel(message));
+                };
+                let window_name = recovery::xdotool_output(
+                    &xdotool,
+                    config,
+                    &["getactivewindow", "getwindowname"],
+                )?;
+                let title = browser::title_from_window_name(&window_name);
+                let url = browser::read_address_bar(&xdotool, config)?;
+                let mut content =
+                    format!("{browser} tab \"{title}\" at {url} (read from the address bar)");
+                if let Some(err) = cdp_error {
+                    content.push_str(&format!("; DevTools endpoint unavailable: {err}"));
+                }
+                Ok(ToolOutput::Function {
+                    content,
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_dom_query" => {
+                let args: DomQueryArgs = parse_args(&arguments)?;
+                let codex_home = turn.client.config().codex_home.clone();
+                let content = browser_bridge::query(
+                    &codex_home,
+                    &args.selector,
+                    args.text.as_deref(),
+                    args.limit,
+                )?;
+                Ok(ToolOutput::Function {
+                    content,
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_dom_click" => {
+                let args: DomClickArgs = parse_args(&arguments)?;
+                let codex_home = turn.client.config().codex_home.clone();
+                let content = browser_bridge::click(
+                    &codex_home,
+                    &args.selector,
+                    args.text.as_deref(),
+                    args.index,
+                )?;
+                Ok(ToolOutput::Function {
+                    content,
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            "computer_landmarks" => {
+                let args: LandmarkArgs = parse_args(&arguments)?;
+                let xdotool = input_command(config)?;
+                let class = active_window_class(&xdotool, config)?;
+                let (screen_w, screen_h) = screen_geometry(&session, &xdotool, config).await?;
+                let key = landmarks::landmark_key(&class, screen_w, screen_h);
+                let path = turn
+                    .client
+                    .config()
+                    .codex_home
+                    .join(landmarks::LANDMARKS_FILE);
+                let store_error = |err: std::io::Error| {
+                    FunctionCallError::RespondToModel(format!(
+                        "failed to access landmark store {}: {err}",
+                        path.display()
+                    ))
+                };
+                let context = format!("{class} at {}x{}", screen_w as i64, screen_h as i64);
+                let content = match args.action {
+                    LandmarkAction::Save => {
+                        let label = require_label(args.label, "save")?;
+                        let (Some(x), Some(y)) = (args.x, args.y) else {
+                            return Err(FunctionCallError::RespondToModel(
+                                "computer_landmarks save requires x and y".to_string(),
+                            ));
+                        };
+                        let point = map_point(config, x, y, screen_w, screen_h);
+                        landmarks::save(&path, &key, &label, point.screen_x, point.screen_y)
+                            .map_err(store_error)?;
+                        let at = point.display(space);
+                        format!("saved landmark `{label}` at {at} for {context}")
+                    }
+                    LandmarkAction::Find | LandmarkAction::List => {
+                        let found = match (&args.action, args.label) {
+                            (LandmarkAction::Find, label) => {
+                                let label = require_label(label, "find")?;
+                                landmarks::find(&path, &key, &label)
+                            }
+                            _ => landmarks::list(&path, &key),
+                        }
+                        .map_err(store_error)?;
+                        if found.is_empty() {
+                            format!(
+                                "no saved landmarks match for {context}; take a screenshot to locate the element"
+                            )
+                        } else {
+                            let mut lines = vec![format!(
+                                "saved landmarks for {context} (verify with a screenshot if the layout may have changed):"
+                            )];
+                            lines.extend(found.iter().map(|landmark| {
+                                let at = screen_to_model(
+                                    config,
+                                    landmark.screen_x,
+                                    landmark.screen_y,
+                                    screen_w,
+                                    screen_h,
+                                );
+                                let saved = landmark.updated_at.format("%Y-%m-%d");
+                                format!("- {}: {at} (saved {saved})", landmark.label)
+                            }));
+                            lines.join("\n")
+                        }
+                    }
+                    LandmarkAction::Forget => {
+                        let label = require_label(args.label, "forget")?;
+                        if landmarks::forget(&path, &key, &label).map_err(store_error)? {
+                            format!("forgot landmark `{label}` for {context}")
+                        } else {
+                            format!("no landmark named `{label}` for {context}")
+                        }
+                    }
+                };
+                Ok(ToolOutput::Function {
+                    content,
+                    content_items: None,
+                    success: Some(true),
+                })
+            }
+            _ => Err(FunctionCallError::RespondToModel(format!(
+                "unsupported computer-use tool: {tool_name}"
+            ))),
+        }
+    }
+}
+
+/// Runs an xdotool input command with focus recovery: if nothing is focused
+/// beforehand, or the command fails, the last input target is restored and
+/// the command is retried once. Returns a suffix describing any recovery.
+/// Runs an xdotool input command with focus recovery and records `breadcrumb`
+/// (the action and its model-space coordinates) in the session history.
+/// Returns the suffix for the tool result: recovery steps, then earlier actions.
+async fn run_input(
+    session: &Session,
+    xdotool: &Path,
+    args: &[String],
+    config: &ComputerUseConfig,
+    breadcrumb: Option<String>,
+) -> Result<String, FunctionCallError> {
+    let helper = input_helper(session, config).await;
+    let helper = helper.as_ref();
+    let target = session.services.computer_use.lock().await.target.clone();
+    let mut steps = Vec::new();
+    if let Some(target) = &target
+        && input_helper::active_window(helper, xdotool, config).is_err()
+    {
+        steps = recovery::recover_focus(xdotool, config, target);
+    }
+
+    let mut result = input_helper::send_input_via(helper, xdotool, args, config);
+    if result.is_err()
+        && steps.is_empty()
+        && let Some(target) = &target
+    {
+        steps = recovery::recover_focus(xdotool, config, target);
+        result = input_helper::send_input_via(helper, xdotool, args, config);
+    }
+    let provider = match result {
+        Ok(provider) => provider,
+        Err(err) if steps.is_empty() => return Err(err),
+        Err(err) => {
+            let tried = steps.join("; ");
+            return Err(FunctionCallError::RespondToModel(format!(
+                "{err}; retried after focus recovery ({tried})"
+            )));
+        }
+    };
+
+    let window = input_helper::active_window(helper, xdotool, config).ok();
+    let debug = verbosity::input_note(config, args, provider, window.as_ref());
+    let mut state = session.services.computer_use.lock().await;
+    if let Some(window) = window {
+        state.target = Some(window);
+    }
+    let history = state.push_breadcrumb(breadcrumb, config.breadcrumbs);
+    Ok(format!("{}{history}{debug}", recovery::describe(&steps)))
+}
+
+/// Appends the recipe guidance of the app that last received input, the
+/// first time that app gets input in the session.
+async fn add_app_guidance(
+    session: &Session,
+    config: &ComputerUseConfig,
+    output: ToolOutput,
+) -> ToolOutput {
+    let ToolOutput::Function {
+        mut content,
+        content_items,
+        success,
+    } = output
+    else {
+        return output;
+    };
+    let mut state = session.services.computer_use.lock().await;
+    if let Some(recipe) = state
+        .target
+        .as_ref()
+        .and_then(|target| recipes::guidance_for(&config.recipes, &target.class))
+        && let Some(guidance) = &recipe.guidance
+        && state.take_guidance_turn(&recipe.app)
+    {
+        content.push_str(&format!(
+            "\n\nnotes on working in {} (from its recipe file):\n{guidance}",
+            recipe.app
+        ));
+    }
+    ToolOutput::Function {
+        content,
+        content_items,
+        success,
+    }
+}
+
+/// Applies the side-effect approval rules (see [`side_effects`]) to a GUI
+/// input action, asking the user through the exec approval flow when the
+/// rule requires it.
+async fn check_side_effects(
+    session: &Session,
+    turn: &TurnContext,
+    call_id: &str,
+    tool_name: &str,
+    arguments: &str,
+) -> Result<(), FunctionCallError> {
+    let config = &turn.tools_config.computer_use;
+    // The exec-policy check comes on top of the rules below, never instead
+    // of them.
+    if tool_name == "computer_type" && config.terminal_typing_exec_policy {
+        check_terminal_command(session, turn, call_id, arguments).await?;
+    }
+    let effect = match tool_name {
+        "computer_type" | "computer_key" | "computer_clear_field" => SideEffect::TerminalInput,
+        "computer_click" => SideEffect::Download,
+        _ => return Ok(()),
+    };
+    let requirement = side_effects::requirement(
+        effect.policy(config),
+        &turn.sandbox_policy,
+        turn.approval_policy,
+    );
+    if requirement == Requirement::Allow
+        || session
+            .services
+            .computer_use
+            .lock()
+            .await
+            .side_effect_approved(effect)
+    {
+        return Ok(());
+    }
+    let Ok(xdotool) = input_command(config) else {
+        return Ok(());
+    };
+    let class = active_window_class(&xdotool, config).unwrap_or_default();
+    let action = match effect {
+        SideEffect::TerminalInput => {
+            if !side_effects::is_terminal(&class, config) {
+                return Ok(());
+            }
+            if tool_name == "computer_type" {
+                let args: TypeArgs = parse_args(arguments)?;
+                format!("type {:?} into {class}", args.text)
+            } else if tool_name == "computer_clear_field" {
+                format!("clear the input line in {class}")
+            } else {
+                let args: KeyArgs = parse_args(arguments)?;
+                format!("press {} in {class}", args.keys.join("+"))
+            }
+        }
+        SideEffect::Download => {
+            if browser::browser_name(&class).is_none() {
+                return Ok(());
+            }
+            let args: ClickArgs = parse_args(arguments)?;
+            let (screen_w, screen_h) = screen_geometry(session, &xdotool, config).await?;
+            let point = map_point(config, args.x, args.y, screen_w, screen_h);
+            if !click_target_mentions_download(config, &point, screen_w, screen_h) {
+                return Ok(());
+            }
+            format!(
+                "click the download control at {} in {class}",
+                point.display(config.coordinate_space)
+            )
+        }
+    };
+    let key = effect.config_key();
+    let consequence = effect.consequence();
+    if requirement == Requirement::Deny {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "{tool_name} was blocked: {consequence}, and {key} does not allow it in this session. Do not retry; tell the user what you need done instead"
+        )));
+    }
+    let decision = session
+        .request_command_approval(
+            turn,
+            call_id.to_string(),
+            vec![tool_name.to_string(), action],
+            turn.cwd.clone(),
+            Some(format!("{consequence} ({key})")),
+            None,
+        )
+        .await;
+    match decision {
+        ReviewDecision::Approved | ReviewDecision::ApprovedExecpolicyAmendment { .. } => Ok(()),
+        ReviewDecision::ApprovedForSession => {
+            session
+                .services
+                .computer_use
+                .lock()
+                .await
+                .approve_side_effect(effect);
+            Ok(())
+        }
+        ReviewDecision::Denied | ReviewDecision::Abort => Err(FunctionCallError::RespondToModel(
+            format!("the user declined {tool_name}; {consequence}"),
+        )),
+    }
+}
+
+/// Fails GUI input while another client has held a pointer or keyboard grab
+/// for longer than `computer_use.stuck_grab_timeout_secs`, after trying to
+/// release it with Escape, button releases, and a click away from the
+/// pointer. Shorter grabs, like a menu the model just opened, are left alone.
+async fn break_stuck_grab(
+    session: &Session,
+    config: &ComputerUseConfig,
+) -> Result<(), FunctionCallError> {
+    // Grabs on this machine's X server don't reach a VM's console.
+    if config.stuck_grab_timeout_secs == 0 || config.backend == ComputerUseBackend::Qemu {
+        return Ok(());
+    }
+    let probe = match grabs::probe(config) {
+        Ok(probe) => probe,
+        Err(err) => {
+            tracing::debug!("could not check for input grabs: {err}");
+            return Ok(());
+        }
+    };
+    if probe.grabs.server {
+        return Err(FunctionCallError::RespondToModel(
+            "the X server did not answer within 2 s, which usually means another client grabbed the whole server; nothing was sent. Wait a moment and retry, and if it persists, stop and tell the user".to_string(),
+        ));
+    }
+    let held = session
+        .services
+        .computer_use
+        .lock()
+        .await
+        .note_grab(probe.grabs.any());
+    let timeout = Duration::from_secs(config.stuck_grab_timeout_secs);
+    if held < timeout {
+        return Ok(());
+    }
+    let xdotool = input_command(config)?;
+    let (x, y) = grabs::dismiss_point(probe.pointer, probe.screen);
+    let attempts = [
+        ["key", "Escape"].map(str::to_string).to_vec(),
+        ["mouseup", "1", "mouseup", "2", "mouseup", "3"]
+            .map(str::to_string)
+            .to_vec(),
+        vec![
+            "mousemove".to_string(),
+            x.to_string(),
+            y.to_string(),
+            "click".to_string(),
+            "1".to_string(),
+            "mousemove".to_string(),
+            probe.pointer.0.to_string(),
+            probe.pointer.1.to_string(),
+        ],
+    ];
+    let mut still = probe.grabs;
+    for attempt in attempts {
+        if let Err(err) = run_command(&xdotool, &attempt, config) {
+            tracing::debug!("grab release step failed: {err:?}");
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        still = grabs::probe(config).map_or(still, |probe| probe.grabs);
+        if !still.any() {
+            tracing::info!(
+                "released {} after {} s with `xdotool {}`",
+                probe.grabs.describe(),
+                held.as_secs(),
+                attempt.join(" ")
+            );
+            session.services.computer_use.lock().await.note_grab(false);
+            return Ok(());
+        }
+    }
+    let clicked = coordinates::screen_to_model(
+        config,
+        i64::from(x),
+        i64::from(y),
+        f64::from(probe.screen.0),
+        f64::from(probe.screen.1),
+    );
+    Err(FunctionCallError::RespondToModel(format!(
+        "another app has grabbed {} for {} s, so input would go to it instead of its target, and nothing was sent. Pressing Escape, releasing the mouse buttons, and clicking at {clicked} did not release it; take a screenshot to find a stuck menu or drag, and if you can't close it, stop and tell the user",
+        still.describe(),
+        held.as_secs()
+    )))
+}
+
+/// Fails GUI input while the screen is locked. After a lock, input stays
+/// suspended until the screen is unlocked and the user approves resuming.
+async fn ensure_unlocked(
+    session: &Session,
+    turn: &TurnContext,
+    call_id: &str,
+) -> Result<(), FunctionCallError> {
+    let config = &turn.tools_config.computer_use;
+    // This machine's screen lock says nothing about a VM's console.
+    if !config.lock_detection || config.backend == ComputerUseBackend::Qemu {
+        return Ok(());
+    }
+    if let Some(reason) = lock::lock_reason(config) {
+        session
+            .services
+            .computer_use
+            .lock()
+            .await
+            .suspend_for_lock(reason.clone());
+        let next_step = if config.unlock_helper {
+            "call computer_unlock to unlock it with the user's stored secret, then continue"
+        } else {
+            "do not retry, stop and tell the user"
+        };
+        return Err(FunctionCallError::RespondToModel(format!(
+            "screen locked: {reason}. GUI actions are suspended until the user unlocks the screen and confirms; {next_step}"
+        )));
+    }
+    let Some(reason) = session.services.computer_use.lock().await.lock_suspension() else {
+        return Ok(());
+    };
+    if turn.approval_policy == AskForApproval::Never {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "screen locked earlier ({reason}); GUI actions stay suspended because this session can't ask the user to confirm resuming"
+        )));
+    }
+    let decision = session
+        .request_command_approval(
+            turn,
+            call_id.to_string(),
+            vec!["resume GUI actions".to_string()],
+            turn.cwd.clone(),
+            Some(format!(
+                "the screen was locked ({reason}), so GUI actions were suspended; approve to let the agent continue"
+            )),
+            None,
+        )
+        .await;
+    match decision {
+        ReviewDecision::Approved
+        | ReviewDecision::ApprovedExecpolicyAmendment { .. }
+        | ReviewDecision::ApprovedForSession => {
+            session
+                .services
+                .computer_use
+                .lock()
+                .await
+                .clear_lock_suspension();
+            Ok(())
+        }
+        ReviewDecision::Denied | ReviewDecision::Abort => Err(FunctionCallError::RespondToModel(
+            "screen locked earlier and the user has not confirmed resuming GUI actions; stop and wait for them".to_string(),
+        )),
+    }
+}
+
+/// Adds an image just attached to the conversation to the session's GUI usage.
+async fn record_attached_image(session: &Session, path: &Path, screenshot: bool) {
+    let bytes = std::fs::metadata(path).map(|meta| meta.len()).unwrap_or(0);
+    session
+        .services
+        .computer_use
+        .lock()
+        .await
+        .record_attached_image(bytes, screenshot);
+}
+
+/// Checks text about to be typed into a terminal against the exec policy, the
+/// same way the shell tool checks a command it runs outside the sandbox.
+async fn check_terminal_command(
+    session: &Session,
+    turn: &TurnContext,
+    call_id: &str,
+    arguments: &str,
+) -> Result<(), FunctionCallError> {
+    let config = &turn.tools_config.computer_use;
+    let args: TypeArgs = parse_args(arguments)?;
+    let Some(command) = side_effects::typed_command(&args.text) else {
+        return Ok(());
+    };
+    let focused = input_command(config)
+        .and_then(|xdotool| active_window_class(&xdotool, config))
+        .ok()
+        .filter(|class| !class.is_empty());
+    let Some(class) = terminal_target(&args.text, focused.as_deref(), config) else {
+        return Ok(());
+    };
+    let unidentified = focused.is_none();
+    if unidentified && turn.approval_policy == AskForApproval::Never {
+        return Err(FunctionCallError::RespondToModel(
+            "computer_type was blocked: the text ends with Enter, the focused window couldn't be identified to rule out a terminal, and approvals are off in this session".to_string(),
+        ));
+    }
+    let features = session.features();
+    let requirement = session
+        .services
+        .exec_policy
+        .create_exec_approval_requirement_for_command(
+            &features,
+            &command,
+            turn.approval_policy,
+            &turn.sandbox_policy,
+            SandboxPermissions::RequireEscalated,
+        )
+        .await;
+    let (reason, amendment) = match requirement {
+        // The text may run in a terminal the policy never saw, so ask anyway.
+        ExecApprovalRequirement::Skip { .. } if unidentified => (
+            Some("the focused window couldn't be identified; if it is a terminal, typing this runs it as a command".to_string()),
+            None,
+        ),
+        ExecApprovalRequirement::Skip { .. } => return Ok(()),
+        ExecApprovalRequirement::Forbidden { reason } => {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "computer_type was blocked: typing into {class} runs the text as a command, and the exec policy rejected it: {reason}"
+            )));
+        }
+        ExecApprovalRequirement::NeedsApproval {
+            reason,
+            proposed_execpolicy_amendment,
+        } => (reason, proposed_execpolicy_amendment),
+    };
+    let reason = reason.unwrap_or_else(|| format!("typing into {class} runs this command"));
+    let decision = session
+        .request_command_approval(
+            turn,
+            call_id.to_string(),
+            command,
+            turn.cwd.clone(),
+            Some(reason),
+            amendment,
+        )
+        .await;
+    match decision {
+        ReviewDecision::Approved
+        | ReviewDecision::ApprovedExecpolicyAmendment { .. }
+        | ReviewDecision::ApprovedForSession => Ok(()),
+        ReviewDecision::Denied | ReviewDecision::Abort => Err(FunctionCallError::RespondToModel(
+            format!("the user declined running the typed text as a command in {class}"),
+        )),
+    }
+}
+
+/// What to call the window `text` would be typed into when it must pass the
+/// exec policy first, or `None` when it needn't. `focused` is the class of
+/// the focused window, `None` when it couldn't be identified; text ending in
+/// Enter is then checked as if it went to a terminal rather than let through.
+fn terminal_target(
+    text: &str,
+    focused: Option<&str>,
+    config: &ComputerUseConfig,
+) -> Option<String> {
+    match focused {
+        Some(class) => side_effects::is_terminal(class, config).then(|| class.to_string()),
+        None => text
+            .ends_with(['\n', '\r'])
+            .then(|| "an unidentified window".to_string()),
+    }
+}
+
+/// Reads the area around a browser click with OCR and reports whether it
+/// shows a download, save, or install label. Unreadable screens count as no.
+fn click_target_mentions_download(
+    config: &ComputerUseConfig,
+    point: &coordinates::MappedPoint,
+    screen_w: f64,
+    screen_h: f64,
+) -> bool {
+    let (width, height) = side_effects::DOWNLOAD_PROBE_SIZE;
+    let (origin_x, origin_y) = coordinates::viewport_origin(config);
+    let rect = ScreenRect {
+        left: (point.screen_x - width / 2)
+            .clamp(origin_x, origin_x + (screen_w as i64 - width).max(0)),
+        top: (point.screen_y - height / 2)
+            .clamp(origin_y, origin_y + (screen_h as i64 - height).max(0)),
+        width: width.min(screen_w as i64),
+        height: height.min(screen_h as i64),
+    };
+    let Ok(capture) = capture_root_window(config, Some(rect.crop_geometry()), None) else {
+        return false;
+    };
+    let tsv = find_text::recognize(&capture);
+    let _ = std::fs::remove_file(&capture);
+    tsv.is_ok_and(|tsv| side_effects::mentions_download(&tsv))
+}
+
+/// The session's input helper, if `computer_use.persistent_input` is on,
+/// `input_providers` lists `native`, and the helper can run on the
+/// configured display.
+async fn input_helper(
+    session: &Session,
+    config: &ComputerUseConfig,
+) -> Option<input_helper::InputHelper> {
+    if !config.persistent_input || !config.input_providers.contains(&InputProvider::Native) {
+        return None;
+    }
+    session
+        .services
+        .computer_use
+        .lock()
+        .await
+        .input_helper(config.display.as_deref())
+}
+
+/// Screen size in pixels: the configured viewport's, or the screen's from the
+/// input helper when it's running and from `xdotool getdisplaygeometry`
+/// otherwise.
+async fn screen_geometry(
+    session: &Session,
+    xdotool: &Path,
+    config: &ComputerUseConfig,
+) -> Result<(f64, f64), FunctionCallError> {
+    if let Some(viewport) = config.viewport {
+        return Ok((f64::from(viewport.width), f64::from(viewport.height)));
+    }
+    if let Some(helper) = input_helper(session, config).await
+        && let Ok(size) = helper.screen_size()
+    {
+        return Ok(size);
+    }
+    display_geometry(xdotool, config)
+}
+
+/// The `computer_click` call that clicks the center of the element `query`
+/// locates, for a recipe step.
+async fn recipe_click(
+    session: &Session,
+    config: &ComputerUseConfig,
+    xdotool: &Path,
+    codex_home: &Path,
+    query: &str,
+    button: &str,
+    double: bool,
+) -> Result<(&'static str, serde_json::Value), FunctionCallError> {
+    let (screen_w, screen_h) = screen_geometry(session, xdotool, config).await?;
+    let outcome = locator::locate(config, codex_home, query, None);
+    let Some(found) = outcome.found else {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "no element matches `{query}` ({})",
+            pii::scrub(&outcome.misses.join("; "), &config.ocr_scrubbers)
+        )));
+    };
+    let (x, y) = coordinates::screen_to_model_xy(
+        config,
+        (found.left + found.right) / 2,
+        (found.top + found.bottom) / 2,
+        screen_w,
+        screen_h,
+    );
+    Ok((
+        "computer_click",
+        serde_json::json!({ "x": x, "y": y, "button": button, "double": double }),
+    ))
+}
+
+/// Captures the desktop for the human operator (the TUI screenshot key),
+/// scaled like `computer_screenshot` so positions in the user's note line up
+/// with the model's coordinate space.
+pub fn capture_operator_screenshot(config: &ComputerUseConfig) -> anyhow::Result<PathBuf> {
+    ensure_display(config)?;
+    Ok(capture_screenshot(config)?)
+}
+
+fn ensure_display(config: &ComputerUseConfig) -> Result<(), FunctionCallError> {
+    // The VM's console is reached through its QMP socket, not a display.
+    if config.backend == ComputerUseBackend::Qemu {
+        return Ok(());
+    }
+    if !cfg!(target_os = "linux") {
+        return Err(FunctionCallError::RespondToModel(
+            "computer-use GUI tools are only supported on Linux/X11".to_string(),
+        ));
+    }
+    // A sandbox without the X socket can still reach the screen and input
+    // through the portals.
+    if config.display.is_none() && env::var("DISPLAY").is_err() && confinement::detect().is_none() {
+        return Err(FunctionCallError::RespondToModel(
+            "DISPLAY is not set; GUI tools require an X11 session".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Rejects GUI input when the focused window's class is on
+/// `computer_use.blocked_apps`, or when `computer_use.allowed_apps` is set and
+/// the class is not on it.
+fn ensure_window_permitted(config: &ComputerUseConfig) -> Result<(), FunctionCallError> {
+    if config.allowed_apps.is_empty() && config.blocked_apps.is_empty() {
+        return Ok(());
+    }
+    let xdotool = input_command(config)?;
+    let class = active_window_class(&xdotool, config).unwrap_or_default();
+    if matches_app(&config.blocked_apps, &class) {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "the focused window (`{class}`) is in computer_use.blocked_apps; GUI input to it is not permitted"
+        )));
+    }
+    if config.allowed_apps.is_empty() || matches_app(&config.allowed_apps, &class) {
+        return Ok(());
+    }
+    let allowed = config.allowed_apps.join(", ");
+    Err(FunctionCallError::RespondToModel(format!(
+        "the focused window (`{class}`) is not in computer_use.allowed_apps ({allowed}); focus an allowed application first"
+    )))
+}
+
+/// Checks that the window named by a `window` argument has focus, moving
+/// focus to it when configured, and re-applies the app rules if it moved.
+fn expect_window(
+    xdotool: &Path,
+    config: &ComputerUseConfig,
+    window: Option<&str>,
+) -> Result<String, FunctionCallError> {
+    let Some(window) = window else {
+        return Ok(String::new());
+    };
+    let focused = focus_guard::ensure_focused(xdotool, config, window)?;
+    if !focused.is_empty() {
+        ensure_window_permitted(config)?;
+    }
+    Ok(focused)
+}
+
+/// `WM_CLASS` of the focused window.
+fn active_window_class(
+    xdotool: &Path,
+    config: &ComputerUseConfig,
+) -> Result<String, FunctionCallError> {
+    let output = gui_command(xdotool, config)
+        .args(["getactivewindow", "getwindowclassname"])
+        .output()
+        .map_err(|err| {
+            FunctionCallError::RespondToModel(format!(
+                "failed to run xdotool getwindowclassname: {err}"
+            ))
+        })?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(FunctionCallError::RespondToModel(format!(
+            "xdotool getwindowclassname failed: {stderr}"
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn require_label(label: Option<String>, action: &str) -> Result<String, FunctionCallError> {
+    label
+        .map(|label| label.trim().to_string())
+        .filter(|label| !label.is_empty())
+        .ok_or_else(|| {
+            FunctionCallError::RespondToModel(format!(
+                "computer_landmarks {action} requires a non-empty label"
+            ))
+        })
+}
+
+fn matches_app(apps: &[String], class: &str) -> bool {
+    !class.is_empty()
+        && apps
+            .iter()
+            .any(|app| app.trim().eq_ignore_ascii_case(class))
+}
+
+/// Builds a [`Command`] for a GUI helper, pointed at the configured display.
+fn gui_command(program: &Path, config: &ComputerUseConfig) -> Command {
+    let mut command = Command::new(program);
+    if let Some(display) = &config.display {
+        command.env("DISPLAY", display);
+    }
+    command
+}
+
+fn ensure_enabled(config: &ComputerUseConfig, tool_name: &str) -> Result<(), FunctionCallError> {
+    if enabled_tools(config).contains(&tool_name) {
+        return Ok(());
+    }
+    let reason = if config.keyboard_only {
+        "pointer tools are disabled by computer_use.keyboard_only; navigate with the keyboard instead"
+    } else {
+        "it is disabled by the computer_use configuration"
+    };
+    Err(FunctionCallError::RespondToModel(format!(
+        "{tool_name} is not available: {reason}"
+    )))
+}
+
+/// Rejects malformed arguments when an action is queued rather than when the
+/// queue runs, so the model can fix them while it still has the plan in mind.
+fn check_queued_args(tool_name: &str, arguments: &str) -> Result<(), FunctionCallError> {
+    schema::check_args(tool_name, arguments)?;
+    if tool_name == "computer_key" {
+        let args: KeyArgs = parse_args(arguments)?;
+        keys::parse_chord(&args.keys).map_err(FunctionCallError::RespondToModel)?;
+    }
+    Ok(())
+}
+
+/// Reserves up to `wanted` images against
+/// `computer_use.max_screenshots_per_turn`, in the same lock as the check.
+/// Returns how many were granted, the limit, and how many are left, or
+/// `None` when there is no budget.
+fn reserve_screenshots(
+    state: &mut ComputerUseState,
+    turn_id: &str,
+    config: &ComputerUseConfig,
+    wanted: u32,
+) -> Option<(u32, u32, u32)> {
+    let limit = config.max_screenshots_per_turn?;
+    let (granted, remaining) = state.reserve_screenshots(turn_id, limit, wanted);
+    Some((granted, limit, remaining))
+}
+
+/// Gives back what [`reserve_screenshots`] granted when no image was
+/// attached after all.
+fn release_screenshots(
+    state: &mut ComputerUseState,
+    turn_id: &str,
+    budget: Option<(u32, u32, u32)>,
+) {
+    if let Some((granted, _, _)) = budget {
+        state.release_screenshots(turn_id, granted);
+    }
+}
+
+fn screenshot_budget_exhausted(limit: u32) -> FunctionCallError {
+    FunctionCallError::RespondToModel(format!(
+        "screenshot budget exhausted: all {limit} screenshot(s) for this turn are used. Do not call computer_screenshot again this turn; continue with computer_find_text or keyboard input if that is safe, otherwise stop and report where the task stands"
+    ))
+}
+
+/// Suffix for image results when `computer_use.max_screenshots_per_turn`
+/// is set, so the model can pace itself.
+fn screenshot_budget_note(limit: u32, remaining: u32) -> String {
+    if remaining == 0 {
+        format!(
+            "\nscreenshot budget: this was the last of {limit} screenshot(s) allowed this turn; plan the remaining steps from it"
+        )
+    } else {
+        format!("\nscreenshot budget: {remaining} of {limit} left this turn")
+    }
+}
+
+/// The xdotool that input and focus checks go through. The `qemu` and
+/// `console` backends have no windows to check, so they run without one.
+fn input_command(config: &ComputerUseConfig) -> Result<PathBuf, FunctionCallError> {
+    match config.backend {
+        ComputerUseBackend::X11 => require_command("xdotool"),
+        ComputerUseBackend::Qemu | ComputerUseBackend::Console => Ok(PathBuf::from("xdotool")),
+    }
+}
+
+fn require_command(name: &str) -> Result<PathBuf, FunctionCallError> {
+    if let Ok(path) = which(name) {
+        return Ok(path);
+    }
+    // The bundled helper answers the xdotool commands of the common path.
+    if name == "xdotool"
+        && let Some(helper) = bundled::helper()
+    {
+        return Ok(helper);
+    }
+    let hint = match name {
+        "xdotool" => "sudo apt-get install -y xdotool",
+        "import" => "sudo apt-get install -y imagemagick",
+        "xclip" => "sudo apt-get install -y xclip",
+        _ => "install the required package",
+    };
+    Err(FunctionCallError::RespondToModel(confinement::explain(
+        format!("required command `{name}` not found; install it with `{hint}`"),
+    )))
+}
+
+fn display_geometry(
+    xdotool: &Path,
+    config: &ComputerUseConfig,
+) -> Result<(f64, f64), FunctionCallError> {
+    if let Some(viewport) = config.viewport {
+        return Ok((f64::from(viewport.width), f64::from(viewport.height)));
+    }
+    if config.backend == ComputerUseBackend::Qemu {
+        let (width, height) =
+            qmp::screen_size(config).map_err(FunctionCallError::RespondToModel)?;
+        return Ok((f64::from(width), f64::from(height)));
+    }
+    let output = gui_command(xdotool, config)
+        .arg("getdisplaygeometry")
+        .output()
+        .map_err(|err| {
+            FunctionCallError::RespondToModel(format!(
+                "failed to run xdotool getdisplaygeometry: {err}"
+            ))
+        })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(FunctionCallError::RespondToModel(confinement::explain(
+            format!(
+                "xdotool getdisplaygeometry failed: {stderr}; without an X display, set computer_use.viewport to the screen size"
+            ),
+        )));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut parts = stdout.split_whitespace();
+    let width = parts
+        .next()
+        .ok_or_else(|| {
+            FunctionCallError::RespondToModel(
+                "xdotool getdisplaygeometry returned no width".to_string(),
+            )
+        })?
+        .parse::<f64>()
+        .map_err(|err| {
+            FunctionCallError::RespondToModel(format!(
+                "xdotool getdisplaygeometry invalid width: {err}"
+            ))
+        })?;
+    let height = parts
+        .next()
+        .ok_or_else(|| {
+            FunctionCallError::RespondToModel(
+                "xdotool getdisplaygeometry returned no height".to_string(),
+            )
+        })?
+        .parse::<f64>()
+        .map_err(|err| {
+            FunctionCallError::RespondToModel(format!(
+                "xdotool getdisplaygeometry invalid height: {err}"
+            ))
+        })?;
+
+    Ok((width, height))
+}
+
+/// Backspaces `computer_clear_field` presses when the model gives no count.
+const DEFAULT_CLEAR_BACKSPACES: u32 = 100;
+
+fn clear_backspaces(count: Option<u32>) -> u32 {
+    count.unwrap_or(DEFAULT_CLEAR_BACKSPACES).max(1)
+}
+
+/// xdotool arguments that empty the focused field with `method`. Each chord is
+/// its own `key` command so the input helper can replay them.
+fn clear_field_keys(method: ClearMethod, count: Option<u32>, os: TargetOs) -> Vec<String> {
+    let chord = |name: &str, fallback: &str| {
+        keys::shortcut_keys(name, os).map_or_else(|| fallback.to_string(), |keys| keys.join("+"))
+    };
+    match method {
+        ClearMethod::SelectAll => vec![
+            "key".to_string(),
+            chord("select_all", "ctrl+a"),
+            "key".to_string(),
+            "BackSpace".to_string(),
+        ],
+        ClearMethod::Backspace => vec![
+            "key".to_string(),
+            chord("line_end", "End"),
+            "key".to_string(),
+            "--repeat".to_string(),
+            clear_backspaces(count).to_string(),
+            "BackSpace".to_string(),
+        ],
+    }
+}
+
+fn requires_confirmation(keys: &[String]) -> bool {
+    let normalized: std::collections::BTreeSet<String> = keys
+        .iter()
+        .map(|key| {
+            keys::canonical_key(key.trim())
+                .unwrap_or_else(|_| key.trim().to_string())
+                .to_ascii_lowercase()
+        })
+        .collect();
+
+    DESTRUCTIVE_KEY_COMBOS
+        .iter()
+        .any(|combo| combo.iter().all(|key| normalized.contains(*key)))
+}
+
+fn run_command(
+    command: &Path,
+    args: &[String],
+    config: &ComputerUseConfig,
+) -> Result<(), FunctionCallError> {
+    let output = gui_command(command, config)
+        .args(args)
+        .output()
+        .map_err(|err| {
+            FunctionCallError::RespondToModel(format!("failed to run {command:?}: {err}"))
+        })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let debug = verbosity::command_failure(config, args, output.status);
+        return Err(FunctionCallError::RespondToModel(format!(
+            "command {command:?} failed: {stderr}{stdout}{debug}"
+        )));
+    }
+
+    Ok(())
+}
+
+fn capture_screenshot(config: &ComputerUseConfig) -> Result<PathBuf, FunctionCallError> {
+    capture_root_window(config, None, screenshot_resize(config))
+}
+
+/// Captures the screenshot for `tier`, returning it with a note on how its
+/// pixels map to tool coordinates when they differ from the standard capture.
+fn capture_at_detail(
+    config: &ComputerUseConfig,
+    tier: Tier,
+    region: Option<(f64, f64, f64, f64)>,
+) -> Result<(PathBuf, Option<String>), FunctionCallError> {
+    if tier == Tier::Standard {
+        return Ok((capture_screenshot(config)?, None));
+    }
+    let xdotool = input_command(config)?;
+    let (screen_w, screen_h) = display_geometry(&xdotool, config)?;
+    let full = ScreenRect::visible(config, screen_w, screen_h);
+    let (rect, path, image_size) = if tier == Tier::Low {
+        let low = detail::LOW_DETAIL;
+        let path = capture_root_window(config, None, Some(low))?;
+        (full, path, (low.width, low.height))
+    } else {
+        let rect = region.map_or(full, |region| {
+            detail::region_to_screen(config, region, screen_w, screen_h)
+        });
+        let crop = region.map(|_| rect.crop_geometry());
+        let path = capture_root_window(config, crop, None)?;
+        (rect, path, (rect.width as u32, rect.height as u32))
+    };
+    let note = detail::mapping_note(config, tier, rect, image_size, screen_w, screen_h);
+    Ok((path, Some(note)))
+}
+
+/// Captures the root window to a temporary PNG, optionally cropped with
+/// `import -crop` and then resized to exactly `resize`.
+///
+/// Resizing happens in-process on SIMD kernels: `import` hands over raw PAM
+/// pixels, which is several times faster than letting ImageMagick resample
+/// and encode a 4K capture.
+fn capture_root_window(
+    config: &ComputerUseConfig,
+    crop: Option<String>,
+    resize: Option<Resolution>,
+) -> Result<PathBuf, FunctionCallError> {
+    let id = Uuid::new_v4();
+    let filename = format!("codex-screenshot-{id}.png");
+    let path = env::temp_dir().join(filename);
+    let crop = match crop {
+        Some(geometry) => Some(
+            geometry
+                .parse::<Viewport>()
+                .map_err(FunctionCallError::RespondToModel)?,
+        ),
+        None => config.viewport,
+    };
+    let mut failures = Vec::new();
+    let mut captured = None;
+    for &provider in &config.screenshot_providers {
+        match capture::capture(provider, config, crop, &path, resize.is_some()) {
+            Ok(found) => {
+                captured = Some((provider, found));
+                break;
+            }
+            Err(err) => failures.push(format!("{}: {err}", capture::provider_name(provider))),
+        }
+    }
+    let Some((provider, captured)) = captured else {
+        return Err(FunctionCallError::RespondToModel(confinement::explain(
+            format!(
+                "no screenshot provider in computer_use.screenshot_providers worked ({}); install one with `sudo apt-get install -y imagemagick`",
+                failures.join("; ")
+            ),
+        )));
+    };
+
+    let open = || {
+        RawImage::open(&path).map_err(|err| {
+            FunctionCallError::RespondToModel(format!("failed to read screenshot: {err}"))
+        })
+    };
+    let mut resize = resize;
+    let mut raw = match (captured, resize) {
+        (Captured::Raw(raw), _) => Some(raw),
+        (Captured::File, Some(_)) => Some(open()?),
+        (Captured::File, None) => None,
+    };
+    // Device pixels of a scaled Wayland output go back onto the input grid.
+    if resize.is_none() && scaling::in_device_pixels(provider) {
+        let image = match raw {
+            Some(image) => image,
+            None => open()?,
+        };
+        resize = scaling::input_grid(config, crop, image.width, image.height);
+        raw = Some(image);
+    }
+    if let Some(raw) = raw {
+        let scaled = match resize {
+            Some(size) => raw.resize(size.width, size.height),
+            None => Ok(raw),
+        };
+        let png = scaled.and_then(|image| image.encode_png()).map_err(|err| {
+            FunctionCallError::RespondToModel(format!("failed to scale screenshot: {err}"))
+        })?;
+        std::fs::write(&path, png).map_err(|err| {
+            FunctionCallError::RespondToModel(format!(
+                "failed to write screenshot to {}: {err}",
+                path.display()
+            ))
+        })?;
+    }
+
+    if !path.is_file() {
+        let display = path.display();
+        return Err(FunctionCallError::RespondToModel(format!(
+            "screenshot was not created at {display}"
+        )));
+    }
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn destructive_combos_match_regardless_of_order_and_aliases() {
+        let keys = ["Q".to_string(), "Control".to_string()];
+        assert!(requires_confirmation(&keys));
+        assert!(!requires_confirmation(&[
+            "ctrl".to_string(),
+            "c".to_string()
+        ]));
+    }
+
+    #[test]
+    fn destructive_combos_are_checked_after_translating_for_macos() {
+        let on_macos = |keys: &[&str]| {
+            let keys: Vec<String> = keys.iter().map(|key| (*key).to_string()).collect();
+            keys::parse_chord(&keys).map(|chord| chord.keys_for(TargetOs::Macos))
+        };
+        for keys in [
+            &["close_tab"][..],
+            &["close_window"],
+            &["quit"],
+            &["cmd", "w"],
+            &["cmd+shift+w"],
+        ] {
+            let chord = on_macos(keys);
+            assert!(
+                chord
+                    .as_ref()
+                    .is_ok_and(|chord| requires_confirmation(chord)),
+                "{keys:?} became {chord:?}"
+            );
+        }
+        assert!(on_macos(&["copy"]).is_ok_and(|chord| !requires_confirmation(&chord)));
+    }
+
+    #[test]
+    fn app_lists_match_window_class_case_insensitively() {
+        let apps = vec!["firefox".to_string(), " Gimp ".to_string()];
+        assert!(matches_app(&apps, "Firefox"));
+        assert!(matches_app(&apps, "gimp"));
+        assert!(!matches_app(&apps, "xterm"));
+        assert!(!matches_app(&apps, ""));
+    }
+
+    #[test]
+    fn keyboard_only_swaps_pointer_tools_for_focus_helpers() {
+        let default_tools = enabled_tools(&ComputerUseConfig::default());
+        assert!(default_tools.contains(&"computer_click"));
+        assert!(!default_tools.contains(&"computer_focus_next"));
+
+        let keyboard_tools = enabled_tools(&ComputerUseConfig {
+            keyboard_only: true,
+            ..Default::default()
+        });
+        for tool in POINTER_TOOLS {
+            assert!(!keyboard_tools.contains(tool), "{tool} should be disabled");
+        }
+        assert!(keyboard_tools.contains(&"computer_focus_next"));
+        assert!(keyboard_tools.contains(&"computer_focus_prev"));
+        assert!(keyboard_tools.contains(&"computer_key"));
+    }
+
+    #[test]
+    fn gesture_tools_require_opt_in() {
+        let default_tools = enabled_tools(&ComputerUseConfig::default());
+        for tool in GESTURE_TOOLS {
+            assert!(!default_tools.contains(tool), "{tool} should be opt-in");
+        }
+        let touch_tools = enabled_tools(&ComputerUseConfig {
+            gestures: true,
+            ..Default::default()
+        });
+        for tool in GESTURE_TOOLS {
+            assert!(touch_tools.contains(tool), "{tool} should be enabled");
+        }
+    }
+
+    #[test]
+    fn clear_field_keys_follow_method_and_target_os() {
+        assert_eq!(
+            clear_field_keys(ClearMethod::SelectAll, Some(5), TargetOs::Macos),
+            ["key", "super+a", "key", "BackSpace"]
+        );
+        assert_eq!(
+            clear_field_keys(ClearMethod::Backspace, Some(20), TargetOs::Linux),
+            ["key", "End", "key", "--repeat", "20", "BackSpace"]
+        );
+        assert!(
+            input_helper::parse_steps(&clear_field_keys(
+                ClearMethod::Backspace,
+                None,
+                TargetOs::Linux
+            ))
+            .is_some()
+        );
+    }
+
+    #[test]
+    fn magnifier_tool_is_opt_in() {
+        assert!(!enabled_tools(&ComputerUseConfig::default()).contains(&"computer_magnifier"));
+        let magnifier_tools = enabled_tools(&ComputerUseConfig {
+            magnifier: true,
+            ..Default::default()
+        });
+        assert!(magnifier_tools.contains(&"computer_magnifier"));
+    }
+
+    #[test]
+    fn theme_tool_is_opt_in() {
+        assert!(!enabled_tools(&ComputerUseConfig::default()).contains(&"computer_set_theme"));
+        let theme_tools = enabled_tools(&ComputerUseConfig {
+            theme_switching: true,
+            ..Default::default()
+        });
+        assert!(theme_tools.contains(&"computer_set_theme"));
+    }
+
+    #[test]
+    fn system_control_tool_is_opt_in() {
+        assert!(!enabled_tools(&ComputerUseConfig::default()).contains(&"computer_system_control"));
+        let control_tools = enabled_tools(&ComputerUseConfig {
+            system_controls: true,
+            ..Default::default()
+        });
+        assert!(control_tools.contains(&"computer_system_control"));
+    }
+
+    #[test]
+    fn announce_tool_is_opt_in() {
+        assert!(!enabled_tools(&ComputerUseConfig::default()).contains(&"computer_announce"));
+        let announce_tools = enabled_tools(&ComputerUseConfig {
+            announcements: true,
+            ..Default::default()
+        });
+        assert!(announce_tools.contains(&"computer_announce"));
+    }
+
+    #[test]
+    fn watch_demo_tool_is_opt_in() {
+        assert!(!enabled_tools(&ComputerUseConfig::default()).contains(&"computer_watch_demo"));
+        let demo_tools = enabled_tools(&ComputerUseConfig {
+            demonstrations: true,
+            ..Default::default()
+        });
+        assert!(demo_tools.contains(&"computer_watch_demo"));
+    }
+
+    #[test]
+    fn detect_elements_tool_needs_a_detector() {
+        assert!(
+            !enabled_tools(&ComputerUseConfig::default()).contains(&"computer_detect_elements")
+        );
+        let detector_tools = enabled_tools(&ComputerUseConfig {
+            element_detector: Some(crate::config::types::ElementDetector {
+                model: PathBuf::from("/opt/ui-detector/model.onnx"),
+                labels: Vec::new(),
+                input_size: None,
+            }),
+            ..Default::default()
+        });
+        assert!(detector_tools.contains(&"computer_detect_elements"));
+    }
+
+    #[test]
+    fn locate_tool_reads_the_screen_without_input() {
+        assert!(enabled_tools(&ComputerUseConfig::default()).contains(&"computer_locate"));
+        assert!(SCREEN_TOOLS.contains(&"computer_locate"));
+        assert!(!sends_input("computer_locate"));
+    }
+
+    #[test]
+    fn recent_frames_tool_is_opt_in() {
+        assert!(!enabled_tools(&ComputerUseConfig::default()).contains(&"computer_recent_frames"));
+        let frame_tools = enabled_tools(&ComputerUseConfig {
+            recent_frames: true,
+            ..Default::default()
+        });
+        assert!(frame_tools.contains(&"computer_recent_frames"));
+    }
+
+    #[test]
+    fn dom_tools_are_opt_in() {
+        assert!(!enabled_tools(&ComputerUseConfig::default()).contains(&"computer_dom_query"));
+        let dom_tools = enabled_tools(&ComputerUseConfig {
+            browser_bridge: true,
+            ..Default::default()
+        });
+        assert!(dom_tools.contains(&"computer_dom_query"));
+        assert!(dom_tools.contains(&"computer_dom_click"));
+    }
+
+    #[test]
+    fn office_tool_is_opt_in() {
+        assert!(!enabled_tools(&ComputerUseConfig::default()).contains(&"computer_office"));
+        assert!(
+            enabled_tools(&ComputerUseConfig {
+                office_bridge: true,
+                ..Default::default()
+            })
+            .contains(&"computer_office")
+        );
+    }
+
+    #[test]
+    fn camera_tool_is_opt_in() {
+        assert!(
+            !enabled_tools(&ComputerUseConfig::default()).contains(&"computer_camera_snapshot")
+        );
+        let camera_tools = enabled_tools(&ComputerUseConfig {
+            camera: true,
+            ..Default::default()
+        });
+        assert!(camera_tools.contains(&"computer_camera_snapshot"));
+    }
+
+    #[test]
+    fn unlock_tool_is_opt_in() {
+        assert!(!enabled_tools(&ComputerUseConfig::default()).contains(&"computer_unlock"));
+        let unlock_tools = enabled_tools(&ComputerUseConfig {
+            unlock_helper: true,
+            ..Default::default()
+        });
+        assert!(unlock_tools.contains(&"computer_unlock"));
+    }
+
+    #[test]
+    fn action_queue_offers_run_queue_and_checks_arguments_up_front() {
+        assert!(!enabled_tools(&ComputerUseConfig::default()).contains(&"computer_run_queue"));
+        let queue_tools = enabled_tools(&ComputerUseConfig {
+            action_queue: true,
+            ..Default::default()
+        });
+        assert!(queue_tools.contains(&"computer_run_queue"));
+
+        assert!(check_queued_args("computer_click", r#"{"x": 10, "y": 20}"#).is_ok());
+        assert!(check_queued_args("computer_click", r#"{"x": 10}"#).is_err());
+        assert!(check_queued_args("computer_key", r#"{"keys": ["ctrl", "s"]}"#).is_ok());
+        assert!(check_queued_args("computer_key", r#"{"keys": ["ctrl", "Pg Dwn"]}"#).is_err());
+    }
+
+    #[test]
+    fn unidentified_windows_get_the_terminal_check_for_enter() {
+        let config = ComputerUseConfig::default();
+        assert_eq!(
+            terminal_target("ls\n", Some("gnome-terminal"), &config),
+            Some("gnome-terminal".to_string())
+        );
+        assert_eq!(terminal_target("ls\n", Some("firefox"), &config), None);
+        // Without the focused window, text that submits itself must be
+        // checked rather than typed unseen.
+        assert_eq!(
+            terminal_target("rm -rf ~\n", None, &config),
+            Some("an unidentified window".to_string())
+        );
+        assert_eq!(
+            terminal_target("rm -rf ~\r", None, &config),
+            Some("an unidentified window".to_string())
+        );
+        assert_eq!(terminal_target("rm -rf ~", None, &config), None);
+    }
+
+    #[test]
+    fn screenshot_budget_note_warns_on_last_screenshot() {
+        assert_eq!(
+            screenshot_budget_note(5, 3),
+            "\nscreenshot budget: 3 of 5 left this turn"
+        );
+        assert!(screenshot_budget_note(5, 0).contains("this was the last of 5 screenshot(s)"));
+    }
+}
@@ -0,0 +1,194 @@
+//! `computer_with_modifiers`: pointer actions performed while modifier keys
+//! are held, such as a Shift marquee selection or a Ctrl-drag copy.
+//!
+//! The modifiers are pressed, the actions run, and the modifiers are released
+//! in a single input sequence. When that sequence fails partway, the handler
+//! sends [`release_args`] separately so no key or button stays stuck down.
+
+use super::coordinates::MappedPoint;
+use super::keys;
+use super::keys::Chord;
+use crate::config::types::TargetOs;
+
+/// Modifier keys `computer_with_modifiers` may hold, as xdotool names.
+const MODIFIERS: &[&str] = &["ctrl", "shift", "alt", "super"];
+
+/// Most pointer actions one call may perform while modifiers are held.
+pub(super) const MAX_HELD_ACTIONS: usize = 20;
+
+/// A pointer action resolved to screen pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum HeldStep {
+    Move(MappedPoint),
+    Click {
+        at: MappedPoint,
+        button: &'static str,
+        double: bool,
+    },
+    Drag {
+        from: MappedPoint,
+        to: MappedPoint,
+        button: &'static str,
+    },
+}
+
+/// Resolves `names` to distinct xdotool modifier names for `os`, so `cmd`
+/// holds Ctrl or Super as it does in `computer_key`.
+pub(super) fn modifier_keys(names: &[String], os: TargetOs) -> Result<Vec<String>, String> {
+    if names.is_empty() {
+        return Err("computer_with_modifiers needs at least one modifier".to_string());
+    }
+    let canonical = names
+        .iter()
+        .map(|name| keys::canonical_key(name.trim()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut modifiers: Vec<String> = Vec::new();
+    for key in Chord::Keys(canonical).keys_for(os) {
+        if !MODIFIERS.contains(&key.as_str()) {
+            return Err(format!(
+                "`{key}` is not a modifier; hold ctrl, shift, alt, super, or cmd and use computer_key for other keys"
+            ));
+        }
+        if !modifiers.contains(&key) {
+            modifiers.push(key);
+        }
+    }
+    Ok(modifiers)
+}
+
+/// xdotool arguments that press `modifiers`, perform `steps`, and release the
+/// modifiers again.
+pub(super) fn held_args(modifiers: &[String], steps: &[HeldStep]) -> Vec<String> {
+    let chord = modifiers.join("+");
+    let mut args = vec!["keydown".to_string(), chord.clone()];
+    for step in steps {
+        match *step {
+            HeldStep::Move(point) => args.extend(move_to(point)),
+            HeldStep::Click { at, button, double } => {
+                args.extend(move_to(at));
+                args.push("click".to_string());
+                if double {
+                    args.extend(["--repeat".to_string(), "2".to_string()]);
+                }
+                args.push(button.to_string());
+            }
+            HeldStep::Drag { from, to, button } => {
+                args.extend(move_to(from));
+                args.extend(["mousedown".to_string(), button.to_string()]);
+                args.extend(move_to(to));
+                args.extend(["mouseup".to_string(), button.to_string()]);
+            }
+        }
+    }
+    args.extend(["keyup".to_string(), chord]);
+    args
+}
+
+/// xdotool arguments that release every button `steps` may have pressed and
+/// then `modifiers`, for cleanup after a failed [`held_args`] sequence.
+pub(super) fn release_args(modifiers: &[String], steps: &[HeldStep]) -> Vec<String> {
+    let mut buttons: Vec<&str> = Vec::new();
+    for step in steps {
+        if let HeldStep::Drag { button, .. } = step
+            && !buttons.contains(button)
+        {
+            buttons.push(button);
+        }
+    }
+    let mut args = Vec::new();
+    for button in buttons {
+        args.extend(["mouseup".to_string(), button.to_string()]);
+    }
+    args.extend(["keyup".to_string(), modifiers.join("+")]);
+    args
+}
+
+fn move_to(point: MappedPoint) -> [String; 4] {
+    [
+        "mousemove".to_string(),
+        "--sync".to_string(),
+        point.screen_x.to_string(),
+        point.screen_y.to_string(),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn point(x: i64, y: i64) -> MappedPoint {
+        MappedPoint {
+            model_x: x as f64,
+            model_y: y as f64,
+            screen_x: x,
+            screen_y: y,
+        }
+    }
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| (*arg).to_string()).collect()
+    }
+
+    #[test]
+    fn modifiers_are_canonical_and_deduplicated() {
+        assert_eq!(
+            modifier_keys(&strings(&["Shift", "cmd", "Control"]), TargetOs::Linux),
+            Ok(strings(&["shift", "ctrl"]))
+        );
+        assert_eq!(
+            modifier_keys(&strings(&["⌘"]), TargetOs::Macos),
+            Ok(strings(&["super"]))
+        );
+        assert!(modifier_keys(&strings(&["a"]), TargetOs::Linux).is_err());
+        assert!(modifier_keys(&[], TargetOs::Linux).is_err());
+    }
+
+    #[test]
+    fn held_sequence_releases_modifiers_and_buttons() {
+        let modifiers = strings(&["ctrl", "shift"]);
+        let steps = [
+            HeldStep::Click {
+                at: point(10, 20),
+                button: "1",
+                double: false,
+            },
+            HeldStep::Drag {
+                from: point(30, 40),
+                to: point(50, 60),
+                button: "1",
+            },
+        ];
+        assert_eq!(
+            held_args(&modifiers, &steps),
+            strings(&[
+                "keydown",
+                "ctrl+shift",
+                "mousemove",
+                "--sync",
+                "10",
+                "20",
+                "click",
+                "1",
+                "mousemove",
+                "--sync",
+                "30",
+                "40",
+                "mousedown",
+                "1",
+                "mousemove",
+                "--sync",
+                "50",
+                "60",
+                "mouseup",
+                "1",
+                "keyup",
+                "ctrl+shift",
+            ])
+        );
+        assert_eq!(
+            release_args(&modifiers, &steps),
+            strings(&["mouseup", "1", "keyup", "ctrl+shift"])
+        );
+    }
+}
@@ -0,0 +1,293 @@
+//! Network state for `computer_network_state`.
+//!
+//! After toggling Wi-Fi or connecting a VPN in a settings dialog, the panel
+//! icon is the only on-screen sign of the outcome, and it is too small to
+//! read reliably. NetworkManager publishes the same state on the system bus:
+//! overall connectivity, the active connections with their devices, and for
+//! Wi-Fi the access point in use. D-Bus calls can block while
+//! NetworkManager is busy reconnecting, so the lookup runs on its own thread
+//! under a deadline.
+
+use std::sync::mpsc;
+use std::time::Duration;
+
+use zbus::blocking::Connection;
+use zbus::zvariant::OwnedObjectPath;
+use zbus::zvariant::OwnedValue;
+
+const NM: &str = "org.freedesktop.NetworkManager";
+const NM_PATH: &str = "/org/freedesktop/NetworkManager";
+const ACTIVE: &str = "org.freedesktop.NetworkManager.Connection.Active";
+const DEVICE: &str = "org.freedesktop.NetworkManager.Device";
+const WIRELESS: &str = "org.freedesktop.NetworkManager.Device.Wireless";
+const ACCESS_POINT: &str = "org.freedesktop.NetworkManager.AccessPoint";
+const PROPERTIES: &str = "org.freedesktop.DBus.Properties";
+
+/// `NMConnectivityState` values.
+const CONNECTIVITY_NONE: u32 = 1;
+const CONNECTIVITY_PORTAL: u32 = 2;
+const CONNECTIVITY_LIMITED: u32 = 3;
+const CONNECTIVITY_FULL: u32 = 4;
+
+/// `NMActiveConnectionState` values.
+const ACTIVATING: u32 = 1;
+const ACTIVATED: u32 = 2;
+const DEACTIVATING: u32 = 3;
+
+/// How long the whole lookup may take.
+const DEADLINE: Duration = Duration::from_secs(4);
+
+/// What NetworkManager reports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct NetworkState {
+    /// `NMConnectivityState`.
+    pub connectivity: u32,
+    pub networking_enabled: bool,
+    pub wireless_enabled: bool,
+    pub connections: Vec<ActiveConnection>,
+}
+
+/// An active, activating, or deactivating connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct ActiveConnection {
+    /// The connection's name, e.g. `Wired connection 1`.
+    pub name: String,
+    /// NetworkManager's type, e.g. `802-11-wireless` or `vpn`.
+    pub kind: String,
+    /// `NMActiveConnectionState`.
+    pub state: u32,
+    pub vpn: bool,
+    /// Whether it carries the default route.
+    pub default: bool,
+    pub interface: Option<String>,
+    /// Network name and signal strength in percent, for Wi-Fi.
+    pub wifi: Option<(String, u8)>,
+}
+
+/// The current state, or why NetworkManager couldn't be asked.
+pub(super) fn read() -> Result<NetworkState, String> {
+    let (sender, receiver) = mpsc::channel();
+    let spawned = std::thread::Builder::new()
+        .name("computex-network".to_string())
+        .spawn(move || {
+            let result = Connection::system()
+                .map_err(|err| format!("the system bus is unavailable: {err}"))
+                .and_then(|bus| state(&bus));
+            let _ = sender.send(result);
+        });
+    if let Err(err) = spawned {
+        return Err(format!("failed to start the network lookup: {err}"));
+    }
+    match receiver.recv_timeout(DEADLINE) {
+        Ok(result) => result,
+        Err(_) => Err(format!(
+            "NetworkManager did not answer within {} s",
+            DEADLINE.as_secs()
+        )),
+    }
+}
+
+fn state(bus: &Connection) -> Result<NetworkState, String> {
+    let connectivity = property(bus, NM_PATH, NM, "Connectivity")
+        .and_then(|value| u32::try_from(value).map_err(zbus::Error::from))
+        .map_err(|err| format!("NetworkManager is not running on the system bus ({err})"))?;
+    let flag = |name: &str| {
+        property(bus, NM_PATH, NM, name)
+            .ok()
+            .and_then(|value| bool::try_from(value).ok())
+            .unwrap_or(false)
+    };
+    let paths = property(bus, NM_PATH, NM, "ActiveConnections")
+        .ok()
+        .and_then(|value| Vec::<OwnedObjectPath>::try_from(value).ok())
+        .unwrap_or_default();
+    // A connection can go away while it is read; leave it out.
+    let connections = paths
+        .iter()
+        .filter_map(|path| connection(bus, path.as_str()).ok())
+        .collect();
+    Ok(NetworkState {
+        connectivity,
+        networking_enabled: flag("NetworkingEnabled"),
+        wireless_enabled: flag("WirelessEnabled"),
+        connections,
+    })
+}
+
+fn connection(bus: &Connection, path: &str) -> zbus::Result<ActiveConnection> {
+    let text = |name: &str| -> zbus::Result<String> {
+        Ok(String::try_from(property(bus, path, ACTIVE, name)?)?)
+    };
+    let flag = |name: &str| {
+        property(bus, path, ACTIVE, name)
+            .ok()
+            .and_then(|value| bool::try_from(value).ok())
+            .unwrap_or(false)
+    };
+    let device = property(bus, path, ACTIVE, "Devices")
+        .ok()
+        .and_then(|value| Vec::<OwnedObjectPath>::try_from(value).ok())
+        .and_then(|devices| devices.into_iter().next());
+    let interface = device.as_ref().and_then(|device| {
+        property(bus, device.as_str(), DEVICE, "Interface")
+            .ok()
+            .and_then(|value| String::try_from(value).ok())
+    });
+    let kind = text("Type")?;
+    let wifi = match (&device, kind.as_str()) {
+        (Some(device), "802-11-wireless") => access_point(bus, device.as_str()),
+        _ => None,
+    };
+    Ok(ActiveConnection {
+        name: text("Id")?,
+        kind,
+        state: u32::try_from(property(bus, path, ACTIVE, "State")?)?,
+        vpn: flag("Vpn"),
+        default: flag("Default") || flag("Default6"),
+        interface,
+        wifi,
+    })
+}
+
+/// The network name and signal strength of the access point `device` uses.
+fn access_point(bus: &Connection, device: &str) -> Option<(String, u8)> {
+    let point =
+        OwnedObjectPath::try_from(property(bus, device, WIRELESS, "ActiveAccessPoint").ok()?)
+            .ok()?;
+    if point.as_str() == "/" {
+        return None;
+    }
+    let ssid =
+        Vec::<u8>::try_from(property(bus, point.as_str(), ACCESS_POINT, "Ssid").ok()?).ok()?;
+    let strength = property(bus, point.as_str(), ACCESS_POINT, "Strength")
+        .ok()
+        .and_then(|value| u8::try_from(value).ok())
+        .unwrap_or(0);
+    Some((String::from_utf8_lossy(&ssid).into_owned(), strength))
+}
+
+fn property(bus: &Connection, path: &str, interface: &str, name: &str) -> zbus::Result<OwnedValue> {
+    bus.call_method(Some(NM), path, Some(PROPERTIES), "Get", &(interface, name))?
+        .body()
+        .deserialize()
+}
+
+/// Lines describing `state`, for the model.
+pub(super) fn describe(state: &NetworkState) -> Vec<String> {
+    let connectivity = match state.connectivity {
+        CONNECTIVITY_NONE => "no connectivity",
+        CONNECTIVITY_PORTAL => "behind a captive portal (a login page must be completed first)",
+        CONNECTIVITY_LIMITED => {
+            "limited connectivity (connected, but the internet isn't reachable)"
+        }
+        CONNECTIVITY_FULL => "full internet connectivity",
+        _ => "connectivity unknown (NetworkManager's connectivity check may be off)",
+    };
+    let mut lines = vec![format!("network: {connectivity}")];
+    if !state.networking_enabled {
+        lines.push("networking is disabled".to_string());
+    }
+    lines.push(format!(
+        "wifi radio: {}",
+        if state.wireless_enabled { "on" } else { "off" }
+    ));
+    for connection in &state.connections {
+        let kind = match connection.kind.as_str() {
+            _ if connection.vpn => "vpn",
+            "802-11-wireless" => "wifi",
+            "802-3-ethernet" => "ethernet",
+            "wireguard" => "wireguard",
+            "gsm" | "cdma" => "mobile",
+            "bridge" | "loopback" | "tun" => continue,
+            other => other,
+        };
+        let mut line = format!("{kind}: \"{}\"", connection.name);
+        if let Some((ssid, strength)) = &connection.wifi {
+            line.push_str(&format!(", network \"{ssid}\", signal {strength}%"));
+        }
+        if let Some(interface) = &connection.interface {
+            line.push_str(&format!(" on {interface}"));
+        }
+        line.push_str(match connection.state {
+            ACTIVATED => ", connected",
+            ACTIVATING => ", connecting",
+            DEACTIVATING => ", disconnecting",
+            _ => ", disconnected",
+        });
+        if connection.default {
+            line.push_str(", default route");
+        }
+        lines.push(line);
+    }
+    if !state
+        .connections
+        .iter()
+        .any(|connection| connection.vpn || connection.kind == "wireguard")
+    {
+        lines.push("no VPN is active".to_string());
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn describes_connectivity_wifi_and_vpn() {
+        let wifi = ActiveConnection {
+            name: "Home".to_string(),
+            kind: "802-11-wireless".to_string(),
+            state: ACTIVATED,
+            vpn: false,
+            default: true,
+            interface: Some("wlp2s0".to_string()),
+            wifi: Some(("HomeNet 5G".to_string(), 78)),
+        };
+        let vpn = ActiveConnection {
+            name: "Work".to_string(),
+            kind: "vpn".to_string(),
+            state: ACTIVATING,
+            vpn: true,
+            default: false,
+            interface: None,
+            wifi: None,
+        };
+        let loopback = ActiveConnection {
+            name: "lo".to_string(),
+            kind: "loopback".to_string(),
+            state: ACTIVATED,
+            vpn: false,
+            default: false,
+            interface: Some("lo".to_string()),
+            wifi: None,
+        };
+        let state = NetworkState {
+            connectivity: CONNECTIVITY_FULL,
+            networking_enabled: true,
+            wireless_enabled: true,
+            connections: vec![wifi.clone(), vpn, loopback],
+        };
+        assert_eq!(
+            describe(&state),
+            vec![
+                "network: full internet connectivity",
+                "wifi radio: on",
+                "wifi: \"Home\", network \"HomeNet 5G\", signal 78% on wlp2s0, connected, default route",
+                "vpn: \"Work\", connecting",
+            ]
+        );
+
+        let offline = NetworkState {
+            connectivity: CONNECTIVITY_PORTAL,
+            networking_enabled: true,
+            wireless_enabled: false,
+            connections: vec![wifi],
+        };
+        let lines = describe(&offline);
+        assert!(lines[0].starts_with("network: behind a captive portal"));
+        assert_eq!(lines[1], "wifi radio: off");
+        assert_eq!(lines.last().map(String::as_str), Some("no VPN is active"));
+    }
+}
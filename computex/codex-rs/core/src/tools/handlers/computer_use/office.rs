@@ -0,0 +1,317 @@
+//! Document operations through LibreOffice's UNO API, for `computer_office`.
+//!
+//! Opening a file, jumping to a spreadsheet cell, and exporting a PDF take a
+//! dozen fragile clicks through dialogs, but one call each over UNO. A small
+//! Python script, run with an interpreter that has the `uno` bindings,
+//! connects to the user's own LibreOffice on a named pipe, so the result shows
+//! on screen and the GUI tools can carry on from there. A running LibreOffice
+//! that doesn't listen yet is asked to with `soffice --accept`, which it
+//! forwards to the existing instance instead of starting a second one.
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+use std::time::Instant;
+
+use serde::Deserialize;
+use serde_json::json;
+use which::which;
+
+use super::print_dialog::target_path;
+use super::schema::OfficeAction;
+use super::schema::OfficeArgs;
+use crate::config::types::ComputerUseConfig;
+
+const SCRIPT: &str = include_str!("../../../../templates/office/uno_bridge.py");
+
+/// Connection string the script resolves; must match `PIPE` in the script.
+const ACCEPT: &str = "--accept=pipe,name=computex;urp;";
+
+/// Window classes of LibreOffice, checked against the app rules.
+const APP_CLASSES: &[&str] = &["libreoffice", "soffice"];
+
+/// Extensions of the documents `open` accepts, so the tool doesn't hand
+/// LibreOffice arbitrary files to import.
+const DOCUMENT_EXTENSIONS: &[&str] = &[
+    "odt", "ods", "odp", "odg", "fodt", "fods", "fodp", "doc", "docx", "xls", "xlsx", "xlsm",
+    "ppt", "pptx", "rtf", "txt", "csv", "tsv",
+];
+
+/// How long a freshly started LibreOffice may take to accept connections.
+const START_TIMEOUT: Duration = Duration::from_secs(30);
+const START_POLL: Duration = Duration::from_millis(500);
+
+/// How long one request may take. A modal dialog in LibreOffice blocks UNO
+/// calls, so a hung request is reported instead of waited on.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The script's answer.
+#[derive(Debug, Deserialize)]
+struct Reply {
+    ok: bool,
+    result: Option<String>,
+    error: Option<String>,
+    #[serde(default)]
+    unreachable: bool,
+    #[serde(default)]
+    missing_uno: bool,
+}
+
+/// Carries out `args` and describes the result.
+pub(super) async fn run(config: &ComputerUseConfig, args: &OfficeArgs) -> Result<String, String> {
+    if let Some(target) = &config.target {
+        return Err(format!(
+            "computer_office drives LibreOffice on this machine, but the GUI tools drive {}; use them instead",
+            target.destination()
+        ));
+    }
+    let request = request(args)?;
+    let soffice = soffice();
+    let pythons = interpreters(soffice.as_deref());
+    if pythons.is_empty() {
+        return Err(fallback("python3 is not installed"));
+    }
+
+    let mut started: Option<Instant> = None;
+    loop {
+        let mut missing = None;
+        let mut unreachable = false;
+        for python in &pythons {
+            let reply = call(python, config, &request).await?;
+            if reply.missing_uno {
+                missing = reply.error;
+                continue;
+            }
+            if reply.unreachable {
+                unreachable = true;
+                break;
+            }
+            return match (reply.ok, reply.result, reply.error) {
+                (true, Some(result), _) => Ok(result),
+                (_, _, Some(error)) => Err(format!("LibreOffice: {error}")),
+                _ => Err("LibreOffice gave no answer".to_string()),
+            };
+        }
+        if !unreachable {
+            return Err(fallback(&format!(
+                "{}; install it with `sudo apt-get install -y python3-uno`",
+                missing.unwrap_or_else(|| "no Python has the UNO bindings".to_string())
+            )));
+        }
+        match started {
+            None => {
+                let Some(soffice) = &soffice else {
+                    return Err(fallback("LibreOffice (`soffice`) is not installed"));
+                };
+                start(soffice, config)?;
+                started = Some(Instant::now());
+            }
+            Some(at) if at.elapsed() >= START_TIMEOUT => {
+                return Err(fallback(&format!(
+                    "LibreOffice did not accept UNO connections within {} s",
+                    START_TIMEOUT.as_secs()
+                )));
+            }
+            Some(_) => {}
+        }
+        tokio::time::sleep(START_POLL).await;
+    }
+}
+
+/// Applies the app rules to LibreOffice, which the tool drives whatever
+/// window has focus. Rules name its window classes, such as
+/// `libreoffice-calc`.
+pub(super) fn permitted(config: &ComputerUseConfig) -> Result<(), String> {
+    let is_office = |app: &String| {
+        let app = app.trim().to_ascii_lowercase();
+        APP_CLASSES.iter().any(|class| app.starts_with(class))
+    };
+    if config.blocked_apps.iter().any(is_office) {
+        return Err(
+            "LibreOffice is in computer_use.blocked_apps; driving it is not permitted".to_string(),
+        );
+    }
+    if !config.allowed_apps.is_empty() && !config.allowed_apps.iter().any(is_office) {
+        return Err(format!(
+            "LibreOffice is not in computer_use.allowed_apps ({})",
+            config.allowed_apps.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// The JSON request for the script, with paths checked up front so the
+/// model gets the same errors as from the GUI tools.
+fn request(args: &OfficeArgs) -> Result<serde_json::Value, String> {
+    match args.action {
+        OfficeAction::Status => Ok(json!({ "action": "status" })),
+        OfficeAction::Open => {
+            let path = args.path.as_deref().ok_or("open needs a path")?;
+            let path = document_path(path)?;
+            Ok(json!({ "action": "open", "path": path }))
+        }
+        OfficeAction::GotoCell => {
+            let cell = args
+                .cell
+                .as_deref()
+                .map(str::trim)
+                .filter(|cell| !cell.is_empty())
+                .ok_or("goto_cell needs a cell, e.g. `B7` or `Sheet2.A1:C3`")?;
+            Ok(json!({ "action": "goto_cell", "cell": cell }))
+        }
+        OfficeAction::ExportPdf => {
+            let path = args.path.as_deref().ok_or("export_pdf needs a path")?;
+            let path = target_path(path)?;
+            Ok(json!({ "action": "export_pdf", "path": path }))
+        }
+    }
+}
+
+/// Checks that `path` is an existing document LibreOffice opens.
+fn document_path(path: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(path.trim());
+    if !path.is_absolute() {
+        return Err(format!("path must be absolute, got `{}`", path.display()));
+    }
+    if !path.is_file() {
+        return Err(format!("`{}` does not exist", path.display()));
+    }
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+    if !DOCUMENT_EXTENSIONS.contains(&extension.as_str()) {
+        return Err(format!(
+            "`{}` is not an office document ({})",
+            path.display(),
+            DOCUMENT_EXTENSIONS.join(", ")
+        ));
+    }
+    Ok(path)
+}
+
+/// The LibreOffice launcher, resolved through symlinks so its `program`
+/// directory can be found.
+fn soffice() -> Option<PathBuf> {
+    ["soffice", "libreoffice"]
+        .into_iter()
+        .find_map(|name| which(name).ok())
+        .map(|path| std::fs::canonicalize(&path).unwrap_or(path))
+}
+
+/// Interpreters to try: the Python bundled with LibreOffice's own packages,
+/// which always has `uno`, then the system one, which has it with the
+/// distribution's `python3-uno` package.
+fn interpreters(soffice: Option<&Path>) -> Vec<PathBuf> {
+    let bundled = soffice
+        .and_then(Path::parent)
+        .filter(|dir| dir.ends_with("program"))
+        .map(|program| program.join("python"))
+        .filter(|python| python.is_file());
+    bundled.into_iter().chain(which("python3").ok()).collect()
+}
+
+async fn call(
+    python: &Path,
+    config: &ComputerUseConfig,
+    request: &serde_json::Value,
+) -> Result<Reply, String> {
+    let mut command = tokio::process::Command::new(python);
+    if let Some(display) = &config.display {
+        command.env("DISPLAY", display);
+    }
+    command
+        .arg("-c")
+        .arg(SCRIPT)
+        .arg(request.to_string())
+        .stdin(Stdio::null())
+        .kill_on_drop(true);
+    let output = tokio::time::timeout(REQUEST_TIMEOUT, command.output())
+        .await
+        .map_err(|_| {
+            format!(
+                "LibreOffice did not answer within {} s; a dialog may be open in it, so take a screenshot",
+                REQUEST_TIMEOUT.as_secs()
+            )
+        })?
+        .map_err(|err| format!("failed to run {}: {err}", python.display()))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    serde_json::from_str(stdout.trim()).map_err(|_| {
+        format!(
+            "the UNO script failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+    })
+}
+
+/// Asks LibreOffice to accept connections, starting it if it isn't running.
+fn start(soffice: &Path, config: &ComputerUseConfig) -> Result<(), String> {
+    let mut command = std::process::Command::new(soffice);
+    if let Some(display) = &config.display {
+        command.env("DISPLAY", display);
+    }
+    let mut child = command
+        .args([ACCEPT, "--norestore", "--nologo"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|err| fallback(&format!("failed to start {}: {err}", soffice.display())))?;
+    // A new instance runs for the rest of the session; reap it when it exits.
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+fn fallback(reason: &str) -> String {
+    format!(
+        "{reason}. The UNO integration is unavailable, so use the GUI tools instead (computer_menu_select, computer_file_dialog, computer_print_dialog_handle)"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn args(action: OfficeAction, path: Option<&str>, cell: Option<&str>) -> OfficeArgs {
+        OfficeArgs {
+            action,
+            path: path.map(str::to_string),
+            cell: cell.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn builds_requests_and_checks_paths() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let sheet = dir.path().join("budget.xlsx");
+        std::fs::write(&sheet, b"").expect("write");
+        let script = dir.path().join("run.sh");
+        std::fs::write(&script, b"").expect("write");
+
+        assert_eq!(
+            request(&args(OfficeAction::Open, sheet.to_str(), None)),
+            Ok(json!({ "action": "open", "path": sheet }))
+        );
+        assert!(request(&args(OfficeAction::Open, script.to_str(), None)).is_err());
+        assert!(request(&args(OfficeAction::Open, Some("budget.xlsx"), None)).is_err());
+        assert_eq!(
+            request(&args(OfficeAction::GotoCell, None, Some(" Sheet2.B7 "))),
+            Ok(json!({ "action": "goto_cell", "cell": "Sheet2.B7" }))
+        );
+        assert!(request(&args(OfficeAction::GotoCell, None, Some(" "))).is_err());
+
+        let pdf = dir.path().join("budget.pdf");
+        assert_eq!(
+            request(&args(OfficeAction::ExportPdf, pdf.to_str(), None)),
+            Ok(json!({ "action": "export_pdf", "path": pdf }))
+        );
+        assert!(request(&args(OfficeAction::ExportPdf, sheet.to_str(), None)).is_err());
+        assert_eq!(
+            request(&args(OfficeAction::Status, None, None)),
+            Ok(json!({ "action": "status" }))
+        );
+    }
+}
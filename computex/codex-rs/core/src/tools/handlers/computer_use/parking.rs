@@ -0,0 +1,112 @@
+//! Moving the pointer out of the way, for `computer_use.park_cursor`.
+//!
+//! A pointer left where the last click landed covers part of what it clicked
+//! and keeps tooltips, hover highlights, and open submenus showing in the
+//! next capture, which the model then reads as the state of the app. Parking
+//! the pointer in a corner of the screen, or of the viewport, after each
+//! pointer action or right before a screenshot keeps captures free of both.
+
+use std::time::Duration;
+
+use super::display_geometry;
+use super::input_command;
+use super::input_helper;
+use super::pause;
+use crate::codex::Session;
+use crate::config::types::ComputerUseConfig;
+use crate::config::types::ScreenCorner;
+use crate::config::types::Viewport;
+use crate::function_tool::FunctionCallError;
+
+/// Tools after which the pointer is parked with `after_action`.
+pub(super) const PARKED_TOOLS: &[&str] = &[
+    "computer_click",
+    "computer_drag",
+    "computer_scroll",
+    "computer_long_press",
+    "computer_swipe",
+    "computer_pinch",
+];
+
+/// How long hover effects get to fade after the pointer leaves them.
+pub(super) const HOVER_CLEAR: Duration = Duration::from_millis(100);
+
+/// Distance from the very corner, which desktops often treat as a hot corner.
+const INSET: u32 = 2;
+
+/// Moves the pointer into the configured corner. Does nothing while the
+/// user has paused the agent.
+pub(super) async fn park(
+    session: &Session,
+    config: &ComputerUseConfig,
+) -> Result<(), FunctionCallError> {
+    if pause::is_paused() {
+        return Ok(());
+    }
+    let xdotool = input_command(config)?;
+    let (width, height) = display_geometry(&xdotool, config)?;
+    let area = config.viewport.unwrap_or(Viewport {
+        width: width as u32,
+        height: height as u32,
+        x: 0,
+        y: 0,
+    });
+    let (x, y) = corner(area, config.park_cursor_corner);
+    let helper = input_helper(session, config).await;
+    input_helper::send_input(
+        helper.as_ref(),
+        &xdotool,
+        &["mousemove".to_string(), x.to_string(), y.to_string()],
+        config,
+    )
+}
+
+/// The point `INSET` pixels in from `corner` of `area`, in screen pixels.
+fn corner(area: Viewport, corner: ScreenCorner) -> (u32, u32) {
+    let left = area.x + INSET.min(area.width.saturating_sub(1));
+    let top = area.y + INSET.min(area.height.saturating_sub(1));
+    let right = area.x + area.width.saturating_sub(1 + INSET);
+    let bottom = area.y + area.height.saturating_sub(1 + INSET);
+    match corner {
+        ScreenCorner::TopLeft => (left, top),
+        ScreenCorner::TopRight => (right.max(left), top),
+        ScreenCorner::BottomLeft => (left, bottom.max(top)),
+        ScreenCorner::BottomRight => (right.max(left), bottom.max(top)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn corners_sit_just_inside_the_area() {
+        let screen = Viewport {
+            width: 1920,
+            height: 1080,
+            x: 0,
+            y: 0,
+        };
+        assert_eq!(corner(screen, ScreenCorner::TopLeft), (2, 2));
+        assert_eq!(corner(screen, ScreenCorner::TopRight), (1917, 2));
+        assert_eq!(corner(screen, ScreenCorner::BottomLeft), (2, 1077));
+        assert_eq!(corner(screen, ScreenCorner::BottomRight), (1917, 1077));
+
+        let window = Viewport {
+            width: 800,
+            height: 600,
+            x: 1920,
+            y: 100,
+        };
+        assert_eq!(corner(window, ScreenCorner::BottomRight), (2717, 697));
+
+        let tiny = Viewport {
+            width: 2,
+            height: 1,
+            x: 10,
+            y: 10,
+        };
+        assert_eq!(corner(tiny, ScreenCorner::BottomRight), (11, 10));
+    }
+}
@@ -0,0 +1,97 @@
+//! Password-field detection for `computer_type`.
+//!
+//! Text passed to `computer_type` lands in the transcript, the rollout, and
+//! exported scripts, so typing a password by accident leaks it for good.
+//! Before typing, the focused widget's role is read from the accessibility
+//! tree, where GTK, Qt, and browsers mark masked entries as `password text`.
+//! Apps that don't publish their widgets get a weaker check instead: known
+//! credential prompts by window class, and windows whose title asks for a
+//! password or passphrase.
+
+use std::path::Path;
+
+use super::accessibility;
+use super::recovery::active_window;
+use super::recovery::xdotool_output;
+use crate::config::types::ComputerUseConfig;
+
+/// AT-SPI role name of masked text entries.
+const PASSWORD_ROLE: &str = "password text";
+
+/// Window classes, lowercase, of dialogs that only ever ask for secrets.
+const SECRET_PROMPT_CLASSES: &[&str] = &[
+    "pinentry",
+    "gcr-prompter",
+    "polkit-gnome-authentication-agent-1",
+    "polkit-kde-authentication-agent-1",
+    "lxpolkit",
+    "ssh-askpass",
+    "ksshaskpass",
+    "kwalletd5",
+    "kwalletd6",
+];
+
+/// Title words, lowercase, of windows asking for a secret.
+const SECRET_TITLE_WORDS: &[&str] = &[
+    "password",
+    "passphrase",
+    "passcode",
+    "pin code",
+    "authenticate",
+    "authentication required",
+    "unlock keyring",
+];
+
+/// Why the focused widget looks like a password field, or `None` when it
+/// doesn't.
+pub(super) fn detect(xdotool: &Path, config: &ComputerUseConfig) -> Option<String> {
+    // A role from the accessibility tree is exact, so it overrides the
+    // window heuristics either way.
+    match accessibility::focused_role() {
+        Ok(Some(role)) if role == PASSWORD_ROLE => {
+            return Some("the focused field is a password field".to_string());
+        }
+        Ok(Some(_)) => return None,
+        Ok(None) => {}
+        Err(err) => tracing::debug!("no focused role from the accessibility tree: {err}"),
+    }
+    let window = active_window(xdotool, config).ok()?;
+    let title = xdotool_output(xdotool, config, &["getwindowname", &window.id]).unwrap_or_default();
+    looks_secret(&window.class, &title)
+}
+
+/// Window heuristics for apps without accessibility support.
+fn looks_secret(class: &str, title: &str) -> Option<String> {
+    let class = class.trim().to_lowercase();
+    if SECRET_PROMPT_CLASSES
+        .iter()
+        .any(|prompt| class.starts_with(prompt))
+    {
+        return Some(format!("the focused window `{class}` asks for credentials"));
+    }
+    let lower = title.to_lowercase();
+    SECRET_TITLE_WORDS
+        .iter()
+        .find(|word| lower.contains(*word))
+        .map(|word| format!("the focused window's title mentions \"{word}\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn recognizes_credential_prompts() {
+        assert_eq!(
+            looks_secret("Pinentry-gtk-2", ""),
+            Some("the focused window `pinentry-gtk-2` asks for credentials".to_string())
+        );
+        assert_eq!(
+            looks_secret("firefox", "Enter Master Password - Mozilla Firefox"),
+            Some("the focused window's title mentions \"password\"".to_string())
+        );
+        assert_eq!(looks_secret("gedit", "notes.txt - gedit"), None);
+        assert_eq!(looks_secret("", ""), None);
+    }
+}
@@ -0,0 +1,34 @@
+//! Process-wide pause switch for GUI input.
+//!
+//! A supervisor watching the live preview can pause the agent. While paused,
+//! GUI tools that send input wait before acting; observation tools such as
+//! `computer_screenshot` keep working. Interrupting the turn still cancels a
+//! waiting tool.
+
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+/// How often a waiting tool checks whether input was resumed.
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+static PAUSED: AtomicBool = AtomicBool::new(false);
+
+pub fn set_paused(paused: bool) {
+    PAUSED.store(paused, Ordering::SeqCst);
+}
+
+pub fn is_paused() -> bool {
+    PAUSED.load(Ordering::SeqCst)
+}
+
+/// Waits until input is resumed. Returns whether it had to wait.
+pub(super) async fn wait_while_paused() -> bool {
+    if !is_paused() {
+        return false;
+    }
+    while is_paused() {
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+    true
+}
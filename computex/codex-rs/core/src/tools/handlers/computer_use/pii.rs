@@ -0,0 +1,205 @@
+//! Scrubbing of OCR text before it reaches the model, for
+//! `computer_use.ocr_scrubbers`.
+//!
+//! OCR reads whatever is on screen, including a notification with someone's
+//! phone number or a card number in a half-visible form. Text the GUI tools
+//! read with tesseract goes through the configured scrubbers, which replace
+//! each match with a placeholder like `[email]`. Only the reported text is
+//! changed: matching and positions still use what was read, so a scrubbed
+//! match can be clicked as usual.
+
+use crate::config::types::OcrScrubber;
+
+/// Fewest digits in a number reported as a phone number, unless it starts
+/// with `+`; shorter runs are usually dates, times, or amounts.
+const MIN_PHONE_DIGITS: usize = 10;
+const MIN_INTERNATIONAL_PHONE_DIGITS: usize = 8;
+const MAX_PHONE_DIGITS: usize = 15;
+
+/// Digit counts of payment card numbers.
+const CARD_DIGITS: std::ops::RangeInclusive<usize> = 13..=19;
+
+/// `text` with every match of `scrubbers` replaced by its placeholder.
+pub(super) fn scrub(text: &str, scrubbers: &[OcrScrubber]) -> String {
+    if scrubbers.is_empty() {
+        return text.to_string();
+    }
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut index = 0;
+    while index < chars.len() {
+        if let Some((end, placeholder)) = match_at(&chars, index, scrubbers) {
+            out.push_str(placeholder);
+            index = end;
+        } else {
+            out.push(chars[index]);
+            index += 1;
+        }
+    }
+    out
+}
+
+/// The end and placeholder of a match starting at `start`, if any.
+fn match_at(
+    chars: &[char],
+    start: usize,
+    scrubbers: &[OcrScrubber],
+) -> Option<(usize, &'static str)> {
+    // Matches start at a word boundary, so `a1234567890` stays whole.
+    if start > 0 && is_word_char(chars[start - 1]) {
+        return None;
+    }
+    if scrubbers.contains(&OcrScrubber::Email)
+        && let Some(end) = email_at(chars, start)
+    {
+        return Some((end, "[email]"));
+    }
+    let (end, digits, separators) = number_at(chars, start)?;
+    let international = chars[start] == '+';
+    if scrubbers.contains(&OcrScrubber::CardNumber)
+        && !international
+        && CARD_DIGITS.contains(&digits.len())
+        && separators.iter().all(|c| *c == ' ' || *c == '-')
+        && luhn_valid(&digits)
+    {
+        return Some((end, "[card number]"));
+    }
+    let min_digits = if international {
+        MIN_INTERNATIONAL_PHONE_DIGITS
+    } else {
+        MIN_PHONE_DIGITS
+    };
+    if scrubbers.contains(&OcrScrubber::Phone)
+        && (min_digits..=MAX_PHONE_DIGITS).contains(&digits.len())
+    {
+        return Some((end, "[phone]"));
+    }
+    None
+}
+
+/// The end of an email address starting at `start`.
+fn email_at(chars: &[char], start: usize) -> Option<usize> {
+    let is_local = |c: char| c.is_ascii_alphanumeric() || "._%+-".contains(c);
+    let is_domain = |c: char| c.is_ascii_alphanumeric() || c == '.' || c == '-';
+    let at = start + chars[start..].iter().take_while(|c| is_local(**c)).count();
+    if at == start || chars.get(at) != Some(&'@') {
+        return None;
+    }
+    let mut end = at
+        + 1
+        + chars[at + 1..]
+            .iter()
+            .take_while(|c| is_domain(**c))
+            .count();
+    // A sentence may end right after the address.
+    while end > at + 1 && chars[end - 1] == '.' {
+        end -= 1;
+    }
+    let domain: String = chars[at + 1..end].iter().collect();
+    let (name, tld) = domain.rsplit_once('.')?;
+    let valid = !name.is_empty() && tld.len() >= 2 && tld.chars().all(|c| c.is_ascii_alphabetic());
+    valid.then_some(end)
+}
+
+/// A run of digits starting at `start`, optionally after a `+` and with
+/// single separators between groups. Returns its end, digits, and
+/// separators.
+fn number_at(chars: &[char], start: usize) -> Option<(usize, Vec<char>, Vec<char>)> {
+    let mut index = start;
+    if chars[index] == '+' || chars[index] == '(' {
+        index += 1;
+    }
+    if !chars.get(index).is_some_and(char::is_ascii_digit) {
+        return None;
+    }
+    let mut digits = Vec::new();
+    let mut separators = Vec::new();
+    let mut end = index;
+    while index < chars.len() {
+        let c = chars[index];
+        if c.is_ascii_digit() {
+            digits.push(c);
+            index += 1;
+            end = index;
+        } else if " -.()".contains(c)
+            && chars.get(index + 1).is_some_and(|next| {
+                next.is_ascii_digit() || (c == ')' && *next == ' ') || (c == ' ' && *next == '(')
+            })
+        {
+            separators.push(c);
+            index += 1;
+        } else {
+            break;
+        }
+    }
+    if chars.get(end).is_some_and(|c| is_word_char(*c)) {
+        return None;
+    }
+    Some((end, digits, separators))
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// The Luhn checksum all payment card numbers carry.
+fn luhn_valid(digits: &[char]) -> bool {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .filter_map(|c| c.to_digit(10))
+        .enumerate()
+        .map(|(position, digit)| {
+            if position % 2 == 1 {
+                let doubled = digit * 2;
+                if doubled > 9 { doubled - 9 } else { doubled }
+            } else {
+                digit
+            }
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn scrubs_emails_cards_and_phone_numbers() {
+        let all = [
+            OcrScrubber::Email,
+            OcrScrubber::CardNumber,
+            OcrScrubber::Phone,
+        ];
+        assert_eq!(
+            scrub("Reply to jane.doe+work@example.co.uk.", &all),
+            "Reply to [email]."
+        );
+        assert_eq!(
+            scrub("Card 4111 1111 1111 1111 exp 04/27", &all),
+            "Card [card number] exp 04/27"
+        );
+        assert_eq!(
+            scrub("Call (555) 123-4567 or +44 20 7946 0958", &all),
+            "Call [phone] or [phone]"
+        );
+        assert_eq!(
+            scrub(
+                "Invoice 2025-06-01, total 1,234.50, order 4111111111111112",
+                &all
+            ),
+            "Invoice 2025-06-01, total 1,234.50, order 4111111111111112"
+        );
+        assert_eq!(
+            scrub("build a1234567890 at 10:45", &all),
+            "build a1234567890 at 10:45"
+        );
+        assert_eq!(
+            scrub("jane@example.com 555-123-4567", &[OcrScrubber::Phone]),
+            "jane@example.com [phone]"
+        );
+        assert_eq!(scrub("jane@example.com", &[]), "jane@example.com");
+    }
+}
@@ -0,0 +1,607 @@
+//! Plugin tools: extra GUI tools implemented as WebAssembly modules.
+//!
+//! Each file in `<codex_home>/computer_use/plugins` declares one plugin,
+//! named after it (`invoices.toml`), and the tools its module provides:
+//!
+//! ```toml
+//! module = "invoices.wasm"
+//!
+//! [tools.fill_invoice]
+//! description = "Fill the open invoice form from a customer and an amount."
+//! parameters = ["customer", "amount"]
+//! ```
+//!
+//! Every tool becomes a `plugin_<plugin>_<tool>` tool. Computex links no
+//! WebAssembly runtime; the module runs as a WASI command through one of the
+//! [`Runtime`]s (`wasmtime` by default), started so it grants the module no
+//! files, network or environment. Any other command is refused, since it
+//! could run the module with whatever access it likes. The module's only way
+//! out is a line-based JSON protocol on stdin and stdout. The first line it reads is the call:
+//!
+//! ```json
+//! {"tool": "fill_invoice", "arguments": {"customer": "ACME", "amount": "120"}}
+//! ```
+//!
+//! It then writes requests, one per line, and reads one answer per request:
+//!
+//! - `{"call": "computer_click", "arguments": {...}}` runs one of
+//!   [`HOST_TOOLS`], with the same checks as when the model calls it, and
+//!   answers `{"ok": true, "output": "..."}`. A tool that only reads the
+//!   screen answers `{"ok": false, "error": "..."}` when it fails; a refused
+//!   or failed action that changes something ends the call.
+//! - `{"call": "capture", "arguments": {"x": 0, "y": 0, "width": 320, "height":
+//!   240}}` captures the screen, or the given area of it, and answers
+//!   `{"ok": true, "png": "<base64>"}`.
+//! - `{"done": "..."}` ends the call with that result, and `{"fail": "..."}`
+//!   ends it as a failure.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use base64::Engine;
+use serde::Deserialize;
+use serde_json::Value;
+use serde_json::json;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::io::BufReader;
+use tokio::process::ChildStdin;
+use tokio::process::ChildStdout;
+use tokio::process::Command;
+
+use super::ComputerUseHandler;
+use super::capture_root_window;
+use super::check_side_effects;
+use super::classifier;
+use super::ensure_display;
+use super::mutates;
+use super::schema::parse_args;
+use super::sends_input;
+use crate::codex::Session;
+use crate::codex::TurnContext;
+use crate::config::types::ComputerUseConfig;
+use crate::config::types::Viewport;
+use crate::function_tool::FunctionCallError;
+use crate::tools::context::ToolOutput;
+
+/// Directory under `codex_home` that holds plugin files.
+pub const PLUGINS_DIR: &str = "computer_use/plugins";
+
+/// Prefix of every plugin tool name.
+pub(crate) const TOOL_PREFIX: &str = "plugin_";
+
+/// Longest tool name the model API accepts.
+const MAX_TOOL_NAME: usize = 64;
+
+/// GUI tools a module may call. Screenshots go through `capture`, so the
+/// module sees the pixels without them landing in the conversation.
+const HOST_TOOLS: &[&str] = &[
+    "computer_click",
+    "computer_drag",
+    "computer_scroll",
+    "computer_type",
+    "computer_key",
+    "computer_clear_field",
+    "computer_focus_next",
+    "computer_focus_prev",
+    "computer_menu_select",
+    "computer_locate",
+    "computer_find_text",
+    "computer_active_window",
+];
+
+/// Most requests one call may make.
+const MAX_REQUESTS: usize = 200;
+
+/// How long one call may take in total.
+const TIMEOUT: Duration = Duration::from_secs(120);
+
+/// One plugin and the tools its module provides.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plugin {
+    /// The file name without `.toml`, used in tool names.
+    pub name: String,
+    /// The WebAssembly module, resolved against the plugins directory.
+    pub module: PathBuf,
+    pub runtime: Runtime,
+    pub tools: Vec<PluginTool>,
+}
+
+/// A WASI runtime a module may run through.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Runtime {
+    #[default]
+    Wasmtime,
+    Wasmer,
+    Wasmedge,
+}
+
+impl Runtime {
+    /// The program and arguments that run a module, before its path. None
+    /// of them preopens a directory, opens the network, or passes the
+    /// environment unless asked to, and they are never asked to.
+    fn command(self) -> (&'static str, &'static [&'static str]) {
+        match self {
+            Runtime::Wasmtime => ("wasmtime", &["run"]),
+            Runtime::Wasmer => ("wasmer", &["run"]),
+            Runtime::Wasmedge => ("wasmedge", &[]),
+        }
+    }
+}
+
+/// A tool a plugin provides.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PluginTool {
+    pub name: String,
+    pub description: String,
+    /// String arguments the tool takes.
+    pub parameters: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PluginFile {
+    module: PathBuf,
+    #[serde(default)]
+    runtime: Runtime,
+    #[serde(default)]
+    tools: BTreeMap<String, ToolToml>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ToolToml {
+    description: String,
+    #[serde(default)]
+    parameters: Vec<String>,
+}
+
+/// The area of a `capture` request.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CaptureArea {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+/// A line the module writes.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+enum Message {
+    Call {
+        call: String,
+        #[serde(default)]
+        arguments: Value,
+    },
+    Done {
+        done: String,
+    },
+    Fail {
+        fail: String,
+    },
+}
+
+impl Plugin {
+    /// The tool that runs `tool`.
+    pub fn tool_name(&self, tool: &PluginTool) -> String {
+        format!("{TOOL_PREFIX}{}_{}", self.name, tool.name)
+    }
+}
+
+/// Reads every plugin file under `codex_home`. Files that fail to parse are
+/// logged and skipped so one typo doesn't take the other plugins' tools away.
+pub fn load(codex_home: &Path) -> Vec<Plugin> {
+    let dir = codex_home.join(PLUGINS_DIR);
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<_> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .collect();
+    paths.sort();
+    paths
+        .iter()
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_string_lossy().into_owned();
+            let parsed = std::fs::read_to_string(path)
+                .map_err(|err| err.to_string())
+                .and_then(|contents| parse(&dir, &name, &contents));
+            match parsed {
+                Ok(plugin) => Some(plugin),
+                Err(err) => {
+                    tracing::warn!("skipping plugin file {}: {err}", path.display());
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+fn parse(dir: &Path, name: &str, contents: &str) -> Result<Plugin, String> {
+    let file: PluginFile = toml::from_str(contents).map_err(|err| err.to_string())?;
+    let mut plugin = Plugin {
+        name: identifier(name),
+        module: dir.join(file.module),
+        runtime: file.runtime,
+        tools: Vec::new(),
+    };
+    for (name, tool) in file.tools {
+        let tool = PluginTool {
+            name: identifier(&name),
+            description: tool.description.trim().to_string(),
+            parameters: tool.parameters,
+        };
+        check_tool(&plugin, &tool).map_err(|err| format!("tool `{name}`: {err}"))?;
+        plugin.tools.push(tool);
+    }
+    if plugin.tools.is_empty() {
+        return Err("declares no tools".to_string());
+    }
+    Ok(plugin)
+}
+
+fn check_tool(plugin: &Plugin, tool: &PluginTool) -> Result<(), String> {
+    let name = plugin.tool_name(tool);
+    if name.len() > MAX_TOOL_NAME {
+        return Err(format!(
+            "tool name `{name}` is longer than {MAX_TOOL_NAME} characters"
+        ));
+    }
+    if let Some(parameter) = tool
+        .parameters
+        .iter()
+        .find(|parameter| identifier(parameter) != **parameter)
+    {
+        return Err(format!(
+            "parameter `{parameter}` must be lowercase letters, digits and underscores"
+        ));
+    }
+    Ok(())
+}
+
+/// `name` lowercased with everything but letters and digits turned into
+/// underscores, as tool names require.
+fn identifier(name: &str) -> String {
+    name.trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// The plugin and tool behind `tool_name`, if it is a plugin tool.
+pub(crate) fn find<'a>(
+    plugins: &'a [Plugin],
+    tool_name: &str,
+) -> Option<(&'a Plugin, &'a PluginTool)> {
+    if !tool_name.starts_with(TOOL_PREFIX) {
+        return None;
+    }
+    plugins.iter().find_map(|plugin| {
+        plugin
+            .tools
+            .iter()
+            .find(|tool| plugin.tool_name(tool) == tool_name)
+            .map(|tool| (plugin, tool))
+    })
+}
+
+/// Runs `tool` of `plugin`, answering the module's requests until it is
+/// done, fails, or runs out of time.
+pub(super) async fn run(
+    session: &Arc<Session>,
+    turn: &Arc<TurnContext>,
+    call_id: String,
+    plugin: &Plugin,
+    tool: &PluginTool,
+    arguments: &str,
+) -> Result<ToolOutput, FunctionCallError> {
+    let config = &turn.tools_config.computer_use;
+    let name = plugin.tool_name(tool);
+    if config.action_queue {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "{name} can't run while computer_use.action_queue is set; queue its steps as individual actions instead"
+        )));
+    }
+    let arguments: BTreeMap<String, String> = parse_args(arguments)?;
+    if let Some(missing) = tool
+        .parameters
+        .iter()
+        .find(|parameter| !arguments.contains_key(*parameter))
+    {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "{name} requires `{missing}`"
+        )));
+    }
+    ensure_display(config)?;
+
+    let (program, args) = plugin.runtime.command();
+    let mut child = Command::new(program)
+        .args(args)
+        .arg(&plugin.module)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|err| {
+            FunctionCallError::RespondToModel(format!(
+                "failed to run {name} with `{program}`: {err}; install {program} or set `runtime` in its plugin file to another of wasmtime, wasmer and wasmedge"
+            ))
+        })?;
+    let (Some(mut stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "failed to talk to {name}"
+        )));
+    };
+    let input = json!({ "tool": tool.name, "arguments": arguments });
+    let outcome = tokio::time::timeout(
+        TIMEOUT,
+        exchange(session, turn, &call_id, &mut stdin, stdout, &input),
+    )
+    .await
+    .unwrap_or_else(|_| Err(format!("didn't finish within {} s", TIMEOUT.as_secs())));
+    match outcome {
+        Ok(Ok(done)) => Ok(ToolOutput::Function {
+            content: done,
+            content_items: None,
+            success: Some(true),
+        }),
+        Ok(Err(fail)) => Ok(ToolOutput::Function {
+            content: format!("{name} failed: {fail}"),
+            content_items: None,
+            success: Some(false),
+        }),
+        Err(err) => {
+            let _ = child.start_kill();
+            Err(FunctionCallError::RespondToModel(format!("{name} {err}")))
+        }
+    }
+}
+
+/// Sends the call to the module and answers its requests. The outer `Err`
+/// is a protocol failure; the inner result is the module's own outcome.
+async fn exchange(
+    session: &Arc<Session>,
+    turn: &Arc<TurnContext>,
+    call_id: &str,
+    stdin: &mut ChildStdin,
+    stdout: ChildStdout,
+    input: &Value,
+) -> Result<Result<String, String>, String> {
+    let mut lines = BufReader::new(stdout).lines();
+    send(stdin, input).await?;
+    let mut requests = 0;
+    loop {
+        let Some(line) = lines.next_line().await.map_err(|err| err.to_string())? else {
+            return Err("exited without answering".to_string());
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let message: Message =
+            serde_json::from_str(&line).map_err(|err| format!("sent an unreadable line: {err}"))?;
+        let (call, arguments) = match message {
+            Message::Done { done } => return Ok(Ok(done)),
+            Message::Fail { fail } => return Ok(Err(fail)),
+            Message::Call { call, arguments } => (call, arguments),
+        };
+        requests += 1;
+        if requests > MAX_REQUESTS {
+            return Err(format!("made more than {MAX_REQUESTS} requests"));
+        }
+        let answer = match answer(session, turn, call_id, &call, arguments).await {
+            Ok(answer) => answer,
+            // A refused or failed action stops the plugin rather than letting
+            // it try another way around.
+            Err(FunctionCallError::RespondToModel(err)) if mutates(&call) => {
+                return Ok(Err(err));
+            }
+            Err(FunctionCallError::RespondToModel(err)) => json!({ "ok": false, "error": err }),
+            Err(err) => return Err(err.to_string()),
+        };
+        send(stdin, &answer).await?;
+    }
+}
+
+async fn send(stdin: &mut ChildStdin, value: &Value) -> Result<(), String> {
+    let mut line = value.to_string();
+    line.push('\n');
+    stdin
+        .write_all(line.as_bytes())
+        .await
+        .map_err(|err| format!("stopped reading its input: {err}"))
+}
+
+/// Runs one request of a module. An `Err` for an action that changes
+/// something means it was refused or failed, and ends the call.
+async fn answer(
+    session: &Arc<Session>,
+    turn: &Arc<TurnContext>,
+    call_id: &str,
+    call: &str,
+    arguments: Value,
+) -> Result<Value, FunctionCallError> {
+    let config = &turn.tools_config.computer_use;
+    if call == "capture" {
+        return capture(config, &arguments).map(|png| json!({ "ok": true, "png": png }));
+    }
+    if !HOST_TOOLS.contains(&call) {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "`{call}` is not available to plugins (available: capture, {})",
+            HOST_TOOLS.join(", ")
+        )));
+    }
+    let arguments = arguments.to_string();
+    if mutates(call) {
+        classifier::check(session, turn, call_id, call, &arguments).await?;
+    }
+    if sends_input(call) {
+        check_side_effects(session, turn, call_id, call, &arguments).await?;
+    }
+    let output = ComputerUseHandler::run_counted(
+        Arc::clone(session),
+        Arc::clone(turn),
+        call_id.to_string(),
+        call.to_string(),
+        arguments,
+    )
+    .await?;
+    let output = match output {
+        ToolOutput::Function { content, .. } => content,
+        _ => "done".to_string(),
+    };
+    Ok(json!({ "ok": true, "output": output }))
+}
+
+/// The screen, or the area `arguments` names, as base64 PNG.
+fn capture(config: &ComputerUseConfig, arguments: &Value) -> Result<String, FunctionCallError> {
+    let area = area(config, arguments).map_err(FunctionCallError::RespondToModel)?;
+    let path = capture_root_window(config, area.map(|area| area.to_string()), None)?;
+    let png = std::fs::read(&path);
+    let _ = std::fs::remove_file(&path);
+    let png = png.map_err(|err| {
+        FunctionCallError::RespondToModel(format!("failed to read capture: {err}"))
+    })?;
+    Ok(base64::engine::general_purpose::STANDARD.encode(png))
+}
+
+/// The area a `capture` request names, in screen pixels, or `None` for the
+/// whole screen. The area must lie inside `computer_use.viewport`.
+fn area(config: &ComputerUseConfig, arguments: &Value) -> Result<Option<Viewport>, String> {
+    if arguments.is_null() || arguments.as_object().is_some_and(serde_json::Map::is_empty) {
+        return Ok(None);
+    }
+    let area: CaptureArea = serde_json::from_value(arguments.clone())
+        .map_err(|err| format!("capture takes `x`, `y`, `width` and `height`: {err}"))?;
+    let area = Viewport {
+        width: area.width,
+        height: area.height,
+        x: area.x,
+        y: area.y,
+    };
+    if let Some(viewport) = config.viewport
+        && (area.x < viewport.x
+            || area.y < viewport.y
+            || area.x + area.width > viewport.x + viewport.width
+            || area.y + area.height > viewport.y + viewport.height)
+    {
+        return Err(format!(
+            "capture area {area} is outside computer_use.viewport {viewport}"
+        ));
+    }
+    Ok(Some(area))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_tools_and_requests() -> Result<(), String> {
+        let dir = Path::new("/home/user/.codex/computer_use/plugins");
+        let plugin = parse(
+            dir,
+            "Invoices",
+            r#"
+            module = "invoices.wasm"
+
+            [tools.fill_invoice]
+            description = "Fill the open invoice form."
+            parameters = ["customer", "amount"]
+            "#,
+        )?;
+        assert_eq!(plugin.module, dir.join("invoices.wasm"));
+        assert_eq!(plugin.runtime, Runtime::Wasmtime);
+        let plugins = [plugin];
+        let Some((plugin, tool)) = find(&plugins, "plugin_invoices_fill_invoice") else {
+            return Err("plugin_invoices_fill_invoice not found".to_string());
+        };
+        assert_eq!(plugin.tool_name(tool), "plugin_invoices_fill_invoice");
+        assert_eq!(tool.parameters, vec!["customer", "amount"]);
+        assert_eq!(find(&plugins, "recipe_invoices_fill_invoice"), None);
+
+        assert_eq!(
+            parse(dir, "invoices", "module = \"invoices.wasm\""),
+            Err("declares no tools".to_string())
+        );
+        assert_eq!(
+            parse(
+                dir,
+                "invoices",
+                "module = \"invoices.wasm\"\nruntime = \"wasmer\"\n[tools.x]\ndescription = \"x\""
+            )
+            .map(|plugin| plugin.runtime),
+            Ok(Runtime::Wasmer)
+        );
+        // Only the known runtimes, which grant the module nothing, may run it.
+        assert!(
+            parse(
+                dir,
+                "invoices",
+                "module = \"invoices.wasm\"\nruntime = [\"sh\", \"-c\"]\n[tools.x]\ndescription = \"x\""
+            )
+            .is_err()
+        );
+
+        assert_eq!(
+            serde_json::from_str::<Message>(
+                r#"{"call": "computer_key", "arguments": {"keys": ["ctrl+s"]}}"#
+            )
+            .map_err(|err| err.to_string())?,
+            Message::Call {
+                call: "computer_key".to_string(),
+                arguments: json!({"keys": ["ctrl+s"]}),
+            }
+        );
+        assert_eq!(
+            serde_json::from_str::<Message>(r#"{"done": "filled"}"#)
+                .map_err(|err| err.to_string())?,
+            Message::Done {
+                done: "filled".to_string()
+            }
+        );
+        let config = ComputerUseConfig {
+            viewport: Some(Viewport {
+                width: 1280,
+                height: 720,
+                x: 1920,
+                y: 0,
+            }),
+            ..Default::default()
+        };
+        assert_eq!(area(&config, &json!({})), Ok(None));
+        assert_eq!(
+            area(
+                &config,
+                &json!({"x": 2000, "y": 100, "width": 320, "height": 240})
+            )
+            .map(|area| area.map(|area| area.to_string())),
+            Ok(Some("320x240+2000+100".to_string()))
+        );
+        assert!(
+            area(
+                &config,
+                &json!({"x": 0, "y": 0, "width": 320, "height": 240})
+            )
+            .is_err()
+        );
+        assert!(area(&config, &json!({"x": 1})).is_err());
+        Ok(())
+    }
+}
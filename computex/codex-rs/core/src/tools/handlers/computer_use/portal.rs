@@ -0,0 +1,537 @@
+//! The xdg desktop portals behind the `portal` screenshot and input
+//! providers.
+//!
+//! Portals are how a Flatpak or Snap sandbox reaches the screen and input:
+//! `org.freedesktop.portal.Screenshot` captures the screen without a
+//! dialog once the user has allowed it, and a session of
+//! `org.freedesktop.portal.RemoteDesktop` sends pointer and key events. The
+//! desktop asks before the first remote desktop session; the grant comes
+//! back as a restore token, kept under `$CODEX_HOME`, that starts the next
+//! session without asking. Each input call runs in its own session, like
+//! each `xdotool` run. Portal methods answer through the `Response` signal
+//! of a request object, and every call runs on its own thread under a
+//! deadline, like the tray lookups.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use codex_computer_use_inject::Command;
+use codex_computer_use_inject::KeyAction;
+use codex_computer_use_inject::char_keysym;
+use codex_computer_use_inject::keysym;
+use uuid::Uuid;
+use zbus::blocking::Connection;
+use zbus::blocking::Proxy;
+use zbus::zvariant::ObjectPath;
+use zbus::zvariant::OwnedObjectPath;
+use zbus::zvariant::OwnedValue;
+use zbus::zvariant::Value;
+
+use crate::config::find_codex_home;
+use crate::function_tool::FunctionCallError;
+
+const DESKTOP: &str = "org.freedesktop.portal.Desktop";
+const DESKTOP_PATH: &str = "/org/freedesktop/portal/desktop";
+pub(super) const SCREENSHOT: &str = "org.freedesktop.portal.Screenshot";
+pub(super) const REMOTE_DESKTOP: &str = "org.freedesktop.portal.RemoteDesktop";
+const SCREEN_CAST: &str = "org.freedesktop.portal.ScreenCast";
+const REQUEST: &str = "org.freedesktop.portal.Request";
+const SESSION: &str = "org.freedesktop.portal.Session";
+const PROPERTIES: &str = "org.freedesktop.DBus.Properties";
+
+/// Where the remote desktop grant is kept, under `$CODEX_HOME`.
+const RESTORE_TOKEN_FILE: &str = "computer_use/remote_desktop_token";
+
+/// How long a capture or an input call may take.
+const DEADLINE: Duration = Duration::from_secs(10);
+/// How long the user has to answer the desktop's dialog for a remote
+/// desktop session.
+const CONSENT_DEADLINE: Duration = Duration::from_secs(60);
+
+// From the RemoteDesktop and ScreenCast portal specs.
+const DEVICE_KEYBOARD: u32 = 1;
+const DEVICE_POINTER: u32 = 2;
+const SOURCE_MONITOR: u32 = 1;
+/// Keep the grant until the user revokes it.
+const PERSIST_UNTIL_REVOKED: u32 = 2;
+const AXIS_VERTICAL: u32 = 0;
+const AXIS_HORIZONTAL: u32 = 1;
+
+// Button codes from <linux/input-event-codes.h>.
+const BTN_LEFT: i32 = 0x110;
+const BTN_RIGHT: i32 = 0x111;
+const BTN_MIDDLE: i32 = 0x112;
+const BTN_SIDE: i32 = 0x113;
+const BTN_EXTRA: i32 = 0x114;
+
+/// One remote desktop event, translated from xdotool commands.
+#[derive(Debug, Clone, PartialEq)]
+enum Event {
+    MoveTo { x: f64, y: f64 },
+    Button { code: i32, press: bool },
+    Scroll { axis: u32, steps: i32 },
+    Key { keysym: i32, press: bool },
+    Pause(Duration),
+}
+
+type Options<'a> = HashMap<&'a str, Value<'a>>;
+
+/// The `version` property of a portal `interface`, or `None` when the
+/// desktop doesn't offer it.
+pub(super) fn version(interface: &'static str) -> Option<u32> {
+    on_thread(DEADLINE, move || {
+        let bus = connect()?;
+        let reply = bus
+            .call_method(
+                Some(DESKTOP),
+                DESKTOP_PATH,
+                Some(PROPERTIES),
+                "Get",
+                &(interface, "version"),
+            )
+            .map_err(|err| err.to_string())?;
+        let value: OwnedValue = reply.body().deserialize().map_err(|err| err.to_string())?;
+        u32::try_from(value).map_err(|err| err.to_string())
+    })
+    .ok()
+}
+
+/// Captures the whole screen into the PNG at `path`.
+pub(super) fn screenshot(path: &Path) -> Result<(), String> {
+    let path = path.to_path_buf();
+    on_thread(DEADLINE, move || {
+        let bus = connect()?;
+        let token = handle_token();
+        let options = Options::from([
+            ("handle_token", Value::from(token.as_str())),
+            ("interactive", Value::from(false)),
+        ]);
+        let results = request(&bus, SCREENSHOT, "Screenshot", &("", options), &token)?;
+        let uri = results
+            .get("uri")
+            .and_then(|uri| <&str>::try_from(uri).ok())
+            .ok_or("the screenshot portal returned no file")?;
+        let saved = url::Url::parse(uri)
+            .ok()
+            .and_then(|uri| uri.to_file_path().ok())
+            .ok_or_else(|| format!("the screenshot portal returned `{uri}`, not a file"))?;
+        // The desktop saves into the user's pictures, so move the file out.
+        std::fs::copy(&saved, &path)
+            .map_err(|err| format!("failed to copy {}: {err}", saved.display()))?;
+        let _ = std::fs::remove_file(&saved);
+        Ok(())
+    })
+}
+
+/// Sends xdotool `args` through a remote desktop session. `None` when they
+/// hold something the portal can't send, in which case nothing was sent.
+pub(super) fn send(args: &[String]) -> Option<Result<(), FunctionCallError>> {
+    let events = translate(&codex_computer_use_inject::parse(args)?)?;
+    let token_file = find_codex_home()
+        .ok()
+        .map(|codex_home| codex_home.join(RESTORE_TOKEN_FILE));
+    let restore_token = token_file
+        .as_ref()
+        .and_then(|file| std::fs::read_to_string(file).ok())
+        .map(|token| token.trim().to_string())
+        .filter(|token| !token.is_empty());
+    // Without a grant, the user has to answer the desktop's dialog first.
+    let deadline = match restore_token {
+        Some(_) => DEADLINE,
+        None => CONSENT_DEADLINE,
+    };
+    let result = on_thread(deadline, move || {
+        let bus = connect()?;
+        let session = start_session(&bus, restore_token, token_file)?;
+        let sent = events
+            .iter()
+            .try_for_each(|event| notify(&bus, &session, event));
+        let _ = bus.call_method(
+            Some(DESKTOP),
+            session.path.as_str(),
+            Some(SESSION),
+            "Close",
+            &(),
+        );
+        sent
+    });
+    Some(result.map_err(|err| {
+        FunctionCallError::RespondToModel(format!("the remote desktop portal failed: {err}"))
+    }))
+}
+
+/// Translates xdotool commands into portal events. `None` for commands the
+/// portal can't replay, such as window queries or typing from stdin.
+fn translate(commands: &[Command]) -> Option<Vec<Event>> {
+    let mut events = Vec::new();
+    for command in commands {
+        match command {
+            Command::MoveTo { x, y } => events.push(Event::MoveTo {
+                x: f64::from(*x),
+                y: f64::from(*y),
+            }),
+            Command::Click {
+                button,
+                repeat,
+                delay,
+            } => {
+                for index in 0..*repeat {
+                    if index > 0 {
+                        events.push(Event::Pause(*delay));
+                    }
+                    events.extend(button_event(*button, true)?);
+                    events.extend(button_event(*button, false)?);
+                }
+            }
+            Command::Button { button, press } => events.extend(button_event(*button, *press)?),
+            // Held modifiers can't be read through the portal, so
+            // `--clearmodifiers` has nothing to clear.
+            Command::Keys {
+                chords,
+                action,
+                repeat,
+                delay,
+                ..
+            } => {
+                for index in 0..*repeat {
+                    for (chord_index, chord) in chords.iter().enumerate() {
+                        if index > 0 || chord_index > 0 {
+                            events.push(Event::Pause(*delay));
+                        }
+                        let keysyms = chord
+                            .iter()
+                            .map(|name| keysym(name).and_then(|keysym| i32::try_from(keysym).ok()))
+                            .collect::<Option<Vec<_>>>()?;
+                        if *action != KeyAction::Release {
+                            events.extend(keysyms.iter().map(|&keysym| Event::Key {
+                                keysym,
+                                press: true,
+                            }));
+                        }
+                        if *action != KeyAction::Press {
+                            events.extend(keysyms.iter().rev().map(|&keysym| Event::Key {
+                                keysym,
+                                press: false,
+                            }));
+                        }
+                    }
+                }
+            }
+            Command::Type {
+                text: Some(text),
+                delay,
+                ..
+            } => {
+                for (index, c) in text.chars().enumerate() {
+                    if index > 0 {
+                        events.push(Event::Pause(*delay));
+                    }
+                    let keysym = char_keysym(c).and_then(|keysym| i32::try_from(keysym).ok())?;
+                    events.push(Event::Key {
+                        keysym,
+                        press: true,
+                    });
+                    events.push(Event::Key {
+                        keysym,
+                        press: false,
+                    });
+                }
+            }
+            Command::Sleep(pause) => events.push(Event::Pause(*pause)),
+            _ => return None,
+        }
+    }
+    Some(events)
+}
+
+/// The event for pressing or releasing X `button`. Wheel buttons scroll
+/// once on the press and send nothing on the release.
+fn button_event(button: u8, press: bool) -> Option<Option<Event>> {
+    let code = match button {
+        1 => BTN_LEFT,
+        2 => BTN_MIDDLE,
+        3 => BTN_RIGHT,
+        8 => BTN_SIDE,
+        9 => BTN_EXTRA,
+        4..=7 if !press => return Some(None),
+        4 => return Some(Some(scroll(AXIS_VERTICAL, -1))),
+        5 => return Some(Some(scroll(AXIS_VERTICAL, 1))),
+        6 => return Some(Some(scroll(AXIS_HORIZONTAL, -1))),
+        7 => return Some(Some(scroll(AXIS_HORIZONTAL, 1))),
+        _ => return None,
+    };
+    Some(Some(Event::Button { code, press }))
+}
+
+fn scroll(axis: u32, steps: i32) -> Event {
+    Event::Scroll { axis, steps }
+}
+
+/// A started remote desktop session.
+struct Session {
+    path: OwnedObjectPath,
+    /// The screen cast stream absolute pointer positions are relative to.
+    stream: u32,
+}
+
+/// Starts a remote desktop session with a keyboard, a pointer, and one
+/// monitor, saving the grant it returns to `token_file`.
+fn start_session(
+    bus: &Connection,
+    restore_token: Option<String>,
+    token_file: Option<PathBuf>,
+) -> Result<Session, String> {
+    let token = handle_token();
+    let options = Options::from([
+        ("handle_token", Value::from(token.as_str())),
+        ("session_handle_token", Value::from(handle_token())),
+    ]);
+    let created = request(bus, REMOTE_DESKTOP, "CreateSession", &(options,), &token)?;
+    let path = created
+        .get("session_handle")
+        .and_then(|handle| match &**handle {
+            // A string in the spec, an object path in older portals.
+            Value::Str(handle) => OwnedObjectPath::try_from(handle.as_str()).ok(),
+            Value::ObjectPath(handle) => Some(handle.clone().into()),
+            _ => None,
+        })
+        .ok_or("the portal returned no session")?;
+    let session = ObjectPath::from(&path);
+
+    let token = handle_token();
+    let mut options = Options::from([
+        ("handle_token", Value::from(token.as_str())),
+        ("types", Value::from(DEVICE_KEYBOARD | DEVICE_POINTER)),
+        ("persist_mode", Value::from(PERSIST_UNTIL_REVOKED)),
+    ]);
+    if let Some(restore_token) = &restore_token {
+        options.insert("restore_token", Value::from(restore_token.as_str()));
+    }
+    request(
+        bus,
+        REMOTE_DESKTOP,
+        "SelectDevices",
+        &(&session, options),
+        &token,
+    )?;
+
+    // Absolute pointer positions need a screen cast stream to refer to.
+    let token = handle_token();
+    let options = Options::from([
+        ("handle_token", Value::from(token.as_str())),
+        ("types", Value::from(SOURCE_MONITOR)),
+    ]);
+    request(
+        bus,
+        SCREEN_CAST,
+        "SelectSources",
+        &(&session, options),
+        &token,
+    )?;
+
+    let token = handle_token();
+    let options = Options::from([("handle_token", Value::from(token.as_str()))]);
+    let started = request(
+        bus,
+        REMOTE_DESKTOP,
+        "Start",
+        &(&session, "", options),
+        &token,
+    )?;
+    if let (Some(file), Some(grant)) = (
+        token_file,
+        started
+            .get("restore_token")
+            .and_then(|grant| <&str>::try_from(grant).ok()),
+    ) {
+        // Grants are single use, so each session leaves the next one.
+        if let Some(dir) = file.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let _ = std::fs::write(file, grant);
+    }
+    let stream = started
+        .get("streams")
+        .and_then(|streams| first_stream(streams))
+        .ok_or("the portal shared no monitor")?;
+    Ok(Session { path, stream })
+}
+
+/// The node id of the first stream in a `Start` response's `streams`.
+fn first_stream(streams: &Value) -> Option<u32> {
+    let Value::Array(streams) = streams else {
+        return None;
+    };
+    let Some(Value::Structure(stream)) = streams.inner().first() else {
+        return None;
+    };
+    match stream.fields().first() {
+        Some(Value::U32(node)) => Some(*node),
+        _ => None,
+    }
+}
+
+fn notify(bus: &Connection, session: &Session, event: &Event) -> Result<(), String> {
+    let path = ObjectPath::from(&session.path);
+    let options = Options::new();
+    let result = match *event {
+        Event::MoveTo { x, y } => call(
+            bus,
+            "NotifyPointerMotionAbsolute",
+            &(path, options, session.stream, x, y),
+        ),
+        Event::Button { code, press } => call(
+            bus,
+            "NotifyPointerButton",
+            &(path, options, code, u32::from(press)),
+        ),
+        Event::Scroll { axis, steps } => call(
+            bus,
+            "NotifyPointerAxisDiscrete",
+            &(path, options, axis, steps),
+        ),
+        Event::Key { keysym, press } => call(
+            bus,
+            "NotifyKeyboardKeysym",
+            &(path, options, keysym, u32::from(press)),
+        ),
+        Event::Pause(pause) => {
+            thread::sleep(pause);
+            Ok(())
+        }
+    };
+    result.map_err(|err| err.to_string())
+}
+
+fn call<B>(bus: &Connection, method: &str, body: &B) -> zbus::Result<()>
+where
+    B: serde::Serialize + zbus::zvariant::DynamicType,
+{
+    bus.call_method(
+        Some(DESKTOP),
+        DESKTOP_PATH,
+        Some(REMOTE_DESKTOP),
+        method,
+        body,
+    )
+    .map(|_| ())
+}
+
+/// Calls a portal method that answers through a request object, returning
+/// the results of its `Response`. `token` is the `handle_token` in `body`.
+fn request<B>(
+    bus: &Connection,
+    interface: &str,
+    method: &str,
+    body: &B,
+    token: &str,
+) -> Result<HashMap<String, OwnedValue>, String>
+where
+    B: serde::Serialize + zbus::zvariant::DynamicType,
+{
+    // Subscribe before calling, so a quick answer isn't missed.
+    let sender = bus
+        .unique_name()
+        .ok_or("the session bus gave no name")?
+        .trim_start_matches(':')
+        .replace('.', "_");
+    let path = format!("{DESKTOP_PATH}/request/{sender}/{token}");
+    let proxy = Proxy::new(bus, DESKTOP, path.as_str(), REQUEST).map_err(|err| err.to_string())?;
+    let mut responses = proxy
+        .receive_signal("Response")
+        .map_err(|err| err.to_string())?;
+    bus.call_method(Some(DESKTOP), DESKTOP_PATH, Some(interface), method, body)
+        .map_err(|err| format!("{method} failed: {err}"))?;
+    let response = responses.next().ok_or("the portal dropped the request")?;
+    let (code, results): (u32, HashMap<String, OwnedValue>) = response
+        .body()
+        .deserialize()
+        .map_err(|err| err.to_string())?;
+    match code {
+        0 => Ok(results),
+        1 => Err(format!("{method} was denied")),
+        _ => Err(format!("{method} was cancelled")),
+    }
+}
+
+/// A fresh `handle_token`, which must be a valid object path element.
+fn handle_token() -> String {
+    format!("computex{}", Uuid::new_v4().simple())
+}
+
+fn on_thread<T: Send + 'static>(
+    deadline: Duration,
+    work: impl FnOnce() -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    let (sender, receiver) = mpsc::channel();
+    let spawned = thread::Builder::new()
+        .name("computex-portal".to_string())
+        .spawn(move || {
+            let _ = sender.send(work());
+        });
+    if let Err(err) = spawned {
+        return Err(format!("failed to start the portal call: {err}"));
+    }
+    match receiver.recv_timeout(deadline) {
+        Ok(result) => result,
+        Err(_) => Err(format!(
+            "the desktop portal did not answer within {} s",
+            deadline.as_secs()
+        )),
+    }
+}
+
+fn connect() -> Result<Connection, String> {
+    Connection::session().map_err(|err| format!("the session bus is unavailable: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn args(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| (*word).to_string()).collect()
+    }
+
+    fn events(words: &[&str]) -> Option<Vec<Event>> {
+        translate(&codex_computer_use_inject::parse(&args(words))?)
+    }
+
+    #[test]
+    fn translates_xdotool_commands() {
+        let key = |keysym: u32, press| Event::Key {
+            keysym: i32::try_from(keysym).expect("keysym"),
+            press,
+        };
+        assert_eq!(
+            events(&["mousemove", "--sync", "10", "20", "click", "5"]),
+            Some(vec![
+                Event::MoveTo { x: 10.0, y: 20.0 },
+                Event::Scroll {
+                    axis: AXIS_VERTICAL,
+                    steps: 1
+                },
+            ])
+        );
+        assert_eq!(
+            events(&["key", "ctrl+l"]),
+            Some(vec![
+                key(0xffe3, true),
+                key(u32::from('l'), true),
+                key(u32::from('l'), false),
+                key(0xffe3, false),
+            ])
+        );
+        assert_eq!(
+            events(&["type", "--delay", "0", "--", "é"]),
+            Some(vec![key(0xe9, true), key(0xe9, false)])
+        );
+        assert_eq!(events(&["getactivewindow"]), None);
+        assert_eq!(events(&["click", "12"]), None);
+    }
+}
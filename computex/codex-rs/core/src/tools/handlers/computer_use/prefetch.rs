@@ -0,0 +1,76 @@
+//! Speculative screenshots for `computer_use.screenshot_prefetch_ms`.
+//!
+//! After an input action, the model nearly always asks for a screenshot in
+//! its next response. Capturing one in the background while the model is
+//! still generating takes the capture off the critical path. The prefetched
+//! image is only served if no input happened since and it is younger than
+//! the configured freshness window.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use super::capture_screenshot;
+use crate::codex::Session;
+use crate::config::types::ComputerUseConfig;
+
+/// Delay before the speculative capture so the UI can repaint after input.
+const SETTLE_DELAY: Duration = Duration::from_millis(250);
+
+/// A screenshot captured ahead of the model asking for it.
+#[derive(Debug)]
+pub(super) struct Prefetched {
+    pub path: PathBuf,
+    pub captured_at: Instant,
+    /// Input generation the capture belongs to; see
+    /// [`super::ComputerUseState::note_input`].
+    pub generation: u64,
+}
+
+/// Captures a screenshot in the background if prefetching is enabled.
+pub(super) async fn schedule(session: &Arc<Session>, config: &ComputerUseConfig) {
+    if config.screenshot_prefetch_ms.is_none() {
+        return;
+    }
+    let generation = session
+        .services
+        .computer_use
+        .lock()
+        .await
+        .input_generation();
+    let session = Arc::clone(session);
+    let config = config.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(SETTLE_DELAY).await;
+        let Ok(Ok(path)) = tokio::task::spawn_blocking(move || capture_screenshot(&config)).await
+        else {
+            return;
+        };
+        let stale = session
+            .services
+            .computer_use
+            .lock()
+            .await
+            .store_prefetch(Prefetched {
+                path,
+                captured_at: Instant::now(),
+                generation,
+            });
+        if let Some(stale) = stale {
+            let _ = std::fs::remove_file(stale);
+        }
+    });
+}
+
+/// Returns the prefetched screenshot if it is still within `window`, and
+/// deletes it otherwise.
+pub(super) fn fresh(prefetched: Option<Prefetched>, window: Duration) -> Option<Prefetched> {
+    let prefetched = prefetched?;
+    if prefetched.captured_at.elapsed() <= window {
+        Some(prefetched)
+    } else {
+        let _ = std::fs::remove_file(&prefetched.path);
+        None
+    }
+}
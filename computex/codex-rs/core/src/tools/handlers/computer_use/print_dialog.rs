@@ -0,0 +1,254 @@
+//! Print-to-PDF through the app's print dialog, for
+//! `computer_print_dialog_handle`.
+//!
+//! Printing to a file takes several clicks in dialogs that differ between
+//! toolkits, and a misplaced click can send the job to a real printer. The
+//! helper finds the PDF destination on screen with OCR ("Print to File" in
+//! the GTK dialog, "Save as PDF" in Chromium's preview), selects it, checks
+//! that it took, enters the target path in whichever file chooser the
+//! dialog opens, confirms, and waits for the file to appear.
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::Instant;
+
+use super::capture_root_window;
+use super::coordinates::viewport_origin;
+use super::file_dialog::POLL_INTERVAL;
+use super::file_dialog::enter_path;
+use super::file_dialog::is_file_chooser;
+use super::file_dialog::wait_for_title;
+use super::find_text;
+use super::find_text::TextMatch;
+use super::keys::shortcut_keys;
+use super::require_command;
+use super::run_input;
+use crate::codex::Session;
+use crate::config::types::ComputerUseConfig;
+use crate::function_tool::FunctionCallError;
+
+/// PDF destinations as print dialogs label them.
+const PDF_DESTINATIONS: &[&str] = &[
+    "Print to File",
+    "Save as PDF",
+    "Microsoft Print to PDF",
+    "Print to PDF",
+];
+
+/// How closely OCR text must match a destination label.
+const LABEL_SIMILARITY: f64 = 0.85;
+
+/// How long to wait for the PDF to be written.
+const FILE_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Time for the dialog to redraw after a click.
+const SETTLE: Duration = Duration::from_millis(700);
+
+/// Prints the focused app's document to `path` as a PDF, opening the print
+/// dialog first when `open` is set. Returns the tool result.
+pub(super) async fn print_to_pdf(
+    session: &Session,
+    config: &ComputerUseConfig,
+    path: &str,
+    open: bool,
+) -> Result<String, FunctionCallError> {
+    let path = target_path(path).map_err(FunctionCallError::RespondToModel)?;
+    let xdotool = require_command("xdotool")?;
+    let mut steps = Vec::new();
+    if open {
+        let print = chord(config, "print_document", "ctrl+p");
+        run_input(session, &xdotool, &["key".to_string(), print], config, None).await?;
+        // Chromium's preview is part of the page, so the title may not change.
+        let _ = wait_for_title(&xdotool, config, is_print_dialog).await;
+        tokio::time::sleep(SETTLE).await;
+        steps.push("opened the print dialog".to_string());
+    }
+
+    let tsv = screen_text(config)?;
+    let Some((destination, label)) = find_destination(&tsv) else {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "no PDF destination is visible (looked for {}); open the print dialog (or pass open=true) and, if the destination is a drop-down, open it so the option shows",
+            PDF_DESTINATIONS.join(", ")
+        )));
+    };
+    click(session, &xdotool, config, &label).await?;
+    tokio::time::sleep(SETTLE).await;
+    steps.push(format!("selected \"{destination}\""));
+
+    // Check that the selection took before confirming anything, so the job
+    // can't go to the previously selected printer.
+    let tsv = screen_text(config)?;
+    let file_field = find_text::find_suffix(&tsv, ".pdf").into_iter().next();
+    let selected = match destination {
+        "Print to File" => file_field.is_some(),
+        _ => find_destination(&tsv).is_some(),
+    };
+    if !selected {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "selected \"{destination}\" but could not confirm the PDF destination took effect, so the dialog was not confirmed; take a screenshot and finish by hand"
+        )));
+    }
+
+    let mut path_entered = false;
+    if let Some(field) = file_field {
+        // The GTK dialog names the output file on a button that opens a
+        // file chooser.
+        click(session, &xdotool, config, &field).await?;
+        if wait_for_title(&xdotool, config, is_file_chooser)
+            .await
+            .is_some()
+        {
+            enter_path(session, &xdotool, config, &path).await?;
+            tokio::time::sleep(SETTLE).await;
+            path_entered = true;
+            steps.push("entered the output path".to_string());
+        }
+    }
+
+    run_input(
+        session,
+        &xdotool,
+        &["key".to_string(), "Return".to_string()],
+        config,
+        None,
+    )
+    .await?;
+    steps.push("confirmed".to_string());
+    if !path_entered && let Some(title) = wait_for_title(&xdotool, config, is_file_chooser).await {
+        enter_path(session, &xdotool, config, &path).await?;
+        path_entered = true;
+        steps.push(format!("entered the output path in \"{title}\""));
+    }
+
+    if !wait_for_file(&path).await {
+        let hint = if path_entered {
+            "the dialog may be asking a question"
+        } else {
+            "no file chooser appeared, so the PDF may have gone to the dialog's default location"
+        };
+        return Err(FunctionCallError::RespondToModel(format!(
+            "{} did not appear within {} s after {}; {hint}. Take a screenshot to check",
+            path.display(),
+            FILE_TIMEOUT.as_secs(),
+            steps.join(", ")
+        )));
+    }
+    Ok(format!(
+        "printed to PDF at {} ({})",
+        path.display(),
+        steps.join(", ")
+    ))
+}
+
+/// Checks that `path` is an absolute `.pdf` path in an existing directory
+/// that doesn't exist yet, so an overwrite prompt can't be mistaken for
+/// success.
+pub(super) fn target_path(path: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(path.trim());
+    if !path.is_absolute() {
+        return Err(format!("path must be absolute, got `{}`", path.display()));
+    }
+    if !path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("pdf"))
+    {
+        return Err(format!("path must end in .pdf, got `{}`", path.display()));
+    }
+    if !path.parent().is_some_and(Path::is_dir) {
+        return Err(format!(
+            "the directory for `{}` does not exist",
+            path.display()
+        ));
+    }
+    if path.exists() {
+        return Err(format!(
+            "`{}` already exists; choose a new path",
+            path.display()
+        ));
+    }
+    Ok(path)
+}
+
+fn is_print_dialog(title: &str) -> bool {
+    title.to_lowercase().contains("print")
+}
+
+/// The first PDF destination label on screen.
+fn find_destination(tsv: &str) -> Option<(&'static str, TextMatch)> {
+    PDF_DESTINATIONS.iter().find_map(|destination| {
+        find_text::find_matches(tsv, destination, LABEL_SIMILARITY)
+            .into_iter()
+            .next()
+            .map(|hit| (*destination, hit))
+    })
+}
+
+fn screen_text(config: &ComputerUseConfig) -> Result<String, FunctionCallError> {
+    let image_path = capture_root_window(config, None, None)?;
+    let tsv = find_text::recognize(&image_path);
+    let _ = std::fs::remove_file(&image_path);
+    tsv
+}
+
+async fn click(
+    session: &Session,
+    xdotool: &Path,
+    config: &ComputerUseConfig,
+    hit: &TextMatch,
+) -> Result<(), FunctionCallError> {
+    let (origin_x, origin_y) = viewport_origin(config);
+    let x = origin_x + (hit.left + hit.right) / 2;
+    let y = origin_y + (hit.top + hit.bottom) / 2;
+    let cmd = [
+        "mousemove".to_string(),
+        "--sync".to_string(),
+        x.to_string(),
+        y.to_string(),
+        "click".to_string(),
+        "1".to_string(),
+    ];
+    run_input(session, xdotool, &cmd, config, None)
+        .await
+        .map(drop)
+}
+
+/// Waits until `path` exists with a size that has stopped changing.
+async fn wait_for_file(path: &Path) -> bool {
+    let deadline = Instant::now() + FILE_TIMEOUT;
+    let mut last_size = None;
+    loop {
+        let size = std::fs::metadata(path).ok().map(|meta| meta.len());
+        if size.is_some_and(|size| size > 0) && size == last_size {
+            return true;
+        }
+        last_size = size;
+        if Instant::now() >= deadline {
+            return false;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+fn chord(config: &ComputerUseConfig, name: &str, fallback: &str) -> String {
+    shortcut_keys(name, config.target_os)
+        .map_or_else(|| fallback.to_string(), |keys| keys.join("+"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn target_must_be_a_new_absolute_pdf_path() {
+        let dir = std::env::temp_dir();
+        let new = dir.join(format!("computex-print-{}.pdf", uuid::Uuid::new_v4()));
+        assert_eq!(target_path(&new.display().to_string()), Ok(new));
+        assert!(target_path("report.pdf").is_err());
+        assert!(target_path(&dir.join("report.txt").display().to_string()).is_err());
+        assert!(target_path("/no/such/dir/report.pdf").is_err());
+        assert!(is_file_chooser("Select a filename"));
+        assert!(is_file_chooser("Save File"));
+        assert!(!is_file_chooser("Print"));
+    }
+}
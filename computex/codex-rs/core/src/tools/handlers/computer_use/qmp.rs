@@ -0,0 +1,507 @@
+//! The QMP monitor of a local QEMU/KVM virtual machine, behind the `qemu`
+//! backend.
+//!
+//! QEMU draws the VM's console itself, so its monitor can capture the screen
+//! with `screendump` and inject input with `input-send-event` whatever runs
+//! in the guest: firmware menus, boot loaders, and OS installers that no
+//! in-guest tool can reach. Each call opens its own connection to the socket
+//! in `computer_use.qmp_socket`, like each `xdotool` run. Pointer positions
+//! are absolute, so the VM needs a tablet such as `-device usb-tablet`, and
+//! keys are sent as scan codes for a US layout.
+
+use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use codex_computer_use_inject::Command;
+use codex_computer_use_inject::KeyAction;
+use codex_computer_use_inject::char_keysym;
+use codex_computer_use_inject::key_code;
+use codex_computer_use_inject::keysym;
+use codex_utils_image::screenshot::RawImage;
+use serde_json::Value;
+use serde_json::json;
+use uuid::Uuid;
+
+use super::rotation;
+use crate::config::types::ComputerUseConfig;
+use crate::function_tool::FunctionCallError;
+
+/// How long QEMU may take to answer a command.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Absolute pointer axes in `input-send-event` run from 0 to this.
+const ABS_MAX: i64 = 0x7fff;
+
+const KEY_LEFTSHIFT: u16 = 42;
+
+/// Linux key codes past the main block, and the scan codes QEMU numbers
+/// them by. Key codes 1 to 88 are their own scan codes.
+const EXTENDED_SCAN_CODES: &[(u16, u16)] = &[
+    (97, 0x9d),
+    (99, 0xb7),
+    (100, 0xb8),
+    (102, 0xc7),
+    (103, 0xc8),
+    (104, 0xc9),
+    (105, 0xcb),
+    (106, 0xcd),
+    (107, 0xcf),
+    (108, 0xd0),
+    (109, 0xd1),
+    (110, 0xd2),
+    (111, 0xd3),
+    (119, 0xc6),
+    (125, 0xdb),
+    (126, 0xdc),
+    (127, 0xdd),
+];
+
+/// One step of an input call, translated from xdotool commands.
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    /// A pointer move in screen pixels, scaled to the absolute axes once
+    /// the screen size is known.
+    MoveTo {
+        x: i32,
+        y: i32,
+    },
+    Event(Value),
+    Pause(Duration),
+}
+
+/// A QMP connection past capabilities negotiation.
+struct Monitor {
+    reader: BufReader<Box<dyn Read>>,
+    writer: Box<dyn Write>,
+}
+
+impl Monitor {
+    fn connect(config: &ComputerUseConfig) -> Result<Self, String> {
+        let socket = config
+            .qmp_socket
+            .as_deref()
+            .ok_or("set computer_use.qmp_socket to the VM's QMP socket")?;
+        let (reader, writer) =
+            open(socket).map_err(|err| format!("cannot connect to {}: {err}", socket.display()))?;
+        let mut monitor = Self {
+            reader: BufReader::new(reader),
+            writer,
+        };
+        monitor.handshake().map_err(|err| {
+            format!(
+                "{err}; QEMU serves one QMP client per socket, so give computex its own `-qmp` socket"
+            )
+        })?;
+        Ok(monitor)
+    }
+
+    fn handshake(&mut self) -> Result<(), String> {
+        if self.read()?.get("QMP").is_none() {
+            return Err("the socket did not greet like a QMP monitor".to_string());
+        }
+        self.execute("qmp_capabilities", json!({}))?;
+        Ok(())
+    }
+
+    fn execute(&mut self, command: &str, arguments: Value) -> Result<Value, String> {
+        let request = json!({ "execute": command, "arguments": arguments });
+        writeln!(self.writer, "{request}")
+            .and_then(|()| self.writer.flush())
+            .map_err(|err| format!("failed to send {command}: {err}"))?;
+        loop {
+            let mut reply = self.read()?;
+            if let Some(value) = reply.get_mut("return") {
+                return Ok(value.take());
+            }
+            if let Some(error) = reply.get("error") {
+                let desc = error
+                    .get("desc")
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown error");
+                return Err(format!("{command} failed: {desc}"));
+            }
+            // Events such as RESET or SHUTDOWN can arrive before the reply.
+        }
+    }
+
+    fn read(&mut self) -> Result<Value, String> {
+        let mut line = String::new();
+        match self.reader.read_line(&mut line) {
+            Ok(0) => Err("QEMU closed the QMP connection".to_string()),
+            Ok(_) => serde_json::from_str(&line).map_err(|err| format!("QEMU sent bad QMP: {err}")),
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                Err(format!(
+                    "QEMU did not answer within {} s",
+                    TIMEOUT.as_secs()
+                ))
+            }
+            Err(err) => Err(format!("failed to read from QMP: {err}")),
+        }
+    }
+
+    /// Captures the console. QEMU writes the file itself, so it goes to the
+    /// temp directory both processes share.
+    fn screendump(&mut self) -> Result<RawImage, String> {
+        let path = std::env::temp_dir().join(format!("codex-qmp-{}.ppm", Uuid::new_v4()));
+        let dumped = self.execute("screendump", json!({ "filename": path }));
+        let image = dumped.and_then(|_| {
+            std::fs::read(&path)
+                .map_err(|err| format!("failed to read {}: {err}", path.display()))
+                .and_then(parse_ppm)
+        });
+        let _ = std::fs::remove_file(&path);
+        image
+    }
+
+    fn send(&mut self, events: &mut Vec<Value>) -> Result<(), String> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        let events = std::mem::take(events);
+        self.execute("input-send-event", json!({ "events": events }))
+            .map(drop)
+    }
+}
+
+#[cfg(unix)]
+fn open(socket: &Path) -> io::Result<(Box<dyn Read>, Box<dyn Write>)> {
+    let stream = std::os::unix::net::UnixStream::connect(socket)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    Ok((Box::new(stream.try_clone()?), Box::new(stream)))
+}
+
+#[cfg(not(unix))]
+fn open(_socket: &Path) -> io::Result<(Box<dyn Read>, Box<dyn Write>)> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "QMP sockets are Unix sockets",
+    ))
+}
+
+/// The QEMU version behind the socket, e.g. `QEMU 8.2.2`.
+pub(super) fn version(config: &ComputerUseConfig) -> Result<String, String> {
+    let version = Monitor::connect(config)?.execute("query-version", json!({}))?;
+    let part = |name: &str| version["qemu"][name].as_u64().unwrap_or(0);
+    Ok(format!(
+        "QEMU {}.{}.{}",
+        part("major"),
+        part("minor"),
+        part("micro")
+    ))
+}
+
+/// Captures the VM's console, turned upright by `qmp_rotation`.
+pub(super) fn screendump(config: &ComputerUseConfig) -> Result<RawImage, String> {
+    let image = Monitor::connect(config)?.screendump()?;
+    rotation::upright(image, config.qmp_rotation)
+}
+
+/// The screen size in pixels, which changes as the guest switches modes.
+pub(super) fn screen_size(config: &ComputerUseConfig) -> Result<(u32, u32), String> {
+    let image = screendump(config)?;
+    Ok((image.width, image.height))
+}
+
+/// Sends xdotool `args` to the VM. `None` when they hold something QMP
+/// can't send, such as window queries or characters off a US layout, in
+/// which case nothing was sent.
+pub(super) fn send(
+    config: &ComputerUseConfig,
+    args: &[String],
+) -> Option<Result<(), FunctionCallError>> {
+    let steps = translate(&codex_computer_use_inject::parse(args)?)?;
+    Some(run(config, &steps).map_err(|err| {
+        FunctionCallError::RespondToModel(format!("QMP input to the VM failed: {err}"))
+    }))
+}
+
+fn run(config: &ComputerUseConfig, steps: &[Step]) -> Result<(), String> {
+    let mut monitor = Monitor::connect(config)?;
+    let mut screen = None;
+    let mut events = Vec::new();
+    for step in steps {
+        match step {
+            Step::MoveTo { x, y } => {
+                let (width, height) = match screen {
+                    Some(screen) => screen,
+                    None => {
+                        // The guest maps the tablet onto its rotated screen.
+                        let image = monitor.screendump()?;
+                        let size = if config.qmp_rotation.is_sideways() {
+                            (image.height, image.width)
+                        } else {
+                            (image.width, image.height)
+                        };
+                        *screen.insert(size)
+                    }
+                };
+                events.push(abs("x", *x, width));
+                events.push(abs("y", *y, height));
+            }
+            Step::Event(event) => events.push(event.clone()),
+            Step::Pause(pause) => {
+                monitor.send(&mut events)?;
+                thread::sleep(*pause);
+            }
+        }
+    }
+    monitor.send(&mut events)
+}
+
+/// Translates xdotool commands into QMP input steps.
+fn translate(commands: &[Command]) -> Option<Vec<Step>> {
+    let mut steps = Vec::new();
+    for command in commands {
+        match command {
+            Command::MoveTo { x, y } => steps.push(Step::MoveTo { x: *x, y: *y }),
+            Command::Click {
+                button,
+                repeat,
+                delay,
+            } => {
+                for index in 0..*repeat {
+                    if index > 0 {
+                        steps.push(Step::Pause(*delay));
+                    }
+                    steps.extend(button_events(*button, true)?);
+                    steps.extend(button_events(*button, false)?);
+                }
+            }
+            Command::Button { button, press } => steps.extend(button_events(*button, *press)?),
+            // The guest's held modifiers can't be read over QMP, so
+            // `--clearmodifiers` has nothing to clear.
+            Command::Keys {
+                chords,
+                action,
+                repeat,
+                delay,
+                ..
+            } => {
+                for index in 0..*repeat {
+                    for (chord_index, chord) in chords.iter().enumerate() {
+                        if index > 0 || chord_index > 0 {
+                            steps.push(Step::Pause(*delay));
+                        }
+                        let keys = chord
+                            .iter()
+                            .map(|name| keysym(name).and_then(key_code))
+                            .collect::<Option<Vec<_>>>()?;
+                        if *action != KeyAction::Release {
+                            for &(code, shift) in &keys {
+                                steps.extend(key_events(code, shift, true)?);
+                            }
+                        }
+                        if *action != KeyAction::Press {
+                            for &(code, shift) in keys.iter().rev() {
+                                steps.extend(key_events(code, shift, false)?);
+                            }
+                        }
+                    }
+                }
+            }
+            Command::Type {
+                text: Some(text),
+                delay,
+                ..
+            } => {
+                for (index, c) in text.chars().enumerate() {
+                    if index > 0 {
+                        steps.push(Step::Pause(*delay));
+                    }
+                    let (code, shift) = char_keysym(c).and_then(key_code)?;
+                    steps.extend(key_events(code, shift, true)?);
+                    steps.extend(key_events(code, shift, false)?);
+                }
+            }
+            Command::Sleep(pause) => steps.push(Step::Pause(*pause)),
+            _ => return None,
+        }
+    }
+    Some(steps)
+}
+
+/// The events for pressing or releasing X `button`. Wheel buttons scroll
+/// once on the press and send nothing on the release.
+fn button_events(button: u8, press: bool) -> Option<Vec<Step>> {
+    let name = match button {
+        1 => "left",
+        2 => "middle",
+        3 => "right",
+        8 => "side",
+        9 => "extra",
+        4..=7 if !press => return Some(Vec::new()),
+        4 => "wheel-up",
+        5 => "wheel-down",
+        6 => "wheel-left",
+        7 => "wheel-right",
+        _ => return None,
+    };
+    let event = |down: bool| {
+        Step::Event(json!({ "type": "btn", "data": { "down": down, "button": name } }))
+    };
+    Some(match button {
+        4..=7 => vec![event(true), event(false)],
+        _ => vec![event(press)],
+    })
+}
+
+/// The events for pressing or releasing the key with Linux `code`, holding
+/// Shift around it when `shift` is set.
+fn key_events(code: u16, shift: bool, press: bool) -> Option<Vec<Step>> {
+    let key = |code: u16, down: bool| {
+        scan_code(code).map(|number| {
+            Step::Event(json!({
+                "type": "key",
+                "data": { "down": down, "key": { "type": "number", "data": number } },
+            }))
+        })
+    };
+    Some(match (shift, press) {
+        (true, true) => vec![key(KEY_LEFTSHIFT, true)?, key(code, true)?],
+        (true, false) => vec![key(code, false)?, key(KEY_LEFTSHIFT, false)?],
+        (false, down) => vec![key(code, down)?],
+    })
+}
+
+/// The scan code QEMU numbers the key with Linux `code` by.
+fn scan_code(code: u16) -> Option<u16> {
+    match code {
+        1..=88 => Some(code),
+        _ => EXTENDED_SCAN_CODES
+            .iter()
+            .find(|(linux, _)| *linux == code)
+            .map(|&(_, scan)| scan),
+    }
+}
+
+/// An absolute position on `axis`, aimed at the middle of pixel `value` of
+/// `size`.
+fn abs(axis: &str, value: i32, size: u32) -> Value {
+    let size = i64::from(size.max(1));
+    let value = i64::from(value).clamp(0, size - 1);
+    json!({ "type": "abs", "data": { "axis": axis, "value": (2 * value + 1) * ABS_MAX / (2 * size) } })
+}
+
+/// Parses the binary PPM (`P6`) image `screendump` writes by default.
+fn parse_ppm(mut bytes: Vec<u8>) -> Result<RawImage, String> {
+    let invalid = || "QEMU wrote an unreadable screendump".to_string();
+    let mut fields = Vec::new();
+    let mut at = 0;
+    while fields.len() < 4 {
+        while bytes.get(at).is_some_and(u8::is_ascii_whitespace) {
+            at += 1;
+        }
+        let start = at;
+        while bytes
+            .get(at)
+            .is_some_and(|byte| !byte.is_ascii_whitespace())
+        {
+            at += 1;
+        }
+        if start == at {
+            return Err(invalid());
+        }
+        fields.push(String::from_utf8_lossy(&bytes[start..at]).into_owned());
+    }
+    let [magic, width, height, maxval] = fields.as_slice() else {
+        return Err(invalid());
+    };
+    let (Ok(width), Ok(height)) = (width.parse(), height.parse()) else {
+        return Err(invalid());
+    };
+    // One whitespace byte ends the header.
+    if magic != "P6" || maxval != "255" || at >= bytes.len() {
+        return Err(invalid());
+    }
+    bytes.drain(..=at);
+    RawImage::new(width, height, 3, bytes).map_err(|err| err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    fn key(number: u16, down: bool) -> Step {
+        Step::Event(json!({
+            "type": "key",
+            "data": { "down": down, "key": { "type": "number", "data": number } },
+        }))
+    }
+
+    #[test]
+    fn translates_xdotool_commands() {
+        let commands =
+            codex_computer_use_inject::parse(&args("mousemove 10 20 click 5 key ctrl+Up type A"))
+                .expect("parse");
+        assert_eq!(
+            translate(&commands),
+            Some(vec![
+                Step::MoveTo { x: 10, y: 20 },
+                Step::Event(
+                    json!({ "type": "btn", "data": { "down": true, "button": "wheel-down" } })
+                ),
+                Step::Event(
+                    json!({ "type": "btn", "data": { "down": false, "button": "wheel-down" } })
+                ),
+                key(29, true),
+                key(0xc8, true),
+                key(0xc8, false),
+                key(29, false),
+                key(42, true),
+                key(30, true),
+                key(30, false),
+                key(42, false),
+            ])
+        );
+        let commands = codex_computer_use_inject::parse(&args("type é")).expect("parse");
+        assert_eq!(translate(&commands), None);
+        assert_eq!(
+            abs("x", 1023, 1024),
+            json!({ "type": "abs", "data": { "axis": "x", "value": 32751 } })
+        );
+    }
+
+    #[test]
+    fn talks_qmp_and_reads_screendumps() {
+        let replies = concat!(
+            "{\"QMP\": {\"version\": {}, \"capabilities\": []}}\n",
+            "{\"return\": {}}\n",
+            "{\"event\": \"RESET\", \"data\": {}}\n",
+            "{\"error\": {\"class\": \"GenericError\", \"desc\": \"no tablet\"}}\n",
+        );
+        let mut monitor = Monitor {
+            reader: BufReader::new(Box::new(io::Cursor::new(replies.as_bytes().to_vec()))),
+            writer: Box::new(io::sink()),
+        };
+        monitor.handshake().expect("handshake");
+        assert_eq!(
+            monitor.execute("input-send-event", json!({ "events": [] })),
+            Err("input-send-event failed: no tablet".to_string())
+        );
+
+        let mut ppm = b"P6\n2 1\n255\n".to_vec();
+        ppm.extend([1, 2, 3, 4, 5, 6]);
+        assert_eq!(
+            parse_ppm(ppm),
+            Ok(RawImage::new(2, 1, 3, vec![1, 2, 3, 4, 5, 6]).expect("image"))
+        );
+        assert!(parse_ppm(b"P5\n2 1\n255\n".to_vec()).is_err());
+    }
+}
@@ -0,0 +1,370 @@
+//! Per-app automation recipes: named action sequences the user writes once
+//! and the model calls as tools.
+//!
+//! Each file in `<codex_home>/computer_use/recipes` describes one app, named
+//! after it (`firefox.toml`), and declares actions as steps:
+//!
+//! ```toml
+//! window_class = "firefox"
+//!
+//! [actions.open_private_window]
+//! description = "Open a new private browsing window."
+//! steps = [{ key = "ctrl+shift+p" }]
+//!
+//! [actions.open_url]
+//! description = "Open a URL in the current tab."
+//! parameters = ["url"]
+//! steps = [{ click = "Search or enter address" }, { type = "{url}\n" }]
+//! ```
+//!
+//! Every action becomes a `recipe_<app>_<action>` tool that only runs while
+//! a window of `window_class` (the file name by default) is focused. Click
+//! steps find their target with `computer_locate`'s strategies, and every
+//! step runs as the matching GUI tool, with the same checks.
+//!
+//! A file may also carry `guidance`, notes on working in the app ("tool
+//! options live in the left dock"). Rather than growing the instructions
+//! of every session, the notes are added to the result of the first GUI
+//! tool call after the app receives input.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::Deserialize;
+
+/// Directory under `codex_home` that holds recipe files.
+pub const RECIPES_DIR: &str = "computer_use/recipes";
+
+/// Prefix of every recipe tool name.
+pub(crate) const TOOL_PREFIX: &str = "recipe_";
+
+/// Longest tool name the model API accepts.
+const MAX_TOOL_NAME: usize = 64;
+
+/// One app's recipes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recipe {
+    /// The file name without `.toml`, used in tool names.
+    pub app: String,
+    /// WM_CLASS the focused window must have, compared case-insensitively.
+    pub window_class: String,
+    /// Notes on working in the app, shown once it receives input.
+    pub guidance: Option<String>,
+    pub actions: Vec<RecipeAction>,
+}
+
+/// A named action of a recipe.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecipeAction {
+    pub name: String,
+    pub description: String,
+    /// String arguments the tool takes, substituted for `{name}` in steps.
+    pub parameters: Vec<String>,
+    pub steps: Vec<RecipeStep>,
+}
+
+/// One step of an action.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum RecipeStep {
+    /// A key chord like `ctrl+l`, or a named shortcut like `new_tab`.
+    Key(String),
+    /// Text typed at the focus.
+    Type(String),
+    /// Clicks the element this query locates.
+    Click(String),
+    DoubleClick(String),
+    RightClick(String),
+    /// Pauses before the next step.
+    WaitMs(u64),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RecipeFile {
+    window_class: Option<String>,
+    guidance: Option<String>,
+    #[serde(default)]
+    actions: BTreeMap<String, ActionToml>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ActionToml {
+    description: String,
+    #[serde(default)]
+    parameters: Vec<String>,
+    steps: Vec<RecipeStep>,
+}
+
+impl Recipe {
+    /// The tool that runs `action`.
+    pub fn tool_name(&self, action: &RecipeAction) -> String {
+        format!("{TOOL_PREFIX}{}_{}", self.app, action.name)
+    }
+}
+
+impl RecipeStep {
+    /// The step with `{parameter}` placeholders replaced by `arguments`.
+    pub(super) fn fill(&self, arguments: &BTreeMap<String, String>) -> RecipeStep {
+        let fill = |text: &str| {
+            arguments
+                .iter()
+                .fold(text.to_string(), |text, (name, value)| {
+                    text.replace(&format!("{{{name}}}"), value)
+                })
+        };
+        match self {
+            RecipeStep::Key(chord) => RecipeStep::Key(fill(chord)),
+            RecipeStep::Type(text) => RecipeStep::Type(fill(text)),
+            RecipeStep::Click(query) => RecipeStep::Click(fill(query)),
+            RecipeStep::DoubleClick(query) => RecipeStep::DoubleClick(fill(query)),
+            RecipeStep::RightClick(query) => RecipeStep::RightClick(fill(query)),
+            RecipeStep::WaitMs(ms) => RecipeStep::WaitMs(*ms),
+        }
+    }
+
+    fn text(&self) -> Option<&str> {
+        match self {
+            RecipeStep::Key(text)
+            | RecipeStep::Type(text)
+            | RecipeStep::Click(text)
+            | RecipeStep::DoubleClick(text)
+            | RecipeStep::RightClick(text) => Some(text),
+            RecipeStep::WaitMs(_) => None,
+        }
+    }
+}
+
+/// Reads every recipe file under `codex_home`. Files that fail to parse are
+/// logged and skipped so one typo doesn't take the other apps' tools away.
+pub fn load(codex_home: &Path) -> Vec<Recipe> {
+    let Ok(entries) = std::fs::read_dir(codex_home.join(RECIPES_DIR)) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<_> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .collect();
+    paths.sort();
+    paths
+        .iter()
+        .filter_map(|path| {
+            let app = path.file_stem()?.to_string_lossy().into_owned();
+            let parsed = std::fs::read_to_string(path)
+                .map_err(|err| err.to_string())
+                .and_then(|contents| parse(&app, &contents));
+            match parsed {
+                Ok(recipe) => Some(recipe),
+                Err(err) => {
+                    tracing::warn!("skipping recipe file {}: {err}", path.display());
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+fn parse(app: &str, contents: &str) -> Result<Recipe, String> {
+    let file: RecipeFile = toml::from_str(contents).map_err(|err| err.to_string())?;
+    let app = identifier(app);
+    let window_class = file
+        .window_class
+        .map(|class| class.trim().to_string())
+        .filter(|class| !class.is_empty())
+        .unwrap_or_else(|| app.clone());
+    let mut recipe = Recipe {
+        app,
+        window_class,
+        guidance: file
+            .guidance
+            .map(|guidance| guidance.trim().to_string())
+            .filter(|guidance| !guidance.is_empty()),
+        actions: Vec::new(),
+    };
+    for (name, action) in file.actions {
+        let action = RecipeAction {
+            name: identifier(&name),
+            description: action.description.trim().to_string(),
+            parameters: action.parameters,
+            steps: action.steps,
+        };
+        check_action(&recipe, &action).map_err(|err| format!("action `{name}`: {err}"))?;
+        recipe.actions.push(action);
+    }
+    Ok(recipe)
+}
+
+fn check_action(recipe: &Recipe, action: &RecipeAction) -> Result<(), String> {
+    if action.steps.is_empty() {
+        return Err("has no steps".to_string());
+    }
+    let tool = recipe.tool_name(action);
+    if tool.len() > MAX_TOOL_NAME {
+        return Err(format!(
+            "tool name `{tool}` is longer than {MAX_TOOL_NAME} characters"
+        ));
+    }
+    if let Some(parameter) = action
+        .parameters
+        .iter()
+        .find(|parameter| identifier(parameter) != **parameter)
+    {
+        return Err(format!(
+            "parameter `{parameter}` must be lowercase letters, digits and underscores"
+        ));
+    }
+    for text in action.steps.iter().filter_map(RecipeStep::text) {
+        if let Some(placeholder) = placeholders(text)
+            .into_iter()
+            .find(|placeholder| !action.parameters.contains(placeholder))
+        {
+            return Err(format!("`{{{placeholder}}}` is not a declared parameter"));
+        }
+    }
+    Ok(())
+}
+
+/// `name` lowercased with everything but letters and digits turned into
+/// underscores, as tool names require.
+fn identifier(name: &str) -> String {
+    name.trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// The `{name}` placeholders in `text`.
+fn placeholders(text: &str) -> Vec<String> {
+    let mut found = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        rest = &rest[start + 1..];
+        let Some(end) = rest.find('}') else {
+            break;
+        };
+        let name = &rest[..end];
+        if !name.is_empty() && identifier(name) == name {
+            found.push(name.to_string());
+        }
+        rest = &rest[end + 1..];
+    }
+    found
+}
+
+/// The recipe and action behind `tool_name`, if it is a recipe tool.
+pub(crate) fn find<'a>(
+    recipes: &'a [Recipe],
+    tool_name: &str,
+) -> Option<(&'a Recipe, &'a RecipeAction)> {
+    if !tool_name.starts_with(TOOL_PREFIX) {
+        return None;
+    }
+    recipes.iter().find_map(|recipe| {
+        recipe
+            .actions
+            .iter()
+            .find(|action| recipe.tool_name(action) == tool_name)
+            .map(|action| (recipe, action))
+    })
+}
+
+/// The recipe with guidance for windows of `class`, if any.
+pub(super) fn guidance_for<'a>(recipes: &'a [Recipe], class: &str) -> Option<&'a Recipe> {
+    recipes
+        .iter()
+        .find(|recipe| recipe.guidance.is_some() && recipe.window_class.eq_ignore_ascii_case(class))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_actions_into_tools() -> Result<(), String> {
+        let recipe = parse(
+            "Firefox",
+            r#"
+            [actions.open_private_window]
+            description = "Open a new private browsing window."
+            steps = [{ key = "ctrl+shift+p" }]
+
+            [actions.open_url]
+            description = "Open a URL in the current tab."
+            parameters = ["url"]
+            steps = [{ click = "Search or enter address" }, { wait_ms = 200 }, { type = "{url}\n" }]
+            "#,
+        )?;
+        assert_eq!(recipe.window_class, "firefox");
+        let tools: Vec<String> = recipe
+            .actions
+            .iter()
+            .map(|action| recipe.tool_name(action))
+            .collect();
+        assert_eq!(
+            tools,
+            vec![
+                "recipe_firefox_open_private_window".to_string(),
+                "recipe_firefox_open_url".to_string(),
+            ]
+        );
+        let recipes = [recipe];
+        let Some((_, open_url)) = find(&recipes, "recipe_firefox_open_url") else {
+            return Err("recipe_firefox_open_url not found".to_string());
+        };
+        let arguments = BTreeMap::from([("url".to_string(), "example.com".to_string())]);
+        assert_eq!(
+            open_url.steps[2].fill(&arguments),
+            RecipeStep::Type("example.com\n".to_string())
+        );
+        assert_eq!(find(&recipes, "computer_click"), None);
+        assert_eq!(guidance_for(&recipes, "firefox"), None);
+
+        let gimp = parse(
+            "gimp",
+            r#"
+            window_class = "Gimp-2.10"
+            guidance = """
+            Tool options live in the left dock.
+            """
+            "#,
+        )?;
+        assert_eq!(
+            gimp.guidance.as_deref(),
+            Some("Tool options live in the left dock.")
+        );
+        let recipes = [gimp];
+        assert_eq!(
+            guidance_for(&recipes, "gimp-2.10").map(|recipe| recipe.app.as_str()),
+            Some("gimp")
+        );
+
+        assert_eq!(
+            parse(
+                "firefox",
+                r#"
+                [actions.open_url]
+                description = "Open a URL."
+                steps = [{ type = "{url}" }]
+                "#,
+            ),
+            Err("action `open_url`: `{url}` is not a declared parameter".to_string())
+        );
+        assert!(
+            parse(
+                "firefox",
+                "[actions.empty]\ndescription = \"x\"\nsteps = []"
+            )
+            .is_err()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,155 @@
+//! Focus recovery for GUI input.
+//!
+//! The handler remembers the window that last received input. When nothing is
+//! focused before an action (the target was closed or minimized), or when the
+//! action itself fails, it tries to bring that window back and retries once.
+
+use std::path::Path;
+
+use super::gui_command;
+use super::run_command;
+use super::verbosity;
+use crate::config::types::ComputerUseConfig;
+use crate::function_tool::FunctionCallError;
+
+/// A window identified by its X11 id and `WM_CLASS`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TargetWindow {
+    pub id: String,
+    pub class: String,
+}
+
+/// The focused window, or an error when no window has focus.
+pub(super) fn active_window(
+    xdotool: &Path,
+    config: &ComputerUseConfig,
+) -> Result<TargetWindow, FunctionCallError> {
+    let id = xdotool_output(xdotool, config, &["getactivewindow"])?;
+    if id.is_empty() {
+        return Err(FunctionCallError::RespondToModel(
+            "no window has focus".to_string(),
+        ));
+    }
+    let class = xdotool_output(xdotool, config, &["getwindowclassname", &id])?;
+    Ok(TargetWindow { id, class })
+}
+
+/// Tries to restore and focus `target`, falling back to another visible window
+/// of the same class. Returns the steps taken, in order.
+pub(super) fn recover_focus(
+    xdotool: &Path,
+    config: &ComputerUseConfig,
+    target: &TargetWindow,
+) -> Vec<String> {
+    let TargetWindow { id, class } = target;
+    let mut steps = Vec::new();
+    if xdotool_output(xdotool, config, &["getwindowname", id]).is_ok() {
+        if activate(xdotool, config, id).is_ok() {
+            steps.push(format!("restored and raised {class} window {id}"));
+            return steps;
+        }
+        steps.push(format!("could not raise {class} window {id}"));
+    } else {
+        steps.push(format!("{class} window {id} no longer exists"));
+    }
+
+    let pattern = format!("^{}$", regex_escape(class));
+    let replacement = xdotool_output(
+        xdotool,
+        config,
+        &["search", "--onlyvisible", "--class", &pattern],
+    )
+    .ok()
+    .and_then(|ids| {
+        ids.lines()
+            .map(str::trim)
+            .find(|candidate| candidate != id)
+            .map(str::to_string)
+    });
+    match replacement {
+        Some(other) if activate(xdotool, config, &other).is_ok() => {
+            steps.push(format!("activated another {class} window {other}"));
+        }
+        Some(other) => steps.push(format!("could not activate {class} window {other}")),
+        None => steps.push(format!("found no other visible {class} window")),
+    }
+    steps
+}
+
+/// Suffix for a tool result describing recovery steps, or an empty string.
+pub(super) fn describe(steps: &[String]) -> String {
+    if steps.is_empty() {
+        String::new()
+    } else {
+        format!(" (recovered focus: {})", steps.join("; "))
+    }
+}
+
+pub(super) fn activate(
+    xdotool: &Path,
+    config: &ComputerUseConfig,
+    id: &str,
+) -> Result<(), FunctionCallError> {
+    let args = ["windowmap", id, "windowactivate", "--sync", id].map(str::to_string);
+    run_command(xdotool, &args, config)
+}
+
+/// Runs an xdotool query and returns its trimmed stdout.
+pub(super) fn xdotool_output(
+    xdotool: &Path,
+    config: &ComputerUseConfig,
+    args: &[&str],
+) -> Result<String, FunctionCallError> {
+    let output = gui_command(xdotool, config)
+        .args(args)
+        .output()
+        .map_err(|err| {
+            FunctionCallError::RespondToModel(format!("failed to run xdotool: {err}"))
+        })?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let command = args.first().copied().unwrap_or_default();
+        let debug = verbosity::command_failure(config, args, output.status);
+        return Err(FunctionCallError::RespondToModel(format!(
+            "xdotool {command} failed: {stderr}{debug}"
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Escapes regex metacharacters so a window class matches literally in
+/// `xdotool search`.
+pub(super) fn regex_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        if "\\.+*?()|[]{}^$".contains(ch) {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn describe_lists_recovery_steps() {
+        assert_eq!(describe(&[]), "");
+        let steps = vec![
+            "firefox window 42 no longer exists".to_string(),
+            "activated another firefox window 43".to_string(),
+        ];
+        assert_eq!(
+            describe(&steps),
+            " (recovered focus: firefox window 42 no longer exists; activated another firefox window 43)"
+        );
+    }
+
+    #[test]
+    fn regex_escape_matches_class_literally() {
+        assert_eq!(regex_escape("Gimp-2.10"), "Gimp-2\\.10");
+    }
+}
@@ -0,0 +1,250 @@
+//! Driving another machine over SSH, for `computer_use.target`.
+//!
+//! The GUI tools talk to an X display, so the remote display's socket is
+//! forwarded to a local TCP display with `ssh -L`, and its auth cookie is
+//! copied into the local `~/.Xauthority`. Every tool, xdotool, and the
+//! native X connection then work on it unchanged. Shell commands are routed
+//! to the same machine by running them through `ssh`, so a terminal the
+//! agent opens on screen and the shell tool see the same files.
+
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::path::Path;
+use std::process::Child;
+use std::process::Command;
+use std::process::Stdio;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use anyhow::Context;
+use which::which;
+
+use crate::config::types::RemoteTarget;
+
+/// First local display number tried for the tunnel, above the displays a
+/// desktop and `ssh -X` usually take.
+const FIRST_LOCAL_DISPLAY: u16 = 20;
+const LOCAL_DISPLAYS: u16 = 40;
+/// X displays listen on TCP port 6000 plus their number.
+const X_TCP_PORT: u16 = 6000;
+const TUNNEL_TIMEOUT: Duration = Duration::from_secs(15);
+const TUNNEL_POLL: Duration = Duration::from_millis(100);
+
+/// `ssh` to `target`, ready for the remote command.
+fn ssh_command(target: &RemoteTarget) -> Command {
+    let mut command = Command::new("ssh");
+    command.args(ssh_options(target)).arg(target.destination());
+    command
+}
+
+/// The options every `ssh` call to `target` takes.
+fn ssh_options(target: &RemoteTarget) -> Vec<String> {
+    // Fail instead of prompting; the agent can't answer a password prompt.
+    let mut options = vec!["-o".to_string(), "BatchMode=yes".to_string()];
+    if let Some(port) = target.port {
+        options.extend(["-p".to_string(), port.to_string()]);
+    }
+    options
+}
+
+/// The command line that runs `command` on `target` in `cwd`. The command
+/// fails when `cwd` doesn't exist there rather than running somewhere else.
+/// `tty` asks for a remote terminal, for interactive sessions.
+///
+/// A `shell -lc script` command runs `script` in the remote user's own
+/// shell, since the local shell may not be installed there.
+pub(crate) fn remote_command(
+    target: &RemoteTarget,
+    command: &[String],
+    cwd: &Path,
+    tty: bool,
+) -> Vec<String> {
+    let script = match command {
+        [_, flag, script] if flag == "-lc" || flag == "-c" => script.clone(),
+        _ => shlex::try_join(command.iter().map(String::as_str))
+            .unwrap_or_else(|_| command.join(" ")),
+    };
+    let cwd = shlex::try_quote(&cwd.to_string_lossy())
+        .map(std::borrow::Cow::into_owned)
+        .unwrap_or_default();
+    let mut args = vec!["ssh".to_string()];
+    args.extend(ssh_options(target));
+    args.extend([
+        if tty { "-tt" } else { "-T" }.to_string(),
+        target.destination(),
+        "--".to_string(),
+        format!("cd {cwd} && {script}"),
+    ]);
+    args
+}
+
+/// The remote display, forwarded to a local one until dropped.
+#[derive(Debug)]
+pub struct Tunnel {
+    ssh: Child,
+    display: String,
+    /// Display name of the copied auth cookie, removed again on drop.
+    cookie: Option<String>,
+}
+
+impl Tunnel {
+    /// Forwards the X display of `target` to a free local display.
+    pub fn open(target: &RemoteTarget) -> anyhow::Result<Self> {
+        which("ssh").context("`ssh` is needed for computer_use.target")?;
+        let number = (FIRST_LOCAL_DISPLAY..FIRST_LOCAL_DISPLAY + LOCAL_DISPLAYS)
+            .find(|number| TcpListener::bind(("127.0.0.1", X_TCP_PORT + number)).is_ok())
+            .context("no free local X display number for the SSH tunnel")?;
+        let port = X_TCP_PORT + number;
+        let ssh = ssh_command(target)
+            .args([
+                "-N",
+                "-o",
+                "ExitOnForwardFailure=yes",
+                "-L",
+                &format!("127.0.0.1:{port}:/tmp/.X11-unix/X{}", target.display),
+            ])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("failed to start ssh")?;
+        let mut tunnel = Self {
+            ssh,
+            display: format!("127.0.0.1:{number}"),
+            cookie: None,
+        };
+        tunnel.wait_until_listening(port, target)?;
+        tunnel.copy_cookie(target, number)?;
+        Ok(tunnel)
+    }
+
+    /// The local display that reaches the remote one.
+    pub fn display(&self) -> &str {
+        &self.display
+    }
+
+    fn wait_until_listening(&mut self, port: u16, target: &RemoteTarget) -> anyhow::Result<()> {
+        let started = Instant::now();
+        while started.elapsed() < TUNNEL_TIMEOUT {
+            if let Some(status) = self.ssh.try_wait()? {
+                let mut stderr = String::new();
+                if let Some(mut pipe) = self.ssh.stderr.take() {
+                    let _ = std::io::Read::read_to_string(&mut pipe, &mut stderr);
+                }
+                anyhow::bail!(
+                    "ssh to {} exited with {status}: {}",
+                    target.destination(),
+                    stderr.trim()
+                );
+            }
+            if TcpStream::connect(("127.0.0.1", port)).is_ok() {
+                return Ok(());
+            }
+            thread::sleep(TUNNEL_POLL);
+        }
+        anyhow::bail!(
+            "the SSH tunnel to {} was not up within {} s",
+            target.destination(),
+            TUNNEL_TIMEOUT.as_secs()
+        )
+    }
+
+    /// Copies the remote display's auth cookie to the local display, the way
+    /// `ssh -X` does for forwarded displays. Displays without one need none.
+    fn copy_cookie(&mut self, target: &RemoteTarget, number: u16) -> anyhow::Result<()> {
+        let output = ssh_command(target)
+            .args(["xauth", "list", &format!(":{}", target.display)])
+            .stdin(Stdio::null())
+            .output()
+            .context("failed to run xauth on the target")?;
+        let listed = String::from_utf8_lossy(&output.stdout);
+        let Some((protocol, cookie)) = parse_cookie(&listed) else {
+            return Ok(());
+        };
+        let xauth = which("xauth").context(
+            "the target's display needs an auth cookie; install xauth with `sudo apt-get install -y xauth`",
+        )?;
+        let display = format!("unix:{number}");
+        let status = Command::new(xauth)
+            .args(["add", &display, protocol, cookie])
+            .status()
+            .context("failed to run xauth")?;
+        anyhow::ensure!(status.success(), "xauth add {display} failed");
+        self.cookie = Some(display);
+        Ok(())
+    }
+}
+
+/// The protocol and hex cookie of the first `xauth list` entry.
+fn parse_cookie(listed: &str) -> Option<(&str, &str)> {
+    let mut fields = listed.lines().next()?.split_whitespace();
+    let _display = fields.next()?;
+    Some((fields.next()?, fields.next()?))
+}
+
+impl Drop for Tunnel {
+    fn drop(&mut self) {
+        if let Some(display) = &self.cookie
+            && let Ok(xauth) = which("xauth")
+        {
+            let _ = Command::new(xauth).args(["remove", display]).status();
+        }
+        let _ = self.ssh.kill();
+        let _ = self.ssh.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_targets_and_builds_remote_commands() {
+        let target: RemoteTarget = "ssh://me@lab.example:2222/:1".parse().expect("target");
+        assert_eq!(
+            target,
+            RemoteTarget {
+                user: Some("me".to_string()),
+                host: "lab.example".to_string(),
+                port: Some(2222),
+                display: 1,
+            }
+        );
+        assert_eq!(target.to_string(), "ssh://me@lab.example:2222/:1");
+        assert_eq!(
+            "ssh://lab"
+                .parse::<RemoteTarget>()
+                .map(|target| target.display),
+            Ok(0)
+        );
+        assert!("lab.example".parse::<RemoteTarget>().is_err());
+        assert!("ssh://lab/1".parse::<RemoteTarget>().is_err());
+
+        let command = [
+            "/bin/zsh".to_string(),
+            "-lc".to_string(),
+            "ls -la".to_string(),
+        ];
+        assert_eq!(
+            remote_command(&target, &command, Path::new("/home/me/my project"), false),
+            [
+                "ssh",
+                "-o",
+                "BatchMode=yes",
+                "-p",
+                "2222",
+                "-T",
+                "me@lab.example",
+                "--",
+                "cd '/home/me/my project' && ls -la",
+            ]
+        );
+        assert_eq!(
+            parse_cookie("lab/unix:1  MIT-MAGIC-COOKIE-1  0123abcd\n"),
+            Some(("MIT-MAGIC-COOKIE-1", "0123abcd"))
+        );
+        assert_eq!(parse_cookie(""), None);
+    }
+}
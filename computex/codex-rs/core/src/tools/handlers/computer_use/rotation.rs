@@ -0,0 +1,190 @@
+//! Rotated outputs: portrait monitors and kiosk screens turned on their side.
+//!
+//! Under X11, RandR rotates the root window itself, so xdotool and every X
+//! capture already agree on a portrait output's coordinates. Two things
+//! still need the rotation. The virtual canvas is landscape by default, and
+//! squeezing a portrait screen into it distorts every screenshot, so
+//! [`oriented_canvas`] turns the canvas to match the screen at startup. And
+//! QEMU's console shows a guest framebuffer before the guest's rotation, so
+//! with `computer_use.qmp_rotation` set the VM's screenshots are turned
+//! upright with [`upright`] and its pointer is aimed in the rotated space the
+//! guest maps its tablet onto.
+
+use codex_utils_image::screenshot::RawImage;
+
+use super::qmp;
+use super::scaling;
+use super::scaling::ActiveOutput;
+use crate::config::types::ComputerUseBackend;
+use crate::config::types::ComputerUseConfig;
+use crate::config::types::CoordinateSpace;
+use crate::config::types::Resolution;
+use crate::config::types::Rotation;
+
+/// The canvas turned to the orientation of the screen, or of the viewport,
+/// when the two differ. `None` when the canvas already fits, coordinates
+/// are native pixels, or the screen size is unknown.
+pub fn oriented_canvas(config: &ComputerUseConfig) -> Option<Resolution> {
+    if config.coordinate_space == CoordinateSpace::Native {
+        return None;
+    }
+    let (width, height) = match (config.viewport, config.backend) {
+        (Some(viewport), _) => (viewport.width, viewport.height),
+        (None, ComputerUseBackend::X11) => scaling::screen_size(config)?,
+        (None, ComputerUseBackend::Qemu) => qmp::screen_size(config).ok()?,
+        (None, ComputerUseBackend::Console) => return None,
+    };
+    turned(config.resolution, width, height)
+}
+
+/// `canvas` turned to match a `width` x `height` screen, if it doesn't.
+fn turned(canvas: Resolution, width: u32, height: u32) -> Option<Resolution> {
+    let portrait = |width: u32, height: u32| width < height;
+    let square = width == height || canvas.width == canvas.height;
+    (!square && portrait(width, height) != portrait(canvas.width, canvas.height)).then_some(
+        Resolution {
+            width: canvas.height,
+            height: canvas.width,
+        },
+    )
+}
+
+/// `image` of an unrotated framebuffer, turned the way `rotation` shows it.
+/// `left` shows the framebuffer turned counter-clockwise, so its image is
+/// turned clockwise to undo that, and the other way round for `right`.
+pub(super) fn upright(image: RawImage, rotation: Rotation) -> Result<RawImage, String> {
+    if rotation == Rotation::Normal {
+        return Ok(image);
+    }
+    let (width, height) = (image.width as usize, image.height as usize);
+    let channels = usize::from(image.channels);
+    let (out_width, out_height) = if rotation.is_sideways() {
+        (height, width)
+    } else {
+        (width, height)
+    };
+    let mut pixels = Vec::with_capacity(image.pixels.len());
+    for y in 0..out_height {
+        for x in 0..out_width {
+            let (source_x, source_y) = match rotation {
+                Rotation::Left => (y, height - 1 - x),
+                Rotation::Right => (width - 1 - y, x),
+                Rotation::Inverted => (width - 1 - x, height - 1 - y),
+                Rotation::Normal => (x, y),
+            };
+            let start = (source_y * width + source_x) * channels;
+            pixels.extend_from_slice(&image.pixels[start..start + channels]);
+        }
+    }
+    RawImage::new(out_width as u32, out_height as u32, image.channels, pixels)
+        .map_err(|err| format!("failed to rotate the screenshot: {err}"))
+}
+
+/// One line for the backend summary, e.g. `rotated monitors: HDMI-1 left
+/// (1080x1920)`.
+/// `None` when nothing is rotated.
+pub(super) fn describe(outputs: &[ActiveOutput]) -> Option<String> {
+    let rotated: Vec<String> = outputs
+        .iter()
+        .filter(|output| output.rotation != Rotation::Normal)
+        .map(|output| {
+            let rotation = match output.rotation {
+                Rotation::Left => "left",
+                Rotation::Inverted => "inverted",
+                Rotation::Right => "right",
+                Rotation::Normal => "normal",
+            };
+            format!(
+                "{} {rotation} ({}x{})",
+                output.name, output.geometry.width, output.geometry.height
+            )
+        })
+        .collect();
+    (!rotated.is_empty()).then(|| format!("rotated monitors: {}", rotated.join(", ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::types::Viewport;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn turns_images_and_canvases_to_the_screen() {
+        // 3x2 framebuffer, pixels numbered by their red channel:
+        // 1 2 3
+        // 4 5 6
+        let pixels = (1..=6).flat_map(|value| [value, 0, 0]).collect::<Vec<u8>>();
+        let image = || RawImage::new(3, 2, 3, pixels.clone()).expect("image");
+        let turned_image = |rotation| {
+            let image = upright(image(), rotation).expect("rotate");
+            let reds = image.pixels.iter().step_by(3).copied().collect::<Vec<_>>();
+            (image.width, image.height, reds)
+        };
+        assert_eq!(
+            turned_image(Rotation::Normal),
+            (3, 2, vec![1, 2, 3, 4, 5, 6])
+        );
+        assert_eq!(turned_image(Rotation::Left), (2, 3, vec![4, 1, 5, 2, 6, 3]));
+        assert_eq!(
+            turned_image(Rotation::Right),
+            (2, 3, vec![3, 6, 2, 5, 1, 4])
+        );
+        assert_eq!(
+            turned_image(Rotation::Inverted),
+            (3, 2, vec![6, 5, 4, 3, 2, 1])
+        );
+
+        let canvas = Resolution {
+            width: 1280,
+            height: 720,
+        };
+        assert_eq!(
+            turned(canvas, 1080, 1920),
+            Some(Resolution {
+                width: 720,
+                height: 1280
+            })
+        );
+        assert_eq!(turned(canvas, 1920, 1080), None);
+        assert_eq!(turned(canvas, 1024, 1024), None);
+
+        let config = ComputerUseConfig {
+            viewport: Some(Viewport {
+                width: 1080,
+                height: 1920,
+                x: 1920,
+                y: 0,
+            }),
+            ..ComputerUseConfig::default()
+        };
+        assert_eq!(
+            oriented_canvas(&config),
+            Some(Resolution {
+                width: 720,
+                height: 1280
+            })
+        );
+        let native = ComputerUseConfig {
+            coordinate_space: CoordinateSpace::Native,
+            ..config
+        };
+        assert_eq!(oriented_canvas(&native), None);
+
+        let outputs = [ActiveOutput {
+            name: "HDMI-1".to_string(),
+            geometry: Viewport {
+                width: 1080,
+                height: 1920,
+                x: 0,
+                y: 0,
+            },
+            mode: Some((1920, 1080)),
+            rotation: Rotation::Right,
+        }];
+        assert_eq!(
+            describe(&outputs).as_deref(),
+            Some("rotated monitors: HDMI-1 right (1080x1920)")
+        );
+    }
+}
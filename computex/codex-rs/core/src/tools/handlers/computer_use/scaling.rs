@@ -0,0 +1,339 @@
+//! HiDPI and fractional scaling between screenshots and input.
+//!
+//! Under X11 alone, xdotool and every X capture share one pixel grid. A
+//! Wayland compositor that scales an output by 125% or 200% breaks that:
+//! `grim` and the screenshot portal return the output's device pixels while
+//! Xwayland, and so xdotool, works in logical ones. Screenshots from those
+//! providers are measured against the X screen and scaled back onto the
+//! input grid, so what the model measures in an image is where its clicks
+//! land. Without an X screen to measure against, `computer_use.display_scale`
+//! gives the ratio.
+//!
+//! The scale factors the desktop applies per monitor (an xrandr scaling
+//! transform, `Xft.dpi`, `GDK_SCALE`) are detected for the backend summary,
+//! since they explain captures that look blurry or oversized.
+
+use std::fmt::Write as _;
+
+use which::which;
+use x11rb::connection::Connection;
+
+use super::gui_command;
+use crate::config::types::ComputerUseConfig;
+use crate::config::types::Resolution;
+use crate::config::types::Rotation;
+use crate::config::types::ScreenshotProvider;
+use crate::config::types::Viewport;
+
+/// The DPI X clients assume without `Xft.dpi`.
+const BASE_DPI: f64 = 96.0;
+
+/// Differences in size up to this many pixels are rounding, not scaling.
+const SIZE_SLACK: u32 = 1;
+
+/// A monitor and the scale factor its content is drawn at.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct MonitorScale {
+    pub name: String,
+    pub geometry: Viewport,
+    pub scale: f64,
+    /// Where `scale` comes from, e.g. `Xft.dpi`.
+    pub source: &'static str,
+}
+
+/// An active output from `xrandr --query`, with its current mode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct ActiveOutput {
+    pub name: String,
+    pub geometry: Viewport,
+    /// Width and height of the current mode, before rotation.
+    pub mode: Option<(u32, u32)>,
+    pub rotation: Rotation,
+}
+
+/// Whether `provider` captures device pixels, which differ from the X
+/// screen's under a scaling Wayland compositor.
+pub(super) fn in_device_pixels(provider: ScreenshotProvider) -> bool {
+    matches!(
+        provider,
+        ScreenshotProvider::Grim | ScreenshotProvider::Portal
+    )
+}
+
+/// The size a `width` x `height` capture of `crop`, or of the whole screen,
+/// must be scaled to so its pixels are input pixels. `None` when it already
+/// is, or when neither the X screen nor `display_scale` tells.
+pub(super) fn input_grid(
+    config: &ComputerUseConfig,
+    crop: Option<Viewport>,
+    width: u32,
+    height: u32,
+) -> Option<Resolution> {
+    let (grid_width, grid_height) = match crop {
+        Some(area) => (area.width, area.height),
+        None => screen_size(config).or_else(|| {
+            let scale = config.display_scale?;
+            Some((
+                (f64::from(width) / scale).round() as u32,
+                (f64::from(height) / scale).round() as u32,
+            ))
+        })?,
+    };
+    let differs = |a: u32, b: u32| a.abs_diff(b) > SIZE_SLACK;
+    (grid_width > 0
+        && grid_height > 0
+        && (differs(width, grid_width) || differs(height, grid_height)))
+    .then_some(Resolution {
+        width: grid_width,
+        height: grid_height,
+    })
+}
+
+/// Size of the X screen, when one can be opened.
+pub(super) fn screen_size(config: &ComputerUseConfig) -> Option<(u32, u32)> {
+    let (conn, screen) = x11rb::connect(config.display.as_deref()).ok()?;
+    let screen = conn.setup().roots.get(screen)?;
+    Some((
+        u32::from(screen.width_in_pixels),
+        u32::from(screen.height_in_pixels),
+    ))
+}
+
+/// The active outputs `xrandr --query` lists; empty without xrandr.
+pub(super) fn active_outputs(config: &ComputerUseConfig) -> Vec<ActiveOutput> {
+    let Ok(xrandr) = which("xrandr") else {
+        return Vec::new();
+    };
+    let Ok(query) = gui_command(&xrandr, config).arg("--query").output() else {
+        return Vec::new();
+    };
+    parse_active_outputs(&String::from_utf8_lossy(&query.stdout))
+}
+
+/// The scale factor of each of `outputs`. The configured `display_scale`
+/// wins; then an xrandr scaling transform, which differs per monitor; then
+/// the desktop-wide `Xft.dpi` and `GDK_SCALE`.
+pub(super) fn monitors(config: &ComputerUseConfig, outputs: &[ActiveOutput]) -> Vec<MonitorScale> {
+    let desktop = desktop_scale(config);
+    outputs
+        .iter()
+        .map(|output| {
+            let (scale, source) = match (config.display_scale, transform_scale(output)) {
+                (Some(scale), _) => (scale, "computer_use.display_scale"),
+                (None, Some(scale)) => (scale, "xrandr scaling"),
+                (None, None) => desktop.unwrap_or((1.0, "unscaled")),
+            };
+            MonitorScale {
+                name: output.name.clone(),
+                geometry: output.geometry,
+                scale,
+                source,
+            }
+        })
+        .collect()
+}
+
+/// One line for the backend summary, e.g. `scaled monitors: eDP-1 1.5x
+/// (Xft.dpi), HDMI-1 1x`. `None` when nothing is scaled.
+pub(super) fn describe(monitors: &[MonitorScale]) -> Option<String> {
+    if monitors.iter().all(|monitor| !is_scaled(monitor.scale)) {
+        return None;
+    }
+    let mut line = "scaled monitors:".to_string();
+    for (index, monitor) in monitors.iter().enumerate() {
+        let separator = if index == 0 { " " } else { ", " };
+        let _ = write!(line, "{separator}{} {}x", monitor.name, monitor.scale);
+        if is_scaled(monitor.scale) {
+            let _ = write!(line, " ({})", monitor.source);
+        }
+    }
+    Some(line)
+}
+
+fn is_scaled(scale: f64) -> bool {
+    (scale - 1.0).abs() > 0.01
+}
+
+/// The scale the whole desktop draws at, from `Xft.dpi` or `GDK_SCALE`.
+fn desktop_scale(config: &ComputerUseConfig) -> Option<(f64, &'static str)> {
+    let dpi = which("xrdb")
+        .ok()
+        .and_then(|xrdb| gui_command(&xrdb, config).arg("-query").output().ok())
+        .and_then(|output| parse_xft_dpi(&String::from_utf8_lossy(&output.stdout)))
+        .map(|dpi| (dpi / BASE_DPI, "Xft.dpi"));
+    let gdk = std::env::var("GDK_SCALE")
+        .ok()
+        .and_then(|scale| scale.trim().parse::<f64>().ok())
+        .map(|scale| (scale, "GDK_SCALE"));
+    dpi.into_iter()
+        .chain(gdk)
+        .find(|(scale, _)| scale.is_finite() && *scale > 0.0 && is_scaled(*scale))
+}
+
+/// Screen pixels per mode pixel, when `xrandr --scale` or a transform
+/// stretches the output.
+fn transform_scale(output: &ActiveOutput) -> Option<f64> {
+    let (mode_width, mode_height) = output.mode?;
+    let mode_width = if output.rotation.is_sideways() {
+        mode_height
+    } else {
+        mode_width
+    };
+    let scale = f64::from(output.geometry.width) / f64::from(mode_width);
+    (mode_width > 0 && is_scaled(scale)).then_some(scale)
+}
+
+/// Reads the active outputs of `xrandr --query`: the output line, e.g.
+/// `eDP-1 connected primary 2880x1620+0+0 left (normal left ...)`, then the
+/// indented mode lines, with `*` marking the current mode.
+fn parse_active_outputs(query: &str) -> Vec<ActiveOutput> {
+    let mut outputs: Vec<ActiveOutput> = Vec::new();
+    let mut current: Option<usize> = None;
+    for line in query.lines() {
+        if line.starts_with(char::is_whitespace) {
+            let Some(index) = current else {
+                continue;
+            };
+            let mut words = line.split_whitespace();
+            let (Some(mode), true) = (words.next(), line.contains('*')) else {
+                continue;
+            };
+            if outputs[index].mode.is_none() {
+                outputs[index].mode = mode.split_once('x').and_then(|(width, height)| {
+                    let height = height.trim_end_matches(|c: char| !c.is_ascii_digit());
+                    Some((width.parse().ok()?, height.parse().ok()?))
+                });
+            }
+            continue;
+        }
+        current = None;
+        let mut words = line
+            .split_whitespace()
+            .take_while(|word| !word.starts_with('('));
+        let (Some(name), Some("connected")) = (words.next(), words.next()) else {
+            continue;
+        };
+        let words: Vec<&str> = words.collect();
+        let Some(geometry) = words.iter().find_map(|word| word.parse::<Viewport>().ok()) else {
+            continue;
+        };
+        // The rotation, when not `normal`, follows the geometry.
+        let rotation = words
+            .iter()
+            .find_map(|word| match *word {
+                "left" => Some(Rotation::Left),
+                "inverted" => Some(Rotation::Inverted),
+                "right" => Some(Rotation::Right),
+                _ => None,
+            })
+            .unwrap_or_default();
+        current = Some(outputs.len());
+        outputs.push(ActiveOutput {
+            name: name.to_string(),
+            geometry,
+            mode: None,
+            rotation,
+        });
+    }
+    outputs
+}
+
+/// The `Xft.dpi` resource in `xrdb -query` output.
+fn parse_xft_dpi(resources: &str) -> Option<f64> {
+    resources.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        (name.trim() == "Xft.dpi")
+            .then(|| value.trim().parse().ok())
+            .flatten()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn detects_scale_sources_and_grid_mismatches() {
+        let query = "\
+Screen 0: minimum 8 x 8, current 4800 x 1920, maximum 32767 x 32767
+eDP-1 connected primary 2880x1620+0+0 (normal left inverted right x axis y axis) 344mm x 193mm
+   1920x1080     60.00*+  59.97
+   1280x720      60.00
+HDMI-1 connected 1080x1920+2880+0 left (normal left inverted right x axis y axis) 527mm x 296mm
+   1920x1080     60.00*+
+DP-1 disconnected (normal left inverted right x axis y axis)
+";
+        let outputs = parse_active_outputs(query);
+        assert_eq!(
+            outputs
+                .iter()
+                .map(|output| (output.name.as_str(), output.mode, output.rotation))
+                .collect::<Vec<_>>(),
+            vec![
+                ("eDP-1", Some((1920, 1080)), Rotation::Normal),
+                ("HDMI-1", Some((1920, 1080)), Rotation::Left),
+            ]
+        );
+        assert_eq!(transform_scale(&outputs[0]), Some(1.5));
+        assert_eq!(transform_scale(&outputs[1]), None);
+
+        assert_eq!(
+            parse_xft_dpi("Xcursor.size:\t24\nXft.dpi:\t144\n"),
+            Some(144.0)
+        );
+        assert_eq!(parse_xft_dpi("Xcursor.size:\t24\n"), None);
+
+        let monitors = [
+            MonitorScale {
+                name: "eDP-1".to_string(),
+                geometry: outputs[0].geometry,
+                scale: 1.5,
+                source: "xrandr scaling",
+            },
+            MonitorScale {
+                name: "HDMI-1".to_string(),
+                geometry: outputs[1].geometry,
+                scale: 1.0,
+                source: "unscaled",
+            },
+        ];
+        assert_eq!(
+            describe(&monitors).as_deref(),
+            Some("scaled monitors: eDP-1 1.5x (xrandr scaling), HDMI-1 1x")
+        );
+        assert_eq!(describe(&monitors[1..]), None);
+
+        let config = ComputerUseConfig {
+            display: Some(":computex-no-such-display".to_string()),
+            display_scale: Some(1.25),
+            ..ComputerUseConfig::default()
+        };
+        let crop = Viewport {
+            width: 400,
+            height: 300,
+            x: 10,
+            y: 20,
+        };
+        assert_eq!(
+            input_grid(&config, Some(crop), 800, 600),
+            Some(Resolution {
+                width: 400,
+                height: 300
+            })
+        );
+        assert_eq!(input_grid(&config, Some(crop), 401, 300), None);
+        assert_eq!(
+            input_grid(&config, None, 2400, 1500),
+            Some(Resolution {
+                width: 1920,
+                height: 1200
+            })
+        );
+        let unconfigured = ComputerUseConfig {
+            display_scale: None,
+            ..config
+        };
+        assert_eq!(input_grid(&unconfigured, None, 2400, 1500), None);
+    }
+}
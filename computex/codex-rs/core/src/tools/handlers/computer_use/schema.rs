@@ -0,0 +1,1173 @@
+//! Argument types for the computer-use tools, and the JSON Schemas derived
+//! from them.
+//!
+//! Each tool's parameters are defined once, as a serde struct whose doc
+//! comments become the advertised descriptions. The same schema is checked
+//! against the model's arguments before a tool runs, so a bad call gets a
+//! precise error (`keys[1]: expected string, got number`) instead of a serde
+//! message. Descriptions may use `{space}`, `{delegate_max_actions}`, and
+//! `{delegate_timeout_secs}`, which are filled in from the configuration.
+
+use schemars::JsonSchema;
+use schemars::r#gen::SchemaSettings;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value as JsonValue;
+
+use super::DELEGATE_TOOL;
+use super::detail::ScreenshotDetail;
+use crate::config::types::ComputerUseConfig;
+use crate::config::types::TypingVerification;
+use crate::function_tool::FunctionCallError;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub(super) enum MouseButton {
+    #[default]
+    Left,
+    Middle,
+    Right,
+}
+
+impl MouseButton {
+    /// xdotool button number.
+    pub(super) fn xdotool_button(self) -> &'static str {
+        match self {
+            MouseButton::Left => "1",
+            MouseButton::Middle => "2",
+            MouseButton::Right => "3",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub(super) enum ScrollDirection {
+    Up,
+    Down,
+}
+
+impl ScrollDirection {
+    pub(super) fn name(self) -> &'static str {
+        match self {
+            ScrollDirection::Up => "up",
+            ScrollDirection::Down => "down",
+        }
+    }
+
+    /// xdotool wheel button number.
+    pub(super) fn xdotool_button(self) -> &'static str {
+        match self {
+            ScrollDirection::Up => "4",
+            ScrollDirection::Down => "5",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub(super) enum PinchDirection {
+    In,
+    Out,
+}
+
+impl PinchDirection {
+    /// Pinching out (fingers apart) zooms in.
+    pub(super) fn zooms_in(self) -> bool {
+        self == PinchDirection::Out
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum LandmarkAction {
+    Save,
+    Find,
+    List,
+    Forget,
+}
+
+#[derive(Deserialize, JsonSchema, Default)]
+#[serde(deny_unknown_fields)]
+pub(super) struct ScreenshotArgs {
+    /// low (small 640x360 image for orientation), high (native resolution,
+    /// for small text; combine with a region), or auto (default; picks from
+    /// how well computer_find_text read the screen this turn).
+    #[serde(default)]
+    pub detail: ScreenshotDetail,
+    /// Left edge of the region to capture, in {space}. Optional; give all
+    /// four of x, y, width, and height to capture only that region at high
+    /// detail.
+    pub x: Option<f64>,
+    /// Top edge of the region to capture, in {space}.
+    pub y: Option<f64>,
+    /// Region width in {space}.
+    pub width: Option<f64>,
+    /// Region height in {space}.
+    pub height: Option<f64>,
+}
+
+impl ScreenshotArgs {
+    /// The requested region as `(x, y, width, height)` in model space.
+    pub(super) fn region(&self) -> Result<Option<(f64, f64, f64, f64)>, FunctionCallError> {
+        match (self.x, self.y, self.width, self.height) {
+            (Some(x), Some(y), Some(width), Some(height)) => Ok(Some((x, y, width, height))),
+            (None, None, None, None) => Ok(None),
+            _ => Err(FunctionCallError::RespondToModel(
+                "computer_screenshot region needs all of x, y, width, and height".to_string(),
+            )),
+        }
+    }
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct ClickArgs {
+    /// X coordinate in {space}.
+    pub x: f64,
+    /// Y coordinate in {space}.
+    pub y: f64,
+    /// Mouse button: left (default), right, or middle.
+    pub button: Option<MouseButton>,
+    /// Double-click when true.
+    pub double: Option<bool>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct ContextMenuArgs {
+    /// X coordinate to right-click in {space}.
+    pub x: f64,
+    /// Y coordinate to right-click in {space}.
+    pub y: f64,
+    /// Label of the menu entry to choose, e.g. "Copy link".
+    pub item: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct DragArgs {
+    /// Start X coordinate in {space}.
+    pub from_x: f64,
+    /// Start Y coordinate in {space}.
+    pub from_y: f64,
+    /// End X coordinate in {space}.
+    pub to_x: f64,
+    /// End Y coordinate in {space}.
+    pub to_y: f64,
+    /// Mouse button: left (default), right, or middle.
+    pub button: Option<MouseButton>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum HeldActionKind {
+    Move,
+    Click,
+    Drag,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct HeldAction {
+    /// move (pointer only), click, or drag (press at x,y and release at
+    /// to_x,to_y).
+    pub action: HeldActionKind,
+    /// X coordinate in {space}; the start point for drag.
+    pub x: f64,
+    /// Y coordinate in {space}; the start point for drag.
+    pub y: f64,
+    /// End X coordinate in {space} (drag only).
+    pub to_x: Option<f64>,
+    /// End Y coordinate in {space} (drag only).
+    pub to_y: Option<f64>,
+    /// Mouse button for click and drag: left (default), right, or middle.
+    pub button: Option<MouseButton>,
+    /// Double-click when true (click only).
+    pub double: Option<bool>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct WithModifiersArgs {
+    /// Modifier keys to hold, e.g. ["shift"]: ctrl, shift, alt, super, or
+    /// cmd (the target OS's primary modifier).
+    pub modifiers: Vec<String>,
+    /// Pointer actions to perform in order while the modifiers are held (at
+    /// most 20).
+    pub actions: Vec<HeldAction>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct ScrollArgs {
+    /// Scroll direction: up or down.
+    pub direction: ScrollDirection,
+    /// Number of scroll ticks (defaults to 3).
+    pub amount: Option<u32>,
+    /// Optional X coordinate in {space}.
+    pub x: Option<f64>,
+    /// Optional Y coordinate in {space}.
+    pub y: Option<f64>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct SwipeArgs {
+    /// Start X coordinate in {space}.
+    pub from_x: f64,
+    /// Start Y coordinate in {space}.
+    pub from_y: f64,
+    /// End X coordinate in {space}.
+    pub to_x: f64,
+    /// End Y coordinate in {space}.
+    pub to_y: f64,
+    /// How long the swipe takes in milliseconds (default 300).
+    pub duration_ms: Option<u64>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct LongPressArgs {
+    /// X coordinate in {space}.
+    pub x: f64,
+    /// Y coordinate in {space}.
+    pub y: f64,
+    /// How long to hold in milliseconds (default 800).
+    pub duration_ms: Option<u64>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct PinchArgs {
+    /// X coordinate of the pinch center in {space}.
+    pub x: f64,
+    /// Y coordinate of the pinch center in {space}.
+    pub y: f64,
+    /// out (fingers apart, zoom in) or in (fingers together, zoom out).
+    pub direction: PinchDirection,
+    /// Zoom steps (default 3).
+    pub amount: Option<u32>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct TypeArgs {
+    /// Text to type.
+    pub text: String,
+    /// Optional delay between keystrokes in milliseconds.
+    pub delay_ms: Option<u64>,
+    /// Read the last typed line back to catch dropped or substituted
+    /// characters. Defaults to the configured `verify_typing`.
+    pub verify: Option<TypeVerification>,
+    /// Window the text is meant for: its class (e.g. "firefox") or part of
+    /// its title. Nothing is typed into any other window.
+    pub window: Option<String>,
+    /// Set only when the user asked you to type this secret. Typing into a
+    /// password field fails without it, and it turns off `verify`.
+    pub confirm_secret: Option<bool>,
+}
+
+/// How `computer_type` reads the typed text back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum TypeVerification {
+    /// Do not check.
+    Off,
+    /// Select the line, copy it and compare the clipboard. Exact, but needs a
+    /// field that allows copying.
+    Clipboard,
+    /// Look for the line on screen with OCR. Works anywhere but only catches
+    /// larger differences.
+    Ocr,
+}
+
+impl From<TypeVerification> for TypingVerification {
+    fn from(verify: TypeVerification) -> Self {
+        match verify {
+            TypeVerification::Off => TypingVerification::Off,
+            TypeVerification::Clipboard => TypingVerification::Clipboard,
+            TypeVerification::Ocr => TypingVerification::Ocr,
+        }
+    }
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct KeyArgs {
+    /// Key chord, e.g. ["ctrl", "c"]. Common spellings ("Esc", "PgDn",
+    /// "Win", "Strg") are mapped to X key names; unknown names are rejected.
+    /// "cmd" is the target OS's primary modifier, and a single named
+    /// shortcut ("copy", "paste", "new_tab", "address_bar", "undo") is
+    /// translated for the target OS.
+    pub keys: Vec<String>,
+    /// Required for destructive combos (Alt+F4, Ctrl+Q, Ctrl+W, etc.).
+    pub confirm: Option<bool>,
+    /// Window the keys are meant for: its class (e.g. "firefox") or part of
+    /// its title. No keys are sent to any other window.
+    pub window: Option<String>,
+}
+
+/// How `computer_clear_field` empties the focused input.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum ClearMethod {
+    /// Select everything in the field, then delete it.
+    #[default]
+    SelectAll,
+    /// Move to the end, then press Backspace `count` times. For fields where
+    /// select-all selects the whole page instead.
+    Backspace,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct ClearFieldArgs {
+    /// "select_all" (default) or "backspace".
+    pub method: Option<ClearMethod>,
+    /// Backspaces to press with "backspace" (default 100). Ignored for
+    /// "select_all".
+    pub count: Option<u32>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct FocusArgs {
+    /// How many times to move focus (default 1).
+    pub count: Option<u32>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct FindTextArgs {
+    /// Visible text to locate, e.g. "Submit". Matching is case-insensitive
+    /// and tolerates OCR errors.
+    pub query: String,
+    /// Minimum fuzzy similarity between 0 and 1 (default 0.8). Lower it to
+    /// catch misread text.
+    pub min_similarity: Option<f64>,
+    /// Maximum number of matches to return (default 10).
+    pub max_results: Option<usize>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct DetectElementsArgs {
+    /// Only return elements of this kind, e.g. "button", "input" or "icon".
+    /// The kinds depend on the detector's model.
+    pub label: Option<String>,
+    /// Minimum detector confidence between 0 and 1 (default 0.5).
+    pub min_confidence: Option<f64>,
+    /// Maximum number of elements to return (default 20).
+    pub max_results: Option<usize>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct DomQueryArgs {
+    /// CSS selector, e.g. "button", "a[href*=login]" or "#email".
+    pub selector: String,
+    /// Only elements whose text, value, or label contains this
+    /// (case-insensitive).
+    pub text: Option<String>,
+    /// Maximum number of elements to return (default 20).
+    pub limit: Option<u32>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct DomClickArgs {
+    /// CSS selector, e.g. "button[type=submit]".
+    pub selector: String,
+    /// Only elements whose text, value, or label contains this
+    /// (case-insensitive).
+    pub text: Option<String>,
+    /// Which match to click, as the `[index]` from `computer_dom_query`
+    /// (default 0, the first).
+    pub index: Option<u32>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct LocateArgs {
+    /// What to find, e.g. "Save button" or "search field". Matched against
+    /// accessible names, on-screen text, template names, and detected
+    /// elements.
+    pub query: String,
+    /// Minimum confidence between 0 and 1 for every strategy. By default
+    /// each strategy uses its own threshold.
+    pub min_confidence: Option<f64>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct WorkspaceSwitchArgs {
+    /// Workspace number from computer_workspace_list, starting at 1.
+    pub workspace: u32,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct WorkspaceMoveWindowArgs {
+    /// Workspace number to move the window to, starting at 1.
+    pub workspace: u32,
+    /// Window to move: its class (e.g. "firefox") or part of its title, on
+    /// any workspace. Defaults to the focused window.
+    pub window: Option<String>,
+    /// Switch to the target workspace afterwards (default false).
+    pub follow: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum TrayActionArg {
+    /// What a left click on the icon does, usually showing the app's window.
+    #[default]
+    Activate,
+    /// What a middle click does.
+    SecondaryActivate,
+    /// List the item's menu entries, or pop the menu up when the app
+    /// doesn't publish it.
+    ContextMenu,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct TrayActivateArgs {
+    /// Tray item id or title from computer_tray_list, or part of one.
+    pub item: String,
+    /// What to do with the item (default "activate"). Ignored when
+    /// `menu_item` is set.
+    pub action: Option<TrayActionArg>,
+    /// Menu entry to trigger, with submenus separated by ">", e.g.
+    /// "VPN Connections > Configure VPN".
+    pub menu_item: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum ShortcutScope {
+    /// Both the desktop's shortcuts and the focused app's.
+    #[default]
+    All,
+    /// Global shortcuts from the GNOME or KDE settings.
+    Desktop,
+    /// Accelerators of the focused app's menu items.
+    App,
+}
+
+#[derive(Default, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct ListShortcutsArgs {
+    /// Which shortcuts to list (default "all").
+    pub scope: Option<ShortcutScope>,
+    /// Only list shortcuts whose keys or action contain this text, e.g.
+    /// "save" or "maximize".
+    pub query: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct CompareArgs {
+    /// PNG to compare against, relative to the working directory. If it
+    /// doesn't exist, the current capture is saved there as the baseline.
+    pub baseline_path: String,
+    /// Left edge of the region to compare, in {space}. Give all four of x,
+    /// y, width, and height to compare only that region; the baseline must
+    /// have been captured from the same region.
+    pub x: Option<f64>,
+    /// Top edge of the region, in {space}.
+    pub y: Option<f64>,
+    /// Region width in {space}.
+    pub width: Option<f64>,
+    /// Region height in {space}.
+    pub height: Option<f64>,
+    /// Per-channel color difference (0-255) still treated as unchanged
+    /// (default 16).
+    pub tolerance: Option<u8>,
+}
+
+impl CompareArgs {
+    /// The requested region as `(x, y, width, height)` in model space.
+    pub(super) fn region(&self) -> Result<Option<(f64, f64, f64, f64)>, FunctionCallError> {
+        match (self.x, self.y, self.width, self.height) {
+            (Some(x), Some(y), Some(width), Some(height)) => Ok(Some((x, y, width, height))),
+            (None, None, None, None) => Ok(None),
+            _ => Err(FunctionCallError::RespondToModel(
+                "computer_compare region needs all of x, y, width, and height".to_string(),
+            )),
+        }
+    }
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct LandmarkArgs {
+    /// save (remember label at x,y), find (look up labels containing
+    /// `label`), list, or forget.
+    pub action: LandmarkAction,
+    /// Short name of the UI element, e.g. "Submit button". Required for save,
+    /// find, and forget.
+    pub label: Option<String>,
+    /// X coordinate in {space} (save only).
+    pub x: Option<f64>,
+    /// Y coordinate in {space} (save only).
+    pub y: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum MagnifierAction {
+    Enable,
+    Disable,
+    Zoom,
+    Status,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct MagnifierArgs {
+    /// enable, disable, zoom (change the magnification to `factor`), or
+    /// status.
+    pub action: MagnifierAction,
+    /// Magnification from 1 to 32, e.g. 2 for double size. Required for
+    /// zoom; optional for enable.
+    pub factor: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum SystemSetting {
+    /// Volume of the default audio output, in percent.
+    Volume,
+    /// Whether the default audio output is muted.
+    Mute,
+    /// Screen backlight brightness, in percent.
+    Brightness,
+}
+
+impl SystemSetting {
+    pub(super) fn label(self) -> &'static str {
+        match self {
+            SystemSetting::Volume => "volume",
+            SystemSetting::Mute => "mute state",
+            SystemSetting::Brightness => "brightness",
+        }
+    }
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct SystemControlArgs {
+    pub setting: SystemSetting,
+    /// New value: a percentage from 0 to 100 for volume and brightness, or
+    /// 1 to mute and 0 to unmute. Omit it to read the current value.
+    pub value: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum ThemeName {
+    Dark,
+    Light,
+    HighContrast,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct SetThemeArgs {
+    /// dark, light, or high_contrast.
+    pub theme: ThemeName,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct AnnounceArgs {
+    /// Short sentence to speak, e.g. "Computex is taking over the mouse in
+    /// five seconds."
+    pub text: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct CameraSnapshotArgs {
+    /// V4L2 device to read, e.g. "/dev/video0" (the default) or "video2".
+    pub device: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct PrintDialogArgs {
+    /// Absolute path of the PDF to write, e.g. "/home/user/report.pdf". It
+    /// must not exist yet.
+    pub path: String,
+    /// Press the print shortcut first to open the dialog. Defaults to false,
+    /// for a dialog that is already open.
+    pub open: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum OfficeAction {
+    Status,
+    Open,
+    GotoCell,
+    ExportPdf,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct OfficeArgs {
+    /// status (list open documents), open (open `path`), goto_cell (select
+    /// `cell` in the focused spreadsheet), or export_pdf (export the focused
+    /// document to `path`).
+    pub action: OfficeAction,
+    /// Absolute path: the document to open, or the new PDF to write.
+    pub path: Option<String>,
+    /// Cell, range, or named range for goto_cell, e.g. "B7", "A1:C3", or
+    /// "Sheet2.B7".
+    pub cell: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum FileDialogAction {
+    Open,
+    Save,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct FileDialogArgs {
+    /// open (pick an existing file or folder) or save (name the file to
+    /// write).
+    pub action: FileDialogAction,
+    /// Absolute path to enter, e.g. "/home/user/data.csv". For open it must
+    /// exist; for save its directory must.
+    pub path: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct MenuSelectArgs {
+    /// Menu labels from the menu bar down, e.g. ["File", "Export", "PDF"].
+    /// Each is reached with its access key, the label's first letter unless
+    /// an underscore marks another, as in "E_xport".
+    pub path: Vec<String>,
+    /// Expected focused window: its class (e.g. "libreoffice") or part of
+    /// its title.
+    pub window: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct WaitDownloadArgs {
+    /// Absolute directory the browser saves into. Defaults to the XDG
+    /// download directory, usually ~/Downloads.
+    pub dir: Option<String>,
+    /// Glob the finished file name must match, e.g. "*.csv". Defaults to
+    /// "*".
+    pub pattern: Option<String>,
+    /// Seconds to wait (default 60, maximum 600).
+    pub timeout_seconds: Option<u64>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct WaitAppIdleArgs {
+    /// Process to watch, together with its child processes.
+    pub pid: Option<u32>,
+    /// Window class of the app to watch instead, e.g. "gimp"; its first
+    /// window's process is used. Omit both to watch the focused window's
+    /// process.
+    pub wm_class: Option<String>,
+    /// CPU use, in percent of one core, under which the app counts as idle
+    /// (default 5).
+    pub threshold: Option<f64>,
+    /// Seconds to wait (default 60, maximum 600).
+    pub timeout_seconds: Option<u64>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct SetTargetArgs {
+    /// Window to work in: its class (e.g. "firefox") or part of its title.
+    /// Omit to clear the target.
+    pub window: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct WatchDemoArgs {
+    /// What the user is about to demonstrate, shown to them while recording,
+    /// e.g. "export the report as CSV".
+    pub task: Option<String>,
+    /// Longest recording in seconds (default 120, maximum 600).
+    pub max_seconds: Option<u64>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct RecentFramesArgs {
+    /// How many of the newest frames to return (default 3, maximum 10).
+    pub n: Option<usize>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct BatchArgs {
+    /// Actions to run in order. The batch stops at the first action that
+    /// fails.
+    pub steps: Vec<BatchStep>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct BatchStep {
+    /// The input tool to run, e.g. "computer_click" or "computer_key".
+    pub tool: String,
+    /// The tool's arguments, as it takes them on its own.
+    #[serde(default)]
+    pub arguments: serde_json::Map<String, JsonValue>,
+    /// Run the step only if a window whose class or title contains this
+    /// exists.
+    pub if_window_exists: Option<String>,
+    /// Run the step only if this text is on screen.
+    pub if_text_visible: Option<String>,
+    /// Run the step only if the pixel at a point has a color.
+    pub if_pixel: Option<PixelCondition>,
+    /// Try the step again when its condition doesn't hold yet or it fails.
+    pub retry: Option<RetryArgs>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct PixelCondition {
+    /// X coordinate in {space}.
+    pub x: f64,
+    /// Y coordinate in {space}.
+    pub y: f64,
+    /// Expected color as "#rrggbb".
+    pub color: String,
+    /// Largest difference per channel that still matches (default 16).
+    pub tolerance: Option<u8>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct RetryArgs {
+    /// How many times to try the step in total (maximum 10).
+    pub attempts: u32,
+    /// Pause between tries in milliseconds (default 500, maximum 5000).
+    pub delay_ms: Option<u64>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct CheckpointArgs {
+    /// What the checkpoint protects against, e.g. "before running the
+    /// installer".
+    pub label: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct DelegateArgs {
+    /// Self-contained GUI subtask, e.g. "click through the installer at
+    /// ~/Downloads/setup.run with default options". Say what to report back;
+    /// the sub-session sees nothing else from this conversation.
+    pub task: String,
+    /// GUI tool calls the sub-session may make, screenshots included
+    /// (default and maximum {delegate_max_actions}).
+    pub max_actions: Option<u32>,
+    /// Wall-clock budget in seconds (default and maximum
+    /// {delegate_timeout_secs}).
+    pub timeout_seconds: Option<u64>,
+}
+
+/// Tools that take no arguments.
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(super) struct NoArgs {}
+
+/// JSON Schema for `T`, with every subschema inlined so it can be advertised
+/// and checked without resolving references.
+fn schema_of<T: JsonSchema>() -> JsonValue {
+    let settings = SchemaSettings::draft07().with(|settings| {
+        settings.inline_subschemas = true;
+        settings.meta_schema = None;
+    });
+    let schema = settings.into_generator().into_root_schema_for::<T>();
+    serde_json::to_value(schema).unwrap_or(JsonValue::Null)
+}
+
+/// The parameter schema for `tool_name`, or `None` for tools served elsewhere.
+fn tool_schema(tool_name: &str) -> Option<JsonValue> {
+    let schema = match tool_name {
+        "computer_screenshot" => schema_of::<ScreenshotArgs>(),
+        "computer_click" => schema_of::<ClickArgs>(),
+        "computer_drag" => schema_of::<DragArgs>(),
+        "computer_scroll" => schema_of::<ScrollArgs>(),
+        "computer_with_modifiers" => schema_of::<WithModifiersArgs>(),
+        "computer_swipe" => schema_of::<SwipeArgs>(),
+        "computer_long_press" => schema_of::<LongPressArgs>(),
+        "computer_pinch" => schema_of::<PinchArgs>(),
+        "computer_type" => schema_of::<TypeArgs>(),
+        "computer_key" => schema_of::<KeyArgs>(),
+        "computer_clear_field" => schema_of::<ClearFieldArgs>(),
+        "computer_focus_next" | "computer_focus_prev" => schema_of::<FocusArgs>(),
+        "computer_find_text" => schema_of::<FindTextArgs>(),
+        "computer_detect_elements" => schema_of::<DetectElementsArgs>(),
+        "computer_dom_query" => schema_of::<DomQueryArgs>(),
+        "computer_dom_click" => schema_of::<DomClickArgs>(),
+        "computer_locate" => schema_of::<LocateArgs>(),
+        "computer_compare" => schema_of::<CompareArgs>(),
+        "computer_landmarks" => schema_of::<LandmarkArgs>(),
+        "computer_checkpoint" => schema_of::<CheckpointArgs>(),
+        "computer_print_dialog_handle" => schema_of::<PrintDialogArgs>(),
+        "computer_file_dialog" => schema_of::<FileDialogArgs>(),
+        "computer_office" => schema_of::<OfficeArgs>(),
+        "computer_menu_select" => schema_of::<MenuSelectArgs>(),
+        "computer_context_menu" => schema_of::<ContextMenuArgs>(),
+        "computer_wait_download" => schema_of::<WaitDownloadArgs>(),
+        "computer_wait_app_idle" => schema_of::<WaitAppIdleArgs>(),
+        "computer_set_target" => schema_of::<SetTargetArgs>(),
+        "computer_watch_demo" => schema_of::<WatchDemoArgs>(),
+        "computer_recent_frames" => schema_of::<RecentFramesArgs>(),
+        "computer_batch" => schema_of::<BatchArgs>(),
+        "computer_magnifier" => schema_of::<MagnifierArgs>(),
+        "computer_set_theme" => schema_of::<SetThemeArgs>(),
+        "computer_system_control" => schema_of::<SystemControlArgs>(),
+        "computer_announce" => schema_of::<AnnounceArgs>(),
+        "computer_camera_snapshot" => schema_of::<CameraSnapshotArgs>(),
+        "computer_workspace_switch" => schema_of::<WorkspaceSwitchArgs>(),
+        "computer_workspace_move_window" => schema_of::<WorkspaceMoveWindowArgs>(),
+        "computer_tray_activate" => schema_of::<TrayActivateArgs>(),
+        "computer_list_shortcuts" => schema_of::<ListShortcutsArgs>(),
+        "computer_browser_state"
+        | "computer_active_window"
+        | "computer_workspace_list"
+        | "computer_tray_list"
+        | "computer_network_state"
+        | "computer_self_test"
+        | "computer_unlock"
+        | "computer_run_queue" => schema_of::<NoArgs>(),
+        DELEGATE_TOOL => schema_of::<DelegateArgs>(),
+        _ => return None,
+    };
+    Some(schema)
+}
+
+/// The parameter schema advertised for `tool_name`, with placeholders in its
+/// descriptions filled in from `config`.
+pub(crate) fn tool_parameters(tool_name: &str, config: &ComputerUseConfig) -> Option<JsonValue> {
+    let mut schema = tool_schema(tool_name)?;
+    if let JsonValue::Object(root) = &mut schema {
+        // Type-level metadata means nothing to the model.
+        root.remove("title");
+        root.remove("description");
+    }
+    let substitutions = [
+        ("{space}", config.coordinate_label()),
+        (
+            "{delegate_max_actions}",
+            config.delegate_max_actions.to_string(),
+        ),
+        (
+            "{delegate_timeout_secs}",
+            config.delegate_timeout_secs.to_string(),
+        ),
+    ];
+    fill_descriptions(&mut schema, &substitutions);
+    Some(schema)
+}
+
+fn fill_descriptions(value: &mut JsonValue, substitutions: &[(&str, String)]) {
+    match value {
+        JsonValue::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                match child {
+                    JsonValue::String(text) if key == "description" => {
+                        for (placeholder, replacement) in substitutions {
+                            *text = text.replace(placeholder, replacement);
+                        }
+                    }
+                    _ => fill_descriptions(child, substitutions),
+                }
+            }
+        }
+        JsonValue::Array(items) => {
+            for item in items {
+                fill_descriptions(item, substitutions);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Parses `arguments` into `T` after checking them against `T`'s schema.
+pub(super) fn parse_args<T: DeserializeOwned + JsonSchema>(
+    arguments: &str,
+) -> Result<T, FunctionCallError> {
+    let value = parse_json(arguments)?;
+    check(&schema_of::<T>(), &value)?;
+    serde_json::from_value(value).map_err(|err| {
+        FunctionCallError::RespondToModel(format!("failed to parse function arguments: {err}"))
+    })
+}
+
+/// Checks `arguments` against `tool_name`'s schema without running it.
+pub(super) fn check_args(tool_name: &str, arguments: &str) -> Result<(), FunctionCallError> {
+    let Some(schema) = tool_schema(tool_name) else {
+        return Ok(());
+    };
+    check(&schema, &parse_json(arguments)?)
+}
+
+fn parse_json(arguments: &str) -> Result<JsonValue, FunctionCallError> {
+    serde_json::from_str(arguments).map_err(|err| {
+        FunctionCallError::RespondToModel(format!(
+            "failed to parse function arguments as JSON: {err}"
+        ))
+    })
+}
+
+fn check(schema: &JsonValue, value: &JsonValue) -> Result<(), FunctionCallError> {
+    let mut errors = Vec::new();
+    validate(schema, value, "", &mut errors);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(FunctionCallError::RespondToModel(format!(
+            "invalid arguments: {}",
+            errors.join("; ")
+        )))
+    }
+}
+
+/// Validates `value` against the JSON Schema subset schemars emits for the
+/// argument types: `type`, `enum`, `minimum`/`maximum`, unsigned `format`s,
+/// object properties, array items, and `anyOf`. Errors name the offending
+/// path, e.g. `keys[1]`.
+fn validate(schema: &JsonValue, value: &JsonValue, path: &str, errors: &mut Vec<String>) {
+    let JsonValue::Object(schema) = schema else {
+        return;
+    };
+    let at = |path: &str| {
+        if path.is_empty() {
+            "arguments".to_string()
+        } else {
+            format!("`{path}`")
+        }
+    };
+
+    if let Some(JsonValue::Array(branches)) = schema.get("anyOf") {
+        let mut first_errors = None;
+        for branch in branches {
+            let mut branch_errors = Vec::new();
+            validate(branch, value, path, &mut branch_errors);
+            if branch_errors.is_empty() {
+                first_errors = None;
+                break;
+            }
+            first_errors.get_or_insert(branch_errors);
+        }
+        if let Some(branch_errors) = first_errors {
+            errors.extend(branch_errors);
+            return;
+        }
+    }
+
+    if let Some(types) = schema.get("type") {
+        let allowed: Vec<&str> = match types {
+            JsonValue::String(ty) => vec![ty.as_str()],
+            JsonValue::Array(types) => types.iter().filter_map(JsonValue::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !allowed.is_empty() && !allowed.iter().any(|ty| has_type(value, ty)) {
+            let non_null: Vec<&str> = allowed.into_iter().filter(|ty| *ty != "null").collect();
+            errors.push(format!(
+                "{}: expected {}, got {}",
+                at(path),
+                non_null.join(" or "),
+                type_name(value)
+            ));
+            return;
+        }
+    }
+
+    if let Some(JsonValue::Array(choices)) = schema.get("enum")
+        && !choices.contains(value)
+    {
+        let choices: Vec<String> = choices.iter().map(JsonValue::to_string).collect();
+        errors.push(format!(
+            "{}: expected one of {}, got {value}",
+            at(path),
+            choices.join(", ")
+        ));
+        return;
+    }
+
+    if let Some(number) = value.as_f64() {
+        if let Some(minimum) = schema.get("minimum").and_then(JsonValue::as_f64)
+            && number < minimum
+        {
+            errors.push(format!(
+                "{}: must be at least {minimum}, got {value}",
+                at(path)
+            ));
+        }
+        if let Some(maximum) = schema.get("maximum").and_then(JsonValue::as_f64)
+            && number > maximum
+        {
+            errors.push(format!(
+                "{}: must be at most {maximum}, got {value}",
+                at(path)
+            ));
+        }
+        let limit = match schema.get("format").and_then(JsonValue::as_str) {
+            Some("uint8") => Some(u64::from(u8::MAX)),
+            Some("uint16") => Some(u64::from(u16::MAX)),
+            Some("uint32") => Some(u64::from(u32::MAX)),
+            _ => None,
+        };
+        if let Some(limit) = limit
+            && value.as_u64().is_some_and(|number| number > limit)
+        {
+            errors.push(format!(
+                "{}: must be at most {limit}, got {value}",
+                at(path)
+            ));
+        }
+    }
+
+    if let JsonValue::Object(fields) = value {
+        let properties = schema.get("properties").and_then(JsonValue::as_object);
+        if let Some(JsonValue::Array(required)) = schema.get("required") {
+            for name in required.iter().filter_map(JsonValue::as_str) {
+                if !fields.contains_key(name) {
+                    errors.push(format!("missing required {}", at(&join(path, name))));
+                }
+            }
+        }
+        for (name, field) in fields {
+            match properties.and_then(|properties| properties.get(name)) {
+                Some(field_schema) => validate(field_schema, field, &join(path, name), errors),
+                None if schema.get("additionalProperties") == Some(&JsonValue::Bool(false)) => {
+                    let known: Vec<&str> = properties
+                        .map(|properties| properties.keys().map(String::as_str).collect())
+                        .unwrap_or_default();
+                    let expected = if known.is_empty() {
+                        "this tool takes no arguments".to_string()
+                    } else {
+                        format!("expected one of {}", known.join(", "))
+                    };
+                    errors.push(format!(
+                        "unknown argument {}; {expected}",
+                        at(&join(path, name))
+                    ));
+                }
+                None => {}
+            }
+        }
+    }
+
+    if let (JsonValue::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+        for (index, item) in items.iter().enumerate() {
+            validate(item_schema, item, &format!("{path}[{index}]"), errors);
+        }
+    }
+}
+
+fn join(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{path}.{name}")
+    }
+}
+
+fn has_type(value: &JsonValue, ty: &str) -> bool {
+    match ty {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        // serde only reads whole numbers written without a fraction into
+        // integer fields.
+        "integer" => value.is_i64() || value.is_u64(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &JsonValue) -> &'static str {
+    match value {
+        JsonValue::Null => "null",
+        JsonValue::Bool(_) => "boolean",
+        JsonValue::Number(number) if number.is_f64() => "number",
+        JsonValue::Number(_) => "integer",
+        JsonValue::String(_) => "string",
+        JsonValue::Array(_) => "array",
+        JsonValue::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn error(tool_name: &str, arguments: JsonValue) -> String {
+        match check_args(tool_name, &arguments.to_string()) {
+            Err(FunctionCallError::RespondToModel(message)) => message,
+            other => panic!("expected a validation error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn advertised_schema_fills_in_the_coordinate_space() {
+        let schema =
+            tool_parameters("computer_click", &ComputerUseConfig::default()).expect("schema");
+        assert_eq!(
+            schema["properties"]["x"]["description"],
+            json!("X coordinate in 1280x720 space.")
+        );
+        assert_eq!(schema["required"], json!(["x", "y"]));
+        assert_eq!(schema["additionalProperties"], json!(false));
+        assert_eq!(schema.get("title"), None);
+    }
+
+    #[test]
+    fn errors_name_the_offending_path() {
+        assert_eq!(
+            error("computer_key", json!({"keys": ["ctrl", 3]})),
+            "invalid arguments: `keys[1]`: expected string, got integer"
+        );
+        assert_eq!(
+            error("computer_click", json!({"x": 1, "button": "top"})),
+            "invalid arguments: missing required `y`; `button`: expected one of \"left\", \"middle\", \"right\", got \"top\""
+        );
+        assert_eq!(
+            error("computer_scroll", json!({"direction": "up", "amount": -2})),
+            "invalid arguments: `amount`: must be at least 0, got -2"
+        );
+        assert_eq!(
+            error("computer_type", json!({"text": "hi", "speed": 2})),
+            "invalid arguments: unknown argument `speed`; expected one of confirm_secret, delay_ms, text, verify, window"
+        );
+    }
+
+    #[test]
+    fn valid_arguments_parse_into_typed_values() {
+        let args: ClickArgs =
+            parse_args(r#"{"x": 10, "y": 20.5, "button": "right", "double": null}"#)
+                .expect("valid click");
+        assert_eq!(args.button, Some(MouseButton::Right));
+        assert_eq!(args.double, None);
+        assert!(check_args("computer_self_test", "{}").is_ok());
+        assert!(check_args("computer_screenshot", r#"{"detail": "low"}"#).is_ok());
+    }
+}
@@ -0,0 +1,172 @@
+//! Screens described in text, for `computer_use.screenshot_format = "text"`.
+//!
+//! A model without image input can't look at a screenshot, but most of what
+//! it acts on is the text on screen and where that text sits. The screen is
+//! captured at native resolution and read with tesseract, like
+//! `computer_find_text`, and each recognized line is listed top to bottom
+//! with the part of the screen it is in and its center in model
+//! coordinates, under the focused window's class and title. Icons and
+//! controls without text don't show up, so the tool descriptions steer the
+//! model to `computer_find_text`, `computer_locate`, and the keyboard.
+
+use codex_utils_image::screenshot::RawImage;
+
+use super::find_text;
+use super::find_text::TextMatch;
+use super::pii;
+use super::recovery::TargetWindow;
+use super::recovery::xdotool_output;
+use super::require_command;
+use crate::config::types::ComputerUseConfig;
+use crate::function_tool::FunctionCallError;
+
+/// OCR lines less confident than this are mostly noise from icons and
+/// textures, so they are left out.
+const MIN_CONFIDENCE: f64 = 0.4;
+
+/// Lines beyond this many are counted but not listed.
+const MAX_LINES: usize = 200;
+
+/// Names of a 3x3 grid over the screen, row by row.
+const REGIONS: [&str; 9] = [
+    "top-left",
+    "top",
+    "top-right",
+    "left",
+    "center",
+    "right",
+    "bottom-left",
+    "bottom",
+    "bottom-right",
+];
+
+/// The text read off one capture.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct ScreenText {
+    /// Recognized lines, in pixels relative to the capture.
+    lines: Vec<TextMatch>,
+    /// The capture's size, for naming regions.
+    size: Option<(i64, i64)>,
+}
+
+/// Reads the text in the native-resolution capture at `image`.
+pub(super) fn read(image: &std::path::Path) -> Result<ScreenText, FunctionCallError> {
+    let size = RawImage::open(image)
+        .ok()
+        .map(|image| (i64::from(image.width), i64::from(image.height)));
+    let lines = find_text::text_lines(&find_text::recognize(image)?)
+        .into_iter()
+        .filter(|line| line.confidence >= MIN_CONFIDENCE)
+        .collect();
+    Ok(ScreenText { lines, size })
+}
+
+/// The focused window's class and title, when xdotool can tell.
+pub(super) fn focused_window(config: &ComputerUseConfig) -> Option<(String, String)> {
+    let xdotool = require_command("xdotool").ok()?;
+    let TargetWindow { id, class } = super::recovery::active_window(&xdotool, config).ok()?;
+    let title = xdotool_output(&xdotool, config, &["getwindowname", &id]).unwrap_or_default();
+    Some((class, title))
+}
+
+/// The tool result for `screen`. `to_model` maps a capture pixel to model
+/// coordinates.
+pub(super) fn describe(
+    config: &ComputerUseConfig,
+    screen: &ScreenText,
+    focused: Option<(String, String)>,
+    to_model: impl Fn(i64, i64) -> String,
+) -> String {
+    let mut out = vec![format!(
+        "screen as text (no image attached; positions in {})",
+        config.coordinate_label()
+    )];
+    if let Some((class, title)) = focused {
+        out.push(format!("focused window: `{class}` \"{title}\""));
+    }
+    if screen.lines.is_empty() {
+        out.push("no text was recognized on screen; the screen may show only graphics, or be blank or locked".to_string());
+        return out.join("\n");
+    }
+    let mut sorted: Vec<&TextMatch> = screen.lines.iter().collect();
+    sorted.sort_by_key(|line| (line.top, line.left));
+    out.push("text, top to bottom (region, then the center of each line):".to_string());
+    for line in sorted.iter().take(MAX_LINES) {
+        let (x, y) = ((line.left + line.right) / 2, (line.top + line.bottom) / 2);
+        let text = pii::scrub(&line.text, &config.ocr_scrubbers);
+        out.push(match screen.size.and_then(|size| region(x, y, size)) {
+            Some(region) => format!("- {region}: \"{text}\" at {}", to_model(x, y)),
+            None => format!("- \"{text}\" at {}", to_model(x, y)),
+        });
+    }
+    if sorted.len() > MAX_LINES {
+        out.push(format!("({} more lines omitted)", sorted.len() - MAX_LINES));
+    }
+    out.join("\n")
+}
+
+/// The grid cell of a `width` x `height` capture that `(x, y)` is in.
+fn region(x: i64, y: i64, (width, height): (i64, i64)) -> Option<&'static str> {
+    if width <= 0 || height <= 0 {
+        return None;
+    }
+    let column = (x * 3 / width).clamp(0, 2);
+    let row = (y * 3 / height).clamp(0, 2);
+    REGIONS.get((row * 3 + column) as usize).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn line(text: &str, left: i64, top: i64, right: i64, bottom: i64) -> TextMatch {
+        TextMatch {
+            text: text.to_string(),
+            left,
+            top,
+            right,
+            bottom,
+            confidence: 0.9,
+        }
+    }
+
+    #[test]
+    fn lists_text_top_to_bottom_with_regions() {
+        let screen = ScreenText {
+            lines: vec![
+                line("Save changes?", 800, 500, 1120, 540),
+                line("File Edit View", 0, 0, 200, 20),
+                line("Cancel", 1700, 1040, 1800, 1070),
+            ],
+            size: Some((1920, 1080)),
+        };
+        let config = ComputerUseConfig::default();
+        let described = describe(
+            &config,
+            &screen,
+            Some(("gedit".to_string(), "notes.txt".to_string())),
+            |x, y| format!("({x}, {y})"),
+        );
+        assert_eq!(
+            described,
+            format!(
+                "screen as text (no image attached; positions in {})\n\
+                 focused window: `gedit` \"notes.txt\"\n\
+                 text, top to bottom (region, then the center of each line):\n\
+                 - top-left: \"File Edit View\" at (100, 10)\n\
+                 - center: \"Save changes?\" at (960, 520)\n\
+                 - bottom-right: \"Cancel\" at (1750, 1055)",
+                config.coordinate_label()
+            )
+        );
+        let blank = ScreenText {
+            lines: Vec::new(),
+            size: None,
+        };
+        assert!(
+            describe(&config, &blank, None, |x, y| format!("({x}, {y})"))
+                .ends_with("no text was recognized on screen; the screen may show only graphics, or be blank or locked")
+        );
+    }
+}
@@ -0,0 +1,164 @@
+//! Predictable screenshot paths for `computer_use.screenshot_dir`.
+//!
+//! Screenshots are captured into the temp directory under random names.
+//! With a screenshot directory configured, each `computer_screenshot` image
+//! is moved to a path built from `computer_use.screenshot_name`, so external
+//! tools (recorders, dashboards, bug reporters) can find the images of a
+//! session without parsing its events. The scheme is announced once per
+//! session, and every `ViewImageToolCall` event carries the final path.
+//! With `computer_use.encrypt_captures`, the kept file is encrypted and gets
+//! an extra `.enc` extension.
+
+use std::path::Component;
+use std::path::PathBuf;
+
+use super::encryption;
+use crate::config::types::ComputerUseConfig;
+use crate::function_tool::FunctionCallError;
+
+/// Placeholders `screenshot_name` may use.
+const PLACEHOLDERS: &[&str] = &["{session}", "{turn}", "{seq}"];
+
+/// The template resolved against `screenshot_dir`, for announcing the scheme.
+pub(super) fn scheme(config: &ComputerUseConfig) -> Option<String> {
+    let dir = config.screenshot_dir.as_ref()?;
+    let scheme = dir.join(&config.screenshot_name).display().to_string();
+    Some(if config.encrypt_captures {
+        format!("{scheme}.{} (encrypted)", encryption::ENCRYPTED_EXTENSION)
+    } else {
+        scheme
+    })
+}
+
+/// Moves the capture at `captured` to its place under `screenshot_dir` and
+/// returns the new path, or returns `captured` unchanged when no directory
+/// is configured.
+pub(super) fn keep(
+    config: &ComputerUseConfig,
+    captured: PathBuf,
+    session: &str,
+    turn: &str,
+    seq: u64,
+) -> Result<PathBuf, FunctionCallError> {
+    let Some(dir) = &config.screenshot_dir else {
+        return Ok(captured);
+    };
+    if !dir.is_absolute() {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "computer_use.screenshot_dir must be absolute, got `{}`",
+            dir.display()
+        )));
+    }
+    let name = file_name(&config.screenshot_name, session, turn, seq)
+        .map_err(FunctionCallError::RespondToModel)?;
+    let mut path = dir.join(name);
+    if config.encrypt_captures {
+        path.as_mut_os_string()
+            .push(format!(".{}", encryption::ENCRYPTED_EXTENSION));
+    }
+    let write_error = |err: std::io::Error| {
+        FunctionCallError::RespondToModel(format!(
+            "failed to save the screenshot to {}: {err}",
+            path.display()
+        ))
+    };
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(write_error)?;
+    }
+    if config.encrypt_captures {
+        let sealed = std::fs::read(&captured)
+            .map_err(write_error)
+            .and_then(|image| {
+                encryption::encrypt(&image).map_err(|err| {
+                    FunctionCallError::RespondToModel(format!(
+                        "failed to encrypt the screenshot: {err:#}"
+                    ))
+                })
+            });
+        let _ = std::fs::remove_file(&captured);
+        std::fs::write(&path, sealed?).map_err(write_error)?;
+        return Ok(path);
+    }
+    // The temp directory is often another filesystem, where rename fails.
+    if std::fs::rename(&captured, &path).is_err() {
+        std::fs::copy(&captured, &path).map_err(write_error)?;
+        let _ = std::fs::remove_file(&captured);
+    }
+    Ok(path)
+}
+
+/// Fills in `template`, which must stay inside the screenshot directory.
+fn file_name(template: &str, session: &str, turn: &str, seq: u64) -> Result<PathBuf, String> {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..]
+            .find('}')
+            .map(|end| start + end + 1)
+            .unwrap_or(rest.len());
+        let placeholder = &rest[start..end];
+        if !PLACEHOLDERS.contains(&placeholder) {
+            return Err(format!(
+                "computer_use.screenshot_name has unknown placeholder `{placeholder}` (supported: {})",
+                PLACEHOLDERS.join(", ")
+            ));
+        }
+        rest = &rest[end..];
+    }
+    let name = PathBuf::from(
+        template
+            .replace("{session}", &sanitize(session))
+            .replace("{turn}", &sanitize(turn))
+            .replace("{seq}", &format!("{seq:04}")),
+    );
+    let inside = name
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+    if !inside || name.as_os_str().is_empty() {
+        return Err(format!(
+            "computer_use.screenshot_name `{template}` must be a relative path inside screenshot_dir"
+        ));
+    }
+    Ok(name)
+}
+
+/// Keeps ids from adding path separators or parent references.
+fn sanitize(id: &str) -> String {
+    let id: String = id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if id.is_empty() { "_".to_string() } else { id }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::types::DEFAULT_SCREENSHOT_NAME;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn fills_in_the_name_template() {
+        let session = "0199a213-81c0-7800-8aa1-bbab2a035a53";
+        assert_eq!(
+            file_name(DEFAULT_SCREENSHOT_NAME, session, "3", 12),
+            Ok(PathBuf::from(format!("{session}/3-0012.png")))
+        );
+        assert_eq!(
+            file_name("shot-{seq}.png", session, "../x", 1),
+            Ok(PathBuf::from("shot-0001.png"))
+        );
+        assert_eq!(
+            file_name("{turn}/{seq}.png", session, "../x", 1),
+            Ok(PathBuf::from("___x/0001.png"))
+        );
+        assert!(file_name("{date}/{seq}.png", session, "3", 1).is_err());
+        assert!(file_name("../{seq}.png", session, "3", 1).is_err());
+        assert!(file_name("/tmp/{seq}.png", session, "3", 1).is_err());
+    }
+}
@@ -0,0 +1,1435 @@
+//! Declarative action scripts for `computex run-script`: GUI input replayed
+//! through the X11 backend without a model.
+//!
+//! The native syntax has one action per line (see the docs for the full list).
+//! PyAutoGUI and AutoHotkey scripts written as flat lists of calls are
+//! converted into the same actions. Running a script goes through the same
+//! checks as the GUI tools: disabled tools, the screen lock,
+//! `allowed_apps`/`blocked_apps`, the password guard, the side-effect rules,
+//! and destructive key chords. Each step is recorded in an audit log.
+
+use std::fmt;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use chrono::DateTime;
+use chrono::Utc;
+use serde::Serialize;
+
+use super::browser;
+use super::coordinates::MappedPoint;
+use super::coordinates::map_point;
+use super::keys;
+use super::schema::MouseButton;
+use super::schema::ScrollDirection;
+use super::side_effects;
+use super::side_effects::SideEffect;
+use crate::config::types::ComputerUseBackend;
+use crate::config::types::ComputerUseConfig;
+use crate::config::types::CoordinateSpace;
+use crate::config::types::GuiSideEffectPolicy;
+
+/// Longest `wait` a script may ask for.
+const MAX_WAIT_MS: u64 = 10 * 60 * 1000;
+
+/// The language a script file is written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptSyntax {
+    /// The computex action syntax.
+    Native,
+    /// Flat PyAutoGUI calls (`pyautogui.click(100, 200)`, `time.sleep(1)`).
+    Pyautogui,
+    /// AutoHotkey v1 commands (`Click, 100, 200`, `Send ^s`, `Sleep 500`).
+    Autohotkey,
+}
+
+impl ScriptSyntax {
+    /// Guesses the syntax from the file extension: `.py` and `.ahk` are
+    /// converted, anything else is read as native.
+    pub fn for_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("py") => ScriptSyntax::Pyautogui,
+            Some(ext) if ext.eq_ignore_ascii_case("ahk") => ScriptSyntax::Autohotkey,
+            _ => ScriptSyntax::Native,
+        }
+    }
+}
+
+impl FromStr for ScriptSyntax {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "native" | "computex" => Ok(ScriptSyntax::Native),
+            "pyautogui" => Ok(ScriptSyntax::Pyautogui),
+            "autohotkey" | "ahk" => Ok(ScriptSyntax::Autohotkey),
+            _ => Err(format!(
+                "unknown script syntax `{value}` (supported: native, pyautogui, autohotkey)"
+            )),
+        }
+    }
+}
+
+/// A parsed script, ready to run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActionScript {
+    /// Coordinate space the script's points are written in.
+    space: CoordinateSpace,
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Step {
+    /// 1-based line in the source file, for messages.
+    line: usize,
+    action: Action,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Action {
+    Move {
+        x: f64,
+        y: f64,
+    },
+    Click {
+        x: f64,
+        y: f64,
+        button: MouseButton,
+        double: bool,
+    },
+    Drag {
+        from: (f64, f64),
+        to: (f64, f64),
+        button: MouseButton,
+    },
+    Scroll {
+        direction: ScrollDirection,
+        ticks: u32,
+        at: Option<(f64, f64)>,
+    },
+    Type(String),
+    /// Key names as written; validated with [`keys::parse_chord`] when parsed.
+    Key(Vec<String>),
+    Wait(u64),
+}
+
+impl Action {
+    /// The GUI tool whose configuration governs this action.
+    fn tool(&self) -> Option<&'static str> {
+        match self {
+            Action::Move { .. } | Action::Click { .. } => Some("computer_click"),
+            Action::Drag { .. } => Some("computer_drag"),
+            Action::Scroll { .. } => Some("computer_scroll"),
+            Action::Type(_) => Some("computer_type"),
+            Action::Key(_) => Some("computer_key"),
+            Action::Wait(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    /// Formats the action in native syntax.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Move { x, y } => write!(f, "move {x} {y}"),
+            Action::Click {
+                x,
+                y,
+                button,
+                double,
+            } => {
+                write!(f, "click {x} {y}")?;
+                if *button != MouseButton::Left {
+                    write!(f, " {}", button_name(*button))?;
+                }
+                if *double {
+                    write!(f, " double")?;
+                }
+                Ok(())
+            }
+            Action::Drag { from, to, button } => {
+                write!(f, "drag {} {} {} {}", from.0, from.1, to.0, to.1)?;
+                if *button != MouseButton::Left {
+                    write!(f, " {}", button_name(*button))?;
+                }
+                Ok(())
+            }
+            Action::Scroll {
+                direction,
+                ticks,
+                at,
+            } => {
+                write!(f, "scroll {} {ticks}", direction.name())?;
+                if let Some((x, y)) = at {
+                    write!(f, " at {x} {y}")?;
+                }
+                Ok(())
+            }
+            Action::Type(text) => write!(
+                f,
+                "type {}",
+                serde_json::to_string(text).map_err(|_| fmt::Error)?
+            ),
+            Action::Key(keys) => write!(f, "key {}", keys.join("+")),
+            Action::Wait(ms) => write!(f, "wait {ms}"),
+        }
+    }
+}
+
+impl ActionScript {
+    /// Parses `text` written in `syntax`. Native scripts use `space` unless
+    /// they pick one with a `space` line. PyAutoGUI and AutoHotkey scripts
+    /// always use screen pixels.
+    pub fn parse(text: &str, syntax: ScriptSyntax, space: CoordinateSpace) -> Result<Self, String> {
+        match syntax {
+            ScriptSyntax::Native => parse_native(text, space),
+            ScriptSyntax::Pyautogui => Ok(ActionScript {
+                space: CoordinateSpace::Native,
+                steps: parse_pyautogui(text)?,
+            }),
+            ScriptSyntax::Autohotkey => Ok(ActionScript {
+                space: CoordinateSpace::Native,
+                steps: parse_autohotkey(text)?,
+            }),
+        }
+    }
+
+    /// Number of actions, waits included.
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// The script in native syntax, which is how converted scripts are
+    /// reviewed before they run.
+    pub fn to_native(&self) -> String {
+        let mut out = format!("space {}\n", space_name(self.space));
+        for step in &self.steps {
+            out.push_str(&step.action.to_string());
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Where `computex run-script` appends its audit records, under
+/// `CODEX_HOME`.
+pub const SCRIPT_AUDIT_FILE: &str = "computer_use/script_audit.jsonl";
+
+/// One audit record: a step that ran, or the step a run stopped at.
+#[derive(Debug, Serialize)]
+struct AuditRecord<'a> {
+    time: DateTime<Utc>,
+    script: &'a str,
+    line: usize,
+    action: String,
+    outcome: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'a str>,
+}
+
+/// The audit log of one run, appended to as the steps run.
+pub struct ScriptAudit {
+    file: File,
+    script: String,
+}
+
+impl ScriptAudit {
+    /// Opens [`SCRIPT_AUDIT_FILE`] under `codex_home` for the run of
+    /// `script`.
+    pub fn open(codex_home: &Path, script: &Path) -> io::Result<Self> {
+        let path = codex_home.join(SCRIPT_AUDIT_FILE);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self {
+            file,
+            script: script.display().to_string(),
+        })
+    }
+
+    fn record(
+        &mut self,
+        step: &Step,
+        outcome: &'static str,
+        error: Option<&str>,
+    ) -> io::Result<()> {
+        // Typed text stays out of the log, as it may be a secret.
+        let action = match &step.action {
+            Action::Type(text) => format!("type ({} characters)", text.chars().count()),
+            action => action.to_string(),
+        };
+        let record = AuditRecord {
+            time: Utc::now(),
+            script: &self.script,
+            line: step.line,
+            action,
+            outcome,
+            error,
+        };
+        let mut line = serde_json::to_string(&record).map_err(io::Error::other)?;
+        line.push('\n');
+        self.file.write_all(line.as_bytes())
+    }
+}
+
+/// Runs `script` on the configured X11 display, stopping at the first
+/// failure. `report` receives one line per completed action, and `audit`
+/// gets a record for each step that ran and for the one the run stopped at.
+///
+/// Steps pass the checks the GUI tools apply before sending input, except
+/// that nothing can be put to the user: destructive key chords are refused
+/// while `computer_use.confirm_destructive_keys` is set unless
+/// `allow_destructive_keys` is, and steps the side-effect rules would ask
+/// about are refused unless their rule is `allow`. Returns the number of
+/// actions run.
+pub fn run_script(
+    script: &ActionScript,
+    config: &ComputerUseConfig,
+    allow_destructive_keys: bool,
+    audit: &mut ScriptAudit,
+    mut report: impl FnMut(&str),
+) -> Result<usize, String> {
+    if config.backend != ComputerUseBackend::X11 {
+        return Err(format!(
+            "scripts only run with the `x11` backend, and this config uses {}",
+            super::backend_description(config.backend)
+        ));
+    }
+    super::ensure_display(config).map_err(|err| err.to_string())?;
+    let xdotool = super::input_command(config).map_err(|err| err.to_string())?;
+    let (screen_w, screen_h) =
+        super::display_geometry(&xdotool, config).map_err(|err| err.to_string())?;
+    let mapping = ComputerUseConfig {
+        coordinate_space: script.space,
+        ..config.clone()
+    };
+    let map = |(x, y): (f64, f64)| map_point(&mapping, x, y, screen_w, screen_h);
+
+    let prepare = |action: &Action| -> Result<(Vec<String>, String), String> {
+        if let Some(tool) = action.tool() {
+            super::ensure_enabled(config, tool).map_err(|err| err.to_string())?;
+            if config.lock_detection
+                && let Some(reason) = super::lock::lock_reason(config)
+            {
+                return Err(format!(
+                    "screen locked: {reason}; unlock it and run the script again"
+                ));
+            }
+            super::ensure_window_permitted(config).map_err(|err| err.to_string())?;
+        }
+        let clicked = match action {
+            Action::Click { x, y, .. } => Some(map((*x, *y))),
+            _ => None,
+        };
+        check_side_effects(
+            action,
+            &xdotool,
+            config,
+            clicked.as_ref(),
+            screen_w,
+            screen_h,
+        )?;
+        Ok(match action {
+            Action::Wait(ms) => (Vec::new(), format!("waited {ms} ms")),
+            Action::Move { x, y } => {
+                let at = map((*x, *y));
+                (
+                    move_to(&at).to_vec(),
+                    format!("moved to {}", at.display(script.space)),
+                )
+            }
+            Action::Click {
+                x,
+                y,
+                button,
+                double,
+            } => {
+                let at = map((*x, *y));
+                let button = button.xdotool_button().to_string();
+                let mut args = move_to(&at).to_vec();
+                args.extend(["click".to_string(), button.clone()]);
+                if *double {
+                    args.extend(["click".to_string(), button]);
+                }
+                (args, format!("clicked at {}", at.display(script.space)))
+            }
+            Action::Drag { from, to, button } => {
+                let (from, to) = (map(*from), map(*to));
+                let button = button.xdotool_button().to_string();
+                let mut args = move_to(&from).to_vec();
+                args.extend(["mousedown".to_string(), button.clone()]);
+                args.extend(move_to(&to));
+                args.extend(["mouseup".to_string(), button]);
+                let done = format!(
+                    "dragged from {} to {}",
+                    from.display(script.space),
+                    to.display(script.space)
+                );
+                (args, done)
+            }
+            Action::Scroll {
+                direction,
+                ticks,
+                at,
+            } => {
+                let mut args = Vec::new();
+                let mut position = String::new();
+                if let Some(at) = at {
+                    let at = map(*at);
+                    args.extend(move_to(&at));
+                    position = format!(" at {}", at.display(script.space));
+                }
+                args.extend([
+                    "click".to_string(),
+                    "--repeat".to_string(),
+                    ticks.to_string(),
+                    direction.xdotool_button().to_string(),
+                ]);
+                (
+                    args,
+                    format!("scrolled {} {ticks} ticks{position}", direction.name()),
+                )
+            }
+            Action::Type(text) => {
+                if config.password_guard
+                    && let Some(reason) = super::password_guard::detect(&xdotool, config)
+                {
+                    return Err(format!(
+                        "nothing was typed: {reason}; type secrets yourself instead of scripting them"
+                    ));
+                }
+                (
+                    vec!["type".to_string(), "--".to_string(), text.clone()],
+                    format!("typed {} characters", text.len()),
+                )
+            }
+            Action::Key(names) => {
+                let chord = keys::parse_chord(names)?.keys_for(config.target_os);
+                if config.confirm_destructive_keys
+                    && super::requires_confirmation(&chord)
+                    && !allow_destructive_keys
+                {
+                    return Err(format!(
+                        "{} is a destructive key combo; rerun with --allow-destructive-keys if it is intended",
+                        chord.join("+")
+                    ));
+                }
+                let combo = chord.join("+");
+                (
+                    vec!["key".to_string(), combo.clone()],
+                    format!("pressed {combo}"),
+                )
+            }
+        })
+    };
+
+    let total = script.steps.len();
+    for (index, step) in script.steps.iter().enumerate() {
+        let fail = |err: String| {
+            let remaining = total - index - 1;
+            if remaining == 0 {
+                format!("line {}: {err}", step.line)
+            } else {
+                format!(
+                    "line {}: {err}; stopped, {remaining} action(s) did not run",
+                    step.line
+                )
+            }
+        };
+        let audit_error = |err: io::Error| fail(format!("failed to write the audit log: {err}"));
+        let (args, done) = match prepare(&step.action) {
+            Ok(prepared) => prepared,
+            Err(err) => {
+                audit
+                    .record(step, "refused", Some(&err))
+                    .map_err(audit_error)?;
+                return Err(fail(err));
+            }
+        };
+        if let Action::Wait(ms) = step.action {
+            std::thread::sleep(Duration::from_millis(ms));
+        } else if let Err(err) = super::input_helper::send_input(None, &xdotool, &args, config) {
+            let err = err.to_string();
+            audit
+                .record(step, "failed", Some(&err))
+                .map_err(audit_error)?;
+            return Err(fail(err));
+        }
+        audit.record(step, "ran", None).map_err(audit_error)?;
+        report(&format!("line {}: {done}", step.line));
+    }
+    Ok(total)
+}
+
+/// Refuses a step the side-effect rules would put to the user. A script has
+/// no one to ask, so only a rule set to `allow` lets such a step through,
+/// and text that would run as a command in a terminal is refused while
+/// `computer_use.terminal_typing_exec_policy` is on.
+fn check_side_effects(
+    action: &Action,
+    xdotool: &Path,
+    config: &ComputerUseConfig,
+    clicked: Option<&MappedPoint>,
+    screen_w: f64,
+    screen_h: f64,
+) -> Result<(), String> {
+    if !matches!(
+        action,
+        Action::Type(_) | Action::Key(_) | Action::Click { .. }
+    ) {
+        return Ok(());
+    }
+    let class = super::active_window_class(xdotool, config)
+        .ok()
+        .filter(|class| !class.is_empty());
+    if let Action::Type(text) = action
+        && config.terminal_typing_exec_policy
+        && side_effects::typed_command(text).is_some()
+        && let Some(target) = super::terminal_target(text, class.as_deref(), config)
+    {
+        return Err(format!(
+            "typing into {target} runs the text as a command, and only a session can check it against the exec policy; type it from a session instead"
+        ));
+    }
+    let class = class.unwrap_or_default();
+    let effect = match action {
+        Action::Type(_) | Action::Key(_) if side_effects::is_terminal(&class, config) => {
+            SideEffect::TerminalInput
+        }
+        Action::Click { .. }
+            if browser::browser_name(&class).is_some()
+                && clicked.is_some_and(|point| {
+                    super::click_target_mentions_download(config, point, screen_w, screen_h)
+                }) =>
+        {
+            SideEffect::Download
+        }
+        _ => return Ok(()),
+    };
+    if effect.policy(config) == GuiSideEffectPolicy::Allow {
+        return Ok(());
+    }
+    Err(format!(
+        "{} in {class}, and a script can't ask for approval; set {} = \"allow\" to script it",
+        effect.consequence(),
+        effect.config_key()
+    ))
+}
+
+fn move_to(point: &MappedPoint) -> [String; 4] {
+    [
+        "mousemove".to_string(),
+        "--sync".to_string(),
+        point.screen_x.to_string(),
+        point.screen_y.to_string(),
+    ]
+}
+
+fn button_name(button: MouseButton) -> &'static str {
+    match button {
+        MouseButton::Left => "left",
+        MouseButton::Middle => "middle",
+        MouseButton::Right => "right",
+    }
+}
+
+fn space_name(space: CoordinateSpace) -> &'static str {
+    match space {
+        CoordinateSpace::Virtual => "virtual",
+        CoordinateSpace::Normalized => "normalized",
+        CoordinateSpace::Native => "native",
+    }
+}
+
+fn parse_button(name: &str) -> Option<MouseButton> {
+    match name.to_ascii_lowercase().as_str() {
+        "left" | "l" => Some(MouseButton::Left),
+        "middle" | "m" => Some(MouseButton::Middle),
+        "right" | "r" => Some(MouseButton::Right),
+        _ => None,
+    }
+}
+
+fn number(token: &str) -> Result<f64, String> {
+    token
+        .parse::<f64>()
+        .ok()
+        .filter(|value| value.is_finite())
+        .ok_or_else(|| format!("expected a number, got `{token}`"))
+}
+
+fn wait_ms(ms: f64) -> Result<Action, String> {
+    if !(0.0..=MAX_WAIT_MS as f64).contains(&ms) {
+        return Err(format!("waits must be between 0 and {MAX_WAIT_MS} ms"));
+    }
+    Ok(Action::Wait(ms.round() as u64))
+}
+
+fn key_action(names: Vec<String>) -> Result<Action, String> {
+    keys::parse_chord(&names)?;
+    Ok(Action::Key(names))
+}
+
+/// Splits `ctrl+shift+t` into key names; a trailing `++` names the plus key.
+fn split_chord(chord: &str) -> Vec<String> {
+    let mut names: Vec<String> = chord
+        .split('+')
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect();
+    if chord.ends_with("++") || chord == "+" {
+        names.push("+".to_string());
+    }
+    names
+}
+
+fn with_line<T>(line: usize, result: Result<T, String>) -> Result<T, String> {
+    result.map_err(|err| format!("line {line}: {err}"))
+}
+
+fn parse_native(text: &str, default_space: CoordinateSpace) -> Result<ActionScript, String> {
+    let mut space = default_space;
+    let mut steps = Vec::new();
+    for (index, raw) in text.lines().enumerate() {
+        let line = index + 1;
+        let trimmed = raw.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let (verb, rest) = trimmed
+            .split_once(char::is_whitespace)
+            .map_or((trimmed, ""), |(verb, rest)| (verb, rest.trim()));
+        if verb == "space" {
+            if !steps.is_empty() {
+                return Err(format!("line {line}: `space` must come before any action"));
+            }
+            space = with_line(
+                line,
+                toml::Value::String(rest.to_string())
+                    .try_into()
+                    .map_err(|_| {
+                        format!("unknown coordinate space `{rest}` (virtual, normalized, native)")
+                    }),
+            )?;
+            continue;
+        }
+        let action = with_line(line, parse_native_action(verb, rest))?;
+        steps.push(Step { line, action });
+    }
+    Ok(ActionScript { space, steps })
+}
+
+fn parse_native_action(verb: &str, rest: &str) -> Result<Action, String> {
+    let args: Vec<&str> = rest.split_whitespace().collect();
+    match verb {
+        "move" => match args.as_slice() {
+            [x, y] => Ok(Action::Move {
+                x: number(x)?,
+                y: number(y)?,
+            }),
+            _ => Err("usage: move X Y".to_string()),
+        },
+        "click" => {
+            let [x, y, options @ ..] = args.as_slice() else {
+                return Err("usage: click X Y [left|middle|right] [double]".to_string());
+            };
+            let mut button = MouseButton::Left;
+            let mut double = false;
+            for option in options {
+                match (*option, parse_button(option)) {
+                    ("double", _) => double = true,
+                    (_, Some(parsed)) => button = parsed,
+                    _ => return Err(format!("unknown click option `{option}`")),
+                }
+            }
+            Ok(Action::Click {
+                x: number(x)?,
+                y: number(y)?,
+                button,
+                double,
+            })
+        }
+        "drag" => {
+            let (coords, button) = match args.as_slice() {
+                [a, b, c, d] => ([a, b, c, d], MouseButton::Left),
+                [a, b, c, d, button] => (
+                    [a, b, c, d],
+                    parse_button(button).ok_or_else(|| format!("unknown button `{button}`"))?,
+                ),
+                _ => return Err("usage: drag X1 Y1 X2 Y2 [left|middle|right]".to_string()),
+            };
+            Ok(Action::Drag {
+                from: (number(coords[0])?, number(coords[1])?),
+                to: (number(coords[2])?, number(coords[3])?),
+                button,
+            })
+        }
+        "scroll" => {
+            let usage = "usage: scroll up|down [TICKS] [at X Y]";
+            let Some((direction, mut rest)) = args.split_first() else {
+                return Err(usage.to_string());
+            };
+            let direction = match *direction {
+                "up" => ScrollDirection::Up,
+                "down" => ScrollDirection::Down,
+                _ => return Err(usage.to_string()),
+            };
+            let mut ticks = super::DEFAULT_SCROLL_TICKS;
+            if let Some((first, tail)) = rest.split_first()
+                && *first != "at"
+            {
+                ticks = first
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|ticks| *ticks > 0)
+                    .ok_or_else(|| format!("expected a positive tick count, got `{first}`"))?;
+                rest = tail;
+            }
+            let at = match rest {
+                [] => None,
+                ["at", x, y] => Some((number(x)?, number(y)?)),
+                _ => return Err(usage.to_string()),
+            };
+            Ok(Action::Scroll {
+                direction,
+                ticks,
+                at,
+            })
+        }
+        "type" => {
+            if rest.starts_with('"') {
+                serde_json::from_str::<String>(rest)
+                    .map(Action::Type)
+                    .map_err(|err| format!("invalid quoted text: {err}"))
+            } else if rest.is_empty() {
+                Err("usage: type TEXT or type \"TEXT\"".to_string())
+            } else {
+                Ok(Action::Type(rest.to_string()))
+            }
+        }
+        "key" => match args.as_slice() {
+            [chord] => key_action(split_chord(chord)),
+            _ => Err("usage: key CHORD, for example `key ctrl+s` or `key copy`".to_string()),
+        },
+        "wait" => match args.as_slice() {
+            [ms] => wait_ms(number(ms)?),
+            _ => Err("usage: wait MILLISECONDS".to_string()),
+        },
+        _ => Err(format!(
+            "unknown action `{verb}` (move, click, drag, scroll, type, key, wait)"
+        )),
+    }
+}
+
+/// A Python literal in a PyAutoGUI call.
+#[derive(Debug, Clone, PartialEq)]
+enum PyValue {
+    Number(f64),
+    Str(String),
+    List(Vec<PyValue>),
+    Other(String),
+}
+
+impl PyValue {
+    fn number(&self) -> Result<f64, String> {
+        match self {
+            PyValue::Number(value) => Ok(*value),
+            other => Err(format!("expected a number, got {other:?}")),
+        }
+    }
+
+    fn string(&self) -> Result<String, String> {
+        match self {
+            PyValue::Str(value) => Ok(value.clone()),
+            other => Err(format!("expected a string, got {other:?}")),
+        }
+    }
+}
+
+/// Positional and keyword arguments of one call.
+struct PyArgs {
+    positional: Vec<PyValue>,
+    keyword: Vec<(String, PyValue)>,
+}
+
+impl PyArgs {
+    fn get(&self, index: usize, name: &str) -> Option<&PyValue> {
+        self.keyword
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value)
+            .or_else(|| self.positional.get(index))
+            .filter(|value| !matches!(value, PyValue::Other(other) if other == "None"))
+    }
+
+    fn point(&self) -> Result<Option<(f64, f64)>, String> {
+        match (self.get(0, "x"), self.get(1, "y")) {
+            (Some(x), Some(y)) => Ok(Some((x.number()?, y.number()?))),
+            (None, None) => Ok(None),
+            _ => Err("give both x and y".to_string()),
+        }
+    }
+}
+
+fn parse_pyautogui(text: &str) -> Result<Vec<Step>, String> {
+    let mut steps = Vec::new();
+    let mut cursor: Option<(f64, f64)> = None;
+    for (index, raw) in text.lines().enumerate() {
+        let line = index + 1;
+        let code = strip_python_comment(raw).trim();
+        if code.is_empty()
+            || code.starts_with("import ")
+            || code.starts_with("from ")
+            || code.starts_with("pyautogui.PAUSE")
+            || code.starts_with("pyautogui.FAILSAFE")
+        {
+            continue;
+        }
+        let actions = with_line(line, pyautogui_call(code, &mut cursor))?;
+        steps.extend(actions.into_iter().map(|action| Step { line, action }));
+    }
+    Ok(steps)
+}
+
+fn pyautogui_call(code: &str, cursor: &mut Option<(f64, f64)>) -> Result<Vec<Action>, String> {
+    let unsupported = || format!("unsupported statement `{code}`");
+    let (callee, rest) = code.split_once('(').ok_or_else(unsupported)?;
+    let inner = rest.strip_suffix(')').ok_or_else(unsupported)?;
+    let args = parse_python_args(inner)?;
+    let function = match callee.trim() {
+        "time.sleep" | "sleep" => {
+            let seconds = args
+                .get(0, "secs")
+                .ok_or("sleep needs a duration")?
+                .number()?;
+            return Ok(vec![wait_ms(seconds * 1000.0)?]);
+        }
+        callee => callee.strip_prefix("pyautogui.").ok_or_else(unsupported)?,
+    };
+    let at = |args: &PyArgs, cursor: &Option<(f64, f64)>| -> Result<(f64, f64), String> {
+        args.point()?
+            .or(*cursor)
+            .ok_or_else(|| format!("{function} without x and y needs an earlier moveTo"))
+    };
+    let actions = match function {
+        "moveTo" => {
+            let (x, y) = args.point()?.ok_or("moveTo needs x and y")?;
+            *cursor = Some((x, y));
+            vec![Action::Move { x, y }]
+        }
+        "click" | "leftClick" | "rightClick" | "middleClick" | "doubleClick" => {
+            let (x, y) = at(&args, cursor)?;
+            *cursor = Some((x, y));
+            let button = match function {
+                "rightClick" => MouseButton::Right,
+                "middleClick" => MouseButton::Middle,
+                _ => match args.get(usize::MAX, "button") {
+                    Some(button) => {
+                        let name = button.string()?;
+                        parse_button(&name).ok_or_else(|| format!("unknown button `{name}`"))?
+                    }
+                    None => MouseButton::Left,
+                },
+            };
+            let clicks = match function {
+                "doubleClick" => 2,
+                "click" => match args.get(2, "clicks") {
+                    Some(clicks) => clicks.number()?.max(0.0) as u32,
+                    None => 1,
+                },
+                _ => 1,
+            };
+            let click = Action::Click {
+                x,
+                y,
+                button,
+                double: false,
+            };
+            match clicks {
+                0 => Vec::new(),
+                2 => vec![Action::Click {
+                    x,
+                    y,
+                    button,
+                    double: true,
+                }],
+                count => vec![click; count as usize],
+            }
+        }
+        "dragTo" => {
+            let from = cursor.ok_or("dragTo needs an earlier moveTo or click")?;
+            let to = args.point()?.ok_or("dragTo needs x and y")?;
+            *cursor = Some(to);
+            let button = match args.get(usize::MAX, "button") {
+                Some(button) => {
+                    let name = button.string()?;
+                    parse_button(&name).ok_or_else(|| format!("unknown button `{name}`"))?
+                }
+                None => MouseButton::Left,
+            };
+            vec![Action::Drag { from, to, button }]
+        }
+        "scroll" => {
+            let clicks = args
+                .get(0, "clicks")
+                .ok_or("scroll needs clicks")?
+                .number()?;
+            let at = match (args.get(1, "x"), args.get(2, "y")) {
+                (Some(x), Some(y)) => Some((x.number()?, y.number()?)),
+                _ => None,
+            };
+            if clicks == 0.0 {
+                Vec::new()
+            } else {
+                vec![Action::Scroll {
+                    direction: if clicks > 0.0 {
+                        ScrollDirection::Up
+                    } else {
+                        ScrollDirection::Down
+                    },
+                    ticks: clicks.abs().round().max(1.0) as u32,
+                    at,
+                }]
+            }
+        }
+        "write" | "typewrite" => match args.get(0, "message").ok_or("write needs text")? {
+            PyValue::List(keys) => keys
+                .iter()
+                .map(|key| key_action(vec![key.string()?]))
+                .collect::<Result<_, _>>()?,
+            text => vec![Action::Type(text.string()?)],
+        },
+        "press" => {
+            let keys = match args.get(0, "keys").ok_or("press needs a key")? {
+                PyValue::List(keys) => keys
+                    .iter()
+                    .map(PyValue::string)
+                    .collect::<Result<Vec<_>, _>>()?,
+                key => vec![key.string()?],
+            };
+            let presses = match args.get(1, "presses") {
+                Some(presses) => presses.number()?.max(0.0) as usize,
+                None => 1,
+            };
+            let mut actions = Vec::new();
+            for _ in 0..presses {
+                for key in &keys {
+                    actions.push(key_action(vec![key.clone()])?);
+                }
+            }
+            actions
+        }
+        "hotkey" => {
+            let keys = args
+                .positional
+                .iter()
+                .map(PyValue::string)
+                .collect::<Result<Vec<_>, _>>()?;
+            vec![key_action(keys)?]
+        }
+        other => return Err(format!("unsupported PyAutoGUI function `{other}`")),
+    };
+    Ok(actions)
+}
+
+/// Drops a trailing `# comment` that is outside string literals.
+fn strip_python_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    for (index, ch) in line.char_indices() {
+        match quote {
+            Some(_) if escaped => escaped = false,
+            Some(_) if ch == '\\' => escaped = true,
+            Some(open) if ch == open => quote = None,
+            Some(_) => {}
+            None if ch == '"' || ch == '\'' => quote = Some(ch),
+            None if ch == '#' => return &line[..index],
+            None => {}
+        }
+    }
+    line
+}
+
+fn parse_python_args(inner: &str) -> Result<PyArgs, String> {
+    let mut args = PyArgs {
+        positional: Vec::new(),
+        keyword: Vec::new(),
+    };
+    for part in split_top_level(inner, ',') {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+        match part.split_once('=') {
+            Some((name, value))
+                if !name.trim().is_empty()
+                    && name.trim().chars().all(|c| c.is_alphanumeric() || c == '_') =>
+            {
+                args.keyword
+                    .push((name.trim().to_string(), parse_python_value(value.trim())?));
+            }
+            _ => args.positional.push(parse_python_value(part)?),
+        }
+    }
+    Ok(args)
+}
+
+fn parse_python_value(value: &str) -> Result<PyValue, String> {
+    if let Some(inner) = value
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+    {
+        return split_top_level(inner, ',')
+            .into_iter()
+            .filter(|item| !item.trim().is_empty())
+            .map(|item| parse_python_value(item.trim()))
+            .collect::<Result<_, _>>()
+            .map(PyValue::List);
+    }
+    let quote = value.chars().next();
+    if matches!(quote, Some('"' | '\''))
+        && value.len() >= 2
+        && value.ends_with(quote.unwrap_or('"'))
+    {
+        return unescape_python(&value[1..value.len() - 1]).map(PyValue::Str);
+    }
+    Ok(value
+        .parse::<f64>()
+        .map_or_else(|_| PyValue::Other(value.to_string()), PyValue::Number))
+}
+
+fn unescape_python(body: &str) -> Result<String, String> {
+    let mut out = String::new();
+    let mut chars = body.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            out.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some(other @ ('\\' | '\'' | '"')) => out.push(other),
+            Some(other) => return Err(format!("unsupported escape `\\{other}`")),
+            None => return Err("string ends with a backslash".to_string()),
+        }
+    }
+    Ok(out)
+}
+
+/// Splits on `separator` outside quotes and brackets.
+fn split_top_level(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut quote = None;
+    let mut escaped = false;
+    let mut start = 0;
+    for (index, ch) in text.char_indices() {
+        match quote {
+            Some(_) if escaped => escaped = false,
+            Some(_) if ch == '\\' => escaped = true,
+            Some(open) if ch == open => quote = None,
+            Some(_) => {}
+            None => match ch {
+                '"' | '\'' => quote = Some(ch),
+                '[' | '(' => depth += 1,
+                ']' | ')' => depth = depth.saturating_sub(1),
+                _ if ch == separator && depth == 0 => {
+                    parts.push(&text[start..index]);
+                    start = index + ch.len_utf8();
+                }
+                _ => {}
+            },
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+/// AutoHotkey commands that only tune the interpreter and are skipped.
+const AHK_IGNORED: &[&str] = &[
+    "sendmode",
+    "setworkingdir",
+    "coordmode",
+    "setkeydelay",
+    "setmousedelay",
+    "setdefaultmousespeed",
+    "settitlematchmode",
+    "return",
+    "exitapp",
+];
+
+fn parse_autohotkey(text: &str) -> Result<Vec<Step>, String> {
+    let mut steps = Vec::new();
+    let mut cursor: Option<(f64, f64)> = None;
+    for (index, raw) in text.lines().enumerate() {
+        let line = index + 1;
+        let code = raw.trim();
+        let code = code.find(" ;").map_or(code, |at| &code[..at]).trim();
+        if code.is_empty() || code.starts_with(';') || code.starts_with('#') {
+            continue;
+        }
+        let split = code
+            .find(|c: char| c == ',' || c.is_whitespace())
+            .unwrap_or(code.len());
+        let command = code[..split].to_ascii_lowercase();
+        let rest = code[split..].trim_start();
+        let rest = rest.strip_prefix(',').unwrap_or(rest).trim_start();
+        if AHK_IGNORED.contains(&command.as_str()) {
+            continue;
+        }
+        let actions = with_line(line, autohotkey_command(&command, rest, &mut cursor))?;
+        steps.extend(actions.into_iter().map(|action| Step { line, action }));
+    }
+    Ok(steps)
+}
+
+fn autohotkey_command(
+    command: &str,
+    rest: &str,
+    cursor: &mut Option<(f64, f64)>,
+) -> Result<Vec<Action>, String> {
+    let params: Vec<&str> = rest.split(',').map(str::trim).collect();
+    let actions = match command {
+        "sleep" => vec![wait_ms(number(params[0])?)?],
+        "mousemove" => {
+            let [x, y, ..] = params.as_slice() else {
+                return Err("MouseMove needs X and Y".to_string());
+            };
+            let (x, y) = (number(x)?, number(y)?);
+            *cursor = Some((x, y));
+            vec![Action::Move { x, y }]
+        }
+        "click" => {
+            let mut numbers = Vec::new();
+            let mut button = MouseButton::Left;
+            let mut wheel = None;
+            for token in rest
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|token| !token.is_empty())
+            {
+                if let Ok(value) = token.parse::<f64>() {
+                    numbers.push(value);
+                } else if let Some(parsed) = parse_button(token) {
+                    button = parsed;
+                } else {
+                    wheel = Some(match token.to_ascii_lowercase().as_str() {
+                        "wheelup" | "wu" => ScrollDirection::Up,
+                        "wheeldown" | "wd" => ScrollDirection::Down,
+                        _ => return Err(format!("unsupported Click option `{token}`")),
+                    });
+                }
+            }
+            let (point, count) = match numbers.as_slice() {
+                [] => (None, 1.0),
+                [count] => (None, *count),
+                [x, y] => (Some((*x, *y)), 1.0),
+                [x, y, count, ..] => (Some((*x, *y)), *count),
+            };
+            if let Some(direction) = wheel {
+                vec![Action::Scroll {
+                    direction,
+                    ticks: count.max(1.0) as u32,
+                    at: point,
+                }]
+            } else {
+                let (x, y) = point
+                    .or(*cursor)
+                    .ok_or("Click without X and Y needs an earlier MouseMove")?;
+                *cursor = Some((x, y));
+                ahk_clicks(x, y, button, count)
+            }
+        }
+        "mouseclick" => {
+            let button = match params.first() {
+                Some(name) if !name.is_empty() => {
+                    parse_button(name).ok_or_else(|| format!("unknown button `{name}`"))?
+                }
+                _ => MouseButton::Left,
+            };
+            let (x, y) = match (params.get(1), params.get(2)) {
+                (Some(x), Some(y)) if !x.is_empty() && !y.is_empty() => (number(x)?, number(y)?),
+                _ => cursor.ok_or("MouseClick without X and Y needs an earlier MouseMove")?,
+            };
+            *cursor = Some((x, y));
+            let count = match params.get(3) {
+                Some(count) if !count.is_empty() => number(count)?,
+                _ => 1.0,
+            };
+            ahk_clicks(x, y, button, count)
+        }
+        "mouseclickdrag" => {
+            let [button, x1, y1, x2, y2, ..] = params.as_slice() else {
+                return Err("MouseClickDrag needs WhichButton, X1, Y1, X2, Y2".to_string());
+            };
+            let button =
+                parse_button(button).ok_or_else(|| format!("unknown button `{button}`"))?;
+            let to = (number(x2)?, number(y2)?);
+            *cursor = Some(to);
+            vec![Action::Drag {
+                from: (number(x1)?, number(y1)?),
+                to,
+                button,
+            }]
+        }
+        "send" | "sendinput" | "sendevent" | "sendplay" => parse_ahk_send(rest)?,
+        "sendraw" => vec![Action::Type(rest.to_string())],
+        _ => return Err(format!("unsupported AutoHotkey command `{command}`")),
+    };
+    Ok(actions)
+}
+
+fn ahk_clicks(x: f64, y: f64, button: MouseButton, count: f64) -> Vec<Action> {
+    let count = count.max(0.0) as usize;
+    if count == 2 {
+        return vec![Action::Click {
+            x,
+            y,
+            button,
+            double: true,
+        }];
+    }
+    vec![
+        Action::Click {
+            x,
+            y,
+            button,
+            double: false,
+        };
+        count
+    ]
+}
+
+/// Converts AutoHotkey `Send` keys: `^!+#` modifiers, `{Key}` and
+/// `{Key N}` names, and literal text.
+fn parse_ahk_send(keys: &str) -> Result<Vec<Action>, String> {
+    let mut actions = Vec::new();
+    let mut text = String::new();
+    let mut modifiers: Vec<String> = Vec::new();
+    let mut chars = keys.chars().peekable();
+    while let Some(ch) = chars.next() {
+        let modifier = match ch {
+            '^' => Some("ctrl"),
+            '!' => Some("alt"),
+            '+' => Some("shift"),
+            '#' => Some("super"),
+            _ => None,
+        };
+        if let Some(modifier) = modifier {
+            modifiers.push(modifier.to_string());
+            continue;
+        }
+        let (key, repeat, literal) = if ch == '{' {
+            let mut name = String::new();
+            // `{{}` and `{}}` send the brace itself.
+            if let Some(&next) = chars.peek()
+                && (next == '{' || next == '}')
+            {
+                name.push(next);
+                chars.next();
+            }
+            for next in chars.by_ref() {
+                if next == '}' {
+                    break;
+                }
+                name.push(next);
+            }
+            let (name, repeat) = match name.rsplit_once(' ') {
+                Some((name, count)) => match count.parse::<usize>() {
+                    Ok(count) => (name.to_string(), count),
+                    Err(_) => return Err(format!("unsupported Send key `{{{name}}}`")),
+                },
+                None => (name, 1),
+            };
+            let literal = name.chars().count() == 1;
+            (name, repeat, literal)
+        } else {
+            (ch.to_string(), 1, true)
+        };
+        if literal && modifiers.is_empty() {
+            text.push_str(&key.repeat(repeat));
+            continue;
+        }
+        if !text.is_empty() {
+            actions.push(Action::Type(std::mem::take(&mut text)));
+        }
+        let mut chord = std::mem::take(&mut modifiers);
+        chord.push(key);
+        let action = key_action(chord)?;
+        actions.extend(std::iter::repeat_n(action, repeat));
+    }
+    if !modifiers.is_empty() {
+        return Err("Send ends with a modifier and no key".to_string());
+    }
+    if !text.is_empty() {
+        actions.push(Action::Type(text));
+    }
+    Ok(actions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn native_script_parses_and_round_trips() {
+        let text = "\
+# log in
+space native
+click 640 360
+click 10 20 right double
+drag 1 2 3 4 middle
+scroll down
+scroll up 5 at 100 200
+type \"hunter2\\n\"
+type plain words
+key ctrl+s
+key copy
+wait 250
+";
+        let script = ActionScript::parse(text, ScriptSyntax::Native, CoordinateSpace::Virtual)
+            .expect("valid script");
+        assert_eq!(script.len(), 10);
+        assert_eq!(script.steps[0].line, 3);
+        let native = script.to_native();
+        assert_eq!(
+            native,
+            "space native\nclick 640 360\nclick 10 20 right double\ndrag 1 2 3 4 middle\nscroll down 3\nscroll up 5 at 100 200\ntype \"hunter2\\n\"\ntype \"plain words\"\nkey ctrl+s\nkey copy\nwait 250\n"
+        );
+        let reparsed = ActionScript::parse(&native, ScriptSyntax::Native, CoordinateSpace::Virtual)
+            .expect("native output parses");
+        assert_eq!(reparsed.to_native(), native);
+
+        assert_eq!(
+            ActionScript::parse("click 1\n", ScriptSyntax::Native, CoordinateSpace::Virtual),
+            Err("line 1: usage: click X Y [left|middle|right] [double]".to_string())
+        );
+        assert_eq!(
+            ActionScript::parse(
+                "wait 1\nkey ctlr+s\n",
+                ScriptSyntax::Native,
+                CoordinateSpace::Virtual
+            ),
+            Err("line 2: unknown key `ctlr`; did you mean `ctrl`?".to_string())
+        );
+    }
+
+    #[test]
+    fn pyautogui_script_converts_to_native_actions() {
+        let text = "\
+import pyautogui, time
+pyautogui.PAUSE = 0.5
+pyautogui.moveTo(100, 200)  # start
+pyautogui.click()
+pyautogui.doubleClick(300, 400, button='right')
+pyautogui.dragTo(500, 600)
+pyautogui.scroll(-3, x=10, y=20)
+pyautogui.write('it\\'s # here')
+pyautogui.press(['tab', 'enter'], presses=1)
+pyautogui.hotkey('ctrl', 'shift', 't')
+time.sleep(1.5)
+";
+        let script = ActionScript::parse(text, ScriptSyntax::Pyautogui, CoordinateSpace::Virtual)
+            .expect("valid script");
+        assert_eq!(
+            script.to_native(),
+            "space native\nmove 100 200\nclick 100 200\nclick 300 400 right double\ndrag 300 400 500 600\nscroll down 3 at 10 20\ntype \"it's # here\"\nkey tab\nkey enter\nkey ctrl+shift+t\nwait 1500\n"
+        );
+        assert_eq!(
+            ActionScript::parse(
+                "pyautogui.click()\n",
+                ScriptSyntax::Pyautogui,
+                CoordinateSpace::Virtual
+            ),
+            Err("line 1: click without x and y needs an earlier moveTo".to_string())
+        );
+    }
+
+    #[test]
+    fn autohotkey_script_converts_to_native_actions() {
+        let text = "\
+#NoEnv
+SendMode Input ; faster
+MouseMove, 10, 20
+Click
+Click, 30, 40, 2
+Click right 50 60
+Click WheelDown 3
+MouseClickDrag, Left, 1, 2, 3, 4
+Send, Hello{Enter}^s{Tab 2}{!}
+Sleep, 500
+";
+        let script = ActionScript::parse(text, ScriptSyntax::Autohotkey, CoordinateSpace::Virtual)
+            .expect("valid script");
+        assert_eq!(
+            script.to_native(),
+            "space native\nmove 10 20\nclick 10 20\nclick 30 40 double\nclick 50 60 right\nscroll down 3\ndrag 1 2 3 4\ntype \"Hello\"\nkey Enter\nkey ctrl+s\nkey Tab\nkey Tab\ntype \"!\"\nwait 500\n"
+        );
+        assert_eq!(
+            ActionScript::parse(
+                "Run, notepad\n",
+                ScriptSyntax::Autohotkey,
+                CoordinateSpace::Virtual
+            ),
+            Err("line 1: unsupported AutoHotkey command `run`".to_string())
+        );
+        assert_eq!(
+            ScriptSyntax::for_path(Path::new("macro.AHK")),
+            ScriptSyntax::Autohotkey
+        );
+    }
+
+    #[test]
+    fn audit_records_leave_typed_text_out() -> anyhow::Result<()> {
+        let script = ActionScript::parse(
+            "type \"hunter2\"\nkey ctrl+s\n",
+            ScriptSyntax::Native,
+            CoordinateSpace::Native,
+        )
+        .map_err(anyhow::Error::msg)?;
+        let dir = tempfile::tempdir()?;
+        let mut audit = ScriptAudit::open(dir.path(), Path::new("login.txt"))?;
+        audit.record(&script.steps[0], "ran", None)?;
+        audit.record(&script.steps[1], "refused", Some("screen locked"))?;
+
+        let log = std::fs::read_to_string(dir.path().join(SCRIPT_AUDIT_FILE))?;
+        assert!(!log.contains("hunter2"));
+        let records = log
+            .lines()
+            .map(serde_json::from_str::<serde_json::Value>)
+            .collect::<Result<Vec<_>, _>>()?;
+        let fields = |record: &serde_json::Value| {
+            (
+                record["line"].clone(),
+                record["action"].clone(),
+                record["outcome"].clone(),
+                record.get("error").cloned(),
+            )
+        };
+        assert_eq!(
+            records.iter().map(fields).collect::<Vec<_>>(),
+            vec![
+                (1.into(), "type (7 characters)".into(), "ran".into(), None),
+                (
+                    2.into(),
+                    "key ctrl+s".into(),
+                    "refused".into(),
+                    Some("screen locked".into())
+                ),
+            ]
+        );
+        assert_eq!(records[0]["script"], "login.txt");
+        Ok(())
+    }
+}
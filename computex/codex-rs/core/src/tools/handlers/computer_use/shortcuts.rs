@@ -0,0 +1,436 @@
+//! Keyboard shortcuts for `computer_list_shortcuts`.
+//!
+//! A shortcut does the same thing every time, where a click depends on
+//! finding the right pixels first. Three places say which ones exist: the
+//! desktop-wide bindings GNOME keeps in gsettings, the global shortcuts KDE
+//! Plasma keeps in `kglobalshortcutsrc`, and the accelerators the focused
+//! app shows beside its menu items, read from the accessibility tree. Every
+//! binding is spelled as a `computer_key` chord, e.g. `ctrl+shift+t`.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use which::which;
+
+use super::accessibility;
+use super::desktop::output;
+use crate::config::types::ComputerUseConfig;
+
+/// gsettings schemas holding GNOME's desktop-wide bindings.
+const GNOME_SCHEMAS: &[&str] = &[
+    "org.gnome.desktop.wm.keybindings",
+    "org.gnome.mutter.keybindings",
+    "org.gnome.mutter.wayland.keybindings",
+    "org.gnome.shell.keybindings",
+    "org.gnome.settings-daemon.plugins.media-keys",
+];
+
+/// Schema listing the user's own GNOME shortcuts, and the relocatable
+/// schema each of them is stored under.
+const MEDIA_KEYS: &str = "org.gnome.settings-daemon.plugins.media-keys";
+const CUSTOM_KEYBINDING: &str = "org.gnome.settings-daemon.plugins.media-keys.custom-keybinding";
+
+/// A key chord and what it does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Shortcut {
+    /// The chord as `computer_key` takes it, joined with `+`.
+    pub keys: String,
+    pub action: String,
+}
+
+/// Shortcuts from one place, such as GNOME or an app's menus.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct ShortcutGroup {
+    pub source: String,
+    pub shortcuts: Vec<Shortcut>,
+}
+
+/// The desktop's global shortcuts, one group per desktop or KDE component.
+pub(super) fn desktop(config: &ComputerUseConfig) -> Vec<ShortcutGroup> {
+    let mut groups = Vec::new();
+    if let Ok(gsettings) = which("gsettings") {
+        let shortcuts = gnome(&gsettings, config);
+        if !shortcuts.is_empty() {
+            groups.push(ShortcutGroup {
+                source: "GNOME".to_string(),
+                shortcuts,
+            });
+        }
+    }
+    if let Some(file) = kde_config()
+        && let Ok(contents) = std::fs::read_to_string(file)
+    {
+        groups.extend(parse_kglobalshortcuts(&contents));
+    }
+    groups
+}
+
+/// The accelerators of the focused app's menu items.
+pub(super) fn app() -> Result<ShortcutGroup, String> {
+    let (app, accelerators) = accessibility::menu_accelerators()?;
+    let shortcuts = accelerators
+        .into_iter()
+        .filter_map(|item| {
+            Some(Shortcut {
+                keys: menu_chord(&item.binding)?,
+                action: item.path.join(" > "),
+            })
+        })
+        .collect();
+    let app = if app.is_empty() {
+        "the focused app".to_string()
+    } else {
+        app
+    };
+    Ok(ShortcutGroup {
+        source: format!("{app} menus"),
+        shortcuts,
+    })
+}
+
+/// Keeps the shortcuts whose keys or action contain `query`, and drops the
+/// groups left empty.
+pub(super) fn filter(groups: Vec<ShortcutGroup>, query: &str) -> Vec<ShortcutGroup> {
+    let query = query.trim().to_lowercase();
+    groups
+        .into_iter()
+        .filter_map(|mut group| {
+            group.shortcuts.retain(|shortcut| {
+                shortcut.keys.to_lowercase().contains(&query)
+                    || shortcut.action.to_lowercase().contains(&query)
+            });
+            (!group.shortcuts.is_empty()).then_some(group)
+        })
+        .collect()
+}
+
+/// One line per shortcut under a heading per group.
+pub(super) fn describe(groups: &[ShortcutGroup]) -> Vec<String> {
+    let count: usize = groups.iter().map(|group| group.shortcuts.len()).sum();
+    let mut lines = vec![format!(
+        "shortcuts: {count} (pass the keys to computer_key split at \"+\")"
+    )];
+    for group in groups {
+        lines.push(format!("{}:", group.source));
+        lines.extend(
+            group
+                .shortcuts
+                .iter()
+                .map(|shortcut| format!("  {} – {}", shortcut.keys, shortcut.action)),
+        );
+    }
+    lines
+}
+
+/// GNOME's bindings from `GNOME_SCHEMAS`, then the user's custom ones.
+fn gnome(gsettings: &Path, config: &ComputerUseConfig) -> Vec<Shortcut> {
+    let mut shortcuts = Vec::new();
+    for schema in GNOME_SCHEMAS {
+        // A schema the desktop doesn't install fails, and is skipped.
+        if let Ok(listing) = output(gsettings, config, &["list-recursively", schema]) {
+            shortcuts.extend(parse_gsettings(&listing));
+        }
+    }
+    let Ok(paths) = output(
+        gsettings,
+        config,
+        &["get", MEDIA_KEYS, "custom-keybindings"],
+    ) else {
+        return shortcuts;
+    };
+    for path in gvariant_strings(&paths) {
+        let schema = format!("{CUSTOM_KEYBINDING}:{path}");
+        let get = |key: &str| {
+            let value = output(gsettings, config, &["get", &schema, key]).ok()?;
+            let value = value.trim().trim_matches('\'').trim();
+            (!value.is_empty()).then(|| value.to_string())
+        };
+        if let (Some(name), Some(binding)) = (get("name"), get("binding"))
+            && let Some(keys) = gtk_chord(&binding)
+        {
+            shortcuts.push(Shortcut { keys, action: name });
+        }
+    }
+    shortcuts
+}
+
+/// Reads `gsettings list-recursively` lines such as
+/// `org.gnome.desktop.wm.keybindings maximize ['<Super>Up']`, keeping the
+/// keys bound to chords. Numbers and booleans in the same schemas are
+/// settings, not bindings.
+fn parse_gsettings(listing: &str) -> Vec<Shortcut> {
+    let mut shortcuts = Vec::new();
+    for line in listing.lines() {
+        let mut fields = line.splitn(3, ' ');
+        let (Some(_), Some(key), Some(value)) = (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        if !value.starts_with(['\'', '[', '@']) {
+            continue;
+        }
+        let action = key.replace('-', " ");
+        shortcuts.extend(
+            gvariant_strings(value)
+                .iter()
+                .filter_map(|binding| gtk_chord(binding))
+                .map(|keys| Shortcut {
+                    keys,
+                    action: action.clone(),
+                }),
+        );
+    }
+    shortcuts
+}
+
+/// The strings of a GVariant string or string array as gsettings prints it:
+/// `'<Super>l'`, `['<Super>Up', '<Alt>F10']`, or `@as []`.
+fn gvariant_strings(value: &str) -> Vec<String> {
+    let value = value.trim();
+    let value = value.strip_prefix("@as").unwrap_or(value).trim();
+    let inner = value
+        .strip_prefix('[')
+        .and_then(|value| value.strip_suffix(']'))
+        .unwrap_or(value);
+    inner
+        .split(',')
+        .map(|item| item.trim().trim_matches(|c| c == '\'' || c == '"'))
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// `$XDG_CONFIG_HOME/kglobalshortcutsrc`, or the one in `~/.config`.
+fn kde_config() -> Option<PathBuf> {
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| dirs::home_dir().map(|home| home.join(".config")))?;
+    Some(config.join("kglobalshortcutsrc"))
+}
+
+/// Reads `kglobalshortcutsrc`: a section per component, and lines such as
+/// `Window Maximize=Meta+PgUp,Meta+PgUp,Maximize Window` giving the active
+/// shortcuts (tab separated), the defaults, and a friendly name.
+fn parse_kglobalshortcuts(contents: &str) -> Vec<ShortcutGroup> {
+    let mut groups: Vec<ShortcutGroup> = Vec::new();
+    for line in contents.lines() {
+        let line = line.trim();
+        if let Some(section) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            groups.push(ShortcutGroup {
+                source: format!("KDE {section}"),
+                shortcuts: Vec::new(),
+            });
+            continue;
+        }
+        let (Some(group), Some((key, value))) = (groups.last_mut(), line.split_once('=')) else {
+            continue;
+        };
+        if key == "_k_friendly_name" {
+            group.source = format!("KDE {}", value.trim());
+            continue;
+        }
+        if key.starts_with("_k_") {
+            continue;
+        }
+        let mut fields = value.splitn(3, ',');
+        let active = fields.next().unwrap_or_default();
+        let action = fields
+            .nth(1)
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .unwrap_or(key);
+        group.shortcuts.extend(
+            active
+                .split('\t')
+                .filter_map(qt_chord)
+                .map(|keys| Shortcut {
+                    keys,
+                    action: action.to_string(),
+                }),
+        );
+    }
+    groups.retain(|group| !group.shortcuts.is_empty());
+    groups
+}
+
+/// The accelerator of a menu item's AT-SPI key binding. GTK reports
+/// `mnemonic;full path;accelerator`, e.g. `o;<Alt>f:o;<Control>o`; Qt and
+/// others report the accelerator alone, e.g. `Ctrl+O`.
+fn menu_chord(binding: &str) -> Option<String> {
+    let fields: Vec<&str> = binding.split(';').collect();
+    let accelerator = match fields.as_slice() {
+        [_, _, accelerator] => accelerator.trim(),
+        [accelerator] => accelerator.trim(),
+        _ => return None,
+    };
+    if accelerator.starts_with('<') {
+        gtk_chord(accelerator)
+    } else if accelerator.contains('+') || accelerator.len() > 1 {
+        qt_chord(accelerator)
+    } else {
+        // A bare letter is a mnemonic, which only works with its menu open.
+        None
+    }
+}
+
+/// A GTK accelerator such as `<Primary><Shift>t` as a `computer_key` chord.
+fn gtk_chord(accelerator: &str) -> Option<String> {
+    let mut rest = accelerator.trim();
+    let mut keys = Vec::new();
+    while let Some(tail) = rest.strip_prefix('<') {
+        let (modifier, tail) = tail.split_once('>')?;
+        keys.push(modifier_name(modifier)?);
+        rest = tail;
+    }
+    let key = key_name(rest)?;
+    keys.push(key);
+    Some(keys.join("+"))
+}
+
+/// A Qt key sequence such as `Meta+Shift+E` or `Ctrl++` as a `computer_key`
+/// chord. `none` and empty sequences have no chord.
+fn qt_chord(sequence: &str) -> Option<String> {
+    let sequence = sequence.trim();
+    if sequence.is_empty() || sequence.eq_ignore_ascii_case("none") {
+        return None;
+    }
+    // A trailing `+` after a separator is the plus key itself.
+    let (sequence, plus) = match sequence.strip_suffix("++") {
+        Some(modifiers) => (modifiers, true),
+        None => (sequence, false),
+    };
+    let mut parts: Vec<&str> = sequence.split('+').collect();
+    let key = if plus {
+        "plus".to_string()
+    } else {
+        key_name(parts.pop()?)?
+    };
+    let mut keys = parts
+        .into_iter()
+        .map(modifier_name)
+        .collect::<Option<Vec<_>>>()?;
+    keys.push(key);
+    Some(keys.join("+"))
+}
+
+fn modifier_name(modifier: &str) -> Option<String> {
+    let name = match modifier.trim().to_ascii_lowercase().as_str() {
+        "primary" | "control" | "ctrl" | "ctl" => "ctrl",
+        "shift" => "shift",
+        "alt" | "mod1" => "alt",
+        "super" | "meta" | "hyper" | "mod4" => "super",
+        _ => return None,
+    };
+    Some(name.to_string())
+}
+
+/// Single letters are lowercased, as `computer_key` expects; key names such
+/// as `F10` or `Print` are kept. Paths and phrases aren't keys.
+fn key_name(key: &str) -> Option<String> {
+    let key = key.trim();
+    if key.contains(['/', ' ']) {
+        return None;
+    }
+    let mut chars = key.chars();
+    match (chars.next(), chars.next()) {
+        (None, _) => None,
+        (Some(letter), None) => Some(letter.to_lowercase().to_string()),
+        _ => Some(key.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn reads_gnome_kde_and_menu_bindings_as_chords() {
+        let listing = "\
+org.gnome.desktop.wm.keybindings maximize ['<Super>Up']
+org.gnome.desktop.wm.keybindings close ['<Alt>F4', '<Primary><Shift>q']
+org.gnome.desktop.wm.keybindings lower @as []
+org.gnome.settings-daemon.plugins.media-keys custom-keybindings ['/org/gnome/settings-daemon/plugins/media-keys/custom-keybindings/custom0/']
+org.gnome.settings-daemon.plugins.media-keys volume-step 6
+org.gnome.settings-daemon.plugins.media-keys screensaver '<Super>l'
+";
+        let keys = |shortcuts: &[Shortcut]| {
+            shortcuts
+                .iter()
+                .map(|shortcut| format!("{} {}", shortcut.keys, shortcut.action))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            keys(&parse_gsettings(listing)),
+            vec![
+                "super+Up maximize",
+                "alt+F4 close",
+                "ctrl+shift+q close",
+                "super+l screensaver",
+            ]
+        );
+        assert_eq!(
+            gvariant_strings(
+                "['/org/gnome/settings-daemon/plugins/media-keys/custom-keybindings/custom0/']"
+            ),
+            vec!["/org/gnome/settings-daemon/plugins/media-keys/custom-keybindings/custom0/"]
+        );
+
+        let kde = "\
+[kwin]
+_k_friendly_name=KWin
+Window Maximize=Meta+PgUp,Meta+PgUp,Maximize Window
+Window Close=Alt+F4\tMeta+Q,Alt+F4,Close Window
+Kill Window=none,Meta+Ctrl+Esc,Kill a Window
+
+[org.kde.konsole.desktop]
+_launch=Ctrl+Alt+T,Ctrl+Alt+T,Konsole
+";
+        let groups = parse_kglobalshortcuts(kde);
+        assert_eq!(
+            groups
+                .iter()
+                .map(|group| (group.source.as_str(), keys(&group.shortcuts)))
+                .collect::<Vec<(&str, Vec<String>)>>(),
+            vec![
+                (
+                    "KDE KWin",
+                    vec![
+                        "super+PgUp Maximize Window".to_string(),
+                        "alt+F4 Close Window".to_string(),
+                        "super+q Close Window".to_string(),
+                    ]
+                ),
+                (
+                    "KDE org.kde.konsole.desktop",
+                    vec!["ctrl+alt+t Konsole".to_string()]
+                ),
+            ]
+        );
+
+        assert_eq!(
+            menu_chord("o;<Alt>f:o;<Control>o").as_deref(),
+            Some("ctrl+o")
+        );
+        assert_eq!(menu_chord("Ctrl+Shift+N").as_deref(), Some("ctrl+shift+n"));
+        assert_eq!(menu_chord("Ctrl++").as_deref(), Some("ctrl+plus"));
+        assert_eq!(menu_chord("F11").as_deref(), Some("F11"));
+        assert_eq!(menu_chord("x"), None);
+        assert_eq!(menu_chord("x;<Alt>e:x;"), None);
+
+        let filtered = filter(groups, "close");
+        assert_eq!(
+            describe(&filtered),
+            vec![
+                "shortcuts: 2 (pass the keys to computer_key split at \"+\")",
+                "KDE KWin:",
+                "  alt+F4 – Close Window",
+                "  super+q – Close Window",
+            ]
+        );
+    }
+}
@@ -0,0 +1,216 @@
+//! Approval rules for GUI actions whose side effects escape the command
+//! sandbox: typing into a terminal runs commands, and clicking a browser's
+//! download button writes files fetched from the network.
+//!
+//! Each class has a [`GuiSideEffectPolicy`]. `auto` defers to the session's
+//! sandbox and approval policies, so a session that may not write outside the
+//! workspace or reach the network asks before the GUI does it on its behalf.
+//! With `terminal_typing_exec_policy`, text typed into a terminal is instead
+//! checked like a shell command the agent asked to run outside the sandbox.
+
+use super::find_text;
+use crate::config::types::ComputerUseConfig;
+use crate::config::types::GuiSideEffectPolicy;
+use crate::protocol::AskForApproval;
+use crate::protocol::SandboxPolicy;
+
+/// Terminal emulator window classes (lowercase).
+const TERMINALS: &[&str] = &[
+    "gnome-terminal-server",
+    "gnome-terminal",
+    "org.gnome.console",
+    "kgx",
+    "konsole",
+    "xterm",
+    "uxterm",
+    "urxvt",
+    "rxvt",
+    "kitty",
+    "alacritty",
+    "org.wezfurlong.wezterm",
+    "wezterm",
+    "tilix",
+    "terminator",
+    "xfce4-terminal",
+    "mate-terminal",
+    "lxterminal",
+    "qterminal",
+    "st-256color",
+    "foot",
+    "ghostty",
+    "com.mitchellh.ghostty",
+];
+
+/// Words on controls that save files from the network.
+const DOWNLOAD_WORDS: &[&str] = &["download", "save", "install", "export"];
+
+/// Minimum OCR similarity for a download word to count.
+const DOWNLOAD_WORD_SIMILARITY: f64 = 0.85;
+
+/// Size in screen pixels of the area read around a browser click.
+pub(super) const DOWNLOAD_PROBE_SIZE: (i64, i64) = (240, 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum SideEffect {
+    TerminalInput,
+    Download,
+}
+
+impl SideEffect {
+    /// The config key holding this class's rule.
+    pub(super) fn config_key(self) -> &'static str {
+        match self {
+            SideEffect::TerminalInput => "computer_use.terminal_input_policy",
+            SideEffect::Download => "computer_use.download_policy",
+        }
+    }
+
+    pub(super) fn policy(self, config: &ComputerUseConfig) -> GuiSideEffectPolicy {
+        match self {
+            SideEffect::TerminalInput => config.terminal_input_policy,
+            SideEffect::Download => config.download_policy,
+        }
+    }
+
+    /// What the action can do that the sandbox would otherwise prevent.
+    pub(super) fn consequence(self) -> &'static str {
+        match self {
+            SideEffect::TerminalInput => {
+                "input to a terminal runs commands outside the sandbox, which can write files and reach the network"
+            }
+            SideEffect::Download => {
+                "clicking a download control fetches from the network and writes the file to disk"
+            }
+        }
+    }
+}
+
+/// What to do with an action of a given class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Requirement {
+    Allow,
+    Ask,
+    Deny,
+}
+
+/// Resolves `policy` against the session's sandbox and approval policies.
+pub(super) fn requirement(
+    policy: GuiSideEffectPolicy,
+    sandbox: &SandboxPolicy,
+    approval: AskForApproval,
+) -> Requirement {
+    match policy {
+        GuiSideEffectPolicy::Allow => Requirement::Allow,
+        GuiSideEffectPolicy::Ask => Requirement::Ask,
+        GuiSideEffectPolicy::Deny => Requirement::Deny,
+        GuiSideEffectPolicy::Auto
+            if sandbox.has_full_disk_write_access() && sandbox.has_full_network_access() =>
+        {
+            Requirement::Allow
+        }
+        GuiSideEffectPolicy::Auto if approval == AskForApproval::Never => Requirement::Deny,
+        GuiSideEffectPolicy::Auto => Requirement::Ask,
+    }
+}
+
+/// Whether the window class `class` belongs to a terminal emulator.
+pub(super) fn is_terminal(class: &str, config: &ComputerUseConfig) -> bool {
+    let class = class.trim();
+    !class.is_empty()
+        && (TERMINALS
+            .iter()
+            .any(|known| known.eq_ignore_ascii_case(class))
+            || config
+                .terminal_apps
+                .iter()
+                .any(|app| app.trim().eq_ignore_ascii_case(class)))
+}
+
+/// The shell command that typing `text` into a terminal amounts to, for the
+/// exec policy. Trailing line breaks are dropped since they only submit it.
+pub(super) fn typed_command(text: &str) -> Option<Vec<String>> {
+    let script = text.trim_end_matches(['\n', '\r']);
+    if script.trim().is_empty() {
+        return None;
+    }
+    Some(vec![
+        "bash".to_string(),
+        "-lc".to_string(),
+        script.to_string(),
+    ])
+}
+
+/// Whether tesseract `tsv` output from around a click shows a download word.
+pub(super) fn mentions_download(tsv: &str) -> bool {
+    DOWNLOAD_WORDS
+        .iter()
+        .any(|word| !find_text::find_matches(tsv, word, DOWNLOAD_WORD_SIMILARITY).is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn auto_follows_sandbox_and_approval_policies() {
+        let restricted = SandboxPolicy::new_workspace_write_policy();
+        assert_eq!(
+            requirement(
+                GuiSideEffectPolicy::Auto,
+                &restricted,
+                AskForApproval::OnRequest
+            ),
+            Requirement::Ask
+        );
+        assert_eq!(
+            requirement(
+                GuiSideEffectPolicy::Auto,
+                &restricted,
+                AskForApproval::Never
+            ),
+            Requirement::Deny
+        );
+        assert_eq!(
+            requirement(
+                GuiSideEffectPolicy::Auto,
+                &SandboxPolicy::DangerFullAccess,
+                AskForApproval::OnRequest
+            ),
+            Requirement::Allow
+        );
+        assert_eq!(
+            requirement(
+                GuiSideEffectPolicy::Ask,
+                &SandboxPolicy::DangerFullAccess,
+                AskForApproval::Never
+            ),
+            Requirement::Ask
+        );
+    }
+
+    #[test]
+    fn terminals_include_configured_apps() {
+        let config = ComputerUseConfig {
+            terminal_apps: vec!["Cool-Retro-Term".to_string()],
+            ..Default::default()
+        };
+        assert!(is_terminal("Alacritty", &config));
+        assert!(is_terminal("cool-retro-term", &config));
+        assert!(!is_terminal("firefox", &config));
+        assert!(!is_terminal("", &config));
+    }
+
+    #[test]
+    fn typed_text_becomes_a_shell_command() {
+        assert_eq!(
+            typed_command("rm -rf ~\n"),
+            Some(vec![
+                "bash".to_string(),
+                "-lc".to_string(),
+                "rm -rf ~".to_string()
+            ])
+        );
+        assert_eq!(typed_command(" \n"), None);
+    }
+}
@@ -0,0 +1,355 @@
+//! VM checkpoints for `computer_checkpoint` and `computex rollback`.
+//!
+//! When `computer_use.vm_provider` and `computer_use.vm_name` point at the VM
+//! the agent drives, the agent can snapshot it before a risky sequence through
+//! the hypervisor's CLI (`virsh` or `VBoxManage`). Snapshots computex took are
+//! recorded in `$CODEX_HOME/computer_use/checkpoints.json` so `computex
+//! rollback` can find the latest one without touching snapshots taken by hand.
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+
+use anyhow::Context;
+use chrono::DateTime;
+use chrono::Utc;
+use serde::Deserialize;
+use serde::Serialize;
+use which::which;
+
+use super::json_store;
+use super::json_store::StoreLock;
+use crate::config::types::VmProvider;
+use crate::config::types::VmTarget;
+
+/// Location of the checkpoint record, relative to `$CODEX_HOME`.
+pub const CHECKPOINTS_FILE: &str = "computer_use/checkpoints.json";
+
+/// Oldest records are dropped once a VM has more than this many checkpoints.
+/// The snapshots themselves stay in the hypervisor.
+const MAX_CHECKPOINTS_PER_VM: usize = 100;
+
+static STORE_LOCK: StoreLock = StoreLock::new();
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Snapshot name in the hypervisor, e.g. `computex-20260101-120000`.
+    pub name: String,
+    pub vm: String,
+    pub provider: VmProvider,
+    /// Why the agent took the checkpoint.
+    pub label: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CheckpointStore {
+    #[serde(default)]
+    checkpoints: Vec<Checkpoint>,
+}
+
+/// One hypervisor CLI invocation.
+#[derive(Debug, PartialEq)]
+struct VmCommand {
+    program: &'static str,
+    args: Vec<String>,
+    /// Failure is expected in some states (e.g. powering off a stopped VM).
+    may_fail: bool,
+}
+
+impl VmCommand {
+    fn new(program: &'static str, args: &[&str]) -> Self {
+        Self {
+            program,
+            args: args.iter().map(|arg| (*arg).to_string()).collect(),
+            may_fail: false,
+        }
+    }
+}
+
+/// Snapshots `target` and records the checkpoint.
+pub fn create_checkpoint(
+    target: &VmTarget,
+    codex_home: &Path,
+    label: &str,
+) -> anyhow::Result<Checkpoint> {
+    let path = codex_home.join(CHECKPOINTS_FILE);
+    let _guard = STORE_LOCK.lock();
+    let mut store: CheckpointStore = json_store::load(&path)?;
+    let created_at = Utc::now();
+    let name = unique_name(&store, target, created_at);
+    run_all(&[take_command(target, &name, label)])?;
+    let checkpoint = Checkpoint {
+        name,
+        vm: target.name.clone(),
+        provider: target.provider,
+        label: label.to_string(),
+        created_at,
+    };
+    store.checkpoints.push(checkpoint.clone());
+    prune(&mut store, target);
+    json_store::persist(&path, &store)?;
+    Ok(checkpoint)
+}
+
+/// Checkpoints recorded for `target`, newest first.
+pub fn list_checkpoints(target: &VmTarget, codex_home: &Path) -> anyhow::Result<Vec<Checkpoint>> {
+    let path = codex_home.join(CHECKPOINTS_FILE);
+    let _guard = STORE_LOCK.lock();
+    let mut checkpoints: Vec<Checkpoint> = json_store::load::<CheckpointStore>(&path)?
+        .checkpoints
+        .into_iter()
+        .filter(|checkpoint| is_for(checkpoint, target))
+        .collect();
+    checkpoints.sort_by_key(|checkpoint| std::cmp::Reverse(checkpoint.created_at));
+    Ok(checkpoints)
+}
+
+/// Restores `target` to the checkpoint called `name`, or to the latest one.
+pub fn rollback(
+    target: &VmTarget,
+    codex_home: &Path,
+    name: Option<&str>,
+) -> anyhow::Result<Checkpoint> {
+    let checkpoints = list_checkpoints(target, codex_home)?;
+    let checkpoint = match name {
+        Some(name) => checkpoints
+            .into_iter()
+            .find(|checkpoint| checkpoint.name == name)
+            .with_context(|| format!("no computex checkpoint named `{name}` for {}", target.name))?,
+        None => checkpoints.into_iter().next().with_context(|| {
+            format!(
+                "no computex checkpoints recorded for {}; the agent creates them with computer_checkpoint",
+                target.name
+            )
+        })?,
+    };
+    run_all(&restore_commands(target, &checkpoint.name))?;
+    Ok(checkpoint)
+}
+
+fn take_command(target: &VmTarget, name: &str, label: &str) -> VmCommand {
+    let vm = target.name.as_str();
+    match target.provider {
+        VmProvider::Libvirt => VmCommand::new(
+            "virsh",
+            &[
+                "snapshot-create-as",
+                "--domain",
+                vm,
+                "--name",
+                name,
+                "--description",
+                label,
+                "--atomic",
+            ],
+        ),
+        VmProvider::Virtualbox => VmCommand::new(
+            "VBoxManage",
+            &[
+                "snapshot",
+                vm,
+                "take",
+                name,
+                "--description",
+                label,
+                "--live",
+            ],
+        ),
+    }
+}
+
+/// libvirt reverts a running domain in place. VirtualBox only restores
+/// snapshots of a powered-off VM, so it is stopped first and restarted
+/// headless afterwards.
+fn restore_commands(target: &VmTarget, name: &str) -> Vec<VmCommand> {
+    let vm = target.name.as_str();
+    match target.provider {
+        VmProvider::Libvirt => vec![VmCommand::new(
+            "virsh",
+            &[
+                "snapshot-revert",
+                "--domain",
+                vm,
+                "--snapshotname",
+                name,
+                "--running",
+            ],
+        )],
+        VmProvider::Virtualbox => vec![
+            VmCommand {
+                may_fail: true,
+                ..VmCommand::new("VBoxManage", &["controlvm", vm, "poweroff"])
+            },
+            VmCommand::new("VBoxManage", &["snapshot", vm, "restore", name]),
+            VmCommand::new("VBoxManage", &["startvm", vm, "--type", "headless"]),
+        ],
+    }
+}
+
+fn run_all(commands: &[VmCommand]) -> anyhow::Result<()> {
+    for command in commands {
+        let program = require_program(command.program)?;
+        let output = Command::new(&program)
+            .args(&command.args)
+            .output()
+            .with_context(|| format!("failed to run {}", command.program))?;
+        if !output.status.success() && !command.may_fail {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!(
+                "`{} {}` failed: {}",
+                command.program,
+                command.args.join(" "),
+                stderr.trim()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn require_program(program: &str) -> anyhow::Result<PathBuf> {
+    which(program).with_context(|| {
+        let hint = match program {
+            "virsh" => "install libvirt-clients",
+            _ => "install VirtualBox",
+        };
+        format!("{program} not found; {hint}")
+    })
+}
+
+fn is_for(checkpoint: &Checkpoint, target: &VmTarget) -> bool {
+    checkpoint.vm == target.name && checkpoint.provider == target.provider
+}
+
+/// `computex-<UTC timestamp>`, with a suffix if one was already taken this second.
+fn unique_name(store: &CheckpointStore, target: &VmTarget, at: DateTime<Utc>) -> String {
+    let base = format!("computex-{}", at.format("%Y%m%d-%H%M%S"));
+    let taken = |name: &str| {
+        store
+            .checkpoints
+            .iter()
+            .any(|checkpoint| is_for(checkpoint, target) && checkpoint.name == name)
+    };
+    let mut name = base.clone();
+    let mut suffix = 2;
+    while taken(&name) {
+        name = format!("{base}-{suffix}");
+        suffix += 1;
+    }
+    name
+}
+
+fn prune(store: &mut CheckpointStore, target: &VmTarget) {
+    let count = store
+        .checkpoints
+        .iter()
+        .filter(|checkpoint| is_for(checkpoint, target))
+        .count();
+    let mut excess = count.saturating_sub(MAX_CHECKPOINTS_PER_VM);
+    store
+        .checkpoints
+        .sort_by_key(|checkpoint| checkpoint.created_at);
+    store.checkpoints.retain(|checkpoint| {
+        if excess > 0 && is_for(checkpoint, target) {
+            excess -= 1;
+            false
+        } else {
+            true
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use pretty_assertions::assert_eq;
+
+    fn target(provider: VmProvider) -> VmTarget {
+        VmTarget {
+            provider,
+            name: "win11".to_string(),
+        }
+    }
+
+    #[test]
+    fn builds_hypervisor_commands() {
+        assert_eq!(
+            take_command(&target(VmProvider::Libvirt), "computex-1", "before install"),
+            VmCommand::new(
+                "virsh",
+                &[
+                    "snapshot-create-as",
+                    "--domain",
+                    "win11",
+                    "--name",
+                    "computex-1",
+                    "--description",
+                    "before install",
+                    "--atomic",
+                ],
+            )
+        );
+        let restore = restore_commands(&target(VmProvider::Virtualbox), "computex-1");
+        let steps: Vec<(String, bool)> = restore
+            .iter()
+            .map(|command| (command.args.join(" "), command.may_fail))
+            .collect();
+        assert_eq!(
+            steps,
+            vec![
+                ("controlvm win11 poweroff".to_string(), true),
+                ("snapshot win11 restore computex-1".to_string(), false),
+                ("startvm win11 --type headless".to_string(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn names_are_unique_per_vm_and_lists_are_newest_first() -> anyhow::Result<()> {
+        let libvirt = target(VmProvider::Libvirt);
+        let at = Utc.with_ymd_and_hms(2026, 1, 2, 3, 4, 5).unwrap();
+        let checkpoint = |name: &str, created_at| Checkpoint {
+            name: name.to_string(),
+            vm: "win11".to_string(),
+            provider: VmProvider::Libvirt,
+            label: String::new(),
+            created_at,
+        };
+        let store = CheckpointStore {
+            checkpoints: vec![
+                checkpoint("computex-20260102-030405", at),
+                checkpoint(
+                    "computex-20260102-030405-2",
+                    at + chrono::Duration::seconds(1),
+                ),
+            ],
+        };
+        assert_eq!(
+            unique_name(&store, &libvirt, at),
+            "computex-20260102-030405-3"
+        );
+        assert_eq!(
+            unique_name(&store, &target(VmProvider::Virtualbox), at),
+            "computex-20260102-030405"
+        );
+
+        let dir = tempfile::tempdir()?;
+        json_store::persist(&dir.path().join(CHECKPOINTS_FILE), &store)?;
+        let names: Vec<String> = list_checkpoints(&libvirt, dir.path())?
+            .into_iter()
+            .map(|checkpoint| checkpoint.name)
+            .collect();
+        assert_eq!(
+            names,
+            vec!["computex-20260102-030405-2", "computex-20260102-030405"]
+        );
+        assert!(
+            rollback(&libvirt, dir.path(), Some("missing"))
+                .unwrap_err()
+                .to_string()
+                .contains("no computex checkpoint named `missing`")
+        );
+        Ok(())
+    }
+}
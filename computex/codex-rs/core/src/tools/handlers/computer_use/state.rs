@@ -0,0 +1,521 @@
+//! Per-session computer-use state shared across turns.
+
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::Instant;
+
+use super::clipboard::AgentClipboard;
+use super::console::SerialConsole;
+use super::deferred::DeferredImages;
+use super::frames::FrameRecorder;
+use super::inhibit::DisplayInhibitor;
+use super::input_helper::InputHelper;
+use super::prefetch::Prefetched;
+use super::recovery::TargetWindow;
+use super::schema::SystemSetting;
+use super::side_effects::SideEffect;
+use super::system_control::Level;
+use super::system_control::SavedSettings;
+use super::theme::SavedTheme;
+use super::turn_summary::ScreenState;
+use crate::config::types::ComputerUseConfig;
+use crate::protocol::GuiClickAccuracy;
+use crate::protocol::GuiUsage;
+use crate::protocol::QueuedGuiAction;
+use codex_protocol::user_input::UserInput;
+
+#[derive(Debug, Default)]
+pub(crate) struct ComputerUseState {
+    /// Window that last received input successfully.
+    pub(super) target: Option<TargetWindow>,
+    /// Recent pointer actions with their model-space coordinates, newest first.
+    breadcrumbs: VecDeque<String>,
+    /// GUI actions held for review while `computer_use.action_queue` is set.
+    queue: Vec<QueuedGuiAction>,
+    /// Turn whose screenshots `screenshots_taken` counts.
+    screenshot_turn: Option<String>,
+    screenshots_taken: u32,
+    /// Best `computer_find_text` confidence and the turn it was seen in.
+    ocr_confidence: Option<(String, f64)>,
+    /// Bumped on every input action, so prefetched screenshots taken before
+    /// it are never served.
+    input_generation: u64,
+    prefetched: Option<Prefetched>,
+    /// Input helper and the display it was started for; the helper is
+    /// `None` when it could not start there.
+    input_helper: Option<(Option<String>, Option<InputHelper>)>,
+    /// Side-effect classes the user approved for the rest of the session.
+    approved_side_effects: HashSet<SideEffect>,
+    /// Session totals reported with token usage.
+    usage: GuiUsage,
+    /// Why GUI input was suspended by a screen lock, until the user confirms.
+    lock_suspension: Option<String>,
+    /// Keeps the display from blanking until the session ends.
+    display_inhibitor: Option<DisplayInhibitor>,
+    /// Recent low-resolution frames, once `computer_use.recent_frames` has
+    /// started recording them.
+    frame_recorder: Option<FrameRecorder>,
+    /// The desktop theme from before `computer_set_theme`, restored when the
+    /// session ends.
+    saved_theme: Option<SavedTheme>,
+    /// Volume, mute, and brightness from before `computer_system_control`
+    /// changed them, restored when the session ends.
+    saved_system_settings: Option<SavedSettings>,
+    /// Window named by `computer_set_target`, which scopes every GUI tool
+    /// until it is cleared.
+    sticky_target: Option<String>,
+    /// What the screen showed before the first input since the model last
+    /// looked at it, for `computer_use.turn_change_summary`.
+    change_baseline: Option<ScreenState>,
+    /// Screenshots kept in `computer_use.screenshot_dir` so far.
+    kept_screenshots: u64,
+    /// Where the current tool call kept its screenshot, for the timeline.
+    kept_screenshot: Option<PathBuf>,
+    /// Text agent actions copied while `computer_use.preserve_clipboard`
+    /// kept it off the user's clipboard.
+    agent_clipboard: AgentClipboard,
+    /// When a pointer or keyboard grab by another client was first seen
+    /// before an input action, while it lasts.
+    grab_since: Option<Instant>,
+    /// The serial port of the `console` backend, read since the first call.
+    serial_console: Option<SerialConsole>,
+    /// Apps whose recipe guidance the model has been shown.
+    guided_apps: HashSet<String>,
+    /// How the current tool call's click landed, for the timeline.
+    click_accuracy: Option<GuiClickAccuracy>,
+    /// Images captured while no task was running, for the next one.
+    deferred_images: DeferredImages,
+}
+
+impl ComputerUseState {
+    /// Records `action` and returns a result suffix listing up to `limit`
+    /// earlier actions, so the model keeps spatial context without another
+    /// screenshot. A `limit` of zero disables breadcrumbs.
+    pub(super) fn push_breadcrumb(&mut self, action: Option<String>, limit: usize) -> String {
+        let suffix = format_breadcrumbs(self.breadcrumbs.iter().take(limit));
+        if let Some(action) = action
+            && limit > 0
+        {
+            self.breadcrumbs.push_front(action);
+            self.breadcrumbs.truncate(limit);
+        }
+        suffix
+    }
+
+    /// Appends `action` to the review queue and returns its 1-based position.
+    pub(super) fn enqueue(&mut self, action: QueuedGuiAction) -> usize {
+        self.queue.push(action);
+        self.queue.len()
+    }
+
+    /// Takes up to `wanted` images from `turn_id`'s budget of `limit`, in one
+    /// step so parallel calls can't both take the last one. Returns how many
+    /// were granted and how many are left. The count starts over whenever a
+    /// new turn asks.
+    pub(super) fn reserve_screenshots(
+        &mut self,
+        turn_id: &str,
+        limit: u32,
+        wanted: u32,
+    ) -> (u32, u32) {
+        if self.screenshot_turn.as_deref() != Some(turn_id) {
+            self.screenshot_turn = Some(turn_id.to_string());
+            self.screenshots_taken = 0;
+        }
+        let granted = wanted.min(limit.saturating_sub(self.screenshots_taken));
+        self.screenshots_taken += granted;
+        (granted, limit.saturating_sub(self.screenshots_taken))
+    }
+
+    /// Gives back `count` images reserved for `turn_id` that were never
+    /// attached.
+    pub(super) fn release_screenshots(&mut self, turn_id: &str, count: u32) {
+        if self.screenshot_turn.as_deref() == Some(turn_id) {
+            self.screenshots_taken = self.screenshots_taken.saturating_sub(count);
+        }
+    }
+
+    /// Counts a finished GUI tool run toward the session's automation time,
+    /// and toward its actions when it sent input.
+    pub(super) fn record_tool_run(&mut self, elapsed: Duration, sent_input: bool) {
+        self.usage.automation_ms += elapsed.as_millis() as u64;
+        if sent_input {
+            self.usage.actions += 1;
+        }
+    }
+
+    /// Counts an image of `bytes` attached to the conversation.
+    pub(super) fn record_attached_image(&mut self, bytes: u64, screenshot: bool) {
+        self.usage.image_bytes += bytes;
+        if screenshot {
+            self.usage.screenshots += 1;
+            // The model has seen the screen, so changes count from here.
+            self.change_baseline = None;
+        }
+    }
+
+    /// GUI automation totals for the session so far.
+    pub(crate) fn usage(&self) -> GuiUsage {
+        self.usage
+    }
+
+    /// Remembers how well OCR read the screen, for `detail: "auto"`.
+    pub(super) fn record_ocr_confidence(&mut self, turn_id: &str, confidence: f64) {
+        self.ocr_confidence = Some((turn_id.to_string(), confidence));
+    }
+
+    /// OCR confidence recorded earlier in `turn_id`, if any.
+    pub(super) fn recent_ocr_confidence(&self, turn_id: &str) -> Option<f64> {
+        self.ocr_confidence
+            .as_ref()
+            .filter(|(turn, _)| turn == turn_id)
+            .map(|(_, confidence)| *confidence)
+    }
+
+    pub(super) fn input_generation(&self) -> u64 {
+        self.input_generation
+    }
+
+    /// Records that input is about to change the screen. Returns the path of
+    /// a prefetched screenshot that is now stale, for the caller to delete.
+    pub(super) fn note_input(&mut self) -> Option<PathBuf> {
+        self.input_generation += 1;
+        self.prefetched.take().map(|prefetched| prefetched.path)
+    }
+
+    /// Keeps `prefetched` unless input happened since it was scheduled.
+    /// Returns the path of whichever screenshot was discarded.
+    pub(super) fn store_prefetch(&mut self, prefetched: Prefetched) -> Option<PathBuf> {
+        if prefetched.generation != self.input_generation {
+            return Some(prefetched.path);
+        }
+        self.prefetched
+            .replace(prefetched)
+            .map(|previous| previous.path)
+    }
+
+    pub(super) fn take_prefetch(&mut self) -> Option<Prefetched> {
+        self.prefetched.take()
+    }
+
+    /// The input helper for `display`, started on first use and restarted
+    /// if its X connection failed. `None` when it can't run there, in which
+    /// case input goes through `xdotool`.
+    pub(super) fn input_helper(&mut self, display: Option<&str>) -> Option<InputHelper> {
+        let reusable = match &self.input_helper {
+            Some((started_for, helper)) => {
+                started_for.as_deref() == display
+                    && helper.as_ref().is_none_or(InputHelper::is_alive)
+            }
+            None => false,
+        };
+        if !reusable {
+            let helper = InputHelper::spawn(display)
+                .inspect_err(|err| tracing::debug!("computer use input helper unavailable: {err}"))
+                .ok();
+            self.input_helper = Some((display.map(str::to_string), helper));
+        }
+        self.input_helper
+            .as_ref()
+            .and_then(|(_, helper)| helper.clone())
+    }
+
+    /// Empties the review queue, returning the actions in the order queued.
+    pub(super) fn take_queue(&mut self) -> Vec<QueuedGuiAction> {
+        std::mem::take(&mut self.queue)
+    }
+
+    /// Keeps the display awake for the rest of the session, starting the
+    /// inhibitor on the first call.
+    pub(super) fn hold_display_awake(&mut self, config: &ComputerUseConfig) {
+        if self.display_inhibitor.is_none() {
+            self.display_inhibitor = Some(DisplayInhibitor::acquire(config));
+        }
+    }
+
+    /// Starts recording frames on the first call; they are kept until the
+    /// session ends.
+    pub(super) fn record_frames(&mut self, config: &ComputerUseConfig) {
+        if self.frame_recorder.is_none() {
+            self.frame_recorder = Some(FrameRecorder::start(config));
+        }
+    }
+
+    /// Copies of the newest `count` recorded frames, oldest first, with
+    /// their ages.
+    pub(super) fn recent_frames(&self, count: usize) -> Vec<(PathBuf, Duration)> {
+        self.frame_recorder
+            .as_ref()
+            .map(|recorder| recorder.recent(count))
+            .unwrap_or_default()
+    }
+
+    /// The serial console on `device`, opened on the first call and
+    /// reopened when the configured port changes.
+    pub(super) fn serial_console(
+        &mut self,
+        device: &Path,
+        baud: u32,
+    ) -> Result<&SerialConsole, String> {
+        if !self
+            .serial_console
+            .as_ref()
+            .is_some_and(|console| console.is_for(device, baud))
+        {
+            // Close the old port before opening it again at another rate.
+            self.serial_console = None;
+            self.serial_console = Some(SerialConsole::open(device, baud)?);
+        }
+        self.serial_console
+            .as_ref()
+            .ok_or_else(|| format!("{} is not open", device.display()))
+    }
+
+    pub(super) fn sticky_target(&self) -> Option<String> {
+        self.sticky_target.clone()
+    }
+
+    pub(super) fn set_sticky_target(&mut self, window: Option<String>) {
+        self.sticky_target = window;
+    }
+
+    /// The agent's latest copy, which its pastes insert.
+    pub(super) fn agent_clipboard(&self) -> Option<String> {
+        self.agent_clipboard.top().map(str::to_string)
+    }
+
+    pub(super) fn push_agent_clipboard(&mut self, copied: String) {
+        self.agent_clipboard.push(copied);
+    }
+
+    /// Records whether a grab is held and returns how long it has been.
+    pub(super) fn note_grab(&mut self, held: bool) -> Duration {
+        if !held {
+            self.grab_since = None;
+            return Duration::ZERO;
+        }
+        self.grab_since.get_or_insert_with(Instant::now).elapsed()
+    }
+
+    /// Numbers the next screenshot kept in the screenshot directory,
+    /// starting at 1.
+    pub(super) fn next_kept_screenshot(&mut self) -> u64 {
+        self.kept_screenshots += 1;
+        self.kept_screenshots
+    }
+
+    /// Whether `app`'s recipe guidance is still to be shown; it is shown
+    /// once per session.
+    pub(super) fn take_guidance_turn(&mut self, app: &str) -> bool {
+        self.guided_apps.insert(app.to_string())
+    }
+
+    pub(super) fn note_click_accuracy(&mut self, sample: GuiClickAccuracy) {
+        self.click_accuracy = Some(sample);
+    }
+
+    pub(super) fn take_click_accuracy(&mut self) -> Option<GuiClickAccuracy> {
+        self.click_accuracy.take()
+    }
+
+    pub(super) fn defer_images(&mut self, images: Vec<UserInput>) {
+        self.deferred_images.push(images);
+    }
+
+    pub(super) fn take_deferred_images(&mut self) -> Vec<UserInput> {
+        self.deferred_images.take()
+    }
+
+    pub(super) fn note_kept_screenshot(&mut self, path: PathBuf) {
+        self.kept_screenshot = Some(path);
+    }
+
+    pub(super) fn take_kept_screenshot(&mut self) -> Option<PathBuf> {
+        self.kept_screenshot.take()
+    }
+
+    /// Picks up the context a resumed session recorded: the window that
+    /// last got input, the `computer_set_target` window, and how many
+    /// screenshots were kept, so numbering continues instead of
+    /// overwriting them.
+    pub(super) fn restore(
+        &mut self,
+        target: Option<TargetWindow>,
+        sticky_target: Option<String>,
+        kept_screenshots: u64,
+    ) {
+        self.target = target;
+        self.sticky_target = sticky_target;
+        self.kept_screenshots = kept_screenshots;
+    }
+
+    pub(super) fn has_change_baseline(&self) -> bool {
+        self.change_baseline.is_some()
+    }
+
+    pub(super) fn set_change_baseline(&mut self, baseline: ScreenState) {
+        self.change_baseline = Some(baseline);
+    }
+
+    pub(super) fn take_change_baseline(&mut self) -> Option<ScreenState> {
+        self.change_baseline.take()
+    }
+
+    /// Whether the theme to restore at the end of the session is saved.
+    pub(super) fn has_saved_theme(&self) -> bool {
+        self.saved_theme.is_some()
+    }
+
+    pub(super) fn save_theme(&mut self, theme: SavedTheme) {
+        self.saved_theme = Some(theme);
+    }
+
+    /// Saves `level` as the value `setting` is restored to when the session
+    /// ends, unless one is already saved.
+    pub(super) fn save_system_setting(
+        &mut self,
+        config: &ComputerUseConfig,
+        setting: SystemSetting,
+        level: Level,
+    ) {
+        self.saved_system_settings
+            .get_or_insert_with(|| SavedSettings::new(config))
+            .remember(setting, level);
+    }
+
+    /// Suspends GUI input because the screen locked for `reason`.
+    pub(super) fn suspend_for_lock(&mut self, reason: String) {
+        self.lock_suspension = Some(reason);
+    }
+
+    pub(super) fn lock_suspension(&self) -> Option<String> {
+        self.lock_suspension.clone()
+    }
+
+    pub(super) fn clear_lock_suspension(&mut self) {
+        self.lock_suspension = None;
+    }
+
+    pub(super) fn side_effect_approved(&self, effect: SideEffect) -> bool {
+        self.approved_side_effects.contains(&effect)
+    }
+
+    /// Stops asking about `effect` for the rest of the session.
+    pub(super) fn approve_side_effect(&mut self, effect: SideEffect) {
+        self.approved_side_effects.insert(effect);
+    }
+}
+
+fn format_breadcrumbs<'a>(entries: impl Iterator<Item = &'a String>) -> String {
+    let entries: Vec<&str> = entries.map(String::as_str).collect();
+    if entries.is_empty() {
+        String::new()
+    } else {
+        format!("\nprevious actions (newest first): {}", entries.join("; "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn breadcrumbs_report_earlier_actions_newest_first() {
+        let mut state = ComputerUseState::default();
+        assert_eq!(
+            state.push_breadcrumb(Some("click at 640,360".to_string()), 2),
+            ""
+        );
+        assert_eq!(
+            state.push_breadcrumb(Some("scroll down at 900,400".to_string()), 2),
+            "\nprevious actions (newest first): click at 640,360"
+        );
+        assert_eq!(
+            state.push_breadcrumb(None, 2),
+            "\nprevious actions (newest first): scroll down at 900,400; click at 640,360"
+        );
+        state.push_breadcrumb(Some("drag from 1,1 to 2,2".to_string()), 2);
+        assert_eq!(
+            state.push_breadcrumb(None, 2),
+            "\nprevious actions (newest first): drag from 1,1 to 2,2; scroll down at 900,400"
+        );
+        assert_eq!(
+            state.push_breadcrumb(Some("click at 0,0".to_string()), 0),
+            ""
+        );
+    }
+
+    #[test]
+    fn queue_keeps_actions_in_order_until_taken() {
+        let mut state = ComputerUseState::default();
+        let action = |tool: &str| QueuedGuiAction {
+            tool: tool.to_string(),
+            arguments: "{}".to_string(),
+        };
+        assert_eq!(state.enqueue(action("computer_click")), 1);
+        assert_eq!(state.enqueue(action("computer_type")), 2);
+        assert_eq!(
+            state.take_queue(),
+            vec![action("computer_click"), action("computer_type")]
+        );
+        assert_eq!(state.take_queue(), Vec::new());
+    }
+
+    #[test]
+    fn screenshot_budget_resets_each_turn() {
+        let mut state = ComputerUseState::default();
+        assert_eq!(state.reserve_screenshots("turn-1", 2, 1), (1, 1));
+        assert_eq!(state.reserve_screenshots("turn-1", 2, 1), (1, 0));
+        assert_eq!(state.reserve_screenshots("turn-1", 2, 1), (0, 0));
+        assert_eq!(state.reserve_screenshots("turn-2", 2, 1), (1, 1));
+    }
+
+    #[test]
+    fn screenshot_budget_grants_what_is_left() {
+        let mut state = ComputerUseState::default();
+        assert_eq!(state.reserve_screenshots("turn-1", 5, 3), (3, 2));
+        assert_eq!(state.reserve_screenshots("turn-1", 5, 3), (2, 0));
+        state.release_screenshots("turn-1", 1);
+        state.release_screenshots("turn-0", 1);
+        assert_eq!(state.reserve_screenshots("turn-1", 5, 3), (1, 0));
+    }
+
+    #[test]
+    fn ocr_confidence_is_scoped_to_its_turn() {
+        let mut state = ComputerUseState::default();
+        assert_eq!(state.recent_ocr_confidence("turn-1"), None);
+        state.record_ocr_confidence("turn-1", 0.9);
+        assert_eq!(state.recent_ocr_confidence("turn-1"), Some(0.9));
+        assert_eq!(state.recent_ocr_confidence("turn-2"), None);
+    }
+
+    #[test]
+    fn input_invalidates_prefetched_screenshots() {
+        let mut state = ComputerUseState::default();
+        let prefetched = |name: &str, generation| Prefetched {
+            path: PathBuf::from(name),
+            captured_at: std::time::Instant::now(),
+            generation,
+        };
+
+        let scheduled = state.input_generation();
+        assert_eq!(state.store_prefetch(prefetched("a.png", scheduled)), None);
+        assert_eq!(state.note_input(), Some(PathBuf::from("a.png")));
+        assert_eq!(
+            state.store_prefetch(prefetched("b.png", scheduled)),
+            Some(PathBuf::from("b.png"))
+        );
+        assert!(state.take_prefetch().is_none());
+
+        let scheduled = state.input_generation();
+        state.store_prefetch(prefetched("c.png", scheduled));
+        assert_eq!(
+            state.take_prefetch().map(|prefetched| prefetched.path),
+            Some(PathBuf::from("c.png"))
+        );
+    }
+}
@@ -0,0 +1,468 @@
+//! Success rates, retries, and actions per subtask for GUI tools across
+//! recorded sessions, for `computex stats`.
+//!
+//! Sessions recorded with the GUI action timeline say whether each call
+//! succeeded. In older rollouts a call counts as successful when its result
+//! starts the way the handler words success, and as failed otherwise; tools
+//! whose results have no fixed wording are counted without a verdict. A call
+//! is a retry when the previous GUI call in the session was the same tool and
+//! failed. A subtask is one user turn that sent GUI input; it is completed
+//! unless the turn was interrupted. Click accuracy samples in the timeline
+//! are summed per coordinate space.
+
+use std::collections::BTreeMap;
+
+use codex_protocol::models::ResponseItem;
+use codex_protocol::protocol::EventMsg;
+use codex_protocol::protocol::RolloutItem;
+
+use super::export::SUCCESS_PREFIXES;
+use super::verbosity::is_terse_ok;
+
+/// How the results of GUI tools that send no input start when they succeeded.
+const OBSERVATION_SUCCESS_PREFIXES: &[(&str, &[&str])] = &[
+    (
+        "computer_screenshot",
+        &["captured screenshot at ", "screen as text "],
+    ),
+    (
+        "computer_find_text",
+        &["found ", "no on-screen text matches "],
+    ),
+    (
+        "computer_detect_elements",
+        &["detected ", "the detector found no "],
+    ),
+    ("computer_locate", &["found ", "no element matches "]),
+    ("computer_dom_query", &["found ", "no element matches "]),
+    ("computer_compare", &["similarity "]),
+    ("computer_active_window", &["active window "]),
+    ("computer_workspace_list", &["workspaces: "]),
+    (
+        "computer_workspace_switch",
+        &["switched to workspace ", "already on workspace "],
+    ),
+    ("computer_workspace_move_window", &["moved "]),
+    ("computer_tray_list", &["tray items: ", "no tray items "]),
+    ("computer_network_state", &["network: "]),
+    ("computer_list_shortcuts", &["shortcuts: ", "no shortcuts "]),
+    (
+        "computer_tray_activate",
+        &[
+            "activated ",
+            "sent the secondary action ",
+            "the menu of ",
+            "asked ",
+            "selected ",
+        ],
+    ),
+    ("computer_run_queue", &["ran "]),
+    ("computer_checkpoint", &["snapshotted VM "]),
+    ("computer_unlock", &["unlocked the screen "]),
+    ("computer_magnifier", &["magnifier "]),
+    ("computer_set_theme", &["theme set to "]),
+    (
+        "computer_system_control",
+        &[
+            "volume: ",
+            "volume set to ",
+            "mute state: ",
+            "brightness: ",
+            "brightness set to ",
+            "audio ",
+        ],
+    ),
+    ("computer_announce", &["announced "]),
+    ("computer_camera_snapshot", &["captured camera frame "]),
+    ("computer_print_dialog_handle", &["printed to PDF at "]),
+    ("computer_file_dialog", &["opened ", "saved as "]),
+    (
+        "computer_office",
+        &[
+            "open documents ",
+            "no documents are open ",
+            "opened ",
+            "selected ",
+            "exported ",
+        ],
+    ),
+    ("computer_menu_select", &["selected ", "opened the "]),
+    ("computer_context_menu", &["right-clicked at "]),
+    ("computer_wait_download", &["downloaded "]),
+    ("computer_wait_app_idle", &["idle: "]),
+    (
+        "computer_set_target",
+        &["targeting ", "cleared the target window"],
+    ),
+    ("computer_watch_demo", &["recorded a "]),
+    ("computer_recent_frames", &["attached the last "]),
+];
+
+/// Counts for one GUI tool.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ToolStats {
+    pub calls: u64,
+    pub succeeded: u64,
+    pub failed: u64,
+    /// Calls that repeated this tool right after it failed.
+    pub retries: u64,
+}
+
+/// Click accuracy samples in one coordinate space.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClickAccuracyStats {
+    pub clicks: u64,
+    /// Sum of the distances from the element centers, in screen pixels.
+    pub distance: f64,
+    /// Sum of the offsets relative to the element size; 1 is the edge.
+    pub relative_offset: f64,
+}
+
+/// GUI tool statistics summed over one or more sessions.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GuiStats {
+    /// Sessions that called at least one GUI tool.
+    pub sessions: u64,
+    pub tools: BTreeMap<String, ToolStats>,
+    pub completed_subtasks: u64,
+    pub interrupted_subtasks: u64,
+    /// Input actions that succeeded within completed subtasks.
+    pub completed_subtask_actions: u64,
+    /// Click accuracy by coordinate space, e.g. `1280x800 space`.
+    pub click_accuracy: BTreeMap<String, ClickAccuracyStats>,
+}
+
+/// One user turn being tallied.
+#[derive(Default)]
+struct Subtask {
+    actions: u64,
+    sent_input: bool,
+    interrupted: bool,
+}
+
+impl GuiStats {
+    /// Adds the GUI tool calls recorded in one session's rollout `items`.
+    pub fn add_session(&mut self, items: &[RolloutItem]) {
+        let outputs: BTreeMap<&str, &str> = items
+            .iter()
+            .filter_map(|item| match item {
+                RolloutItem::ResponseItem(ResponseItem::FunctionCallOutput { call_id, output }) => {
+                    Some((call_id.as_str(), output.content.as_str()))
+                }
+                _ => None,
+            })
+            .collect();
+        // Sessions recorded with the GUI action timeline say outright.
+        let mut recorded: BTreeMap<&str, bool> = BTreeMap::new();
+        for item in items {
+            let RolloutItem::GuiAction(action) = item else {
+                continue;
+            };
+            recorded.insert(action.call_id.as_str(), action.success);
+            if let Some(sample) = &action.click_accuracy {
+                let stats = self
+                    .click_accuracy
+                    .entry(sample.coordinate_space.clone())
+                    .or_default();
+                stats.clicks += 1;
+                stats.distance += sample.distance();
+                stats.relative_offset += sample.relative_offset();
+            }
+        }
+
+        let mut saw_gui = false;
+        let mut previous_failure: Option<&str> = None;
+        let mut subtask = Subtask::default();
+        for item in items {
+            let (name, call_id) =
+                match item {
+                    RolloutItem::EventMsg(EventMsg::UserMessage(_)) => {
+                        self.finish_subtask(std::mem::take(&mut subtask));
+                        continue;
+                    }
+                    RolloutItem::EventMsg(EventMsg::TurnAborted(_)) => {
+                        subtask.interrupted = true;
+                        continue;
+                    }
+                    RolloutItem::ResponseItem(ResponseItem::FunctionCall {
+                        name, call_id, ..
+                    }) if name.starts_with("computer_") => (name.as_str(), call_id.as_str()),
+                    _ => continue,
+                };
+            let output = outputs.get(call_id).copied().unwrap_or_default();
+            if output.starts_with("queued ") {
+                // Held for review; the run is counted under computer_run_queue.
+                continue;
+            }
+            saw_gui = true;
+            let verdict = match recorded.get(call_id) {
+                Some(success) => Some(*success),
+                None => succeeded(name, output),
+            };
+            let stats = self.tools.entry(name.to_string()).or_default();
+            stats.calls += 1;
+            if previous_failure == Some(name) {
+                stats.retries += 1;
+            }
+            match verdict {
+                Some(true) => stats.succeeded += 1,
+                Some(false) => stats.failed += 1,
+                None => {}
+            }
+            previous_failure = (verdict == Some(false)).then_some(name);
+            if SUCCESS_PREFIXES.iter().any(|(tool, _)| *tool == name) {
+                subtask.sent_input = true;
+                if verdict == Some(true) {
+                    subtask.actions += 1;
+                }
+            }
+        }
+        self.finish_subtask(subtask);
+        if saw_gui {
+            self.sessions += 1;
+        }
+    }
+
+    fn finish_subtask(&mut self, subtask: Subtask) {
+        if !subtask.sent_input {
+            return;
+        }
+        if subtask.interrupted {
+            self.interrupted_subtasks += 1;
+        } else {
+            self.completed_subtasks += 1;
+            self.completed_subtask_actions += subtask.actions;
+        }
+    }
+
+    /// A plain-text report with a summary and one row per tool.
+    pub fn report(&self) -> String {
+        if self.sessions == 0 {
+            return "No GUI tool calls found in the recorded sessions.\n".to_string();
+        }
+        let calls: u64 = self.tools.values().map(|stats| stats.calls).sum();
+        let succeeded: u64 = self.tools.values().map(|stats| stats.succeeded).sum();
+        let failed: u64 = self.tools.values().map(|stats| stats.failed).sum();
+        let retries: u64 = self.tools.values().map(|stats| stats.retries).sum();
+        let mut out = format!(
+            "{calls} GUI tool call(s) in {} session(s): {} succeeded, {failed} failed, {retries} retries ({:.2} per call)\n",
+            self.sessions,
+            percent(succeeded, succeeded + failed),
+            ratio(retries, calls - retries),
+        );
+        out.push_str(&format!(
+            "{} completed subtask(s), {} interrupted; {:.1} GUI actions per completed subtask\n\n",
+            self.completed_subtasks,
+            self.interrupted_subtasks,
+            ratio(self.completed_subtask_actions, self.completed_subtasks),
+        ));
+        let width = self
+            .tools
+            .keys()
+            .map(String::len)
+            .max()
+            .unwrap_or_default()
+            .max("tool".len());
+        out.push_str(&format!(
+            "{:<width$}  {:>6}  {:>9}  {:>6}  {:>7}  {:>11}\n",
+            "tool", "calls", "succeeded", "failed", "retries", "avg retries"
+        ));
+        for (name, stats) in &self.tools {
+            let rate = if stats.succeeded + stats.failed == 0 {
+                "-".to_string()
+            } else {
+                percent(stats.succeeded, stats.succeeded + stats.failed)
+            };
+            out.push_str(&format!(
+                "{name:<width$}  {:>6}  {rate:>9}  {:>6}  {:>7}  {:>11.2}\n",
+                stats.calls,
+                stats.failed,
+                stats.retries,
+                ratio(stats.retries, stats.calls - stats.retries),
+            ));
+        }
+        if !self.click_accuracy.is_empty() {
+            out.push_str(&self.click_accuracy_report());
+        }
+        out
+    }
+
+    /// One row per coordinate space: measured clicks, their mean distance
+    /// from the element center, and their mean offset relative to the
+    /// element's size.
+    fn click_accuracy_report(&self) -> String {
+        let width = self
+            .click_accuracy
+            .keys()
+            .map(String::len)
+            .max()
+            .unwrap_or_default()
+            .max("coordinate space".len());
+        let mut out = format!(
+            "\nclick accuracy (distance from the center of the element under each click):\n{:<width$}  {:>6}  {:>9}  {:>13}\n",
+            "coordinate space", "clicks", "avg px", "avg to edge"
+        );
+        for (space, stats) in &self.click_accuracy {
+            let clicks = stats.clicks.max(1) as f64;
+            out.push_str(&format!(
+                "{space:<width$}  {:>6}  {:>9.1}  {:>12.0}%\n",
+                stats.clicks,
+                stats.distance / clicks,
+                stats.relative_offset / clicks * 100.0,
+            ));
+        }
+        out
+    }
+}
+
+/// Whether a call to `tool` that returned `output` succeeded, or `None` when
+/// the tool's results can't tell.
+fn succeeded(tool: &str, output: &str) -> Option<bool> {
+    if let Some((_, prefix)) = SUCCESS_PREFIXES.iter().find(|(name, _)| *name == tool) {
+        return Some(output.starts_with(prefix) || is_terse_ok(output));
+    }
+    OBSERVATION_SUCCESS_PREFIXES
+        .iter()
+        .find(|(name, _)| *name == tool)
+        .map(|(_, prefixes)| prefixes.iter().any(|prefix| output.starts_with(prefix)))
+}
+
+fn percent(part: u64, whole: u64) -> String {
+    format!("{:.0}%", ratio(part, whole) * 100.0)
+}
+
+fn ratio(part: u64, whole: u64) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_protocol::models::FunctionCallOutputPayload;
+    use codex_protocol::protocol::GuiActionItem;
+    use codex_protocol::protocol::GuiClickAccuracy;
+    use codex_protocol::protocol::TurnAbortReason;
+    use codex_protocol::protocol::TurnAbortedEvent;
+    use codex_protocol::protocol::UserMessageEvent;
+    use pretty_assertions::assert_eq;
+
+    fn user() -> RolloutItem {
+        RolloutItem::EventMsg(EventMsg::UserMessage(UserMessageEvent {
+            message: "go".to_string(),
+            images: None,
+        }))
+    }
+
+    fn call(id: &str, name: &str, output: &str) -> Vec<RolloutItem> {
+        vec![
+            RolloutItem::ResponseItem(ResponseItem::FunctionCall {
+                id: None,
+                name: name.to_string(),
+                arguments: "{}".to_string(),
+                call_id: id.to_string(),
+            }),
+            RolloutItem::ResponseItem(ResponseItem::FunctionCallOutput {
+                call_id: id.to_string(),
+                output: FunctionCallOutputPayload {
+                    content: output.to_string(),
+                    ..Default::default()
+                },
+            }),
+        ]
+    }
+
+    #[test]
+    fn counts_failures_retries_and_subtasks() {
+        let mut items = vec![user()];
+        items.extend(call(
+            "1",
+            "computer_screenshot",
+            "captured screenshot at /tmp/a.png",
+        ));
+        items.extend(call("2", "computer_click", "no display available"));
+        items.extend(call("3", "computer_click", "clicked at (10, 20)"));
+        items.extend(call("4", "computer_type", "typed 5 characters"));
+        items.push(user());
+        items.extend(call("5", "computer_key", "pressed Return"));
+        items.push(RolloutItem::EventMsg(EventMsg::TurnAborted(
+            TurnAbortedEvent {
+                reason: TurnAbortReason::Interrupted,
+            },
+        )));
+
+        let mut stats = GuiStats::default();
+        stats.add_session(&items);
+        assert_eq!(stats.sessions, 1);
+        assert_eq!(
+            stats.tools["computer_click"],
+            ToolStats {
+                calls: 2,
+                succeeded: 1,
+                failed: 1,
+                retries: 1,
+            }
+        );
+        assert_eq!(stats.completed_subtasks, 1);
+        assert_eq!(stats.interrupted_subtasks, 1);
+        assert_eq!(stats.completed_subtask_actions, 2);
+        assert!(stats.report().contains(
+            "1 completed subtask(s), 1 interrupted; 2.0 GUI actions per completed subtask"
+        ));
+    }
+
+    #[test]
+    fn sums_click_accuracy_per_coordinate_space() {
+        let click = |id: &str, success: bool, clicked_x: i64| {
+            RolloutItem::GuiAction(GuiActionItem {
+                call_id: id.to_string(),
+                tool: "computer_click".to_string(),
+                success,
+                duration_ms: 80,
+                screenshot: None,
+                target_class: None,
+                target_id: None,
+                sticky_target: None,
+                calibration: None,
+                recipes: Vec::new(),
+                click_accuracy: Some(GuiClickAccuracy {
+                    coordinate_space: "1280x800 space".to_string(),
+                    clicked_x,
+                    clicked_y: 55,
+                    center_x: 140,
+                    center_y: 55,
+                    element_width: 80,
+                    element_height: 30,
+                    role: "push button".to_string(),
+                }),
+            })
+        };
+        let mut items = vec![user()];
+        // The result wording says otherwise; the recorded flag wins.
+        items.extend(call("1", "computer_click", "clicked at (400, 200)"));
+        items.push(click("1", false, 170));
+        items.extend(call("2", "computer_click", "clicked at (380, 200)"));
+        items.push(click("2", true, 150));
+
+        let mut stats = GuiStats::default();
+        stats.add_session(&items);
+        assert_eq!(stats.tools["computer_click"].failed, 1);
+        assert_eq!(
+            stats.click_accuracy["1280x800 space"],
+            ClickAccuracyStats {
+                clicks: 2,
+                distance: 40.0,
+                relative_offset: 1.0,
+            }
+        );
+        let report = stats.report();
+        let row = report
+            .lines()
+            .find(|line| line.starts_with("1280x800 space"))
+            .map(|line| line.split_whitespace().collect::<Vec<_>>());
+        assert_eq!(row, Some(vec!["1280x800", "space", "2", "20.0", "50%"]));
+    }
+}
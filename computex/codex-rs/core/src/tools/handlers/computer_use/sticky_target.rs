@@ -0,0 +1,152 @@
+//! Session-wide target window for `computer_set_target`.
+//!
+//! Once a target is set, every GUI tool call treats that window's client
+//! area as the viewport: screenshots are cropped to it and model
+//! coordinates are relative to its top-left corner, exactly as with a
+//! configured `computer_use.viewport`. The window's geometry is looked up
+//! again on every call, so moving or resizing it is followed. Input tools
+//! also check that the target has focus before sending anything, with the
+//! same refocus-or-fail rule as an explicit `window` argument.
+
+use std::path::Path;
+
+use super::focus_guard;
+use super::recovery::active_window;
+use super::recovery::xdotool_output;
+use crate::config::types::ComputerUseConfig;
+use crate::config::types::Viewport;
+use crate::function_tool::FunctionCallError;
+
+/// A window matched by the target pattern.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct ResolvedTarget {
+    pub class: String,
+    pub title: String,
+    pub viewport: Viewport,
+}
+
+/// Finds the window `pattern` names, preferring the focused one, and
+/// returns it with its client area clipped to `config`'s viewport.
+pub(super) fn resolve(
+    xdotool: &Path,
+    config: &ComputerUseConfig,
+    pattern: &str,
+) -> Result<ResolvedTarget, FunctionCallError> {
+    let focused = active_window(xdotool, config).ok().filter(|window| {
+        let title =
+            xdotool_output(xdotool, config, &["getwindowname", &window.id]).unwrap_or_default();
+        focus_guard::matches_window(pattern, &window.class, &title)
+    });
+    let id = match focused {
+        Some(window) => window.id,
+        None => focus_guard::find_window(xdotool, config, pattern).ok_or_else(|| {
+            FunctionCallError::RespondToModel(format!(
+                "no visible window matches the target `{pattern}`; open it, or call computer_set_target with another window or none to clear the target"
+            ))
+        })?,
+    };
+    let class = xdotool_output(xdotool, config, &["getwindowclassname", &id])?;
+    let title = xdotool_output(xdotool, config, &["getwindowname", &id])?;
+    let geometry = xdotool_output(xdotool, config, &["getwindowgeometry", "--shell", &id])?;
+    let viewport = parse_geometry(&geometry)
+        .and_then(|(x, y, width, height)| clip(x, y, width, height, config.viewport))
+        .ok_or_else(|| {
+            FunctionCallError::RespondToModel(format!(
+                "the target window `{class}` (\"{title}\") is off screen or has no size"
+            ))
+        })?;
+    Ok(ResolvedTarget {
+        class,
+        title,
+        viewport,
+    })
+}
+
+/// `config` with the target's client area as the viewport.
+pub(super) fn scoped_config(
+    config: &ComputerUseConfig,
+    target: &ResolvedTarget,
+) -> ComputerUseConfig {
+    ComputerUseConfig {
+        viewport: Some(target.viewport),
+        ..config.clone()
+    }
+}
+
+/// Reads `X`, `Y`, `WIDTH` and `HEIGHT` from `xdotool getwindowgeometry
+/// --shell`.
+fn parse_geometry(shell: &str) -> Option<(i64, i64, i64, i64)> {
+    let value = |key: &str| {
+        shell.lines().find_map(|line| {
+            let (name, value) = line.trim().split_once('=')?;
+            (name == key).then(|| value.parse::<i64>().ok()).flatten()
+        })
+    };
+    Some((value("X")?, value("Y")?, value("WIDTH")?, value("HEIGHT")?))
+}
+
+/// The part of the window at `x`,`y` that lies inside `bounds` (or the
+/// screen's positive quadrant), or `None` when nothing does.
+fn clip(x: i64, y: i64, width: i64, height: i64, bounds: Option<Viewport>) -> Option<Viewport> {
+    let (min_x, min_y, max_x, max_y) = match bounds {
+        Some(bounds) => (
+            i64::from(bounds.x),
+            i64::from(bounds.y),
+            i64::from(bounds.x) + i64::from(bounds.width),
+            i64::from(bounds.y) + i64::from(bounds.height),
+        ),
+        None => (0, 0, i64::MAX, i64::MAX),
+    };
+    let left = x.max(min_x);
+    let top = y.max(min_y);
+    let right = x.saturating_add(width).min(max_x);
+    let bottom = y.saturating_add(height).min(max_y);
+    if right <= left || bottom <= top {
+        return None;
+    }
+    Some(Viewport {
+        x: u32::try_from(left).ok()?,
+        y: u32::try_from(top).ok()?,
+        width: u32::try_from(right - left).ok()?,
+        height: u32::try_from(bottom - top).ok()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn window_geometry_becomes_a_clipped_viewport() {
+        let shell = "WINDOW=62914563\nX=-20\nY=64\nWIDTH=1300\nHEIGHT=700\nSCREEN=0\n";
+        let (x, y, width, height) = parse_geometry(shell).unwrap_or_default();
+        assert_eq!((x, y, width, height), (-20, 64, 1300, 700));
+        assert_eq!(
+            clip(x, y, width, height, None),
+            Some(Viewport {
+                x: 0,
+                y: 64,
+                width: 1280,
+                height: 700,
+            })
+        );
+        let bounds = Viewport {
+            x: 0,
+            y: 0,
+            width: 1280,
+            height: 720,
+        };
+        assert_eq!(
+            clip(x, y, width, height, Some(bounds)),
+            Some(Viewport {
+                x: 0,
+                y: 64,
+                width: 1280,
+                height: 656,
+            })
+        );
+        assert_eq!(clip(1400, 0, 100, 100, Some(bounds)), None);
+        assert_eq!(parse_geometry("WINDOW=1\n"), None);
+    }
+}
@@ -0,0 +1,354 @@
+//! Volume, mute, and brightness for `computer_system_control`.
+//!
+//! Sliders in a panel or a media app are a few pixels tall and move in
+//! uneven steps, so setting an exact level by dragging one rarely works.
+//! The audio of the default sink is read and set with `pactl`, which
+//! PulseAudio and PipeWire both answer, or with PipeWire's `wpctl`. The
+//! backlight goes through `brightnessctl`, or GNOME's power daemon over
+//! D-Bus where brightnessctl isn't installed. Each setting's value before
+//! the first change is saved in [`SavedSettings`] and put back when the
+//! session ends.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use which::which;
+use zbus::blocking::Connection;
+use zbus::zvariant::OwnedValue;
+use zbus::zvariant::Value;
+
+use super::desktop::output;
+use super::schema::SystemSetting;
+use crate::config::types::ComputerUseConfig;
+use crate::function_tool::FunctionCallError;
+
+const PACTL_SINK: &str = "@DEFAULT_SINK@";
+const WPCTL_SINK: &str = "@DEFAULT_AUDIO_SINK@";
+
+const GSD_POWER: &str = "org.gnome.SettingsDaemon.Power";
+const GSD_POWER_PATH: &str = "/org/gnome/SettingsDaemon/Power";
+const GSD_SCREEN: &str = "org.gnome.SettingsDaemon.Power.Screen";
+const PROPERTIES: &str = "org.freedesktop.DBus.Properties";
+
+/// A setting's value: a level in percent, or whether audio is muted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Level {
+    Percent(f64),
+    Muted(bool),
+}
+
+impl std::fmt::Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Level::Percent(percent) => write!(f, "{}%", percent.round()),
+            Level::Muted(true) => f.write_str("muted"),
+            Level::Muted(false) => f.write_str("unmuted"),
+        }
+    }
+}
+
+/// The program or service that reads and sets a setting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Control {
+    Pactl(PathBuf),
+    Wpctl(PathBuf),
+    Brightnessctl(PathBuf),
+    GnomePower,
+}
+
+impl Control {
+    fn for_setting(setting: SystemSetting) -> Result<Self, FunctionCallError> {
+        match setting {
+            SystemSetting::Volume | SystemSetting::Mute => which("pactl")
+                .map(Control::Pactl)
+                .or_else(|_| which("wpctl").map(Control::Wpctl))
+                .map_err(|_| {
+                    FunctionCallError::RespondToModel(
+                        "audio control needs `pactl` (PulseAudio or pipewire-pulse) or `wpctl` (WirePlumber); install it with `sudo apt-get install -y pulseaudio-utils`".to_string(),
+                    )
+                }),
+            SystemSetting::Brightness => Ok(which("brightnessctl")
+                .map(Control::Brightnessctl)
+                .unwrap_or(Control::GnomePower)),
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Control::Pactl(_) => "pactl",
+            Control::Wpctl(_) => "wpctl",
+            Control::Brightnessctl(_) => "brightnessctl",
+            Control::GnomePower => "GNOME power settings",
+        }
+    }
+}
+
+/// The current value of `setting`, and what read it.
+pub(super) fn read(
+    config: &ComputerUseConfig,
+    setting: SystemSetting,
+) -> Result<(Level, &'static str), FunctionCallError> {
+    let control = Control::for_setting(setting)?;
+    let level = read_with(&control, config, setting)?;
+    Ok((level, control.label()))
+}
+
+/// Sets `setting` to `level`.
+pub(super) fn write(
+    config: &ComputerUseConfig,
+    setting: SystemSetting,
+    level: Level,
+) -> Result<(), FunctionCallError> {
+    let control = Control::for_setting(setting)?;
+    write_with(&control, config, level)
+}
+
+/// The level `value` asks `setting` for: a percentage from 0 to 100, or for
+/// `mute`, 1 to mute and 0 to unmute.
+pub(super) fn requested(setting: SystemSetting, value: f64) -> Result<Level, FunctionCallError> {
+    let out_of_range =
+        |what: &str| FunctionCallError::RespondToModel(format!("{what}; got {value}"));
+    match setting {
+        SystemSetting::Mute if value == 0.0 || value == 1.0 => Ok(Level::Muted(value == 1.0)),
+        SystemSetting::Mute => Err(out_of_range("mute takes 1 to mute or 0 to unmute")),
+        _ if (0.0..=100.0).contains(&value) => Ok(Level::Percent(value)),
+        _ => Err(out_of_range("the value is a percentage from 0 to 100")),
+    }
+}
+
+fn read_with(
+    control: &Control,
+    config: &ComputerUseConfig,
+    setting: SystemSetting,
+) -> Result<Level, FunctionCallError> {
+    let unreadable = |text: &str| {
+        FunctionCallError::RespondToModel(format!(
+            "could not read the {} from {}: {:?}",
+            setting.label(),
+            control.label(),
+            text.trim()
+        ))
+    };
+    match control {
+        Control::Pactl(pactl) => {
+            let (command, parse): (&str, fn(&str) -> Option<Level>) = match setting {
+                SystemSetting::Mute => ("get-sink-mute", parse_pactl_mute),
+                _ => ("get-sink-volume", parse_pactl_volume),
+            };
+            let text = output(pactl, config, &[command, PACTL_SINK])?;
+            parse(&text).ok_or_else(|| unreadable(&text))
+        }
+        Control::Wpctl(wpctl) => {
+            let text = output(wpctl, config, &["get-volume", WPCTL_SINK])?;
+            let (percent, muted) = parse_wpctl(&text).ok_or_else(|| unreadable(&text))?;
+            Ok(match setting {
+                SystemSetting::Mute => Level::Muted(muted),
+                _ => Level::Percent(percent),
+            })
+        }
+        Control::Brightnessctl(brightnessctl) => {
+            let text = output(brightnessctl, config, &["--class=backlight", "-m", "info"])?;
+            parse_brightnessctl(&text)
+                .map(|(_, percent)| Level::Percent(percent))
+                .ok_or_else(|| unreadable(&text))
+        }
+        Control::GnomePower => gnome_brightness().map(|percent| Level::Percent(f64::from(percent))),
+    }
+}
+
+fn write_with(
+    control: &Control,
+    config: &ComputerUseConfig,
+    level: Level,
+) -> Result<(), FunctionCallError> {
+    let percent = |value: f64| format!("{}%", value.round());
+    match (control, level) {
+        (Control::Pactl(pactl), Level::Percent(value)) => run(
+            pactl,
+            config,
+            &["set-sink-volume", PACTL_SINK, &percent(value)],
+        ),
+        (Control::Pactl(pactl), Level::Muted(muted)) => run(
+            pactl,
+            config,
+            &["set-sink-mute", PACTL_SINK, if muted { "1" } else { "0" }],
+        ),
+        (Control::Wpctl(wpctl), Level::Percent(value)) => run(
+            wpctl,
+            config,
+            &["set-volume", WPCTL_SINK, &format!("{:.2}", value / 100.0)],
+        ),
+        (Control::Wpctl(wpctl), Level::Muted(muted)) => run(
+            wpctl,
+            config,
+            &["set-mute", WPCTL_SINK, if muted { "1" } else { "0" }],
+        ),
+        (Control::Brightnessctl(brightnessctl), Level::Percent(value)) => run(
+            brightnessctl,
+            config,
+            &["--class=backlight", "set", &percent(value)],
+        ),
+        (Control::GnomePower, Level::Percent(value)) => set_gnome_brightness(value.round() as i32),
+        (Control::Brightnessctl(_) | Control::GnomePower, Level::Muted(_)) => Err(
+            FunctionCallError::RespondToModel("brightness can't be muted".to_string()),
+        ),
+    }
+}
+
+fn run(program: &Path, config: &ComputerUseConfig, args: &[&str]) -> Result<(), FunctionCallError> {
+    output(program, config, args).map(drop)
+}
+
+/// The screen brightness GNOME's power daemon reports, in percent.
+fn gnome_brightness() -> Result<i32, FunctionCallError> {
+    let unavailable = |err: &dyn std::fmt::Display| {
+        FunctionCallError::RespondToModel(format!(
+            "brightness needs `brightnessctl` (install it with `sudo apt-get install -y brightnessctl`) or GNOME's power settings, which did not answer: {err}"
+        ))
+    };
+    let bus = Connection::session().map_err(|err| unavailable(&err))?;
+    let value: OwnedValue = bus
+        .call_method(
+            Some(GSD_POWER),
+            GSD_POWER_PATH,
+            Some(PROPERTIES),
+            "Get",
+            &(GSD_SCREEN, "Brightness"),
+        )
+        .map_err(|err| unavailable(&err))?
+        .body()
+        .deserialize()
+        .map_err(|err| unavailable(&err))?;
+    let percent = i32::try_from(value).map_err(|err| unavailable(&err))?;
+    if percent < 0 {
+        return Err(FunctionCallError::RespondToModel(
+            "this screen has no adjustable backlight".to_string(),
+        ));
+    }
+    Ok(percent)
+}
+
+fn set_gnome_brightness(percent: i32) -> Result<(), FunctionCallError> {
+    let failed = |err: zbus::Error| {
+        FunctionCallError::RespondToModel(format!(
+            "failed to set the brightness through GNOME's power settings: {err}"
+        ))
+    };
+    let bus = Connection::session().map_err(failed)?;
+    bus.call_method(
+        Some(GSD_POWER),
+        GSD_POWER_PATH,
+        Some(PROPERTIES),
+        "Set",
+        &(GSD_SCREEN, "Brightness", Value::from(percent)),
+    )
+    .map_err(failed)?;
+    Ok(())
+}
+
+/// The settings computex changed, with their values from before the first
+/// change, restored on drop.
+#[derive(Debug)]
+pub(super) struct SavedSettings {
+    config: ComputerUseConfig,
+    saved: Vec<(SystemSetting, Level)>,
+}
+
+impl SavedSettings {
+    pub(super) fn new(config: &ComputerUseConfig) -> Self {
+        Self {
+            config: config.clone(),
+            saved: Vec::new(),
+        }
+    }
+
+    /// Saves `level` as the value `setting` goes back to, unless one is
+    /// already saved.
+    pub(super) fn remember(&mut self, setting: SystemSetting, level: Level) {
+        if !self.saved.iter().any(|(saved, _)| *saved == setting) {
+            self.saved.push((setting, level));
+        }
+    }
+}
+
+impl Drop for SavedSettings {
+    fn drop(&mut self) {
+        for (setting, level) in self.saved.drain(..) {
+            if let Err(err) = write(&self.config, setting, level) {
+                tracing::warn!("failed to restore the {}: {err:?}", setting.label());
+            }
+        }
+    }
+}
+
+/// The first percentage of `pactl get-sink-volume`, e.g.
+/// `Volume: front-left: 32768 /  50% / -18.06 dB,   front-right: ...`.
+fn parse_pactl_volume(text: &str) -> Option<Level> {
+    text.split_whitespace()
+        .find_map(|word| word.strip_suffix('%')?.parse().ok())
+        .map(Level::Percent)
+}
+
+/// `pactl get-sink-mute`, e.g. `Mute: yes`.
+fn parse_pactl_mute(text: &str) -> Option<Level> {
+    match text.trim().strip_prefix("Mute:")?.trim() {
+        "yes" => Some(Level::Muted(true)),
+        "no" => Some(Level::Muted(false)),
+        _ => None,
+    }
+}
+
+/// `wpctl get-volume`, e.g. `Volume: 0.40 [MUTED]`, as a percentage and
+/// whether the sink is muted.
+fn parse_wpctl(text: &str) -> Option<(f64, bool)> {
+    let rest = text.trim().strip_prefix("Volume:")?;
+    let volume: f64 = rest.split_whitespace().next()?.parse().ok()?;
+    Some(((volume * 100.0).round(), rest.contains("[MUTED]")))
+}
+
+/// The device and percentage of `brightnessctl -m info`, e.g.
+/// `intel_backlight,backlight,1200,60%,2000`.
+fn parse_brightnessctl(text: &str) -> Option<(String, f64)> {
+    let fields: Vec<&str> = text.lines().next()?.split(',').collect();
+    let [device, _, _, percent, _] = fields.as_slice() else {
+        return None;
+    };
+    Some((device.to_string(), percent.strip_suffix('%')?.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn reads_levels_from_each_control() {
+        assert_eq!(
+            parse_pactl_volume(
+                "Volume: front-left: 32768 /  50% / -18.06 dB,   front-right: 32768 /  50% / -18.06 dB\n        balance 0.00\n"
+            ),
+            Some(Level::Percent(50.0))
+        );
+        assert_eq!(parse_pactl_mute("Mute: yes\n"), Some(Level::Muted(true)));
+        assert_eq!(parse_pactl_mute("Mute: no\n"), Some(Level::Muted(false)));
+        assert_eq!(parse_wpctl("Volume: 0.40 [MUTED]\n"), Some((40.0, true)));
+        assert_eq!(parse_wpctl("Volume: 1.00\n"), Some((100.0, false)));
+        assert_eq!(
+            parse_brightnessctl("intel_backlight,backlight,1200,60%,2000\n"),
+            Some(("intel_backlight".to_string(), 60.0))
+        );
+        assert_eq!(parse_brightnessctl("Device 'x' not found.\n"), None);
+
+        assert_eq!(
+            requested(SystemSetting::Volume, 35.0).ok(),
+            Some(Level::Percent(35.0))
+        );
+        assert_eq!(
+            requested(SystemSetting::Mute, 1.0).ok(),
+            Some(Level::Muted(true))
+        );
+        assert!(requested(SystemSetting::Brightness, 120.0).is_err());
+        assert!(requested(SystemSetting::Mute, 0.5).is_err());
+        assert_eq!(Level::Percent(40.4).to_string(), "40%");
+    }
+}
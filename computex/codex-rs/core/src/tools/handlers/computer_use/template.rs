@@ -0,0 +1,219 @@
+//! Template matching, the locator strategy for elements that look the same
+//! every time but have no text or accessible name, like toolbar icons.
+//!
+//! Templates are PNG crops saved under `<codex_home>/computer_use/templates`,
+//! named after the query: `save_button.png` answers "Save button". The search
+//! compares grayscale pixels, first at a quarter of the resolution over the
+//! whole capture and then at full resolution around the best coarse hit, so
+//! a 1080p screen takes a few hundred milliseconds.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_utils_image::screenshot::RawImage;
+
+/// Downscale factor of the coarse pass.
+const COARSE: u32 = 4;
+
+/// Full-resolution pixels searched around the coarse hit in each direction.
+const REFINE_RADIUS: i64 = 2 * COARSE as i64;
+
+/// Where a template was found, in capture pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct TemplateMatch {
+    pub left: i64,
+    pub top: i64,
+    pub width: i64,
+    pub height: i64,
+    /// One minus the mean absolute gray difference, in `0..=1`.
+    pub score: f64,
+}
+
+/// The template file for `query`, `<dir>/<words_joined_by_underscores>.png`.
+pub(super) fn template_path(codex_home: &Path, query: &str) -> PathBuf {
+    let name: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    codex_home
+        .join("computer_use")
+        .join("templates")
+        .join(format!("{}.png", name.join("_")))
+}
+
+/// The best placement of `template` in `screen`.
+pub(super) fn find(screen: &RawImage, template: &RawImage) -> Option<TemplateMatch> {
+    if template.width > screen.width || template.height > screen.height {
+        return None;
+    }
+    let coarse_template = Gray::from(
+        &template
+            .resize(
+                (template.width / COARSE).max(1),
+                (template.height / COARSE).max(1),
+            )
+            .ok()?,
+    );
+    let coarse_screen = Gray::from(
+        &screen
+            .resize(
+                (screen.width / COARSE).max(1),
+                (screen.height / COARSE).max(1),
+            )
+            .ok()?,
+    );
+    let (coarse_x, coarse_y, _) = best_placement(
+        &coarse_screen,
+        &coarse_template,
+        0..=coarse_screen.width - coarse_template.width,
+        0..=coarse_screen.height - coarse_template.height,
+    )?;
+
+    let screen = Gray::from(screen);
+    let template = Gray::from(template);
+    let max_x = (screen.width - template.width) as i64;
+    let max_y = (screen.height - template.height) as i64;
+    let center_x = coarse_x as i64 * i64::from(COARSE);
+    let center_y = coarse_y as i64 * i64::from(COARSE);
+    let xs = (center_x - REFINE_RADIUS).clamp(0, max_x) as usize
+        ..=(center_x + REFINE_RADIUS).clamp(0, max_x) as usize;
+    let ys = (center_y - REFINE_RADIUS).clamp(0, max_y) as usize
+        ..=(center_y + REFINE_RADIUS).clamp(0, max_y) as usize;
+    let (x, y, score) = best_placement(&screen, &template, xs, ys)?;
+    Some(TemplateMatch {
+        left: x as i64,
+        top: y as i64,
+        width: template.width as i64,
+        height: template.height as i64,
+        score,
+    })
+}
+
+/// A grayscale copy of an image, one byte per pixel.
+struct Gray {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl From<&RawImage> for Gray {
+    fn from(image: &RawImage) -> Self {
+        let pixels = image
+            .pixels
+            .chunks_exact(usize::from(image.channels))
+            .map(|pixel| {
+                let luma = 299 * u32::from(pixel[0])
+                    + 587 * u32::from(pixel[1])
+                    + 114 * u32::from(pixel[2]);
+                (luma / 1000) as u8
+            })
+            .collect();
+        Self {
+            width: image.width as usize,
+            height: image.height as usize,
+            pixels,
+        }
+    }
+}
+
+/// The placement among `xs` x `ys` with the smallest mean absolute
+/// difference, and its score.
+fn best_placement(
+    screen: &Gray,
+    template: &Gray,
+    xs: std::ops::RangeInclusive<usize>,
+    ys: std::ops::RangeInclusive<usize>,
+) -> Option<(usize, usize, f64)> {
+    let area = (template.width * template.height) as u64;
+    let mut best: Option<(usize, usize, u64)> = None;
+    for y in ys {
+        for x in xs.clone() {
+            let limit = best.map_or(u64::MAX, |(_, _, total)| total);
+            let total = difference(screen, template, x, y, limit);
+            if total < limit {
+                best = Some((x, y, total));
+            }
+        }
+    }
+    best.map(|(x, y, total)| (x, y, 1.0 - total as f64 / area as f64 / 255.0))
+}
+
+/// Sum of absolute differences with the template placed at `x`, `y`. Stops
+/// early once the sum reaches `limit`.
+fn difference(screen: &Gray, template: &Gray, x: usize, y: usize, limit: u64) -> u64 {
+    let mut total = 0u64;
+    for row in 0..template.height {
+        let screen_row = &screen.pixels[(y + row) * screen.width + x..][..template.width];
+        let template_row = &template.pixels[row * template.width..][..template.width];
+        total += screen_row
+            .iter()
+            .zip(template_row)
+            .map(|(a, b)| u64::from(a.abs_diff(*b)))
+            .sum::<u64>();
+        if total >= limit {
+            return total;
+        }
+    }
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_utils_image::error::ImageProcessingError;
+    use pretty_assertions::assert_eq;
+
+    /// A dark screen with a bright 24x16 "button" whose left half is lighter.
+    fn screen(left: u32, top: u32) -> Result<RawImage, ImageProcessingError> {
+        let (width, height) = (160, 120);
+        let mut pixels = Vec::with_capacity((width * height * 3) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let inside = (left..left + 24).contains(&x) && (top..top + 16).contains(&y);
+                let value = match inside {
+                    true if x < left + 12 => 250,
+                    true => 180,
+                    false => 20,
+                };
+                pixels.extend([value, value, value]);
+            }
+        }
+        RawImage::new(width, height, 3, pixels)
+    }
+
+    fn crop(image: &RawImage, left: u32, top: u32, width: u32, height: u32) -> RawImage {
+        let mut pixels = Vec::new();
+        for y in top..top + height {
+            let start = ((y * image.width + left) * 3) as usize;
+            pixels.extend_from_slice(&image.pixels[start..start + (width * 3) as usize]);
+        }
+        RawImage {
+            width,
+            height,
+            channels: 3,
+            pixels,
+        }
+    }
+
+    #[test]
+    fn finds_a_template_at_full_resolution() -> Result<(), ImageProcessingError> {
+        let template = crop(&screen(40, 30)?, 36, 26, 32, 24);
+        let found = find(&screen(97, 61)?, &template);
+        assert_eq!(
+            found,
+            Some(TemplateMatch {
+                left: 93,
+                top: 57,
+                width: 32,
+                height: 24,
+                score: 1.0,
+            })
+        );
+        assert_eq!(
+            template_path(Path::new("/home/me/.codex"), "Save  button!"),
+            PathBuf::from("/home/me/.codex/computer_use/templates/save_button.png")
+        );
+        Ok(())
+    }
+}
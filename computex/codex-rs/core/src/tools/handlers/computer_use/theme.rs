@@ -0,0 +1,197 @@
+//! Desktop theme switching for `computer_set_theme`.
+//!
+//! Visual checks often need the same screens in dark, light, and
+//! high-contrast variants. On GNOME the theme is changed through the
+//! `org.gnome.desktop.interface` and `org.gnome.desktop.a11y.interface`
+//! settings with `gsettings`; on KDE Plasma the color scheme is applied with
+//! `plasma-apply-colorscheme`. The theme in place before the first switch is
+//! saved in a [`SavedTheme`] and put back when the session ends.
+
+use std::path::PathBuf;
+
+use super::desktop::Desktop;
+use super::desktop::output;
+use super::require_command;
+use super::schema::ThemeName;
+use crate::config::types::ComputerUseConfig;
+use crate::function_tool::FunctionCallError;
+
+const GNOME_INTERFACE: &str = "org.gnome.desktop.interface";
+const GNOME_A11Y_INTERFACE: &str = "org.gnome.desktop.a11y.interface";
+
+/// GTK theme for high contrast on GNOME releases without the
+/// `a11y.interface high-contrast` key.
+const GNOME_HIGH_CONTRAST_GTK_THEME: &str = "HighContrast";
+
+const KDE_DARK_SCHEME: &str = "BreezeDark";
+const KDE_LIGHT_SCHEME: &str = "BreezeLight";
+
+/// The theme before computex changed it, restored on drop.
+#[derive(Debug)]
+pub(super) struct SavedTheme {
+    config: ComputerUseConfig,
+    saved: Saved,
+}
+
+#[derive(Debug)]
+enum Saved {
+    Gnome {
+        gsettings: PathBuf,
+        color_scheme: String,
+        gtk_theme: String,
+        /// `None` on GNOME releases without the key.
+        high_contrast: Option<String>,
+    },
+    Kde {
+        plasma_apply_colorscheme: PathBuf,
+        scheme: String,
+    },
+}
+
+impl SavedTheme {
+    /// Reads the current theme of `desktop`.
+    pub(super) fn capture(
+        config: &ComputerUseConfig,
+        desktop: Desktop,
+    ) -> Result<Self, FunctionCallError> {
+        let saved = match desktop {
+            Desktop::Gnome => {
+                let gsettings = require_command("gsettings")?;
+                let get = |schema: &str, key: &str| {
+                    output(&gsettings, config, &["get", schema, key])
+                        .map(|value| value.trim().to_string())
+                };
+                Saved::Gnome {
+                    color_scheme: get(GNOME_INTERFACE, "color-scheme")?,
+                    gtk_theme: get(GNOME_INTERFACE, "gtk-theme")?,
+                    high_contrast: get(GNOME_A11Y_INTERFACE, "high-contrast").ok(),
+                    gsettings,
+                }
+            }
+            Desktop::Kde => {
+                let plasma_apply_colorscheme = require_command("plasma-apply-colorscheme")?;
+                let schemes = output(&plasma_apply_colorscheme, config, &["--list-schemes"])?;
+                let scheme = current_kde_scheme(&schemes).ok_or_else(|| {
+                    FunctionCallError::RespondToModel(
+                        "plasma-apply-colorscheme did not report the current color scheme"
+                            .to_string(),
+                    )
+                })?;
+                Saved::Kde {
+                    plasma_apply_colorscheme,
+                    scheme,
+                }
+            }
+        };
+        Ok(Self {
+            config: config.clone(),
+            saved,
+        })
+    }
+}
+
+impl Drop for SavedTheme {
+    fn drop(&mut self) {
+        let restored = match &self.saved {
+            Saved::Gnome {
+                gsettings,
+                color_scheme,
+                gtk_theme,
+                high_contrast,
+            } => {
+                let mut steps = vec![
+                    [GNOME_INTERFACE, "color-scheme", color_scheme.as_str()],
+                    [GNOME_INTERFACE, "gtk-theme", gtk_theme.as_str()],
+                ];
+                if let Some(high_contrast) = high_contrast {
+                    steps.push([GNOME_A11Y_INTERFACE, "high-contrast", high_contrast]);
+                }
+                steps.into_iter().try_for_each(|[schema, key, value]| {
+                    output(gsettings, &self.config, &["set", schema, key, value]).map(drop)
+                })
+            }
+            Saved::Kde {
+                plasma_apply_colorscheme,
+                scheme,
+            } => output(plasma_apply_colorscheme, &self.config, &[scheme]).map(drop),
+        };
+        if let Err(err) = restored {
+            tracing::warn!("failed to restore the desktop theme: {err}");
+        }
+    }
+}
+
+/// Switches `desktop` to `theme`.
+pub(super) fn apply(
+    config: &ComputerUseConfig,
+    desktop: Desktop,
+    theme: ThemeName,
+) -> Result<(), FunctionCallError> {
+    match desktop {
+        Desktop::Gnome => apply_gnome(config, theme),
+        Desktop::Kde => {
+            let scheme = match theme {
+                ThemeName::Dark => KDE_DARK_SCHEME,
+                ThemeName::Light => KDE_LIGHT_SCHEME,
+                ThemeName::HighContrast => {
+                    return Err(FunctionCallError::RespondToModel(
+                        "KDE Plasma ships no high-contrast color scheme; use dark or light"
+                            .to_string(),
+                    ));
+                }
+            };
+            let plasma_apply_colorscheme = require_command("plasma-apply-colorscheme")?;
+            output(&plasma_apply_colorscheme, config, &[scheme]).map(drop)
+        }
+    }
+}
+
+fn apply_gnome(config: &ComputerUseConfig, theme: ThemeName) -> Result<(), FunctionCallError> {
+    let gsettings = require_command("gsettings")?;
+    let set = |schema: &str, key: &str, value: &str| {
+        output(&gsettings, config, &["set", schema, key, value]).map(drop)
+    };
+    let (color_scheme, gtk_theme) = match theme {
+        ThemeName::Dark => ("prefer-dark", "Adwaita-dark"),
+        ThemeName::Light | ThemeName::HighContrast => ("default", "Adwaita"),
+    };
+    set(GNOME_INTERFACE, "color-scheme", color_scheme)?;
+    let high_contrast = theme == ThemeName::HighContrast;
+    let a11y = set(
+        GNOME_A11Y_INTERFACE,
+        "high-contrast",
+        if high_contrast { "true" } else { "false" },
+    );
+    if high_contrast && a11y.is_err() {
+        return set(GNOME_INTERFACE, "gtk-theme", GNOME_HIGH_CONTRAST_GTK_THEME);
+    }
+    set(GNOME_INTERFACE, "gtk-theme", gtk_theme)
+}
+
+/// The scheme `plasma-apply-colorscheme --list-schemes` marks as current,
+/// e.g. ` * BreezeDark (current color scheme)`.
+fn current_kde_scheme(schemes: &str) -> Option<String> {
+    schemes.lines().find_map(|line| {
+        let (name, _) = line.split_once("(current color scheme)")?;
+        let name = name.trim().trim_start_matches('*').trim();
+        (!name.is_empty()).then(|| name.to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn finds_the_current_kde_color_scheme() {
+        let schemes = "\
+You have the following color schemes on your system:
+ * BreezeClassic
+ * BreezeDark (current color scheme)
+ * BreezeLight
+";
+        assert_eq!(current_kde_scheme(schemes), Some("BreezeDark".to_string()));
+        assert_eq!(current_kde_scheme(" * BreezeLight\n"), None);
+    }
+}
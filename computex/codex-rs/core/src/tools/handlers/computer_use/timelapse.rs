@@ -0,0 +1,298 @@
+//! Stitches the screenshots of a recorded session into an annotated GIF or
+//! WebM timeline, for `computex timelapse`.
+//!
+//! Every image a session attaches to the conversation is stored in its
+//! rollout as a data URL, between the tool calls that led up to it. Each
+//! image becomes one frame, captioned with its number, the time since the
+//! session started, and the GUI actions taken since the previous frame.
+//! ImageMagick scales and captions the frames and assembles GIFs; WebM
+//! output is encoded with ffmpeg. Images stored encrypted are decrypted with
+//! the key in the keyring.
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+use std::str::FromStr;
+
+use anyhow::Context;
+use base64::Engine;
+use chrono::DateTime;
+use chrono::FixedOffset;
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::ResponseItem;
+use codex_protocol::protocol::RolloutItem;
+use codex_protocol::protocol::RolloutLine;
+use which::which;
+
+use super::encryption;
+
+/// Frame size; screenshots are scaled to fit and letterboxed.
+const FRAME_WIDTH: u32 = 1280;
+const FRAME_HEIGHT: u32 = 720;
+
+/// Longest action list in a caption, in characters.
+const MAX_CAPTION_ACTIONS: usize = 110;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimelapseFormat {
+    Gif,
+    Webm,
+}
+
+impl TimelapseFormat {
+    /// The format named by `path`'s extension, if it is one.
+    pub fn for_path(path: &Path) -> Option<Self> {
+        path.extension()?.to_str()?.parse().ok()
+    }
+}
+
+impl FromStr for TimelapseFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "gif" => Ok(TimelapseFormat::Gif),
+            "webm" => Ok(TimelapseFormat::Webm),
+            _ => Err(format!(
+                "unknown timelapse format `{value}` (supported: gif, webm)"
+            )),
+        }
+    }
+}
+
+/// One image from the session and its caption.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimelapseFrame {
+    /// Encoded image bytes (PNG or JPEG) as attached to the conversation.
+    pub image: Vec<u8>,
+    pub caption: String,
+}
+
+/// Reads the images and GUI actions from the lines of a rollout file.
+pub fn collect_frames(rollout: &str) -> Vec<TimelapseFrame> {
+    let mut frames = Vec::new();
+    let mut started: Option<DateTime<FixedOffset>> = None;
+    let mut actions: Vec<String> = Vec::new();
+    let mut key = None;
+    for line in rollout.lines() {
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(line) else {
+            continue;
+        };
+        // Read the time first: lines of other versions may not parse fully.
+        let at = value
+            .get("timestamp")
+            .and_then(serde_json::Value::as_str)
+            .and_then(|timestamp| DateTime::parse_from_rfc3339(timestamp).ok());
+        if started.is_none() {
+            started = at;
+        }
+        let Ok(RolloutLine { mut item, .. }) = serde_json::from_value::<RolloutLine>(value) else {
+            continue;
+        };
+        if encryption::has_encrypted_images(&item) {
+            let key = key.get_or_insert_with(|| encryption::capture_key(false));
+            encryption::decrypt_images(&mut item, key);
+        }
+        let RolloutItem::ResponseItem(item) = item else {
+            continue;
+        };
+        match item {
+            ResponseItem::FunctionCall {
+                name, arguments, ..
+            } if name.starts_with("computer_") && name != "computer_screenshot" => {
+                actions.push(describe_call(&name, &arguments));
+            }
+            ResponseItem::Message { role, content, .. } if role == "user" => {
+                for content in content {
+                    let ContentItem::InputImage { image_url } = content else {
+                        continue;
+                    };
+                    let Some(image) = decode_data_url(&image_url) else {
+                        continue;
+                    };
+                    let elapsed = match (started, at) {
+                        (Some(started), Some(at)) => (at - started).num_seconds().max(0),
+                        _ => 0,
+                    };
+                    frames.push(TimelapseFrame {
+                        image,
+                        caption: caption(frames.len() + 1, elapsed, &actions),
+                    });
+                    actions.clear();
+                }
+            }
+            _ => {}
+        }
+    }
+    frames
+}
+
+/// `click x=640 y=360`, from a tool name and its JSON arguments.
+fn describe_call(name: &str, arguments: &str) -> String {
+    let tool = name.strip_prefix("computer_").unwrap_or(name);
+    let Ok(serde_json::Value::Object(args)) = serde_json::from_str(arguments) else {
+        return tool.to_string();
+    };
+    let mut parts = vec![tool.to_string()];
+    parts.extend(args.iter().map(|(key, value)| format!("{key}={value}")));
+    parts.join(" ")
+}
+
+fn caption(number: usize, elapsed_seconds: i64, actions: &[String]) -> String {
+    let mut actions = if actions.is_empty() {
+        "no GUI actions".to_string()
+    } else {
+        actions.join("; ")
+    };
+    if actions.chars().count() > MAX_CAPTION_ACTIONS {
+        actions = actions.chars().take(MAX_CAPTION_ACTIONS - 1).collect();
+        actions.push('…');
+    }
+    format!(
+        "#{number}  {:02}:{:02}  {actions}",
+        elapsed_seconds / 60,
+        elapsed_seconds % 60
+    )
+}
+
+fn decode_data_url(url: &str) -> Option<Vec<u8>> {
+    let (header, data) = url.strip_prefix("data:image/")?.split_once(',')?;
+    if !header.ends_with(";base64") {
+        return None;
+    }
+    base64::engine::general_purpose::STANDARD.decode(data).ok()
+}
+
+/// Writes `frames` to `output` as a `format` animation that shows each frame
+/// for `seconds_per_frame`.
+pub fn render(
+    frames: &[TimelapseFrame],
+    format: TimelapseFormat,
+    seconds_per_frame: f64,
+    output: &Path,
+) -> anyhow::Result<()> {
+    if frames.is_empty() {
+        anyhow::bail!("the session has no screenshots");
+    }
+    let magick = which("magick")
+        .or_else(|_| which("convert"))
+        .context("computex timelapse needs ImageMagick (`magick` or `convert`)")?;
+    let ffmpeg = match format {
+        TimelapseFormat::Gif => None,
+        TimelapseFormat::Webm => {
+            Some(which("ffmpeg").context("WebM timelapses need ffmpeg; write a .gif instead")?)
+        }
+    };
+    let dir = tempfile::tempdir()?;
+    let mut paths = Vec::with_capacity(frames.len());
+    for (index, frame) in frames.iter().enumerate() {
+        let source = dir.path().join(format!("source-{index:04}"));
+        std::fs::write(&source, &frame.image)?;
+        let path = dir.path().join(format!("frame-{index:04}.png"));
+        let size = format!("{FRAME_WIDTH}x{FRAME_HEIGHT}");
+        let mut command = Command::new(&magick);
+        command
+            .arg(&source)
+            .args(["-resize", &size, "-background", "black", "-gravity"])
+            .args(["center", "-extent", &size, "-gravity", "South"])
+            .args(["-fill", "white", "-undercolor", "#000000B0", "-pointsize"])
+            .args(["20", "-annotate", "+0+8"])
+            .arg(escape_annotation(&frame.caption))
+            .arg(&path);
+        run(command, "ImageMagick")?;
+        paths.push(path);
+    }
+
+    match ffmpeg {
+        None => {
+            let delay = format!("{}", (seconds_per_frame * 100.0).round().max(1.0) as u64);
+            let mut command = Command::new(&magick);
+            command
+                .args(["-delay", &delay, "-loop", "0"])
+                .args(&paths)
+                .args(["-layers", "Optimize"])
+                .arg(output);
+            run(command, "ImageMagick")
+        }
+        Some(ffmpeg) => {
+            let rate = format!(
+                "1000/{}",
+                (seconds_per_frame * 1000.0).round().max(1.0) as u64
+            );
+            let pattern: PathBuf = dir.path().join("frame-%04d.png");
+            let mut command = Command::new(ffmpeg);
+            command
+                .args(["-hide_banner", "-loglevel", "error", "-y", "-framerate"])
+                .arg(rate)
+                .arg("-i")
+                .arg(pattern)
+                .args(["-c:v", "libvpx-vp9", "-pix_fmt", "yuv420p", "-r", "10"])
+                .arg(output);
+            run(command, "ffmpeg")
+        }
+    }
+}
+
+/// ImageMagick expands `%` escapes and backslashes in annotation text.
+fn escape_annotation(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('%', "%%")
+}
+
+fn run(mut command: Command, tool: &str) -> anyhow::Result<()> {
+    let output = command
+        .output()
+        .with_context(|| format!("failed to run {tool}"))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "{tool} failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn frames_are_captioned_with_the_actions_before_them() {
+        let image = base64::engine::general_purpose::STANDARD.encode(b"png");
+        let lines = [
+            r#"{"timestamp":"2025-06-01T10:00:00.000Z","type":"session_meta","payload":{}}"#
+                .to_string(),
+            format!(
+                r#"{{"timestamp":"2025-06-01T10:00:02.000Z","type":"response_item","payload":{{"type":"message","role":"user","content":[{{"type":"input_image","image_url":"data:image/png;base64,{image}"}}]}}}}"#
+            ),
+            r#"{"timestamp":"2025-06-01T10:01:05.000Z","type":"response_item","payload":{"type":"function_call","name":"computer_click","arguments":"{\"x\":640,\"y\":360}","call_id":"c1"}}"#
+                .to_string(),
+            r#"{"timestamp":"2025-06-01T10:01:06.000Z","type":"response_item","payload":{"type":"function_call","name":"computer_type","arguments":"{\"text\":\"100%\"}","call_id":"c2"}}"#
+                .to_string(),
+            r#"{"timestamp":"2025-06-01T10:01:07.000Z","type":"response_item","payload":{"type":"function_call","name":"computer_screenshot","arguments":"{}","call_id":"c3"}}"#
+                .to_string(),
+            format!(
+                r#"{{"timestamp":"2025-06-01T10:01:08.000Z","type":"response_item","payload":{{"type":"message","role":"user","content":[{{"type":"input_image","image_url":"data:image/png;base64,{image}"}}]}}}}"#
+            ),
+        ];
+        let frames = collect_frames(&lines.join("\n"));
+        let captions: Vec<&str> = frames.iter().map(|frame| frame.caption.as_str()).collect();
+        assert_eq!(
+            captions,
+            vec![
+                "#1  00:02  no GUI actions",
+                "#2  01:08  click x=640 y=360; type text=\"100%\"",
+            ]
+        );
+        assert_eq!(frames[0].image, b"png".to_vec());
+        assert_eq!(
+            escape_annotation(&frames[1].caption),
+            "#2  01:08  click x=640 y=360; type text=\"100%%\""
+        );
+        assert_eq!(
+            TimelapseFormat::for_path(Path::new("run.WebM")),
+            Some(TimelapseFormat::Webm)
+        );
+        assert_eq!(TimelapseFormat::for_path(Path::new("run.mp4")), None);
+    }
+}
@@ -0,0 +1,234 @@
+//! The GUI action timeline in session rollouts.
+//!
+//! The tool calls and their results already land in the rollout, but the
+//! context the automation built up around them does not: the window input
+//! went to, the target set with `computer_set_target`, the calibration and
+//! recipes in effect, and the screenshots kept on disk. Each GUI tool call
+//! appends a [`GuiActionItem`] with that context, and `codex resume` reads
+//! the last one back into the session's computer-use state. Calibration
+//! and recipes come from the config, so they are only compared, and the
+//! user is warned when the resumed session runs without them.
+
+use std::time::Duration;
+
+use super::recovery::TargetWindow;
+use crate::codex::Session;
+use crate::codex::TurnContext;
+use crate::config::types::ComputerUseConfig;
+use crate::protocol::BackgroundEventEvent;
+use crate::protocol::EventMsg;
+use crate::protocol::GuiActionItem;
+use crate::protocol::RolloutItem;
+use crate::protocol::WarningEvent;
+
+/// Appends the timeline entry for a finished GUI tool call.
+pub(super) async fn record(
+    session: &Session,
+    config: &ComputerUseConfig,
+    call_id: String,
+    tool: String,
+    success: bool,
+    elapsed: Duration,
+) {
+    let item = {
+        let mut state = session.services.computer_use.lock().await;
+        GuiActionItem {
+            call_id,
+            tool,
+            success,
+            duration_ms: elapsed.as_millis() as u64,
+            screenshot: state.take_kept_screenshot(),
+            target_class: state.target.as_ref().map(|target| target.class.clone()),
+            target_id: state.target.as_ref().map(|target| target.id.clone()),
+            sticky_target: state.sticky_target(),
+            calibration: calibration(config),
+            recipes: config
+                .recipes
+                .iter()
+                .map(|recipe| recipe.app.clone())
+                .collect(),
+            click_accuracy: state.take_click_accuracy(),
+        }
+    };
+    session
+        .persist_rollout_items(&[RolloutItem::GuiAction(item)])
+        .await;
+}
+
+/// Restores the computer-use context recorded in `items`, the rollout of a
+/// resumed or forked session, and tells the user what was restored and
+/// what the current config no longer provides.
+pub(crate) async fn restore_context(session: &Session, turn: &TurnContext, items: &[RolloutItem]) {
+    let actions: Vec<&GuiActionItem> = items
+        .iter()
+        .filter_map(|item| match item {
+            RolloutItem::GuiAction(action) => Some(action),
+            _ => None,
+        })
+        .collect();
+    let Some(last) = actions.last() else {
+        return;
+    };
+    let screenshots = actions
+        .iter()
+        .filter(|action| action.screenshot.is_some())
+        .count() as u64;
+    let target = match (&last.target_class, &last.target_id) {
+        (Some(class), Some(id)) => Some(TargetWindow {
+            id: id.clone(),
+            class: class.clone(),
+        }),
+        _ => None,
+    };
+    session.services.computer_use.lock().await.restore(
+        target,
+        last.sticky_target.clone(),
+        screenshots,
+    );
+    session
+        .send_event(
+            turn,
+            EventMsg::BackgroundEvent(BackgroundEventEvent {
+                message: describe(&actions),
+            }),
+        )
+        .await;
+    for message in mismatches(last, &turn.tools_config.computer_use) {
+        session
+            .send_event(turn, EventMsg::Warning(WarningEvent { message }))
+            .await;
+    }
+}
+
+fn calibration(config: &ComputerUseConfig) -> Option<String> {
+    config
+        .calibration
+        .map(|calibration| calibration.screen.to_string())
+}
+
+/// A one-line account of the restored context.
+fn describe(actions: &[&GuiActionItem]) -> String {
+    let Some(last) = actions.last() else {
+        return String::new();
+    };
+    let mut parts = vec![match actions.len() {
+        1 => "1 GUI action".to_string(),
+        count => format!("{count} GUI actions"),
+    }];
+    if let Some(class) = &last.target_class {
+        parts.push(format!("last input to {class}"));
+    }
+    if let Some(window) = &last.sticky_target {
+        parts.push(format!("target window \"{window}\""));
+    }
+    if let Some(screenshot) = actions
+        .iter()
+        .rev()
+        .find_map(|action| action.screenshot.as_ref())
+    {
+        parts.push(format!("last screenshot {}", screenshot.display()));
+    }
+    format!("restored computer-use context: {}", parts.join(", "))
+}
+
+/// Warnings for context `last` relied on that `config` no longer provides.
+fn mismatches(last: &GuiActionItem, config: &ComputerUseConfig) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let current = calibration(config);
+    if let Some(recorded) = &last.calibration
+        && current.as_ref() != Some(recorded)
+    {
+        let now = match current {
+            Some(screen) => format!("the current one is for {screen}"),
+            None => "no calibration is configured now".to_string(),
+        };
+        warnings.push(format!(
+            "This session was calibrated for a {recorded} screen, but {now}; run `computex calibrate` if clicks miss."
+        ));
+    }
+    let missing: Vec<&str> = last
+        .recipes
+        .iter()
+        .filter(|app| !config.recipes.iter().any(|recipe| &recipe.app == *app))
+        .map(String::as_str)
+        .collect();
+    if !missing.is_empty() {
+        warnings.push(format!(
+            "Recipes used earlier in this session are no longer loaded: {}.",
+            missing.join(", ")
+        ));
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::types::CoordinateCalibration;
+    use crate::config::types::Resolution;
+    use crate::tools::handlers::computer_use::recipes::Recipe;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    #[test]
+    fn describes_the_restored_context_and_what_went_missing() {
+        let mut first = GuiActionItem {
+            call_id: "call-1".to_string(),
+            tool: "computer_screenshot".to_string(),
+            success: true,
+            duration_ms: 120,
+            screenshot: Some(PathBuf::from("/shots/1.png")),
+            target_class: None,
+            target_id: None,
+            sticky_target: None,
+            calibration: Some("1920x1080".to_string()),
+            recipes: vec!["firefox".to_string(), "gimp".to_string()],
+            click_accuracy: None,
+        };
+        let mut last = first.clone();
+        last.call_id = "call-2".to_string();
+        last.tool = "computer_click".to_string();
+        last.screenshot = None;
+        last.target_class = Some("firefox".to_string());
+        last.target_id = Some("0x2a00005".to_string());
+        last.sticky_target = Some("Firefox".to_string());
+        assert_eq!(
+            describe(&[&first, &last]),
+            "restored computer-use context: 2 GUI actions, last input to firefox, target window \"Firefox\", last screenshot /shots/1.png"
+        );
+
+        let mut config = ComputerUseConfig {
+            calibration: Some(CoordinateCalibration {
+                screen: Resolution {
+                    width: 1920,
+                    height: 1080,
+                },
+                scale_x: 1.0,
+                offset_x: 2.0,
+                scale_y: 1.0,
+                offset_y: -1.0,
+            }),
+            recipes: vec![Recipe {
+                app: "firefox".to_string(),
+                window_class: "firefox".to_string(),
+                guidance: None,
+                actions: Vec::new(),
+            }],
+            ..ComputerUseConfig::default()
+        };
+        assert_eq!(
+            mismatches(&last, &config),
+            vec!["Recipes used earlier in this session are no longer loaded: gimp.".to_string()]
+        );
+
+        config.calibration = None;
+        first.recipes.clear();
+        assert_eq!(
+            mismatches(&first, &config),
+            vec![
+                "This session was calibrated for a 1920x1080 screen, but no calibration is configured now; run `computex calibrate` if clicks miss."
+                    .to_string()
+            ]
+        );
+    }
+}
@@ -0,0 +1,622 @@
+//! Tray icons for the `computer_tray_*` tools.
+//!
+//! Tray icons are a few pixels wide in a scaled screenshot, so clicking them
+//! is unreliable. Apps that follow the StatusNotifierItem spec (most GTK, Qt,
+//! Electron, and libappindicator apps) register their icons with a watcher on
+//! the session bus, which lets the tools list them by name, activate them,
+//! and read and trigger their menus over `com.canonical.dbusmenu` without a
+//! click. Legacy XEmbed icons aren't registered there and still need one.
+//! D-Bus calls to a hung app can block indefinitely, so every lookup runs on
+//! its own thread under a deadline.
+
+use std::collections::HashMap;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use zbus::blocking::Connection;
+use zbus::zvariant::OwnedObjectPath;
+use zbus::zvariant::OwnedStructure;
+use zbus::zvariant::OwnedValue;
+use zbus::zvariant::Value;
+
+const WATCHER: &str = "org.kde.StatusNotifierWatcher";
+const WATCHER_PATH: &str = "/StatusNotifierWatcher";
+const ITEM: &str = "org.kde.StatusNotifierItem";
+/// Object path of items registered by bus name only.
+const DEFAULT_ITEM_PATH: &str = "/StatusNotifierItem";
+const MENU: &str = "com.canonical.dbusmenu";
+const PROPERTIES: &str = "org.freedesktop.DBus.Properties";
+
+/// Separates the levels of a `menu_item` path, as in `Settings > Network`.
+pub(super) const MENU_PATH_SEPARATOR: char = '>';
+
+/// How long a list or an activation may take.
+const DEADLINE: Duration = Duration::from_secs(4);
+
+/// A registered tray item and its menu.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct TrayItem {
+    /// Bus name of the app that owns the item.
+    pub service: String,
+    pub path: String,
+    /// The app's stable name for the item, e.g. `nm-applet`.
+    pub id: String,
+    pub title: String,
+    /// `Active`, `Passive`, or `NeedsAttention`.
+    pub status: String,
+    /// Object path of the item's dbusmenu, if it publishes one.
+    pub menu_path: Option<String>,
+    /// Whether the app only shows its menu when activated.
+    pub is_menu: bool,
+    pub menu: Vec<MenuEntry>,
+}
+
+/// One entry of a tray item's menu.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct MenuEntry {
+    pub id: i32,
+    /// The label with mnemonic underscores removed.
+    pub label: String,
+    pub enabled: bool,
+    /// `Some` for checkboxes and radio items.
+    pub checked: Option<bool>,
+    pub submenu: Vec<MenuEntry>,
+}
+
+/// What to do with a tray item.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum TrayAction {
+    /// The primary action, what a left click does.
+    Activate,
+    /// What a middle click does.
+    SecondaryActivate,
+    /// Lists the menu, or asks the app to pop it up when it has no dbusmenu.
+    ContextMenu,
+    /// Triggers the menu entry at this path of labels.
+    MenuItem(Vec<String>),
+}
+
+/// Every registered tray item, with its menu.
+pub(super) fn list() -> Result<Vec<TrayItem>, String> {
+    on_thread(|| {
+        let bus = connect()?;
+        items(&bus)
+    })
+}
+
+/// Performs `action` on the item that `query` names, once `permitted`
+/// accepts its id. Pop-ups and windows the app opens are placed near `at`,
+/// in screen pixels. Returns what was done, for the model.
+pub(super) fn activate(
+    query: &str,
+    action: TrayAction,
+    at: (i32, i32),
+    permitted: impl Fn(&str) -> Result<(), String> + Send + 'static,
+) -> Result<String, String> {
+    let query = query.to_string();
+    on_thread(move || {
+        let bus = connect()?;
+        let items = items(&bus)?;
+        let item = find_item(&items, &query)?;
+        permitted(&item.id)?;
+        let name = describe_item(item);
+        match action {
+            TrayAction::Activate | TrayAction::SecondaryActivate if item.is_menu => Err(format!(
+                "{name} only opens its menu; pass menu_item to choose an entry:\n{}",
+                menu_lines(&item.menu, 0).join("\n")
+            )),
+            TrayAction::Activate => {
+                call_item(&bus, item, "Activate", at)?;
+                Ok(format!("activated {name}"))
+            }
+            TrayAction::SecondaryActivate => {
+                call_item(&bus, item, "SecondaryActivate", at)?;
+                Ok(format!("sent the secondary action to {name}"))
+            }
+            TrayAction::ContextMenu if !item.menu.is_empty() => Ok(format!(
+                "the menu of {name} has these entries; call again with menu_item to choose one:\n{}",
+                menu_lines(&item.menu, 0).join("\n")
+            )),
+            TrayAction::ContextMenu => {
+                call_item(&bus, item, "ContextMenu", at)?;
+                Ok(format!(
+                    "asked {name} to open its context menu; take a screenshot to see it"
+                ))
+            }
+            TrayAction::MenuItem(path) => {
+                let Some(menu_path) = &item.menu_path else {
+                    return Err(format!(
+                        "{name} publishes no menu over D-Bus; use action `context_menu` and click the entry instead"
+                    ));
+                };
+                let entry = find_entry(&item.menu, &path)?;
+                if !entry.enabled {
+                    return Err(format!(
+                        "`{}` is disabled in the menu of {name}",
+                        entry.label
+                    ));
+                }
+                if !entry.submenu.is_empty() {
+                    return Err(format!(
+                        "`{}` opens a submenu; add one of its entries to the path:\n{}",
+                        entry.label,
+                        menu_lines(&entry.submenu, 0).join("\n")
+                    ));
+                }
+                // dbusmenu has the host report the click; the data and
+                // timestamp are unused by the apps.
+                bus.call_method(
+                    Some(item.service.as_str()),
+                    menu_path.as_str(),
+                    Some(MENU),
+                    "Event",
+                    &(entry.id, "clicked", Value::from(0i32), 0u32),
+                )
+                .map_err(|err| format!("{name} rejected the menu click: {err}"))?;
+                Ok(format!(
+                    "selected `{}` in the menu of {name}",
+                    path.join(&format!(" {MENU_PATH_SEPARATOR} "))
+                ))
+            }
+        }
+    })
+}
+
+fn on_thread<T: Send + 'static>(
+    work: impl FnOnce() -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    let (sender, receiver) = mpsc::channel();
+    let spawned = std::thread::Builder::new()
+        .name("computex-tray".to_string())
+        .spawn(move || {
+            let _ = sender.send(work());
+        });
+    if let Err(err) = spawned {
+        return Err(format!("failed to start the tray lookup: {err}"));
+    }
+    match receiver.recv_timeout(DEADLINE) {
+        Ok(result) => result,
+        Err(_) => Err(format!(
+            "the tray apps did not answer within {} s",
+            DEADLINE.as_secs()
+        )),
+    }
+}
+
+fn connect() -> Result<Connection, String> {
+    Connection::session().map_err(|err| format!("the session bus is unavailable: {err}"))
+}
+
+fn items(bus: &Connection) -> Result<Vec<TrayItem>, String> {
+    let registered: Vec<String> = property(bus, WATCHER, WATCHER_PATH, WATCHER, "RegisteredStatusNotifierItems")
+        .and_then(|value| Vec::<String>::try_from(value).map_err(zbus::Error::from))
+        .map_err(|err| {
+            format!(
+                "no StatusNotifierItem watcher on the session bus ({err}); this desktop's tray can only be clicked"
+            )
+        })?;
+    // An app can quit while it is read; leave it out.
+    Ok(registered
+        .iter()
+        .filter_map(|registered| {
+            let (service, path) = split_item(registered);
+            item(bus, service, path).ok()
+        })
+        .collect())
+}
+
+fn item(bus: &Connection, service: &str, path: &str) -> zbus::Result<TrayItem> {
+    let text = |name: &str| {
+        property(bus, service, path, ITEM, name)
+            .ok()
+            .and_then(|value| String::try_from(value).ok())
+            .unwrap_or_default()
+    };
+    let id = text("Id");
+    if id.is_empty() {
+        // Every item must have an Id; without one this isn't an item.
+        return Err(zbus::Error::InvalidReply);
+    }
+    let menu_path = property(bus, service, path, ITEM, "Menu")
+        .ok()
+        .and_then(|value| OwnedObjectPath::try_from(value).ok())
+        .map(|path| path.as_str().to_string())
+        .filter(|path| path != "/");
+    let is_menu = property(bus, service, path, ITEM, "ItemIsMenu")
+        .ok()
+        .and_then(|value| bool::try_from(value).ok())
+        .unwrap_or(false);
+    let menu = menu_path
+        .as_deref()
+        .map(|menu_path| menu(bus, service, menu_path).unwrap_or_default())
+        .unwrap_or_default();
+    Ok(TrayItem {
+        service: service.to_string(),
+        path: path.to_string(),
+        id,
+        title: text("Title"),
+        status: text("Status"),
+        menu_path,
+        is_menu,
+        menu,
+    })
+}
+
+fn property(
+    bus: &Connection,
+    service: &str,
+    path: &str,
+    interface: &str,
+    name: &str,
+) -> zbus::Result<OwnedValue> {
+    bus.call_method(
+        Some(service),
+        path,
+        Some(PROPERTIES),
+        "Get",
+        &(interface, name),
+    )?
+    .body()
+    .deserialize()
+}
+
+fn menu(bus: &Connection, service: &str, path: &str) -> zbus::Result<Vec<MenuEntry>> {
+    // Apps that build their menus lazily fill them in on AboutToShow.
+    let _ = bus.call_method(Some(service), path, Some(MENU), "AboutToShow", &(0i32,));
+    let names: &[&str] = &[];
+    let layout: OwnedStructure = bus
+        .call_method(
+            Some(service),
+            path,
+            Some(MENU),
+            "GetLayout",
+            &(0i32, -1i32, names),
+        )?
+        .body()
+        .deserialize()?;
+    // The reply is (revision, root), and the root's children are the menu.
+    Ok(layout
+        .0
+        .fields()
+        .get(1)
+        .and_then(entry)
+        .map(|root| root.submenu)
+        .unwrap_or_default())
+}
+
+fn call_item(
+    bus: &Connection,
+    item: &TrayItem,
+    method: &str,
+    (x, y): (i32, i32),
+) -> Result<(), String> {
+    bus.call_method(
+        Some(item.service.as_str()),
+        item.path.as_str(),
+        Some(ITEM),
+        method,
+        &(x, y),
+    )
+    .map(drop)
+    .map_err(|err| format!("{} rejected {method}: {err}", describe_item(item)))
+}
+
+/// A dbusmenu layout node, `(id, properties, children)`, skipping
+/// separators and hidden entries.
+fn entry(value: &Value<'_>) -> Option<MenuEntry> {
+    let Value::Structure(node) = unwrap_variant(value) else {
+        return None;
+    };
+    let [
+        Value::I32(id),
+        Value::Dict(properties),
+        Value::Array(children),
+    ] = node.fields()
+    else {
+        return None;
+    };
+    let properties: HashMap<&str, &Value<'_>> = properties
+        .iter()
+        .filter_map(|(key, value)| match key {
+            Value::Str(key) => Some((key.as_str(), unwrap_variant(value))),
+            _ => None,
+        })
+        .collect();
+    let text = |name: &str| match properties.get(name) {
+        Some(Value::Str(text)) => Some(text.as_str()),
+        _ => None,
+    };
+    let flag = |name: &str| match properties.get(name) {
+        Some(Value::Bool(flag)) => Some(*flag),
+        _ => None,
+    };
+    if text("type") == Some("separator") || flag("visible") == Some(false) {
+        return None;
+    }
+    let checked = match (text("toggle-type"), properties.get("toggle-state")) {
+        (Some("checkmark" | "radio"), Some(Value::I32(state))) => Some(*state == 1),
+        _ => None,
+    };
+    Some(MenuEntry {
+        id: *id,
+        label: menu_label(text("label").unwrap_or_default()),
+        enabled: flag("enabled").unwrap_or(true),
+        checked,
+        submenu: children.inner().iter().filter_map(entry).collect(),
+    })
+}
+
+fn unwrap_variant<'a, 'v>(value: &'a Value<'v>) -> &'a Value<'v> {
+    match value {
+        Value::Value(inner) => unwrap_variant(inner),
+        value => value,
+    }
+}
+
+/// A watcher entry is a bus name followed by the item's object path, or a
+/// bare bus name for items at the default path.
+fn split_item(registered: &str) -> (&str, &str) {
+    match registered.find('/') {
+        Some(slash) => (&registered[..slash], &registered[slash..]),
+        None => (registered, DEFAULT_ITEM_PATH),
+    }
+}
+
+/// `label` without mnemonic underscores (`_File` is "File", `__` is "_").
+fn menu_label(label: &str) -> String {
+    let mut plain = String::with_capacity(label.len());
+    let mut chars = label.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '_' => plain.extend(chars.next()),
+            c => plain.push(c),
+        }
+    }
+    plain.trim().to_string()
+}
+
+/// The item whose id or title is `query`, or else contains it.
+fn find_item<'a>(items: &'a [TrayItem], query: &str) -> Result<&'a TrayItem, String> {
+    let query = query.trim();
+    let wanted = query.to_lowercase();
+    let names = |item: &TrayItem| [item.id.to_lowercase(), item.title.to_lowercase()];
+    items
+        .iter()
+        .find(|item| names(item).contains(&wanted))
+        .or_else(|| {
+            items.iter().find(|item| {
+                names(item)
+                    .iter()
+                    .any(|name| !name.is_empty() && name.contains(&wanted))
+            })
+        })
+        .ok_or_else(|| {
+            let known: Vec<String> = items.iter().map(describe_item).collect();
+            match known.is_empty() {
+                true => format!("no tray item matches `{query}`; no items are registered"),
+                false => format!(
+                    "no tray item matches `{query}`; the tray has {}",
+                    known.join(", ")
+                ),
+            }
+        })
+}
+
+/// The entry at `path`, matching each level's labels case-insensitively and
+/// ignoring trailing ellipses.
+fn find_entry<'a>(menu: &'a [MenuEntry], path: &[String]) -> Result<&'a MenuEntry, String> {
+    let normalize = |label: &str| {
+        label
+            .trim()
+            .trim_end_matches('…')
+            .trim_end_matches("...")
+            .trim()
+            .to_lowercase()
+    };
+    let mut level = menu;
+    let mut found = None;
+    for wanted in path {
+        let entry = level
+            .iter()
+            .find(|entry| normalize(&entry.label) == normalize(wanted))
+            .ok_or_else(|| {
+                let labels: Vec<&str> = level.iter().map(|entry| entry.label.as_str()).collect();
+                format!(
+                    "no menu entry `{}`; the choices are: {}",
+                    wanted.trim(),
+                    labels.join(", ")
+                )
+            })?;
+        level = &entry.submenu;
+        found = Some(entry);
+    }
+    found.ok_or_else(|| "menu_item is empty".to_string())
+}
+
+fn describe_item(item: &TrayItem) -> String {
+    match item.title.is_empty() || item.title == item.id {
+        true => format!("`{}`", item.id),
+        false => format!("`{}` (\"{}\")", item.id, item.title),
+    }
+}
+
+/// Lines listing the tray items and their menus, for the model.
+pub(super) fn describe(items: &[TrayItem]) -> Vec<String> {
+    if items.is_empty() {
+        return vec![
+            "no tray items are registered; icons in a legacy tray can only be clicked".to_string(),
+        ];
+    }
+    let mut lines = vec![format!("tray items: {}", items.len())];
+    for item in items {
+        let mut line = format!("- {}", describe_item(item));
+        if item.status == "NeedsAttention" {
+            line.push_str(", needs attention");
+        }
+        if item.menu.is_empty() {
+            line.push_str(match item.menu_path {
+                Some(_) => ", empty menu",
+                None => ", no D-Bus menu",
+            });
+            lines.push(line);
+        } else {
+            line.push_str(", menu:");
+            lines.push(line);
+            lines.extend(menu_lines(&item.menu, 1));
+        }
+    }
+    lines
+}
+
+fn menu_lines(menu: &[MenuEntry], depth: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for entry in menu {
+        let mut line = format!("{}- {}", "  ".repeat(depth), entry.label);
+        match entry.checked {
+            Some(true) => line.push_str(" [checked]"),
+            Some(false) => line.push_str(" [unchecked]"),
+            None => {}
+        }
+        if !entry.enabled {
+            line.push_str(" (disabled)");
+        }
+        lines.push(line);
+        lines.extend(menu_lines(&entry.submenu, depth + 1));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use zbus::zvariant::Array;
+    use zbus::zvariant::Dict;
+    use zbus::zvariant::StructureBuilder;
+
+    fn node<'a>(
+        id: i32,
+        properties: Vec<(&'a str, Value<'a>)>,
+        children: Vec<Value<'a>>,
+    ) -> Value<'a> {
+        let mut dict = Dict::new(
+            <&str as zbus::zvariant::Type>::signature(),
+            <Value<'_> as zbus::zvariant::Type>::signature(),
+        );
+        for (key, value) in properties {
+            let _ = dict.append(Value::from(key), Value::Value(Box::new(value)));
+        }
+        let mut array = Array::new(<Value<'_> as zbus::zvariant::Type>::signature());
+        for child in children {
+            let _ = array.append(Value::Value(Box::new(child)));
+        }
+        Value::Structure(
+            StructureBuilder::new()
+                .append_field(Value::I32(id))
+                .append_field(Value::Dict(dict))
+                .append_field(Value::Array(array))
+                .build(),
+        )
+    }
+
+    #[test]
+    fn reads_menus_and_resolves_paths() -> Result<(), String> {
+        let root = node(
+            0,
+            vec![],
+            vec![
+                node(
+                    1,
+                    vec![
+                        ("label", Value::from("_Enable Networking")),
+                        ("toggle-type", Value::from("checkmark")),
+                        ("toggle-state", Value::I32(1)),
+                    ],
+                    vec![],
+                ),
+                node(2, vec![("type", Value::from("separator"))], vec![]),
+                node(
+                    3,
+                    vec![
+                        ("label", Value::from("VPN Connections")),
+                        ("children-display", Value::from("submenu")),
+                    ],
+                    vec![
+                        node(4, vec![("label", Value::from("Configure VPN…"))], vec![]),
+                        node(
+                            5,
+                            vec![
+                                ("label", Value::from("Disconnect")),
+                                ("enabled", Value::Bool(false)),
+                            ],
+                            vec![],
+                        ),
+                    ],
+                ),
+                node(
+                    6,
+                    vec![
+                        ("label", Value::from("Hidden")),
+                        ("visible", Value::Bool(false)),
+                    ],
+                    vec![],
+                ),
+            ],
+        );
+        let menu = entry(&root).map(|root| root.submenu).unwrap_or_default();
+        let item = TrayItem {
+            service: ":1.42".to_string(),
+            path: "/org/ayatana/NotificationItem/nm_applet".to_string(),
+            id: "nm-applet".to_string(),
+            title: "Network".to_string(),
+            status: "Active".to_string(),
+            menu_path: Some("/org/ayatana/NotificationItem/nm_applet/Menu".to_string()),
+            is_menu: false,
+            menu,
+        };
+        assert_eq!(
+            describe(std::slice::from_ref(&item)),
+            vec![
+                "tray items: 1",
+                "- `nm-applet` (\"Network\"), menu:",
+                "  - Enable Networking [checked]",
+                "  - VPN Connections",
+                "    - Configure VPN…",
+                "    - Disconnect (disabled)",
+            ]
+        );
+
+        let path = |path: &str| -> Vec<String> {
+            path.split(MENU_PATH_SEPARATOR)
+                .map(str::to_string)
+                .collect()
+        };
+        assert_eq!(
+            find_entry(&item.menu, &path("vpn connections > Configure VPN..."))
+                .map(|entry| entry.id),
+            Ok(4)
+        );
+        assert_eq!(
+            find_entry(&item.menu, &path("Settings")).map(|entry| entry.id),
+            Err(
+                "no menu entry `Settings`; the choices are: Enable Networking, VPN Connections"
+                    .to_string()
+            )
+        );
+
+        let items = [item];
+        assert_eq!(find_item(&items, "network")?.id, "nm-applet");
+        assert_eq!(find_item(&items, "nm")?.id, "nm-applet");
+        assert!(find_item(&items, "slack").is_err());
+        assert_eq!(
+            split_item(":1.42/org/ayatana/NotificationItem/nm_applet"),
+            (":1.42", "/org/ayatana/NotificationItem/nm_applet")
+        );
+        assert_eq!(
+            split_item("org.kde.StatusNotifierItem-1234-1"),
+            ("org.kde.StatusNotifierItem-1234-1", DEFAULT_ITEM_PATH)
+        );
+        assert_eq!(menu_label(" Save__as_ "), "Save_as");
+        Ok(())
+    }
+}
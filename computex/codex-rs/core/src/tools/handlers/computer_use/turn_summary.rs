@@ -0,0 +1,328 @@
+//! Screen change summaries between model turns, for
+//! `computer_use.turn_change_summary`.
+//!
+//! The first input action after the model last looked at the screen records
+//! what the screen showed: the visible titled windows, which one had focus,
+//! and a small thumbnail. When a model turn that sent input ends, the screen
+//! is read again and the differences go into the next request as a short
+//! note, so the model can tell that a dialog opened or a page changed without
+//! paying for another full screenshot. Window titles come from the window
+//! manager rather than OCR, so they are exact. A screenshot resets the
+//! baseline, since the model has then seen the screen itself.
+
+use std::path::Path;
+
+use codex_protocol::user_input::UserInput;
+use codex_utils_image::compare::compare;
+use codex_utils_image::screenshot::RawImage;
+
+use super::capture_root_window;
+use super::compare::DEFAULT_TOLERANCE;
+use super::recovery::active_window;
+use super::recovery::xdotool_output;
+use super::require_command;
+use crate::codex::Session;
+use crate::config::types::ComputerUseConfig;
+use crate::config::types::Resolution;
+use crate::function_tool::FunctionCallError;
+
+/// Size of the thumbnail compared between snapshots, split into a 3x3 grid
+/// of named regions.
+const THUMBNAIL: Resolution = Resolution {
+    width: 96,
+    height: 54,
+};
+
+/// Names of the grid cells, row by row.
+const REGIONS: [&str; 9] = [
+    "top-left",
+    "top",
+    "top-right",
+    "left",
+    "center",
+    "right",
+    "bottom-left",
+    "bottom",
+    "bottom-right",
+];
+
+/// Fraction of a region's thumbnail pixels that must change for it to be
+/// reported, so a blinking caret or clock doesn't count.
+const REGION_THRESHOLD: f64 = 0.02;
+
+/// Windows beyond this many are ignored, to bound the xdotool calls.
+const MAX_WINDOWS: usize = 40;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Window {
+    id: String,
+    class: String,
+    title: String,
+}
+
+/// What the screen showed at one moment.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ScreenState {
+    windows: Vec<Window>,
+    focused: Option<String>,
+    thumbnail: Option<RawImage>,
+}
+
+/// Records the screen before an input action unless a baseline is already
+/// held. Failures leave no baseline, which skips the summary.
+pub(super) async fn record_baseline(session: &Session, config: &ComputerUseConfig) {
+    if session
+        .services
+        .computer_use
+        .lock()
+        .await
+        .has_change_baseline()
+    {
+        return;
+    }
+    match snapshot(config) {
+        Ok(state) => session
+            .services
+            .computer_use
+            .lock()
+            .await
+            .set_change_baseline(state),
+        Err(err) => tracing::debug!("skipping the turn change summary: {err:?}"),
+    }
+}
+
+/// Called when a model turn ends with another to follow. If the turn sent
+/// GUI input since the model last saw the screen, adds a note describing
+/// what changed to the next request.
+pub(crate) async fn summarize_turn(session: &Session, config: &ComputerUseConfig) {
+    if !config.turn_change_summary {
+        return;
+    }
+    let Some(before) = session
+        .services
+        .computer_use
+        .lock()
+        .await
+        .take_change_baseline()
+    else {
+        return;
+    };
+    let after = match snapshot(config) {
+        Ok(after) => after,
+        Err(err) => {
+            tracing::debug!("skipping the turn change summary: {err:?}");
+            return;
+        }
+    };
+    let text = format_changes(&describe(&before, &after));
+    if session
+        .inject_input(vec![UserInput::Text { text }])
+        .await
+        .is_err()
+    {
+        tracing::debug!("no active turn for the screen change summary");
+    }
+}
+
+fn snapshot(config: &ComputerUseConfig) -> Result<ScreenState, FunctionCallError> {
+    let xdotool = require_command("xdotool")?;
+    let ids = xdotool_output(
+        &xdotool,
+        config,
+        &["search", "--onlyvisible", "--name", "."],
+    )?;
+    let windows = ids
+        .lines()
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .take(MAX_WINDOWS)
+        .filter_map(|id| window(&xdotool, config, id))
+        .collect();
+    let focused = active_window(&xdotool, config).ok().map(|window| window.id);
+    Ok(ScreenState {
+        windows,
+        focused,
+        thumbnail: thumbnail(config),
+    })
+}
+
+fn window(xdotool: &Path, config: &ComputerUseConfig, id: &str) -> Option<Window> {
+    let title = xdotool_output(xdotool, config, &["getwindowname", id]).ok()?;
+    if title.is_empty() {
+        return None;
+    }
+    let class = xdotool_output(xdotool, config, &["getwindowclassname", id]).unwrap_or_default();
+    Some(Window {
+        id: id.to_string(),
+        class,
+        title,
+    })
+}
+
+fn thumbnail(config: &ComputerUseConfig) -> Option<RawImage> {
+    let path = capture_root_window(config, None, Some(THUMBNAIL)).ok()?;
+    let image = RawImage::open(&path).ok();
+    let _ = std::fs::remove_file(&path);
+    image
+}
+
+/// Lines describing how `after` differs from `before`.
+fn describe(before: &ScreenState, after: &ScreenState) -> Vec<String> {
+    let mut lines = Vec::new();
+    for window in &after.windows {
+        match before.windows.iter().find(|old| old.id == window.id) {
+            None => lines.push(format!(
+                "opened `{}` window \"{}\"",
+                window.class, window.title
+            )),
+            Some(old) if old.title != window.title => lines.push(format!(
+                "`{}` window \"{}\" is now titled \"{}\"",
+                window.class, old.title, window.title
+            )),
+            Some(_) => {}
+        }
+    }
+    for window in &before.windows {
+        if !after.windows.iter().any(|new| new.id == window.id) {
+            lines.push(format!(
+                "closed `{}` window \"{}\"",
+                window.class, window.title
+            ));
+        }
+    }
+    if after.focused != before.focused {
+        let focused = after
+            .focused
+            .as_ref()
+            .and_then(|id| after.windows.iter().find(|window| &window.id == id));
+        lines.push(match focused {
+            Some(window) => format!("focus moved to `{}` (\"{}\")", window.class, window.title),
+            None => "focus moved to an untitled window".to_string(),
+        });
+    }
+    if let (Some(old), Some(new)) = (&before.thumbnail, &after.thumbnail) {
+        match changed_regions(old, new) {
+            Some((regions, _)) if regions.is_empty() => {
+                lines.push("the screen content looks unchanged".to_string());
+            }
+            Some((regions, percent)) => lines.push(format!(
+                "content changed in the {} region{} (about {percent}% of the screen)",
+                regions.join(", "),
+                if regions.len() == 1 { "" } else { "s" }
+            )),
+            None => {}
+        }
+    }
+    lines
+}
+
+/// Grid regions whose thumbnail pixels changed, with the changed share of
+/// the whole thumbnail in percent.
+fn changed_regions(before: &RawImage, after: &RawImage) -> Option<(Vec<&'static str>, u64)> {
+    let diff = compare(before, after, DEFAULT_TOLERANCE).ok()?;
+    let width = diff.diff.width as usize;
+    let height = diff.diff.height as usize;
+    let mut changed = [0usize; 9];
+    let mut total = [0usize; 9];
+    for (index, pixel) in diff.diff.pixels.chunks_exact(3).enumerate() {
+        let (x, y) = (index % width, index / width);
+        let cell = (y * 3 / height) * 3 + x * 3 / width;
+        total[cell] += 1;
+        // `compare` paints changed pixels pure red and fades the rest to gray.
+        if pixel == [255, 0, 0] {
+            changed[cell] += 1;
+        }
+    }
+    let regions = REGIONS
+        .iter()
+        .enumerate()
+        .filter(|(cell, _)| {
+            total[*cell] > 0 && changed[*cell] as f64 / total[*cell] as f64 >= REGION_THRESHOLD
+        })
+        .map(|(_, name)| *name)
+        .collect();
+    let percent = (100.0 * (1.0 - diff.similarity)).round() as u64;
+    Some((regions, percent.max(1)))
+}
+
+fn format_changes(lines: &[String]) -> String {
+    if lines.is_empty() {
+        return "Screen changes from your last GUI actions: none detected.".to_string();
+    }
+    let mut text =
+        "Screen changes from your last GUI actions (since you last saw the screen):".to_string();
+    for line in lines {
+        text.push_str("\n- ");
+        text.push_str(line);
+    }
+    text.push_str("\nTake a screenshot if you need the details.");
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_utils_image::error::ImageProcessingError;
+    use pretty_assertions::assert_eq;
+
+    fn window(id: &str, class: &str, title: &str) -> Window {
+        Window {
+            id: id.to_string(),
+            class: class.to_string(),
+            title: title.to_string(),
+        }
+    }
+
+    fn image(changed: impl Fn(usize, usize) -> bool) -> Result<RawImage, ImageProcessingError> {
+        let (width, height) = (THUMBNAIL.width as usize, THUMBNAIL.height as usize);
+        let mut pixels = Vec::with_capacity(width * height * 3);
+        for y in 0..height {
+            for x in 0..width {
+                let value = if changed(x, y) { 0 } else { 200 };
+                pixels.extend([value, value, value]);
+            }
+        }
+        RawImage::new(THUMBNAIL.width, THUMBNAIL.height, 3, pixels)
+    }
+
+    #[test]
+    fn describes_window_focus_and_region_changes() -> Result<(), ImageProcessingError> {
+        let before = ScreenState {
+            windows: vec![
+                window("1", "Firefox", "Inbox – Mozilla Firefox"),
+                window("2", "Gnome-terminal", "~"),
+            ],
+            focused: Some("1".to_string()),
+            thumbnail: Some(image(|_, _| false)?),
+        };
+        let after = ScreenState {
+            windows: vec![
+                window("1", "Firefox", "Compose – Mozilla Firefox"),
+                window("3", "Firefox", "Save As"),
+            ],
+            focused: Some("3".to_string()),
+            thumbnail: Some(image(|x, y| {
+                (32..64).contains(&x) && (18..36).contains(&y)
+            })?),
+        };
+        assert_eq!(
+            describe(&before, &after),
+            vec![
+                "`Firefox` window \"Inbox – Mozilla Firefox\" is now titled \"Compose – Mozilla Firefox\"".to_string(),
+                "opened `Firefox` window \"Save As\"".to_string(),
+                "closed `Gnome-terminal` window \"~\"".to_string(),
+                "focus moved to `Firefox` (\"Save As\")".to_string(),
+                "content changed in the center region (about 11% of the screen)".to_string(),
+            ]
+        );
+        assert_eq!(
+            describe(&before, &before),
+            vec!["the screen content looks unchanged".to_string()]
+        );
+        assert_eq!(
+            format_changes(&[]),
+            "Screen changes from your last GUI actions: none detected."
+        );
+        Ok(())
+    }
+}
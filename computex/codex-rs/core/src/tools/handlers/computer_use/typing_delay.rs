@@ -0,0 +1,113 @@
+//! Cross-session store of per-app key delays for `computer_type`.
+//!
+//! When verified typing finds that an app dropped characters, the delay
+//! between keystrokes for that app's window class is raised and saved, so
+//! later `computer_type` calls into the same app type slowly enough without
+//! the model having to pass `delay_ms`.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+
+use chrono::DateTime;
+use chrono::Utc;
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::json_store;
+use super::json_store::StoreLock;
+
+/// Location of the store, relative to `$CODEX_HOME`.
+pub(super) const TYPING_DELAYS_FILE: &str = "computer_use/typing_delays.json";
+
+/// Delay xdotool uses between keystrokes when none is given.
+const XDOTOOL_DEFAULT_DELAY_MS: u64 = 12;
+
+/// Tuning never raises the delay past this.
+pub(super) const MAX_TUNED_DELAY_MS: u64 = 200;
+
+static STORE_LOCK: StoreLock = StoreLock::new();
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct TunedDelay {
+    delay_ms: u64,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TypingDelayStore {
+    #[serde(default)]
+    apps: BTreeMap<String, TunedDelay>,
+}
+
+fn app_key(window_class: &str) -> String {
+    window_class.trim().to_ascii_lowercase()
+}
+
+/// The saved key delay for `window_class`, if tuning has raised one.
+pub(super) fn tuned_delay(path: &Path, window_class: &str) -> io::Result<Option<u64>> {
+    let _guard = STORE_LOCK.lock();
+    Ok(json_store::load::<TypingDelayStore>(path)?
+        .apps
+        .get(&app_key(window_class))
+        .map(|tuned| tuned.delay_ms))
+}
+
+/// Doubles the key delay for `window_class` after characters were dropped at
+/// `used_ms` (`None` for xdotool's default). Returns the new delay, or `None`
+/// when it is already at [`MAX_TUNED_DELAY_MS`].
+pub(super) fn raise(
+    path: &Path,
+    window_class: &str,
+    used_ms: Option<u64>,
+) -> io::Result<Option<u64>> {
+    let _guard = STORE_LOCK.lock();
+    let mut store: TypingDelayStore = json_store::load(path)?;
+    let key = app_key(window_class);
+    let saved = store.apps.get(&key).map(|tuned| tuned.delay_ms);
+    let current = used_ms
+        .into_iter()
+        .chain(saved)
+        .max()
+        .unwrap_or(XDOTOOL_DEFAULT_DELAY_MS);
+    let next = next_delay(current);
+    if next <= current {
+        return Ok(None);
+    }
+    store.apps.insert(
+        key,
+        TunedDelay {
+            delay_ms: next,
+            updated_at: Utc::now(),
+        },
+    );
+    json_store::persist(path, &store)?;
+    Ok(Some(next))
+}
+
+fn next_delay(current_ms: u64) -> u64 {
+    (current_ms.max(XDOTOOL_DEFAULT_DELAY_MS) * 2).min(MAX_TUNED_DELAY_MS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    #[test]
+    fn dropped_characters_double_the_delay_per_app_up_to_the_cap() -> io::Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join(TYPING_DELAYS_FILE);
+        assert_eq!(tuned_delay(&path, "Code")?, None);
+
+        assert_eq!(raise(&path, "Code", None)?, Some(24));
+        assert_eq!(raise(&path, "code", None)?, Some(48));
+        assert_eq!(tuned_delay(&path, "CODE")?, Some(48));
+        assert_eq!(raise(&path, "Slack", Some(80))?, Some(160));
+        assert_eq!(raise(&path, "Slack", None)?, Some(MAX_TUNED_DELAY_MS));
+        assert_eq!(raise(&path, "Slack", None)?, None);
+        assert_eq!(tuned_delay(&path, "code")?, Some(48));
+        Ok(())
+    }
+}
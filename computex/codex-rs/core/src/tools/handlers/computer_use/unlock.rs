@@ -0,0 +1,162 @@
+//! `computer_unlock`: types the user's stored unlock secret at a lock screen.
+//!
+//! Unattended lab rigs lock their screens on a timer. When
+//! `computer_use.unlock_helper` is set, the user stores the secret once with
+//! `computex unlock-secret set` and the model can call `computer_unlock` after
+//! a `screen locked` error. The secret goes from the keyring to `xdotool` on
+//! stdin; it never appears in tool output, arguments, or logs. The tool only
+//! types while a known screen locker's window is mapped and focused, checked
+//! again right before typing, so it can't be used to paste the secret into
+//! an ordinary window. A screen saver that reports itself active isn't
+//! enough: it may be blanking an unlocked session, and waking it would hand
+//! focus back to whatever window had it.
+
+use std::io::Write;
+use std::path::Path;
+use std::process::Stdio;
+use std::thread;
+use std::time::Duration;
+
+use codex_keyring_store::DefaultKeyringStore;
+use codex_keyring_store::KeyringStore;
+
+use super::gui_command;
+use super::lock;
+use super::require_command;
+use crate::config::types::ComputerUseConfig;
+use crate::function_tool::FunctionCallError;
+
+const KEYRING_SERVICE: &str = "Computex Computer Use";
+const KEYRING_ACCOUNT: &str = "unlock-secret";
+
+/// How long the lock screen gets to wake up before the secret is typed, and to
+/// go away after it is submitted.
+const WAKE_DELAY: Duration = Duration::from_millis(700);
+const UNLOCK_DELAY: Duration = Duration::from_secs(2);
+
+/// Stores `secret` in the OS keyring for `computer_unlock`.
+pub fn save_secret(secret: &str) -> anyhow::Result<()> {
+    DefaultKeyringStore
+        .save(KEYRING_SERVICE, KEYRING_ACCOUNT, secret)
+        .map_err(|err| anyhow::anyhow!("failed to save the unlock secret to the keyring: {err}"))
+}
+
+/// Removes the stored unlock secret. Returns whether one was stored.
+pub fn delete_secret() -> anyhow::Result<bool> {
+    DefaultKeyringStore
+        .delete(KEYRING_SERVICE, KEYRING_ACCOUNT)
+        .map_err(|err| {
+            anyhow::anyhow!("failed to delete the unlock secret from the keyring: {err}")
+        })
+}
+
+/// Whether an unlock secret is stored.
+pub fn has_secret() -> anyhow::Result<bool> {
+    load_secret(&DefaultKeyringStore).map(|secret| secret.is_some())
+}
+
+fn load_secret(store: &dyn KeyringStore) -> anyhow::Result<Option<String>> {
+    store
+        .load(KEYRING_SERVICE, KEYRING_ACCOUNT)
+        .map_err(|err| anyhow::anyhow!("failed to read the unlock secret from the keyring: {err}"))
+}
+
+/// Wakes the lock screen, types the stored secret, and submits it. Returns the
+/// model-facing result; the secret is never part of it.
+pub(super) fn unlock(config: &ComputerUseConfig) -> Result<String, FunctionCallError> {
+    let xdotool = require_command("xdotool")?;
+    let Some(locker) = lock::focused_locker(&xdotool, config) else {
+        return Err(FunctionCallError::RespondToModel(
+            "no lock screen has focus; computer_unlock only types the unlock secret into a screen locker's prompt"
+                .to_string(),
+        ));
+    };
+    let secret = load_secret(&DefaultKeyringStore)
+        .map_err(|err| FunctionCallError::RespondToModel(format!("{err:#}")))?
+        .ok_or_else(|| {
+            FunctionCallError::RespondToModel(
+                "no unlock secret is stored; the user must run `computex unlock-secret set`; stop and tell the user"
+                    .to_string(),
+            )
+        })?;
+    // A modifier tap raises the password prompt without typing into it.
+    press(&xdotool, config, "ctrl")?;
+    thread::sleep(WAKE_DELAY);
+    if lock::focused_locker(&xdotool, config).as_deref() != Some(locker.as_str()) {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "`{locker}` lost focus when the screen woke, so the unlock secret was not typed. Do not retry; stop and tell the user"
+        )));
+    }
+    type_secret(&xdotool, config, &secret)?;
+    press(&xdotool, config, "Return")?;
+    thread::sleep(UNLOCK_DELAY);
+    match lock::lock_reason(config) {
+        None => Ok(format!(
+            "unlocked the screen (`{locker}`) with the stored secret; take a screenshot before continuing"
+        )),
+        Some(still) => Err(FunctionCallError::RespondToModel(format!(
+            "typed the stored unlock secret but the screen is still locked ({still}); the secret may be wrong. Do not retry; stop and tell the user"
+        ))),
+    }
+}
+
+fn press(xdotool: &Path, config: &ComputerUseConfig, key: &str) -> Result<(), FunctionCallError> {
+    let status = gui_command(xdotool, config)
+        .args(["key", "--clearmodifiers", key])
+        .status()
+        .map_err(|err| {
+            FunctionCallError::RespondToModel(format!("failed to run xdotool: {err}"))
+        })?;
+    if !status.success() {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "xdotool key {key} failed at the lock screen"
+        )));
+    }
+    Ok(())
+}
+
+/// Types `secret` by piping it to `xdotool type --file -`, so it never shows
+/// up in a process listing.
+fn type_secret(
+    xdotool: &Path,
+    config: &ComputerUseConfig,
+    secret: &str,
+) -> Result<(), FunctionCallError> {
+    let failed = || {
+        FunctionCallError::RespondToModel(
+            "failed to type the unlock secret at the lock screen".to_string(),
+        )
+    };
+    let mut child = gui_command(xdotool, config)
+        .args(["type", "--clearmodifiers", "--file", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|_| failed())?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(secret.as_bytes()).map_err(|_| failed())?;
+    }
+    let status = child.wait().map_err(|_| failed())?;
+    if !status.success() {
+        return Err(failed());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_keyring_store::tests::MockKeyringStore;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn loads_the_secret_from_its_keyring_entry() {
+        let store = MockKeyringStore::default();
+        assert_eq!(load_secret(&store).unwrap(), None);
+        store
+            .save(KEYRING_SERVICE, KEYRING_ACCOUNT, "hunter2")
+            .unwrap();
+        assert_eq!(load_secret(&store).unwrap(), Some("hunter2".to_string()));
+    }
+}
@@ -0,0 +1,222 @@
+//! How much GUI tool results say, for `computer_use.result_verbosity`.
+//!
+//! A model that clicks its way through a long task mostly needs to know
+//! that each action worked, and every coordinate echoed back costs tokens
+//! on every later request. When something goes wrong, it needs the
+//! opposite: the exact input sent, the window it landed in, and what the
+//! failing command printed. `terse` cuts successful input actions down to
+//! `ok` and errors to their first line; `debug` adds those details. What
+//! observation tools return is their point, so it is never cut.
+
+use std::process::ExitStatus;
+use std::time::Duration;
+
+use super::recovery::TargetWindow;
+use crate::config::types::ComputerUseConfig;
+use crate::config::types::InputProvider;
+use crate::config::types::ResultVerbosity;
+use crate::function_tool::FunctionCallError;
+use crate::tools::context::ToolOutput;
+
+/// The whole result of a successful input action under `terse`.
+const TERSE_OK: &str = "ok";
+
+/// Whether `output` is a successful input action's `terse` result, which
+/// recipe guidance may follow.
+pub(super) fn is_terse_ok(output: &str) -> bool {
+    output.lines().next() == Some(TERSE_OK)
+}
+
+/// Fits a finished call's result to `verbosity`. `sent_input` says whether
+/// the tool acts on the GUI rather than reading it.
+pub(super) fn shape(
+    verbosity: ResultVerbosity,
+    tool: &str,
+    sent_input: bool,
+    elapsed: Duration,
+    result: Result<ToolOutput, FunctionCallError>,
+) -> Result<ToolOutput, FunctionCallError> {
+    match (verbosity, result) {
+        (ResultVerbosity::Normal, result) => result,
+        (
+            ResultVerbosity::Terse,
+            Ok(ToolOutput::Function {
+                content_items: None,
+                success: success @ (Some(true) | None),
+                ..
+            }),
+        ) if sent_input => Ok(ToolOutput::Function {
+            content: TERSE_OK.to_string(),
+            content_items: None,
+            success,
+        }),
+        (ResultVerbosity::Terse, Err(FunctionCallError::RespondToModel(message))) => {
+            let first = message.lines().next().unwrap_or_default().trim_end();
+            Err(FunctionCallError::RespondToModel(first.to_string()))
+        }
+        (ResultVerbosity::Terse, result) => result,
+        (ResultVerbosity::Debug, result) => {
+            let timing = format!("[debug] {tool} took {} ms", elapsed.as_millis());
+            match result {
+                Ok(ToolOutput::Function {
+                    content,
+                    content_items,
+                    success,
+                }) => Ok(ToolOutput::Function {
+                    content: format!("{content}\n{timing}"),
+                    content_items,
+                    success,
+                }),
+                Err(FunctionCallError::RespondToModel(message)) => Err(
+                    FunctionCallError::RespondToModel(format!("{message}\n{timing}")),
+                ),
+                result => result,
+            }
+        }
+    }
+}
+
+/// The debug note for input that went through: what was sent, by which
+/// provider, and the window that had focus afterwards.
+pub(super) fn input_note(
+    config: &ComputerUseConfig,
+    args: &[String],
+    provider: InputProvider,
+    window: Option<&TargetWindow>,
+) -> String {
+    if config.result_verbosity != ResultVerbosity::Debug {
+        return String::new();
+    }
+    let window = match window {
+        Some(window) => format!("window {} (`{}`)", window.id, window.class),
+        None => "no window that could be identified".to_string(),
+    };
+    format!(
+        "\n[debug] sent `{}` via {provider}; focus is on {window}",
+        args.join(" ")
+    )
+}
+
+/// The debug suffix for a failed command: its arguments and exit status.
+pub(super) fn command_failure(
+    config: &ComputerUseConfig,
+    args: &[impl AsRef<str>],
+    status: ExitStatus,
+) -> String {
+    if config.result_verbosity != ResultVerbosity::Debug {
+        return String::new();
+    }
+    let args = args.iter().map(AsRef::as_ref).collect::<Vec<_>>().join(" ");
+    format!("\n[debug] arguments `{args}`, {status}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn output(content: &str) -> Result<ToolOutput, FunctionCallError> {
+        Ok(ToolOutput::Function {
+            content: content.to_string(),
+            content_items: None,
+            success: Some(true),
+        })
+    }
+
+    fn content(result: Result<ToolOutput, FunctionCallError>) -> String {
+        match result {
+            Ok(ToolOutput::Function { content, .. }) => content,
+            Err(err) => format!("error: {err}"),
+            Ok(_) => unreachable!("only function output is shaped"),
+        }
+    }
+
+    #[test]
+    fn trims_or_extends_results_by_verbosity() {
+        let elapsed = Duration::from_millis(42);
+        let clicked = "clicked at (640, 400)";
+        let failed = || {
+            Err(FunctionCallError::RespondToModel(
+                "command \"xdotool\" failed: Error: no window\nUsage: xdotool ...".to_string(),
+            ))
+        };
+
+        let terse = ResultVerbosity::Terse;
+        assert_eq!(
+            content(shape(
+                terse,
+                "computer_click",
+                true,
+                elapsed,
+                output(clicked)
+            )),
+            "ok"
+        );
+        assert_eq!(
+            content(shape(
+                terse,
+                "computer_find_text",
+                false,
+                elapsed,
+                output("found 1 match")
+            )),
+            "found 1 match"
+        );
+        assert_eq!(
+            content(shape(terse, "computer_click", true, elapsed, failed())),
+            "error: command \"xdotool\" failed: Error: no window"
+        );
+
+        assert!(is_terse_ok(
+            "ok\n\nnotes on working in gimp (from its recipe file):"
+        ));
+        assert!(!is_terse_ok("okay"));
+
+        let normal = ResultVerbosity::Normal;
+        assert_eq!(
+            content(shape(
+                normal,
+                "computer_click",
+                true,
+                elapsed,
+                output(clicked)
+            )),
+            clicked
+        );
+
+        let debug = ResultVerbosity::Debug;
+        assert_eq!(
+            content(shape(
+                debug,
+                "computer_click",
+                true,
+                elapsed,
+                output(clicked)
+            )),
+            "clicked at (640, 400)\n[debug] computer_click took 42 ms"
+        );
+
+        let config = ComputerUseConfig {
+            result_verbosity: debug,
+            ..ComputerUseConfig::default()
+        };
+        let window = TargetWindow {
+            id: "0x2a00005".to_string(),
+            class: "firefox".to_string(),
+        };
+        let args = ["mousemove", "--sync", "640", "400", "click", "1"].map(String::from);
+        assert_eq!(
+            input_note(&config, &args, InputProvider::Xdotool, Some(&window)),
+            "\n[debug] sent `mousemove --sync 640 400 click 1` via xdotool; focus is on window 0x2a00005 (`firefox`)"
+        );
+        assert_eq!(
+            input_note(
+                &ComputerUseConfig::default(),
+                &args,
+                InputProvider::Xdotool,
+                Some(&window)
+            ),
+            ""
+        );
+    }
+}
@@ -0,0 +1,245 @@
+//! Reads text back after `computer_type` to catch dropped or wrong keys.
+//!
+//! xdotool reports success as soon as the events are queued, so keys an app
+//! drops under load, or characters a mismatched keyboard layout turns into
+//! others, go unnoticed until a later screenshot. With the clipboard method
+//! the last typed line is selected up to the start of the line, copied and
+//! compared; this is exact but needs a field that allows copying. With the
+//! OCR method the screen is searched for the line instead, which works
+//! anywhere text is drawn but only catches gross differences.
+
+use std::path::Path;
+use std::thread;
+
+use super::browser::CLIPBOARD_SETTLE;
+use super::browser::read_clipboard;
+use super::browser::write_clipboard;
+use super::capture_root_window;
+use super::find_text;
+use super::keys::shortcut_keys;
+use super::require_command;
+use super::run_command;
+use crate::config::types::ComputerUseConfig;
+use crate::config::types::TargetOs;
+use crate::config::types::TypingVerification;
+use crate::function_tool::FunctionCallError;
+
+/// Minimum OCR similarity for the typed line to count as present.
+const OCR_MATCH: f64 = 0.9;
+
+/// Minimum OCR similarity for a run of words to be reported as what was
+/// typed instead.
+const OCR_CANDIDATE: f64 = 0.5;
+
+/// Longest read-back text quoted in a mismatch report.
+const MAX_OBSERVED_CHARS: usize = 200;
+
+/// How the read-back text differs from what was typed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Mismatch {
+    /// The field holds the typed text with `missing` characters left out.
+    Dropped { missing: usize },
+    /// The field holds as many characters as were typed, `differing` of them
+    /// different, which usually means a keyboard layout mismatch.
+    Substituted { differing: usize },
+    /// The field holds something else.
+    Different,
+}
+
+/// Result of reading typed text back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Verification {
+    Matched,
+    /// The text could not be checked, for the given reason.
+    Skipped(&'static str),
+    Mismatched {
+        observed: String,
+        mismatch: Mismatch,
+    },
+}
+
+/// Checks that `text` arrived in the focused field using `method`.
+pub(super) fn verify_typed(
+    xdotool: &Path,
+    config: &ComputerUseConfig,
+    method: TypingVerification,
+    text: &str,
+) -> Result<Verification, FunctionCallError> {
+    let expected = text.rsplit('\n').next().unwrap_or_default();
+    if expected.trim().is_empty() {
+        return Ok(Verification::Skipped(
+            "the text ends with a line break, so the typed line cannot be read back",
+        ));
+    }
+    match method {
+        TypingVerification::Off => Ok(Verification::Matched),
+        TypingVerification::Clipboard => verify_with_clipboard(xdotool, config, expected),
+        TypingVerification::Ocr => verify_with_ocr(config, expected),
+    }
+}
+
+/// Tool error for a mismatch found after typing `expected`.
+pub(super) fn mismatch_message(expected: &str, observed: &str, mismatch: Mismatch) -> String {
+    let expected_chars = expected
+        .rsplit('\n')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .count();
+    let what = match mismatch {
+        Mismatch::Dropped { missing } => format!(
+            "{missing} of {expected_chars} characters were dropped (`{observed}`); clear the field with `computer_clear_field` and type again with a larger `delay_ms`"
+        ),
+        Mismatch::Substituted { differing } => format!(
+            "{differing} of {expected_chars} characters came out different (`{observed}`), which usually means the keyboard layout differs from the one xdotool assumes; clear the field and paste the text instead"
+        ),
+        Mismatch::Different if observed.is_empty() => {
+            "the typed text could not be found; take a screenshot to check where the input went"
+                .to_string()
+        }
+        Mismatch::Different => format!(
+            "the field shows `{observed}` instead of the typed text; take a screenshot to check where the input went"
+        ),
+    };
+    format!("verification failed after typing: {what}")
+}
+
+fn verify_with_clipboard(
+    xdotool: &Path,
+    config: &ComputerUseConfig,
+    expected: &str,
+) -> Result<Verification, FunctionCallError> {
+    let xclip = require_command("xclip")?;
+    let previous = read_clipboard(&xclip, config).ok();
+    // Start from an empty clipboard so a field that refuses copying is not
+    // mistaken for one holding the old clipboard contents.
+    let _ = write_clipboard(&xclip, config, "");
+    let os = config.target_os;
+    let keys = [
+        "key".to_string(),
+        "--clearmodifiers".to_string(),
+        format!("shift+{}", shortcut(os, "line_start", "Home")),
+        shortcut(os, "copy", "ctrl+c"),
+        shortcut(os, "line_end", "End"),
+    ];
+    let copied = run_command(xdotool, &keys, config).and_then(|()| {
+        thread::sleep(CLIPBOARD_SETTLE);
+        read_clipboard(&xclip, config)
+    });
+    if let Some(previous) = previous {
+        let _ = write_clipboard(&xclip, config, &previous);
+    }
+    let copied = copied?;
+    let observed = copied.trim_end_matches(['\r', '\n']);
+    if observed.is_empty() {
+        return Ok(Verification::Skipped(
+            "nothing could be copied from the field (it may be a password field)",
+        ));
+    }
+    Ok(compare(expected, observed))
+}
+
+fn verify_with_ocr(
+    config: &ComputerUseConfig,
+    expected: &str,
+) -> Result<Verification, FunctionCallError> {
+    let image_path = capture_root_window(config, None, None)?;
+    let tsv = find_text::recognize(&image_path);
+    let _ = std::fs::remove_file(&image_path);
+    let tsv = tsv?;
+    if !find_text::find_matches(&tsv, expected, OCR_MATCH).is_empty() {
+        return Ok(Verification::Matched);
+    }
+    let observed = find_text::find_matches(&tsv, expected, OCR_CANDIDATE)
+        .into_iter()
+        .next()
+        .map(|hit| hit.text)
+        .unwrap_or_default();
+    let mismatch = if observed.is_empty() {
+        Mismatch::Different
+    } else {
+        classify(expected, &observed)
+    };
+    Ok(Verification::Mismatched { observed, mismatch })
+}
+
+/// Compares the typed line with the field's current line, which may start
+/// with text that was there before typing.
+fn compare(expected: &str, observed: &str) -> Verification {
+    if observed.ends_with(expected) {
+        return Verification::Matched;
+    }
+    let chars: Vec<char> = observed.chars().collect();
+    let observed: String = chars[chars.len().saturating_sub(MAX_OBSERVED_CHARS)..]
+        .iter()
+        .collect();
+    Verification::Mismatched {
+        mismatch: classify(expected, &observed),
+        observed,
+    }
+}
+
+/// Works out how `observed` differs from `expected`, looking only at the end
+/// of `observed` where the typed text landed.
+pub(super) fn classify(expected: &str, observed: &str) -> Mismatch {
+    let expected: Vec<char> = expected.chars().collect();
+    let observed: Vec<char> = observed.chars().collect();
+    if observed.len() >= expected.len() {
+        let tail = &observed[observed.len() - expected.len()..];
+        let differing = tail
+            .iter()
+            .zip(&expected)
+            .filter(|(seen, typed)| seen != typed)
+            .count();
+        if differing * 2 <= expected.len() {
+            return Mismatch::Substituted { differing };
+        }
+    }
+    // The longest tail of the field that is the typed text with some
+    // characters missing.
+    let kept = (1..=observed.len().min(expected.len()))
+        .rev()
+        .find(|&len| is_subsequence(&observed[observed.len() - len..], &expected));
+    match kept {
+        Some(kept) if kept < expected.len() && kept * 2 >= expected.len() => Mismatch::Dropped {
+            missing: expected.len() - kept,
+        },
+        _ => Mismatch::Different,
+    }
+}
+
+fn is_subsequence(needle: &[char], haystack: &[char]) -> bool {
+    let mut haystack = haystack.iter();
+    needle.iter().all(|c| haystack.any(|h| h == c))
+}
+
+fn shortcut(os: TargetOs, name: &str, fallback: &str) -> String {
+    shortcut_keys(name, os).map_or_else(|| fallback.to_string(), |keys| keys.join("+"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn classifies_dropped_and_substituted_characters() {
+        assert_eq!(compare("hello", "Name: hello"), Verification::Matched);
+        assert_eq!(
+            classify("hello world", "Name: helo wrld"),
+            Mismatch::Dropped { missing: 2 }
+        );
+        assert_eq!(
+            classify("qwerty", "azerty"),
+            Mismatch::Substituted { differing: 2 }
+        );
+        assert_eq!(classify("hello", "xyz"), Mismatch::Different);
+        assert_eq!(
+            compare("abc", "abd"),
+            Verification::Mismatched {
+                observed: "abd".to_string(),
+                mismatch: Mismatch::Substituted { differing: 1 },
+            }
+        );
+    }
+}
@@ -0,0 +1,250 @@
+//! A 1280x720 virtual output for `computer_use.virtual_output`.
+//!
+//! The output is added with `xrandr` on a free connector (a `VIRTUAL*`
+//! head of the intel/modesetting drivers, or an `evdi` connector), placed
+//! right of the existing monitors, and the configured app is moved onto it.
+//! The caller then sets `computer_use.viewport` to its geometry, so
+//! screenshots and input stay on the output and, with the default 1280x720
+//! virtual space, model coordinates are its pixels 1:1. The output is turned
+//! off again when the [`VirtualOutput`] is dropped.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use which::which;
+
+use super::gui_command;
+use crate::config::types::ComputerUseConfig;
+use crate::config::types::Viewport;
+
+const MODE_NAME: &str = "computex-1280x720";
+
+/// CEA-861 1280x720@60 timings, which every driver accepts.
+const MODELINE: &[&str] = &[
+    "74.25", "1280", "1390", "1430", "1650", "720", "725", "730", "750", "+hsync", "+vsync",
+];
+
+/// An output from `xrandr --query`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Output {
+    pub name: String,
+    pub connected: bool,
+    /// Current size and position, when the output is on.
+    pub geometry: Option<Viewport>,
+}
+
+/// The virtual output, turned off again on drop.
+#[derive(Debug)]
+pub struct VirtualOutput {
+    config: ComputerUseConfig,
+    xrandr: PathBuf,
+    output: String,
+    viewport: Viewport,
+}
+
+impl VirtualOutput {
+    /// Adds the output and moves `computer_use.virtual_output_app` onto it.
+    pub fn create(config: &ComputerUseConfig) -> anyhow::Result<Self> {
+        let xrandr = which("xrandr").context("creating a virtual output needs xrandr")?;
+        let outputs = query_outputs(&xrandr, config)?;
+        let output = pick_output(&outputs).context(
+            "no free xrandr output for a virtual display; load the evdi module or enable VirtualHeads in the intel or modesetting driver",
+        )?;
+        let viewport = Viewport {
+            width: 1280,
+            height: 720,
+            x: right_edge(&outputs),
+            y: 0,
+        };
+
+        // The mode may be left over from an earlier run; adding it fails then.
+        let _ = gui_command(&xrandr, config)
+            .args(["--newmode", MODE_NAME])
+            .args(MODELINE)
+            .output();
+        xrandr_run(&xrandr, config, &["--addmode", &output, MODE_NAME])?;
+        let position = format!("{}x{}", viewport.x, viewport.y);
+        let created = Self {
+            config: config.clone(),
+            xrandr,
+            output,
+            viewport,
+        };
+        xrandr_run(
+            &created.xrandr,
+            config,
+            &[
+                "--output",
+                &created.output,
+                "--mode",
+                MODE_NAME,
+                "--pos",
+                &position,
+            ],
+        )?;
+        if let Some(app) = &config.virtual_output_app {
+            created.move_app(app)?;
+        }
+        Ok(created)
+    }
+
+    /// Geometry to use as `computer_use.viewport`.
+    pub fn viewport(&self) -> Viewport {
+        self.viewport
+    }
+
+    /// xrandr name of the output.
+    pub fn name(&self) -> &str {
+        &self.output
+    }
+
+    /// Moves and resizes every visible window of class `app` to fill the output.
+    fn move_app(&self, app: &str) -> anyhow::Result<()> {
+        let xdotool = which("xdotool").context("moving the app needs xdotool")?;
+        let search = gui_command(&xdotool, &self.config)
+            .args(["search", "--onlyvisible", "--class", app])
+            .output()
+            .context("failed to run xdotool search")?;
+        let windows = String::from_utf8_lossy(&search.stdout);
+        let windows: Vec<&str> = windows.split_whitespace().collect();
+        if windows.is_empty() {
+            anyhow::bail!(
+                "no visible `{app}` window to move onto the virtual output; start it first"
+            );
+        }
+        let (x, y) = (self.viewport.x.to_string(), self.viewport.y.to_string());
+        let (width, height) = (
+            self.viewport.width.to_string(),
+            self.viewport.height.to_string(),
+        );
+        for window in windows {
+            let status = gui_command(&xdotool, &self.config)
+                .args(["windowmove", window, &x, &y])
+                .args(["windowsize", window, &width, &height])
+                .status()
+                .context("failed to run xdotool windowmove")?;
+            if !status.success() {
+                anyhow::bail!("xdotool failed to move `{app}` window {window}");
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for VirtualOutput {
+    fn drop(&mut self) {
+        let steps: [&[&str]; 3] = [
+            &["--output", &self.output, "--off"],
+            &["--delmode", &self.output, MODE_NAME],
+            &["--rmmode", MODE_NAME],
+        ];
+        for args in steps {
+            if let Err(err) = xrandr_run(&self.xrandr, &self.config, args) {
+                tracing::warn!("failed to remove the virtual output: {err:#}");
+            }
+        }
+    }
+}
+
+fn xrandr_run(xrandr: &Path, config: &ComputerUseConfig, args: &[&str]) -> anyhow::Result<()> {
+    let output = gui_command(xrandr, config)
+        .args(args)
+        .output()
+        .context("failed to run xrandr")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("xrandr {} failed: {}", args.join(" "), stderr.trim());
+    }
+    Ok(())
+}
+
+pub(super) fn query_outputs(
+    xrandr: &Path,
+    config: &ComputerUseConfig,
+) -> anyhow::Result<Vec<Output>> {
+    let output = gui_command(xrandr, config)
+        .arg("--query")
+        .output()
+        .context("failed to run xrandr --query")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("xrandr --query failed: {}", stderr.trim());
+    }
+    Ok(parse_outputs(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Reads the output lines of `xrandr --query`, e.g.
+/// `eDP-1 connected primary 1920x1080+0+0 (normal left inverted ...)`.
+fn parse_outputs(query: &str) -> Vec<Output> {
+    query
+        .lines()
+        .filter(|line| !line.starts_with(char::is_whitespace) && !line.starts_with("Screen "))
+        .filter_map(|line| {
+            let mut words = line.split_whitespace();
+            let name = words.next()?.to_string();
+            let connected = match words.next()? {
+                "connected" => true,
+                "disconnected" => false,
+                _ => return None,
+            };
+            let geometry = words
+                .take_while(|word| !word.starts_with('('))
+                .find_map(|word| word.parse().ok());
+            Some(Output {
+                name,
+                connected,
+                geometry,
+            })
+        })
+        .collect()
+}
+
+/// A connector that is free to drive, preferring the drivers' virtual heads.
+fn pick_output(outputs: &[Output]) -> Option<String> {
+    let free = || {
+        outputs
+            .iter()
+            .filter(|output| !output.connected && output.geometry.is_none())
+    };
+    free()
+        .find(|output| output.name.to_ascii_uppercase().starts_with("VIRTUAL"))
+        .or_else(|| free().next())
+        .map(|output| output.name.clone())
+}
+
+/// X position just right of every active output.
+fn right_edge(outputs: &[Output]) -> u32 {
+    outputs
+        .iter()
+        .filter_map(|output| output.geometry)
+        .map(|geometry| geometry.x + geometry.width)
+        .max()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn picks_a_free_virtual_head_right_of_the_monitors() {
+        let query = "\
+Screen 0: minimum 8 x 8, current 3840 x 1080, maximum 32767 x 32767
+eDP-1 connected primary 1920x1080+0+0 (normal left inverted right x axis y axis) 344mm x 194mm
+   1920x1080     60.01*+
+HDMI-1 connected 1920x1080+1920+0 (normal left inverted right x axis y axis) 527mm x 296mm
+DP-1 disconnected (normal left inverted right x axis y axis)
+VIRTUAL1 disconnected (normal left inverted right x axis y axis)
+";
+        let outputs = parse_outputs(query);
+        assert_eq!(outputs.len(), 4);
+        assert_eq!(
+            outputs[1].geometry,
+            Some("1920x1080+1920+0".parse().unwrap())
+        );
+        assert_eq!(pick_output(&outputs), Some("VIRTUAL1".to_string()));
+        assert_eq!(right_edge(&outputs), 3840);
+    }
+}
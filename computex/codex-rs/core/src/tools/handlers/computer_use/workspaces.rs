@@ -0,0 +1,387 @@
+//! Virtual desktops for the `computer_workspace_*` tools.
+//!
+//! Window managers that follow EWMH (GNOME, KDE, Xfce, i3 and most others)
+//! publish their workspaces and each client's workspace as root and window
+//! properties, and take switch and move requests as client messages to the
+//! root window, which is what `wmctrl` does. Workspaces are numbered from 1
+//! for the model, as desktop environments show them.
+
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use x11rb::connection::Connection;
+use x11rb::protocol::xproto;
+use x11rb::protocol::xproto::AtomEnum;
+use x11rb::protocol::xproto::ClientMessageEvent;
+use x11rb::protocol::xproto::ConnectionExt as _;
+use x11rb::protocol::xproto::EventMask;
+use x11rb::rust_connection::RustConnection;
+
+use super::focus_guard::matches_window;
+use crate::config::types::ComputerUseConfig;
+use crate::function_tool::FunctionCallError;
+
+/// `_NET_WM_DESKTOP` of windows shown on every workspace.
+const ALL_WORKSPACES: u32 = u32::MAX;
+
+/// How long to wait for the window manager to carry out a request.
+const SETTLE: Duration = Duration::from_millis(500);
+
+/// A managed window and the workspace it is on.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct ClientWindow {
+    pub id: u32,
+    pub class: String,
+    pub title: String,
+    /// 0-based workspace, or `None` when the window is on all of them.
+    pub workspace: Option<u32>,
+}
+
+/// The workspaces and the windows on them.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Workspaces {
+    pub count: u32,
+    /// One name per workspace; empty when the window manager names none.
+    pub names: Vec<String>,
+    pub current: u32,
+    pub windows: Vec<ClientWindow>,
+}
+
+/// A display connection for EWMH queries and requests.
+pub(super) struct Ewmh {
+    conn: RustConnection,
+    root: xproto::Window,
+}
+
+impl Ewmh {
+    pub(super) fn connect(config: &ComputerUseConfig) -> Result<Self, FunctionCallError> {
+        let (conn, screen) = x11rb::connect(config.display.as_deref())
+            .map_err(|err| respond(format!("cannot open X display: {err}")))?;
+        let root = conn
+            .setup()
+            .roots
+            .get(screen)
+            .map(|screen| screen.root)
+            .ok_or_else(|| respond(format!("X display has no screen {screen}")))?;
+        Ok(Self { conn, root })
+    }
+
+    /// Every workspace and managed window.
+    pub(super) fn list(&self) -> Result<Workspaces, FunctionCallError> {
+        let count = self
+            .cardinal(self.root, "_NET_NUMBER_OF_DESKTOPS")?
+            .ok_or_else(|| {
+                respond("the window manager doesn't publish workspaces (no EWMH support)")
+            })?;
+        let current = self
+            .cardinal(self.root, "_NET_CURRENT_DESKTOP")?
+            .unwrap_or(0);
+        let names = self
+            .utf8(self.root, "_NET_DESKTOP_NAMES")?
+            .map(|names| split_names(&names, count))
+            .unwrap_or_default();
+        let ids = self.cardinals(self.root, "_NET_CLIENT_LIST", AtomEnum::WINDOW)?;
+        // A window can close while it is read; leave it out.
+        let windows = ids
+            .into_iter()
+            .filter_map(|id| self.client(id).ok())
+            .collect();
+        Ok(Workspaces {
+            count,
+            names,
+            current,
+            windows,
+        })
+    }
+
+    fn client(&self, id: xproto::Window) -> Result<ClientWindow, FunctionCallError> {
+        let workspace = self.cardinal(id, "_NET_WM_DESKTOP")?;
+        let title = match self.utf8(id, "_NET_WM_NAME")? {
+            Some(title) => title,
+            None => self.latin1(id, AtomEnum::WM_NAME)?.unwrap_or_default(),
+        };
+        // WM_CLASS is "instance\0class\0"; report the class, like xdotool.
+        let class = self
+            .latin1(id, AtomEnum::WM_CLASS)?
+            .and_then(|class| class.split('\0').nth(1).map(str::to_string))
+            .unwrap_or_default();
+        Ok(ClientWindow {
+            id,
+            class,
+            title,
+            workspace: workspace.filter(|workspace| *workspace != ALL_WORKSPACES),
+        })
+    }
+
+    /// Switches to the 0-based `workspace` and waits for the window manager
+    /// to report it.
+    pub(super) fn switch(&self, workspace: u32) -> Result<bool, FunctionCallError> {
+        self.request(self.root, "_NET_CURRENT_DESKTOP", [workspace, 0, 0, 0, 0])?;
+        self.wait_for(|this| {
+            Ok(this.cardinal(this.root, "_NET_CURRENT_DESKTOP")? == Some(workspace))
+        })
+    }
+
+    /// Moves `window` to the 0-based `workspace` and waits for the window
+    /// manager to report it.
+    pub(super) fn move_window(
+        &self,
+        window: u32,
+        workspace: u32,
+    ) -> Result<bool, FunctionCallError> {
+        // Source indication 2: the request comes from a pager, not the app.
+        self.request(window, "_NET_WM_DESKTOP", [workspace, 2, 0, 0, 0])?;
+        self.wait_for(|this| Ok(this.cardinal(window, "_NET_WM_DESKTOP")? == Some(workspace)))
+    }
+
+    /// The focused window, if the window manager reports one.
+    pub(super) fn active_window(&self) -> Result<Option<u32>, FunctionCallError> {
+        Ok(self
+            .cardinals(self.root, "_NET_ACTIVE_WINDOW", AtomEnum::WINDOW)?
+            .into_iter()
+            .find(|id| *id != 0))
+    }
+
+    fn request(
+        &self,
+        window: xproto::Window,
+        message: &str,
+        data: [u32; 5],
+    ) -> Result<(), FunctionCallError> {
+        let event = ClientMessageEvent::new(32, window, self.atom(message)?, data);
+        self.conn
+            .send_event(
+                false,
+                self.root,
+                EventMask::SUBSTRUCTURE_NOTIFY | EventMask::SUBSTRUCTURE_REDIRECT,
+                event,
+            )
+            .map_err(x11_error)?;
+        self.conn.flush().map_err(x11_error)
+    }
+
+    fn wait_for(
+        &self,
+        done: impl Fn(&Self) -> Result<bool, FunctionCallError>,
+    ) -> Result<bool, FunctionCallError> {
+        let deadline = Instant::now() + SETTLE;
+        loop {
+            if done(self)? {
+                return Ok(true);
+            }
+            if Instant::now() >= deadline {
+                return Ok(false);
+            }
+            thread::sleep(Duration::from_millis(25));
+        }
+    }
+
+    fn atom(&self, name: &str) -> Result<xproto::Atom, FunctionCallError> {
+        Ok(self
+            .conn
+            .intern_atom(false, name.as_bytes())
+            .map_err(x11_error)?
+            .reply()
+            .map_err(x11_error)?
+            .atom)
+    }
+
+    fn cardinal(
+        &self,
+        window: xproto::Window,
+        name: &str,
+    ) -> Result<Option<u32>, FunctionCallError> {
+        Ok(self
+            .cardinals(window, name, AtomEnum::CARDINAL)?
+            .into_iter()
+            .next())
+    }
+
+    fn cardinals(
+        &self,
+        window: xproto::Window,
+        name: &str,
+        kind: AtomEnum,
+    ) -> Result<Vec<u32>, FunctionCallError> {
+        let atom = self.atom(name)?;
+        let reply = self
+            .conn
+            .get_property(false, window, atom, kind, 0, u32::MAX / 4)
+            .map_err(x11_error)?
+            .reply()
+            .map_err(x11_error)?;
+        Ok(reply.value32().map(Iterator::collect).unwrap_or_default())
+    }
+
+    fn utf8(
+        &self,
+        window: xproto::Window,
+        name: &str,
+    ) -> Result<Option<String>, FunctionCallError> {
+        let atom = self.atom(name)?;
+        let utf8_string = self.atom("UTF8_STRING")?;
+        self.string(window, atom, utf8_string)
+    }
+
+    fn latin1(
+        &self,
+        window: xproto::Window,
+        property: AtomEnum,
+    ) -> Result<Option<String>, FunctionCallError> {
+        self.string(window, property.into(), AtomEnum::STRING.into())
+    }
+
+    fn string(
+        &self,
+        window: xproto::Window,
+        property: xproto::Atom,
+        kind: xproto::Atom,
+    ) -> Result<Option<String>, FunctionCallError> {
+        let reply = self
+            .conn
+            .get_property(false, window, property, kind, 0, 4096)
+            .map_err(x11_error)?
+            .reply()
+            .map_err(x11_error)?;
+        if reply.type_ == u32::from(AtomEnum::NONE) {
+            return Ok(None);
+        }
+        Ok(Some(String::from_utf8_lossy(&reply.value).into_owned()))
+    }
+}
+
+/// The window whose class is `expected`, or else whose title contains it,
+/// on any workspace.
+pub(super) fn find<'a>(windows: &'a [ClientWindow], expected: &str) -> Option<&'a ClientWindow> {
+    windows
+        .iter()
+        .find(|window| window.class.eq_ignore_ascii_case(expected))
+        .or_else(|| {
+            windows
+                .iter()
+                .find(|window| matches_window(expected, &window.class, &window.title))
+        })
+}
+
+/// The 0-based index of workspace `number`, as the model counts them.
+pub(super) fn index(number: u32, workspaces: &Workspaces) -> Result<u32, FunctionCallError> {
+    if number == 0 || number > workspaces.count {
+        return Err(respond(format!(
+            "there is no workspace {number}; workspaces are numbered 1 to {}",
+            workspaces.count
+        )));
+    }
+    Ok(number - 1)
+}
+
+/// Lines listing each workspace and its windows, for the model.
+pub(super) fn describe(workspaces: &Workspaces) -> Vec<String> {
+    let count = workspaces.count;
+    let mut lines = vec![format!(
+        "workspaces: {count}, numbered from 1; the current one is {}",
+        workspaces.current + 1
+    )];
+    let window_line = |window: &ClientWindow| format!("  - {}: \"{}\"", window.class, window.title);
+    for index in 0..count {
+        let mut heading = format!("workspace {}", index + 1);
+        if let Some(name) = workspaces
+            .names
+            .get(index as usize)
+            .filter(|name| !name.is_empty())
+        {
+            heading.push_str(&format!(" \"{name}\""));
+        }
+        if index == workspaces.current {
+            heading.push_str(" (current)");
+        }
+        lines.push(heading);
+        let windows: Vec<String> = workspaces
+            .windows
+            .iter()
+            .filter(|window| window.workspace == Some(index))
+            .map(window_line)
+            .collect();
+        if windows.is_empty() {
+            lines.push("  (no windows)".to_string());
+        }
+        lines.extend(windows);
+    }
+    let sticky: Vec<String> = workspaces
+        .windows
+        .iter()
+        .filter(|window| window.workspace.is_none())
+        .map(window_line)
+        .collect();
+    if !sticky.is_empty() {
+        lines.push("on every workspace".to_string());
+        lines.extend(sticky);
+    }
+    lines
+}
+
+/// `_NET_DESKTOP_NAMES` is a list of NUL-terminated names, possibly fewer
+/// than there are workspaces.
+fn split_names(names: &str, count: u32) -> Vec<String> {
+    names
+        .split('\0')
+        .take(count as usize)
+        .map(str::to_string)
+        .collect()
+}
+
+fn respond(message: impl Into<String>) -> FunctionCallError {
+    FunctionCallError::RespondToModel(message.into())
+}
+
+fn x11_error(err: impl std::fmt::Display) -> FunctionCallError {
+    respond(format!("X request failed: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn window(class: &str, title: &str, workspace: Option<u32>) -> ClientWindow {
+        ClientWindow {
+            id: 1,
+            class: class.to_string(),
+            title: title.to_string(),
+            workspace,
+        }
+    }
+
+    #[test]
+    fn lists_windows_per_workspace() {
+        let workspaces = Workspaces {
+            count: 3,
+            names: split_names("Main\0Mail\0", 3),
+            current: 0,
+            windows: vec![
+                window("firefox", "GitHub — Mozilla Firefox", Some(0)),
+                window("thunderbird", "Inbox", Some(1)),
+                window("Plank", "Plank", None),
+            ],
+        };
+        assert_eq!(
+            describe(&workspaces),
+            vec![
+                "workspaces: 3, numbered from 1; the current one is 1",
+                "workspace 1 \"Main\" (current)",
+                "  - firefox: \"GitHub — Mozilla Firefox\"",
+                "workspace 2 \"Mail\"",
+                "  - thunderbird: \"Inbox\"",
+                "workspace 3",
+                "  (no windows)",
+                "on every workspace",
+                "  - Plank: \"Plank\"",
+            ]
+        );
+        assert_eq!(
+            find(&workspaces.windows, "inbox").map(|window| window.workspace),
+            Some(Some(1))
+        );
+        assert_eq!(find(&workspaces.windows, "slack"), None);
+    }
+}
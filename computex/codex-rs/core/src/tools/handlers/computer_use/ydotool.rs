@@ -0,0 +1,237 @@
+//! Replaying xdotool input through `ydotool` for the `ydotool` input
+//! provider.
+//!
+//! ydotool writes to `/dev/uinput` through its `ydotoold` daemon, so it
+//! works wherever the kernel accepts virtual devices, even where XTEST is
+//! missing or blocked. It speaks Linux key codes instead of X keysyms and
+//! takes one command per run, so the handler's xdotool argument lists are
+//! parsed like the native helper does and each step becomes one ydotool
+//! run. Key names map to their keys on a US layout; anything else, and
+//! xdotool's window commands, stays with the other providers.
+
+use std::path::Path;
+use std::thread;
+
+use super::input_helper::InputStep;
+use super::input_helper::parse_steps;
+use super::run_command;
+use crate::config::types::ComputerUseConfig;
+use crate::function_tool::FunctionCallError;
+
+/// One ydotool run, or a pause between runs.
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Run(Vec<String>),
+    Pause(std::time::Duration),
+}
+
+/// Sends `args`, an xdotool argument list, with ydotool. `None` when it has
+/// no ydotool equivalent.
+pub(super) fn send(
+    ydotool: &Path,
+    args: &[String],
+    config: &ComputerUseConfig,
+) -> Option<Result<(), FunctionCallError>> {
+    let steps = translate(args)?;
+    let result = steps.into_iter().try_for_each(|step| match step {
+        Step::Run(args) => run_command(ydotool, &args, config),
+        Step::Pause(pause) => {
+            thread::sleep(pause);
+            Ok(())
+        }
+    });
+    Some(result)
+}
+
+fn translate(args: &[String]) -> Option<Vec<Step>> {
+    if let Some(text) = typed_text(args) {
+        return Some(vec![Step::Run(vec![
+            "type".to_string(),
+            "--".to_string(),
+            text.to_string(),
+        ])]);
+    }
+    let mut steps = Vec::new();
+    for step in parse_steps(args)? {
+        match step {
+            InputStep::MoveTo { x, y } => steps.push(Step::Run(
+                [
+                    "mousemove",
+                    "--absolute",
+                    "-x",
+                    &x.to_string(),
+                    "-y",
+                    &y.to_string(),
+                ]
+                .map(str::to_string)
+                .to_vec(),
+            )),
+            InputStep::Button { button, press } => steps.extend(button_step(button, press)?),
+            InputStep::Key { name, press } => steps.push(Step::Run(vec![
+                "key".to_string(),
+                format!("{}:{}", key_code(&name)?, u8::from(press)),
+            ])),
+            InputStep::Pause(pause) => steps.push(Step::Pause(pause)),
+        }
+    }
+    Some(steps)
+}
+
+/// The text of `type [--delay N] -- TEXT`.
+fn typed_text(args: &[String]) -> Option<&str> {
+    let mut args = args.iter().map(String::as_str);
+    if args.next()? != "type" {
+        return None;
+    }
+    let mut next = args.next()?;
+    if next == "--delay" {
+        args.next()?;
+        next = args.next()?;
+    }
+    let text = if next == "--" { args.next()? } else { next };
+    args.next().is_none().then_some(text)
+}
+
+/// A button press or release: `None` for buttons ydotool can't press, and
+/// `Some(None)` for the release of a wheel button, which scrolls once on the
+/// press.
+fn button_step(button: u8, press: bool) -> Option<Option<Step>> {
+    let wheel = |x: i8, y: i8| {
+        Step::Run(
+            [
+                "mousemove",
+                "--wheel",
+                "-x",
+                &x.to_string(),
+                "-y",
+                &y.to_string(),
+            ]
+            .map(str::to_string)
+            .to_vec(),
+        )
+    };
+    let code: u8 = match button {
+        1 => 0x00,
+        2 => 0x02,
+        3 => 0x01,
+        4..=7 if !press => return Some(None),
+        4 => return Some(Some(wheel(0, 1))),
+        5 => return Some(Some(wheel(0, -1))),
+        6 => return Some(Some(wheel(-1, 0))),
+        7 => return Some(Some(wheel(1, 0))),
+        _ => return None,
+    };
+    let action = if press { 0x40 } else { 0x80 };
+    Some(Some(Step::Run(vec![
+        "click".to_string(),
+        format!("{:#04x}", code | action),
+    ])))
+}
+
+/// The Linux key code of `name` on a US layout.
+fn key_code(name: &str) -> Option<u16> {
+    let code = match name.to_ascii_lowercase().as_str() {
+        "ctrl" | "control" | "control_l" => 29,
+        "shift" | "shift_l" => 42,
+        "alt" | "alt_l" => 56,
+        "super" | "super_l" | "meta" => 125,
+        "return" => 28,
+        "escape" => 1,
+        "backspace" => 14,
+        "tab" => 15,
+        "space" => 57,
+        "delete" => 111,
+        "insert" => 110,
+        "home" => 102,
+        "end" => 107,
+        "page_up" | "prior" => 104,
+        "page_down" | "next" => 109,
+        "left" => 105,
+        "right" => 106,
+        "up" => 103,
+        "down" => 108,
+        "f11" => 87,
+        "f12" => 88,
+        lower => {
+            if let Some(number) = lower.strip_prefix('f')
+                && let Ok(number @ 1..=10) = number.parse::<u16>()
+            {
+                return Some(58 + number);
+            }
+            let mut chars = lower.chars();
+            return match (chars.next(), chars.next()) {
+                (Some(c), None) => char_code(c),
+                _ => None,
+            };
+        }
+    };
+    Some(code)
+}
+
+fn char_code(c: char) -> Option<u16> {
+    const ROWS: [(&str, u16); 4] = [
+        ("1234567890-=", 2),
+        ("qwertyuiop[]", 16),
+        ("asdfghjkl;'`", 30),
+        ("\\zxcvbnm,./", 43),
+    ];
+    ROWS.iter().find_map(|(row, first)| {
+        row.chars()
+            .position(|key| key == c)
+            .and_then(|index| u16::try_from(index).ok())
+            .map(|index| first + index)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn args(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| (*word).to_string()).collect()
+    }
+
+    #[test]
+    fn translates_xdotool_input() {
+        assert_eq!(
+            translate(&args(&["key", "ctrl+c"])),
+            Some(vec![
+                Step::Run(args(&["key", "29:1"])),
+                Step::Run(args(&["key", "46:1"])),
+                Step::Run(args(&["key", "46:0"])),
+                Step::Run(args(&["key", "29:0"])),
+            ])
+        );
+        assert_eq!(
+            translate(&args(&["mousemove", "--sync", "10", "20", "click", "3"])),
+            Some(vec![
+                Step::Run(args(&["mousemove", "--absolute", "-x", "10", "-y", "20"])),
+                Step::Run(args(&["click", "0x41"])),
+                Step::Run(args(&["click", "0x81"])),
+            ])
+        );
+        assert_eq!(
+            translate(&args(&["click", "5"])),
+            Some(vec![Step::Run(args(&[
+                "mousemove",
+                "--wheel",
+                "-x",
+                "0",
+                "-y",
+                "-1"
+            ]))])
+        );
+        assert_eq!(
+            translate(&args(&["type", "--delay", "40", "--", "hi there"])),
+            Some(vec![Step::Run(args(&["type", "--", "hi there"]))])
+        );
+        assert_eq!(
+            translate(&args(&["key", "F5"])).map(|steps| steps.len()),
+            Some(2)
+        );
+        assert_eq!(char_code('/'), Some(53));
+        assert_eq!(translate(&args(&["key", "XF86AudioMute"])), None);
+        assert_eq!(translate(&args(&["windowactivate", "42"])), None);
+    }
+}
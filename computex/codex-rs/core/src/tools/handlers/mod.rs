@@ -1,5 +1,5 @@
 pub mod apply_patch;
-mod computer_use;
+pub mod computer_use;
 mod grep_files;
 mod list_dir;
 mod mcp;
@@ -14,6 +14,7 @@ mod view_image;
 pub use plan::PLAN_TOOL;
 
 pub use apply_patch::ApplyPatchHandler;
+pub use computer_use::ComputerDelegateHandler;
 pub use computer_use::ComputerUseHandler;
 pub use grep_files::GrepFilesHandler;
 pub use list_dir::ListDirHandler;
@@ -22,6 +22,7 @@ use crate::tools::registry::ToolKind;
 use crate::tools::runtimes::shell::ShellRequest;
 use crate::tools::runtimes::shell::ShellRuntime;
 use crate::tools::sandboxing::ToolCtx;
+use crate::tools::sandboxing::remote_exec_approval_requirement;
 
 pub struct ShellHandler;
 
@@ -262,6 +263,16 @@ impl ShellHandler {
                 exec_params.sandbox_permissions,
             )
             .await;
+        let remote = turn.tools_config.computer_use.target.clone();
+        let exec_approval_requirement = if remote.is_some() {
+            remote_exec_approval_requirement(
+                exec_approval_requirement,
+                turn.approval_policy,
+                &turn.sandbox_policy,
+            )
+        } else {
+            exec_approval_requirement
+        };
 
         let req = ShellRequest {
             command: exec_params.command.clone(),
@@ -271,6 +282,7 @@ impl ShellHandler {
             sandbox_permissions: exec_params.sandbox_permissions,
             justification: exec_params.justification.clone(),
             exec_approval_requirement,
+            remote,
         };
         let mut orchestrator = ToolOrchestrator::new();
         let mut runtime = ShellRuntime::new();
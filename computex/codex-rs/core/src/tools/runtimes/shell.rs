@@ -4,12 +4,14 @@ Runtime: shell
 Executes shell requests under the orchestrator: asks for approval when needed,
 builds a CommandSpec, and runs it under the current SandboxAttempt.
 */
+use crate::config::types::RemoteTarget;
 use crate::exec::ExecToolCallOutput;
 use crate::features::Feature;
 use crate::powershell::prefix_powershell_script_with_utf8;
 use crate::sandboxing::SandboxPermissions;
 use crate::sandboxing::execute_env;
 use crate::shell::ShellType;
+use crate::tools::handlers::computer_use::remote::remote_command;
 use crate::tools::runtimes::build_command_spec;
 use crate::tools::runtimes::maybe_wrap_shell_lc_with_snapshot;
 use crate::tools::sandboxing::Approvable;
@@ -36,6 +38,9 @@ pub struct ShellRequest {
     pub sandbox_permissions: SandboxPermissions,
     pub justification: Option<String>,
     pub exec_approval_requirement: ExecApprovalRequirement,
+    /// The machine the computer use tools drive, where the command runs
+    /// instead.
+    pub remote: Option<RemoteTarget>,
 }
 
 #[derive(Default)]
@@ -121,7 +126,9 @@ impl Approvable<ShellRequest> for ShellRuntime {
     }
 
     fn sandbox_mode_for_first_attempt(&self, req: &ShellRequest) -> SandboxOverride {
+        // The local sandbox can't confine a command on another machine.
         if req.sandbox_permissions.requires_escalated_permissions()
+            || req.remote.is_some()
             || matches!(
                 req.exec_approval_requirement,
                 ExecApprovalRequirement::Skip {
@@ -154,6 +161,12 @@ impl ToolRuntime<ShellRequest, ExecToolCallOutput> for ShellRuntime {
         } else {
             command
         };
+        // Commands for the computer use target run there, in the remote
+        // user's own shell.
+        let command = match &req.remote {
+            Some(target) => remote_command(target, base_command, &req.cwd, false),
+            None => command,
+        };
 
         let spec = build_command_spec(
             &command,
@@ -4,6 +4,7 @@ Runtime: unified exec
 Handles approval + sandbox orchestration for unified exec requests, delegating to
 the session manager to spawn PTYs once an ExecEnv is prepared.
 */
+use crate::config::types::RemoteTarget;
 use crate::error::CodexErr;
 use crate::error::SandboxErr;
 use crate::exec::ExecExpiration;
@@ -11,6 +12,7 @@ use crate::features::Feature;
 use crate::powershell::prefix_powershell_script_with_utf8;
 use crate::sandboxing::SandboxPermissions;
 use crate::shell::ShellType;
+use crate::tools::handlers::computer_use::remote::remote_command;
 use crate::tools::runtimes::build_command_spec;
 use crate::tools::runtimes::maybe_wrap_shell_lc_with_snapshot;
 use crate::tools::sandboxing::Approvable;
@@ -40,6 +42,9 @@ pub struct UnifiedExecRequest {
     pub sandbox_permissions: SandboxPermissions,
     pub justification: Option<String>,
     pub exec_approval_requirement: ExecApprovalRequirement,
+    /// The machine the computer use tools drive, where the command runs
+    /// instead.
+    pub remote: Option<RemoteTarget>,
 }
 
 #[derive(serde::Serialize, Clone, Debug, Eq, PartialEq, Hash)]
@@ -61,6 +66,7 @@ impl UnifiedExecRequest {
         sandbox_permissions: SandboxPermissions,
         justification: Option<String>,
         exec_approval_requirement: ExecApprovalRequirement,
+        remote: Option<RemoteTarget>,
     ) -> Self {
         Self {
             command,
@@ -69,6 +75,7 @@ impl UnifiedExecRequest {
             sandbox_permissions,
             justification,
             exec_approval_requirement,
+            remote,
         }
     }
 }
@@ -142,7 +149,9 @@ impl Approvable<UnifiedExecRequest> for UnifiedExecRuntime<'_> {
     }
 
     fn sandbox_mode_for_first_attempt(&self, req: &UnifiedExecRequest) -> SandboxOverride {
+        // The local sandbox can't confine a command on another machine.
         if req.sandbox_permissions.requires_escalated_permissions()
+            || req.remote.is_some()
             || matches!(
                 req.exec_approval_requirement,
                 ExecApprovalRequirement::Skip {
@@ -175,6 +184,12 @@ impl<'a> ToolRuntime<UnifiedExecRequest, UnifiedExecSession> for UnifiedExecRunt
         } else {
             command
         };
+        // Commands for the computer use target run there, in the remote
+        // user's own shell.
+        let command = match &req.remote {
+            Some(target) => remote_command(target, base_command, &req.cwd, true),
+            None => command,
+        };
 
         let spec = build_command_spec(
             &command,
@@ -152,6 +152,33 @@ pub(crate) fn default_exec_approval_requirement(
     }
 }
 
+/// The requirement for a command that runs on the computer use target
+/// rather than locally. The local sandbox can't confine it there, so it
+/// needs approval unless the sandbox policy already grants full access.
+pub(crate) fn remote_exec_approval_requirement(
+    requirement: ExecApprovalRequirement,
+    policy: AskForApproval,
+    sandbox_policy: &SandboxPolicy,
+) -> ExecApprovalRequirement {
+    match requirement {
+        ExecApprovalRequirement::Forbidden { .. } => requirement,
+        _ if matches!(sandbox_policy, SandboxPolicy::DangerFullAccess) => requirement,
+        _ if policy == AskForApproval::Never => ExecApprovalRequirement::Forbidden {
+            reason: "commands on the remote computer use target can't be sandboxed and need approval, which this session can't ask for".to_string(),
+        },
+        ExecApprovalRequirement::NeedsApproval { .. } => requirement,
+        ExecApprovalRequirement::Skip {
+            proposed_execpolicy_amendment,
+            ..
+        } => ExecApprovalRequirement::NeedsApproval {
+            reason: Some(
+                "runs on the remote computer use target, outside the sandbox".to_string(),
+            ),
+            proposed_execpolicy_amendment,
+        },
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub(crate) enum SandboxOverride {
     NoOverride,
@@ -289,4 +316,41 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn remote_commands_need_approval_outside_full_access() {
+        let skip = ExecApprovalRequirement::Skip {
+            bypass_sandbox: false,
+            proposed_execpolicy_amendment: None,
+        };
+        assert_eq!(
+            remote_exec_approval_requirement(
+                skip.clone(),
+                AskForApproval::OnRequest,
+                &SandboxPolicy::new_workspace_write_policy(),
+            ),
+            ExecApprovalRequirement::NeedsApproval {
+                reason: Some(
+                    "runs on the remote computer use target, outside the sandbox".to_string()
+                ),
+                proposed_execpolicy_amendment: None,
+            }
+        );
+        assert!(matches!(
+            remote_exec_approval_requirement(
+                skip.clone(),
+                AskForApproval::Never,
+                &SandboxPolicy::ReadOnly,
+            ),
+            ExecApprovalRequirement::Forbidden { .. }
+        ));
+        assert_eq!(
+            remote_exec_approval_requirement(
+                skip.clone(),
+                AskForApproval::OnRequest,
+                &SandboxPolicy::DangerFullAccess,
+            ),
+            skip
+        );
+    }
 }
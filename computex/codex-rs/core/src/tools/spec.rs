@@ -1,11 +1,18 @@
 use crate::client_common::tools::ResponsesApiTool;
 use crate::client_common::tools::ToolSpec;
+use crate::config::types::ComputerUseConfig;
+use crate::config::types::ScreenshotFormat;
 use crate::features::Feature;
 use crate::features::Features;
 use crate::models_manager::model_family::ModelFamily;
 use crate::tools::handlers::PLAN_TOOL;
 use crate::tools::handlers::apply_patch::create_apply_patch_freeform_tool;
 use crate::tools::handlers::apply_patch::create_apply_patch_json_tool;
+use crate::tools::handlers::computer_use;
+use crate::tools::handlers::computer_use::plugins::Plugin;
+use crate::tools::handlers::computer_use::plugins::PluginTool;
+use crate::tools::handlers::computer_use::recipes::Recipe;
+use crate::tools::handlers::computer_use::recipes::RecipeAction;
 use crate::tools::registry::ToolRegistryBuilder;
 use codex_protocol::openai_models::ApplyPatchToolType;
 use codex_protocol::openai_models::ConfigShellToolType;
@@ -23,6 +30,7 @@ pub(crate) struct ToolsConfig {
     pub web_search_request: bool,
     pub include_view_image_tool: bool,
     pub include_computer_use_tools: bool,
+    pub computer_use: ComputerUseConfig,
     pub experimental_supported_tools: Vec<String>,
 }
 
@@ -73,9 +81,15 @@ impl ToolsConfig {
             web_search_request: include_web_search_request,
             include_view_image_tool,
             include_computer_use_tools,
+            computer_use: ComputerUseConfig::default(),
             experimental_supported_tools: model_family.experimental_supported_tools.clone(),
         }
     }
+
+    pub fn with_computer_use(mut self, computer_use: &ComputerUseConfig) -> Self {
+        self.computer_use = computer_use.clone();
+        self
+    }
 }
 
 /// Generic JSON‑Schema subset needed for our tool definitions
@@ -419,205 +433,154 @@ fn create_view_image_tool() -> ToolSpec {
     })
 }
 
-fn create_computer_screenshot_tool() -> ToolSpec {
-    let properties = BTreeMap::new();
-
-    ToolSpec::Function(ResponsesApiTool {
-        name: "computer_screenshot".to_string(),
-        description:
-            "Capture a single on-demand screenshot of the GUI (1280x720 coordinate space)."
-                .to_string(),
-        strict: false,
-        parameters: JsonSchema::Object {
-            properties,
-            required: None,
-            additional_properties: Some(false.into()),
-        },
-    })
-}
-
-fn create_computer_click_tool() -> ToolSpec {
-    let mut properties = BTreeMap::new();
-    properties.insert(
-        "x".to_string(),
-        JsonSchema::Number {
-            description: Some("X coordinate in 1280x720 space.".to_string()),
-        },
-    );
-    properties.insert(
-        "y".to_string(),
-        JsonSchema::Number {
-            description: Some("Y coordinate in 1280x720 space.".to_string()),
-        },
-    );
-    properties.insert(
-        "button".to_string(),
-        JsonSchema::String {
-            description: Some("Mouse button: left (default), right, or middle.".to_string()),
-        },
-    );
-    properties.insert(
-        "double".to_string(),
-        JsonSchema::Boolean {
-            description: Some("Double-click when true.".to_string()),
-        },
-    );
-
-    ToolSpec::Function(ResponsesApiTool {
-        name: "computer_click".to_string(),
-        description: "Move the mouse to a coordinate and click (coordinates are 1280x720)."
-            .to_string(),
-        strict: false,
-        parameters: JsonSchema::Object {
-            properties,
-            required: Some(vec!["x".to_string(), "y".to_string()]),
-            additional_properties: Some(false.into()),
-        },
-    })
-}
-
-fn create_computer_drag_tool() -> ToolSpec {
-    let mut properties = BTreeMap::new();
-    properties.insert(
-        "from_x".to_string(),
-        JsonSchema::Number {
-            description: Some("Start X coordinate in 1280x720 space.".to_string()),
-        },
-    );
-    properties.insert(
-        "from_y".to_string(),
-        JsonSchema::Number {
-            description: Some("Start Y coordinate in 1280x720 space.".to_string()),
-        },
-    );
-    properties.insert(
-        "to_x".to_string(),
-        JsonSchema::Number {
-            description: Some("End X coordinate in 1280x720 space.".to_string()),
-        },
-    );
-    properties.insert(
-        "to_y".to_string(),
-        JsonSchema::Number {
-            description: Some("End Y coordinate in 1280x720 space.".to_string()),
-        },
-    );
-    properties.insert(
-        "button".to_string(),
-        JsonSchema::String {
-            description: Some("Mouse button: left (default), right, or middle.".to_string()),
-        },
-    );
-
-    ToolSpec::Function(ResponsesApiTool {
-        name: "computer_drag".to_string(),
-        description: "Click-and-drag between two coordinates (coordinates are 1280x720)."
-            .to_string(),
-        strict: false,
-        parameters: JsonSchema::Object {
-            properties,
-            required: Some(vec![
-                "from_x".to_string(),
-                "from_y".to_string(),
-                "to_x".to_string(),
-                "to_y".to_string(),
-            ]),
-            additional_properties: Some(false.into()),
-        },
-    })
+/// Description of each computer-use tool. Parameters come from the argument
+/// types in [`computer_use::tool_parameters`].
+fn computer_use_tool_description(name: &str, label: &str) -> Option<String> {
+    let description = match name {
+        "computer_screenshot" => format!(
+            "Capture a single on-demand screenshot of the GUI (coordinates are in {label})."
+        ),
+        "computer_click" => format!(
+            "Move the mouse to a coordinate and click (coordinates are in {label})."
+        ),
+        "computer_drag" => format!(
+            "Click-and-drag between two coordinates (coordinates are in {label})."
+        ),
+        "computer_scroll" => {
+            format!("Scroll the mouse wheel (coordinates are in {label} if provided).")
+        }
+        "computer_with_modifiers" => format!(
+            "Hold modifier keys while performing pointer actions, e.g. Shift+click to extend a selection, Shift+drag for a marquee selection, or Ctrl+drag to copy. The modifiers are always released afterwards, even if an action fails (coordinates are in {label})."
+        ),
+        "computer_type" => {
+            "Type text at the current focus, optionally reading the last line back to catch dropped or substituted characters."
+                .to_string()
+        }
+        "computer_key" => "Press a key or key chord.".to_string(),
+        "computer_swipe" => format!(
+            "Swipe (touch drag) from one point to another, e.g. to scroll a list or dismiss a card (coordinates are in {label})."
+        ),
+        "computer_long_press" => format!(
+            "Press and hold at a coordinate, e.g. to open a context menu on a touch UI (coordinates are in {label})."
+        ),
+        "computer_pinch" => format!(
+            "Pinch to zoom around a point (coordinates are in {label}). Emulated with Ctrl+scroll, which most desktop toolkits treat as zoom."
+        ),
+        "computer_clear_field" => "Empty the focused text field before typing, since clicking a field and typing appends to what is already there. `select_all` (default) selects the contents and deletes them; `backspace` moves to the end and presses Backspace `count` times, for fields where select-all selects the whole page.".to_string(),
+        "computer_focus_next" => "Move keyboard focus to the next control by pressing Tab. Take a screenshot afterwards to see which element is focused.".to_string(),
+        "computer_focus_prev" => "Move keyboard focus to the previous control by pressing Shift+Tab. Take a screenshot afterwards to see which element is focused.".to_string(),
+        "computer_find_text" => format!(
+            "Find visible on-screen text with OCR. Returns each match's center and bounding box in {label} with a confidence score, which is cheaper than reading a full screenshot to locate a labeled element."
+        ),
+        "computer_detect_elements" => format!(
+            "Detect buttons, inputs, icons, and other controls on screen with a local vision model, including ones without readable text. Returns each element's kind, center, and bounding box in {label} with a confidence score. Use it when `computer_find_text` can't find an unlabeled or custom-drawn control."
+        ),
+        "computer_locate" => format!(
+            "Find a control by name, e.g. \"Save button\", trying several strategies in turn: the accessibility tree, OCR, a saved template image, and the element detector. Returns the first match that clears its confidence threshold with its center and bounding box in {label}, which strategy found it, and why earlier ones didn't."
+        ),
+        "computer_compare" => format!(
+            "Compare the screen (or a region, in {label}) with a baseline PNG for UI regression checks. Returns a similarity score and where the changes are, and attaches a diff image with changed pixels in red. The first call for a missing baseline saves the current screen as the baseline."
+        ),
+        "computer_active_window" => format!(
+            "Report the focused window's title, class (WM_CLASS), process ID, bounding box in {label}, and monitor. Much cheaper than a screenshot for checking which app and window you are in."
+        ),
+        "computer_workspace_list" => "List the virtual desktops (workspaces), numbered from 1, with the current one marked and the windows on each. Use it to find a window that isn't on the current workspace.".to_string(),
+        "computer_workspace_switch" => "Switch to another virtual desktop (workspace) by its number from `computer_workspace_list`. Take a screenshot afterwards; the screen shows that workspace's windows.".to_string(),
+        "computer_workspace_move_window" => "Move a window (the focused one by default) to another virtual desktop (workspace), optionally switching there, to bring a window onto the current workspace or keep work organized.".to_string(),
+        "computer_tray_list" => "List the system tray (notification area) icons that apps publish over D-Bus, by id and title, with their menu entries. Use it instead of reading or clicking tiny tray icons.".to_string(),
+        "computer_tray_activate" => "Use a system tray icon by id or title from `computer_tray_list`: activate it (what a left click does), send its secondary action, list or open its context menu, or trigger a `menu_item` such as \"Settings > Network\" directly, without clicking.".to_string(),
+        "computer_list_shortcuts" => "List keyboard shortcuts: the desktop's global ones from the GNOME or KDE settings, and the accelerators shown in the focused app's menus, read from the accessibility tree. Each is given as a `computer_key` chord such as `ctrl+shift+t`; prefer one over clicking when it does what you need. Filter with `query`, e.g. \"save\".".to_string(),
+        "computer_network_state" => "Report the network state from NetworkManager: internet connectivity (full, limited, captive portal, or none), whether the Wi-Fi radio is on, and each active connection with its interface, Wi-Fi network name and signal, and VPN status. Use it to verify network settings changes instead of reading the panel icon.".to_string(),
+        "computer_browser_state" => "Report the focused browser tab's title and URL, or every open tab when a DevTools debugging port is configured. Without DevTools the URL is copied from the address bar, which briefly focuses it.".to_string(),
+        "computer_dom_query" => "Find elements in the page of the browser's active tab by CSS selector, optionally filtered by their text. Returns each match's tag, text, key attributes, and position in the page viewport. Works through the computex browser extension in the user's own browser profile, so it sees logged-in pages.".to_string(),
+        "computer_dom_click" => "Click an element in the page of the browser's active tab by CSS selector (and optional text), scrolling it into view first. More reliable than clicking pixels for links and buttons; use `index` to pick among several matches from `computer_dom_query`.".to_string(),
+        "computer_self_test" => "Check the GUI environment before a long task: takes a screenshot, moves the cursor to a corner and back, and presses Shift, reporting each step's latency and any failures.".to_string(),
+        "computer_landmarks" => "Remember and recall positions of UI elements across sessions. Landmarks are scoped to the focused application and screen resolution.".to_string(),
+        "computer_checkpoint" => "Snapshot the VM before a risky or destructive GUI sequence so the user can roll back with `computex rollback`.".to_string(),
+        "computer_magnifier" => "Turn the desktop screen magnifier on or off, set its zoom factor, or report its state, to test the UI as a low-vision user sees it. Coordinates stay those of the unmagnified screen.".to_string(),
+        "computer_menu_select" => "Choose a menu-bar entry by its labels, e.g. path [\"File\", \"Export\", \"PDF\"], with keyboard access keys (Alt+letter, then a letter per level) checked by OCR at each level. More reliable than clicking through nested menus. Mark an access key that isn't the first letter with an underscore, as in \"E_xport\".".to_string(),
+        "computer_context_menu" => "Right-click at a point, wait for the context menu to render, find the entry labeled `item` with OCR (or the accessibility tree) near the click, and click it, all in one call. Use it instead of separate right-click, screenshot, and click calls.".to_string(),
+        "computer_file_dialog" => "Enter a path in the focused GTK or Qt file chooser instead of clicking through it: action=open focuses the location bar (Ctrl+L) first, action=save types into the name field. Waits for the dialog to close.".to_string(),
+        "computer_wait_download" => "Wait for a browser download to finish and return the saved file's path. Watches the download directory (default ~/Downloads) for a new file matching `pattern` that is no longer a partial .crdownload/.part file and has stopped growing; use it instead of screenshotting the download bar.".to_string(),
+        "computer_wait_app_idle" => "Wait until an app stops working: its process and child processes stay under `threshold` percent CPU (default 5) and near-zero disk I/O for 2 seconds. Target `pid`, a window class via `wm_class`, or by default the focused window's app. A better readiness signal than a still screen for compile dialogs, imports, and installers; returns when the process exits too.".to_string(),
+        "computer_set_target" => "Pin the session to one window (by class or title) until cleared: screenshots are cropped to it, coordinates become relative to its top-left corner, and input tools first check that it has focus. Omit `window` to clear the target.".to_string(),
+        "computer_watch_demo" => "Record the user demonstrating a flow by hand: their clicks, drags, scrolls, typed text, and key chords, plus screenshots taken along the way. Returns the steps in order with the screenshots attached. Recording ends when the user presses Pause, stops for 15 s, or `max_seconds` runs out.".to_string(),
+        "computer_recent_frames" => "Return the last few low-resolution frames of the screen, captured about once a second, oldest first. Use it to catch short-lived states such as toasts, progress spinners, or error flashes that a screenshot taken now would miss.".to_string(),
+        "computer_office" => "Work with LibreOffice documents through its UNO API instead of its dialogs: list open documents (status), open a file (open), select a cell, range, or named range in the focused spreadsheet (goto_cell), or export the focused document to a new PDF (export_pdf). Acts on the user's LibreOffice, starting it if needed; fall back to the GUI tools when it reports the integration is unavailable.".to_string(),
+        "computer_print_dialog_handle" => "Print the focused app's document to a PDF file: selects the PDF destination in the print dialog (found by on-screen text), enters the path in the file chooser it opens, confirms, and waits for the file. Pass open=true to press the print shortcut first.".to_string(),
+        "computer_camera_snapshot" => "Attach one frame from a webcam (a V4L2 device such as /dev/video0), to check what the camera sees independently of an app's preview. Fails while another app holds the device exclusively.".to_string(),
+        "computer_announce" => "Speak a short sentence aloud on the machine, e.g. to warn a person sharing the desktop before you take over the mouse and keyboard. Returns after it has been spoken.".to_string(),
+        "computer_system_control" => "Read or set the audio volume, mute state, or screen brightness: `setting` is volume, mute, or brightness, and `value` is a percentage (or 1 to mute, 0 to unmute). Omit `value` to read the current one. Use it instead of dragging volume or brightness sliders; the previous values come back when the session ends.".to_string(),
+        "computer_set_theme" => "Switch the desktop theme to dark, light, or high contrast, e.g. to check each variant of a screen. The original theme comes back when the session ends.".to_string(),
+        "computer_unlock" => "Unlock a locked screen by typing the unlock secret the user stored in the keyring. Only works while a screen locker's prompt has focus; you never see the secret.".to_string(),
+        "computer_batch" => format!("Run several GUI input actions in one call, without a model turn between them. Each step names an input tool and its arguments, and may run only if a condition holds: `if_window_exists` (class or title), `if_text_visible` (OCR), or `if_pixel` (color at a point in {label}). A step whose condition doesn't hold is skipped, which covers cases like dismissing a cookie banner only if one is showing. `retry` tries a step again until its condition holds and it succeeds. The batch stops at the first failed step and returns what each step did."),
+        "computer_run_queue" => "Submit the queued GUI actions for the user's review. The user may reorder, delete, or edit them; the approved actions then run in order and their results are returned.".to_string(),
+        computer_use::DELEGATE_TOOL => "Hand a GUI subtask to a sub-session that can see the screen and send mouse and keyboard input. Blocks until the sub-session finishes or runs out of budget, then returns its summary.".to_string(),
+        _ => return None,
+    };
+    Some(description)
 }
 
-fn create_computer_scroll_tool() -> ToolSpec {
-    let mut properties = BTreeMap::new();
-    properties.insert(
-        "direction".to_string(),
-        JsonSchema::String {
-            description: Some("Scroll direction: up or down.".to_string()),
-        },
-    );
-    properties.insert(
-        "amount".to_string(),
-        JsonSchema::Number {
-            description: Some("Number of scroll ticks (defaults to 3).".to_string()),
-        },
-    );
-    properties.insert(
-        "x".to_string(),
-        JsonSchema::Number {
-            description: Some("Optional X coordinate in 1280x720 space.".to_string()),
-        },
-    );
-    properties.insert(
-        "y".to_string(),
-        JsonSchema::Number {
-            description: Some("Optional Y coordinate in 1280x720 space.".to_string()),
-        },
-    );
-
-    ToolSpec::Function(ResponsesApiTool {
-        name: "computer_scroll".to_string(),
-        description: "Scroll the mouse wheel (coordinates are 1280x720 if provided).".to_string(),
+/// Builds the spec for a computer-use tool from its argument schema, fitted
+/// to our [`JsonSchema`] subset the same way MCP tool schemas are.
+fn create_computer_use_tool(name: &str, computer_use: &ComputerUseConfig) -> Option<ToolSpec> {
+    let label = computer_use.coordinate_label();
+    let description = match (name, computer_use.screenshot_format) {
+        ("computer_screenshot", ScreenshotFormat::Text) => format!(
+            "Read the GUI screen as text: the focused window, then each line of on-screen text from top to bottom with its screen region and center (coordinates are in {label}). No image is attached, so icons and controls without a visible label don't appear."
+        ),
+        _ => computer_use_tool_description(name, &label)?,
+    };
+    let mut parameters = computer_use::tool_parameters(name, computer_use)?;
+    sanitize_json_schema(&mut parameters);
+    let parameters = serde_json::from_value::<JsonSchema>(parameters).ok()?;
+    Some(ToolSpec::Function(ResponsesApiTool {
+        name: name.to_string(),
+        description,
         strict: false,
-        parameters: JsonSchema::Object {
-            properties,
-            required: Some(vec!["direction".to_string()]),
-            additional_properties: Some(false.into()),
-        },
-    })
+        parameters,
+    }))
 }
 
-fn create_computer_type_tool() -> ToolSpec {
-    let mut properties = BTreeMap::new();
-    properties.insert(
-        "text".to_string(),
-        JsonSchema::String {
-            description: Some("Text to type.".to_string()),
-        },
-    );
-    properties.insert(
-        "delay_ms".to_string(),
-        JsonSchema::Number {
-            description: Some("Optional delay between keystrokes in milliseconds.".to_string()),
-        },
-    );
-
+/// Builds the spec for one action of a user recipe: its description plus the
+/// app it is scoped to, with one required string per declared parameter.
+fn create_recipe_tool(recipe: &Recipe, action: &RecipeAction) -> ToolSpec {
+    let properties = action
+        .parameters
+        .iter()
+        .map(|parameter| (parameter.clone(), JsonSchema::String { description: None }))
+        .collect();
     ToolSpec::Function(ResponsesApiTool {
-        name: "computer_type".to_string(),
-        description: "Type text at the current focus.".to_string(),
+        name: recipe.tool_name(action),
+        description: format!(
+            "{} (Recipe for {}: only runs while a {} window is focused, and performs the user's saved steps.)",
+            action.description, recipe.app, recipe.window_class
+        ),
         strict: false,
         parameters: JsonSchema::Object {
             properties,
-            required: Some(vec!["text".to_string()]),
+            required: Some(action.parameters.clone()),
             additional_properties: Some(false.into()),
         },
     })
 }
 
-fn create_computer_key_tool() -> ToolSpec {
-    let mut properties = BTreeMap::new();
-    properties.insert(
-        "keys".to_string(),
-        JsonSchema::Array {
-            items: Box::new(JsonSchema::String { description: None }),
-            description: Some("Key chord, e.g. [\"ctrl\", \"c\"].".to_string()),
-        },
-    );
-    properties.insert(
-        "confirm".to_string(),
-        JsonSchema::Boolean {
-            description: Some(
-                "Required for destructive combos (Alt+F4, Ctrl+Q, Ctrl+W, etc.).".to_string(),
-            ),
-        },
-    );
-
+fn create_plugin_tool(plugin: &Plugin, tool: &PluginTool) -> ToolSpec {
+    let properties = tool
+        .parameters
+        .iter()
+        .map(|parameter| (parameter.clone(), JsonSchema::String { description: None }))
+        .collect();
     ToolSpec::Function(ResponsesApiTool {
-        name: "computer_key".to_string(),
-        description: "Press a key or key chord.".to_string(),
+        name: plugin.tool_name(tool),
+        description: format!(
+            "{} (Plugin tool from {}: drives the GUI itself and returns a summary.)",
+            tool.description, plugin.name
+        ),
         strict: false,
         parameters: JsonSchema::Object {
             properties,
-            required: Some(vec!["keys".to_string()]),
+            required: Some(tool.parameters.clone()),
             additional_properties: Some(false.into()),
         },
     })
@@ -1188,6 +1151,7 @@ pub(crate) fn build_specs(
     mcp_tools: Option<HashMap<String, mcp_types::Tool>>,
 ) -> ToolRegistryBuilder {
     use crate::tools::handlers::ApplyPatchHandler;
+    use crate::tools::handlers::ComputerDelegateHandler;
     use crate::tools::handlers::ComputerUseHandler;
     use crate::tools::handlers::GrepFilesHandler;
     use crate::tools::handlers::ListDirHandler;
@@ -1312,18 +1276,34 @@ pub(crate) fn build_specs(
     }
 
     if config.include_computer_use_tools {
-        builder.push_spec_with_parallel_support(create_computer_screenshot_tool(), true);
-        builder.push_spec_with_parallel_support(create_computer_click_tool(), true);
-        builder.push_spec_with_parallel_support(create_computer_drag_tool(), true);
-        builder.push_spec_with_parallel_support(create_computer_scroll_tool(), true);
-        builder.push_spec_with_parallel_support(create_computer_type_tool(), true);
-        builder.push_spec_with_parallel_support(create_computer_key_tool(), true);
-        builder.register_handler("computer_screenshot", computer_use_handler.clone());
-        builder.register_handler("computer_click", computer_use_handler.clone());
-        builder.register_handler("computer_drag", computer_use_handler.clone());
-        builder.register_handler("computer_scroll", computer_use_handler.clone());
-        builder.register_handler("computer_type", computer_use_handler.clone());
-        builder.register_handler("computer_key", computer_use_handler);
+        for name in computer_use::enabled_tools(&config.computer_use) {
+            let Some(spec) = create_computer_use_tool(name, &config.computer_use) else {
+                continue;
+            };
+            builder.push_spec_with_parallel_support(spec, true);
+            builder.register_handler(name, computer_use_handler.clone());
+        }
+        for recipe in &config.computer_use.recipes {
+            for action in &recipe.actions {
+                builder.push_spec(create_recipe_tool(recipe, action));
+                builder.register_handler(recipe.tool_name(action), computer_use_handler.clone());
+            }
+        }
+        for plugin in &config.computer_use.plugins {
+            for tool in &plugin.tools {
+                builder.push_spec(create_plugin_tool(plugin, tool));
+                builder.register_handler(plugin.tool_name(tool), computer_use_handler.clone());
+            }
+        }
+    } else if config.computer_use.delegate
+        && let Some(spec) =
+            create_computer_use_tool(computer_use::DELEGATE_TOOL, &config.computer_use)
+    {
+        builder.push_spec(spec);
+        builder.register_handler(
+            computer_use::DELEGATE_TOOL,
+            Arc::new(ComputerDelegateHandler),
+        );
     }
 
     if let Some(mcp_tools) = mcp_tools {
@@ -1567,12 +1547,68 @@ mod tests {
                 "computer_click",
                 "computer_drag",
                 "computer_scroll",
+                "computer_with_modifiers",
                 "computer_type",
                 "computer_key",
+                "computer_clear_field",
+                "computer_find_text",
+                "computer_locate",
+                "computer_compare",
+                "computer_browser_state",
+                "computer_active_window",
+                "computer_workspace_list",
+                "computer_workspace_switch",
+                "computer_workspace_move_window",
+                "computer_tray_list",
+                "computer_tray_activate",
+                "computer_list_shortcuts",
+                "computer_network_state",
+                "computer_print_dialog_handle",
+                "computer_file_dialog",
+                "computer_menu_select",
+                "computer_context_menu",
+                "computer_wait_download",
+                "computer_wait_app_idle",
+                "computer_set_target",
+                "computer_self_test",
+                "computer_landmarks",
+                "computer_batch",
             ],
         );
     }
 
+    #[test]
+    fn test_build_specs_computer_delegate_only_without_gui() {
+        let config = test_config();
+        let model_family = ModelsManager::construct_model_family_offline("gpt-5-codex", &config);
+        let computer_use = ComputerUseConfig {
+            delegate: true,
+            ..Default::default()
+        };
+        let tool_names = |features: &Features| {
+            let tools_config = ToolsConfig::new(&ToolsConfigParams {
+                model_family: &model_family,
+                features,
+            })
+            .with_computer_use(&computer_use);
+            let (tools, _) = build_specs(&tools_config, None).build();
+            tools
+                .iter()
+                .map(|t| t.spec.name().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let headless = tool_names(&Features::with_defaults());
+        assert_eq!(
+            headless.last().map(String::as_str),
+            Some("computer_delegate")
+        );
+
+        let gui = tool_names(Features::with_defaults().enable(Feature::ComputerUseGui));
+        assert!(!gui.iter().any(|name| name == "computer_delegate"));
+        assert!(gui.iter().any(|name| name == "computer_screenshot"));
+    }
+
     #[test]
     fn test_build_specs_gpt5_codex_unified_exec_web_search() {
         assert_model_tools(
@@ -24,6 +24,7 @@ use crate::tools::orchestrator::ToolOrchestrator;
 use crate::tools::runtimes::unified_exec::UnifiedExecRequest as UnifiedExecToolRequest;
 use crate::tools::runtimes::unified_exec::UnifiedExecRuntime;
 use crate::tools::sandboxing::ToolCtx;
+use crate::tools::sandboxing::remote_exec_approval_requirement;
 use crate::truncate::TruncationPolicy;
 use crate::truncate::approx_token_count;
 use crate::truncate::formatted_truncate_text;
@@ -526,6 +527,16 @@ impl UnifiedExecSessionManager {
                 sandbox_permissions,
             )
             .await;
+        let remote = context.turn.tools_config.computer_use.target.clone();
+        let exec_approval_requirement = if remote.is_some() {
+            remote_exec_approval_requirement(
+                exec_approval_requirement,
+                context.turn.approval_policy,
+                &context.turn.sandbox_policy,
+            )
+        } else {
+            exec_approval_requirement
+        };
         let req = UnifiedExecToolRequest::new(
             command.to_vec(),
             cwd,
@@ -533,6 +544,7 @@ impl UnifiedExecSessionManager {
             sandbox_permissions,
             justification,
             exec_approval_requirement,
+            remote,
         );
         let tool_ctx = ToolCtx {
             session: context.session.as_ref(),
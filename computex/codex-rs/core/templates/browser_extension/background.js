@@ -0,0 +1,97 @@
+// Relays computex's DOM requests from the native messaging host to the page
+// in the active tab, and sends the results back.
+
+const HOST = "com.computex.bridge";
+const RECONNECT_MS = 5000;
+
+function connect() {
+  const port = chrome.runtime.connectNative(HOST);
+  port.onMessage.addListener(async (request) => {
+    try {
+      const result = await handle(request);
+      port.postMessage({ id: request.id, ok: true, result });
+    } catch (error) {
+      port.postMessage({ id: request.id, ok: false, error: String(error?.message ?? error) });
+    }
+  });
+  // The host exits with the browser's last window or when a newer one takes
+  // over; keep trying so a restarted computex finds it again.
+  port.onDisconnect.addListener(() => setTimeout(connect, RECONNECT_MS));
+}
+
+async function handle(request) {
+  const [tab] = await chrome.tabs.query({ active: true, lastFocusedWindow: true });
+  if (!tab) {
+    throw new Error("the browser has no active tab");
+  }
+  const [injection] = await chrome.scripting.executeScript({
+    target: { tabId: tab.id },
+    func: inPage,
+    args: [request],
+  });
+  const result = injection?.result;
+  if (!result) {
+    throw new Error(`the page in the active tab (${tab.url}) can't be scripted`);
+  }
+  if (result.error) {
+    throw new Error(result.error);
+  }
+  return result;
+}
+
+// Runs inside the page, so it must not use anything from this file.
+function inPage(request) {
+  const page = { url: location.href, title: document.title };
+  let nodes;
+  try {
+    nodes = [...document.querySelectorAll(request.selector)];
+  } catch (error) {
+    return { error: `invalid selector \`${request.selector}\`: ${error.message}` };
+  }
+  const label = (el) =>
+    (el.innerText || el.value || el.getAttribute("aria-label") || el.getAttribute("title") || el.getAttribute("alt") || "")
+      .replace(/\s+/g, " ")
+      .trim();
+  if (request.text) {
+    const wanted = request.text.toLowerCase();
+    nodes = nodes.filter((el) => label(el).toLowerCase().includes(wanted));
+  }
+  const describe = (el, index) => {
+    const rect = el.getBoundingClientRect();
+    const attributes = {};
+    for (const name of ["id", "name", "type", "href", "role", "aria-label", "placeholder"]) {
+      const value = el.getAttribute(name);
+      if (value) {
+        attributes[name] = value;
+      }
+    }
+    return {
+      index,
+      tag: el.tagName.toLowerCase(),
+      text: label(el).slice(0, 200),
+      attributes,
+      visible: rect.width > 0 && rect.height > 0,
+      rect: {
+        x: Math.round(rect.x),
+        y: Math.round(rect.y),
+        width: Math.round(rect.width),
+        height: Math.round(rect.height),
+      },
+    };
+  };
+  if (request.action === "click") {
+    const index = request.index ?? 0;
+    const el = nodes[index];
+    if (!el) {
+      return { error: `no element ${index} among the ${nodes.length} matching \`${request.selector}\`` };
+    }
+    el.scrollIntoView({ block: "center", inline: "center" });
+    el.focus?.();
+    el.click();
+    return { ...page, total: nodes.length, element: describe(el, index) };
+  }
+  const limit = request.limit ?? 20;
+  return { ...page, total: nodes.length, elements: nodes.slice(0, limit).map(describe) };
+}
+
+connect();
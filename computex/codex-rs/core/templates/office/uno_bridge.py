@@ -0,0 +1,175 @@
+# Runs one computer_office request against the user's LibreOffice over UNO.
+#
+# computex passes the request as JSON in argv[1] and reads one JSON object
+# from stdout: {"ok": true, "result": "..."} or {"ok": false, "error": "..."},
+# with "unreachable": true when no LibreOffice listens on the pipe and
+# "missing_uno": true when this Python has no UNO bindings.
+
+import json
+import sys
+
+PIPE = "computex"
+
+
+def reply(**fields):
+    print(json.dumps(fields))
+    sys.exit(0)
+
+
+try:
+    import uno
+    from com.sun.star.beans import PropertyValue
+    from com.sun.star.connection import NoConnectException
+except ImportError:
+    reply(
+        ok=False,
+        missing_uno=True,
+        error="this Python can't import the LibreOffice UNO bindings (`uno`)",
+    )
+
+
+def prop(name, value):
+    property = PropertyValue()
+    property.Name = name
+    property.Value = value
+    return property
+
+
+# Service each document model supports, its kind, and its PDF export filter.
+KINDS = [
+    ("com.sun.star.sheet.SpreadsheetDocument", "spreadsheet", "calc_pdf_Export"),
+    ("com.sun.star.presentation.PresentationDocument", "presentation", "impress_pdf_Export"),
+    ("com.sun.star.drawing.DrawingDocument", "drawing", "draw_pdf_Export"),
+    ("com.sun.star.text.TextDocument", "text document", "writer_pdf_Export"),
+]
+
+
+def kind(doc):
+    for service, name, export_filter in KINDS:
+        if doc.supportsService(service):
+            return name, export_filter
+    return None, None
+
+
+def title(doc):
+    try:
+        return doc.getCurrentController().getFrame().getTitle()
+    except Exception:
+        return doc.getURL() or "untitled"
+
+
+def describe(doc):
+    name, _ = kind(doc)
+    location = uno.fileUrlToSystemPath(doc.getURL()) if doc.getURL().startswith("file:") else "not saved"
+    return f'{name} "{title(doc)}" ({location})'
+
+
+def current_document(desktop):
+    doc = desktop.getCurrentComponent()
+    if doc is None or not hasattr(doc, "supportsService") or kind(doc)[0] is None:
+        raise RuntimeError("no document has focus in LibreOffice; open one with action `open`")
+    return doc
+
+
+def connect():
+    local = uno.getComponentContext()
+    resolver = local.ServiceManager.createInstanceWithContext(
+        "com.sun.star.bridge.UnoUrlResolver", local
+    )
+    try:
+        context = resolver.resolve(f"uno:pipe,name={PIPE};urp;StarOffice.ComponentContext")
+    except NoConnectException:
+        reply(ok=False, unreachable=True, error="LibreOffice is not accepting UNO connections")
+    desktop = context.ServiceManager.createInstanceWithContext("com.sun.star.frame.Desktop", context)
+    return desktop
+
+
+def list_documents(desktop):
+    current = desktop.getCurrentComponent()
+    lines = []
+    components = desktop.getComponents().createEnumeration()
+    while components.hasMoreElements():
+        doc = components.nextElement()
+        if not hasattr(doc, "supportsService") or kind(doc)[0] is None:
+            continue
+        marker = " (focused)" if doc == current else ""
+        lines.append(f"- {describe(doc)}{marker}")
+    if not lines:
+        return "no documents are open in LibreOffice"
+    return "\n".join([f"open documents in LibreOffice ({len(lines)}):"] + lines)
+
+
+def open_document(desktop, path):
+    doc = desktop.loadComponentFromURL(uno.systemPathToFileUrl(path), "_default", 0, ())
+    if doc is None:
+        raise RuntimeError(f"LibreOffice could not open {path}")
+    doc.getCurrentController().getFrame().getContainerWindow().setFocus()
+    return f"opened {describe(doc)}"
+
+
+def cell_range(doc, reference):
+    """The cell range `reference` names: `B7`, `A1:C3`, `Sheet2.B7`, or a named range."""
+    named = doc.NamedRanges
+    if named.hasByName(reference):
+        return named.getByName(reference).getReferredCells()
+    sheets = doc.getSheets()
+    if "." in reference:
+        sheet_name, address = reference.rsplit(".", 1)
+        sheet_name = sheet_name.lstrip("$").strip("'")
+        if not sheets.hasByName(sheet_name):
+            names = ", ".join(sheets.getElementNames())
+            raise RuntimeError(f"no sheet `{sheet_name}` in {title(doc)} (sheets: {names})")
+        sheet = sheets.getByName(sheet_name)
+    else:
+        sheet, address = doc.getCurrentController().getActiveSheet(), reference
+    try:
+        return sheet.getCellRangeByName(address)
+    except Exception:
+        raise RuntimeError(f"`{reference}` is not a cell, range, or named range") from None
+
+
+def go_to_cell(desktop, cell):
+    doc = current_document(desktop)
+    if kind(doc)[0] != "spreadsheet":
+        raise RuntimeError(f"the focused document is a {kind(doc)[0]}, not a spreadsheet")
+    target = cell_range(doc, cell.strip())
+    doc.getCurrentController().select(target)
+    name = target.AbsoluteName
+    if target.supportsService("com.sun.star.sheet.SheetCell"):
+        formula = target.getFormula()
+        shown = target.getString()
+        if formula and formula != shown:
+            return f'selected {name} in "{title(doc)}": {shown!r} (formula {formula})'
+        return f'selected {name} in "{title(doc)}": {shown!r}'
+    return f'selected {name} in "{title(doc)}"'
+
+
+def export_pdf(desktop, path):
+    doc = current_document(desktop)
+    _, export_filter = kind(doc)
+    doc.storeToURL(uno.systemPathToFileUrl(path), (prop("FilterName", export_filter),))
+    return f"exported {describe(doc)} to {path}"
+
+
+def main():
+    request = json.loads(sys.argv[1])
+    desktop = connect()
+    action = request["action"]
+    try:
+        if action == "status":
+            result = list_documents(desktop)
+        elif action == "open":
+            result = open_document(desktop, request["path"])
+        elif action == "goto_cell":
+            result = go_to_cell(desktop, request["cell"])
+        elif action == "export_pdf":
+            result = export_pdf(desktop, request["path"])
+        else:
+            raise RuntimeError(f"unknown action `{action}`")
+    except Exception as error:
+        message = getattr(error, "Message", "") or str(error)
+        reply(ok=False, error=message)
+    reply(ok=True, result=result)
+
+
+main()
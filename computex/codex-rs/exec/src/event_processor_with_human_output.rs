@@ -236,6 +236,19 @@ impl EventProcessor for EventProcessorWithHumanOutput {
             EventMsg::TaskStarted(_) => {
                 // Ignore.
             }
+            EventMsg::GuiActionQueueReview(ev) => {
+                ts_msg!(
+                    self,
+                    "{} {} action(s)",
+                    "GUI action queue".style(self.magenta),
+                    ev.actions.len()
+                );
+                ts_msg!(
+                    self,
+                    "{}",
+                    "auto-rejecting (review is not supported in exec mode)".style(self.dimmed)
+                );
+            }
             EventMsg::ElicitationRequest(ev) => {
                 ts_msg!(
                     self,
@@ -513,6 +513,15 @@ pub async fn run_main_with_harness_overrides(
                 })
                 .await?;
         }
+        if let EventMsg::GuiActionQueueReview(_) = &event.msg {
+            // Nobody can review the queue in exec mode, so reject it.
+            conversation
+                .submit(Op::ResolveGuiActionQueue {
+                    id: event.id.clone(),
+                    actions: None,
+                })
+                .await?;
+        }
         if matches!(event.msg, EventMsg::Error(_)) {
             error_seen = true;
         }
@@ -1054,6 +1054,7 @@ fn task_complete_produces_turn_completed_with_usage() {
         EventMsg::TokenCount(codex_core::protocol::TokenCountEvent {
             info: Some(info),
             rate_limits: None,
+            gui_usage: None,
         }),
     );
     assert!(ep.collect_thread_events(&token_count_event).is_empty());
@@ -211,6 +211,20 @@ async fn run_codex_tool_session_inner(
                         // TODO: forward elicitation requests to the client?
                         continue;
                     }
+                    EventMsg::GuiActionQueueReview(_) => {
+                        // MCP clients cannot review GUI action queues; reject
+                        // the queue so the turn does not wait forever.
+                        if let Err(err) = codex
+                            .submit(Op::ResolveGuiActionQueue {
+                                id: event.id.clone(),
+                                actions: None,
+                            })
+                            .await
+                        {
+                            tracing::error!("Failed to reject GUI action queue: {err}");
+                        }
+                        continue;
+                    }
                     EventMsg::ApplyPatchApprovalRequest(ApplyPatchApprovalRequestEvent {
                         call_id,
                         turn_id: _,
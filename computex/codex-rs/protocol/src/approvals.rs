@@ -93,3 +93,23 @@ pub struct ApplyPatchApprovalRequestEvent {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub grant_root: Option<PathBuf>,
 }
+
+/// A GUI tool call held in the computer-use action queue.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, JsonSchema, TS)]
+pub struct QueuedGuiAction {
+    /// Name of the computer-use tool, e.g. `computer_click`.
+    pub tool: String,
+    /// JSON-encoded tool arguments, as sent by the model.
+    pub arguments: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema, TS)]
+pub struct GuiActionQueueReviewEvent {
+    /// Identifier for the `computer_run_queue` call that submitted the queue.
+    pub call_id: String,
+    /// Turn ID that this queue belongs to.
+    #[serde(default)]
+    pub turn_id: String,
+    /// Queued actions, in the order they would run.
+    pub actions: Vec<QueuedGuiAction>,
+}
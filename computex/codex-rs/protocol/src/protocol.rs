@@ -42,6 +42,8 @@ pub use crate::approvals::ApplyPatchApprovalRequestEvent;
 pub use crate::approvals::ElicitationAction;
 pub use crate::approvals::ExecApprovalRequestEvent;
 pub use crate::approvals::ExecPolicyAmendment;
+pub use crate::approvals::GuiActionQueueReviewEvent;
+pub use crate::approvals::QueuedGuiAction;
 
 /// Open/close tags for special user-input blocks. Used across crates to avoid
 /// duplicated hardcoded strings.
@@ -167,6 +169,15 @@ pub enum Op {
         decision: ElicitationAction,
     },
 
+    /// Resolve a computer-use action queue review. `actions` is the queue to
+    /// run, possibly reordered or edited; `None` rejects the whole queue.
+    ResolveGuiActionQueue {
+        /// The id of the submission whose queue is being reviewed.
+        id: String,
+        /// The actions to run, in order.
+        actions: Option<Vec<QueuedGuiAction>>,
+    },
+
     /// Append an entry to the persistent cross-session message history.
     ///
     /// Note the entry is not guaranteed to be logged if the user has
@@ -608,6 +619,9 @@ pub enum EventMsg {
 
     ApplyPatchApprovalRequest(ApplyPatchApprovalRequestEvent),
 
+    /// Queued GUI actions awaiting the user's review before they run.
+    GuiActionQueueReview(GuiActionQueueReviewEvent),
+
     /// Notification advising the user that something they are using has been
     /// deprecated and should be phased out.
     DeprecationNotice(DeprecationNoticeEvent),
@@ -925,6 +939,10 @@ impl TokenUsageInfo {
 pub struct TokenCountEvent {
     pub info: Option<TokenUsageInfo>,
     pub rate_limits: Option<RateLimitSnapshot>,
+    /// Session totals for GUI automation, once a computer-use tool has run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub gui_usage: Option<GuiUsage>,
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema, TS)]
@@ -1012,14 +1030,71 @@ impl TokenUsage {
     }
 }
 
+/// Cost of GUI automation over a session, alongside its token usage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default, JsonSchema, TS)]
+pub struct GuiUsage {
+    /// GUI input actions that ran, such as clicks, key presses, and typing.
+    #[ts(type = "number")]
+    pub actions: u64,
+    /// Screenshots attached to the conversation.
+    #[ts(type = "number")]
+    pub screenshots: u64,
+    /// Total size of the images attached to the conversation.
+    #[ts(type = "number")]
+    pub image_bytes: u64,
+    /// Wall-clock time spent running GUI tools.
+    #[ts(type = "number")]
+    pub automation_ms: u64,
+}
+
+impl GuiUsage {
+    pub fn is_zero(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl fmt::Display for GuiUsage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.automation_ms / 1000;
+        let time = if secs >= 60 {
+            format!("{}m {:02}s", secs / 60, secs % 60)
+        } else {
+            format!("{:.1}s", self.automation_ms as f64 / 1000.0)
+        };
+        let images = if self.image_bytes >= 1024 * 1024 {
+            format!("{:.1} MB", self.image_bytes as f64 / (1024.0 * 1024.0))
+        } else {
+            format!("{} KB", self.image_bytes.div_ceil(1024))
+        };
+        write!(
+            f,
+            "GUI automation: actions={} screenshots={} images={images} time={time}",
+            format_with_separators(self.actions as i64),
+            format_with_separators(self.screenshots as i64),
+        )
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct FinalOutput {
     pub token_usage: TokenUsage,
+    #[serde(default)]
+    pub gui_usage: GuiUsage,
 }
 
 impl From<TokenUsage> for FinalOutput {
     fn from(token_usage: TokenUsage) -> Self {
-        Self { token_usage }
+        Self {
+            token_usage,
+            gui_usage: GuiUsage::default(),
+        }
+    }
+}
+
+impl FinalOutput {
+    pub fn with_gui_usage(mut self, gui_usage: GuiUsage) -> Self {
+        self.gui_usage = gui_usage;
+        self
     }
 }
 
@@ -1049,7 +1124,11 @@ impl fmt::Display for FinalOutput {
             } else {
                 String::new()
             }
-        )
+        )?;
+        if !self.gui_usage.is_zero() {
+            write!(f, "\n{}", self.gui_usage)?;
+        }
+        Ok(())
     }
 }
 
@@ -1294,6 +1373,7 @@ pub enum RolloutItem {
     Compacted(CompactedItem),
     TurnContext(TurnContextItem),
     EventMsg(EventMsg),
+    GuiAction(GuiActionItem),
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, JsonSchema, TS)]
@@ -1343,6 +1423,69 @@ pub enum TruncationPolicy {
     Tokens(usize),
 }
 
+/// A computer-use tool call and the automation context after it, so that
+/// resuming the session restores the context along with the conversation.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, TS)]
+pub struct GuiActionItem {
+    pub call_id: String,
+    pub tool: String,
+    pub success: bool,
+    pub duration_ms: u64,
+    /// Screenshot the call saved under `computer_use.screenshot_dir`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub screenshot: Option<PathBuf>,
+    /// `WM_CLASS` and X11 id of the window that last received input.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_class: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_id: Option<String>,
+    /// Window set with `computer_set_target`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sticky_target: Option<String>,
+    /// Screen size of the coordinate calibration in effect, e.g. `1920x1080`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub calibration: Option<String>,
+    /// Apps whose recipes were loaded.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recipes: Vec<String>,
+    /// Where a click landed on the element under it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub click_accuracy: Option<GuiClickAccuracy>,
+}
+
+/// A click's target point against the center of the accessible element
+/// under it, in screen pixels, for `computer_use.click_accuracy`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, TS)]
+pub struct GuiClickAccuracy {
+    /// Coordinate space the model clicked in, e.g. `1280x800 space`.
+    pub coordinate_space: String,
+    pub clicked_x: i64,
+    pub clicked_y: i64,
+    pub center_x: i64,
+    pub center_y: i64,
+    pub element_width: i64,
+    pub element_height: i64,
+    /// The element's AT-SPI role, e.g. `push button`.
+    pub role: String,
+}
+
+impl GuiClickAccuracy {
+    /// Distance from the element's center in screen pixels.
+    pub fn distance(&self) -> f64 {
+        let dx = (self.clicked_x - self.center_x) as f64;
+        let dy = (self.clicked_y - self.center_y) as f64;
+        dx.hypot(dy)
+    }
+
+    /// How far toward the element's edge the click landed: 0 at the
+    /// center, 1 on the edge.
+    pub fn relative_offset(&self) -> f64 {
+        let along = |offset: i64, size: i64| offset.abs() as f64 / (size as f64 / 2.0).max(1.0);
+        along(self.clicked_x - self.center_x, self.element_width)
+            .max(along(self.clicked_y - self.center_y, self.element_height))
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, JsonSchema)]
 pub struct RolloutLine {
     pub timestamp: String,
@@ -33,6 +33,7 @@ use codex_core::models_manager::model_presets::HIDE_GPT_5_1_CODEX_MAX_MIGRATION_
 use codex_core::models_manager::model_presets::HIDE_GPT5_1_MIGRATION_PROMPT_CONFIG;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::FinalOutput;
+use codex_core::protocol::GuiUsage;
 use codex_core::protocol::ListSkillsResponseEvent;
 use codex_core::protocol::Op;
 use codex_core::protocol::SessionSource;
@@ -70,6 +71,8 @@ const EXTERNAL_EDITOR_HINT: &str = "Save and close external editor to continue."
 #[derive(Debug, Clone)]
 pub struct AppExitInfo {
     pub token_usage: TokenUsage,
+    /// GUI automation totals, reported alongside token usage.
+    pub gui_usage: GuiUsage,
     pub conversation_id: Option<ConversationId>,
     pub update_action: Option<UpdateAction>,
 }
@@ -275,6 +278,7 @@ async fn handle_model_migration_prompt_if_needed(
             ModelMigrationOutcome::Exit => {
                 return Some(AppExitInfo {
                     token_usage: TokenUsage::default(),
+                    gui_usage: GuiUsage::default(),
                     conversation_id: None,
                     update_action: None,
                 });
@@ -505,6 +509,7 @@ impl App {
         tui.terminal.clear()?;
         Ok(AppExitInfo {
             token_usage: app.token_usage(),
+            gui_usage: app.chat_widget.gui_usage(),
             conversation_id: app.chat_widget.conversation_id(),
             update_action: app.pending_update_action,
         })
@@ -1096,6 +1101,9 @@ impl App {
             AppEvent::OpenReviewCustomPrompt => {
                 self.chat_widget.show_review_custom_prompt();
             }
+            AppEvent::MarkScreenshot(input) => {
+                self.chat_widget.attach_screenshot_mark(&input);
+            }
             AppEvent::FullScreenApprovalRequest(request) => match request {
                 ApprovalRequest::ApplyPatch { cwd, changes, .. } => {
                     let _ = tui.enter_alt_screen();
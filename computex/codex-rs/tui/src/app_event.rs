@@ -168,6 +168,9 @@ pub(crate) enum AppEvent {
     /// Open the custom prompt option from the review popup.
     OpenReviewCustomPrompt,
 
+    /// Attach a `/mark` annotation (raw user input) of the latest screenshot.
+    MarkScreenshot(String),
+
     /// Open the approval popup.
     FullScreenApprovalRequest(ApprovalRequest),
 
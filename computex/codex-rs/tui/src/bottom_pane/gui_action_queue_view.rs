@@ -0,0 +1,329 @@
+use crossterm::event::KeyCode;
+use crossterm::event::KeyEvent;
+use crossterm::event::KeyModifiers;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Stylize;
+use ratatui::text::Line;
+use ratatui::widgets::Paragraph;
+use ratatui::widgets::Widget;
+use ratatui::widgets::Wrap;
+
+use crate::app_event::AppEvent;
+use crate::app_event_sender::AppEventSender;
+use crate::render::renderable::Renderable;
+use codex_core::protocol::GuiActionQueueReviewEvent;
+use codex_core::protocol::Op;
+use codex_core::protocol::QueuedGuiAction;
+
+use super::CancellationEvent;
+use super::bottom_pane_view::BottomPaneView;
+
+/// Review step for `computer_use.action_queue`: lists the GUI actions the
+/// model queued and lets the user reorder, delete, or edit their arguments
+/// before running them. Nothing runs until the user presses Enter.
+pub(crate) struct GuiActionQueueView {
+    id: String,
+    actions: Vec<QueuedGuiAction>,
+    selected: usize,
+    /// Argument text being edited for the selected action, if any.
+    editing: Option<String>,
+    error: Option<String>,
+    complete: bool,
+    app_event_tx: AppEventSender,
+}
+
+impl GuiActionQueueView {
+    pub(crate) fn new(
+        id: String,
+        event: GuiActionQueueReviewEvent,
+        app_event_tx: AppEventSender,
+    ) -> Self {
+        Self {
+            id,
+            actions: event.actions,
+            selected: 0,
+            editing: None,
+            error: None,
+            complete: false,
+            app_event_tx,
+        }
+    }
+
+    fn resolve(&mut self, actions: Option<Vec<QueuedGuiAction>>) {
+        self.app_event_tx
+            .send(AppEvent::CodexOp(Op::ResolveGuiActionQueue {
+                id: self.id.clone(),
+                actions,
+            }));
+        self.complete = true;
+    }
+
+    fn select(&mut self, delta: isize) {
+        if self.actions.is_empty() {
+            return;
+        }
+        let len = self.actions.len() as isize;
+        self.selected = (self.selected as isize + delta).rem_euclid(len) as usize;
+    }
+
+    /// Swaps the selected action with its neighbor, keeping it selected.
+    fn move_selected(&mut self, delta: isize) {
+        let target = self.selected as isize + delta;
+        if target < 0 || target >= self.actions.len() as isize {
+            return;
+        }
+        self.actions.swap(self.selected, target as usize);
+        self.selected = target as usize;
+    }
+
+    fn delete_selected(&mut self) {
+        if self.selected < self.actions.len() {
+            self.actions.remove(self.selected);
+            self.selected = self.selected.min(self.actions.len().saturating_sub(1));
+        }
+    }
+
+    fn start_editing(&mut self) {
+        if let Some(action) = self.actions.get(self.selected) {
+            self.editing = Some(action.arguments.clone());
+            self.error = None;
+        }
+    }
+
+    /// Saves the edited arguments if they are a JSON object.
+    fn finish_editing(&mut self) {
+        let Some(text) = self.editing.as_deref() else {
+            return;
+        };
+        match serde_json::from_str::<serde_json::Value>(text) {
+            Ok(value) if value.is_object() => {
+                if let Some(action) = self.actions.get_mut(self.selected) {
+                    action.arguments = value.to_string();
+                }
+                self.editing = None;
+                self.error = None;
+            }
+            Ok(_) => self.error = Some("arguments must be a JSON object".to_string()),
+            Err(err) => self.error = Some(format!("invalid JSON: {err}")),
+        }
+    }
+
+    fn handle_edit_key(&mut self, key_event: KeyEvent) {
+        match key_event.code {
+            KeyCode::Enter => self.finish_editing(),
+            KeyCode::Esc => {
+                self.editing = None;
+                self.error = None;
+            }
+            KeyCode::Backspace => {
+                if let Some(text) = self.editing.as_mut() {
+                    text.pop();
+                }
+            }
+            KeyCode::Char(c)
+                if !key_event
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                if let Some(text) = self.editing.as_mut() {
+                    text.push(c);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn paragraph(&self) -> Paragraph<'static> {
+        let mut lines = vec![Line::from(vec![
+            "Queued GUI actions".bold(),
+            " – nothing runs until you press Enter".dim(),
+        ])];
+        if self.actions.is_empty() {
+            lines.push("  (queue is empty; Enter runs nothing)".dim().into());
+        }
+        for (index, action) in self.actions.iter().enumerate() {
+            let text = format!("{}. {} {}", index + 1, action.tool, action.arguments);
+            if index == self.selected {
+                lines.push(Line::from(vec!["› ".cyan(), text.cyan()]));
+            } else {
+                lines.push(Line::from(format!("  {text}")));
+            }
+        }
+        if let Some(text) = &self.editing {
+            lines.push(Line::from(vec![
+                "  edit: ".cyan(),
+                text.clone().into(),
+                "▌".dim(),
+            ]));
+        }
+        if let Some(error) = &self.error {
+            lines.push(Line::from(format!("  {error}").red()));
+        }
+        lines.push(Line::from(""));
+        let hint = if self.editing.is_some() {
+            "enter save · esc cancel edit"
+        } else {
+            "↑/↓ select · shift+↑/↓ move · e edit · d delete · enter run · esc reject"
+        };
+        lines.push(Line::from(hint.dim()));
+        Paragraph::new(lines).wrap(Wrap { trim: false })
+    }
+}
+
+impl BottomPaneView for GuiActionQueueView {
+    fn handle_key_event(&mut self, key_event: KeyEvent) {
+        if self.editing.is_some() {
+            self.handle_edit_key(key_event);
+            return;
+        }
+        let shift = key_event.modifiers.contains(KeyModifiers::SHIFT);
+        match key_event.code {
+            KeyCode::Up if shift => self.move_selected(-1),
+            KeyCode::Down if shift => self.move_selected(1),
+            KeyCode::Char('K') => self.move_selected(-1),
+            KeyCode::Char('J') => self.move_selected(1),
+            KeyCode::Up | KeyCode::Char('k') => self.select(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.select(1),
+            KeyCode::Char('d') | KeyCode::Delete => self.delete_selected(),
+            KeyCode::Char('e') => self.start_editing(),
+            KeyCode::Enter => {
+                let actions = self.actions.clone();
+                self.resolve(Some(actions));
+            }
+            KeyCode::Esc => {
+                self.on_ctrl_c();
+            }
+            _ => {}
+        }
+    }
+
+    fn on_ctrl_c(&mut self) -> CancellationEvent {
+        self.resolve(None);
+        CancellationEvent::Handled
+    }
+
+    fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    fn handle_paste(&mut self, pasted: String) -> bool {
+        match self.editing.as_mut() {
+            Some(text) => {
+                text.push_str(&pasted);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Renderable for GuiActionQueueView {
+    fn desired_height(&self, width: u16) -> u16 {
+        self.paragraph().line_count(width) as u16
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer) {
+        if area.height == 0 || area.width == 0 {
+            return;
+        }
+        self.paragraph().render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tokio::sync::mpsc::unbounded_channel;
+
+    fn action(tool: &str, arguments: &str) -> QueuedGuiAction {
+        QueuedGuiAction {
+            tool: tool.to_string(),
+            arguments: arguments.to_string(),
+        }
+    }
+
+    fn view() -> (
+        GuiActionQueueView,
+        tokio::sync::mpsc::UnboundedReceiver<AppEvent>,
+    ) {
+        let (tx, rx) = unbounded_channel::<AppEvent>();
+        let event = GuiActionQueueReviewEvent {
+            call_id: "call".to_string(),
+            turn_id: "turn".to_string(),
+            actions: vec![
+                action("computer_click", r#"{"x":10,"y":20}"#),
+                action("computer_type", r#"{"text":"hi"}"#),
+                action("computer_key", r#"{"keys":["Return"]}"#),
+            ],
+        };
+        (
+            GuiActionQueueView::new("turn".to_string(), event, AppEventSender::new(tx)),
+            rx,
+        )
+    }
+
+    fn press(view: &mut GuiActionQueueView, code: KeyCode) {
+        view.handle_key_event(KeyEvent::new(code, KeyModifiers::NONE));
+    }
+
+    fn resolved(rx: &mut tokio::sync::mpsc::UnboundedReceiver<AppEvent>) -> Option<Vec<String>> {
+        match rx.try_recv() {
+            Ok(AppEvent::CodexOp(Op::ResolveGuiActionQueue { id, actions })) => {
+                assert_eq!(id, "turn");
+                actions.map(|actions| {
+                    actions
+                        .into_iter()
+                        .map(|action| format!("{} {}", action.tool, action.arguments))
+                        .collect()
+                })
+            }
+            _ => panic!("expected ResolveGuiActionQueue"),
+        }
+    }
+
+    #[test]
+    fn reorders_deletes_and_edits_before_running() {
+        let (mut view, mut rx) = view();
+        press(&mut view, KeyCode::Char('J'));
+        press(&mut view, KeyCode::Char('j'));
+        press(&mut view, KeyCode::Char('d'));
+        press(&mut view, KeyCode::Char('e'));
+        for _ in 0..r#"10,"y":20}"#.len() {
+            press(&mut view, KeyCode::Backspace);
+        }
+        for c in r#"12,"y":24}"#.chars() {
+            press(&mut view, KeyCode::Char(c));
+        }
+        press(&mut view, KeyCode::Enter);
+        assert!(!view.is_complete());
+        press(&mut view, KeyCode::Enter);
+
+        assert!(view.is_complete());
+        assert_eq!(
+            resolved(&mut rx),
+            Some(vec![
+                r#"computer_type {"text":"hi"}"#.to_string(),
+                r#"computer_click {"x":12,"y":24}"#.to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn invalid_edits_are_rejected_and_escape_rejects_the_queue() {
+        let (mut view, mut rx) = view();
+        press(&mut view, KeyCode::Char('e'));
+        press(&mut view, KeyCode::Backspace);
+        press(&mut view, KeyCode::Enter);
+        assert!(view.error.is_some());
+        assert!(view.editing.is_some());
+        press(&mut view, KeyCode::Esc);
+        assert!(view.editing.is_none());
+        assert!(!view.is_complete());
+
+        press(&mut view, KeyCode::Esc);
+        assert!(view.is_complete());
+        assert_eq!(resolved(&mut rx), None);
+    }
+}
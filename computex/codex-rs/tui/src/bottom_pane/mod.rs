@@ -28,6 +28,8 @@ mod command_popup;
 pub mod custom_prompt_view;
 mod experimental_features_view;
 mod file_search_popup;
+mod gui_action_queue_view;
+pub(crate) use gui_action_queue_view::GuiActionQueueView;
 mod footer;
 mod list_selection_view;
 mod prompt_args;
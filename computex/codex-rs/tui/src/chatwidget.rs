@@ -7,6 +7,7 @@ use std::time::Duration;
 
 use codex_app_server_protocol::AuthMode;
 use codex_backend_client::Client as BackendClient;
+use codex_core::capture_operator_screenshot;
 use codex_core::config::Config;
 use codex_core::config::ConstraintResult;
 use codex_core::config::types::Notifications;
@@ -35,6 +36,8 @@ use codex_core::protocol::ExecCommandBeginEvent;
 use codex_core::protocol::ExecCommandEndEvent;
 use codex_core::protocol::ExecCommandSource;
 use codex_core::protocol::ExitedReviewModeEvent;
+use codex_core::protocol::GuiActionQueueReviewEvent;
+use codex_core::protocol::GuiUsage;
 use codex_core::protocol::ListCustomPromptsResponseEvent;
 use codex_core::protocol::ListSkillsResponseEvent;
 use codex_core::protocol::McpListToolsResponseEvent;
@@ -93,6 +96,7 @@ use crate::bottom_pane::BottomPane;
 use crate::bottom_pane::BottomPaneParams;
 use crate::bottom_pane::CancellationEvent;
 use crate::bottom_pane::ExperimentalFeaturesView;
+use crate::bottom_pane::GuiActionQueueView;
 use crate::bottom_pane::InputResult;
 use crate::bottom_pane::SelectionAction;
 use crate::bottom_pane::SelectionItem;
@@ -111,6 +115,8 @@ use crate::history_cell::AgentMessageCell;
 use crate::history_cell::HistoryCell;
 use crate::history_cell::McpToolCallCell;
 use crate::history_cell::PlainHistoryCell;
+use crate::key_hint::KeyBinding;
+use crate::key_hint::parse_key_binding;
 use crate::markdown::append_markdown;
 use crate::render::Insets;
 use crate::render::renderable::ColumnRenderable;
@@ -118,6 +124,7 @@ use crate::render::renderable::FlexRenderable;
 use crate::render::renderable::Renderable;
 use crate::render::renderable::RenderableExt;
 use crate::render::renderable::RenderableItem;
+use crate::screenshot_mark;
 use crate::slash_command::SlashCommand;
 use crate::status::RateLimitSnapshotDisplay;
 use crate::text_formatting::truncate_text;
@@ -322,6 +329,8 @@ pub(crate) struct ChatWidget {
     session_header: SessionHeader,
     initial_user_message: Option<UserMessage>,
     token_info: Option<TokenUsageInfo>,
+    /// GUI automation totals from the latest token count.
+    gui_usage: GuiUsage,
     rate_limit_snapshot: Option<RateLimitSnapshotDisplay>,
     plan_type: Option<PlanType>,
     rate_limit_warnings: RateLimitWarningState,
@@ -369,6 +378,8 @@ pub(crate) struct ChatWidget {
     // Current session rollout path (if known)
     current_rollout_path: Option<PathBuf>,
     external_editor_state: ExternalEditorState,
+    // Most recent screenshot from `computer_screenshot` or the operator key, for `/mark`.
+    last_screenshot: Option<PathBuf>,
 }
 
 struct UserMessage {
@@ -869,6 +880,16 @@ impl ChatWidget {
         );
     }
 
+    fn on_gui_action_queue_review(&mut self, id: String, ev: GuiActionQueueReviewEvent) {
+        self.flush_answer_stream_with_separator();
+        self.bottom_pane.show_view(Box::new(GuiActionQueueView::new(
+            id,
+            ev,
+            self.app_event_tx.clone(),
+        )));
+        self.request_redraw();
+    }
+
     fn on_exec_command_begin(&mut self, ev: ExecCommandBeginEvent) {
         self.flush_answer_stream_with_separator();
         if is_unified_exec_source(ev.source) {
@@ -954,6 +975,7 @@ impl ChatWidget {
 
     fn on_view_image_tool_call(&mut self, event: ViewImageToolCallEvent) {
         self.flush_answer_stream_with_separator();
+        self.last_screenshot = Some(event.path.clone());
         self.add_to_history(history_cell::new_view_image_tool_call(
             event.path,
             &self.config.cwd,
@@ -1454,6 +1476,7 @@ impl ChatWidget {
                 initial_images,
             ),
             token_info: None,
+            gui_usage: GuiUsage::default(),
             rate_limit_snapshot: None,
             plan_type: None,
             rate_limit_warnings: RateLimitWarningState::default(),
@@ -1483,6 +1506,7 @@ impl ChatWidget {
             feedback,
             current_rollout_path: None,
             external_editor_state: ExternalEditorState::Closed,
+            last_screenshot: None,
         };
 
         widget.prefetch_rate_limits();
@@ -1541,6 +1565,7 @@ impl ChatWidget {
                 initial_images,
             ),
             token_info: None,
+            gui_usage: GuiUsage::default(),
             rate_limit_snapshot: None,
             plan_type: None,
             rate_limit_warnings: RateLimitWarningState::default(),
@@ -1570,6 +1595,7 @@ impl ChatWidget {
             feedback,
             current_rollout_path: None,
             external_editor_state: ExternalEditorState::Closed,
+            last_screenshot: None,
         };
 
         widget.prefetch_rate_limits();
@@ -1578,6 +1604,13 @@ impl ChatWidget {
     }
 
     pub(crate) fn handle_key_event(&mut self, key_event: KeyEvent) {
+        if self
+            .operator_screenshot_binding()
+            .is_some_and(|binding| binding.is_press(key_event))
+        {
+            self.attach_operator_screenshot();
+            return;
+        }
         match key_event {
             KeyEvent {
                 code: KeyCode::Char(c),
@@ -1668,6 +1701,83 @@ impl ChatWidget {
         self.request_redraw();
     }
 
+    /// Key binding for `computer_use.operator_screenshot_key`, when GUI tools
+    /// are enabled and the configured spec parses.
+    fn operator_screenshot_binding(&self) -> Option<KeyBinding> {
+        if !self.config.features.enabled(Feature::ComputerUseGui) {
+            return None;
+        }
+        parse_key_binding(&self.config.computer_use.operator_screenshot_key)
+    }
+
+    /// Captures the desktop and attaches it to the composer so the user can add
+    /// a note ("look at this error dialog") before sending.
+    fn attach_operator_screenshot(&mut self) {
+        let attached = capture_operator_screenshot(&self.config.computer_use).and_then(|path| {
+            let (width, height) = image::image_dimensions(&path)?;
+            Ok((path, width, height))
+        });
+        match attached {
+            Ok((path, width, height)) => {
+                self.last_screenshot = Some(path.clone());
+                self.attach_image(path, width, height, "PNG");
+            }
+            Err(err) => {
+                tracing::warn!("failed to capture operator screenshot: {err}");
+                self.add_to_history(history_cell::new_error_event(format!(
+                    "Failed to capture screenshot: {err}",
+                )));
+            }
+        }
+    }
+
+    fn show_screenshot_mark_prompt(&mut self) {
+        let Some(path) = &self.last_screenshot else {
+            self.add_error_message(
+                "No screenshot to mark yet. Wait for the agent to take one, or press the operator screenshot key."
+                    .to_string(),
+            );
+            return;
+        };
+        let context = path
+            .file_name()
+            .map(|name| format!("Latest screenshot: {}", name.to_string_lossy()));
+        let tx = self.app_event_tx.clone();
+        let view = CustomPromptView::new(
+            "Mark the latest screenshot".to_string(),
+            "x,y or x1,y1 x2,y2 in screenshot pixels, then a note (e.g. 640,360 click here)"
+                .to_string(),
+            context,
+            Box::new(move |input: String| {
+                tx.send(AppEvent::MarkScreenshot(input));
+            }),
+        );
+        self.bottom_pane.show_view(Box::new(view));
+    }
+
+    /// Crops the marked area out of the latest screenshot and loads the
+    /// excerpt plus a coordinate hint into the composer for the user to send.
+    pub(crate) fn attach_screenshot_mark(&mut self, input: &str) {
+        let Some(screenshot) = self.last_screenshot.clone() else {
+            return;
+        };
+        let attached = screenshot_mark::parse_mark(input)
+            .map_err(anyhow::Error::msg)
+            .and_then(|request| {
+                let size = image::image_dimensions(&screenshot)?;
+                let excerpt = screenshot_mark::crop_mark(&screenshot, request.mark)?;
+                let hint = screenshot_mark::hint_text(&request, &self.config.computer_use, size);
+                Ok((hint, excerpt))
+            });
+        match attached {
+            Ok((hint, (path, width, height))) => {
+                self.bottom_pane.set_composer_text(hint);
+                self.attach_image(path, width, height, "PNG");
+            }
+            Err(err) => self.add_error_message(format!("Failed to mark screenshot: {err}")),
+        }
+    }
+
     pub(crate) fn composer_text_with_pending(&self) -> String {
         self.bottom_pane.composer_text_with_pending()
     }
@@ -1714,6 +1824,9 @@ impl ChatWidget {
             SlashCommand::New => {
                 self.app_event_tx.send(AppEvent::NewSession);
             }
+            SlashCommand::Mark => {
+                self.show_screenshot_mark_prompt();
+            }
             SlashCommand::Resume => {
                 self.app_event_tx.send(AppEvent::OpenResumePicker);
             }
@@ -2044,6 +2157,9 @@ impl ChatWidget {
                 self.on_task_complete(last_agent_message)
             }
             EventMsg::TokenCount(ev) => {
+                if let Some(gui_usage) = ev.gui_usage {
+                    self.gui_usage = gui_usage;
+                }
                 self.set_token_info(ev.info);
                 self.on_rate_limit_snapshot(ev.rate_limits);
             }
@@ -2073,6 +2189,9 @@ impl ChatWidget {
             EventMsg::ElicitationRequest(ev) => {
                 self.on_elicitation_request(ev);
             }
+            EventMsg::GuiActionQueueReview(ev) => {
+                self.on_gui_action_queue_review(id.unwrap_or_default(), ev);
+            }
             EventMsg::ExecCommandBegin(ev) => self.on_exec_command_begin(ev),
             EventMsg::TerminalInteraction(delta) => self.on_terminal_interaction(delta),
             EventMsg::ExecCommandOutputDelta(delta) => self.on_exec_command_output_delta(delta),
@@ -3517,6 +3636,10 @@ impl ChatWidget {
             .unwrap_or_default()
     }
 
+    pub(crate) fn gui_usage(&self) -> GuiUsage {
+        self.gui_usage
+    }
+
     pub(crate) fn conversation_id(&self) -> Option<ConversationId> {
         self.conversation_id
     }
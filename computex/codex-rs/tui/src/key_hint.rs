@@ -53,6 +53,36 @@ pub(crate) const fn ctrl_alt(key: KeyCode) -> KeyBinding {
     KeyBinding::new(key, KeyModifiers::CONTROL.union(KeyModifiers::ALT))
 }
 
+/// Parses a user-configured binding such as `ctrl+alt+s` or `f9`. Returns
+/// `None` for an empty or unrecognized spec.
+pub(crate) fn parse_key_binding(spec: &str) -> Option<KeyBinding> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut key = None;
+    for part in spec.split('+').map(str::trim) {
+        let part = part.to_ascii_lowercase();
+        match part.as_str() {
+            "ctrl" | "control" => modifiers |= KeyModifiers::CONTROL,
+            "alt" | "option" => modifiers |= KeyModifiers::ALT,
+            "shift" => modifiers |= KeyModifiers::SHIFT,
+            _ if key.is_some() => return None,
+            "enter" => key = Some(KeyCode::Enter),
+            "esc" | "escape" => key = Some(KeyCode::Esc),
+            "tab" => key = Some(KeyCode::Tab),
+            "space" => key = Some(KeyCode::Char(' ')),
+            _ => {
+                let mut chars = part.chars();
+                key = match (chars.next(), chars.next()) {
+                    (Some(c), None) => Some(KeyCode::Char(c)),
+                    (Some('f'), Some(_)) => part[1..].parse().ok().map(KeyCode::F),
+                    _ => None,
+                };
+                key?;
+            }
+        }
+    }
+    key.map(|key| KeyBinding::new(key, modifiers))
+}
+
 fn modifiers_to_string(modifiers: KeyModifiers) -> String {
     let mut result = String::new();
     if modifiers.contains(KeyModifiers::CONTROL) {
@@ -110,3 +140,23 @@ pub(crate) fn is_altgr(mods: KeyModifiers) -> bool {
 pub(crate) fn is_altgr(_mods: KeyModifiers) -> bool {
     false
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_configured_key_bindings() {
+        assert_eq!(
+            parse_key_binding("ctrl+alt+s"),
+            Some(ctrl_alt(KeyCode::Char('s')))
+        );
+        assert_eq!(parse_key_binding(" F9 "), Some(plain(KeyCode::F(9))));
+        assert_eq!(parse_key_binding("Shift+Tab"), Some(shift(KeyCode::Tab)));
+        assert_eq!(parse_key_binding(""), None);
+        assert_eq!(parse_key_binding("ctrl"), None);
+        assert_eq!(parse_key_binding("ctrl+a+b"), None);
+        assert_eq!(parse_key_binding("hyper+s"), None);
+    }
+}
@@ -66,6 +66,7 @@ mod pager_overlay;
 pub mod public_widgets;
 mod render;
 mod resume_picker;
+mod screenshot_mark;
 mod selection_list;
 mod session_log;
 mod shimmer;
@@ -376,6 +377,7 @@ async fn run_ratatui_app(
                     crate::tui::restore()?;
                     return Ok(AppExitInfo {
                         token_usage: codex_core::protocol::TokenUsage::default(),
+                        gui_usage: codex_core::protocol::GuiUsage::default(),
                         conversation_id: None,
                         update_action: Some(action),
                     });
@@ -415,6 +417,7 @@ async fn run_ratatui_app(
             let _ = tui.terminal.clear();
             return Ok(AppExitInfo {
                 token_usage: codex_core::protocol::TokenUsage::default(),
+                gui_usage: codex_core::protocol::GuiUsage::default(),
                 conversation_id: None,
                 update_action: None,
             });
@@ -450,6 +453,7 @@ async fn run_ratatui_app(
                 }
                 return Ok(AppExitInfo {
                     token_usage: codex_core::protocol::TokenUsage::default(),
+                    gui_usage: codex_core::protocol::GuiUsage::default(),
                     conversation_id: None,
                     update_action: None,
                 });
@@ -488,6 +492,7 @@ async fn run_ratatui_app(
                 session_log::log_session_end();
                 return Ok(AppExitInfo {
                     token_usage: codex_core::protocol::TokenUsage::default(),
+                    gui_usage: codex_core::protocol::GuiUsage::default(),
                     conversation_id: None,
                     update_action: None,
                 });
@@ -0,0 +1,272 @@
+//! Operator annotations for `/mark`: the user marks a point or rectangle on
+//! the latest screenshot, and the TUI attaches a cropped, outlined excerpt
+//! together with a text hint carrying the coordinates.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_core::config::types::ComputerUseConfig;
+use codex_core::config::types::CoordinateSpace;
+use image::Rgba;
+use image::RgbaImage;
+use tempfile::Builder;
+
+/// Context kept around a marked point, in screenshot pixels.
+const POINT_CONTEXT: u32 = 80;
+/// Padding kept around a marked rectangle, in screenshot pixels.
+const RECT_PADDING: u32 = 24;
+const MARK_COLOR: Rgba<u8> = Rgba([255, 0, 0, 255]);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Mark {
+    Point {
+        x: u32,
+        y: u32,
+    },
+    Rect {
+        left: u32,
+        top: u32,
+        right: u32,
+        bottom: u32,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct MarkRequest {
+    pub mark: Mark,
+    pub note: String,
+}
+
+/// Parses `/mark` input: `x,y` or `x1,y1 x2,y2`, in screenshot pixels,
+/// followed by an optional note.
+pub(crate) fn parse_mark(input: &str) -> Result<MarkRequest, String> {
+    let mut parts = input.split_whitespace().peekable();
+    let first = parts
+        .next()
+        .and_then(parse_pair)
+        .ok_or_else(|| "start with a point such as 640,360".to_string())?;
+    let mark = match parts.peek().copied().and_then(parse_pair) {
+        Some(second) => {
+            parts.next();
+            Mark::Rect {
+                left: first.0.min(second.0),
+                top: first.1.min(second.1),
+                right: first.0.max(second.0),
+                bottom: first.1.max(second.1),
+            }
+        }
+        None => Mark::Point {
+            x: first.0,
+            y: first.1,
+        },
+    };
+    let note = parts.collect::<Vec<_>>().join(" ");
+    Ok(MarkRequest { mark, note })
+}
+
+fn parse_pair(token: &str) -> Option<(u32, u32)> {
+    let (x, y) = token.split_once(',')?;
+    Some((x.trim().parse().ok()?, y.trim().parse().ok()?))
+}
+
+/// Crops the marked area (with some context) out of `screenshot`, outlines
+/// the mark in red, and writes the excerpt to a temporary PNG. Returns the
+/// path and the excerpt size.
+pub(crate) fn crop_mark(screenshot: &Path, mark: Mark) -> anyhow::Result<(PathBuf, u32, u32)> {
+    let image = image::open(screenshot)?.to_rgba8();
+    let (width, height) = image.dimensions();
+    let (left, top, right, bottom) = match mark {
+        Mark::Point { x, y } => (x, y, x, y),
+        Mark::Rect {
+            left,
+            top,
+            right,
+            bottom,
+        } => (left, top, right, bottom),
+    };
+    if right >= width || bottom >= height {
+        anyhow::bail!("the mark is outside the {width}x{height} screenshot");
+    }
+    let padding = match mark {
+        Mark::Point { .. } => POINT_CONTEXT,
+        Mark::Rect { .. } => RECT_PADDING,
+    };
+    let crop_left = left.saturating_sub(padding);
+    let crop_top = top.saturating_sub(padding);
+    let crop_right = (right + padding).min(width - 1);
+    let crop_bottom = (bottom + padding).min(height - 1);
+    let mut excerpt = image::imageops::crop_imm(
+        &image,
+        crop_left,
+        crop_top,
+        crop_right - crop_left + 1,
+        crop_bottom - crop_top + 1,
+    )
+    .to_image();
+    outline(
+        &mut excerpt,
+        mark,
+        (
+            left - crop_left,
+            top - crop_top,
+            right - crop_left,
+            bottom - crop_top,
+        ),
+    );
+
+    let file = Builder::new()
+        .prefix("codex-mark-")
+        .suffix(".png")
+        .tempfile()?;
+    excerpt.save_with_format(file.path(), image::ImageFormat::Png)?;
+    let (_file, path) = file.keep()?;
+    let (excerpt_width, excerpt_height) = excerpt.dimensions();
+    Ok((path, excerpt_width, excerpt_height))
+}
+
+/// Draws a crosshair for a point or a rectangle outline, in excerpt pixels.
+fn outline(image: &mut RgbaImage, mark: Mark, (left, top, right, bottom): (u32, u32, u32, u32)) {
+    let (width, height) = image.dimensions();
+    let mut put = |x: u32, y: u32| {
+        if x < width && y < height {
+            image.put_pixel(x, y, MARK_COLOR);
+        }
+    };
+    match mark {
+        Mark::Point { .. } => {
+            for offset in 0..=12 {
+                put(left.saturating_sub(offset), top);
+                put(left + offset, top);
+                put(left, top.saturating_sub(offset));
+                put(left, top + offset);
+            }
+        }
+        Mark::Rect { .. } => {
+            for x in left..=right {
+                put(x, top);
+                put(x, bottom);
+            }
+            for y in top..=bottom {
+                put(left, y);
+                put(right, y);
+            }
+        }
+    }
+}
+
+/// Text hint sent with the excerpt. Screenshot pixels are converted into the
+/// model's coordinate space so the hint can be used with the GUI tools as-is.
+pub(crate) fn hint_text(
+    request: &MarkRequest,
+    config: &ComputerUseConfig,
+    screenshot_size: (u32, u32),
+) -> String {
+    let point = |x: u32, y: u32| format_point(config.coordinate_space, x, y, screenshot_size);
+    let (shape, center) = match request.mark {
+        Mark::Point { x, y } => (format!("point {}", point(x, y)), point(x, y)),
+        Mark::Rect {
+            left,
+            top,
+            right,
+            bottom,
+        } => (
+            format!("region {} to {}", point(left, top), point(right, bottom)),
+            point((left + right) / 2, (top + bottom) / 2),
+        ),
+    };
+    let label = config.coordinate_label();
+    let note = if request.note.is_empty() {
+        "look here".to_string()
+    } else {
+        request.note.clone()
+    };
+    format!(
+        "Operator hint on the latest screenshot: {note}. Marked {shape} (center {center}, in {label}); the attached excerpt outlines it in red."
+    )
+}
+
+fn format_point(space: CoordinateSpace, x: u32, y: u32, (width, height): (u32, u32)) -> String {
+    match space {
+        CoordinateSpace::Normalized => {
+            let nx = f64::from(x) / f64::from(width.saturating_sub(1).max(1));
+            let ny = f64::from(y) / f64::from(height.saturating_sub(1).max(1));
+            format!("{nx:.3},{ny:.3}")
+        }
+        CoordinateSpace::Virtual | CoordinateSpace::Native => format!("{x},{y}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_points_rectangles_and_notes() {
+        assert_eq!(
+            parse_mark("640,360 click here"),
+            Ok(MarkRequest {
+                mark: Mark::Point { x: 640, y: 360 },
+                note: "click here".to_string(),
+            })
+        );
+        assert_eq!(
+            parse_mark("300,200 100,50"),
+            Ok(MarkRequest {
+                mark: Mark::Rect {
+                    left: 100,
+                    top: 50,
+                    right: 300,
+                    bottom: 200,
+                },
+                note: String::new(),
+            })
+        );
+        assert!(parse_mark("click here").is_err());
+    }
+
+    #[test]
+    fn hint_uses_model_coordinate_space() {
+        let request = MarkRequest {
+            mark: Mark::Rect {
+                left: 0,
+                top: 0,
+                right: 1279,
+                bottom: 719,
+            },
+            note: "the Save button".to_string(),
+        };
+        assert_eq!(
+            hint_text(&request, &ComputerUseConfig::default(), (1280, 720)),
+            "Operator hint on the latest screenshot: the Save button. Marked region 0,0 to 1279,719 (center 639,359, in 1280x720 space); the attached excerpt outlines it in red."
+        );
+        let normalized = ComputerUseConfig {
+            coordinate_space: CoordinateSpace::Normalized,
+            ..Default::default()
+        };
+        let request = MarkRequest {
+            mark: Mark::Point { x: 1279, y: 0 },
+            note: String::new(),
+        };
+        assert_eq!(
+            hint_text(&request, &normalized, (1280, 720)),
+            "Operator hint on the latest screenshot: look here. Marked point 1.000,0.000 (center 1.000,0.000, in normalized 0-1 space); the attached excerpt outlines it in red."
+        );
+    }
+
+    #[test]
+    fn crops_around_the_mark() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let screenshot = dir.path().join("shot.png");
+        RgbaImage::new(400, 300).save(&screenshot)?;
+
+        let (path, width, height) = crop_mark(&screenshot, Mark::Point { x: 10, y: 290 })?;
+        assert_eq!((width, height), (91, 90));
+        let excerpt = image::open(&path)?.to_rgba8();
+        assert_eq!(*excerpt.get_pixel(10, 80), MARK_COLOR);
+        std::fs::remove_file(path)?;
+
+        assert!(crop_mark(&screenshot, Mark::Point { x: 400, y: 0 }).is_err());
+        Ok(())
+    }
+}
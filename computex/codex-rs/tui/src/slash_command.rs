@@ -24,6 +24,7 @@ pub enum SlashCommand {
     // Undo,
     Diff,
     Mention,
+    Mark,
     Status,
     Mcp,
     Logout,
@@ -49,6 +50,7 @@ impl SlashCommand {
             SlashCommand::Quit | SlashCommand::Exit => "exit Codex",
             SlashCommand::Diff => "show git diff (including untracked files)",
             SlashCommand::Mention => "mention a file",
+            SlashCommand::Mark => "mark a point or region on the latest screenshot for the agent",
             SlashCommand::Skills => "use skills to improve how Codex performs specific tasks",
             SlashCommand::Status => "show current session configuration and token usage",
             SlashCommand::Ps => "list background terminals",
@@ -83,6 +85,7 @@ impl SlashCommand {
             | SlashCommand::Logout => false,
             SlashCommand::Diff
             | SlashCommand::Mention
+            | SlashCommand::Mark
             | SlashCommand::Skills
             | SlashCommand::Status
             | SlashCommand::Ps
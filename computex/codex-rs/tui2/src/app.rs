@@ -44,6 +44,7 @@ use codex_core::models_manager::model_presets::HIDE_GPT_5_1_CODEX_MAX_MIGRATION_
 use codex_core::models_manager::model_presets::HIDE_GPT5_1_MIGRATION_PROMPT_CONFIG;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::FinalOutput;
+use codex_core::protocol::GuiUsage;
 use codex_core::protocol::ListSkillsResponseEvent;
 use codex_core::protocol::Op;
 use codex_core::protocol::SessionSource;
@@ -85,6 +86,8 @@ use crate::history_cell::UpdateAvailableHistoryCell;
 #[derive(Debug, Clone)]
 pub struct AppExitInfo {
     pub token_usage: TokenUsage,
+    /// GUI automation totals, reported alongside token usage.
+    pub gui_usage: GuiUsage,
     pub conversation_id: Option<ConversationId>,
     pub update_action: Option<UpdateAction>,
     /// ANSI-styled transcript lines to print after the TUI exits.
@@ -99,6 +102,7 @@ impl From<AppExitInfo> for codex_tui::AppExitInfo {
     fn from(info: AppExitInfo) -> Self {
         codex_tui::AppExitInfo {
             token_usage: info.token_usage,
+            gui_usage: info.gui_usage,
             conversation_id: info.conversation_id,
             update_action: info.update_action.map(Into::into),
         }
@@ -302,6 +306,7 @@ async fn handle_model_migration_prompt_if_needed(
             ModelMigrationOutcome::Exit => {
                 return Some(AppExitInfo {
                     token_usage: TokenUsage::default(),
+                    gui_usage: GuiUsage::default(),
                     conversation_id: None,
                     update_action: None,
                     session_lines: Vec::new(),
@@ -582,6 +587,7 @@ impl App {
         tui.terminal.clear()?;
         Ok(AppExitInfo {
             token_usage: app.token_usage(),
+            gui_usage: app.chat_widget.gui_usage(),
             conversation_id: app.chat_widget.conversation_id(),
             update_action: app.pending_update_action,
             session_lines,
@@ -33,6 +33,7 @@ use codex_core::protocol::ExecCommandBeginEvent;
 use codex_core::protocol::ExecCommandEndEvent;
 use codex_core::protocol::ExecCommandSource;
 use codex_core::protocol::ExitedReviewModeEvent;
+use codex_core::protocol::GuiUsage;
 use codex_core::protocol::ListCustomPromptsResponseEvent;
 use codex_core::protocol::ListSkillsResponseEvent;
 use codex_core::protocol::McpListToolsResponseEvent;
@@ -290,6 +291,8 @@ pub(crate) struct ChatWidget {
     session_header: SessionHeader,
     initial_user_message: Option<UserMessage>,
     token_info: Option<TokenUsageInfo>,
+    /// GUI automation totals from the latest token count.
+    gui_usage: GuiUsage,
     rate_limit_snapshot: Option<RateLimitSnapshotDisplay>,
     plan_type: Option<PlanType>,
     rate_limit_warnings: RateLimitWarningState,
@@ -834,6 +837,17 @@ impl ChatWidget {
         );
     }
 
+    /// This TUI has no queue review view, so reject the queue rather than
+    /// leave the turn waiting.
+    fn reject_gui_action_queue(&mut self, id: String) {
+        self.submit_op(Op::ResolveGuiActionQueue { id, actions: None });
+        self.add_info_message(
+            "Rejected the queued GUI actions: queue review is not supported in this TUI."
+                .to_string(),
+            None,
+        );
+    }
+
     fn on_exec_command_begin(&mut self, ev: ExecCommandBeginEvent) {
         self.flush_answer_stream_with_separator();
         let ev2 = ev.clone();
@@ -1314,6 +1328,7 @@ impl ChatWidget {
                 initial_images,
             ),
             token_info: None,
+            gui_usage: GuiUsage::default(),
             rate_limit_snapshot: None,
             plan_type: None,
             rate_limit_warnings: RateLimitWarningState::default(),
@@ -1399,6 +1414,7 @@ impl ChatWidget {
                 initial_images,
             ),
             token_info: None,
+            gui_usage: GuiUsage::default(),
             rate_limit_snapshot: None,
             plan_type: None,
             rate_limit_warnings: RateLimitWarningState::default(),
@@ -1847,6 +1863,9 @@ impl ChatWidget {
                 self.on_task_complete(last_agent_message)
             }
             EventMsg::TokenCount(ev) => {
+                if let Some(gui_usage) = ev.gui_usage {
+                    self.gui_usage = gui_usage;
+                }
                 self.set_token_info(ev.info);
                 self.on_rate_limit_snapshot(ev.rate_limits);
             }
@@ -1876,6 +1895,9 @@ impl ChatWidget {
             EventMsg::ElicitationRequest(ev) => {
                 self.on_elicitation_request(ev);
             }
+            EventMsg::GuiActionQueueReview(_) => {
+                self.reject_gui_action_queue(id.unwrap_or_default());
+            }
             EventMsg::ExecCommandBegin(ev) => self.on_exec_command_begin(ev),
             EventMsg::TerminalInteraction(delta) => self.on_terminal_interaction(delta),
             EventMsg::ExecCommandOutputDelta(delta) => self.on_exec_command_output_delta(delta),
@@ -3308,6 +3330,10 @@ impl ChatWidget {
             .unwrap_or_default()
     }
 
+    pub(crate) fn gui_usage(&self) -> GuiUsage {
+        self.gui_usage
+    }
+
     pub(crate) fn conversation_id(&self) -> Option<ConversationId> {
         self.conversation_id
     }
@@ -388,6 +388,7 @@ async fn run_ratatui_app(
                     crate::tui::restore()?;
                     return Ok(AppExitInfo {
                         token_usage: codex_core::protocol::TokenUsage::default(),
+                        gui_usage: codex_core::protocol::GuiUsage::default(),
                         conversation_id: None,
                         update_action: Some(action),
                         session_lines: Vec::new(),
@@ -428,6 +429,7 @@ async fn run_ratatui_app(
             let _ = tui.terminal.clear();
             return Ok(AppExitInfo {
                 token_usage: codex_core::protocol::TokenUsage::default(),
+                gui_usage: codex_core::protocol::GuiUsage::default(),
                 conversation_id: None,
                 update_action: None,
                 session_lines: Vec::new(),
@@ -464,6 +466,7 @@ async fn run_ratatui_app(
                 }
                 return Ok(AppExitInfo {
                     token_usage: codex_core::protocol::TokenUsage::default(),
+                    gui_usage: codex_core::protocol::GuiUsage::default(),
                     conversation_id: None,
                     update_action: None,
                     session_lines: Vec::new(),
@@ -503,6 +506,7 @@ async fn run_ratatui_app(
                 session_log::log_session_end();
                 return Ok(AppExitInfo {
                     token_usage: codex_core::protocol::TokenUsage::default(),
+                    gui_usage: codex_core::protocol::GuiUsage::default(),
                     conversation_id: None,
                     update_action: None,
                     session_lines: Vec::new(),
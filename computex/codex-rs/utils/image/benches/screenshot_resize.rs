@@ -0,0 +1,67 @@
+//! 4K to 1280x720 screenshot downscale, the common `computer_screenshot`
+//! path. The target is under 50ms for the full resize and encode.
+//!
+//! Run with `cargo bench -p codex-utils-image --bench screenshot_resize`.
+
+#![allow(clippy::expect_used)]
+
+use codex_utils_image::screenshot::RawImage;
+use criterion::BatchSize;
+use criterion::Criterion;
+use criterion::criterion_group;
+use criterion::criterion_main;
+use std::hint::black_box;
+
+/// A synthetic desktop: flat panels with text-like stripes, which compresses
+/// and resizes like a real UI rather than like noise.
+fn desktop_4k() -> RawImage {
+    let (width, height) = (3840u32, 2160u32);
+    let mut pixels = Vec::with_capacity(width as usize * height as usize * 3);
+    for y in 0..height {
+        for x in 0..width {
+            let panel = ((x / 480) + (y / 270)) % 3;
+            let text = (y % 24 < 12) && (x % 9 < 5) && (x / 480) % 2 == 0;
+            let value = if text { 20 } else { 180 + 30 * panel as u8 };
+            pixels.extend_from_slice(&[value, value, value.saturating_add(10)]);
+        }
+    }
+    RawImage::new(width, height, 3, pixels).expect("valid image")
+}
+
+fn bench_screenshot_resize(c: &mut Criterion) {
+    let source = desktop_4k();
+    let mut group = c.benchmark_group("screenshot_4k_to_720p");
+    group.bench_function("resize", |b| {
+        b.iter(|| black_box(source.resize(1280, 720).expect("resize")))
+    });
+    let resized = source.resize(1280, 720).expect("resize");
+    group.bench_function("encode_png", |b| {
+        b.iter(|| black_box(resized.encode_png().expect("encode")))
+    });
+    group.bench_function("parse_resize_encode", |b| {
+        let mut pam = format!(
+            "P7\nWIDTH {}\nHEIGHT {}\nDEPTH 3\nMAXVAL 255\nTUPLTYPE RGB\nENDHDR\n",
+            source.width, source.height
+        )
+        .into_bytes();
+        pam.extend_from_slice(&source.pixels);
+        b.iter_batched(
+            || pam.clone(),
+            |pam| {
+                let image = RawImage::from_pam(pam).expect("parse");
+                black_box(
+                    image
+                        .resize(1280, 720)
+                        .expect("resize")
+                        .encode_png()
+                        .expect("encode"),
+                )
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_screenshot_resize);
+criterion_main!(benches);
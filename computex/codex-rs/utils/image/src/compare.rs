@@ -0,0 +1,115 @@
+//! Pixel comparison of a screen capture against a stored baseline, for UI
+//! regression checks.
+
+use crate::error::ImageProcessingError;
+use crate::screenshot::RawImage;
+
+/// How `current` differs from `baseline`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageDiff {
+    /// Fraction of pixels that match within the tolerance, from 0.0 to 1.0.
+    pub similarity: f64,
+    pub changed_pixels: u64,
+    /// Smallest rectangle `(left, top, width, height)` holding every changed
+    /// pixel, or `None` when nothing changed.
+    pub changed_bounds: Option<(u32, u32, u32, u32)>,
+    /// `current` faded to gray with changed pixels painted red.
+    pub diff: RawImage,
+}
+
+/// Compares two images of the same size. A pixel counts as changed when any
+/// RGB channel differs by more than `tolerance`, which absorbs antialiasing
+/// and compression noise. Alpha is ignored.
+pub fn compare(
+    baseline: &RawImage,
+    current: &RawImage,
+    tolerance: u8,
+) -> Result<ImageDiff, ImageProcessingError> {
+    if (baseline.width, baseline.height) != (current.width, current.height) {
+        return Err(ImageProcessingError::InvalidRaw {
+            message: format!(
+                "baseline is {}x{} but the capture is {}x{}",
+                baseline.width, baseline.height, current.width, current.height
+            ),
+        });
+    }
+    let width = current.width as usize;
+    let baseline_step = usize::from(baseline.channels);
+    let current_step = usize::from(current.channels);
+    let mut diff = Vec::with_capacity(width * current.height as usize * 3);
+    let mut changed_pixels = 0u64;
+    let mut bounds: Option<(u32, u32, u32, u32)> = None;
+    let pixels = baseline
+        .pixels
+        .chunks_exact(baseline_step)
+        .zip(current.pixels.chunks_exact(current_step));
+    for (index, (before, after)) in pixels.enumerate() {
+        let changed = before[..3]
+            .iter()
+            .zip(&after[..3])
+            .any(|(a, b)| a.abs_diff(*b) > tolerance);
+        if changed {
+            changed_pixels += 1;
+            let x = (index % width) as u32;
+            let y = (index / width) as u32;
+            bounds = Some(match bounds {
+                None => (x, y, x, y),
+                Some((left, top, right, bottom)) => {
+                    (left.min(x), top.min(y), right.max(x), bottom.max(y))
+                }
+            });
+            diff.extend([255, 0, 0]);
+        } else {
+            let luma =
+                (u32::from(after[0]) * 299 + u32::from(after[1]) * 587 + u32::from(after[2]) * 114)
+                    / 1000;
+            // Fade unchanged content so the red stands out.
+            let faded = (128 + luma / 2) as u8;
+            diff.extend([faded, faded, faded]);
+        }
+    }
+    let total = width as u64 * u64::from(current.height);
+    Ok(ImageDiff {
+        similarity: 1.0 - changed_pixels as f64 / total as f64,
+        changed_pixels,
+        changed_bounds: bounds
+            .map(|(left, top, right, bottom)| (left, top, right - left + 1, bottom - top + 1)),
+        diff: RawImage::new(current.width, current.height, 3, diff)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(width: u32, height: u32, channels: u8, value: u8) -> RawImage {
+        let len = width as usize * height as usize * usize::from(channels);
+        RawImage::new(width, height, channels, vec![value; len]).expect("valid image")
+    }
+
+    #[test]
+    fn identical_images_within_tolerance_match() {
+        let baseline = solid(4, 3, 3, 100);
+        let current = solid(4, 3, 4, 104);
+        let diff = compare(&baseline, &current, 8).expect("same size");
+        assert_eq!(diff.similarity, 1.0);
+        assert_eq!(diff.changed_pixels, 0);
+        assert_eq!(diff.changed_bounds, None);
+    }
+
+    #[test]
+    fn changed_pixels_are_counted_bounded_and_painted() {
+        let baseline = solid(4, 3, 3, 100);
+        let mut current = baseline.clone();
+        for (x, y) in [(1, 0), (2, 2)] {
+            let offset = (y * 4 + x) * 3;
+            current.pixels[offset] = 250;
+        }
+        let diff = compare(&baseline, &current, 8).expect("same size");
+        assert_eq!(diff.changed_pixels, 2);
+        assert_eq!(diff.similarity, 1.0 - 2.0 / 12.0);
+        assert_eq!(diff.changed_bounds, Some((1, 0, 2, 3)));
+        assert_eq!(&diff.diff.pixels[3..6], &[255, 0, 0]);
+        assert!(compare(&baseline, &solid(3, 3, 3, 100), 8).is_err());
+    }
+}
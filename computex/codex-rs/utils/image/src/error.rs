@@ -23,6 +23,13 @@ pub enum ImageProcessingError {
         #[source]
         source: image::ImageError,
     },
+    #[error("invalid raw image: {message}")]
+    InvalidRaw { message: String },
+    #[error("failed to resize image: {source}")]
+    Resize {
+        #[source]
+        source: fast_image_resize::ResizeError,
+    },
 }
 
 impl ImageProcessingError {
@@ -20,7 +20,9 @@ pub const MAX_WIDTH: u32 = 2048;
 /// Maximum height used when resizing images before uploading.
 pub const MAX_HEIGHT: u32 = 768;
 
+pub mod compare;
 pub mod error;
+pub mod screenshot;
 
 #[derive(Debug, Clone)]
 pub struct EncodedImage {